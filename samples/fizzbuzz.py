@@ -0,0 +1,17 @@
+"""FizzBuzz with a configurable rule table."""
+
+RULES = [(3, "Fizz"), (5, "Buzz"), (7, "Bazz")]
+
+
+def label(number, rules=RULES):
+    words = [word for divisor, word in rules if number % divisor == 0]
+    return "".join(words) or str(number)
+
+
+def main():
+    for number in range(1, 31):
+        print(label(number))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,90 @@
+//! A tiny inventory tracker
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub quantity: u32,
+    pub price_cents: u64,
+}
+
+impl Item {
+    pub fn new(name: &str, quantity: u32, price_cents: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            quantity,
+            price_cents,
+        }
+    }
+
+    pub fn total_cents(&self) -> u64 {
+        self.quantity as u64 * self.price_cents
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} x{:>3} @ ${}.{:02}",
+            self.name,
+            self.quantity,
+            self.price_cents / 100,
+            self.price_cents % 100
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Inventory {
+    items: HashMap<String, Item>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: Item) {
+        self.items
+            .entry(item.name.clone())
+            .and_modify(|existing| existing.quantity += item.quantity)
+            .or_insert(item);
+    }
+
+    pub fn remove(&mut self, name: &str, quantity: u32) -> Result<(), String> {
+        let item = self
+            .items
+            .get_mut(name)
+            .ok_or_else(|| format!("no item named {}", name))?;
+        if item.quantity < quantity {
+            return Err(format!("only {} {} left", item.quantity, name));
+        }
+        item.quantity -= quantity;
+        Ok(())
+    }
+
+    pub fn total_value(&self) -> u64 {
+        self.items.values().map(Item::total_cents).sum()
+    }
+
+    pub fn sorted(&self) -> Vec<&Item> {
+        let mut items: Vec<&Item> = self.items.values().collect();
+        items.sort_by(|a, b| b.total_cents().cmp(&a.total_cents()));
+        items
+    }
+}
+
+fn main() {
+    let mut inventory = Inventory::default();
+    inventory.add(Item::new("keyboard", 3, 4999));
+    inventory.add(Item::new("mouse", 10, 1999));
+    inventory.add(Item::new("keyboard", 2, 4999));
+
+    if let Err(e) = inventory.remove("monitor", 1) {
+        eprintln!("error: {}", e);
+    }
+
+    for item in inventory.sorted() {
+        println!("{}", item);
+    }
+    println!("total: {} cents", inventory.total_value());
+}
@@ -0,0 +1,9 @@
+// Count the words in a sentence
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+fn main() {
+    let sentence = "the quick brown fox jumps over the lazy dog";
+    println!("{} words", word_count(sentence));
+}
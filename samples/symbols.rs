@@ -0,0 +1,14 @@
+// Symbol drill: generics, closures, references and operators
+fn apply<F: Fn(&[i32]) -> Option<i32>>(xs: &[i32], f: F) -> i32 {
+    f(xs).map_or(-1, |v| v * 2 + 1)
+}
+
+fn main() {
+    let xs: Vec<i32> = (0..=9).filter(|&x| x % 3 != 0).collect::<Vec<_>>();
+    let total = xs.iter().fold(0, |acc, &x| acc ^ (x << 2) | (x >> 1));
+    let first = apply(&xs[1..], |s| s.first().copied());
+    let pair: (&str, [u8; 3]) = ("#!@", [0x1f, 0b1010, 0o17]);
+    let ok = total >= 0 && first <= 100 || !xs.is_empty();
+    println!("{total:?} {first:#x} {:?} {ok}", pair);
+    println!("{}", format!("{{}} -> {:>8.2}% ~ `{}`", 12.5_f64, '\\'));
+}
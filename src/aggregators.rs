@@ -0,0 +1,294 @@
+//! Statistics computed over a set of sessions
+//!
+//! Each number on the statistics screen is an `Aggregator`. The summary fields are
+//! built from the ones below, and any built-in aggregator can be listed under
+//! `[statistics] metrics` to be shown as well. A new metric is one more
+//! implementation added to `BUILTIN`; `SessionHistory` only hands it the sessions.
+
+use crate::locale::Locale;
+use crate::session_state::SessionStats;
+
+/// A statistic over a set of sessions
+pub trait Aggregator {
+    /// Name used in `[statistics] metrics`
+    fn id(&self) -> &'static str;
+
+    /// Label on the statistics screen
+    fn label(&self) -> &'static str;
+
+    /// The value over `sessions` (most recent first), or None when there are none
+    fn compute(&self, sessions: &[&SessionStats]) -> Option<f64>;
+
+    fn format(&self, value: f64, locale: &Locale) -> String;
+}
+
+/// A per-session number aggregators work on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Cpm,
+    Wpm,
+    Accuracy,
+    Chars,
+    Errors,
+    Seconds,
+}
+
+impl Metric {
+    pub fn of(self, session: &SessionStats) -> f64 {
+        match self {
+            Metric::Cpm => session.chars_per_minute,
+            Metric::Wpm => session.words_per_minute,
+            Metric::Accuracy => session.accuracy,
+            Metric::Chars => session.chars_typed as f64,
+            Metric::Errors => session.errors as f64,
+            Metric::Seconds => session.time_elapsed_secs,
+        }
+    }
+
+    fn format(self, value: f64, locale: &Locale) -> String {
+        match self {
+            Metric::Cpm => format!("{} CPM", locale.decimal(value, 0)),
+            Metric::Wpm => format!("{} WPM", locale.decimal(value, 0)),
+            Metric::Accuracy => locale.percent(value, 1),
+            Metric::Chars | Metric::Errors => locale.decimal(value, 0),
+            Metric::Seconds => format!("{} minutes", locale.decimal(value / 60.0, 1)),
+        }
+    }
+}
+
+/// How a metric's per-session values are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reduce {
+    Sum,
+    Mean,
+    Max,
+    /// The middle session, which a few unusual sessions don't pull around like the mean
+    Median,
+    /// Spread of the middle half of the sessions (third minus first quartile)
+    InterquartileRange,
+}
+
+/// An aggregator that reduces one metric's values
+#[derive(Debug, Clone, Copy)]
+pub struct MetricAggregator {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub metric: Metric,
+    pub reduce: Reduce,
+}
+
+impl Aggregator for MetricAggregator {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn label(&self) -> &'static str {
+        self.label
+    }
+
+    fn compute(&self, sessions: &[&SessionStats]) -> Option<f64> {
+        if sessions.is_empty() {
+            return None;
+        }
+        let values: Vec<f64> = sessions.iter().map(|s| self.metric.of(s)).collect();
+        Some(match self.reduce {
+            Reduce::Sum => values.iter().sum(),
+            Reduce::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Reduce::Max => values.into_iter().fold(0.0_f64, f64::max),
+            Reduce::Median => quantile(values, 0.5),
+            Reduce::InterquartileRange => {
+                let q1 = quantile(values.clone(), 0.25);
+                quantile(values, 0.75) - q1
+            }
+        })
+    }
+
+    fn format(&self, value: f64, locale: &Locale) -> String {
+        self.metric.format(value, locale)
+    }
+}
+
+/// Most sessions in a row without a single error
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorFreeStreak;
+
+impl Aggregator for ErrorFreeStreak {
+    fn id(&self) -> &'static str {
+        "error_free_streak"
+    }
+
+    fn label(&self) -> &'static str {
+        "Longest Error-Free Streak"
+    }
+
+    fn compute(&self, sessions: &[&SessionStats]) -> Option<f64> {
+        if sessions.is_empty() {
+            return None;
+        }
+        let (longest, _) = sessions.iter().fold((0, 0), |(longest, run), session| {
+            let run = if session.errors == 0 { run + 1 } else { 0 };
+            (longest.max(run), run)
+        });
+        Some(longest as f64)
+    }
+
+    fn format(&self, value: f64, locale: &Locale) -> String {
+        format!("{} sessions", locale.decimal(value, 0))
+    }
+}
+
+/// Linear interpolation between the closest ranks
+fn quantile(mut values: Vec<f64>, q: f64) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let rank = q * (values.len() - 1) as f64;
+    let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+    values[below] + (values[above] - values[below]) * (rank - below as f64)
+}
+
+pub const TOTAL_CHARS: MetricAggregator = MetricAggregator {
+    id: "total_chars",
+    label: "Total Characters",
+    metric: Metric::Chars,
+    reduce: Reduce::Sum,
+};
+pub const TOTAL_TIME: MetricAggregator = MetricAggregator {
+    id: "total_time",
+    label: "Total Time",
+    metric: Metric::Seconds,
+    reduce: Reduce::Sum,
+};
+pub const TOTAL_ERRORS: MetricAggregator = MetricAggregator {
+    id: "total_errors",
+    label: "Total Errors",
+    metric: Metric::Errors,
+    reduce: Reduce::Sum,
+};
+pub const AVG_CPM: MetricAggregator = MetricAggregator {
+    id: "avg_cpm",
+    label: "Avg CPM",
+    metric: Metric::Cpm,
+    reduce: Reduce::Mean,
+};
+pub const AVG_WPM: MetricAggregator = MetricAggregator {
+    id: "avg_wpm",
+    label: "Avg WPM",
+    metric: Metric::Wpm,
+    reduce: Reduce::Mean,
+};
+pub const AVG_ACCURACY: MetricAggregator = MetricAggregator {
+    id: "avg_accuracy",
+    label: "Avg Accuracy",
+    metric: Metric::Accuracy,
+    reduce: Reduce::Mean,
+};
+pub const BEST_CPM: MetricAggregator = MetricAggregator {
+    id: "best_cpm",
+    label: "Best CPM",
+    metric: Metric::Cpm,
+    reduce: Reduce::Max,
+};
+pub const BEST_WPM: MetricAggregator = MetricAggregator {
+    id: "best_wpm",
+    label: "Best WPM",
+    metric: Metric::Wpm,
+    reduce: Reduce::Max,
+};
+pub const BEST_ACCURACY: MetricAggregator = MetricAggregator {
+    id: "best_accuracy",
+    label: "Best Accuracy",
+    metric: Metric::Accuracy,
+    reduce: Reduce::Max,
+};
+pub const MEDIAN_WPM: MetricAggregator = MetricAggregator {
+    id: "median_wpm",
+    label: "Median WPM",
+    metric: Metric::Wpm,
+    reduce: Reduce::Median,
+};
+pub const MEDIAN_ACCURACY: MetricAggregator = MetricAggregator {
+    id: "median_accuracy",
+    label: "Median Accuracy",
+    metric: Metric::Accuracy,
+    reduce: Reduce::Median,
+};
+pub const WPM_IQR: MetricAggregator = MetricAggregator {
+    id: "wpm_iqr",
+    label: "WPM Interquartile Range",
+    metric: Metric::Wpm,
+    reduce: Reduce::InterquartileRange,
+};
+
+/// Every aggregator `[statistics] metrics` can name
+pub static BUILTIN: &[&(dyn Aggregator + Sync)] = &[
+    &TOTAL_CHARS,
+    &TOTAL_TIME,
+    &TOTAL_ERRORS,
+    &AVG_CPM,
+    &AVG_WPM,
+    &AVG_ACCURACY,
+    &BEST_CPM,
+    &BEST_WPM,
+    &BEST_ACCURACY,
+    &MEDIAN_WPM,
+    &MEDIAN_ACCURACY,
+    &WPM_IQR,
+    &ErrorFreeStreak,
+];
+
+/// The built-in aggregator called `id`
+pub fn find(id: &str) -> Option<&'static (dyn Aggregator + Sync)> {
+    BUILTIN
+        .iter()
+        .copied()
+        .find(|aggregator| aggregator.id() == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(wpm: f64, errors: usize) -> SessionStats {
+        let mut stats = SessionStats::new(300, 60.0, 0, 300, errors, "main.rs".to_string());
+        stats.words_per_minute = wpm;
+        stats
+    }
+
+    #[test]
+    fn test_median_and_iqr() {
+        let sessions: Vec<SessionStats> = [40.0, 10.0, 30.0, 20.0, 100.0]
+            .iter()
+            .map(|&wpm| session(wpm, 0))
+            .collect();
+        let refs: Vec<&SessionStats> = sessions.iter().collect();
+
+        assert_eq!(MEDIAN_WPM.compute(&refs), Some(30.0));
+        assert_eq!(WPM_IQR.compute(&refs), Some(20.0));
+        assert_eq!(AVG_WPM.compute(&refs), Some(40.0));
+        assert_eq!(BEST_WPM.compute(&refs), Some(100.0));
+        assert_eq!(MEDIAN_WPM.compute(&[]), None);
+    }
+
+    #[test]
+    fn test_error_free_streak() {
+        let sessions: Vec<SessionStats> = [0, 0, 2, 0, 0, 0, 1]
+            .iter()
+            .map(|&errors| session(50.0, errors))
+            .collect();
+        let refs: Vec<&SessionStats> = sessions.iter().collect();
+
+        assert_eq!(ErrorFreeStreak.compute(&refs), Some(3.0));
+        assert_eq!(
+            ErrorFreeStreak.format(3.0, &Locale::default()),
+            "3 sessions"
+        );
+    }
+
+    #[test]
+    fn test_ids_are_unique_and_found() {
+        for aggregator in BUILTIN {
+            let found = find(aggregator.id()).unwrap();
+            assert_eq!(found.label(), aggregator.label());
+        }
+        assert!(find("no_such_metric").is_none());
+    }
+}
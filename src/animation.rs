@@ -0,0 +1,208 @@
+//! Animations of the code view
+//!
+//! The app keeps `scroll_offset` where scrolling wants the view; the view itself
+//! shows `Animations::scroll_offset`, which moves there a line at a time over
+//! `scroll_ms` (see `[animation]`). Lines that come into view fade in over
+//! `fade_ms`. Both are stepped once per frame from `poll`, and the text is laid out
+//! again while they run; the caret slide (`[text.caret] slide_ms`) is worked out by
+//! the renderer instead and only shares the easing.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{AnimationConfig, Easing};
+
+/// A value moving from one number to another
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tween {
+    from: f32,
+    to: f32,
+    started: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Tween {
+    fn progress(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(self.started);
+        (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    fn value(&self, now: Instant) -> f32 {
+        self.from + (self.to - self.from) * self.easing.apply(self.progress(now))
+    }
+
+    fn is_done(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Animations {
+    config: AnimationConfig,
+    /// Scroll offset the view shows
+    shown: usize,
+    scroll: Option<Tween>,
+    /// Lines of the text that came into view, and when
+    revealed: Vec<(usize, Instant)>,
+}
+
+impl Animations {
+    pub fn new(config: AnimationConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Scroll offset to lay the view out at
+    pub fn scroll_offset(&self) -> usize {
+        self.shown
+    }
+
+    /// Moves the view toward `target` with `view_lines` lines on screen, and fades;
+    /// returns true when the view has to be laid out again. With `instant` set (the
+    /// text budget is short) the view jumps and nothing fades.
+    pub fn step(&mut self, target: usize, view_lines: usize, instant: bool, now: Instant) -> bool {
+        let fading = !self.revealed.is_empty();
+        let fade = self.fade_duration();
+        self.revealed
+            .retain(|(_, at)| now.saturating_duration_since(*at) < fade);
+        if instant {
+            self.scroll = None;
+            self.revealed.clear();
+            return self.move_to(target, view_lines, now) || fading;
+        }
+
+        if self.scroll.is_none_or(|tween| tween.to != target as f32) && self.shown != target {
+            let from = match self.scroll {
+                Some(tween) => tween.value(now),
+                None => self.shown as f32,
+            };
+            // A jump of more than a screen would only flash by
+            let far = self.shown.abs_diff(target) > view_lines.max(1);
+            self.scroll = (!far).then_some(Tween {
+                from,
+                to: target as f32,
+                started: now,
+                duration: Duration::from_millis(self.config.scroll_ms),
+                easing: self.config.easing,
+            });
+        }
+        let wanted = match self.scroll {
+            Some(tween) if tween.is_done(now) => target,
+            Some(tween) => tween.value(now).round().max(0.0) as usize,
+            None => target,
+        };
+        if self.scroll.is_some_and(|tween| tween.is_done(now)) {
+            self.scroll = None;
+        }
+        self.move_to(wanted, view_lines, now) || fading
+    }
+
+    /// Shows the view from `offset` on, fading in the lines that weren't shown before
+    fn move_to(&mut self, offset: usize, view_lines: usize, now: Instant) -> bool {
+        if offset == self.shown {
+            return false;
+        }
+        if !self.fade_duration().is_zero() {
+            let before = self.shown..self.shown + view_lines;
+            for line in offset..offset + view_lines {
+                if !before.contains(&line) && !self.revealed.iter().any(|(l, _)| *l == line) {
+                    self.revealed.push((line, now));
+                }
+            }
+        }
+        self.shown = offset;
+        true
+    }
+
+    fn fade_duration(&self) -> Duration {
+        Duration::from_millis(self.config.fade_ms)
+    }
+
+    /// Opacity of line `line` of the text at `now`, 1.0 unless it is fading in
+    pub fn opacity(&self, line: usize, now: Instant) -> f32 {
+        let Some((_, at)) = self.revealed.iter().find(|(l, _)| *l == line) else {
+            return 1.0;
+        };
+        let tween = Tween {
+            from: 0.0,
+            to: 1.0,
+            started: *at,
+            duration: self.fade_duration(),
+            easing: self.config.easing,
+        };
+        tween.value(now)
+    }
+
+    /// Lines fading in at `now` and their opacity
+    pub fn fading(&self, now: Instant) -> Vec<(usize, f32)> {
+        self.revealed
+            .iter()
+            .map(|(line, _)| (*line, self.opacity(*line, now)))
+            .collect()
+    }
+
+    /// True while something still moves, so frames have to be laid out
+    pub fn is_running(&self) -> bool {
+        self.scroll.is_some() || !self.revealed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animations(scroll_ms: u64, fade_ms: u64) -> Animations {
+        Animations::new(AnimationConfig {
+            scroll_ms,
+            fade_ms,
+            easing: Easing::Linear,
+        })
+    }
+
+    #[test]
+    fn test_scroll_moves_a_line_at_a_time() {
+        let start = Instant::now();
+        let mut view = animations(100, 0);
+        assert!(!view.step(0, 20, false, start));
+
+        assert!(!view.step(10, 20, false, start));
+        assert_eq!(view.scroll_offset(), 0);
+        assert!(view.step(10, 20, false, start + Duration::from_millis(50)));
+        assert_eq!(view.scroll_offset(), 5);
+        // Scrolling back mid-way starts from where the view is
+        view.step(0, 20, false, start + Duration::from_millis(50));
+        view.step(0, 20, false, start + Duration::from_millis(100));
+        assert_eq!(view.scroll_offset(), 3);
+        view.step(0, 20, false, start + Duration::from_millis(150));
+        assert_eq!(view.scroll_offset(), 0);
+        assert!(!view.is_running());
+
+        // Far jumps and a short text budget don't animate
+        assert!(view.step(500, 20, false, start));
+        assert_eq!(view.scroll_offset(), 500);
+        view.step(490, 20, true, start);
+        assert_eq!(view.scroll_offset(), 490);
+    }
+
+    #[test]
+    fn test_revealed_lines_fade_in() {
+        let start = Instant::now();
+        let mut view = animations(0, 100);
+        view.step(2, 10, false, start);
+        assert_eq!(view.scroll_offset(), 2);
+        // Lines 10 and 11 came into view at the bottom
+        assert_eq!(view.opacity(9, start), 1.0);
+        assert_eq!(view.opacity(10, start), 0.0);
+        assert_eq!(view.opacity(11, start + Duration::from_millis(25)), 0.25);
+        assert!(view.step(2, 10, false, start + Duration::from_millis(60)));
+        // The last step after the fade lays out the lines fully shown
+        assert!(view.step(2, 10, false, start + Duration::from_millis(100)));
+        assert!(!view.is_running());
+        assert!(!view.step(2, 10, false, start + Duration::from_millis(120)));
+    }
+}
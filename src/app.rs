@@ -1,57 +1,282 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use log::info;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use std::collections::VecDeque;
-use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+
+use std::time::{Duration, Instant};
 use winit::event_loop::EventLoop;
 
+use crate::animation;
+use crate::audio;
+use crate::challenge;
+use crate::changelog;
+use crate::code_source;
 use crate::code_state;
+use crate::color_picker;
 use crate::config;
+use crate::daemon;
+use crate::discord;
+use crate::dogfood;
+use crate::drills;
+use crate::encoding;
+use crate::events::{AppEvent, EventBus};
+use crate::file_analysis;
+use crate::file_browser;
+use crate::highlight;
+use crate::highlighter;
+use crate::home_row;
+use crate::ignore::IgnoreList;
 use crate::input;
+use crate::keyboard;
+use crate::language;
+use crate::license_header;
+use crate::line_diff;
+use crate::locale;
+use crate::metronome;
+use crate::paths;
+use crate::pomodoro;
+use crate::practice_lock;
+use crate::profiling;
 use crate::progress_storage;
+use crate::quotes;
+use crate::refresh;
+use crate::reminder;
 use crate::renderer;
+use crate::result_card;
+use crate::review;
+use crate::samples;
+use crate::scripting;
+use crate::secrets;
 use crate::session_history;
 use crate::session_state;
+use crate::split;
+use crate::state_snapshot;
+use crate::symbol_hints;
 use crate::text;
+use crate::trace;
+use crate::tutorial;
+use crate::typing_engine;
+use crate::webhook;
+
+/// Lines shown above the cursor line while scroll lock keeps the view on it
+const SCROLL_LOCK_LINES_ABOVE: usize = 3;
+
+/// How often keystrokes relayout the screen once the text budget defers relayout
+const DEFERRED_RELAYOUT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often a running session publishes its speed; Discord takes presence updates
+/// at most every 15 seconds
+const SESSION_PROGRESS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Limits for adjusting spacing from the keyboard
+const MIN_LINE_SPACING: f32 = 0.8;
+const MAX_LINE_SPACING: f32 = 3.0;
+const MIN_CHAR_SPACING: f32 = -10.0;
+const MAX_CHAR_SPACING: f32 = 40.0;
+/// Limits for the per-file zoom; the glyph atlas has to fit the zoomed font
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 1.5;
 
 pub struct CargoTapApp {
     pub render_engine: renderer::VulkanRenderer,
-    pub text_system: Option<Arc<Mutex<text::TextSystem>>>,
+    /// Only the app writes to it; the renderer gets finished frames over a channel
+    pub text_system: Option<text::TextSystem>,
     pub input_handler: input::InputHandler,
     pub code_state: code_state::CodeState,
     pub config: config::Config,
+    /// How numbers and dates are written on the statistics screens
+    pub locale: locale::Locale,
+    /// Picks the syntax highlighter for each file's language
+    pub languages: highlight::Languages,
+    /// Colors the code on a worker thread so large files don't stall the event loop
+    pub highlighter: highlighter::Highlighter,
     pub scroll_offset: usize,
+    /// With scroll lock on, the view stays where the scroll keys put it until then
+    pub scroll_peek_until: Option<Instant>,
+    /// Lines of code the last layout had room for from `scroll_offset` on, which
+    /// follow and centered scrolling keep the cursor within; 0 before the first one
+    pub code_view_lines: usize,
+    /// Where the code view is on its way to `scroll_offset`, and lines fading in
+    pub animations: animation::Animations,
+    /// Display overrides of the current file, restored from its saved progress
+    pub view: progress_storage::ViewPreferences,
+    /// Set when a file is opened for the first time and `text.fit_font_size` is on;
+    /// the code view then measures its widest line
+    pub fit_font_pending: bool,
+    /// Zoom the code view measured for `fit_font_pending`, applied after the layout
+    pub fitted_zoom: Option<f32>,
     pub progress_storage: progress_storage::ProgressStorage,
     pub current_file_path: String,
     pub current_file_hash: String,
+    pub current_file_encoding: String,
+    /// License header the current file starts after, if it was skipped
+    pub license_header: Option<license_header::Header>,
     pub session_state: session_state::SessionState,
     pub session_history: session_history::SessionHistory,
     pub show_statistics: bool,
+    /// Which view of the statistics screen is shown
+    pub stats_view: session_history::StatsView,
+    /// Sessions the keyboard heatmap and speed views are computed from
+    pub stats_range: session_history::HistoryRange,
     pub file_selection_mode: bool,
     pub file_input_buffer: String,
+    /// The file picker only lists files typed mostly by this hand (Tab cycles it)
+    pub file_hand_filter: Option<keyboard::Hand>,
+    /// Selection and extension filter of the file picker's listing
+    pub file_browser: file_browser::FileBrowser,
+    /// Why the last file in file selection mode couldn't be loaded
+    pub file_error: Option<String>,
+    /// File whose note is being edited in the note overlay
+    pub note_edit_path: Option<String>,
+    pub note_input: String,
+    pub settings_mode: bool,
+    pub color_picker: color_picker::ColorPicker,
+    pub script_host: Option<scripting::ScriptHost>,
+    /// Events for the script host, which lives on the UI thread
+    script_events: Option<Receiver<AppEvent>>,
+    /// Broadcasts app state changes to integrations
+    pub events: EventBus,
+    /// Daily practice reminder, if a reminder time is configured
+    pub reminder: Option<reminder::ReminderScheduler>,
+    /// Work/break cycle state when pomodoro mode is enabled
+    pub pomodoro: Option<pomodoro::Pomodoro>,
+    /// Holds typing to a maximum pace when the metronome is enabled
+    pub metronome: Option<metronome::Metronome>,
+    /// Sound output when any sound is turned on (see `audio`) and there is a device
+    pub audio: Option<audio::Audio>,
+    /// System clipboard, opened the first time something is copied
+    pub clipboard: Option<arboard::Clipboard>,
+    pub toast: Option<(String, Instant)>,
+    pub quote_database: quotes::QuoteDatabase,
+    /// Quote being typed while in quote mode
+    pub active_quote: Option<quotes::Quote>,
+    pub drill_menu_mode: bool,
+    pub drill_menu: drills::DrillMenu,
+    /// Drill being typed while in drill mode
+    pub active_drill: Option<drills::Drill>,
+    /// Challenge being run, its text loaded in place of a file
+    pub active_challenge: Option<challenge::Challenge>,
+    /// Mistyped lines being typed again in a review round
+    pub active_review: Option<review::Review>,
+    /// Second file and turn-taking state while practicing two files side by side
+    pub split: Option<split::SplitPractice>,
+    /// Keys of the running session, kept when `record_traces` is on
+    pub trace: Option<trace::Recorder>,
+    /// Symbols whose key combination was already explained
+    pub shown_hints: symbol_hints::ShownHints,
+    /// Session setup overlay, open while choosing what ends a session
+    pub end_condition_menu: Option<session_state::EndConditionMenu>,
+    /// Analysis of the file just opened from the picker, shown before practicing it
+    pub file_analysis: Option<file_analysis::FileAnalysis>,
+    /// The file changed since the last session; shown until accepted or dismissed
+    pub resume_offer: Option<line_diff::ResumeOffer>,
+    /// Home row reminder shown before the next session, when `home_row_reminder` is on
+    pub home_row_check: Option<home_row::HomeRowCheck>,
+    /// Guided first session, shown over the tutorial text until finished or skipped
+    pub tutorial: Option<tutorial::Tutorial>,
+    /// What changed since the last version that ran, shown once after an update
+    pub whats_new: Option<changelog::WhatsNew>,
+    pub dogfood_menu_mode: bool,
+    pub dogfood_modules: Vec<String>,
+    pub dogfood_selected: usize,
+    pub line_range_mode: bool,
+    pub line_range_input: String,
+    /// Why the last range typed in the line range prompt was rejected
+    pub line_range_error: Option<String>,
+    /// Lines the current sessions are restricted to
+    pub line_range: Option<session_state::LineRange>,
+    /// The go-to-line prompt is open
+    pub goto_line_mode: bool,
+    pub goto_line_input: String,
+    /// Why the last line typed in the go-to-line prompt was rejected
+    pub goto_line_error: Option<String>,
+    /// The GitHub prompt is open
+    pub github_mode: bool,
+    /// owner/repo/path typed into the GitHub prompt, kept for next time
+    pub github_input: String,
+    /// Why the last fetch from GitHub failed
+    pub github_error: Option<String>,
+    /// The download in progress and what was asked for
+    pub github_fetch: Option<(
+        code_source::RepoFile,
+        Receiver<Result<code_source::Fetched>>,
+    )>,
     pub frame_times: VecDeque<Instant>,
+    /// Recent frame durations for the frame-time graph
+    pub frame_time_history: profiling::FrameTimeHistory,
+    /// Timing counters and keystroke latency percentiles, logged on exit
+    pub perf_stats: profiling::PerfStats,
     pub last_frame_time: Instant,
     pub current_fps: f32,
     pub last_key_processing_time_ms: f64,
     pub text_update_time_ms: f64,
     pub ui_generation_time_ms: f64,
+    /// When the whole screen was last laid out
+    pub last_text_update: Instant,
+    /// Monitor refresh rate and how often the HUD is laid out during a session
+    pub refresh: refresh::RefreshSchedule,
+    /// When the running session last published `SessionProgress`
+    pub last_progress_event: Instant,
+    /// The last keystroke only moved the caret one character to the right
+    pub caret_only_change: bool,
+    /// Time budget of text updates and how far layout is cut back to keep it
+    pub text_budget: profiling::TextBudget,
+    /// A keystroke's relayout was put off until `DEFERRED_RELAYOUT_INTERVAL` passes
+    pub relayout_pending: bool,
+    /// Takes diagnostic snapshots of slow frames, when `debug.capture_stutters` is on
+    pub stutter_monitor: Option<profiling::StutterMonitor>,
+    /// When state.json was last written
+    pub last_state_dump: Instant,
+    /// Turns the session HUD red while live accuracy is below `gameplay.accuracy_floor`
+    pub accuracy_alarm: session_state::AccuracyAlarm,
+    /// Quitting was requested mid-session and the exit confirmation is shown
+    pub exit_confirm_mode: bool,
+    /// The exit confirmation paused the session, and resumes it when it is dismissed
+    exit_confirm_paused: bool,
+    /// Quitting was requested before the daily goal and the lock phrase is being typed
+    pub practice_lock_mode: bool,
+    /// What has been typed of the practice lock phrase so far
+    pub practice_lock_input: String,
+    /// The lock phrase was typed, so quitting is no longer held back this run
+    pub practice_lock_passed: bool,
+    /// The user confirmed quitting; the event loop exits after the current event
+    pub exit_requested: bool,
+    /// Set while running as a daemon: closing the window hides it, and `cargotap
+    /// open` requests come in here
+    pub daemon: Option<daemon::Server>,
 }
 
 impl CargoTapApp {
     pub fn new(event_loop: &EventLoop<()>) -> Result<Self> {
+        Self::with_renderer(renderer::VulkanRenderer::new(event_loop)?)
+    }
+
+    /// Builds the app around an already created renderer (windowed or embedded)
+    pub fn with_renderer(mut render_engine: renderer::VulkanRenderer) -> Result<Self> {
         let config = config::Config::load();
 
         let warnings = config.validate();
         for warning in warnings {
             log::warn!("Config validation: {}", warning);
         }
+        render_engine.set_opacity(config.window.opacity.clamp(config::MIN_OPACITY, 1.0));
 
-        let render_engine = renderer::VulkanRenderer::new(event_loop);
         let input_handler = input::InputHandler::new();
 
         let mut progress_storage = progress_storage::ProgressStorage::default();
         let _ = progress_storage.load();
+        render_engine.set_window_options(renderer::WindowOptions {
+            title: config.window.title.clone(),
+            size: [config.window.width, config.window.height],
+            saved: config
+                .window
+                .remember_geometry
+                .then(|| progress_storage.window_geometry())
+                .flatten(),
+        });
 
         // Determine which file to load: last opened > config > demo
         let file_path = if let Some(last_opened) = progress_storage.get_last_opened_file() {
@@ -62,51 +287,71 @@ impl CargoTapApp {
             custom_path.clone()
         } else {
             log::info!("Using demo code");
-            "demo_code.rs".to_string()
+            DEMO_CODE_PATH.to_string()
         };
 
         // Load the file content
-        let demo_code = if file_path == "demo_code.rs" {
-            include_str!("demo_code.rs").to_string()
-        } else {
-            match std::fs::read_to_string(&file_path) {
-                Ok(code) => {
-                    log::info!("Successfully loaded file from: {}", file_path);
-                    code
-                }
-                Err(e) => {
-                    log::error!("Failed to load file from {}: {}", file_path, e);
-                    log::info!("Falling back to demo code");
-                    include_str!("demo_code.rs").to_string()
-                }
+        let mut load_error = None;
+        let loaded = match load_code(
+            &file_path,
+            config.gameplay.streaming_threshold_bytes(),
+            config.gameplay.skip_license_headers,
+            config.gameplay.secrets,
+            config.gameplay.strip_untypeable_lines,
+            &progress_storage,
+        ) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log::error!("Failed to load file from {}: {}", file_path, e);
+                log::info!("Falling back to demo code");
+                load_error = Some(e.to_string());
+                LoadedCode::demo()
             }
         };
+        let LoadedCode {
+            code_state,
+            hash: current_file_hash,
+            encoding: current_file_encoding,
+            scroll_offset,
+            license_header,
+            resume_offer,
+            secrets: secret_findings,
+        } = loaded;
+        let secrets_notice = secrets::notice(&secret_findings, config.gameplay.secrets, &file_path);
+        let view = progress_storage.get_view(&file_path);
+        let fit_font_pending = wants_font_fit(&config, &progress_storage, &file_path);
 
-        let current_file_hash = progress_storage::compute_hash(&demo_code);
-
-        let mut code_state = code_state::CodeState::new(demo_code);
+        let mut session_state =
+            session_state::SessionState::new(config.gameplay.session_duration_minutes);
+        session_state.set_dead_zone_streak(config.gameplay.dead_zone_streak);
+        session_state.set_end_condition(config.gameplay.end_condition);
+        session_state.set_warmup(config.gameplay.warmup());
+        let accuracy_alarm = session_state::AccuracyAlarm::new(config.gameplay.accuracy_floor);
+        let languages = highlight::Languages::new(&config);
 
-        let mut scroll_offset = 0;
-        if let Some(progress) = progress_storage.get_progress(&file_path) {
-            if progress.content_hash == current_file_hash {
-                log::info!(
-                    "Restoring progress at position {} with scroll offset {}",
-                    progress.position,
-                    progress.scroll_offset
-                );
-                for _ in 0..progress.position {
-                    if code_state.type_character().is_none() {
-                        break;
-                    }
-                }
-                scroll_offset = progress.scroll_offset;
-            } else {
-                log::info!("File changed, starting from beginning");
-            }
+        let pomodoro = config
+            .pomodoro
+            .enabled
+            .then(|| pomodoro::Pomodoro::new(config.pomodoro.clone()));
+        if let Some(ref pomodoro) = pomodoro {
+            // Work periods are timed, whatever sessions end on otherwise
+            session_state.set_end_condition(session_state::EndCondition::Time);
+            session_state.set_duration_minutes(pomodoro.work_minutes());
+            log::info!(
+                "🍅 Pomodoro mode: {:.0} minute work periods",
+                pomodoro.work_minutes()
+            );
         }
 
-        let session_state =
-            session_state::SessionState::new(config.gameplay.session_duration_minutes);
+        let metronome = config
+            .metronome
+            .enabled
+            .then(|| metronome::Metronome::new(&config.metronome));
+        let audio = (config.gameplay.audio_feedback
+            || config.gameplay.accuracy_alarm_bell
+            || (config.metronome.enabled && config.metronome.audible))
+            .then(|| audio::Audio::new(&config.audio))
+            .flatten();
 
         let mut session_history = session_history::SessionHistory::default();
         if let Err(e) = session_history.load() {
@@ -115,6 +360,61 @@ impl CargoTapApp {
             log::info!("Loaded {} previous sessions", session_history.count());
         }
 
+        let script_host = if config.scripting.enabled {
+            config.scripting.script_path.as_deref().and_then(|path| {
+                match scripting::ScriptHost::load(path) {
+                    Ok(host) => {
+                        log::info!("Loaded event script: {}", path);
+                        Some(host)
+                    }
+                    Err(e) => {
+                        log::error!("Failed to load event script {}: {}", path, e);
+                        None
+                    }
+                }
+            })
+        } else {
+            None
+        };
+
+        let reminder = config
+            .notifications
+            .reminder_time
+            .as_deref()
+            .and_then(reminder::parse_time)
+            .map(|time| {
+                log::info!(
+                    "⏰ Daily practice reminder set for {}",
+                    time.format("%H:%M")
+                );
+                reminder::ReminderScheduler::new(time, config.notifications.reminder_snooze_minutes)
+            });
+
+        let quote_database =
+            quotes::QuoteDatabase::load(config.gameplay.quotes_path.as_deref().map(Path::new));
+        if quote_database.is_empty() {
+            log::warn!("No quotes available, quote mode is disabled");
+        } else {
+            log::info!("Loaded {} quotes", quote_database.len());
+        }
+
+        let mut events = EventBus::default();
+        if let Some(ref url) = config.notifications.webhook_url {
+            webhook::subscribe(
+                events.subscribe(),
+                url.clone(),
+                config.notifications.webhook_max_retries,
+            );
+        }
+        if config.discord.enabled {
+            discord::subscribe(events.subscribe(), config.discord.clone());
+        }
+        let script_events = script_host.as_ref().map(|_| events.subscribe());
+        let home_row_check = config
+            .gameplay
+            .home_row_reminder
+            .then(home_row::HomeRowCheck::new);
+
         // Save the current file as last opened
         progress_storage.set_last_opened_file(file_path.clone());
         if let Err(e) = progress_storage.save() {
@@ -122,27 +422,120 @@ impl CargoTapApp {
         }
 
         let now = Instant::now();
+        let text_budget = profiling::TextBudget::new(config.debug.text_update_budget_ms);
+        let refresh = refresh::RefreshSchedule::new(
+            config.window.hud_refresh_divisor,
+            config.window.target_fps,
+        );
+        let stutter_monitor = config.debug.capture_stutters.then(|| {
+            profiling::StutterMonitor::new(
+                config.debug.stutter_threshold_ms,
+                Duration::from_secs_f64(config.debug.stutter_capture_interval_secs.max(0.0)),
+            )
+        });
+
         Ok(Self {
             render_engine,
             text_system: None,
             input_handler,
             code_state,
+            highlighter: highlighter::Highlighter::spawn(),
+            languages,
+            animations: animation::Animations::new(config.animation),
+            locale: locale::Locale::resolve(&config.statistics.locale),
             config,
             scroll_offset,
+            scroll_peek_until: None,
+            code_view_lines: 0,
+            view,
+            fit_font_pending,
+            fitted_zoom: None,
             progress_storage,
             current_file_path: file_path,
             current_file_hash,
+            current_file_encoding,
+            license_header,
             session_state,
             session_history,
             show_statistics: false,
+            stats_view: session_history::StatsView::default(),
+            stats_range: session_history::HistoryRange::default(),
             file_selection_mode: false,
             file_input_buffer: String::new(),
+            file_hand_filter: None,
+            file_browser: file_browser::FileBrowser::default(),
+            note_edit_path: None,
+            note_input: String::new(),
+            file_error: None,
+            settings_mode: false,
+            color_picker: color_picker::ColorPicker::new(),
+            script_host,
+            script_events,
+            events,
+            reminder,
+            pomodoro,
+            metronome,
+            audio,
+            clipboard: None,
+            toast: load_error
+                .or(secrets_notice)
+                .map(|message| (message, Instant::now())),
+            quote_database,
+            active_quote: None,
+            drill_menu_mode: false,
+            drill_menu: drills::DrillMenu::new(),
+            active_drill: None,
+            active_challenge: None,
+            active_review: None,
+            split: None,
+            trace: None,
+            shown_hints: symbol_hints::ShownHints::load(&crate::profile::data_file(
+                symbol_hints::SHOWN_HINTS_FILE,
+            )),
+            end_condition_menu: None,
+            dogfood_menu_mode: false,
+            dogfood_modules: dogfood::module_paths(),
+            dogfood_selected: 0,
+            file_analysis: None,
+            resume_offer,
+            home_row_check,
+            tutorial: None,
+            whats_new: None,
+            line_range_mode: false,
+            line_range_input: String::new(),
+            line_range_error: None,
+            line_range: None,
+            goto_line_mode: false,
+            goto_line_input: String::new(),
+            goto_line_error: None,
+            github_mode: false,
+            github_input: String::new(),
+            github_error: None,
+            github_fetch: None,
             frame_times: VecDeque::with_capacity(60),
+            frame_time_history: profiling::FrameTimeHistory::default(),
+            perf_stats: profiling::PerfStats::new(),
             last_frame_time: now,
             current_fps: 0.0,
             last_key_processing_time_ms: 0.0,
             text_update_time_ms: 0.0,
             ui_generation_time_ms: 0.0,
+            last_text_update: now,
+            refresh,
+            last_progress_event: now,
+            caret_only_change: false,
+            text_budget,
+            relayout_pending: false,
+            stutter_monitor,
+            last_state_dump: now,
+            accuracy_alarm,
+            exit_confirm_mode: false,
+            exit_confirm_paused: false,
+            practice_lock_mode: false,
+            practice_lock_input: String::new(),
+            practice_lock_passed: false,
+            exit_requested: false,
+            daemon: None,
         })
     }
 
@@ -150,8 +543,13 @@ impl CargoTapApp {
         if self.text_system.is_none() {
             let initial_settings = text::TextRenderSettings {
                 color: self.config.colors.text_default,
-                font_size: self.config.text.font_size,
+                font_size: self.font_size(),
                 position: [self.config.text.position_x, self.config.text.position_y],
+                shadow: self.config.text.shadow,
+                line_spacing: self.config.text.line_spacing,
+                char_spacing: self.config.text.char_spacing * self.ui_scale(),
+                caret: self.config.text.caret,
+                caret_easing: self.config.animation.easing,
             };
 
             let mut text_system = text::TextSystem::new(
@@ -159,10 +557,11 @@ impl CargoTapApp {
                 self.render_engine.queue.clone(),
                 self.render_engine.memory_allocator.clone(),
                 initial_settings,
+                &self.config.text.fallback_fonts,
             )?;
 
             info!("Initializing text system and rendering demo code");
-            let display_text = self.code_state.get_full_code();
+            let display_text = self.code_state.get_full_code().to_string();
             text_system.rasterize_text_to_console(&display_text)?;
 
             info!("Initial code state:");
@@ -177,9 +576,11 @@ impl CargoTapApp {
             info!("Creating text rendering pipeline");
             text_system.create_text_pipeline()?;
 
-            let text_system_arc = Arc::new(Mutex::new(text_system));
-            self.render_engine.set_text_system(text_system_arc.clone());
-            self.text_system = Some(text_system_arc);
+            if let Some([width, height]) = self.render_engine.get_window_size() {
+                text_system.update_window_size(width, height);
+            }
+            self.render_engine.set_text_frames(text_system.frames());
+            self.text_system = Some(text_system);
             self.update_text();
         }
         Ok(())
@@ -187,18 +588,14 @@ impl CargoTapApp {
 
     pub fn try_initialize_text_pipeline(&mut self) {
         let mut atlas_created = false;
-        if let Some(text_system_arc) = &self.text_system {
-            if let Ok(mut text_system) = text_system_arc.lock() {
-                if !text_system.is_pipeline_ready && self.render_engine.is_ready() {
-                    if let Some(text_pipeline_layout) =
-                        self.render_engine.get_text_pipeline_layout()
-                    {
-                        if let Err(e) = text_system.create_text_atlas(text_pipeline_layout) {
-                            log::error!("Failed to create text atlas: {}", e);
-                        } else {
-                            info!("Text atlas created successfully");
-                            atlas_created = true;
-                        }
+        if let Some(text_system) = &mut self.text_system {
+            if !text_system.is_pipeline_ready && self.render_engine.is_ready() {
+                if let Some(text_pipeline_layout) = self.render_engine.get_text_pipeline_layout() {
+                    if let Err(e) = text_system.create_text_atlas(text_pipeline_layout) {
+                        log::error!("Failed to create text atlas: {}", e);
+                    } else {
+                        info!("Text atlas created successfully");
+                        atlas_created = true;
                     }
                 }
             }
@@ -212,35 +609,575 @@ impl CargoTapApp {
     pub fn update_text(&mut self) {
         let start = Instant::now();
 
-        // Update the syntax highlighting setting in code_state
+        // Update the syntax highlighting setting and language in code_state
+        self.languages.set_colors(&self.config.colors);
         self.code_state
             .set_syntax_highlighting(self.config.text.syntax_highlighting);
+        self.code_state
+            .set_highlighter(self.languages.for_path(&self.current_file_path));
+        if let Some(split) = &mut self.split {
+            split
+                .other
+                .code_state
+                .set_syntax_highlighting(self.config.text.syntax_highlighting);
+            split
+                .other
+                .code_state
+                .set_highlighter(self.languages.for_path(&split.other.file_path));
+        }
+
+        // Keep the previous frame on screen until the new colors arrive
+        if let Some(generation) = self.code_state.missing_colors() {
+            if !self.highlighter.is_pending(generation) {
+                self.highlighter.request(self.code_state.highlight_job());
+            }
+            return;
+        }
 
         let ui_start = Instant::now();
 
-        if let Some(text_system_arc) = self.text_system.clone() {
-            if let Ok(mut text_system) = text_system_arc.lock() {
-                text_system.clear();
-                crate::ui::create_colored_text(self, text_system.deref_mut());
+        // Taken out while the UI writes into it, as the UI also reads the app
+        if let Some(mut text_system) = self.text_system.take() {
+            text_system.clear();
+            crate::ui::create_colored_text(self, &mut text_system);
+
+            self.ui_generation_time_ms = ui_start.elapsed().as_secs_f64() * 1000.0;
+            self.perf_stats
+                .ui_generation
+                .record(self.ui_generation_time_ms);
+            text_system.flush_records();
+            self.perf_stats.skipped_text_uploads += text_system.take_skipped_uploads();
+            self.text_system = Some(text_system);
+        }
+
+        self.text_update_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+        self.perf_stats.text_update.record(self.text_update_time_ms);
+        self.last_text_update = Instant::now();
+        self.relayout_pending = false;
 
-                self.ui_generation_time_ms = ui_start.elapsed().as_secs_f64() * 1000.0;
-                if let Err(e) = text_system.flush_vertices() {
-                    log::error!("Failed to update main text: {}", e);
+        let previous = self.text_budget.level();
+        if let Some(level) = self.text_budget.record(self.text_update_time_ms) {
+            let message = if level > previous {
+                format!(
+                    "🐢 Screen updates take {:.1}ms (budget {:.1}ms): {}",
+                    self.text_update_time_ms,
+                    self.text_budget.budget_ms(),
+                    level.label()
+                )
+            } else {
+                format!("🐇 Screen updates are fast again: {}", level.label())
+            };
+            log::warn!(
+                event = "text_budget",
+                duration_ms = self.text_update_time_ms,
+                level = level.label();
+                "{}",
+                message
+            );
+            // Shown with the next layout
+            self.toast = Some((message, Instant::now()));
+        }
+
+        if let Some(zoom) = self.fitted_zoom.take() {
+            self.fit_font_size(zoom);
+        }
+    }
+
+    /// Takes colors finished by the highlighter thread and redraws if they are for the current text
+    pub fn collect_highlighting(&mut self) {
+        if let Some(result) = self.highlighter.try_take()
+            && self
+                .code_state
+                .accept_colors(result.generation, result.colored)
+        {
+            self.update_text();
+        }
+    }
+
+    /// Blocks until the colors for the current text are ready, then redraws
+    pub fn finish_highlighting(&mut self) {
+        if let Some(result) = self.highlighter.wait()
+            && self
+                .code_state
+                .accept_colors(result.generation, result.colored)
+        {
+            self.update_text();
+        }
+    }
+
+    /// Changes line and character spacing for this run (config.toml is left alone)
+    pub fn set_text_spacing(&mut self, line_spacing: f32, char_spacing: f32) {
+        let line_spacing = line_spacing.clamp(MIN_LINE_SPACING, MAX_LINE_SPACING);
+        let char_spacing = char_spacing.clamp(MIN_CHAR_SPACING, MAX_CHAR_SPACING);
+        self.config.text.line_spacing = line_spacing;
+        self.config.text.char_spacing = char_spacing;
+
+        let ui_scale = self.ui_scale();
+        if let Some(text_system) = &mut self.text_system {
+            text_system.set_spacing(line_spacing, char_spacing * ui_scale);
+        }
+
+        info!(
+            "🔠 Line spacing {:.1}, character spacing {:+.0}px",
+            line_spacing, char_spacing
+        );
+        self.toast = Some((
+            format!(
+                "Line spacing {:.1} | Character spacing {:+.0}px",
+                line_spacing, char_spacing
+            ),
+            Instant::now(),
+        ));
+    }
+
+    /// Makes the window background `opacity` opaque and saves it to `config.toml`
+    pub fn set_opacity(&mut self, opacity: f32) {
+        // Round so repeated steps land on whole tenths
+        let opacity = ((opacity * 10.0).round() / 10.0).clamp(config::MIN_OPACITY, 1.0);
+        self.config.window.opacity = opacity;
+        self.render_engine.set_opacity(opacity);
+
+        info!("🪟 Window opacity {:.0}%", opacity * 100.0);
+        self.toast = Some((
+            format!("Window opacity {:.0}%", opacity * 100.0),
+            Instant::now(),
+        ));
+        let config_path = crate::profile::config_path();
+        if let Err(e) = self.config.save_setting(&config_path, "window.opacity") {
+            log::error!("Failed to save window opacity: {}", e);
+        }
+    }
+
+    /// How much larger everything is drawn: `ui_scale` in low-vision mode, 1 otherwise
+    pub fn ui_scale(&self) -> f32 {
+        let accessibility = &self.config.accessibility;
+        if accessibility.low_vision {
+            accessibility.ui_scale.clamp(1.0, 3.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// Font size after applying the current file's zoom and the UI scale
+    pub fn font_size(&self) -> f32 {
+        self.config.text.font_size * self.view.zoom.unwrap_or(1.0) * self.ui_scale()
+    }
+
+    /// Stops the running session's clock and hides the code until `resume_session`
+    pub fn pause_session(&mut self) {
+        if self.session_state.pause() {
+            self.save_progress();
+            self.update_text();
+        }
+    }
+
+    pub fn resume_session(&mut self) {
+        if self.session_state.resume() {
+            self.update_text();
+        }
+    }
+
+    /// Asks before quitting an unfinished session. The session is paused meanwhile,
+    /// so the time spent deciding doesn't count as typing time.
+    pub fn open_exit_confirm(&mut self) {
+        self.exit_confirm_mode = true;
+        self.exit_confirm_paused = self.session_state.pause();
+    }
+
+    /// Goes back from the exit confirmation, resuming the session if it paused it
+    pub fn close_exit_confirm(&mut self) {
+        self.exit_confirm_mode = false;
+        if std::mem::take(&mut self.exit_confirm_paused) {
+            self.session_state.resume();
+        }
+    }
+
+    /// Turns low-vision mode on or off and saves the choice to the config file
+    pub fn toggle_low_vision(&mut self) {
+        self.config.accessibility.low_vision = !self.config.accessibility.low_vision;
+        self.apply_font_size();
+        let char_spacing = self.config.text.char_spacing * self.ui_scale();
+        if let Some(text_system) = &mut self.text_system {
+            text_system.set_spacing(self.config.text.line_spacing, char_spacing);
+        }
+
+        let state = if self.config.accessibility.low_vision {
+            format!("on ({:.1}x)", self.ui_scale())
+        } else {
+            "off".to_string()
+        };
+        info!("👓 Low-vision mode {}", state);
+        self.toast = Some((format!("Low-vision mode {}", state), Instant::now()));
+
+        let config_path = crate::profile::config_path();
+        // Only the toggle: spacing from set_text_spacing is for this run only
+        if let Err(e) = self
+            .config
+            .save_setting(&config_path, "accessibility.low_vision")
+        {
+            log::error!("Failed to save low-vision mode: {}", e);
+        }
+    }
+
+    pub fn wrap_lines(&self) -> bool {
+        self.view.wrap_lines.unwrap_or(self.config.text.wrap_lines)
+    }
+
+    pub fn show_line_numbers(&self) -> bool {
+        self.view
+            .show_line_numbers
+            .unwrap_or(self.config.text.show_line_numbers)
+    }
+
+    pub fn fold_typed(&self) -> bool {
+        self.view.fold_typed.unwrap_or(self.config.text.fold_typed)
+    }
+
+    pub fn show_whitespace(&self) -> bool {
+        self.view
+            .show_whitespace
+            .unwrap_or(self.config.text.show_whitespace)
+    }
+
+    /// Changes the display overrides of the current file and remembers them for next time
+    pub fn set_view(&mut self, mut view: progress_storage::ViewPreferences) {
+        // Round so repeated steps don't drift away from whole percentages
+        view.zoom = view
+            .zoom
+            .map(|zoom| ((zoom * 10.0).round() / 10.0).clamp(MIN_ZOOM, MAX_ZOOM));
+        self.view = view;
+
+        // Quotes and drills have no file to remember the view for
+        if !self.is_generated_text() {
+            self.progress_storage
+                .set_view(&self.current_file_path, view);
+            if let Err(e) = self.progress_storage.save() {
+                log::error!("Failed to save view preferences: {}", e);
+            }
+        }
+
+        self.apply_font_size();
+        info!("🔍 View for {}: {:?}", self.current_file_path, view);
+        self.toast = Some((
+            format!(
+                "Zoom {:.0}% | Wrap {} | Line numbers {} | Folding {} | Whitespace {}",
+                view.zoom.unwrap_or(1.0) * 100.0,
+                if self.wrap_lines() { "on" } else { "off" },
+                if self.show_line_numbers() {
+                    "on"
+                } else {
+                    "off"
+                },
+                if self.fold_typed() { "on" } else { "off" },
+                if self.show_whitespace() { "on" } else { "off" },
+            ),
+            Instant::now(),
+        ));
+    }
+
+    /// Zooms the current file out to `zoom`, at which its widest line fits, or
+    /// suggests it, as `text.fit_font_size` says
+    fn fit_font_size(&mut self, zoom: f32) {
+        let zoom = zoom.max(MIN_ZOOM);
+        if zoom >= self.view.zoom.unwrap_or(1.0) {
+            return;
+        }
+        match self.config.text.fit_font_size {
+            config::FontFit::Apply => {
+                info!(
+                    "🔍 Zooming {} to {:.0}% so its widest line fits",
+                    self.current_file_path,
+                    zoom * 100.0
+                );
+                self.set_view(progress_storage::ViewPreferences {
+                    zoom: Some(zoom),
+                    ..self.view
+                });
+                self.relayout_pending = true;
+            }
+            config::FontFit::Suggest => {
+                self.toast = Some((
+                    format!(
+                        "The widest line fits at {:.0}% zoom (Ctrl+- / Cmd+- zooms out)",
+                        zoom * 100.0
+                    ),
+                    Instant::now(),
+                ));
+            }
+            config::FontFit::Off => {}
+        }
+    }
+
+    /// Rebuilds the glyph atlas if the zoomed font size differs from the current one
+    fn apply_font_size(&mut self) {
+        let font_size = self.font_size();
+        if let Some(text_system) = &mut self.text_system {
+            text_system.set_font_size(font_size);
+        }
+        self.try_initialize_text_pipeline();
+    }
+
+    /// Updates the accuracy alarm from the running session and redraws when it flips
+    pub fn check_accuracy_floor(&mut self) {
+        let stats = self
+            .session_state
+            .current_stats(self.code_state.get_cursor_position());
+        match self.accuracy_alarm.update(&stats) {
+            Some(true) => {
+                info!(
+                    "🚨 Accuracy {:.1}% dropped below the {:.1}% floor",
+                    stats.accuracy,
+                    self.accuracy_alarm.floor().unwrap_or_default()
+                );
+                if self.config.gameplay.accuracy_alarm_bell {
+                    self.play_sound(audio::Cue::Alarm);
                 }
+                self.update_text();
+            }
+            Some(false) => {
+                info!("✅ Accuracy back above the floor ({:.1}%)", stats.accuracy);
+                self.update_text();
             }
+            None => {}
         }
+    }
 
-        self.text_update_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+    /// Remembers the window's size and position for the next start
+    pub fn save_window_geometry(&mut self) {
+        if !self.config.window.remember_geometry {
+            return;
+        }
+        let Some(geometry) = self.render_engine.window_geometry() else {
+            return;
+        };
+        self.progress_storage.set_window_geometry(geometry);
+        if let Err(e) = self.progress_storage.save() {
+            log::error!("Failed to save the window geometry: {}", e);
+        }
+    }
+
+    /// Plays the sound of `cue` when sound feedback is on. The alarm and the beat
+    /// have settings of their own, which the caller checks.
+    pub fn play_sound(&self, cue: audio::Cue) {
+        let wanted = match cue {
+            audio::Cue::Alarm | audio::Cue::Beat => true,
+            audio::Cue::Key | audio::Cue::Error | audio::Cue::Finish => {
+                self.config.gameplay.audio_feedback
+            }
+        };
+        if wanted && let Some(audio) = &self.audio {
+            audio.play(cue);
+        }
+    }
+
+    /// Ticks and redraws on every metronome beat of the running session, so the
+    /// HUD's beat keeps time
+    pub fn tick_metronome(&mut self) {
+        let elapsed = Duration::from_secs_f64(self.session_state.time_elapsed());
+        let Some(metronome) = &mut self.metronome else {
+            return;
+        };
+        if !metronome.new_beat(elapsed) {
+            return;
+        }
+        if metronome.audible() {
+            self.play_sound(audio::Cue::Beat);
+        }
+        self.update_text();
+    }
+
+    /// Scroll offset to lay the code view out at, a step further on its way to
+    /// `scroll_offset`. Animations are skipped while the text budget is short.
+    pub fn animated_scroll_offset(&mut self) -> usize {
+        let instant = self.text_budget.level() >= profiling::Degradation::NoEffects;
+        self.animations.step(
+            self.scroll_offset,
+            self.code_view_lines,
+            instant,
+            Instant::now(),
+        );
+        self.animations.scroll_offset()
+    }
+
+    /// Minutes of today's practice goal still missing, or None when quitting isn't locked
+    pub fn practice_lock_remaining(&self) -> Option<f64> {
+        if self.practice_lock_passed {
+            return None;
+        }
+        let current_secs = if self.session_state.is_active() || self.session_state.is_paused() {
+            self.session_state
+                .current_stats(self.code_state.get_cursor_position())
+                .time_elapsed_secs
+        } else {
+            0.0
+        };
+        let practiced = practice_lock::minutes_practiced(
+            &self.session_history,
+            chrono::Local::now().date_naive(),
+            current_secs,
+        );
+        practice_lock::remaining_minutes(&self.config.practice_lock, practiced)
+    }
+
+    /// Saves progress before quitting, or asks first if the daily goal isn't met
+    /// or a session is running. Returns true if the app can exit right away.
+    pub fn request_exit(&mut self) -> bool {
+        if let Some(remaining) = self.practice_lock_remaining() {
+            if !self.practice_lock_mode {
+                self.practice_lock_mode = true;
+                self.practice_lock_input.clear();
+                info!(
+                    "🔒 {:.0} minutes left of today's practice, asking for the phrase",
+                    remaining.ceil()
+                );
+                self.update_text();
+            }
+            return false;
+        }
+
+        if self.session_state.is_active() || self.session_state.is_paused() {
+            if !self.exit_confirm_mode {
+                self.open_exit_confirm();
+                info!("🚪 Session in progress, asking before quitting");
+                self.update_text();
+            }
+            return false;
+        }
+
+        self.save_progress();
+        true
+    }
+
+    /// Redraws after a keystroke, only moving the caret when that is all that changed
+    pub fn update_text_after_input(&mut self) {
+        if std::mem::take(&mut self.caret_only_change)
+            && let Some(next_char) = self.code_state.peek_next_character()
+            && let Some(typed_color) = self.code_state.typed_char_color()
+            && let Some(text_system) = &mut self.text_system
+        {
+            if text_system.advance_caret(next_char, typed_color) {
+                return;
+            }
+        }
+        if self.text_budget.level() >= profiling::Degradation::DeferredLayout
+            && self.last_text_update.elapsed() < DEFERRED_RELAYOUT_INTERVAL
+        {
+            self.relayout_pending = true;
+            return;
+        }
+        self.update_text();
+    }
+
+    /// Does the relayout `update_text_after_input` put off, once its interval has passed
+    pub fn flush_deferred_relayout(&mut self) {
+        if self.relayout_pending && self.last_text_update.elapsed() >= DEFERRED_RELAYOUT_INTERVAL {
+            self.update_text();
+        }
+    }
+
+    /// Keeps the view from snapping back to the cursor for a while after a scroll key,
+    /// when scroll lock is on
+    pub fn start_scroll_peek(&mut self) {
+        let gameplay = &self.config.gameplay;
+        if gameplay.scroll_lock || gameplay.scroll_mode != config::ScrollMode::Manual {
+            let peek = Duration::from_secs_f64(self.config.gameplay.scroll_peek_secs.max(0.0));
+            self.scroll_peek_until = Some(Instant::now() + peek);
+        }
+    }
+
+    /// Moves the view to the cursor line the way scroll lock or `scroll_mode` asks,
+    /// unless a peek is running. Returns true when the view moved; laying it out is up
+    /// to the caller.
+    pub fn snap_scroll_to_cursor(&mut self) -> bool {
+        if self
+            .scroll_peek_until
+            .is_some_and(|until| Instant::now() < until)
+        {
+            return false;
+        }
+        self.scroll_peek_until = None;
+        let cursor_line = (self.code_state.get_cursor_line() - 1)
+            .saturating_sub(self.code_state.first_line_number());
+        let offset = if self.config.gameplay.scroll_lock {
+            cursor_line.saturating_sub(SCROLL_LOCK_LINES_ABOVE)
+        } else {
+            match self.config.gameplay.scroll_mode.offset(
+                self.scroll_offset,
+                cursor_line,
+                self.code_view_lines,
+            ) {
+                Some(offset) => offset,
+                None => return false,
+            }
+        };
+        std::mem::replace(&mut self.scroll_offset, offset) != offset
+    }
+
+    /// Lays out the screen again if the last layout is older than the HUD interval.
+    /// Keystrokes that only move the caret are drawn right away and the rest of the
+    /// screen catches up every few monitor refreshes.
+    pub fn update_text_if_stale(&mut self) {
+        if self.last_text_update.elapsed() >= self.refresh.hud_interval() {
+            self.update_text();
+        }
+    }
+
+    /// Hands events published since the last call to the script host.
+    /// Returns true if any were handled.
+    pub fn dispatch_script_events(&mut self) -> bool {
+        let Some(receiver) = &self.script_events else {
+            return false;
+        };
+        let events: Vec<AppEvent> = receiver.try_iter().collect();
+        for event in &events {
+            if let AppEvent::SessionFinished(stats) = event {
+                self.run_script_hook(|host| host.on_session_finished(stats));
+            }
+        }
+        !events.is_empty()
+    }
+
+    /// Runs a script hook (if a script is loaded) and surfaces the error it failed with,
+    /// or else any toast it requested
+    pub fn run_script_hook<F: FnOnce(&mut scripting::ScriptHost)>(&mut self, hook: F) {
+        if let Some(host) = self.script_host.as_mut() {
+            hook(host);
+            if let Some(message) = host.take_error().or_else(|| host.take_toasts().pop()) {
+                self.toast = Some((message, Instant::now()));
+            }
+        }
     }
 
     pub fn save_session_statistics(&mut self) -> bool {
-        if let Some(stats) = self.session_state.last_stats() {
-            self.session_history.add_session(stats.clone());
+        if let Some(mut stats) = self.session_state.last_stats().cloned() {
+            self.play_sound(audio::Cue::Finish);
+            // Only a session that ran its full timer completes a work period
+            if let Some(ref mut pomodoro) = self.pomodoro
+                && stats.time_elapsed_secs >= self.session_state.duration_secs()
+            {
+                stats.pomodoro = true;
+                pomodoro.start_break();
+            }
+            stats.score = Some(stats.score_or_compute(self.config.scoring.accuracy_exponent));
+            if self.config.gameplay.record_session_context {
+                stats.context = Some(self.session_context());
+            }
+            // A pomodoro starts the next session on its own after the break
+            if self.config.gameplay.home_row_reminder && self.pomodoro.is_none() {
+                self.home_row_check = Some(home_row::HomeRowCheck::new());
+            }
+
+            self.save_trace(&stats);
+            self.events
+                .publish(AppEvent::SessionFinished(Box::new(stats.clone())));
+            self.session_history.add_session(stats);
             if let Err(e) = self.session_history.save() {
                 log::error!("Failed to save session history: {}", e);
                 return false;
             } else {
                 log::info!(
+                    event = "session_saved",
+                    file = self.current_file_path.as_str();
                     "✅ Session saved to history (total: {})",
                     self.session_history.count()
                 );
@@ -250,18 +1187,112 @@ impl CargoTapApp {
         false
     }
 
+    /// The setup sessions are typed on right now
+    fn session_context(&self) -> session_state::SessionContext {
+        session_state::SessionContext {
+            keyboard: self
+                .config
+                .gameplay
+                .keyboard_name
+                .clone()
+                .filter(|name| !name.trim().is_empty()),
+            window_size: self
+                .render_engine
+                .get_window_size()
+                .map(|[width, height]| [width as u32, height as u32]),
+            font_size: self.font_size(),
+            theme: self.config.colors.theme_id(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    fn save_trace(&mut self, stats: &session_state::SessionStats) {
+        let Some(trace) = self
+            .trace
+            .take()
+            .and_then(|recorder| recorder.finish(&self.current_file_path, stats))
+        else {
+            return;
+        };
+        let path = crate::profile::data_file(trace::TRACES_FILE);
+        match trace::append(&trace, &path) {
+            Ok(()) => log::info!("Trace saved to {}", path.display()),
+            Err(e) => log::error!("Failed to save trace: {}", e),
+        }
+    }
+
+    /// Remembers a key for the trace of the running session, if one is recorded
+    pub fn record_trace_key(&mut self, key: trace::Key) {
+        if let Some(recorder) = &mut self.trace {
+            recorder.push(key);
+        }
+    }
+
+    /// Explains how to type `expected` if it keeps being missed and hasn't been explained yet
+    pub fn offer_symbol_hint(&mut self, expected: char) {
+        if !self.config.gameplay.symbol_hints || self.shown_hints.contains(expected) {
+            return;
+        }
+        let Some(hint) = symbol_hints::hint(self.config.gameplay.keyboard_layout, expected) else {
+            return;
+        };
+        let session = self.session_state.key_stat(expected);
+        let mut overall = self
+            .session_history
+            .get_key_stats(session_history::HistoryRange::All)
+            .remove(&expected)
+            .unwrap_or_default();
+        overall.add(session);
+        if !symbol_hints::struggling(session, overall) {
+            return;
+        }
+
+        info!("💡 {}", hint);
+        self.toast = Some((hint, Instant::now()));
+        self.shown_hints.insert(expected);
+        let path = crate::profile::data_file(symbol_hints::SHOWN_HINTS_FILE);
+        if let Err(e) = self.shown_hints.save(&path) {
+            log::error!("Failed to save shown hints: {}", e);
+        }
+    }
+
+    /// What keys do under the current settings
+    pub fn typing_rules(&self) -> typing_engine::Rules {
+        typing_engine::Rules::new(&self.config.gameplay, self.strict_rules())
+    }
+
     pub fn save_progress(&mut self) {
+        // Quotes and drills are throwaway text, only file progress is worth keeping
+        if self.is_generated_text() {
+            return;
+        }
+
         let position = self.code_state.get_cursor_position();
-        self.progress_storage.save_progress_with_scroll_offset(
-            self.current_file_path.clone(),
-            self.current_file_hash.clone(),
-            position,
-            self.scroll_offset,
-        );
+        self.progress_storage
+            .save_progress_with_scroll_offset_and_encoding(
+                self.current_file_path.clone(),
+                self.current_file_hash.clone(),
+                position,
+                self.scroll_offset,
+                self.current_file_encoding.clone(),
+            );
+        // Streamed files are never held in memory as a whole
+        if !self.code_state.is_streaming() {
+            self.progress_storage.set_normalized_anchor(
+                &self.current_file_path,
+                &self.code_state.get_full_code().to_string(),
+                position,
+            );
+        }
+        self.progress_storage
+            .set_skipped_header(&self.current_file_path, self.license_header);
         if let Err(e) = self.progress_storage.save() {
             log::error!("Failed to save progress: {}", e);
         } else {
             log::info!(
+                event = "progress_saved",
+                file = self.current_file_path.as_str(),
+                position = position;
                 "Progress saved at position {} with scroll offset {}",
                 position,
                 self.scroll_offset
@@ -270,22 +1301,50 @@ impl CargoTapApp {
     }
 
     pub fn load_file(&mut self, file_path: String) -> Result<()> {
-        let code = match std::fs::read_to_string(&file_path) {
-            Ok(code) => {
-                log::info!("Successfully loaded file: {}", file_path);
-                code
-            }
+        let loaded = match load_code(
+            &file_path,
+            self.config.gameplay.streaming_threshold_bytes(),
+            self.config.gameplay.skip_license_headers,
+            self.config.gameplay.secrets,
+            self.config.gameplay.strip_untypeable_lines,
+            &self.progress_storage,
+        ) {
+            Ok(loaded) => loaded,
             Err(e) => {
                 log::error!("Failed to load file {}: {}", file_path, e);
-                return Err(anyhow::anyhow!("Failed to load file: {}", e));
+                return Err(e);
             }
         };
 
         self.save_progress();
+        self.split = None;
+        self.trace = None;
+        self.tutorial = None;
+        self.active_quote = None;
+        self.active_drill = None;
+        self.leave_review();
+        self.leave_challenge();
+        self.clear_line_range();
+        self.session_state.set_source(session_state::FILE_SOURCE);
+        self.session_state.set_seed(None);
 
-        let new_file_hash = progress_storage::compute_hash(&code);
         self.current_file_path = file_path.clone();
-        self.current_file_hash = new_file_hash;
+        self.current_file_hash = loaded.hash;
+        self.current_file_encoding = loaded.encoding;
+        self.license_header = loaded.license_header;
+        self.code_state = loaded.code_state;
+        self.scroll_offset = loaded.scroll_offset;
+        self.resume_offer = loaded.resume_offer;
+        self.file_analysis = None;
+        self.view = self.progress_storage.get_view(&file_path);
+        self.fit_font_pending = wants_font_fit(&self.config, &self.progress_storage, &file_path);
+        self.fitted_zoom = None;
+        if let Some(notice) =
+            secrets::notice(&loaded.secrets, self.config.gameplay.secrets, &file_path)
+        {
+            self.toast = Some((notice, Instant::now()));
+        }
+        self.apply_font_size();
 
         self.progress_storage
             .set_last_opened_file(file_path.clone());
@@ -293,36 +1352,713 @@ impl CargoTapApp {
             log::error!("Failed to save last opened file: {}", e);
         }
 
-        self.code_state = code_state::CodeState::new(code);
-        self.scroll_offset = 0;
+        let current_pos = self.code_state.get_cursor_position();
+        self.session_state
+            .start_new_session(current_pos, file_path.clone());
+        self.events
+            .publish(AppEvent::FileLoaded { path: file_path });
 
-        if let Some(progress) = self.progress_storage.get_progress(&file_path) {
-            if progress.content_hash == self.current_file_hash {
-                log::info!(
-                    "Restoring progress at position {} with scroll offset {}",
-                    progress.position,
-                    progress.scroll_offset
-                );
-                for _ in 0..progress.position {
-                    if self.code_state.type_character().is_none() {
-                        break;
-                    }
-                }
-                self.scroll_offset = progress.scroll_offset;
+        Ok(())
+    }
+
+    /// Opens two files side by side to type in turns, switching to the other one
+    /// every `switch_lines` lines (0: only on the hotkey)
+    pub fn start_split(&mut self, left: String, right: String, switch_lines: usize) -> Result<()> {
+        if left == right {
+            anyhow::bail!("Split practice needs two different files");
+        }
+        let loaded = load_code(
+            &right,
+            self.config.gameplay.streaming_threshold_bytes(),
+            self.config.gameplay.skip_license_headers,
+            self.config.gameplay.secrets,
+            self.config.gameplay.strip_untypeable_lines,
+            &self.progress_storage,
+        )?;
+        let secrets_notice = secrets::notice(&loaded.secrets, self.config.gameplay.secrets, &right);
+        self.load_file(left)?;
+
+        let other = split::Pane {
+            code_state: loaded.code_state,
+            file_path: right,
+            file_hash: loaded.hash,
+            file_encoding: loaded.encoding,
+            scroll_offset: loaded.scroll_offset,
+            license_header: loaded.license_header,
+        };
+        let split =
+            split::SplitPractice::new(other, switch_lines, self.code_state.get_cursor_line());
+        log::info!(
+            "🔀 Split practice: {} (switching every {} lines)",
+            split.label(&self.current_file_path),
+            switch_lines
+        );
+        self.split = Some(split);
+        if let Some(notice) = secrets_notice {
+            self.toast = Some((notice, Instant::now()));
+        }
+
+        self.session_state.set_source(split::SPLIT_SOURCE);
+        let position = self.code_state.get_cursor_position();
+        self.session_state
+            .start_new_session(position, self.session_file_label());
+        Ok(())
+    }
+
+    /// Makes the other file of a split the one being typed
+    pub fn switch_pane(&mut self) {
+        let Some(mut split) = self.split.take() else {
+            return;
+        };
+        // The pane going inactive can't change, so saving it now is enough
+        self.save_progress();
+
+        let pane = &mut split.other;
+        std::mem::swap(&mut self.code_state, &mut pane.code_state);
+        std::mem::swap(&mut self.current_file_path, &mut pane.file_path);
+        std::mem::swap(&mut self.current_file_hash, &mut pane.file_hash);
+        std::mem::swap(&mut self.current_file_encoding, &mut pane.file_encoding);
+        std::mem::swap(&mut self.scroll_offset, &mut pane.scroll_offset);
+        std::mem::swap(&mut self.license_header, &mut pane.license_header);
+        split.switched(self.code_state.get_cursor_line());
+        self.split = Some(split);
+
+        self.session_state.record_pane_switch();
+        // A trace follows one text
+        self.trace = None;
+        self.caret_only_change = false;
+        log::info!("🔀 Switched to {}", self.current_file_path);
+    }
+
+    /// Name sessions are saved under: the file, or both files of a split
+    pub fn session_file_label(&self) -> String {
+        match &self.split {
+            Some(split) => split.label(&self.current_file_path),
+            None => self.current_file_path.clone(),
+        }
+    }
+
+    /// Starts timing a session from the current cursor position
+    pub fn start_session(&mut self) {
+        // Blank files are refused when loaded; this keeps anything else that ends
+        // up empty from recording a session that finished at its first frame
+        if self.code_state.get_total_length() == 0 {
+            log::warn!("Not starting a session: there is no text to type");
+            return;
+        }
+        let position = self.code_state.get_cursor_position();
+        self.trace = self
+            .config
+            .debug
+            .record_traces
+            .then(|| trace::Recorder::new(position, self.typing_rules()));
+        self.session_state
+            .start(position, self.session_file_label());
+        if let Some(metronome) = &mut self.metronome {
+            metronome.reset();
+        }
+        self.events.publish(AppEvent::SessionStarted {
+            file_path: self.current_file_path.clone(),
+        });
+        self.last_progress_event = Instant::now();
+    }
+
+    /// Publishes the running session's speed, at most every `SESSION_PROGRESS_INTERVAL`
+    pub fn publish_session_progress(&mut self) {
+        if self.last_progress_event.elapsed() < SESSION_PROGRESS_INTERVAL {
+            return;
+        }
+        self.last_progress_event = Instant::now();
+        let stats = self
+            .session_state
+            .current_stats(self.code_state.get_cursor_position());
+        self.events.publish(AppEvent::SessionProgress {
+            file_path: self.current_file_path.clone(),
+            words_per_minute: stats.words_per_minute,
+            elapsed_secs: stats.time_elapsed_secs,
+        });
+    }
+
+    /// The home row reminder covers the text until the next session starts
+    pub fn home_row_pending(&self) -> bool {
+        self.home_row_check.is_some()
+            && !self.session_state.is_active()
+            && !self.session_state.is_paused()
+            && !self.session_state.is_finished()
+    }
+
+    /// Starts the session the home row reminder was counting down to
+    pub fn finish_home_row_check(&mut self) {
+        self.home_row_check = None;
+        log::info!("🏠 Home row checked, starting the session");
+        self.start_session();
+    }
+
+    /// Seed for the next quote or drill: the configured one, or a fresh random one
+    pub fn next_seed(&self) -> u64 {
+        self.config.gameplay.seed.unwrap_or_else(rand::random)
+    }
+
+    /// Replaces the current text with the quote picked by `seed` and starts a quote session
+    pub fn start_quote(&mut self, seed: u64) {
+        let Some(quote) = self
+            .quote_database
+            .pick(
+                self.config.gameplay.quote_length,
+                &mut StdRng::seed_from_u64(seed),
+            )
+            .cloned()
+        else {
+            log::warn!("No quotes available");
+            return;
+        };
+
+        self.save_progress();
+
+        log::info!("Starting quote by {} (seed {})", quote.author, seed);
+        self.load_generated_text(quote.text.clone(), quotes::QUOTE_SOURCE, Some(seed));
+        self.active_quote = Some(quote);
+    }
+
+    /// Replaces the current text with a generated drill and starts a drill session
+    pub fn start_drill(&mut self, drill: drills::Drill) {
+        self.save_progress();
+
+        log::info!(
+            "Starting {} drill (difficulty {}, seed {})",
+            drill.kind.name(),
+            drill.difficulty,
+            drill.seed
+        );
+        self.load_generated_text(drill.generate(), drills::DRILL_SOURCE, Some(drill.seed));
+        self.active_drill = Some(drill);
+    }
+
+    /// Replaces the current text with a challenge's and applies its lines, duration and rules
+    pub fn start_challenge(&mut self, challenge: challenge::Challenge) -> Result<()> {
+        let text = challenge.text()?;
+        self.save_progress();
+
+        log::info!(
+            "🏁 Starting challenge {} ({:.1} minutes, {:?} rules)",
+            challenge.id,
+            challenge.duration_minutes,
+            challenge.rules
+        );
+        self.load_generated_text(text, challenge::CHALLENGE_SOURCE, challenge.seed());
+        if let Some(range) = challenge.line_range() {
+            self.start_line_range(range)?;
+        }
+        self.session_state
+            .set_duration_minutes(challenge.duration_minutes);
+        self.session_state
+            .set_end_condition(session_state::EndCondition::Time);
+        // Everyone taking a challenge is measured over the whole run
+        self.session_state
+            .set_warmup(session_state::Warmup::default());
+        self.session_state.set_challenge(Some(challenge.id.clone()));
+        self.active_challenge = Some(challenge);
+        Ok(())
+    }
+
+    /// Drops the active challenge and its session length
+    fn leave_challenge(&mut self) {
+        if self.active_challenge.take().is_none() {
+            return;
+        }
+        let minutes = self
+            .pomodoro
+            .as_ref()
+            .map_or(self.config.gameplay.session_duration_minutes, |pomodoro| {
+                pomodoro.work_minutes()
+            });
+        self.session_state.set_duration_minutes(minutes);
+        if self.pomodoro.is_none() {
+            self.session_state
+                .set_end_condition(self.config.gameplay.end_condition);
+        }
+        self.session_state.set_warmup(self.config.gameplay.warmup());
+        self.session_state.set_challenge(None);
+    }
+
+    /// Sessions end on their timer while a pomodoro or challenge sets the pace
+    pub fn end_condition_locked(&self) -> bool {
+        self.pomodoro.is_some() || self.active_challenge.is_some()
+    }
+
+    /// Keeps running with the window hidden until `server` gets an open request
+    pub fn start_daemon(&mut self, server: daemon::Server) {
+        info!("Daemon listening, window hidden until opened");
+        self.daemon = Some(server);
+        self.render_engine.set_window_visible(false);
+    }
+
+    /// Hides the window instead of quitting, when running as a daemon. Progress was
+    /// saved on the way out, as for quitting.
+    pub fn hide_window(&mut self) {
+        info!("Window closed, daemon keeps running");
+        self.exit_requested = false;
+        self.exit_confirm_mode = false;
+        self.exit_confirm_paused = false;
+        self.render_engine.set_window_visible(false);
+    }
+
+    /// Shows the window with `path`, or with the current file again (from its saved
+    /// progress, in a new session)
+    pub fn open_from_daemon(&mut self, path: Option<String>) -> Result<()> {
+        let path = match path {
+            Some(path) => Some(path),
+            None if !self.is_generated_text() => Some(self.current_file_path.clone()),
+            None => None,
+        };
+        if let Some(path) = path {
+            self.load_file(path)?;
+        }
+        self.render_engine.set_window_visible(true);
+        self.update_text();
+        Ok(())
+    }
+
+    /// The daemon's answer to `daemon status`
+    pub fn daemon_status(&self) -> String {
+        let uptime = self
+            .daemon
+            .as_ref()
+            .map_or(0, |daemon| daemon.uptime().as_secs());
+        format!(
+            "running (pid {}, up {}), window {}, file {}",
+            std::process::id(),
+            session_state::format_clock(uptime as f64),
+            if self.render_engine.is_window_visible() {
+                "shown"
             } else {
-                log::info!("File changed, starting from beginning");
+                "hidden"
+            },
+            self.current_file_path
+        )
+    }
+
+    /// Copies the finished session's result card to the clipboard
+    pub fn copy_result_card(&mut self) {
+        let Some(stats) = self.session_state.last_stats() else {
+            return;
+        };
+        let card = result_card::render(
+            stats,
+            &self.locale,
+            env!("CARGO_PKG_VERSION"),
+            self.config.accessibility.ascii_ui,
+        );
+        let message = match result_card::copy(&mut self.clipboard, &card) {
+            Ok(()) => "Result card copied to the clipboard".to_string(),
+            Err(e) => {
+                log::error!("Failed to copy the result card: {}", e);
+                format!("Couldn't copy the result card: {}", e)
             }
+        };
+        self.toast = Some((message, Instant::now()));
+    }
+
+    /// Ends sessions on `end_condition` from now on and saves it to `config.toml`
+    ///
+    /// A running session starts over, so its statistics don't mix two conditions.
+    pub fn set_end_condition(&mut self, end_condition: session_state::EndCondition) {
+        self.config.gameplay.end_condition = end_condition;
+        self.session_state.set_end_condition(end_condition);
+        if self.session_state.is_active() || self.session_state.is_paused() {
+            self.session_state.start_new_session(
+                self.code_state.get_cursor_position(),
+                self.session_file_label(),
+            );
         }
 
-        let current_pos = self.code_state.get_cursor_position();
-        self.session_state.start_new_session(current_pos, file_path);
+        let message = format!("Sessions end after {}", end_condition);
+        info!("🏁 {}", message);
+        self.toast = Some((message, Instant::now()));
+        let config_path = crate::profile::config_path();
+        if let Err(e) = self
+            .config
+            .save_setting(&config_path, "gameplay.end_condition")
+        {
+            log::error!("Failed to save end condition: {}", e);
+        }
+    }
+
+    /// Strict mode is on, from the config or the rules of the running challenge:
+    /// backspace is off and the first mistake ends the session
+    pub fn strict_rules(&self) -> bool {
+        self.config.gameplay.strict_mode
+            || self
+                .active_challenge
+                .as_ref()
+                .is_some_and(|challenge| challenge.rules == challenge::Rules::Strict)
+    }
+
+    /// True while practicing a quote, drill, challenge, review or the tutorial instead of a file
+    pub fn is_generated_text(&self) -> bool {
+        self.active_quote.is_some()
+            || self.active_drill.is_some()
+            || self.active_challenge.is_some()
+            || self.active_review.is_some()
+            || self.current_file_path == tutorial::TUTORIAL_SOURCE
+    }
+
+    /// Text of the lines mistyped in the finished session, to type again in a review round
+    pub fn reviewable_lines(&self) -> Vec<String> {
+        // Error lines of a split session point into two files
+        if self.split.is_some() {
+            return Vec::new();
+        }
+        self.session_state
+            .last_stats()
+            .map(|stats| review::mistyped_lines(&self.code_state, &stats.error_lines))
+            .unwrap_or_default()
+    }
+
+    /// Replaces the current text with the lines mistyped in the finished session, or
+    /// in the last review round; returns false if there are none
+    pub fn start_review(&mut self) -> bool {
+        let Some(timestamp) = self.session_state.last_stats().map(|stats| stats.timestamp) else {
+            return false;
+        };
+        let lines = self.reviewable_lines();
+        let review = match &self.active_review {
+            Some(current) => current.next_round(lines),
+            None => review::Review::new(lines, timestamp, self.config.gameplay.review_repetitions),
+        };
+        let Some(review) = review else {
+            return false;
+        };
+
+        self.save_progress();
+        log::info!(
+            "🔁 Review round {}: {} mistyped lines, {} times each",
+            review.round,
+            review.lines.len(),
+            review.repetitions
+        );
+        self.load_generated_text(review.text(), review::REVIEW_SOURCE, None);
+        self.session_state.set_review_of(Some(review.review_of));
+        self.active_review = Some(review);
+        true
+    }
+
+    fn leave_review(&mut self) {
+        self.active_review = None;
+        self.session_state.set_review_of(None);
+    }
+
+    /// The tutorial is shown on the first start of a profile that has never practiced
+    pub fn tutorial_due(&self) -> bool {
+        self.session_history.count() == 0
+            && !tutorial::is_completed(&crate::profile::data_file(tutorial::TUTORIAL_FILE))
+    }
+
+    /// Shows what changed when this version hasn't run in the profile before. New
+    /// users get the tutorial instead, so for them the version is only recorded.
+    pub fn offer_whats_new(&mut self) {
+        let path = crate::profile::data_file(changelog::WHATS_NEW_FILE);
+        let current = env!("CARGO_PKG_VERSION");
+        let last_seen = changelog::last_seen(&path);
+        if last_seen.as_deref() == Some(current) {
+            return;
+        }
+        let new_user = last_seen.is_none() && self.session_history.count() == 0;
+        if !new_user {
+            let entries =
+                changelog::entries_since(&changelog::releases(), last_seen.as_deref(), current);
+            if !entries.is_empty() {
+                log::info!("✨ {} new in version {}", entries.len(), current);
+                self.whats_new = Some(changelog::WhatsNew::new(entries));
+            }
+        }
+        if let Err(e) = changelog::mark_seen(&path, current) {
+            log::error!("Failed to record the version: {}", e);
+        }
+    }
+
+    /// Shows what is new in this version, whether or not it was shown before
+    pub fn open_whats_new(&mut self) {
+        let entries =
+            changelog::entries_since(&changelog::releases(), None, env!("CARGO_PKG_VERSION"));
+        if !entries.is_empty() {
+            self.whats_new = Some(changelog::WhatsNew::new(entries));
+        }
+    }
+
+    /// Replaces the current text with the tutorial's and starts at its first step
+    pub fn start_tutorial(&mut self) {
+        self.save_progress();
+        log::info!("🎓 Starting the tutorial");
+        self.load_generated_text(tutorial::TEXT.to_string(), tutorial::TUTORIAL_SOURCE, None);
+        self.tutorial = Some(tutorial::Tutorial::new());
+    }
+
+    /// Moves the tutorial on if `action` is what its current step waits for
+    pub fn advance_tutorial(&mut self, action: tutorial::Action) {
+        let Some(tutorial) = &mut self.tutorial else {
+            return;
+        };
+        if !tutorial.advance(action) {
+            return;
+        }
+        // The instructions over the text change
+        self.caret_only_change = false;
+        if tutorial.is_done() {
+            self.end_tutorial(false);
+            let message =
+                "Tutorial complete! Keep typing, or press Ctrl+P to open a file of your own";
+            self.toast = Some((message.to_string(), Instant::now()));
+        } else {
+            log::info!("🎓 Tutorial step {}", tutorial.number());
+        }
+    }
+
+    /// Closes the tutorial and records it in the profile, so it isn't shown again
+    pub fn end_tutorial(&mut self, skipped: bool) {
+        if self.tutorial.take().is_none() {
+            return;
+        }
+        log::info!(
+            "🎓 Tutorial {}",
+            if skipped { "skipped" } else { "completed" }
+        );
+        let path = crate::profile::data_file(tutorial::TUTORIAL_FILE);
+        if let Err(e) = tutorial::mark_completed(&path, skipped) {
+            log::error!("Failed to record the tutorial: {}", e);
+        }
+    }
+
+    fn load_generated_text(&mut self, text: String, source: &str, seed: Option<u64>) {
+        self.split = None;
+        self.trace = None;
+        self.tutorial = None;
+        self.active_quote = None;
+        self.active_drill = None;
+        self.leave_review();
+        self.leave_challenge();
+        self.clear_line_range();
+        self.current_file_path = source.to_string();
+        self.current_file_hash = progress_storage::compute_hash(&text);
+        self.current_file_encoding = "UTF-8".to_string();
+        self.license_header = None;
+        self.code_state = code_state::CodeState::new(text);
+        self.scroll_offset = 0;
 
+        self.session_state.set_source(source);
+        self.session_state.set_seed(seed);
+        self.session_state.start_new_session(0, source.to_string());
+    }
+
+    /// Starts the next work period once a pomodoro break is over
+    pub fn resume_after_break(&mut self) {
+        let position = self.code_state.get_cursor_position();
+        self.session_state
+            .start_new_session(position, self.session_file_label());
+        self.start_session();
+    }
+
+    /// Restricts sessions to `range` and moves the cursor to its first line
+    pub fn start_line_range(&mut self, range: session_state::LineRange) -> Result<()> {
+        let Some(start) = self.code_state.line_start_position(range.start) else {
+            anyhow::bail!("Line {} is not in this file", range.start);
+        };
+        self.code_state.set_cursor_position(start);
+        // Put the first line of the range at the top of the view
+        self.scroll_offset = (range.start - 1).saturating_sub(self.code_state.first_line_number());
+
+        self.line_range = Some(range);
+        self.session_state.set_line_range(Some(range));
+        self.session_state
+            .start_new_session(start, self.session_file_label());
+        log::info!("Practicing lines {}", range);
+        Ok(())
+    }
+
+    /// Scrolls the view so 1-based `line` is at the top; the cursor stays where it is
+    pub fn go_to_line(&mut self, line: usize) -> Result<()> {
+        let first_line = self.code_state.first_line_number();
+        let loaded_lines = self.code_state.get_line_break_count() + 1;
+        if line == 0 || line > first_line + loaded_lines {
+            anyhow::bail!("Line {} is not in this file", line);
+        }
+        if line <= first_line {
+            anyhow::bail!("Line {} has been typed and is no longer loaded", line);
+        }
+        self.scroll_offset = line - 1 - first_line;
+        // With scroll lock on, stay there for a peek instead of snapping right back
+        self.start_scroll_peek();
+        log::info!("↪ Went to line {}", line);
         Ok(())
     }
 
+    /// Starts downloading what the GitHub prompt names
+    pub fn start_github_fetch(&mut self) {
+        match code_source::RepoFile::parse(&self.github_input) {
+            Ok(file) => {
+                log::info!("🐙 Fetching {} from GitHub", file);
+                let receiver = code_source::fetch(
+                    file.clone(),
+                    self.config.github.clone(),
+                    &self.config.gameplay.ignore_patterns,
+                    self.next_seed(),
+                );
+                self.github_error = None;
+                self.github_fetch = Some((file, receiver));
+            }
+            Err(e) => self.github_error = Some(e.to_string()),
+        }
+    }
+
+    /// Opens the file from GitHub once it is downloaded.
+    /// Returns true when the download finished, either way.
+    pub fn poll_github_fetch(&mut self) -> bool {
+        let Some((file, receiver)) = &self.github_fetch else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => Err(anyhow::anyhow!("the download stopped")),
+        };
+        let file = file.clone();
+        self.github_fetch = None;
+        let loaded = result.and_then(|fetched| {
+            self.load_file(fetched.local.to_string_lossy().into_owned())?;
+            Ok(fetched.file)
+        });
+        match loaded {
+            Ok(fetched) => {
+                log::info!("🐙 Opened {} from GitHub", fetched);
+                self.github_mode = false;
+                self.toast = Some((format!("From GitHub: {}", fetched), Instant::now()));
+                self.show_file_analysis();
+            }
+            Err(e) => {
+                log::warn!("Failed to fetch {} from GitHub: {:#}", file, e);
+                self.github_error = Some(format!("{:#}", e));
+            }
+        }
+        true
+    }
+
+    /// Moves to where the typed part of a changed file ended up
+    /// Opens the analysis panel for the current file, unless it is turned off or
+    /// the file is streamed (not all of it is in memory)
+    pub fn show_file_analysis(&mut self) {
+        if !self.config.gameplay.show_file_analysis || self.code_state.is_streaming() {
+            return;
+        }
+        let key_stats = self
+            .session_history
+            .get_key_stats(session_history::HistoryRange::All);
+        self.file_analysis = Some(file_analysis::FileAnalysis::analyze(
+            &self.code_state.get_full_code().to_string(),
+            &key_stats,
+        ));
+    }
+
+    /// What the file picker lists for the typed path
+    pub fn file_listing(&self) -> file_browser::Listing {
+        let input = paths::expand(
+            &self.file_input_buffer,
+            self.config.gameplay.workspace_root.as_deref(),
+        )
+        .unwrap_or_else(|_| self.file_input_buffer.clone());
+        file_browser::list(
+            &input,
+            &self
+                .progress_storage
+                .recent_files(file_browser::RECENT_FILES),
+            &IgnoreList::new(&self.config.gameplay.ignore_patterns),
+            self.file_hand_filter,
+            self.file_browser.extension.as_deref(),
+        )
+    }
+
+    /// Turns on `strip_untypeable_lines`, saves it and opens the current file again without them
+    pub fn strip_untypeable_lines(&mut self) {
+        let removed = self
+            .file_analysis
+            .as_ref()
+            .map_or(0, |analysis| analysis.untypeable_lines);
+        self.config.gameplay.strip_untypeable_lines = true;
+        let config_path = crate::profile::config_path();
+        if let Err(e) = self
+            .config
+            .save_setting(&config_path, "gameplay.strip_untypeable_lines")
+        {
+            log::error!("Failed to save strip_untypeable_lines: {}", e);
+        }
+        if let Err(e) = self.load_file(self.current_file_path.clone()) {
+            log::error!("Failed to reload {}: {}", self.current_file_path, e);
+            self.file_analysis = None;
+            return;
+        }
+        self.show_file_analysis();
+        self.toast = Some((
+            format!(
+                "✂ Left out {} lines with untypeable characters (strip_untypeable_lines = true)",
+                removed
+            ),
+            Instant::now(),
+        ));
+    }
+
+    pub fn accept_resume_offer(&mut self) {
+        let Some(offer) = self.resume_offer.take() else {
+            return;
+        };
+        self.code_state.set_cursor_position(offer.position);
+        self.scroll_offset = (offer.line - 1).saturating_sub(self.code_state.first_line_number());
+        self.session_state
+            .start_new_session(offer.position, self.session_file_label());
+        log::info!("↪ Resuming at line {}", offer.line);
+    }
+
+    pub fn clear_line_range(&mut self) {
+        self.line_range = None;
+        self.session_state.set_line_range(None);
+    }
+
+    /// Positions where the active line range starts and ends
+    pub fn line_range_bounds(&self) -> Option<(usize, usize)> {
+        let range = self.line_range?;
+        let start = self.code_state.line_start_position(range.start)?;
+        let end = self
+            .code_state
+            .line_start_position(range.end + 1)
+            .unwrap_or_else(|| self.code_state.get_total_length());
+        Some((start, end.max(start)))
+    }
+
+    /// Leaves quote or drill mode and returns to the last opened file
+    pub fn return_to_file(&mut self) {
+        let file_path = self
+            .progress_storage
+            .get_last_opened_file()
+            .cloned()
+            .unwrap_or_else(|| DEMO_CODE_PATH.to_string());
+
+        if self.load_file(file_path).is_err() {
+            // Never leave the user stuck in quote or drill mode
+            let _ = self.load_file(DEMO_CODE_PATH.to_string());
+        }
+    }
+
     pub fn update_frame_time(&mut self) {
         let now = Instant::now();
         self.frame_times.push_back(now);
+        let frame_ms = now.duration_since(self.last_frame_time).as_secs_f64() * 1000.0;
+        self.frame_time_history.record(frame_ms);
+        self.perf_stats.frame_time.record(frame_ms);
+        if self
+            .stutter_monitor
+            .as_mut()
+            .is_some_and(|monitor| monitor.should_capture(frame_ms, now))
+        {
+            self.capture_stutter(frame_ms);
+        }
 
         if self.frame_times.len() > 60 {
             self.frame_times.pop_front();
@@ -337,4 +2073,409 @@ impl CargoTapApp {
 
         self.last_frame_time = now;
     }
+
+    /// Reads the refresh rate of the window's monitor again, after the window was
+    /// created or moved
+    pub fn detect_refresh_rate(&mut self) {
+        let hz = self.render_engine.monitor_refresh_hz();
+        if !self.refresh.set_detected(hz) {
+            return;
+        }
+        match self.refresh.detected_hz() {
+            Some(hz) => log::info!(
+                "🖥 Monitor refreshes at {:.0} Hz, HUD updates every {:.0} ms",
+                hz,
+                self.refresh.hud_interval().as_secs_f64() * 1000.0
+            ),
+            None => log::info!(
+                "🖥 Monitor refresh rate unknown, assuming {:.0} Hz",
+                self.refresh.rate_hz()
+            ),
+        }
+    }
+
+    /// Logs a diagnostic snapshot of a slow frame and appends it to `STUTTER_FILE`
+    fn capture_stutter(&self, frame_ms: f64) {
+        let Some(monitor) = &self.stutter_monitor else {
+            return;
+        };
+        let render = self
+            .text_system
+            .as_ref()
+            .map(|text_system| text_system.render_stats());
+        let lines = match self.code_state.get_total_lines() {
+            Some(lines) => lines.to_string(),
+            None => "streamed".to_string(),
+        };
+        let context = [
+            format!(
+                "File: {} ({} lines, cursor at line {})",
+                self.current_file_path,
+                lines,
+                self.code_state.get_cursor_line()
+            ),
+            format!(
+                "Session active: {}, statistics shown: {}, split: {}",
+                self.session_state.is_active(),
+                self.show_statistics,
+                self.split.is_some()
+            ),
+            format!(
+                "Layout: {} (last text update {:.3}ms, UI generation {:.3}ms)",
+                self.text_budget.level().label(),
+                self.text_update_time_ms,
+                self.ui_generation_time_ms
+            ),
+        ];
+        let report = monitor.report(
+            frame_ms,
+            &self.frame_time_history,
+            &self.perf_stats,
+            render,
+            &context,
+        );
+        log::warn!(
+            event = "stutter",
+            file = self.current_file_path.as_str(),
+            duration_ms = frame_ms;
+            "{}",
+            report
+        );
+
+        let path = crate::profile::data_file(profiling::STUTTER_FILE);
+        match profiling::append_report(&report, &path) {
+            Ok(()) => log::info!("Stutter snapshot saved to {}", path.display()),
+            Err(e) => log::error!("Failed to save stutter snapshot: {}", e),
+        }
+    }
+
+    /// What the app is doing right now, for bug reports, overlays and tests
+    pub fn snapshot(&self) -> state_snapshot::StateSnapshot {
+        let position = self.code_state.get_cursor_position();
+        let stats = self.session_state.current_stats(position);
+        state_snapshot::StateSnapshot {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: stats.timestamp,
+            profile: crate::profile::active().map(str::to_string),
+            screen: self.screen_name().to_string(),
+            file: state_snapshot::FileState {
+                path: self.current_file_path.clone(),
+                hash: self.current_file_hash.clone(),
+                encoding: self.current_file_encoding.clone(),
+                position,
+                length: self.code_state.get_total_length(),
+                line: self.code_state.get_cursor_line(),
+                column: self.code_state.get_cursor_column(),
+                total_lines: self.code_state.get_total_lines(),
+                progress: self.code_state.get_progress(),
+            },
+            session: state_snapshot::SessionSnapshot {
+                status: self.session_state.status().clone(),
+                source: self.session_state.source().to_string(),
+                chars_typed: stats.chars_typed,
+                errors: stats.errors,
+                elapsed_secs: stats.time_elapsed_secs,
+                words_per_minute: stats.words_per_minute,
+                accuracy: stats.accuracy,
+            },
+            config: state_snapshot::ConfigDigest::of(&self.config),
+        }
+    }
+
+    /// The screen or overlay in front, in the order the UI checks them
+    fn screen_name(&self) -> &'static str {
+        if self.practice_lock_mode {
+            "practice_lock"
+        } else if self.exit_confirm_mode {
+            "exit_confirm"
+        } else if self.resume_offer.is_some() {
+            "resume_offer"
+        } else if self.whats_new.is_some() {
+            "whats_new"
+        } else if self.file_analysis.is_some() {
+            "file_analysis"
+        } else if self.note_edit_path.is_some() {
+            "note"
+        } else if self.file_selection_mode {
+            "file_selection"
+        } else if self.settings_mode {
+            "settings"
+        } else if self.drill_menu_mode {
+            "drill_menu"
+        } else if self.end_condition_menu.is_some() {
+            "session_setup"
+        } else if self.dogfood_menu_mode {
+            "dogfood_menu"
+        } else if self.line_range_mode {
+            "line_range"
+        } else if self.goto_line_mode {
+            "goto_line"
+        } else if self.github_mode {
+            "github"
+        } else if self.show_statistics {
+            "statistics"
+        } else if self.pomodoro.as_ref().is_some_and(|p| p.is_on_break()) {
+            "break"
+        } else if self.session_state.is_paused() {
+            "paused"
+        } else if self.home_row_pending() {
+            "home_row_check"
+        } else {
+            "typing"
+        }
+    }
+
+    /// Writes `snapshot()` to `state_snapshot::STATE_FILE` and returns where
+    pub fn write_state(&mut self) -> Result<std::path::PathBuf> {
+        self.last_state_dump = Instant::now();
+        let path = crate::profile::data_file(state_snapshot::STATE_FILE);
+        state_snapshot::save(&self.snapshot(), &path)?;
+        Ok(path)
+    }
+
+    /// Writes the app state on request and says where it went
+    pub fn dump_state(&mut self) {
+        let message = match self.write_state() {
+            Ok(path) => {
+                log::info!("📋 App state saved to {}", path.display());
+                format!("📋 App state saved to {}", path.display())
+            }
+            Err(e) => {
+                log::error!("Failed to save app state: {}", e);
+                format!("Couldn't save the app state: {}", e)
+            }
+        };
+        self.toast = Some((message, Instant::now()));
+    }
+}
+
+/// Path under which the bundled demo code is tracked
+pub const DEMO_CODE_PATH: &str = "demo_code.rs";
+
+/// Practice code ready to type, positioned at its saved progress
+struct LoadedCode {
+    code_state: code_state::CodeState,
+    hash: String,
+    encoding: String,
+    scroll_offset: usize,
+    license_header: Option<license_header::Header>,
+    /// Where the typed part of a changed file ended up, for the user to accept
+    resume_offer: Option<line_diff::ResumeOffer>,
+    /// Likely secrets found in the file, already masked or left out as configured
+    secrets: Vec<secrets::Finding>,
+}
+
+impl LoadedCode {
+    fn demo() -> Self {
+        let code = include_str!("demo_code.rs").to_string();
+        Self {
+            hash: progress_storage::compute_hash(&code),
+            code_state: code_state::CodeState::new(code),
+            encoding: "UTF-8".to_string(),
+            scroll_offset: 0,
+            license_header: None,
+            resume_offer: None,
+            secrets: Vec::new(),
+        }
+    }
+}
+
+/// Loads practice code and restores saved progress for it
+///
+/// True if `file_path` is opened for the first time and `text.fit_font_size`
+/// asks for the zoom that fits it
+fn wants_font_fit(
+    config: &config::Config,
+    progress_storage: &progress_storage::ProgressStorage,
+    file_path: &str,
+) -> bool {
+    config.text.fit_font_size != config::FontFit::Off
+        && progress_storage.get_progress(file_path).is_none()
+}
+
+/// Files of at least `streaming_threshold_bytes` (when non-zero) are streamed
+/// instead of read whole, so only a window around the cursor is in memory.
+/// Other files from disk are scanned for secrets, handled as `secrets_mode` says,
+/// and lose their lines with untypeable characters if `strip_untypeable` is set.
+fn load_code(
+    file_path: &str,
+    streaming_threshold_bytes: u64,
+    skip_license_headers: bool,
+    secrets_mode: secrets::Mode,
+    strip_untypeable: bool,
+    progress_storage: &progress_storage::ProgressStorage,
+) -> Result<LoadedCode> {
+    let started = Instant::now();
+    let language = language::for_path(file_path);
+    let file_size = std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+    if streaming_threshold_bytes > 0
+        && file_size >= streaming_threshold_bytes
+        && dogfood::read(file_path).is_none()
+        && samples::read(file_path).is_none()
+    {
+        // Streamed text is read as (lossy) UTF-8 line by line
+        let hash = progress_storage::compute_stream_hash(file_path)?;
+        let encoding = "UTF-8".to_string();
+        let start_position = progress_storage
+            .get_progress(file_path)
+            .filter(|progress| progress.matches(&hash, &encoding))
+            .map_or(0, |progress| progress.position);
+
+        log::info!(
+            event = "file_streamed",
+            file = file_path,
+            position = start_position,
+            duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+            "Streaming {} ({:.1} MB) from position {}",
+            file_path,
+            file_size as f64 / (1024.0 * 1024.0),
+            start_position
+        );
+        let mut code_state =
+            code_state::CodeState::open_streaming(Path::new(file_path), start_position)?;
+        code_state.set_language(language);
+        return Ok(LoadedCode {
+            code_state,
+            hash,
+            encoding,
+            scroll_offset: 0,
+            license_header: None,
+            resume_offer: None,
+            secrets: Vec::new(),
+        });
+    }
+
+    let mut secrets = Vec::new();
+    let decoded = if file_path == DEMO_CODE_PATH {
+        encoding::DecodedText::utf8(include_str!("demo_code.rs").to_string())
+    } else if let Some(code) = dogfood::read(file_path) {
+        encoding::DecodedText::utf8(code.to_string())
+    } else if let Some(code) = samples::read(file_path) {
+        encoding::DecodedText::utf8(code.to_string())
+    } else {
+        let mut decoded = encoding::read_file(file_path)?;
+        if let Some(reason) = code_state::blank_reason(&decoded.text) {
+            bail!("{} {}, there is nothing to type", file_path, reason);
+        }
+        let (text, findings) = secrets::apply(decoded.text, secrets_mode);
+        decoded.text = text;
+        if let Some(notice) = secrets::notice(&findings, secrets_mode, file_path) {
+            log::warn!("{}", notice);
+        }
+        secrets = findings;
+        if strip_untypeable {
+            let (text, removed) = file_analysis::strip_untypeable_lines(&decoded.text);
+            if removed > 0 {
+                log::info!("Left out {} lines with untypeable characters", removed);
+                decoded.text = text;
+            }
+        }
+        if code_state::blank_reason(&decoded.text).is_some() {
+            bail!(
+                "Nothing is left to type in {} once secrets and untypeable lines are left out",
+                file_path
+            );
+        }
+        decoded
+    };
+    log::info!(
+        event = "file_loaded",
+        file = file_path,
+        encoding = decoded.encoding,
+        language = language.name,
+        duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        "Successfully loaded file: {} ({})",
+        file_path,
+        decoded.encoding
+    );
+    if decoded.had_crlf {
+        log::info!("Converted CRLF line endings in {}", file_path);
+    }
+
+    let hash = progress_storage::compute_hash(&decoded.text);
+    let mut resume_offer = None;
+    let restore = match progress_storage.get_progress(file_path) {
+        Some(progress) if progress.matches(&hash, decoded.encoding) => {
+            log::info!(
+                "Restoring progress at position {} with scroll offset {}",
+                progress.position,
+                progress.scroll_offset
+            );
+            Some((progress.position, Some(progress.scroll_offset)))
+        }
+        Some(progress) => match progress.remap(&decoded.text, decoded.encoding) {
+            Some(position) => {
+                log::info!(
+                    "File was only reformatted, restoring progress at position {}",
+                    position
+                );
+                Some((position, None))
+            }
+            None => match progress
+                .line_anchor
+                .as_ref()
+                .filter(|_| progress.encoding == decoded.encoding)
+                .and_then(|anchor| line_diff::resume_offer(anchor, &decoded.text))
+            {
+                Some(offer) if offer.typed_part_unchanged() => {
+                    log::info!(
+                        "File changed after the saved position, restoring progress at position {}",
+                        offer.position
+                    );
+                    Some((offer.position, None))
+                }
+                Some(offer) => {
+                    log::info!("File changed, offering to resume at line {}", offer.line);
+                    resume_offer = Some(offer);
+                    None
+                }
+                None => {
+                    log::info!("File changed, starting from beginning");
+                    None
+                }
+            },
+        },
+        None => None,
+    };
+
+    let mut license_header = None;
+    if restore.is_some() {
+        // Keep what was decided when the file was first opened
+        license_header = progress_storage
+            .get_progress(file_path)
+            .and_then(|progress| progress.skipped_header);
+    } else if skip_license_headers {
+        license_header = license_header::detect(&decoded.text, language);
+    }
+
+    let mut code_state = code_state::CodeState::new(decoded.text);
+    code_state.set_language(language);
+    let mut scroll_offset = 0;
+    if restore.is_none()
+        && let Some(header) = license_header
+    {
+        log::info!("Skipping {} line license header", header.lines);
+        code_state.set_cursor_position(header.end);
+        scroll_offset = header.lines;
+    }
+    if let Some((position, saved_scroll_offset)) = restore {
+        while code_state.get_cursor_position() < position {
+            if code_state.type_character().is_none() {
+                break;
+            }
+        }
+        // Reformatting shifts lines, so show the cursor line at the top instead
+        scroll_offset =
+            saved_scroll_offset.unwrap_or_else(|| code_state.get_cursor_line().saturating_sub(1));
+    }
+
+    Ok(LoadedCode {
+        code_state,
+        hash,
+        encoding: decoded.encoding.to_string(),
+        scroll_offset,
+        license_header,
+        resume_offer,
+        secrets,
+    })
 }
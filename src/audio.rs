@@ -0,0 +1,257 @@
+//! Sound feedback
+//!
+//! With `audio_feedback = true` under `[gameplay]`, a correct key clicks, a wrong
+//! key buzzes and a finished session chimes. `accuracy_alarm_bell` sounds an alarm
+//! when accuracy drops below the floor and an audible metronome ticks on every
+//! beat, whether or not the other sounds are on. The sounds are synthesized at
+//! start-up; `[audio] sound_pack` names a directory whose `key.wav`, `error.wav`,
+//! `finish.wav`, `alarm.wav` and `beat.wav` replace them one by one. Without an
+//! output device CargoTap carries on silently.
+
+use std::f32::consts::TAU;
+use std::path::Path;
+
+use log::{info, warn};
+use rodio::{OutputStream, OutputStreamBuilder, Source, buffer::SamplesBuffer};
+
+use crate::config::AudioConfig;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// Something that makes a sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    /// A correct key
+    Key,
+    /// A wrong key
+    Error,
+    /// The end of a session
+    Finish,
+    /// Accuracy dropped below the floor
+    Alarm,
+    /// A metronome beat
+    Beat,
+}
+
+impl Cue {
+    /// File of a sound pack that replaces the built-in sound
+    fn file_name(self) -> &'static str {
+        match self {
+            Cue::Key => "key.wav",
+            Cue::Error => "error.wav",
+            Cue::Finish => "finish.wav",
+            Cue::Alarm => "alarm.wav",
+            Cue::Beat => "beat.wav",
+        }
+    }
+}
+
+/// Decoded samples, kept so playing a sound doesn't decode it again on every key
+#[derive(Debug, Clone, PartialEq)]
+struct Sound {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<f32>,
+}
+
+impl Sound {
+    fn mono(samples: Vec<f32>) -> Self {
+        Self {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            samples,
+        }
+    }
+
+    fn builtin(cue: Cue) -> Self {
+        match cue {
+            Cue::Key => click(),
+            Cue::Error => buzz(),
+            Cue::Finish => chime(),
+            Cue::Alarm => alarm(),
+            Cue::Beat => tick(),
+        }
+    }
+
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let decoder = rodio::Decoder::try_from(std::fs::File::open(path)?)?;
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let samples: Vec<f32> = decoder.collect();
+        if samples.is_empty() || channels == 0 {
+            anyhow::bail!("no samples");
+        }
+        Ok(Self {
+            channels,
+            sample_rate,
+            samples,
+        })
+    }
+
+    /// The pack's sound for `cue`, or the built-in one when there is none
+    fn for_cue(cue: Cue, pack: Option<&Path>) -> Self {
+        let Some(pack) = pack else {
+            return Self::builtin(cue);
+        };
+        let path = pack.join(cue.file_name());
+        if !path.exists() {
+            return Self::builtin(cue);
+        }
+        match Self::load(&path) {
+            Ok(sound) => sound,
+            Err(e) => {
+                warn!(
+                    "Couldn't read {}, using the built-in sound: {e}",
+                    path.display()
+                );
+                Self::builtin(cue)
+            }
+        }
+    }
+}
+
+/// `secs` of a tone at `hz` that dies away at `decay` per second
+fn tone(hz: f32, secs: f32, decay: f32, wave: impl Fn(f32) -> f32) -> Vec<f32> {
+    let len = (secs * SAMPLE_RATE as f32) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            wave((hz * t).fract()) * (-decay * t).exp()
+        })
+        .collect()
+}
+
+fn sine(phase: f32) -> f32 {
+    (phase * TAU).sin()
+}
+
+fn square(phase: f32) -> f32 {
+    if phase < 0.5 { 1.0 } else { -1.0 }
+}
+
+/// A short high tick, quiet enough to hear on every key
+fn click() -> Sound {
+    Sound::mono(tone(1800.0, 0.03, 160.0, sine))
+}
+
+/// A low, rough buzz that doesn't sound like a key
+fn buzz() -> Sound {
+    let samples = tone(220.0, 0.12, 25.0, square);
+    Sound::mono(samples.into_iter().map(|s| s * 0.4).collect())
+}
+
+/// A rising C major arpeggio, the notes ringing into each other
+fn chime() -> Sound {
+    let notes = [523.25, 659.25, 783.99];
+    let step = (0.09 * SAMPLE_RATE as f32) as usize;
+    let mut samples = vec![0.0; step * (notes.len() - 1) + (0.4 * SAMPLE_RATE as f32) as usize];
+    for (i, hz) in notes.into_iter().enumerate() {
+        for (out, s) in samples[i * step..].iter_mut().zip(tone(hz, 0.4, 8.0, sine)) {
+            *out += s / notes.len() as f32;
+        }
+    }
+    Sound::mono(samples)
+}
+
+/// Two falling beeps, loud enough to notice mid-word
+fn alarm() -> Sound {
+    let mut samples = tone(880.0, 0.12, 12.0, square);
+    samples.extend(tone(660.0, 0.2, 10.0, square));
+    Sound::mono(samples.into_iter().map(|s| s * 0.3).collect())
+}
+
+/// A lower, fuller tick than the key click, so the beat and the keys can be told apart
+fn tick() -> Sound {
+    Sound::mono(tone(1000.0, 0.05, 80.0, sine))
+}
+
+pub struct Audio {
+    stream: OutputStream,
+    key: Sound,
+    error: Sound,
+    finish: Sound,
+    alarm: Sound,
+    beat: Sound,
+    volume: f32,
+}
+
+impl Audio {
+    /// Opens the default output device, None (after a warning) when there is none
+    pub fn new(config: &AudioConfig) -> Option<Self> {
+        let mut stream = match OutputStreamBuilder::open_default_stream() {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("No audio output, sound feedback is off: {e}");
+                return None;
+            }
+        };
+        // rodio prints to stderr when the stream is dropped otherwise
+        stream.log_on_drop(false);
+
+        let pack = config.sound_pack.as_deref().map(Path::new);
+        if let Some(pack) = pack {
+            info!("🔊 Sound pack: {}", pack.display());
+        }
+        Some(Self {
+            stream,
+            key: Sound::for_cue(Cue::Key, pack),
+            error: Sound::for_cue(Cue::Error, pack),
+            finish: Sound::for_cue(Cue::Finish, pack),
+            alarm: Sound::for_cue(Cue::Alarm, pack),
+            beat: Sound::for_cue(Cue::Beat, pack),
+            volume: config.volume.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Plays the sound of `cue` over whatever is still playing
+    pub fn play(&self, cue: Cue) {
+        if self.volume <= 0.0 {
+            return;
+        }
+        let sound = match cue {
+            Cue::Key => &self.key,
+            Cue::Error => &self.error,
+            Cue::Finish => &self.finish,
+            Cue::Alarm => &self.alarm,
+            Cue::Beat => &self.beat,
+        };
+        let source = SamplesBuffer::new(sound.channels, sound.sample_rate, sound.samples.clone());
+        self.stream.mixer().add(source.amplify(self.volume));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_sounds() {
+        for cue in [Cue::Key, Cue::Error, Cue::Finish, Cue::Alarm, Cue::Beat] {
+            let sound = Sound::builtin(cue);
+            assert!(!sound.samples.is_empty(), "{cue:?}");
+            assert!(sound.samples.iter().all(|s| s.abs() <= 1.0), "{cue:?}");
+        }
+        // All of them are mono at the same rate, so length is duration
+        let click = Sound::builtin(Cue::Key).samples.len();
+        assert!(click < Sound::builtin(Cue::Error).samples.len());
+        assert!(click < Sound::builtin(Cue::Finish).samples.len());
+    }
+
+    #[test]
+    fn test_missing_pack_sounds_fall_back() {
+        let dir = std::env::temp_dir().join(format!("cargotap_sounds_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        std::fs::write(dir.join("error.wav"), b"not a wav").expect("write");
+
+        // No key.wav at all, and an error.wav that doesn't decode
+        assert_eq!(
+            Sound::for_cue(Cue::Key, Some(&dir)),
+            Sound::builtin(Cue::Key)
+        );
+        assert_eq!(
+            Sound::for_cue(Cue::Error, Some(&dir)),
+            Sound::builtin(Cue::Error)
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,172 @@
+//! Calendar heatmap of practice time
+//!
+//! `cargo run export --heatmap year.png [--year 2026] [history.json]` draws a
+//! GitHub-style grid with one square per day, a column per week and darker greens
+//! for more minutes practiced. It is drawn on the CPU, so no GPU is needed.
+
+use anyhow::{Result, bail};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::session_history::SessionHistory;
+
+/// Side of a day's square and the gap between squares, in pixels
+const CELL: u32 = 11;
+const GAP: u32 = 3;
+const MARGIN: u32 = 10;
+
+/// No practice, then four levels relative to the busiest day (GitHub's palette)
+const LEVEL_COLORS: [[u8; 4]; 5] = [
+    [235, 237, 240, 255],
+    [155, 233, 168, 255],
+    [64, 196, 99, 255],
+    [48, 161, 78, 255],
+    [33, 110, 57, 255],
+];
+const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+
+/// Runs the `export` subcommand; `args` are the arguments after `export`
+pub fn run(args: &[String]) -> Result<()> {
+    const USAGE: &str = "usage: export --heatmap OUT.png [--year YYYY] [history.json]";
+    let mut output: Option<PathBuf> = None;
+    let mut year: Option<i32> = None;
+    let mut history_path: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--heatmap" => match args.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => bail!("--heatmap needs an output file; {}", USAGE),
+            },
+            "--year" => match args.next().and_then(|year| year.parse().ok()) {
+                Some(value) => year = Some(value),
+                None => bail!("--year needs a year like 2026; {}", USAGE),
+            },
+            flag if flag.starts_with("--") => bail!("unknown option {}; {}", flag, USAGE),
+            path if history_path.is_none() => history_path = Some(PathBuf::from(path)),
+            extra => bail!("unexpected argument {}; {}", extra, USAGE),
+        }
+    }
+    let Some(output) = output else {
+        bail!("nothing to export; {}", USAGE);
+    };
+
+    let mut history = match &history_path {
+        Some(path) if !path.exists() => bail!("history file {} not found", path.display()),
+        Some(path) => SessionHistory::new(path),
+        None => SessionHistory::default(),
+    };
+    history.load()?;
+
+    let (first, last) = match year {
+        Some(year) => match (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => bail!("year {} is out of range", year),
+        },
+        // The last 52 weeks up to today, like a profile page
+        None => {
+            let today = Local::now().date_naive();
+            (today - Duration::weeks(52) + Duration::days(1), today)
+        }
+    };
+
+    let minutes = history.minutes_by_day();
+    let practiced = minutes
+        .range(first..=last)
+        .filter(|(_, m)| **m > 0.0)
+        .count();
+    render(&minutes, first, last).save(&output)?;
+    println!(
+        "✓ Heatmap of {} to {} ({} days practiced) written to {}",
+        first,
+        last,
+        practiced,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Color level of a day: 0 for no practice, otherwise 1-4 by its share of the busiest day
+fn level(minutes: f64, busiest: f64) -> usize {
+    if minutes <= 0.0 || busiest <= 0.0 {
+        return 0;
+    }
+    ((minutes / busiest * 4.0).ceil() as usize).clamp(1, 4)
+}
+
+/// Draws the days from `first` to `last`; weeks start on Monday
+pub fn render(minutes: &BTreeMap<NaiveDate, f64>, first: NaiveDate, last: NaiveDate) -> RgbaImage {
+    let start = first - Duration::days(first.weekday().num_days_from_monday().into());
+    let weeks = ((last - start).num_days() / 7 + 1) as u32;
+    let pitch = CELL + GAP;
+    let width = 2 * MARGIN + weeks * pitch - GAP;
+    let height = 2 * MARGIN + 7 * pitch - GAP;
+    let mut image = RgbaImage::from_pixel(width, height, Rgba(BACKGROUND));
+
+    let busiest = minutes
+        .range(first..=last)
+        .map(|(_, m)| *m)
+        .fold(0.0, f64::max);
+    let mut day = first;
+    while day <= last {
+        let week = ((day - start).num_days() / 7) as u32;
+        let weekday = day.weekday().num_days_from_monday();
+        let color = LEVEL_COLORS[level(minutes.get(&day).copied().unwrap_or(0.0), busiest)];
+        let (x, y) = (MARGIN + week * pitch, MARGIN + weekday * pitch);
+        for dy in 0..CELL {
+            for dx in 0..CELL {
+                image.put_pixel(x + dx, y + dy, Rgba(color));
+            }
+        }
+        day += Duration::days(1);
+    }
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn cell_color(image: &RgbaImage, week: u32, weekday: u32) -> [u8; 4] {
+        let pitch = CELL + GAP;
+        image
+            .get_pixel(MARGIN + week * pitch + 1, MARGIN + weekday * pitch + 1)
+            .0
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(level(0.0, 30.0), 0);
+        assert_eq!(level(1.0, 30.0), 1);
+        assert_eq!(level(15.0, 30.0), 2);
+        assert_eq!(level(30.0, 30.0), 4);
+    }
+
+    #[test]
+    fn test_render_places_days_by_week_and_weekday() {
+        // 2026-01-01 is a Thursday
+        let (first, last) = (date(2026, 1, 1), date(2026, 12, 31));
+        let minutes = BTreeMap::from([(date(2026, 1, 1), 30.0), (date(2026, 1, 5), 5.0)]);
+        let image = render(&minutes, first, last);
+
+        let pitch = CELL + GAP;
+        assert_eq!(image.height(), 2 * MARGIN + 7 * pitch - GAP);
+        assert_eq!(image.width(), 2 * MARGIN + 53 * pitch - GAP);
+
+        assert_eq!(cell_color(&image, 0, 3), LEVEL_COLORS[4]);
+        assert_eq!(cell_color(&image, 1, 0), LEVEL_COLORS[1]);
+        assert_eq!(cell_color(&image, 1, 1), LEVEL_COLORS[0]);
+        // Days of the first week before the range stay blank
+        assert_eq!(cell_color(&image, 0, 0), BACKGROUND);
+    }
+}
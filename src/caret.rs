@@ -0,0 +1,151 @@
+//! The caret: its shape, blinking and slide to the next character
+//!
+//! The UI only flags the character the caret is on (`ColoredChar::caret`). The
+//! text system turns that cell into a quad of the configured style (`[text.caret]`),
+//! kept in a buffer of its own so moving the caret needs no new layout. Blinking and
+//! the slide from the previous character are worked out by the renderer on every
+//! frame from the `CaretMotion` sent along with the text, without a new layout either.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{CaretStyle, Easing};
+
+/// Thickness of the underline and bar carets relative to the character cell
+const LINE_FRACTION: f32 = 0.12;
+
+/// Thinnest underline or bar, so it stays visible at small font sizes
+const MIN_LINE_PX: f32 = 2.0;
+
+/// Position and size of the quad drawn for a caret on the cell at `position` of `size`
+pub fn caret_rect(style: CaretStyle, position: [f32; 2], size: [f32; 2]) -> ([f32; 2], [f32; 2]) {
+    let [x, y] = position;
+    let [width, height] = size;
+    match style {
+        CaretStyle::Block => (position, size),
+        CaretStyle::Underline => {
+            let thickness = (height * LINE_FRACTION).max(MIN_LINE_PX).min(height);
+            ([x, y + height - thickness], [width, thickness])
+        }
+        CaretStyle::Bar => {
+            let thickness = (width * LINE_FRACTION).max(MIN_LINE_PX).min(width);
+            (position, [thickness, height])
+        }
+    }
+}
+
+/// How the caret moves and blinks since it last changed cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaretMotion {
+    /// When the caret last moved; blinking starts over from here, so the caret
+    /// stays on while typing
+    pub moved_at: Instant,
+    /// Where the slide starts, relative to the caret's new cell
+    pub slide_from: [f32; 2],
+    /// How long the caret stays on, and then off; None keeps it on
+    pub blink: Option<Duration>,
+    /// How long the slide to a new cell takes; zero jumps there
+    pub slide: Duration,
+    pub easing: Easing,
+}
+
+impl CaretMotion {
+    pub fn new(blink_ms: u64, slide_ms: u64, easing: Easing) -> Self {
+        Self {
+            moved_at: Instant::now(),
+            slide_from: [0.0, 0.0],
+            blink: (blink_ms > 0).then(|| Duration::from_millis(blink_ms)),
+            slide: Duration::from_millis(slide_ms),
+            easing,
+        }
+    }
+
+    /// Starts over at a new cell, `delta` away from the previous one. Only moves
+    /// along a row slide; a jump to another line would sweep across the text.
+    pub fn moved(&mut self, delta: Option<[f32; 2]>, now: Instant) {
+        let [dx, dy] = delta.unwrap_or([0.0, 0.0]);
+        self.slide_from = if dy == 0.0 {
+            // A slide still running carries on from where it got to
+            let [offset_x, _] = self.offset(now);
+            [offset_x - dx, 0.0]
+        } else {
+            [0.0, 0.0]
+        };
+        self.moved_at = now;
+    }
+
+    /// Whether the caret is on at `now`
+    pub fn visible(&self, now: Instant) -> bool {
+        let Some(blink) = self.blink else {
+            return true;
+        };
+        let elapsed = now.saturating_duration_since(self.moved_at);
+        (elapsed.as_nanos() / blink.as_nanos()).is_multiple_of(2)
+    }
+
+    /// How far from its cell the caret is drawn at `now`, easing to nothing
+    pub fn offset(&self, now: Instant) -> [f32; 2] {
+        if self.slide.is_zero() {
+            return [0.0, 0.0];
+        }
+        let elapsed = now.saturating_duration_since(self.moved_at);
+        let progress = (elapsed.as_secs_f32() / self.slide.as_secs_f32()).min(1.0);
+        let remaining = 1.0 - self.easing.apply(progress);
+        self.slide_from.map(|from| from * remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caret_rect_styles() {
+        let cell = ([10.0, 20.0], [12.0, 30.0]);
+        assert_eq!(caret_rect(CaretStyle::Block, cell.0, cell.1), cell);
+        let ([x, y], [width, height]) = caret_rect(CaretStyle::Underline, cell.0, cell.1);
+        assert_eq!((x, width), (10.0, 12.0));
+        assert!((height - 3.6).abs() < 1e-4);
+        // Along the bottom of the cell
+        assert!((y + height - 50.0).abs() < 1e-4);
+        // Too thin to see otherwise
+        assert_eq!(
+            caret_rect(CaretStyle::Bar, cell.0, cell.1),
+            ([10.0, 20.0], [2.0, 30.0])
+        );
+    }
+
+    #[test]
+    fn test_blink_and_slide() {
+        let start = Instant::now();
+        let mut motion = CaretMotion::new(500, 100, Easing::EaseOut);
+        motion.moved(Some([10.0, 0.0]), start);
+        assert!(motion.visible(start + Duration::from_millis(499)));
+        assert!(!motion.visible(start + Duration::from_millis(500)));
+        assert!(motion.visible(start + Duration::from_millis(1000)));
+
+        assert_eq!(motion.offset(start), [-10.0, 0.0]);
+        assert_eq!(
+            motion.offset(start + Duration::from_millis(50)),
+            [-2.5, 0.0]
+        );
+        assert_eq!(
+            motion.offset(start + Duration::from_millis(100)),
+            [0.0, 0.0]
+        );
+
+        // Typing on mid-slide continues from where the caret is drawn
+        motion.moved(Some([10.0, 0.0]), start + Duration::from_millis(50));
+        assert_eq!(
+            motion.offset(start + Duration::from_millis(50)),
+            [-12.5, 0.0]
+        );
+
+        // A new line jumps
+        motion.moved(Some([-200.0, 40.0]), start + Duration::from_millis(60));
+        assert_eq!(motion.offset(start + Duration::from_millis(60)), [0.0, 0.0]);
+
+        let steady = CaretMotion::new(0, 0, Easing::EaseOut);
+        assert!(steady.visible(start + Duration::from_secs(7)));
+        assert_eq!(steady.offset(start), [0.0, 0.0]);
+    }
+}
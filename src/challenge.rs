@@ -0,0 +1,314 @@
+//! Challenge files
+//!
+//! A challenge is a small TOML (or JSON) file that pins a session down so it can be
+//! shared: what to type, which lines, for how long and under which rules. Everyone
+//! who runs it types the same text, and the sessions are saved with the challenge id.
+//!
+//! ```toml
+//! id = "parser-warmup"
+//! duration_minutes = 2.0
+//! lines = "10-40"
+//! rules = "strict"
+//!
+//! [source]
+//! type = "file"
+//! path = "src/parser.rs"
+//! hash = "5f0c3a9e1b2d4c67"
+//! ```
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::config::GameplayConfig;
+use crate::drills::{Drill, DrillKind};
+use crate::encoding;
+use crate::session_state::LineRange;
+
+/// Source name recorded in session history for challenge sessions
+pub const CHALLENGE_SOURCE: &str = "challenge";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Challenge {
+    /// Saved with every session of this challenge so results can be compared
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub duration_minutes: f64,
+    /// Lines of the text to type, like "10-40"; the whole text when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<String>,
+    #[serde(default)]
+    pub rules: Rules,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rules {
+    /// Mistakes are counted, backspace works as configured
+    #[default]
+    Permissive,
+    /// No backspace, and the first mistake ends the session
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Source {
+    /// A file both sides have, checked against the hash of its content
+    File { path: String, hash: String },
+    /// The text itself, carried in the challenge
+    Snippet { text: String },
+    /// A generated drill
+    Drill {
+        drill: DrillKind,
+        difficulty: u8,
+        seed: u64,
+    },
+}
+
+impl Challenge {
+    /// Reads a challenge file; `.json` files are JSON, anything else TOML
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Can't read challenge {}", path.display()))?;
+        let challenge: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&content)?
+        } else {
+            toml::from_str(&content)?
+        };
+        challenge
+            .validate()
+            .with_context(|| format!("Invalid challenge {}", path.display()))?;
+        Ok(challenge)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() || self.id.contains('\n') {
+            bail!("the id must be a single non-empty line");
+        }
+        if self.duration_minutes.is_nan() || self.duration_minutes <= 0.0 {
+            bail!("duration_minutes must be positive");
+        }
+        if let Some(lines) = &self.lines
+            && LineRange::parse(lines).is_none()
+        {
+            bail!("lines must look like 10-40: {}", lines);
+        }
+        Ok(())
+    }
+
+    pub fn line_range(&self) -> Option<LineRange> {
+        self.lines.as_deref().and_then(LineRange::parse)
+    }
+
+    /// Seed of a generated source
+    pub fn seed(&self) -> Option<u64> {
+        match self.source {
+            Source::Drill { seed, .. } => Some(seed),
+            _ => None,
+        }
+    }
+
+    /// The text to type, failing if a referenced file differs from the challenger's
+    pub fn text(&self) -> Result<String> {
+        match &self.source {
+            Source::File { path, hash } => {
+                let text = encoding::read_file(path)?.text;
+                let actual = content_hash(&text);
+                if actual != *hash {
+                    bail!(
+                        "{} is not the file this challenge was made from (hash {}, expected {})",
+                        path,
+                        actual,
+                        hash
+                    );
+                }
+                Ok(text)
+            }
+            Source::Snippet { text } => Ok(text.clone()),
+            Source::Drill {
+                drill,
+                difficulty,
+                seed,
+            } => Ok(Drill {
+                kind: *drill,
+                difficulty: *difficulty,
+                seed: *seed,
+            }
+            .generate()),
+        }
+    }
+}
+
+/// Hash of a text that is the same on every machine and Rust version (64-bit FNV-1a),
+/// unlike the std hasher used for progress files
+pub fn content_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Builds a challenge for a file from `challenge export` arguments
+fn export(args: &[String], gameplay: &GameplayConfig) -> Result<Challenge> {
+    let mut file = None;
+    let mut id = None;
+    let mut title = None;
+    let mut lines = None;
+    let mut minutes = gameplay.session_duration_minutes;
+    let mut rules = Rules::Permissive;
+    let mut embed = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--id" => id = Some(value()?.clone()),
+            "--title" => title = Some(value()?.clone()),
+            "--lines" => lines = Some(value()?.clone()),
+            "--minutes" => {
+                minutes = value()?
+                    .parse()
+                    .with_context(|| format!("{} needs a number of minutes", arg))?
+            }
+            "--strict" => rules = Rules::Strict,
+            "--embed" => embed = true,
+            flag if flag.starts_with("--") => bail!("unknown option {}", flag),
+            path if file.is_none() => file = Some(path.to_string()),
+            extra => bail!("unexpected argument {}", extra),
+        }
+    }
+    let Some(path) = file else {
+        bail!(
+            "usage: challenge export FILE [--lines A-B] [--minutes N] [--strict] [--embed] [--id ID] [--title TEXT]"
+        );
+    };
+
+    let text = encoding::read_file(&path)?.text;
+    let hash = content_hash(&text);
+    let id = id.unwrap_or_else(|| {
+        let stem = Path::new(&path)
+            .file_stem()
+            .map_or("challenge".into(), |stem| stem.to_string_lossy());
+        format!("{}-{}", stem, &hash[..8])
+    });
+
+    let source = if embed {
+        // Embedding just the chosen lines keeps the challenge small
+        let text = match lines.as_deref().and_then(LineRange::parse) {
+            Some(range) => {
+                lines = None;
+                text.lines()
+                    .skip(range.start - 1)
+                    .take(range.end - range.start + 1)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            None => text,
+        };
+        Source::Snippet { text }
+    } else {
+        Source::File { path, hash }
+    };
+
+    let challenge = Challenge {
+        id,
+        title,
+        duration_minutes: minutes,
+        lines,
+        rules,
+        source,
+    };
+    challenge.validate()?;
+    Ok(challenge)
+}
+
+/// Runs the `challenge` subcommand. `run FILE` returns the challenge to start in
+/// the window; `export FILE ...` prints a new challenge file and returns None.
+pub fn command(args: &[String], gameplay: &GameplayConfig) -> Result<Option<Challenge>> {
+    match args {
+        [command, path] if command == "run" => Challenge::load(Path::new(path)).map(Some),
+        [command, rest @ ..] if command == "export" => {
+            print!("{}", toml::to_string_pretty(&export(rest, gameplay)?)?);
+            Ok(None)
+        }
+        _ => bail!("usage: challenge run FILE | challenge export FILE [options]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenges() {
+        let challenge: Challenge = toml::from_str(
+            "id = \"warmup\"\nduration_minutes = 2.0\nlines = \"3-5\"\nrules = \"strict\"\n\n\
+             [source]\ntype = \"snippet\"\ntext = \"fn main() {}\"\n",
+        )
+        .unwrap();
+        assert!(challenge.validate().is_ok());
+        assert_eq!(challenge.rules, Rules::Strict);
+        assert_eq!(challenge.line_range(), Some(LineRange { start: 3, end: 5 }));
+        assert_eq!(challenge.text().unwrap(), "fn main() {}");
+
+        let challenge: Challenge = serde_json::from_str(
+            r#"{"id":"brackets","duration_minutes":1.0,
+                "source":{"type":"drill","drill":"brackets","difficulty":3,"seed":42}}"#,
+        )
+        .unwrap();
+        assert_eq!(challenge.rules, Rules::Permissive);
+        assert_eq!(challenge.seed(), Some(42));
+        assert_eq!(challenge.text().unwrap(), challenge.text().unwrap());
+
+        let mut invalid = challenge.clone();
+        invalid.lines = Some("ten".to_string());
+        assert!(invalid.validate().is_err());
+        invalid.lines = None;
+        invalid.duration_minutes = 0.0;
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let path = std::env::temp_dir().join("cargotap_challenge_export.rs");
+        fs::write(&path, "line one\nline two\nline three\n").unwrap();
+        let args = |extra: &[&str]| -> Vec<String> {
+            std::iter::once(path.to_string_lossy().into_owned())
+                .chain(extra.iter().map(|arg| arg.to_string()))
+                .collect()
+        };
+        let gameplay = GameplayConfig::default();
+
+        let challenge = export(&args(&["--lines", "2-3", "--strict"]), &gameplay).unwrap();
+        assert!(challenge.id.starts_with("cargotap_challenge_export-"));
+        assert_eq!(
+            challenge.duration_minutes,
+            gameplay.session_duration_minutes
+        );
+        let parsed: Challenge =
+            toml::from_str(&toml::to_string_pretty(&challenge).unwrap()).unwrap();
+        assert_eq!(parsed, challenge);
+        assert!(parsed.text().unwrap().starts_with("line one"));
+
+        let embedded = export(&args(&["--lines", "2-3", "--embed"]), &gameplay).unwrap();
+        assert_eq!(embedded.lines, None);
+        assert_eq!(embedded.text().unwrap(), "line two\nline three");
+
+        fs::write(&path, "changed\n").unwrap();
+        assert!(challenge.text().is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,227 @@
+//! What's new after an update
+//!
+//! The changelog is embedded from `changelog.toml`. The first time a version runs
+//! in a profile, the entries of the releases since the last version seen there are
+//! shown over the text, each with its keys and setting, and Enter opens the screen
+//! an entry is about. The last version seen is kept in the profile's data
+//! directory. New users get the tutorial instead; `--whats-new` shows the list again.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Record of the last version that ran, in the data directory of the active profile
+pub const WHATS_NEW_FILE: &str = "whats_new.json";
+
+const CHANGELOG: &str = include_str!("changelog.toml");
+
+/// Screen an entry can take the user to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    Settings,
+    SessionSetup,
+    Drills,
+    Dogfood,
+    FilePicker,
+    LineRange,
+}
+
+impl Target {
+    pub fn label(self) -> &'static str {
+        match self {
+            Target::Settings => "theme settings",
+            Target::SessionSetup => "session setup",
+            Target::Drills => "drills",
+            Target::Dogfood => "dogfood mode",
+            Target::FilePicker => "file picker",
+            Target::LineRange => "line range prompt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Entry {
+    pub title: String,
+    pub text: String,
+    pub keys: Option<String>,
+    /// The config.toml setting behind the entry, as `section.key`
+    pub setting: Option<String>,
+    pub open: Option<Target>,
+}
+
+impl Entry {
+    /// Where the setting goes in config.toml, like `[gameplay] scroll_lock`
+    pub fn setting_location(&self) -> Option<String> {
+        let setting = self.setting.as_deref()?;
+        Some(match setting.split_once('.') {
+            Some((section, key)) => format!("[{}] {}", section, key),
+            None => setting.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub version: String,
+    #[serde(rename = "entry", default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Changelog {
+    #[serde(rename = "release", default)]
+    releases: Vec<Release>,
+}
+
+/// The releases in the embedded changelog, newest first
+pub fn releases() -> Vec<Release> {
+    match toml::from_str::<Changelog>(CHANGELOG) {
+        Ok(changelog) => changelog.releases,
+        Err(e) => {
+            log::error!("Invalid embedded changelog: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// `major.minor.patch` as numbers, so 0.10.0 comes after 0.9.0
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim().splitn(3, '.');
+    let mut next = || parts.next()?.parse().ok();
+    Some((next()?, next()?, next()?))
+}
+
+/// Entries of the releases after `last_seen` up to `current`, newest first. With
+/// nothing seen yet only the current release counts.
+pub fn entries_since(releases: &[Release], last_seen: Option<&str>, current: &str) -> Vec<Entry> {
+    let Some(current) = parse_version(current) else {
+        return Vec::new();
+    };
+    let last_seen = last_seen.and_then(parse_version);
+    releases
+        .iter()
+        .filter(|release| {
+            parse_version(&release.version).is_some_and(|version| {
+                version <= current
+                    && match last_seen {
+                        Some(seen) => version > seen,
+                        None => version == current,
+                    }
+            })
+        })
+        .flat_map(|release| release.entries.iter().cloned())
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Seen {
+    version: String,
+}
+
+/// The last version that ran in the profile whose record is at `path`
+pub fn last_seen(path: &Path) -> Option<String> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<Seen>(&json)
+        .ok()
+        .map(|seen| seen.version)
+}
+
+/// Records that `version` ran, so its entries aren't shown again
+pub fn mark_seen(path: &Path, version: &str) -> io::Result<()> {
+    let seen = Seen {
+        version: version.to_string(),
+    };
+    let json = serde_json::to_string_pretty(&seen)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, json)
+}
+
+/// The what's new list, open over the text
+#[derive(Debug, Clone)]
+pub struct WhatsNew {
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+}
+
+impl WhatsNew {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        Self {
+            entries,
+            selected: 0,
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let count = self.entries.len().max(1);
+        self.selected = (self.selected + count - 1) % count;
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.entries.len().max(1);
+    }
+
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_changelog_parses() {
+        let releases = releases();
+        assert!(!releases.is_empty());
+        for release in &releases {
+            assert!(parse_version(&release.version).is_some());
+        }
+        // The running version has its entries, or nothing would ever be shown
+        let current = entries_since(&releases, None, env!("CARGO_PKG_VERSION"));
+        assert!(!current.is_empty());
+        assert!(current.iter().any(|entry| entry.open.is_some()));
+    }
+
+    #[test]
+    fn test_entries_since_last_seen() {
+        let release = |version: &str, title: &str| Release {
+            version: version.to_string(),
+            entries: vec![Entry {
+                title: title.to_string(),
+                text: String::new(),
+                keys: None,
+                setting: Some("gameplay.scroll_lock".to_string()),
+                open: None,
+            }],
+        };
+        let releases = [
+            release("0.10.0", "ten"),
+            release("0.9.1", "nine"),
+            release("0.9.0", "old"),
+        ];
+        let titles = |entries: Vec<Entry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.title).collect()
+        };
+
+        assert_eq!(
+            titles(entries_since(&releases, Some("0.9.0"), "0.10.0")),
+            ["ten", "nine"]
+        );
+        assert_eq!(titles(entries_since(&releases, None, "0.10.0")), ["ten"]);
+        // A newer changelog than the binary (running an older build again)
+        assert_eq!(
+            titles(entries_since(&releases, Some("0.9.0"), "0.9.1")),
+            ["nine"]
+        );
+        assert!(entries_since(&releases, Some("0.10.0"), "0.10.0").is_empty());
+        assert_eq!(
+            releases[0].entries[0].setting_location().as_deref(),
+            Some("[gameplay] scroll_lock")
+        );
+    }
+}
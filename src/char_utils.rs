@@ -1,5 +1,8 @@
 /// Utilities for handling characters that may be difficult or impossible to type
 /// on a standard US keyboard
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
 
 /// Determines if a character can be easily typed on a US keyboard
 ///
@@ -115,6 +118,32 @@ pub fn get_untypeable_description(ch: char) -> Option<String> {
     })
 }
 
+/// Finds the right-to-left runs of a single line, as byte ranges
+///
+/// The line is resolved with a left-to-right paragraph direction, the way code
+/// is laid out, so digits and punctuation embedded in Arabic or Hebrew text stay
+/// part of the surrounding run while the code around it does not.
+pub fn rtl_runs(line: &str) -> Vec<Range<usize>> {
+    if line.is_ascii() {
+        return Vec::new();
+    }
+
+    let info = BidiInfo::new(line, Some(Level::ltr()));
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (index, ch) in line.char_indices() {
+        // Numbers inside an RTL run resolve to an even level above zero
+        if info.levels[index].number() == 0 {
+            continue;
+        }
+        let end = index + ch.len_utf8();
+        match runs.last_mut() {
+            Some(run) if run.end == index => run.end = end,
+            _ => runs.push(index..end),
+        }
+    }
+    runs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -214,4 +243,32 @@ mod tests {
         let desc = get_untypeable_description('a');
         assert!(desc.is_none());
     }
+
+    #[test]
+    fn test_rtl_runs_in_comment() {
+        let line = "let x = 1; // مرحبا بالعالم";
+        let runs = rtl_runs(line);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(&line[runs[0].clone()], "مرحبا بالعالم");
+    }
+
+    #[test]
+    fn test_rtl_runs_mixed_directions() {
+        let line = "\"שלום\" world \"עולם\"";
+        let runs: Vec<&str> = rtl_runs(line).into_iter().map(|r| &line[r]).collect();
+        assert_eq!(runs, vec!["שלום", "עולם"]);
+    }
+
+    #[test]
+    fn test_rtl_runs_keep_embedded_numbers() {
+        let line = "s = \"عدد 42 عنصر\";";
+        let runs: Vec<&str> = rtl_runs(line).into_iter().map(|r| &line[r]).collect();
+        assert_eq!(runs, vec!["عدد 42 عنصر"]);
+    }
+
+    #[test]
+    fn test_rtl_runs_ltr_only() {
+        assert!(rtl_runs("fn main() { println!(\"héllo\"); }").is_empty());
+        assert!(rtl_runs("").is_empty());
+    }
 }
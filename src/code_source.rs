@@ -0,0 +1,348 @@
+//! Practice code from GitHub
+//!
+//! The GitHub prompt (Ctrl+Shift+O) takes `owner/repo/path/to/file` and
+//! downloads that file from a public repository, or `owner/repo` and picks a
+//! random file with one of the extensions under `[github]`. A branch can be named
+//! as `owner/repo@branch/...`, and a github.com URL pasted from the browser works
+//! too. Downloads go to `github/` in the data directory and are opened from there
+//! like any other file, so progress is saved for them; when GitHub can't be
+//! reached, a file downloaded before is opened from that copy.
+//!
+//! Requests run on their own thread; the app polls for the result between frames.
+
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use crate::config::GithubConfig;
+use crate::ignore::IgnoreList;
+use crate::profile;
+
+/// How long one request to GitHub may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A file (or a whole repository, to pick from) typed into the GitHub prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoFile {
+    pub owner: String,
+    pub repo: String,
+    /// None uses `branch` under `[github]`
+    pub branch: Option<String>,
+    /// None picks a random file
+    pub path: Option<String>,
+}
+
+impl RepoFile {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let input = input
+            .strip_prefix("https://")
+            .or_else(|| input.strip_prefix("http://"))
+            .unwrap_or(input);
+        let input = input
+            .strip_prefix("www.github.com/")
+            .or_else(|| input.strip_prefix("github.com/"))
+            .unwrap_or(input);
+        let segments: Vec<&str> = input.split('/').filter(|s| !s.is_empty()).collect();
+        let [owner, repo, rest @ ..] = segments.as_slice() else {
+            bail!("type owner/repo, optionally followed by /path/to/file");
+        };
+        let repo = repo.strip_suffix(".git").unwrap_or(repo);
+        let (repo, mut branch) = match repo.split_once('@') {
+            Some((repo, branch)) => (repo, Some(branch.to_string())),
+            None => (repo, None),
+        };
+        // URLs of files and directories on github.com: owner/repo/blob/branch/path
+        let rest = match rest {
+            ["blob" | "tree", url_branch, rest @ ..] if branch.is_none() => {
+                branch = Some(url_branch.to_string());
+                rest
+            }
+            rest => rest,
+        };
+        let path = (!rest.is_empty()).then(|| rest.join("/"));
+
+        let parts = [Some(*owner), Some(repo), branch.as_deref(), path.as_deref()];
+        if parts.into_iter().flatten().any(|part| !is_relative(part)) {
+            bail!("'{}' is not a repository path", input);
+        }
+        Ok(Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            branch,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for RepoFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.repo)?;
+        if let Some(branch) = &self.branch {
+            write!(f, "@{}", branch)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "/{}", path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `part` stays inside the directory it is joined to
+fn is_relative(part: &str) -> bool {
+    !part.is_empty()
+        && !part.contains('\\')
+        && Path::new(part)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// A downloaded file
+#[derive(Debug)]
+pub struct Fetched {
+    /// What was downloaded, with the path of a random pick filled in
+    pub file: RepoFile,
+    /// Where it was saved
+    pub local: PathBuf,
+}
+
+/// Where the download of `path` is kept
+fn cache_path(base: &Path, file: &RepoFile, branch: &str, path: &str) -> Result<PathBuf> {
+    let parts = [file.owner.as_str(), &file.repo, branch, path];
+    if !parts.iter().all(|part| is_relative(part)) {
+        bail!("'{}' is not a repository path", path);
+    }
+    Ok(parts
+        .iter()
+        .fold(base.to_path_buf(), |dir, part| dir.join(part)))
+}
+
+/// Files of a repository tree (as listed by the API) a random pick is made from
+fn candidates(tree: &serde_json::Value, config: &GithubConfig, ignore: &IgnoreList) -> Vec<String> {
+    let entries = tree["tree"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    entries
+        .iter()
+        .filter(|entry| entry["type"] == "blob")
+        .filter_map(|entry| {
+            let path = entry["path"].as_str()?;
+            let size = entry["size"].as_u64()?;
+            let extension = Path::new(path).extension()?.to_string_lossy();
+            let wanted = size > 0
+                && size <= config.max_file_bytes
+                && config.extensions.iter().any(|ext| *ext == extension)
+                && !ignore.is_ignored(Path::new(path), false);
+            wanted.then(|| path.to_string())
+        })
+        .collect()
+}
+
+fn get(url: &str) -> Result<ureq::Response> {
+    match ureq::get(url)
+        .set("User-Agent", "CargoTap")
+        .timeout(REQUEST_TIMEOUT)
+        .call()
+    {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(404, _)) => bail!("not found on GitHub: {}", url),
+        Err(ureq::Error::Status(403 | 429, _)) => {
+            bail!("GitHub turned the request down, probably a rate limit; try again later")
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Picks a random file of the repository, using `seed`
+fn pick_file(
+    file: &RepoFile,
+    branch: &str,
+    config: &GithubConfig,
+    ignore: &IgnoreList,
+    seed: u64,
+) -> Result<String> {
+    let url = format!(
+        "{}/repos/{}/{}/git/trees/{}?recursive=1",
+        config.api_url.trim_end_matches('/'),
+        file.owner,
+        file.repo,
+        branch
+    );
+    let tree: serde_json::Value = serde_json::from_str(&get(&url)?.into_string()?)?;
+    let candidates = candidates(&tree, config, ignore);
+    match candidates.choose(&mut StdRng::seed_from_u64(seed)) {
+        Some(path) => Ok(path.clone()),
+        None => bail!(
+            "{}/{} has no .{} files of at most {} bytes",
+            file.owner,
+            file.repo,
+            config.extensions.join(", ."),
+            config.max_file_bytes
+        ),
+    }
+}
+
+fn download(url: &str, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    get(url)?
+        .into_reader()
+        .take(max_bytes + 1)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_bytes {
+        bail!(
+            "the file is larger than {} bytes (max_file_bytes under [github])",
+            max_bytes
+        );
+    }
+    Ok(bytes)
+}
+
+/// Downloads the file, picking one first if needed
+fn fetch_blocking(
+    file: &RepoFile,
+    config: &GithubConfig,
+    ignore: &IgnoreList,
+    seed: u64,
+) -> Result<Fetched> {
+    let branch = file.branch.as_deref().unwrap_or(&config.branch);
+    let path = match &file.path {
+        Some(path) => path.clone(),
+        None => pick_file(file, branch, config, ignore, seed)?,
+    };
+    let cached = cache_path(&profile::data_file("github"), file, branch, &path)?;
+    let url = format!(
+        "{}/{}/{}/{}/{}",
+        config.raw_url.trim_end_matches('/'),
+        file.owner,
+        file.repo,
+        branch,
+        path
+    );
+    match download(&url, config.max_file_bytes) {
+        Ok(bytes) => {
+            if let Some(dir) = cached.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&cached, bytes)?;
+        }
+        Err(e) if cached.is_file() => {
+            log::warn!("Opening the saved copy of {}: {:#}", path, e);
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(Fetched {
+        file: RepoFile {
+            path: Some(path),
+            ..file.clone()
+        },
+        local: cached,
+    })
+}
+
+/// Fetches `file` on a background thread. The receiver gets the download, or why
+/// there is none.
+pub fn fetch(
+    file: RepoFile,
+    config: GithubConfig,
+    ignore_patterns: &[String],
+    seed: u64,
+) -> Receiver<Result<Fetched>> {
+    let ignore = IgnoreList::new(ignore_patterns);
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The prompt may have been closed in the meantime, which is fine
+        let _ = sender.send(fetch_blocking(&file, &config, &ignore, seed));
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_file(branch: Option<&str>, path: Option<&str>) -> RepoFile {
+        RepoFile {
+            owner: "rust-lang".to_string(),
+            repo: "regex".to_string(),
+            branch: branch.map(String::from),
+            path: path.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_parse_prompt() {
+        assert_eq!(
+            RepoFile::parse("rust-lang/regex").unwrap(),
+            repo_file(None, None)
+        );
+        assert_eq!(
+            RepoFile::parse(" rust-lang/regex/src/lib.rs ").unwrap(),
+            repo_file(None, Some("src/lib.rs"))
+        );
+        assert_eq!(
+            RepoFile::parse("rust-lang/regex@1.10/src/lib.rs").unwrap(),
+            repo_file(Some("1.10"), Some("src/lib.rs"))
+        );
+        assert_eq!(
+            RepoFile::parse("https://github.com/rust-lang/regex/blob/master/src/lib.rs").unwrap(),
+            repo_file(Some("master"), Some("src/lib.rs"))
+        );
+        assert_eq!(
+            RepoFile::parse("github.com/rust-lang/regex.git").unwrap(),
+            repo_file(None, None)
+        );
+        assert_eq!(
+            RepoFile::parse("rust-lang/regex@1.10/src/lib.rs")
+                .unwrap()
+                .to_string(),
+            "rust-lang/regex@1.10/src/lib.rs"
+        );
+        assert!(RepoFile::parse("rust-lang").is_err());
+        assert!(RepoFile::parse("rust-lang/regex/../../etc/passwd").is_err());
+        assert!(RepoFile::parse("rust-lang/regex@../src/lib.rs").is_err());
+    }
+
+    #[test]
+    fn test_cache_stays_in_its_directory() {
+        let base = Path::new("/data/github");
+        let file = repo_file(None, None);
+        assert_eq!(
+            cache_path(base, &file, "HEAD", "src/lib.rs").unwrap(),
+            Path::new("/data/github/rust-lang/regex/HEAD/src/lib.rs")
+        );
+        assert!(cache_path(base, &file, "HEAD", "../../x.rs").is_err());
+        assert!(cache_path(base, &file, "HEAD", "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_random_file_candidates() {
+        let tree = serde_json::json!({
+            "tree": [
+                { "path": "src", "type": "tree" },
+                { "path": "src/lib.rs", "type": "blob", "size": 1200 },
+                { "path": "src/empty.rs", "type": "blob", "size": 0 },
+                { "path": "src/huge.rs", "type": "blob", "size": 10_000_000 },
+                { "path": "README.md", "type": "blob", "size": 300 },
+                { "path": "scripts/gen.py", "type": "blob", "size": 800 },
+                { "path": "vendor/dep.rs", "type": "blob", "size": 500 },
+            ]
+        });
+        let config = GithubConfig::default();
+        let ignore = IgnoreList::new(&crate::ignore::default_patterns());
+        assert_eq!(
+            candidates(&tree, &config, &ignore),
+            ["src/lib.rs", "scripts/gen.py"]
+        );
+        assert!(candidates(&serde_json::json!({}), &config, &ignore).is_empty());
+    }
+}
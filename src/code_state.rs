@@ -2,54 +2,347 @@
 //!
 //! This module provides the CodeState struct which manages the state of code
 //! and tracks the user's typing progress with a cursor position.
+//!
+//! The text lives in a rope so line lookups and edits stay O(log n) even for
+//! large files; the cursor is a byte offset into it. The text getters hand out
+//! `RopeSlice`s rather than copies, and `insert_text`/`remove_text` edit it in place.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::examples::colored_text_demo::ColoredTextDemo;
+use ropey::{Rope, RopeSlice};
+
+use crate::char_utils;
+use crate::folding::{self, FoldRegion};
+use crate::highlight::{self, SyntaxHighlighter};
+use crate::language::{self, Language};
 use crate::text::ColoredText;
 
+/// Bytes kept loaded ahead of the cursor in streaming mode
+const STREAM_AHEAD_BYTES: usize = 64 * 1024;
+
+/// Bytes of typed text kept behind the cursor in streaming mode (for backspace and scrolling)
+const STREAM_BEHIND_BYTES: usize = 16 * 1024;
+
+/// Opacity multiplier for right-to-left runs, which are shown in logical order
+/// and marked rather than reordered
+const RTL_RUN_ALPHA: f32 = 0.45;
+
+/// Source of `CodeState::colors_generation`; global so results computed for one
+/// text can never be mistaken for another text's, even across CodeStates
+static NEXT_COLORS_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_colors_generation() -> u64 {
+    NEXT_COLORS_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Why `text` can't be practiced, or None when there is something to type. A
+/// blank text would finish before the first keystroke.
+pub fn blank_reason(text: &str) -> Option<&'static str> {
+    if text.is_empty() {
+        Some("is empty")
+    } else if text.trim().is_empty() {
+        Some("only has whitespace")
+    } else {
+        None
+    }
+}
+
+/// Colors `code` for display: syntax highlighting (unless `highlighter` is None)
+/// and faded right-to-left runs.
+/// Runs on the highlighter thread as well, so it only depends on its arguments.
+pub fn highlight(code: &str, highlighter: Option<&dyn SyntaxHighlighter>) -> ColoredText {
+    let mut colored = match highlighter {
+        Some(highlighter) => highlighter.highlight(code),
+        None => ColoredText::from_str_with_color(code, [1.0, 1.0, 1.0, 1.0]),
+    };
+    mark_rtl_runs(code, &mut colored);
+    colored
+}
+
+/// Lazily read lines of a file too large to keep in memory
+#[derive(Debug)]
+struct LineStream {
+    reader: BufReader<File>,
+    /// Size of the whole file, used for progress
+    total_bytes: usize,
+    finished: bool,
+}
+
+impl LineStream {
+    /// Reads the next line (with its newline, CRLF normalized), or None at end of file
+    fn next_line(&mut self) -> Option<String> {
+        if self.finished {
+            return None;
+        }
+
+        let mut bytes = Vec::new();
+        match self.reader.read_until(b'\n', &mut bytes) {
+            Ok(0) => {
+                self.finished = true;
+                None
+            }
+            Ok(_) => {
+                if bytes.ends_with(b"\r\n") {
+                    bytes.truncate(bytes.len() - 2);
+                    bytes.push(b'\n');
+                }
+                Some(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            Err(e) => {
+                log::error!("Failed to read from streamed file: {}", e);
+                self.finished = true;
+                None
+            }
+        }
+    }
+}
+
 /// Represents the state of code in the typing game
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct CodeState {
-    /// The complete code text
-    code: String,
-    /// Current cursor position (number of characters typed)
+    /// The complete code text (in streaming mode, only the loaded window)
+    code: Rope,
+    /// Current cursor position within `code` (number of bytes typed)
     cursor_position: usize,
     /// Cached syntax-highlighted version of the full code
     cached_colored_text: Option<ColoredText>,
+    /// Byte offset in `code` where each line of the cached colors starts
+    colored_line_starts: Vec<usize>,
+    /// Identifies the current text and highlighting setting; changes whenever the cache is dropped
+    colors_generation: u64,
+    /// Functions in `code`, found again whenever the colors are
+    cached_function_bodies: Option<Vec<FoldRegion>>,
+    /// Right-to-left runs of the line of `code` with this index, dropped with the colors
+    cached_rtl_runs: Option<(usize, Vec<Range<usize>>)>,
     /// Whether syntax highlighting is enabled
     syntax_highlighting_enabled: bool,
+    /// Colors the code for its language
+    highlighter: Arc<dyn SyntaxHighlighter>,
+    /// The language of the text, for folding and the Tab key
+    language: &'static Language,
+    /// Source of further lines when the file is streamed instead of loaded whole
+    stream: Option<LineStream>,
+    /// Byte offset of the start of `code` within the file
+    window_offset: usize,
+    /// Number of file lines before the start of `code`
+    window_first_line: usize,
+    /// Lines dropped from the top of the window since the last `take_dropped_lines`
+    dropped_lines: usize,
+    /// Whitespace-separated words in the text (unknown when streaming)
+    word_count: Option<usize>,
+    /// Wrong keys typed at the cursor in insert error mode, covering the characters
+    /// after it until they are backspaced
+    errors: Vec<char>,
+    /// The last key at the cursor was wrong (block error mode)
+    missed: bool,
 }
 
+/// Wrong keys that can pile up at the cursor before more are just rejected
+pub const MAX_PENDING_ERRORS: usize = 20;
+
 impl CodeState {
     /// Creates a new CodeState with the given initial code
     pub fn new(initial_code: String) -> Self {
         Self {
-            code: initial_code,
+            word_count: Some(initial_code.split_whitespace().count()),
+            code: Rope::from(initial_code),
             cursor_position: 0,
             cached_colored_text: None,
+            colored_line_starts: Vec::new(),
+            colors_generation: next_colors_generation(),
+            cached_function_bodies: None,
+            cached_rtl_runs: None,
             syntax_highlighting_enabled: false,
+            highlighter: highlight::fallback(),
+            language: &language::RUST,
+            stream: None,
+            window_offset: 0,
+            window_first_line: 0,
+            dropped_lines: 0,
+            errors: Vec::new(),
+            missed: false,
         }
     }
 
+    /// Opens a large file in streaming mode, keeping only a window of lines in memory
+    ///
+    /// `start_position` is a byte offset (e.g. restored progress); the window
+    /// starts at the line containing it.
+    pub fn open_streaming(path: &Path, start_position: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let total_bytes = file.metadata()?.len() as usize;
+        let mut stream = LineStream {
+            reader: BufReader::new(file),
+            total_bytes,
+            finished: false,
+        };
+
+        let mut state = Self::new(String::new());
+        while let Some(line) = stream.next_line() {
+            if state.window_offset + line.len() > start_position {
+                state.code = Rope::from(line);
+                break;
+            }
+            state.window_offset += line.len();
+            state.window_first_line += 1;
+        }
+
+        let cursor = start_position
+            .saturating_sub(state.window_offset)
+            .min(state.code.len_bytes());
+        // Snap to the start of the character containing the offset
+        state.cursor_position = state.code.char_to_byte(state.code.byte_to_char(cursor));
+        state.stream = Some(stream);
+        state.word_count = None;
+        state.fill_window();
+        Ok(state)
+    }
+
+    /// True if the text is streamed from disk rather than fully loaded
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Number of file lines above the loaded window (0 unless streaming)
+    pub fn first_line_number(&self) -> usize {
+        self.window_first_line
+    }
+
+    /// Returns how many lines scrolled out of the top of the window since the last call
+    pub fn take_dropped_lines(&mut self) -> usize {
+        std::mem::take(&mut self.dropped_lines)
+    }
+
+    /// Loads lines ahead of the cursor and drops typed lines far behind it
+    fn slide_window(&mut self) {
+        if self.stream.is_none() {
+            return;
+        }
+
+        self.fill_window();
+
+        if self.cursor_position > 2 * STREAM_BEHIND_BYTES {
+            let keep_from = self.cursor_position - STREAM_BEHIND_BYTES;
+            // Only cut at a line start so the display and line numbers stay aligned
+            let lines = self.code.byte_to_line(keep_from);
+            if lines > 0 {
+                let cut = self.code.line_to_byte(lines);
+                self.remove_text(0..cut);
+                self.window_offset += cut;
+                self.window_first_line += lines;
+                self.dropped_lines += lines;
+            }
+        }
+    }
+
+    fn fill_window(&mut self) {
+        let Some(stream) = self.stream.as_mut() else {
+            return;
+        };
+
+        let mut lines = String::new();
+        while self.code.len_bytes() + lines.len() - self.cursor_position < STREAM_AHEAD_BYTES {
+            match stream.next_line() {
+                Some(line) => lines.push_str(&line),
+                None => break,
+            }
+        }
+
+        if !lines.is_empty() {
+            self.insert_text(self.code.len_bytes(), &lines);
+        }
+    }
+
+    /// Inserts `text` at byte `position` of the loaded text in O(log n). A cursor
+    /// after `position` moves along and stays on the same character; one at it
+    /// stays, so the inserted text is typed next.
+    pub fn insert_text(&mut self, position: usize, text: &str) {
+        let position = self.snap_to_char(position.min(self.code.len_bytes()));
+        let line = self.code.byte_to_line(position);
+        let words_before = self.words_in_lines(line..line + 1);
+        self.code.insert(self.code.byte_to_char(position), text);
+        let last_line = self.code.byte_to_line(position + text.len());
+        self.recount_words(words_before, self.words_in_lines(line..last_line + 1));
+        if self.cursor_position > position {
+            self.cursor_position += text.len();
+        }
+        self.invalidate_colors();
+    }
+
+    /// Removes bytes `range` of the loaded text in O(log n). A cursor inside the
+    /// range ends up where it started, one after it moves back with the text.
+    pub fn remove_text(&mut self, range: Range<usize>) {
+        let end = self.snap_to_char(range.end.min(self.code.len_bytes()));
+        let start = self.snap_to_char(range.start.min(end));
+        if start == end {
+            return;
+        }
+        let line = self.code.byte_to_line(start);
+        let words_before = self.words_in_lines(line..self.code.byte_to_line(end) + 1);
+        self.code
+            .remove(self.code.byte_to_char(start)..self.code.byte_to_char(end));
+        self.recount_words(words_before, self.words_in_lines(line..line + 1));
+        if self.cursor_position >= end {
+            self.cursor_position -= end - start;
+        } else if self.cursor_position > start {
+            self.cursor_position = start;
+        }
+        self.invalidate_colors();
+    }
+
+    /// Words on `lines` of the loaded text; an edit only changes the count on the
+    /// lines it touches, so those are counted instead of the whole text
+    fn words_in_lines(&self, lines: Range<usize>) -> usize {
+        let end = lines.end.min(self.code.len_lines());
+        (lines.start..end)
+            .map(|line| {
+                let text = self.code.line(line);
+                let mut words = 0;
+                let mut in_word = false;
+                for ch in text.chars() {
+                    words += usize::from(!in_word && !ch.is_whitespace());
+                    in_word = !ch.is_whitespace();
+                }
+                words
+            })
+            .sum()
+    }
+
+    fn recount_words(&mut self, before: usize, after: usize) {
+        if let Some(count) = &mut self.word_count {
+            *count = (*count + after).saturating_sub(before);
+        }
+    }
+
+    /// Start of the character containing byte `position` of the loaded text
+    fn snap_to_char(&self, position: usize) -> usize {
+        self.code.char_to_byte(self.code.byte_to_char(position))
+    }
+
     /// Types the next character, advancing the cursor
     /// Returns the character that was typed, or None if no more characters
     pub fn type_character(&mut self) -> Option<char> {
-        if self.cursor_position < self.code.len() {
-            let ch = self.code[self.cursor_position..].chars().next()?;
-            self.cursor_position += ch.len_utf8();
-            self.cached_colored_text = None;
-            Some(ch)
-        } else {
-            None
-        }
+        let ch = self.peek_next_character()?;
+        self.cursor_position += ch.len_utf8();
+        self.clear_errors();
+        self.slide_window();
+        Some(ch)
     }
 
     /// Undoes the last typed character (moves cursor back)
     /// Returns the character that was moved back, or None if nothing to undo
     pub fn backspace(&mut self) -> Option<char> {
+        self.clear_errors();
         if self.cursor_position > 0 {
-            let ch = self.code[..self.cursor_position].chars().last()?;
+            let ch = self
+                .code
+                .char(self.code.byte_to_char(self.cursor_position) - 1);
             self.cursor_position -= ch.len_utf8();
-            self.cached_colored_text = None;
             Some(ch)
         } else {
             None
@@ -57,101 +350,347 @@ impl CodeState {
     }
 
     /// Returns the complete code
-    pub fn get_full_code(&self) -> &str {
-        &self.code
+    pub fn get_full_code(&self) -> RopeSlice<'_> {
+        self.code.slice(..)
     }
 
     /// Returns the code that has been typed so far
-    pub fn get_printed_code(&self) -> &str {
-        &self.code[..self.cursor_position]
+    pub fn get_printed_code(&self) -> RopeSlice<'_> {
+        self.code.byte_slice(..self.cursor_position)
     }
 
     /// Returns the code that still needs to be typed
-    pub fn get_current_code(&self) -> &str {
-        &self.code[self.cursor_position..]
+    pub fn get_current_code(&self) -> RopeSlice<'_> {
+        self.code.byte_slice(self.cursor_position..)
+    }
+
+    /// Returns the text of 1-based `line` without its line break, if it is loaded
+    pub fn line_text(&self, line: usize) -> Option<String> {
+        let index = line.checked_sub(self.window_first_line + 1)?;
+        if index >= self.code.len_lines() {
+            return None;
+        }
+        let text = self.code.line(index).to_string();
+        Some(text.trim_end_matches(['\n', '\r']).to_string())
+    }
+
+    /// Returns the absolute position where 1-based `line` starts, if it is loaded
+    ///
+    /// One line past the end maps to the end of the text so a range can end on the last line.
+    pub fn line_start_position(&self, line: usize) -> Option<usize> {
+        let index = line.checked_sub(self.window_first_line + 1)?;
+        if index < self.code.len_lines() {
+            Some(self.window_offset + self.code.line_to_byte(index))
+        } else if index == self.code.len_lines() {
+            Some(self.window_offset + self.code.len_bytes())
+        } else {
+            None
+        }
+    }
+
+    /// Moves the cursor to an absolute position inside the loaded text
+    /// Returns false if the position is not loaded
+    pub fn set_cursor_position(&mut self, position: usize) -> bool {
+        let Some(relative) = position.checked_sub(self.window_offset) else {
+            return false;
+        };
+        if relative > self.code.len_bytes() {
+            return false;
+        }
+
+        self.cursor_position = self.snap_to_char(relative);
+        self.clear_errors();
+        self.slide_window();
+        true
+    }
+
+    /// Wrong keys typed over the text after the cursor, oldest first
+    pub fn pending_errors(&self) -> &[char] {
+        &self.errors
+    }
+
+    /// Types `typed` wrong over the next character not yet covered. Returns false,
+    /// leaving the text as it is, at the end of the line or after too many.
+    pub fn push_error(&mut self, typed: char) -> bool {
+        let covered = self
+            .code
+            .byte_slice(self.cursor_position..)
+            .chars()
+            .nth(self.errors.len());
+        if covered.is_none_or(|ch| ch == '\n') || self.errors.len() >= MAX_PENDING_ERRORS {
+            return false;
+        }
+        self.errors.push(typed);
+        true
+    }
+
+    /// Takes back the last wrong key typed over the text
+    pub fn pop_error(&mut self) -> Option<char> {
+        self.errors.pop()
+    }
+
+    /// Marks the character at the cursor as missed until the cursor moves
+    pub fn mark_missed(&mut self) {
+        self.missed = true;
+    }
+
+    pub fn is_missed(&self) -> bool {
+        self.missed
+    }
+
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+        self.missed = false;
+    }
+
+    /// Returns the number of line breaks in the loaded code
+    pub fn get_line_break_count(&self) -> usize {
+        self.code.len_lines() - 1
     }
 
     /// Returns the complete code as syntax-highlighted ColoredText
     /// Uses cached version if available, otherwise generates and caches it
     pub fn get_full_code_colored(&mut self) -> &ColoredText {
         if self.cached_colored_text.is_none() {
-            let colored = if self.syntax_highlighting_enabled {
-                ColoredTextDemo::create_syntax_highlighted_rust(&self.code)
-            } else {
-                ColoredText::from_str_with_color(&self.code, [1.0, 1.0, 1.0, 1.0])
-            };
-            self.cached_colored_text = Some(colored);
+            let code = self.code.to_string();
+            let highlighter = self
+                .syntax_highlighting_enabled
+                .then_some(self.highlighter.as_ref());
+            self.set_colors(highlight(&code, highlighter));
         }
         self.cached_colored_text.as_ref().unwrap()
     }
 
+    fn set_colors(&mut self, colored: ColoredText) {
+        let mut start = 0;
+        self.colored_line_starts = colored
+            .lines
+            .iter()
+            .map(|line| {
+                let line_start = start;
+                let len: usize = line.chars.iter().map(|c| c.ch.len_utf8()).sum();
+                start += len + '\n'.len_utf8();
+                line_start
+            })
+            .collect();
+        self.cached_colored_text = Some(colored);
+    }
+
+    /// Syntax color of the character just before the cursor, None while the colors
+    /// are still being computed
+    pub fn typed_char_color(&self) -> Option<[f32; 4]> {
+        let colored = self.cached_colored_text.as_ref()?;
+        let offset = self.cursor_position.checked_sub(1)?;
+        // Called on every key, so only the cursor's line is walked
+        let line = self
+            .colored_line_starts
+            .partition_point(|&start| start <= offset)
+            .checked_sub(1)?;
+        let mut offset = offset - self.colored_line_starts[line];
+        for colored_char in &colored.lines.get(line)?.chars {
+            let len = colored_char.ch.len_utf8();
+            if offset < len {
+                return Some(colored_char.color);
+            }
+            offset -= len;
+        }
+        // The line break, which has no color of its own
+        None
+    }
+
+    /// Generation the colors have to be computed for, or None if they are cached
+    pub fn missing_colors(&self) -> Option<u64> {
+        self.cached_colored_text
+            .is_none()
+            .then_some(self.colors_generation)
+    }
+
+    /// Everything `highlight` needs to color the current text on another thread
+    pub fn highlight_job(&self) -> crate::highlighter::HighlightJob {
+        crate::highlighter::HighlightJob {
+            generation: self.colors_generation,
+            code: self.code.to_string(),
+            highlighter: self
+                .syntax_highlighting_enabled
+                .then(|| self.highlighter.clone()),
+        }
+    }
+
+    /// Stores colors computed elsewhere; returns false (and drops them) if the text
+    /// changed since they were requested
+    pub fn accept_colors(&mut self, generation: u64, colored: ColoredText) -> bool {
+        if generation != self.colors_generation {
+            return false;
+        }
+        self.set_colors(colored);
+        true
+    }
+
+    /// Functions in the loaded text, as lines of `get_full_code`
+    pub fn function_bodies(&mut self) -> &[FoldRegion] {
+        self.cached_function_bodies.get_or_insert_with(|| {
+            let code = self.code.to_string();
+            let lines: Vec<&str> = code.lines().collect();
+            folding::function_bodies(&lines, self.language)
+        })
+    }
+
+    fn invalidate_colors(&mut self) {
+        self.cached_colored_text = None;
+        self.colored_line_starts.clear();
+        self.cached_function_bodies = None;
+        self.cached_rtl_runs = None;
+        self.colors_generation = next_colors_generation();
+    }
+
     /// Sets whether syntax highlighting is enabled
     /// Invalidates cache if the setting changes
     pub fn set_syntax_highlighting(&mut self, enabled: bool) {
         if self.syntax_highlighting_enabled != enabled {
             self.syntax_highlighting_enabled = enabled;
-            self.cached_colored_text = None;
+            self.invalidate_colors();
         }
     }
 
-    /// Returns the current cursor position (number of bytes typed)
+    pub fn language(&self) -> &'static Language {
+        self.language
+    }
+
+    pub fn set_language(&mut self, language: &'static Language) {
+        if !std::ptr::eq(self.language, language) {
+            self.language = language;
+            self.cached_function_bodies = None;
+        }
+    }
+
+    /// Colors the code with `highlighter` from now on
+    /// Invalidates cache if it is a different one
+    pub fn set_highlighter(&mut self, highlighter: Arc<dyn SyntaxHighlighter>) {
+        if !Arc::ptr_eq(&self.highlighter, &highlighter) {
+            self.highlighter = highlighter;
+            self.invalidate_colors();
+        }
+    }
+
+    /// Returns the current cursor position (number of bytes typed from the start of the file)
     pub fn get_cursor_position(&self) -> usize {
+        self.window_offset + self.cursor_position
+    }
+
+    /// Returns the cursor position within the loaded text returned by `get_full_code`
+    pub fn get_window_cursor_position(&self) -> usize {
         self.cursor_position
     }
 
     /// Returns the line number (1-based) where the cursor is located
     pub fn get_cursor_line(&self) -> usize {
-        self.code[..self.cursor_position]
-            .chars()
-            .filter(|&c| c == '\n')
-            .count()
-            + 1
+        self.window_first_line + self.code.byte_to_line(self.cursor_position) + 1
     }
 
-    /// Returns the column number (0-based) where the cursor is located on the current line
+    /// Returns the column number (0-based, in characters) where the cursor is located on the current line
     pub fn get_cursor_column(&self) -> usize {
-        if let Some(last_newline_pos) = self.code[..self.cursor_position].rfind('\n') {
-            self.cursor_position - last_newline_pos - 1
-        } else {
-            self.cursor_position
+        let cursor_char = self.code.byte_to_char(self.cursor_position);
+        let line_start = self.code.line_to_char(self.code.char_to_line(cursor_char));
+        cursor_char - line_start
+    }
+
+    /// Returns the number of lines in the text, or None while a streamed file is still being read
+    pub fn get_total_lines(&self) -> Option<usize> {
+        if self.stream.as_ref().is_some_and(|stream| !stream.finished) {
+            return None;
         }
+        // A trailing newline doesn't start another line
+        let trailing_newline =
+            self.code.len_chars() > 0 && self.code.char(self.code.len_chars() - 1) == '\n';
+        Some(self.window_first_line + self.code.len_lines() - usize::from(trailing_newline))
+    }
+
+    /// Returns the number of whitespace-separated words, or None when streaming
+    pub fn get_word_count(&self) -> Option<usize> {
+        self.word_count
     }
 
     /// Returns the total length of all code
     pub fn get_total_length(&self) -> usize {
-        self.code.len()
+        match &self.stream {
+            // The file size can differ slightly from the text when CRLFs are normalized
+            Some(stream) => stream
+                .total_bytes
+                .max(self.window_offset + self.code.len_bytes()),
+            None => self.code.len_bytes(),
+        }
+    }
+
+    /// Returns the number of characters left to type. The part of a streamed file
+    /// that isn't read yet can only be counted in bytes.
+    pub fn get_remaining_chars(&self) -> usize {
+        let loaded = self.code.len_chars() - self.code.byte_to_char(self.cursor_position);
+        let unread = self
+            .get_total_length()
+            .saturating_sub(self.window_offset + self.code.len_bytes());
+        loaded + unread
     }
 
     /// Returns the progress as a percentage (0.0 to 1.0)
     pub fn get_progress(&self) -> f32 {
-        let total = self.code.len();
+        let total = self.get_total_length();
         if total == 0 {
             1.0
         } else {
-            self.cursor_position as f32 / total as f32
+            (self.get_cursor_position() as f32 / total as f32).min(1.0)
         }
     }
 
     /// Checks if all code has been typed
     pub fn is_complete(&self) -> bool {
-        self.cursor_position >= self.code.len()
+        self.cursor_position >= self.code.len_bytes()
+            && self.stream.as_ref().is_none_or(|stream| stream.finished)
     }
 
     /// Resets the state with new code
     pub fn reset(&mut self, new_code: String) {
-        self.code = new_code;
-        self.cursor_position = 0;
-        self.cached_colored_text = None;
+        *self = Self {
+            syntax_highlighting_enabled: self.syntax_highlighting_enabled,
+            highlighter: self.highlighter.clone(),
+            language: self.language,
+            ..Self::new(new_code)
+        };
     }
 
     /// Returns the next character that should be typed (without removing it)
     pub fn peek_next_character(&self) -> Option<char> {
-        self.code[self.cursor_position..].chars().next()
+        if self.cursor_position < self.code.len_bytes() {
+            Some(self.code.char(self.code.byte_to_char(self.cursor_position)))
+        } else {
+            None
+        }
+    }
+
+    /// True if the next character to type sits inside a right-to-left run. The runs
+    /// are kept for the line, as skipping a run asks again for every character of it.
+    pub fn in_rtl_run(&mut self) -> bool {
+        if self.cursor_position >= self.code.len_bytes() {
+            return false;
+        }
+        let line_index = self.code.byte_to_line(self.cursor_position);
+        let offset = self.cursor_position - self.code.line_to_byte(line_index);
+        if self
+            .cached_rtl_runs
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != line_index)
+        {
+            let line = self.code.line(line_index).to_string();
+            self.cached_rtl_runs = Some((line_index, char_utils::rtl_runs(&line)));
+        }
+        self.cached_rtl_runs
+            .as_ref()
+            .is_some_and(|(_, runs)| runs.iter().any(|run| run.contains(&offset)))
     }
 
     /// Returns a slice of the next N characters to be typed
     pub fn peek_next_chars(&self, count: usize) -> String {
-        self.code[self.cursor_position..]
+        self.code
+            .byte_slice(self.cursor_position..)
             .chars()
             .take(count)
             .collect()
@@ -160,29 +699,50 @@ impl CodeState {
     /// Consumes all whitespace characters (space, tab, newline) until the next non-whitespace character
     /// Returns the number of whitespace characters consumed
     pub fn consume_whitespace(&mut self) -> usize {
+        self.consume_while(char::is_whitespace)
+    }
+
+    /// Consumes whitespace up to the end of the line, leaving the line break to be typed
+    /// Returns the number of whitespace characters consumed
+    pub fn consume_line_whitespace(&mut self) -> usize {
+        self.consume_while(|ch| ch.is_whitespace() && ch != '\n')
+    }
+
+    fn consume_while(&mut self, consume: impl Fn(char) -> bool) -> usize {
         let mut consumed = 0;
 
-        while self.cursor_position < self.code.len() {
-            if let Some(ch) = self.code[self.cursor_position..].chars().next() {
-                if ch.is_whitespace() {
-                    self.cursor_position += ch.len_utf8();
-                    consumed += 1;
-                } else {
-                    break;
-                }
-            } else {
+        while let Some(ch) = self.peek_next_character() {
+            if !consume(ch) {
                 break;
             }
+            self.cursor_position += ch.len_utf8();
+            consumed += 1;
         }
 
         if consumed > 0 {
-            self.cached_colored_text = None;
+            self.clear_errors();
+            self.slide_window();
         }
 
         consumed
     }
 }
 
+/// Dims right-to-left runs so it is clear they are not shown in reading order
+fn mark_rtl_runs(code: &str, colored: &mut ColoredText) {
+    for (line, colored_line) in code.split('\n').zip(colored.lines.iter_mut()) {
+        let runs = char_utils::rtl_runs(line);
+        if runs.is_empty() {
+            continue;
+        }
+        for ((offset, _), colored_char) in line.char_indices().zip(colored_line.chars.iter_mut()) {
+            if runs.iter().any(|run| run.contains(&offset)) {
+                colored_char.color[3] *= RTL_RUN_ALPHA;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,6 +804,18 @@ mod tests {
         assert!(code_state.is_complete());
     }
 
+    #[test]
+    fn test_blank_text() {
+        assert_eq!(blank_reason(""), Some("is empty"));
+        assert_eq!(blank_reason(" \n\t\r\n"), Some("only has whitespace"));
+        assert_eq!(blank_reason("\n}\n"), None);
+
+        let code_state = CodeState::new(String::new());
+        assert_eq!(code_state.get_total_length(), 0);
+        assert_eq!(code_state.get_progress(), 1.0);
+        assert!(code_state.is_complete());
+    }
+
     #[test]
     fn test_peek_methods() {
         let code_state = CodeState::new("hello".to_string());
@@ -255,6 +827,31 @@ mod tests {
         assert_eq!(code_state.get_printed_code(), "");
     }
 
+    #[test]
+    fn test_edits_keep_the_cursor_on_its_character() {
+        let mut code_state = CodeState::new("let x = 1;\nx".to_string());
+        for _ in 0..4 {
+            code_state.type_character();
+        }
+        assert_eq!(code_state.get_word_count(), Some(5));
+
+        // Before the cursor it moves along, at the cursor the text is typed next
+        code_state.insert_text(0, "pub ");
+        assert_eq!(code_state.get_printed_code(), "pub let ");
+        code_state.insert_text(8, "mut ");
+        assert_eq!(code_state.get_current_code(), "mut x = 1;\nx");
+        assert_eq!(code_state.get_word_count(), Some(7));
+
+        code_state.remove_text(0..4);
+        assert_eq!(code_state.get_printed_code(), "let ");
+        // A removal over the cursor leaves it at the start of the removed text
+        code_state.remove_text(2..8);
+        assert_eq!(code_state.get_full_code(), "lex = 1;\nx");
+        assert_eq!(code_state.get_cursor_position(), 2);
+        assert_eq!(code_state.peek_next_character(), Some('x'));
+        assert_eq!(code_state.get_word_count(), Some(4));
+    }
+
     #[test]
     fn test_cursor_line_and_column() {
         let code = "fn main() {\n    println!(\"Hello\");\n}".to_string();
@@ -316,4 +913,194 @@ mod tests {
         assert_eq!(code_state.get_printed_code(), "fn   ");
         assert_eq!(code_state.get_current_code(), "main()");
     }
+
+    fn write_temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_streaming_keeps_bounded_window() {
+        let line = "let value = compute(42);\n";
+        let contents = line.repeat(20_000);
+        let path = write_temp_file("cargotap_test_stream.rs", &contents);
+
+        let mut code_state = CodeState::open_streaming(&path, 0).unwrap();
+        assert!(code_state.is_streaming());
+        assert_eq!(code_state.get_total_length(), contents.len());
+        assert!(code_state.get_full_code().len_bytes() < contents.len());
+
+        let target = line.len() * 10_000;
+        while code_state.get_cursor_position() < target {
+            code_state.type_character();
+        }
+
+        assert_eq!(code_state.get_cursor_line(), 10_001);
+        assert!(
+            code_state.get_full_code().len_bytes() <= STREAM_AHEAD_BYTES + 3 * STREAM_BEHIND_BYTES
+        );
+        assert!(code_state.take_dropped_lines() > 0);
+        assert_eq!(code_state.take_dropped_lines(), 0);
+        assert_eq!(code_state.peek_next_character(), Some('l'));
+
+        while code_state.type_character().is_some() {}
+        assert!(code_state.is_complete());
+        assert_eq!(code_state.get_cursor_position(), contents.len());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_streaming_resumes_mid_file() {
+        let contents = "first line\nsecond line\r\nthird line\n";
+        let path = write_temp_file("cargotap_test_stream_resume.rs", contents);
+
+        let code_state = CodeState::open_streaming(&path, 15).unwrap();
+        assert_eq!(code_state.first_line_number(), 1);
+        assert_eq!(code_state.get_cursor_position(), 15);
+        assert_eq!(code_state.get_cursor_line(), 2);
+        assert_eq!(code_state.peek_next_character(), Some('n'));
+        assert_eq!(code_state.get_full_code(), "second line\nthird line\n");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_document_stats() {
+        let mut state = CodeState::new("fn main() {\n    let é = 1;\n}\n".to_string());
+        assert_eq!(state.get_total_lines(), Some(3));
+        assert_eq!(state.get_word_count(), Some(8));
+
+        for _ in 0.."fn main() {\n    let é".chars().count() {
+            state.type_character();
+        }
+        assert_eq!(state.get_cursor_line(), 2);
+        // Counted in characters, so the two-byte 'é' is one column
+        assert_eq!(state.get_cursor_column(), 9);
+
+        assert_eq!(
+            CodeState::new("no newline".to_string()).get_total_lines(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_multibyte_navigation() {
+        let mut code_state = CodeState::new("é\nñx".to_string());
+
+        assert_eq!(code_state.type_character(), Some('é'));
+        assert_eq!(code_state.get_cursor_position(), 2);
+        assert_eq!(code_state.type_character(), Some('\n'));
+        assert_eq!(code_state.get_cursor_line(), 2);
+        assert_eq!(code_state.type_character(), Some('ñ'));
+        assert_eq!(code_state.get_cursor_column(), 1);
+        assert_eq!(code_state.get_line_break_count(), 1);
+
+        assert_eq!(code_state.backspace(), Some('ñ'));
+        assert_eq!(code_state.get_current_code(), "ñx");
+        assert_eq!(code_state.peek_next_chars(5), "ñx");
+        // Three bytes but two characters to go
+        assert_eq!(code_state.get_remaining_chars(), 2);
+    }
+
+    #[test]
+    fn test_line_positions_and_jumps() {
+        let mut code_state = CodeState::new("one\ntwo\nthree".to_string());
+
+        assert_eq!(code_state.line_start_position(1), Some(0));
+        assert_eq!(code_state.line_start_position(3), Some(8));
+        assert_eq!(code_state.line_start_position(4), Some(13));
+        assert_eq!(code_state.line_start_position(5), None);
+        assert_eq!(code_state.line_start_position(0), None);
+
+        assert!(code_state.set_cursor_position(4));
+        assert_eq!(code_state.peek_next_character(), Some('t'));
+        assert_eq!(code_state.get_cursor_line(), 2);
+        assert!(!code_state.set_cursor_position(100));
+    }
+
+    #[test]
+    fn test_accept_colors_rejects_stale_generation() {
+        let mut state = CodeState::new("let x = 1;".to_string());
+        let generation = state.missing_colors().unwrap();
+        let job = state.highlight_job();
+        assert_eq!(job.generation, generation);
+
+        state.set_syntax_highlighting(true);
+        let stale = highlight(&job.code, job.highlighter.as_deref());
+        assert!(!state.accept_colors(generation, stale));
+        assert!(state.missing_colors().is_some_and(|g| g != generation));
+
+        // Handing the same highlighter back keeps the colors being computed
+        let job = state.highlight_job();
+        state.set_highlighter(highlight::fallback());
+        assert_eq!(state.missing_colors(), Some(job.generation));
+        let colored = highlight(&job.code, job.highlighter.as_deref());
+        assert!(state.accept_colors(job.generation, colored));
+        assert_eq!(state.missing_colors(), None);
+    }
+
+    #[test]
+    fn test_typed_char_color() {
+        let mut state = CodeState::new("é\nab".to_string());
+        state.set_syntax_highlighting(true);
+        assert_eq!(state.typed_char_color(), None);
+        let colored = state.get_full_code_colored().clone();
+        let color = |line: usize, column: usize| Some(colored.lines[line].chars[column].color);
+
+        state.type_character();
+        assert_eq!(state.typed_char_color(), color(0, 0));
+        // Just past the line break
+        state.type_character();
+        assert_eq!(state.typed_char_color(), None);
+        state.type_character();
+        assert_eq!(state.typed_char_color(), color(1, 0));
+        state.type_character();
+        assert_eq!(state.typed_char_color(), color(1, 1));
+    }
+
+    #[test]
+    fn test_rtl_runs_are_marked_and_detected() {
+        let mut state = CodeState::new("x = 1; // שלום 2\ny".to_string());
+        assert!(!state.in_rtl_run());
+
+        let colored = state.get_full_code_colored();
+        let alphas: Vec<f32> = colored.lines[0].chars.iter().map(|c| c.color[3]).collect();
+        assert_eq!(alphas[0], 1.0);
+        assert!(alphas["x = 1; // ".len()] < 1.0);
+        assert!(alphas.iter().filter(|&&a| a < 1.0).count() == "שלום 2".chars().count());
+        assert_eq!(colored.lines[1].chars[0].color[3], 1.0);
+
+        for _ in 0.."x = 1; // ".len() {
+            state.type_character();
+        }
+        assert!(state.in_rtl_run());
+        for _ in 0.."שלום 2".chars().count() {
+            state.type_character();
+        }
+        assert_eq!(state.peek_next_character(), Some('\n'));
+        assert!(!state.in_rtl_run());
+
+        // The runs kept for the line go with an edit of it
+        let cursor = state.get_window_cursor_position();
+        state.insert_text(cursor, "שלום");
+        assert!(state.in_rtl_run());
+    }
+
+    #[test]
+    fn test_errors_stop_at_line_end() {
+        let mut state = CodeState::new("ab\ncd".to_string());
+        assert!(state.push_error('x'));
+        assert!(state.push_error('y'));
+        // Wrong keys don't spill onto the next line
+        assert!(!state.push_error('z'));
+        assert_eq!(state.pending_errors(), ['x', 'y']);
+        assert_eq!(state.pop_error(), Some('y'));
+
+        state.mark_missed();
+        state.set_cursor_position(3);
+        assert!(state.pending_errors().is_empty());
+        assert!(!state.is_missed());
+    }
 }
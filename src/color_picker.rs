@@ -0,0 +1,279 @@
+//! HSV color picker for editing theme colors
+//!
+//! This module provides the state behind the settings screen: which
+//! ColorConfig entry is selected, which HSV component is being adjusted,
+//! and a snapshot of the theme so edits can be reverted on cancel.
+
+use crate::config::ColorConfig;
+
+/// Hue step in degrees for a single adjustment
+const HUE_STEP: f32 = 10.0;
+
+/// Saturation/value step for a single adjustment
+const LEVEL_STEP: f32 = 0.05;
+
+/// A color in HSV space (hue in degrees 0..360, saturation and value in 0..1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+impl Hsv {
+    /// Converts an RGB color (components in 0..1) to HSV
+    pub fn from_rgb(rgb: [f32; 3]) -> Self {
+        let [r, g, b] = rgb;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let h = if delta <= f32::EPSILON {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+
+        let s = if max <= f32::EPSILON {
+            0.0
+        } else {
+            delta / max
+        };
+
+        Self { h, s, v: max }
+    }
+
+    /// Converts this HSV color to RGB (components in 0..1)
+    pub fn to_rgb(self) -> [f32; 3] {
+        let h = self.h.rem_euclid(360.0);
+        let c = self.v * self.s;
+        let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+        let m = self.v - c;
+
+        let (r, g, b) = match (h / 60.0) as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        [r + m, g + m, b + m]
+    }
+
+    /// Converts this HSV color to RGBA with the given alpha
+    pub fn to_rgba(self, alpha: f32) -> [f32; 4] {
+        let [r, g, b] = self.to_rgb();
+        [r, g, b, alpha]
+    }
+}
+
+/// The HSV component currently being adjusted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HsvComponent {
+    Hue,
+    Saturation,
+    Value,
+}
+
+impl HsvComponent {
+    fn next(self) -> Self {
+        match self {
+            HsvComponent::Hue => HsvComponent::Saturation,
+            HsvComponent::Saturation => HsvComponent::Value,
+            HsvComponent::Value => HsvComponent::Hue,
+        }
+    }
+}
+
+/// Interactive color picker state for the settings screen
+#[derive(Debug, Clone)]
+pub struct ColorPicker {
+    /// Index into `ColorConfig::FIELD_NAMES`
+    selected_entry: usize,
+    /// Component adjusted by left/right
+    component: HsvComponent,
+    /// HSV value of the selected entry
+    hsv: Hsv,
+    /// Alpha of the selected entry (preserved while editing)
+    alpha: f32,
+    /// Theme as it was when the picker was opened, used to revert on cancel
+    original: Option<ColorConfig>,
+}
+
+impl ColorPicker {
+    pub fn new() -> Self {
+        Self {
+            selected_entry: 0,
+            component: HsvComponent::Hue,
+            hsv: Hsv {
+                h: 0.0,
+                s: 0.0,
+                v: 0.0,
+            },
+            alpha: 1.0,
+            original: None,
+        }
+    }
+
+    /// Starts editing, remembering the current theme for cancel
+    pub fn open(&mut self, colors: &ColorConfig) {
+        self.original = Some(colors.clone());
+        self.component = HsvComponent::Hue;
+        self.load_selected(colors);
+    }
+
+    /// Keeps the edited theme and forgets the snapshot
+    pub fn confirm(&mut self) {
+        self.original = None;
+    }
+
+    /// Restores the theme captured by `open`
+    pub fn cancel(&mut self, colors: &mut ColorConfig) {
+        if let Some(original) = self.original.take() {
+            *colors = original;
+        }
+    }
+
+    pub fn selected_entry(&self) -> usize {
+        self.selected_entry
+    }
+
+    pub fn selected_name(&self) -> &'static str {
+        ColorConfig::FIELD_NAMES
+            .get(self.selected_entry)
+            .copied()
+            .unwrap_or("")
+    }
+
+    pub fn component(&self) -> HsvComponent {
+        self.component
+    }
+
+    pub fn hsv(&self) -> Hsv {
+        self.hsv
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub fn select_next_entry(&mut self, colors: &ColorConfig) {
+        let count = ColorConfig::FIELD_NAMES.len();
+        self.selected_entry = (self.selected_entry + 1) % count;
+        self.load_selected(colors);
+    }
+
+    pub fn select_previous_entry(&mut self, colors: &ColorConfig) {
+        let count = ColorConfig::FIELD_NAMES.len();
+        self.selected_entry = (self.selected_entry + count - 1) % count;
+        self.load_selected(colors);
+    }
+
+    pub fn select_next_component(&mut self) {
+        self.component = self.component.next();
+    }
+
+    /// Adjusts the active component by `steps` and writes the result into the theme
+    pub fn adjust(&mut self, steps: i32, colors: &mut ColorConfig) {
+        let steps = steps as f32;
+        match self.component {
+            HsvComponent::Hue => {
+                self.hsv.h = (self.hsv.h + steps * HUE_STEP).rem_euclid(360.0);
+            }
+            HsvComponent::Saturation => {
+                self.hsv.s = (self.hsv.s + steps * LEVEL_STEP).clamp(0.0, 1.0);
+            }
+            HsvComponent::Value => {
+                self.hsv.v = (self.hsv.v + steps * LEVEL_STEP).clamp(0.0, 1.0);
+            }
+        }
+
+        if let Some(color) = colors.field_mut(self.selected_name()) {
+            *color = self.hsv.to_rgba(self.alpha);
+        }
+    }
+
+    fn load_selected(&mut self, colors: &ColorConfig) {
+        if let Some(color) = colors.field(self.selected_name()) {
+            self.hsv = Hsv::from_rgb([color[0], color[1], color[2]]);
+            self.alpha = color[3];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rgb_close(a: [f32; 3], b: [f32; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 0.001, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_hsv_roundtrip() {
+        for rgb in [
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.5, 0.8, 1.0],
+            [0.9, 0.9, 0.9],
+            [0.0, 0.0, 0.0],
+        ] {
+            assert_rgb_close(Hsv::from_rgb(rgb).to_rgb(), rgb);
+        }
+    }
+
+    #[test]
+    fn test_hsv_primary_hues() {
+        assert_eq!(Hsv::from_rgb([1.0, 0.0, 0.0]).h, 0.0);
+        assert_eq!(Hsv::from_rgb([0.0, 1.0, 0.0]).h, 120.0);
+        assert_eq!(Hsv::from_rgb([0.0, 0.0, 1.0]).h, 240.0);
+    }
+
+    #[test]
+    fn test_adjust_updates_theme() {
+        let mut colors = ColorConfig::default();
+        let mut picker = ColorPicker::new();
+        picker.open(&colors);
+
+        let before = colors.background;
+        picker.select_next_component();
+        picker.select_next_component();
+        picker.adjust(4, &mut colors);
+
+        assert_eq!(picker.selected_name(), "background");
+        assert!(colors.background[0] > before[0]);
+        assert_eq!(colors.background[3], before[3]);
+    }
+
+    #[test]
+    fn test_cancel_restores_theme() {
+        let mut colors = ColorConfig::default();
+        let mut picker = ColorPicker::new();
+        picker.open(&colors);
+
+        picker.select_next_entry(&colors);
+        picker.adjust(3, &mut colors);
+        picker.cancel(&mut colors);
+
+        assert_eq!(colors.text_default, ColorConfig::default().text_default);
+    }
+
+    #[test]
+    fn test_entry_selection_wraps() {
+        let colors = ColorConfig::default();
+        let mut picker = ColorPicker::new();
+        picker.select_previous_entry(&colors);
+        assert_eq!(picker.selected_entry(), ColorConfig::FIELD_NAMES.len() - 1);
+        picker.select_next_entry(&colors);
+        assert_eq!(picker.selected_entry(), 0);
+    }
+}
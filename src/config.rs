@@ -10,11 +10,16 @@
 //! Configuration is loaded from a TOML file (config.toml) in the project root.
 //! If the file doesn't exist, default values are used.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
+use crate::quotes::QuoteLength;
+use crate::secrets;
+use crate::session_state::{EndCondition, Warmup};
+use crate::symbol_hints::KeyboardLayout;
+
 /// Main configuration structure for CargoTap application
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -26,6 +31,10 @@ pub struct Config {
     #[serde(default)]
     pub text: TextConfig,
 
+    /// Accessibility settings
+    #[serde(default)]
+    pub accessibility: AccessibilityConfig,
+
     /// Gameplay settings
     #[serde(default)]
     pub gameplay: GameplayConfig,
@@ -37,6 +46,58 @@ pub struct Config {
     /// Color scheme settings
     #[serde(default)]
     pub colors: ColorConfig,
+
+    /// Event scripting settings
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+
+    /// Notification settings
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+
+    /// Pomodoro work/break cycle settings
+    #[serde(default)]
+    pub pomodoro: PomodoroConfig,
+
+    /// Metronome pacing settings
+    #[serde(default)]
+    pub metronome: MetronomeConfig,
+
+    /// Discord Rich Presence settings
+    #[serde(default)]
+    pub discord: DiscordConfig,
+
+    /// Daily practice commitment settings
+    #[serde(default)]
+    pub practice_lock: PracticeLockConfig,
+
+    /// Session score settings
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+
+    /// Statistics screen settings
+    #[serde(default)]
+    pub statistics: StatisticsConfig,
+
+    /// Syntax highlighting word lists
+    #[serde(default)]
+    pub syntax: SyntaxConfig,
+
+    /// Quick-launch daemon settings
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+
+    /// Practice code fetched from GitHub
+    #[serde(default)]
+    pub github: GithubConfig,
+
+    /// Smooth scrolling and fading in the code view
+    #[serde(default)]
+    pub animation: AnimationConfig,
+
+    /// Sounds of `gameplay.audio_feedback`
+    #[serde(default)]
+    pub audio: AudioConfig,
 }
 
 /// Window configuration
@@ -54,8 +115,38 @@ pub struct WindowConfig {
     /// Enable VSync
     pub vsync: bool,
 
-    /// Target frame rate (0 = unlimited)
+    /// Target frame rate (0 = unlimited); the refresh rate assumed when the
+    /// monitor doesn't report one
     pub target_fps: u32,
+
+    /// The HUD and session timer are updated every this many monitor refreshes
+    #[serde(default = "default_hud_refresh_divisor")]
+    pub hud_refresh_divisor: u32,
+
+    /// Opacity of the window background, from 0.2 to 1.0 (opaque). Below 1.0 what is
+    /// behind the window shows through where the compositor supports it.
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+
+    /// Open the window where it was last closed, at that size and fullscreen if it
+    /// was, instead of at `width` x `height`
+    #[serde(default = "default_remember_geometry")]
+    pub remember_geometry: bool,
+}
+
+/// The most transparent the window gets, so the text stays readable
+pub const MIN_OPACITY: f32 = 0.2;
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_remember_geometry() -> bool {
+    true
+}
+
+fn default_hud_refresh_divisor() -> u32 {
+    6
 }
 
 impl Default for WindowConfig {
@@ -66,6 +157,9 @@ impl Default for WindowConfig {
             height: 720,
             vsync: true,
             target_fps: 60,
+            hud_refresh_divisor: default_hud_refresh_divisor(),
+            opacity: default_opacity(),
+            remember_geometry: default_remember_geometry(),
         }
     }
 }
@@ -99,6 +193,245 @@ pub struct TextConfig {
 
     /// Show line numbers in the left column
     pub show_line_numbers: bool,
+
+    /// Wrap long lines onto the next row instead of cutting them off at the window edge
+    #[serde(default = "default_wrap_lines")]
+    pub wrap_lines: bool,
+
+    /// Collapse function bodies that have been typed to the end into one row
+    #[serde(default)]
+    pub fold_typed: bool,
+
+    /// Draw marks for spaces (·), tabs (→) and line breaks (¶) in the code view
+    #[serde(default)]
+    pub show_whitespace: bool,
+
+    /// On a file's first load, zoom out so its widest line fits: "apply", "suggest" or "off"
+    #[serde(default)]
+    pub fit_font_size: FontFit,
+
+    /// Upcoming lines shown magnified in a pane at the bottom of the window (0 hides it, at most 2)
+    #[serde(default)]
+    pub preview_lines: usize,
+
+    /// Size of the preview pane text relative to the code
+    #[serde(default = "default_preview_scale")]
+    pub preview_scale: f32,
+
+    /// Drop shadow or outline behind glyphs
+    #[serde(default)]
+    pub shadow: TextShadowConfig,
+
+    /// Shape and animation of the caret
+    #[serde(default)]
+    pub caret: CaretConfig,
+
+    /// Fonts tried in order for characters the main font lacks (emoji, symbols);
+    /// files that don't exist are skipped
+    #[serde(default = "default_fallback_fonts")]
+    pub fallback_fonts: Vec<String>,
+}
+
+/// Symbol and emoji fonts that ship with common Linux distributions, macOS and Windows
+fn default_fallback_fonts() -> Vec<String> {
+    [
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSansSymbols2-Regular.ttf",
+        "/usr/share/fonts/noto/NotoSansSymbols2-Regular.ttf",
+        "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+        "/usr/share/fonts/noto/NotoEmoji-Regular.ttf",
+        "/System/Library/Fonts/Apple Symbols.ttf",
+        "/Library/Fonts/Arial Unicode.ttf",
+        "C:\\Windows\\Fonts\\seguisym.ttf",
+        "C:\\Windows\\Fonts\\seguiemj.ttf",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+/// Lines kept visible below the cursor in follow scrolling, so the code ahead shows
+const FOLLOW_MARGIN_LINES: usize = 2;
+
+/// What a wrong key does to the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorMode {
+    /// The cursor waits for the right key, with the expected character in red
+    #[default]
+    Block,
+    /// The cursor moves on over red characters, which have to be backspaced
+    /// before the text can be typed further
+    Insert,
+}
+
+/// How the code view keeps up with the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollMode {
+    /// Only the scroll keys move the view
+    Manual,
+    /// The view scrolls once the cursor comes near its bottom or goes above its top
+    #[default]
+    Follow,
+    /// The cursor line stays in the middle of the view
+    Centered,
+}
+
+impl ScrollMode {
+    /// Scroll offset that keeps the cursor where this mode wants it, for a view
+    /// scrolled to `offset` that shows `visible_lines` lines; `cursor_line` counts
+    /// from the first line of the text. None when the view stays where it is.
+    pub fn offset(self, offset: usize, cursor_line: usize, visible_lines: usize) -> Option<usize> {
+        if visible_lines == 0 {
+            // Nothing laid out yet, so there's no telling what is visible
+            return None;
+        }
+        let margin = FOLLOW_MARGIN_LINES.min((visible_lines - 1) / 2);
+        let wanted = match self {
+            ScrollMode::Manual => return None,
+            ScrollMode::Follow if cursor_line < offset + margin => {
+                cursor_line.saturating_sub(margin)
+            }
+            ScrollMode::Follow if cursor_line + margin >= offset + visible_lines => {
+                cursor_line + margin + 1 - visible_lines
+            }
+            ScrollMode::Follow => return None,
+            ScrollMode::Centered => cursor_line.saturating_sub(visible_lines / 2),
+        };
+        (wanted != offset).then_some(wanted)
+    }
+}
+
+/// Shape of the caret
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaretStyle {
+    /// Tints the whole character
+    #[default]
+    Block,
+    /// A line under the character
+    Underline,
+    /// A thin bar before the character
+    Bar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaretConfig {
+    pub style: CaretStyle,
+
+    /// How long the caret stays on and then off when blinking, in milliseconds (0 keeps it on).
+    /// Each keystroke starts it over, so the caret doesn't blink while you type
+    pub blink_ms: u64,
+
+    /// How long the caret takes to slide to the next character, in milliseconds (0 jumps)
+    pub slide_ms: u64,
+}
+
+impl Default for CaretConfig {
+    fn default() -> Self {
+        Self {
+            style: CaretStyle::Block,
+            blink_ms: 0,
+            slide_ms: 0,
+        }
+    }
+}
+
+/// What is drawn behind each glyph
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEffect {
+    #[default]
+    None,
+    /// One copy of the glyph, offset down and to the right
+    Shadow,
+    /// Copies of the glyph all around it
+    Outline,
+}
+
+/// What happens when a file opened for the first time has lines wider than the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FontFit {
+    /// Zoom the file out so its widest line fits (saved like a manual zoom)
+    #[default]
+    Apply,
+    /// Only say which zoom would fit
+    Suggest,
+    Off,
+}
+
+/// Accessibility configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilityConfig {
+    /// Scale all text, the caret and character spacing by `ui_scale` (Ctrl+Shift+B toggles it)
+    pub low_vision: bool,
+
+    /// How much larger everything is drawn in low-vision mode
+    pub ui_scale: f32,
+
+    /// Draw text art meant for pasting elsewhere (the result card) with ASCII only
+    pub ascii_ui: bool,
+}
+
+impl Default for AccessibilityConfig {
+    fn default() -> Self {
+        Self {
+            low_vision: false,
+            ui_scale: 1.5,
+            ascii_ui: false,
+        }
+    }
+}
+
+/// Drop shadow or outline to keep text readable over busy backgrounds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextShadowConfig {
+    pub effect: TextEffect,
+
+    /// Shadow or outline color (RGBA)
+    pub color: [f32; 4],
+
+    /// Shadow offset in pixels (x, y); outlines are as thick as the larger of the two
+    pub offset: [f32; 2],
+}
+
+impl TextShadowConfig {
+    /// Offsets at which a copy of each glyph is drawn behind it
+    pub fn offsets(&self) -> Vec<[f32; 2]> {
+        let [x, y] = self.offset;
+        match self.effect {
+            TextEffect::None => Vec::new(),
+            TextEffect::Shadow => vec![[x, y]],
+            TextEffect::Outline => {
+                let t = x.abs().max(y.abs());
+                vec![
+                    [-t, -t],
+                    [0.0, -t],
+                    [t, -t],
+                    [-t, 0.0],
+                    [t, 0.0],
+                    [-t, t],
+                    [0.0, t],
+                    [t, t],
+                ]
+            }
+        }
+    }
+}
+
+impl Default for TextShadowConfig {
+    fn default() -> Self {
+        Self {
+            effect: TextEffect::None,
+            color: [0.0, 0.0, 0.0, 0.8],
+            offset: [2.0, 2.0],
+        }
+    }
 }
 
 impl Default for TextConfig {
@@ -113,23 +446,50 @@ impl Default for TextConfig {
             syntax_highlighting: true,
             rainbow_effects: true,
             show_line_numbers: true,
+            wrap_lines: true,
+            fold_typed: false,
+            show_whitespace: false,
+            fit_font_size: FontFit::default(),
+            preview_lines: 0,
+            preview_scale: default_preview_scale(),
+            shadow: TextShadowConfig::default(),
+            caret: CaretConfig::default(),
+            fallback_fonts: default_fallback_fonts(),
         }
     }
 }
 
+fn default_wrap_lines() -> bool {
+    true
+}
+
+fn default_preview_scale() -> f32 {
+    1.5
+}
+
 /// Gameplay configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameplayConfig {
     /// Source code file to practice typing (if set, overrides demo code)
     pub custom_code_path: Option<String>,
 
+    /// Directory relative paths typed into the file prompt start from (~ and $VARS work)
+    #[serde(default)]
+    pub workspace_root: Option<String>,
+
     /// Enable backspace functionality
     pub allow_backspace: bool,
 
+    /// What a wrong key does: "block" waits for the right key, "insert" moves on
+    /// and the mistakes have to be backspaced
+    #[serde(default)]
+    pub error_mode: ErrorMode,
+
     /// Show typing statistics
     pub show_statistics: bool,
 
-    /// Enable audio feedback (for future implementation)
+    /// Click on correct keys, buzz on mistakes and chime at the end of a session
+    /// (volume and sounds under `[audio]`)
     pub audio_feedback: bool,
 
     /// Strict mode (no backspace, no mistakes allowed)
@@ -141,43 +501,246 @@ pub struct GameplayConfig {
     /// Number of lines to scroll when using scroll shortcut (Command+J)
     pub scroll_lines: usize,
 
+    /// Keep the view on the cursor line; the scroll keys only peek
+    #[serde(default)]
+    pub scroll_lock: bool,
+
+    /// Seconds a peek with the scroll keys lasts before the view snaps back
+    #[serde(default = "default_scroll_peek_secs")]
+    pub scroll_peek_secs: f64,
+
+    /// How the view follows the cursor when scroll lock is off: "manual", "follow"
+    /// or "centered"
+    #[serde(default)]
+    pub scroll_mode: ScrollMode,
+
     /// Session duration in minutes (timer for typing sessions)
     pub session_duration_minutes: f64,
 
+    /// What ends a session: "time" (the timer above), or { chars = N }, { lines = N }
+    /// or { errors = N } to end after N characters, N lines or more than N errors
+    #[serde(default)]
+    pub end_condition: EndCondition,
+
     /// Auto-skip characters that cannot be typed on a US keyboard (emoji, Arabic, etc.)
+    /// Whole right-to-left runs are skipped as well, digits and spaces included
     pub auto_skip_untypeable: bool,
 
+    /// Start new files after their leading license comment instead of typing it
+    #[serde(default)]
+    pub skip_license_headers: bool,
+
+    /// What to do with lines of practice files that look like secrets (API keys,
+    /// private keys, passwords): "warn", "mask", "skip" or "off"
+    #[serde(default)]
+    pub secrets: secrets::Mode,
+
+    /// Show an analysis of files opened from the picker before practicing them
+    #[serde(default = "default_show_file_analysis")]
+    pub show_file_analysis: bool,
+
+    /// Leave lines with characters that can't be typed on a US keyboard out of files
+    #[serde(default)]
+    pub strip_untypeable_lines: bool,
+
     /// Hotkey to manually skip the current character (Ctrl+S or Cmd+S)
     pub enable_manual_skip: bool,
+
+    /// Length of quotes picked in quote mode: "short", "medium", "long" or "any"
+    #[serde(default)]
+    pub quote_length: QuoteLength,
+
+    /// Extra quotes file (JSON list of { text, author }); defaults to quotes.json in the data directory
+    #[serde(default)]
+    pub quotes_path: Option<String>,
+
+    /// Fixed seed for quotes and drills so the same text can be practiced again; random when unset
+    #[serde(default, alias = "drill_seed")]
+    pub seed: Option<u64>,
+
+    /// Times each mistyped line is typed in a review round (V on the results screen)
+    #[serde(default = "default_review_repetitions")]
+    pub review_repetitions: usize,
+
+    /// Lines typed in one file of a split before switching to the other (0: only on the hotkey)
+    #[serde(default = "default_split_switch_lines")]
+    pub split_switch_lines: usize,
+
+    /// Consecutive mismatches that pause input with a "check hand position" hint (0 disables)
+    #[serde(default = "default_dead_zone_streak")]
+    pub dead_zone_streak: usize,
+
+    /// Files at least this large (in MB) are streamed in a window around the cursor (0 disables)
+    #[serde(default = "default_streaming_threshold_mb")]
+    pub streaming_threshold_mb: u64,
+
+    /// Generated or vendored paths hidden from the file picker ("dir/" for directories, "*" wildcards)
+    #[serde(default = "crate::ignore::default_patterns")]
+    pub ignore_patterns: Vec<String>,
+
+    /// Live accuracy (percent) below which the session HUD turns red; unset disables the alarm
+    #[serde(default)]
+    pub accuracy_floor: Option<f64>,
+
+    /// Sound an alarm when accuracy drops below `accuracy_floor`, even with
+    /// `audio_feedback` off
+    #[serde(default)]
+    pub accuracy_alarm_bell: bool,
+
+    /// Explain the key combination of a symbol that keeps going wrong, once per symbol
+    #[serde(default = "default_symbol_hints")]
+    pub symbol_hints: bool,
+
+    /// Layout the symbol hints and the home row reminder describe: "us", "uk" or "de"
+    #[serde(default)]
+    pub keyboard_layout: KeyboardLayout,
+
+    /// Name of the keyboard in use, saved with each session to compare setups
+    #[serde(default)]
+    pub keyboard_name: Option<String>,
+
+    /// Save the setup (keyboard name, window and font size, theme, version) with sessions
+    #[serde(default = "default_record_session_context")]
+    pub record_session_context: bool,
+
+    /// Show the home row before each session and wait for F and J before a short countdown
+    #[serde(default)]
+    pub home_row_reminder: bool,
+
+    /// Pause the running session when the window loses focus
+    #[serde(default = "default_pause_on_focus_loss")]
+    pub pause_on_focus_loss: bool,
+
+    /// Seconds at the start of each session left out of its WPM and accuracy (0 disables)
+    #[serde(default)]
+    pub warmup_secs: f64,
+
+    /// Characters at the start of each session left out of its WPM and accuracy (0 disables)
+    #[serde(default)]
+    pub warmup_chars: usize,
+}
+
+fn default_symbol_hints() -> bool {
+    true
+}
+
+fn default_show_file_analysis() -> bool {
+    true
+}
+
+fn default_record_session_context() -> bool {
+    true
+}
+
+fn default_pause_on_focus_loss() -> bool {
+    true
+}
+
+fn default_scroll_peek_secs() -> f64 {
+    3.0
+}
+
+fn default_review_repetitions() -> usize {
+    3
+}
+
+fn default_split_switch_lines() -> usize {
+    5
+}
+
+fn default_dead_zone_streak() -> usize {
+    5
+}
+
+fn default_streaming_threshold_mb() -> u64 {
+    8
+}
+
+impl GameplayConfig {
+    pub fn streaming_threshold_bytes(&self) -> u64 {
+        self.streaming_threshold_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn warmup(&self) -> Warmup {
+        Warmup {
+            secs: self.warmup_secs.max(0.0),
+            chars: self.warmup_chars,
+        }
+    }
 }
 
 impl Default for GameplayConfig {
     fn default() -> Self {
         Self {
             custom_code_path: None,
+            workspace_root: None,
             allow_backspace: true,
+            error_mode: ErrorMode::default(),
             show_statistics: true,
             audio_feedback: false,
             strict_mode: false,
             show_next_char_hint: true,
             scroll_lines: 5,
+            scroll_lock: false,
+            scroll_peek_secs: default_scroll_peek_secs(),
+            scroll_mode: ScrollMode::default(),
             session_duration_minutes: 3.0,
+            end_condition: EndCondition::default(),
             auto_skip_untypeable: true,
+            skip_license_headers: false,
+            secrets: secrets::Mode::default(),
+            show_file_analysis: true,
+            strip_untypeable_lines: false,
             enable_manual_skip: true,
+            quote_length: QuoteLength::default(),
+            quotes_path: None,
+            seed: None,
+            review_repetitions: default_review_repetitions(),
+            split_switch_lines: default_split_switch_lines(),
+            dead_zone_streak: default_dead_zone_streak(),
+            streaming_threshold_mb: default_streaming_threshold_mb(),
+            ignore_patterns: crate::ignore::default_patterns(),
+            accuracy_floor: None,
+            accuracy_alarm_bell: false,
+            symbol_hints: default_symbol_hints(),
+            keyboard_layout: KeyboardLayout::default(),
+            keyboard_name: None,
+            record_session_context: default_record_session_context(),
+            home_row_reminder: false,
+            pause_on_focus_loss: default_pause_on_focus_loss(),
+            warmup_secs: 0.0,
+            warmup_chars: 0,
         }
     }
 }
 
+/// How log records are written (see `logging`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Colored, with timestamps
+    #[default]
+    Pretty,
+    /// Like pretty, without colors
+    Plain,
+    /// One JSON object per line on stderr, with structured fields
+    Json,
+}
+
 /// Debug configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DebugConfig {
     /// Log level: "trace", "debug", "info", "warn", "error"
     pub log_level: String,
 
+    /// Log format: "pretty", "plain" or "json"
+    #[serde(default)]
+    pub log_format: LogFormat,
+
     /// Enable Vulkan validation layers
     pub vulkan_validation: bool,
 
-    /// Print frame timing information
+    /// Show a rolling graph of recent frame times under the FPS line
     pub show_frame_times: bool,
 
     /// Print memory usage information
@@ -198,6 +761,36 @@ pub struct DebugConfig {
     /// Show detailed profiling information on screen
     pub show_profiling_info: bool,
 
+    /// Warn on screen when the median keystroke latency goes above this many ms (0 disables)
+    #[serde(default = "default_key_latency_warn_ms")]
+    pub key_latency_warn_ms: f64,
+
+    /// Time a text update may take (ms) before layout is cut back: rainbow effects
+    /// off, fewer code lines, then relayout deferred while typing (0 disables)
+    #[serde(default = "default_text_update_budget_ms")]
+    pub text_update_budget_ms: f64,
+
+    /// Write a diagnostic snapshot to the log and stutters.log when a frame is slow
+    #[serde(default)]
+    pub capture_stutters: bool,
+
+    /// Frames at least this long (ms) count as stutters
+    #[serde(default = "default_stutter_threshold_ms")]
+    pub stutter_threshold_ms: f64,
+
+    /// Seconds to wait after a snapshot before taking another one
+    #[serde(default = "default_stutter_capture_interval_secs")]
+    pub stutter_capture_interval_secs: f64,
+
+    /// Rewrite state.json with the app state this often (seconds, 0 disables); it
+    /// is also written on Ctrl+Shift+I
+    #[serde(default)]
+    pub state_dump_interval_secs: f64,
+
+    /// Save the keys of sessions on built-in texts to traces.jsonl for replay tests
+    #[serde(default)]
+    pub record_traces: bool,
+
     /// Save debug logs to file
     pub save_logs_to_file: bool,
 
@@ -205,10 +798,27 @@ pub struct DebugConfig {
     pub log_file_path: String,
 }
 
+fn default_key_latency_warn_ms() -> f64 {
+    16.0
+}
+
+fn default_text_update_budget_ms() -> f64 {
+    2.0
+}
+
+fn default_stutter_threshold_ms() -> f64 {
+    50.0
+}
+
+fn default_stutter_capture_interval_secs() -> f64 {
+    60.0
+}
+
 impl Default for DebugConfig {
     fn default() -> Self {
         Self {
             log_level: "info".to_string(),
+            log_format: LogFormat::default(),
             vulkan_validation: false,
             show_frame_times: false,
             show_memory_usage: false,
@@ -217,6 +827,13 @@ impl Default for DebugConfig {
             log_code_state: true,
             show_fps: false,
             show_profiling_info: false,
+            key_latency_warn_ms: default_key_latency_warn_ms(),
+            text_update_budget_ms: default_text_update_budget_ms(),
+            capture_stutters: false,
+            stutter_threshold_ms: default_stutter_threshold_ms(),
+            stutter_capture_interval_secs: default_stutter_capture_interval_secs(),
+            state_dump_interval_secs: 0.0,
+            record_traces: false,
             save_logs_to_file: false,
             log_file_path: "cargotap_debug.log".to_string(),
         }
@@ -251,6 +868,34 @@ pub struct ColorConfig {
     pub syntax_comment: [f32; 4],
     pub syntax_number: [f32; 4],
     pub syntax_function: [f32; 4],
+
+    /// Marks for spaces and tabs still to type, with `text.show_whitespace`
+    #[serde(default = "default_whitespace_color")]
+    pub text_whitespace: [f32; 4],
+
+    /// Marks for line breaks, with `text.show_whitespace`
+    #[serde(default = "default_newline_color")]
+    pub text_newline: [f32; 4],
+}
+
+fn default_whitespace_color() -> [f32; 4] {
+    [0.35, 0.35, 0.4, 1.0]
+}
+
+fn default_newline_color() -> [f32; 4] {
+    [0.35, 0.45, 0.6, 1.0]
+}
+
+impl ColorConfig {
+    /// "default" for the built-in colors, "custom-" and a hash of the colors otherwise
+    pub fn theme_id(&self) -> String {
+        let colors = toml::to_string(self).unwrap_or_default();
+        if colors == toml::to_string(&Self::default()).unwrap_or_default() {
+            "default".to_string()
+        } else {
+            format!("custom-{}", crate::progress_storage::compute_hash(&colors))
+        }
+    }
 }
 
 impl Default for ColorConfig {
@@ -268,6 +913,436 @@ impl Default for ColorConfig {
             syntax_comment: [0.5, 0.5, 0.5, 1.0],
             syntax_number: [1.0, 0.8, 0.4, 1.0],
             syntax_function: [0.8, 0.6, 1.0, 1.0],
+            text_whitespace: default_whitespace_color(),
+            text_newline: default_newline_color(),
+        }
+    }
+}
+
+/// Event scripting configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptingConfig {
+    /// Run the event script while typing
+    pub enabled: bool,
+
+    /// Path to a Rhai script defining on_char_accepted, on_char_rejected
+    /// and/or on_session_finished hooks
+    pub script_path: Option<String>,
+}
+
+/// Notification configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// URL that receives a JSON POST with the stats of every completed session
+    pub webhook_url: Option<String>,
+
+    /// How many times a failed webhook delivery is retried (with backoff)
+    #[serde(default = "default_webhook_max_retries")]
+    pub webhook_max_retries: u32,
+
+    /// Time of day ("HH:MM") for a desktop reminder if nothing was practiced that day
+    #[serde(default)]
+    pub reminder_time: Option<String>,
+
+    /// How long "remind me later" on the reminder waits, in minutes
+    #[serde(default = "default_reminder_snooze_minutes")]
+    pub reminder_snooze_minutes: u32,
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_reminder_snooze_minutes() -> u32 {
+    60
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            webhook_max_retries: default_webhook_max_retries(),
+            reminder_time: None,
+            reminder_snooze_minutes: default_reminder_snooze_minutes(),
+        }
+    }
+}
+
+/// Pomodoro configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    /// Alternate timed sessions with breaks
+    pub enabled: bool,
+
+    /// Length of a work period in minutes (replaces session_duration_minutes)
+    pub work_minutes: f64,
+
+    /// Length of a short break in minutes
+    pub break_minutes: f64,
+
+    /// Length of the long break in minutes
+    pub long_break_minutes: f64,
+
+    /// Every this many work periods the break is a long one (0 disables long breaks)
+    pub cycles_before_long_break: usize,
+
+    /// Show a desktop notification when a break starts or ends
+    pub notify: bool,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            work_minutes: 25.0,
+            break_minutes: 5.0,
+            long_break_minutes: 15.0,
+            cycles_before_long_break: 4,
+            notify: true,
+        }
+    }
+}
+
+/// What happens to a keystroke that comes too soon after the last one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetronomeMode {
+    /// The key is ignored; type it again on the beat
+    Reject,
+    /// The key is typed and counted as too fast
+    Flag,
+}
+
+/// Metronome configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetronomeConfig {
+    /// Hold typing to a maximum pace
+    pub enabled: bool,
+
+    /// Shortest time between two keystrokes in milliseconds (200 is 60 WPM)
+    pub interval_ms: u64,
+
+    pub mode: MetronomeMode,
+
+    /// Tick on every beat, even with `gameplay.audio_feedback` off
+    pub audible: bool,
+}
+
+impl Default for MetronomeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 200,
+            mode: MetronomeMode::Reject,
+            audible: false,
+        }
+    }
+}
+
+/// Quick-launch daemon configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Allow `cargotap daemon` and let `cargotap open` hand files to it
+    pub enabled: bool,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Where files for the GitHub prompt (Ctrl+Shift+O) come from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GithubConfig {
+    /// Base URL of the REST API, used to list a repository's files
+    pub api_url: String,
+
+    /// Base URL files are downloaded from
+    pub raw_url: String,
+
+    /// Branch, tag or commit used when the prompt names none
+    pub branch: String,
+
+    /// Extensions a random file is picked from
+    pub extensions: Vec<String>,
+
+    /// Larger files are neither picked nor downloaded
+    pub max_file_bytes: u64,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "https://api.github.com".to_string(),
+            raw_url: "https://raw.githubusercontent.com".to_string(),
+            branch: "HEAD".to_string(),
+            extensions: ["rs", "py", "go", "c", "h"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            max_file_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// How an animation speeds up and slows down
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// The same speed all the way
+    Linear,
+    /// Fast at first, settling at the end
+    #[default]
+    EaseOut,
+    /// Slow at both ends
+    EaseInOut,
+}
+
+impl Easing {
+    /// How far along an animation is after `t` (0 to 1) of its time
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut if t < 0.5 => 2.0 * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(2) / 2.0,
+        }
+    }
+}
+
+/// Animations of the code view (see `animation`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationConfig {
+    /// Milliseconds the view takes to scroll to a new position (0 jumps)
+    pub scroll_ms: u64,
+
+    /// Milliseconds lines coming into view take to fade in (0 shows them at once)
+    pub fade_ms: u64,
+
+    /// Easing of scrolling, fading and the caret slide
+    pub easing: Easing,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            scroll_ms: 120,
+            fade_ms: 150,
+            easing: Easing::default(),
+        }
+    }
+}
+
+/// Sound feedback (see `audio`), on with `gameplay.audio_feedback`,
+/// `gameplay.accuracy_alarm_bell` or `metronome.audible`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Volume from 0.0 (silent) to 1.0
+    pub volume: f32,
+
+    /// Directory with `key.wav`, `error.wav`, `finish.wav`, `alarm.wav` and `beat.wav`
+    /// to play instead of the built-in sounds; missing files keep the built-in ones
+    pub sound_pack: Option<String>,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            volume: 0.5,
+            sound_pack: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// Show what is being practiced on your Discord profile
+    pub enabled: bool,
+
+    /// ID of the Discord application the presence is shown for
+    pub client_id: Option<String>,
+
+    /// Show "Practicing code" instead of the file name
+    pub hide_file_name: bool,
+}
+
+/// Practice lock configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeLockConfig {
+    /// Ask for a confirmation phrase when quitting before the daily goal is met
+    pub enabled: bool,
+
+    /// Minutes of practice per day before quitting is no longer locked
+    pub daily_minutes: f64,
+
+    /// Phrase to type to quit early anyway
+    pub phrase: String,
+}
+
+impl Default for PracticeLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            daily_minutes: 15.0,
+            phrase: "I am choosing to stop early".to_string(),
+        }
+    }
+}
+
+/// Scoring configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// Rank personal bests by score instead of raw WPM
+    pub enabled: bool,
+
+    /// k in score = wpm * (accuracy / 100)^k; higher values punish mistakes harder
+    pub accuracy_exponent: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accuracy_exponent: 2.0,
+        }
+    }
+}
+
+/// Statistics screen configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatisticsConfig {
+    /// Extra aggregations shown on the statistics screen, e.g. "median_wpm", "wpm_iqr",
+    /// "error_free_streak" (see `aggregators::BUILTIN` for all names)
+    pub metrics: Vec<String>,
+    /// How numbers, dates and times are written, e.g. "de-DE", or "auto" for the system's
+    pub locale: String,
+}
+
+impl Default for StatisticsConfig {
+    fn default() -> Self {
+        Self {
+            metrics: Vec::new(),
+            locale: crate::locale::AUTO.to_string(),
+        }
+    }
+}
+
+/// Rust edition whose keywords are highlighted unless configured otherwise
+pub const DEFAULT_RUST_EDITION: u32 = 2021;
+
+/// What colors the code (see `highlight`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyntaxEngine {
+    /// syntect grammars picked by file extension, with the keyword matcher for the rest
+    #[default]
+    Syntect,
+    /// The built-in Rust keyword matcher for every file
+    Keywords,
+}
+
+/// Syntax highlighting configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyntaxConfig {
+    /// Highlighting engine: "syntect" or "keywords"
+    pub engine: SyntaxEngine,
+
+    /// Rust edition; keywords added in later editions are not highlighted
+    pub edition: u32,
+
+    /// Extra words colored like declaration keywords
+    pub keywords: Vec<String>,
+
+    /// Extra words colored like types (e.g. crate names such as "tokio")
+    pub types: Vec<String>,
+
+    /// Extra words colored like functions and macros
+    pub functions: Vec<String>,
+}
+
+impl Default for SyntaxConfig {
+    fn default() -> Self {
+        Self {
+            engine: SyntaxEngine::default(),
+            edition: DEFAULT_RUST_EDITION,
+            keywords: Vec::new(),
+            types: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+}
+
+impl ColorConfig {
+    /// Names of all editable color entries, in display order
+    pub const FIELD_NAMES: &'static [&'static str] = &[
+        "background",
+        "text_default",
+        "text_correct",
+        "text_incorrect",
+        "text_current",
+        "text_header",
+        "syntax_keyword",
+        "syntax_type",
+        "syntax_string",
+        "syntax_comment",
+        "syntax_number",
+        "syntax_function",
+        "text_whitespace",
+        "text_newline",
+    ];
+
+    /// Returns the color entry with the given field name
+    pub fn field(&self, name: &str) -> Option<&[f32; 4]> {
+        match name {
+            "background" => Some(&self.background),
+            "text_default" => Some(&self.text_default),
+            "text_correct" => Some(&self.text_correct),
+            "text_incorrect" => Some(&self.text_incorrect),
+            "text_current" => Some(&self.text_current),
+            "text_header" => Some(&self.text_header),
+            "syntax_keyword" => Some(&self.syntax_keyword),
+            "syntax_type" => Some(&self.syntax_type),
+            "syntax_string" => Some(&self.syntax_string),
+            "syntax_comment" => Some(&self.syntax_comment),
+            "syntax_number" => Some(&self.syntax_number),
+            "syntax_function" => Some(&self.syntax_function),
+            "text_whitespace" => Some(&self.text_whitespace),
+            "text_newline" => Some(&self.text_newline),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the color entry with the given field name
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut [f32; 4]> {
+        match name {
+            "background" => Some(&mut self.background),
+            "text_default" => Some(&mut self.text_default),
+            "text_correct" => Some(&mut self.text_correct),
+            "text_incorrect" => Some(&mut self.text_incorrect),
+            "text_current" => Some(&mut self.text_current),
+            "text_header" => Some(&mut self.text_header),
+            "syntax_keyword" => Some(&mut self.syntax_keyword),
+            "syntax_type" => Some(&mut self.syntax_type),
+            "syntax_string" => Some(&mut self.syntax_string),
+            "syntax_comment" => Some(&mut self.syntax_comment),
+            "syntax_number" => Some(&mut self.syntax_number),
+            "syntax_function" => Some(&mut self.syntax_function),
+            "text_whitespace" => Some(&mut self.text_whitespace),
+            "text_newline" => Some(&mut self.text_newline),
+            _ => None,
         }
     }
 }
@@ -277,9 +1352,23 @@ impl Default for Config {
         Self {
             window: WindowConfig::default(),
             text: TextConfig::default(),
+            accessibility: AccessibilityConfig::default(),
             gameplay: GameplayConfig::default(),
             debug: DebugConfig::default(),
             colors: ColorConfig::default(),
+            scripting: ScriptingConfig::default(),
+            notifications: NotificationsConfig::default(),
+            pomodoro: PomodoroConfig::default(),
+            metronome: MetronomeConfig::default(),
+            discord: DiscordConfig::default(),
+            practice_lock: PracticeLockConfig::default(),
+            scoring: ScoringConfig::default(),
+            statistics: StatisticsConfig::default(),
+            syntax: SyntaxConfig::default(),
+            daemon: DaemonConfig::default(),
+            github: GithubConfig::default(),
+            animation: AnimationConfig::default(),
+            audio: AudioConfig::default(),
         }
     }
 }
@@ -307,17 +1396,19 @@ impl Config {
 
     /// Load configuration from default location (config.toml)
     /// If file doesn't exist, returns default configuration
+    /// With a profile selected, its config.toml is layered on top.
     pub fn load() -> Self {
-        let config_path = "config.toml";
+        let config_path = crate::profile::shared_config_path();
 
-        match Self::from_file(config_path) {
+        let config = match Self::from_file(&config_path) {
             Ok(config) => {
-                log::info!("Using configuration from config.toml");
+                log::info!("Using configuration from {}", config_path.display());
                 config
             }
             Err(e) => {
                 log::warn!(
-                    "Could not load config.toml: {}. Using default configuration.",
+                    "Could not load {}: {}. Using default configuration.",
+                    config_path.display(),
                     e
                 );
                 log::info!(
@@ -325,9 +1416,36 @@ impl Config {
                 );
                 Self::default()
             }
+        };
+
+        match crate::profile::config_overrides() {
+            Some(path) if path.exists() => match config.with_overrides(&path) {
+                Ok(merged) => {
+                    log::info!("Applied profile settings from {:?}", path);
+                    merged
+                }
+                Err(e) => {
+                    log::warn!("Could not apply profile settings {:?}: {}", path, e);
+                    config
+                }
+            },
+            _ => config,
         }
     }
 
+    /// Returns this configuration with the settings in the TOML file at `path` on top;
+    /// the file only needs the keys it changes
+    pub fn with_overrides(&self, path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let overrides: toml::Table =
+            toml::from_str(&contents).with_context(|| "Failed to parse config file")?;
+
+        let mut merged = toml::Table::try_from(self)?;
+        merge_toml(&mut merged, overrides);
+        Ok(toml::Value::Table(merged).try_into()?)
+    }
+
     /// Save configuration to a TOML file
     ///
     /// # Arguments
@@ -346,6 +1464,58 @@ impl Config {
         Ok(())
     }
 
+    /// Saves only the setting at the dotted `key` ("colors", "accessibility.low_vision")
+    /// into the TOML file at `path`, keeping everything else the file holds. Changes made
+    /// inside the app go through here, so a profile's file keeps only its own overrides
+    /// and values set for one run are not written out. A setting that is unset is removed.
+    pub fn save_setting<P: AsRef<Path>>(&self, path: P, key: &str) -> Result<()> {
+        let path = path.as_ref();
+        let mut file: toml::Table = if path.exists() {
+            let contents = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file: {:?}", path))?;
+            toml::from_str(&contents).with_context(|| "Failed to parse config file")?
+        } else {
+            toml::Table::new()
+        };
+
+        let parts: Vec<&str> = key.split('.').collect();
+        let current = toml::Table::try_from(self)?;
+        let mut value = None;
+        let mut table = Some(&current);
+        for part in &parts {
+            value = table.and_then(|table| table.get(*part));
+            table = value.and_then(toml::Value::as_table);
+        }
+
+        let Some((last, parents)) = parts.split_last() else {
+            bail!("empty setting name");
+        };
+        let mut section = &mut file;
+        for part in parents {
+            section = section
+                .entry(*part)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .with_context(|| format!("{} in {:?} is not a table", part, path))?;
+        }
+        match value {
+            Some(value) => section.insert(last.to_string(), value.clone()),
+            None => section.remove(*last),
+        };
+
+        // A profile chosen through the environment has no directory until now
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        let toml_string =
+            toml::to_string_pretty(&file).with_context(|| "Failed to serialize config to TOML")?;
+        fs::write(path, toml_string)
+            .with_context(|| format!("Failed to write config file: {:?}", path))?;
+
+        log::info!("Saved {} to: {:?}", key, path);
+        Ok(())
+    }
+
     /// Save default configuration to a file
     /// Useful for generating a template config file
     pub fn save_default<P: AsRef<Path>>(path: P) -> Result<()> {
@@ -411,6 +1581,15 @@ impl Config {
         if self.window.width < 640 || self.window.height < 480 {
             warnings.push("Window size is very small. Minimum recommended: 640x480".to_string());
         }
+        if self.window.hud_refresh_divisor == 0 {
+            warnings.push("hud_refresh_divisor should be at least 1, using 1".to_string());
+        }
+        if !(MIN_OPACITY..=1.0).contains(&self.window.opacity) {
+            warnings.push(format!(
+                "opacity should be between {} and 1.0: {}",
+                MIN_OPACITY, self.window.opacity
+            ));
+        }
 
         // Check font size
         if self.text.font_size < 8.0 {
@@ -419,6 +1598,27 @@ impl Config {
             warnings.push("Font size is very large and may not fit on screen".to_string());
         }
 
+        if self.text.preview_lines > crate::ui_blocks::MAX_PREVIEW_LINES {
+            warnings.push(format!(
+                "preview_lines is capped at {}: {}",
+                crate::ui_blocks::MAX_PREVIEW_LINES,
+                self.text.preview_lines
+            ));
+        }
+        if !(1.0..=3.0).contains(&self.text.preview_scale) {
+            warnings.push(format!(
+                "preview_scale should be between 1.0 and 3.0: {}",
+                self.text.preview_scale
+            ));
+        }
+
+        if !(1.0..=3.0).contains(&self.accessibility.ui_scale) {
+            warnings.push(format!(
+                "ui_scale should be between 1.0 and 3.0: {}",
+                self.accessibility.ui_scale
+            ));
+        }
+
         // Check font file exists
         if !Path::new(&self.text.font_path).exists() {
             warnings.push(format!("Font file not found: {}", self.text.font_path));
@@ -431,6 +1631,76 @@ impl Config {
             }
         }
 
+        if let Some(floor) = self.gameplay.accuracy_floor
+            && !(0.0..=100.0).contains(&floor)
+        {
+            warnings.push(format!(
+                "Accuracy floor must be a percentage between 0 and 100: {}",
+                floor
+            ));
+        }
+
+        if self.scripting.enabled {
+            match self.scripting.script_path {
+                Some(ref script_path) if !Path::new(script_path).exists() => {
+                    warnings.push(format!("Script file not found: {}", script_path));
+                }
+                None => {
+                    warnings.push("Scripting is enabled but no script_path is set".to_string());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(ref url) = self.notifications.webhook_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                warnings.push(format!(
+                    "Webhook URL must start with http:// or https://: {}",
+                    url
+                ));
+            }
+        }
+
+        if let Some(ref time) = self.notifications.reminder_time {
+            if crate::reminder::parse_time(time).is_none() {
+                warnings.push(format!(
+                    "Reminder time must look like HH:MM (e.g. 19:30): {}",
+                    time
+                ));
+            }
+        }
+
+        if self.discord.enabled && self.discord.client_id.is_none() {
+            warnings.push(
+                "Discord presence is enabled but no client_id is set, so it stays off".to_string(),
+            );
+        }
+
+        if self.practice_lock.enabled && self.practice_lock.phrase.trim().is_empty() {
+            warnings.push(
+                "Practice lock phrase is empty, so quitting early only takes Enter".to_string(),
+            );
+        }
+
+        for metric in &self.statistics.metrics {
+            if crate::aggregators::find(metric).is_none() {
+                warnings.push(format!("Unknown statistics metric: {}", metric));
+            }
+        }
+        let locale = &self.statistics.locale;
+        if !locale.eq_ignore_ascii_case(crate::locale::AUTO)
+            && crate::locale::Locale::parse(locale).is_none()
+        {
+            warnings.push(format!("Unknown locale {}, using en-US", locale));
+        }
+
+        if !(0.0..=10.0).contains(&self.scoring.accuracy_exponent) {
+            warnings.push(format!(
+                "Scoring accuracy_exponent should be between 0 and 10: {}",
+                self.scoring.accuracy_exponent
+            ));
+        }
+
         // Check color values are in valid range
         let color_fields = [
             ("background", self.colors.background),
@@ -454,10 +1724,44 @@ impl Config {
     }
 }
 
+/// Copies `overrides` into `base`, descending into tables present in both
+fn merge_toml(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(table)) => {
+                merge_toml(base_table, table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scroll_modes() {
+        // A view of 10 lines scrolled to line 20
+        assert_eq!(ScrollMode::Manual.offset(20, 40, 10), None);
+
+        assert_eq!(ScrollMode::Follow.offset(20, 25, 10), None);
+        // Two lines ahead of the cursor stay visible
+        assert_eq!(ScrollMode::Follow.offset(20, 28, 10), Some(21));
+        assert_eq!(ScrollMode::Follow.offset(20, 50, 10), Some(43));
+        assert_eq!(ScrollMode::Follow.offset(20, 21, 10), Some(19));
+        assert_eq!(ScrollMode::Follow.offset(0, 0, 10), None);
+
+        assert_eq!(ScrollMode::Centered.offset(20, 25, 10), None);
+        assert_eq!(ScrollMode::Centered.offset(20, 30, 10), Some(25));
+        assert_eq!(ScrollMode::Centered.offset(5, 3, 10), Some(0));
+
+        // Before the first layout
+        assert_eq!(ScrollMode::Follow.offset(0, 50, 0), None);
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
@@ -488,4 +1792,146 @@ mod tests {
         config.debug.log_level = "invalid".to_string();
         assert_eq!(config.get_log_level(), log::Level::Info);
     }
+
+    #[test]
+    fn test_color_field_lookup() {
+        let mut colors = ColorConfig::default();
+        for name in ColorConfig::FIELD_NAMES {
+            assert!(colors.field(name).is_some(), "missing field {}", name);
+        }
+        assert!(colors.field("nope").is_none());
+
+        if let Some(color) = colors.field_mut("text_header") {
+            *color = [0.1, 0.2, 0.3, 1.0];
+        }
+        assert_eq!(colors.text_header, [0.1, 0.2, 0.3, 1.0]);
+    }
+
+    #[test]
+    fn test_text_shadow_offsets() {
+        let mut shadow = TextShadowConfig::default();
+        assert!(shadow.offsets().is_empty());
+
+        shadow.effect = TextEffect::Shadow;
+        assert_eq!(shadow.offsets(), vec![[2.0, 2.0]]);
+
+        shadow.effect = TextEffect::Outline;
+        shadow.offset = [1.0, -3.0];
+        let offsets = shadow.offsets();
+        assert_eq!(offsets.len(), 8);
+        assert!(offsets.contains(&[-3.0, 0.0]));
+        assert!(!offsets.contains(&[0.0, 0.0]));
+
+        let shadow: TextShadowConfig = toml::from_str("effect = \"outline\"").unwrap();
+        assert_eq!(shadow.effect, TextEffect::Outline);
+        assert_eq!(shadow.offset, [2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_profile_overrides() {
+        let path = std::env::temp_dir().join("cargotap_test_profile_overrides.toml");
+        fs::write(
+            &path,
+            "[text]\nfont_size = 40.0\n\n[gameplay]\nsession_duration_minutes = 1.0\n",
+        )
+        .unwrap();
+
+        let mut base = Config::default();
+        base.text.syntax_highlighting = false;
+        let merged = base.with_overrides(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(merged.text.font_size, 40.0);
+        assert_eq!(merged.gameplay.session_duration_minutes, 1.0);
+        // Everything not in the file comes from the shared config
+        assert!(!merged.text.syntax_highlighting);
+        assert_eq!(merged.window.title, base.window.title);
+    }
+
+    #[test]
+    fn test_drill_seed_alias() {
+        // A [gameplay] table as an older config.toml wrote it, with the seed under its old name
+        let mut gameplay = toml::Table::try_from(GameplayConfig::default()).unwrap();
+        gameplay.remove("seed");
+        gameplay.insert("drill_seed".to_string(), toml::Value::Integer(42));
+
+        let gameplay: GameplayConfig = toml::Value::Table(gameplay).try_into().unwrap();
+        assert_eq!(gameplay.seed, Some(42));
+    }
+
+    #[test]
+    fn test_notifications_without_max_retries() {
+        let config: Config = toml::from_str(
+            "[notifications]\nwebhook_url = \"https://example.com/hook\"\nreminder_time = \"18:00\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.notifications.webhook_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+        assert_eq!(config.notifications.webhook_max_retries, 3);
+        assert_eq!(config.notifications.reminder_snooze_minutes, 60);
+    }
+
+    #[test]
+    fn test_save_setting_keeps_the_rest_of_the_file() {
+        let dir =
+            std::env::temp_dir().join(format!("cargotap_save_setting_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("profile").join("config.toml");
+
+        let mut config = Config::default();
+        config.accessibility.low_vision = true;
+        config.text.char_spacing = 9.0;
+        // The directory doesn't exist yet
+        config
+            .save_setting(&path, "accessibility.low_vision")
+            .unwrap();
+        let mut saved: toml::Table = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved["accessibility"].as_table().unwrap().len(), 1);
+
+        fs::write(&path, "[text]\nfont_size = 40.0\n").unwrap();
+        config.save_setting(&path, "colors").unwrap();
+        config
+            .save_setting(&path, "accessibility.low_vision")
+            .unwrap();
+        saved = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        let text = saved["text"].as_table().unwrap();
+        assert_eq!(text.len(), 1);
+        assert_eq!(text["font_size"].as_float(), Some(40.0));
+        assert_eq!(saved["accessibility"]["low_vision"].as_bool(), Some(true));
+        assert!(saved.contains_key("colors"));
+        assert!(!saved.contains_key("window"));
+    }
+    #[test]
+    fn test_end_condition_round_trip() {
+        #[derive(Deserialize)]
+        struct Gameplay {
+            end_condition: EndCondition,
+        }
+        let gameplay: Gameplay = toml::from_str("end_condition = { lines = 20 }").unwrap();
+        assert_eq!(gameplay.end_condition, EndCondition::Lines(20));
+
+        let mut config = Config::default();
+        config.gameplay.end_condition = EndCondition::Chars(300);
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let loaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.gameplay.end_condition, EndCondition::Chars(300));
+    }
+
+    #[test]
+    fn test_window_opacity() {
+        let window: WindowConfig = toml::from_str(
+            "title = \"CargoTap\"\nwidth = 800\nheight = 600\nvsync = true\ntarget_fps = 60",
+        )
+        .unwrap();
+        assert_eq!(window.opacity, 1.0);
+
+        let mut config = Config::default();
+        config.window.opacity = 0.1;
+        assert!(config.validate().iter().any(|w| w.contains("opacity")));
+    }
 }
@@ -0,0 +1,299 @@
+//! Quick-launch daemon
+//!
+//! Starting Vulkan and loading fonts takes a noticeable moment. `cargotap daemon`
+//! does it once and keeps the app running with its window hidden; `cargotap open
+//! FILE` then hands FILE to it over a Unix socket in the data directory and the
+//! window shows at once. Closing the window hides it again. `cargotap daemon
+//! status` and `cargotap daemon stop` check on the daemon and end it.
+//!
+//! Each connection carries one request line (`open PATH`, `open`, `status` or
+//! `stop`) and gets one reply line back. Requests are answered by the app between
+//! frames; the listener thread wakes the event loop for them, since a hidden window
+//! draws no frames. Sockets need Unix; elsewhere `open` always starts a new window.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, bail};
+
+use crate::profile;
+
+/// How long a client waits for the app to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Show the window, with the file at the path when one is given
+    Open(Option<PathBuf>),
+    Status,
+    Stop,
+}
+
+impl Command {
+    fn to_line(&self) -> String {
+        match self {
+            Command::Open(Some(path)) => format!("open {}", path.display()),
+            Command::Open(None) => "open".to_string(),
+            Command::Status => "status".to_string(),
+            Command::Stop => "stop".to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        match line.split_once(' ') {
+            Some(("open", path)) if !path.is_empty() => Some(Command::Open(Some(path.into()))),
+            None if line == "open" => Some(Command::Open(None)),
+            None if line == "status" => Some(Command::Status),
+            None if line == "stop" => Some(Command::Stop),
+            _ => None,
+        }
+    }
+}
+
+/// A command from a client, waiting for the app's reply
+#[derive(Debug)]
+pub struct Request {
+    pub command: Command,
+    reply: Sender<String>,
+}
+
+impl Request {
+    pub fn reply(self, text: String) {
+        // The client may have given up waiting, which is fine
+        let _ = self.reply.send(text);
+    }
+}
+
+/// The listening end, owned by the app while it runs as a daemon
+#[derive(Debug)]
+pub struct Server {
+    requests: Receiver<Request>,
+    path: PathBuf,
+    started: Instant,
+}
+
+impl Server {
+    /// Listens at `path`. `wake` is called for every request so the event loop
+    /// comes around to answer it.
+    pub fn bind(path: &Path, wake: impl Fn() + Send + 'static) -> Result<Self> {
+        let (sender, requests) = mpsc::channel();
+        listen(path, sender, wake)?;
+        Ok(Self {
+            requests,
+            path: path.to_path_buf(),
+            started: Instant::now(),
+        })
+    }
+
+    /// Requests that came in since the last call
+    pub fn requests(&self) -> impl Iterator<Item = Request> + '_ {
+        self.requests.try_iter()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::warn!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Where the active profile's daemon listens
+pub fn socket_path() -> PathBuf {
+    profile::data_file("daemon.sock")
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, sender: Sender<Request>, wake: impl Fn() + Send + 'static) -> Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if UnixStream::connect(path).is_ok() {
+        bail!("a daemon is already listening at {}", path.display());
+    }
+    // Nothing answers, so the socket was left behind by a daemon that crashed
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let listener = UnixListener::bind(path)?;
+
+    std::thread::Builder::new()
+        .name("daemon".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| {
+                    let reader = BufReader::new(stream.try_clone()?);
+                    serve(reader, stream, &sender, &wake)
+                });
+                if let Err(e) = result {
+                    log::warn!("Daemon connection failed: {}", e);
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_path: &Path, _sender: Sender<Request>, _wake: impl Fn() + Send + 'static) -> Result<()> {
+    bail!("the daemon needs Unix domain sockets, which this system doesn't have")
+}
+
+/// Answers the one request on a connection
+fn serve(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    sender: &Sender<Request>,
+    wake: &impl Fn(),
+) -> io::Result<()> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let Some(command) = Command::parse(&line) else {
+        return writeln!(writer, "error: unknown request {:?}", line.trim_end());
+    };
+    let (reply, replies) = mpsc::channel();
+    if sender.send(Request { command, reply }).is_err() {
+        return writeln!(writer, "error: CargoTap is shutting down");
+    }
+    wake();
+    match replies.recv_timeout(REPLY_TIMEOUT) {
+        Ok(text) => writeln!(writer, "{}", text),
+        Err(_) => writeln!(writer, "error: CargoTap didn't answer"),
+    }
+}
+
+/// Sends `command` to the daemon and returns its reply, or None when no daemon runs
+#[cfg(unix)]
+pub fn send(command: &Command) -> Result<Option<String>> {
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = match UnixStream::connect(socket_path()) {
+        Ok(stream) => stream,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    stream.set_read_timeout(Some(REPLY_TIMEOUT + Duration::from_secs(1)))?;
+    writeln!(stream, "{}", command.to_line())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(Some(reply.trim_end().to_string()))
+}
+
+#[cfg(not(unix))]
+pub fn send(_command: &Command) -> Result<Option<String>> {
+    Ok(None)
+}
+
+/// Runs `daemon status` and `daemon stop`. Returns false for `daemon` itself
+/// (or `daemon start`), which needs the window and is started by the caller.
+pub fn run(args: &[String]) -> Result<bool> {
+    let command = match args {
+        [] => return Ok(false),
+        [command] if command == "start" => return Ok(false),
+        [command] if command == "status" => Command::Status,
+        [command] if command == "stop" => Command::Stop,
+        _ => bail!("usage: daemon [start | status | stop]"),
+    };
+    match send(&command)? {
+        Some(reply) => println!("{}", reply),
+        None => println!("No daemon running"),
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_lines() {
+        for command in [
+            Command::Open(Some("/home/me/my project/main.rs".into())),
+            Command::Open(None),
+            Command::Status,
+            Command::Stop,
+        ] {
+            assert_eq!(
+                Command::parse(&format!("{}\n", command.to_line())),
+                Some(command)
+            );
+        }
+        assert_eq!(Command::parse("restart\n"), None);
+        assert_eq!(Command::parse("open \n"), None);
+    }
+
+    #[test]
+    fn test_requests_are_answered_by_the_app() {
+        let (sender, requests) = mpsc::channel();
+        let app = std::thread::spawn(move || {
+            let request: Request = requests.recv().unwrap();
+            assert_eq!(request.command, Command::Status);
+            request.reply("running".to_string());
+        });
+
+        let mut written = Vec::new();
+        serve(&b"status\n"[..], &mut written, &sender, &|| {}).unwrap();
+        app.join().unwrap();
+        assert_eq!(written, b"running\n");
+
+        let mut written = Vec::new();
+        serve(&b"reboot\n"[..], &mut written, &sender, &|| {}).unwrap();
+        assert!(String::from_utf8(written).unwrap().starts_with("error:"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_round_trip() {
+        use std::os::unix::net::UnixStream;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let path =
+            std::env::temp_dir().join(format!("cargotap_daemon_{}.sock", std::process::id()));
+        let woken = Arc::new(AtomicBool::new(false));
+        let flag = woken.clone();
+        let server = Server::bind(&path, move || flag.store(true, Ordering::SeqCst)).unwrap();
+        // A second daemon for the same socket is turned away
+        assert!(Server::bind(&path, || {}).is_err());
+
+        let client = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                let mut stream = UnixStream::connect(path).unwrap();
+                writeln!(stream, "open /tmp/a.rs").unwrap();
+                let mut reply = String::new();
+                BufReader::new(stream).read_line(&mut reply).unwrap();
+                reply
+            }
+        });
+        let request = loop {
+            if let Some(request) = server.requests().next() {
+                break request;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        assert!(woken.load(Ordering::SeqCst));
+        assert_eq!(request.command, Command::Open(Some("/tmp/a.rs".into())));
+        request.reply("opened /tmp/a.rs".to_string());
+        assert_eq!(client.join().unwrap(), "opened /tmp/a.rs\n");
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
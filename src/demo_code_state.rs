@@ -116,7 +116,7 @@ fn print_state(code_state: &CodeState) {
     println!(
         "Progress: {:.1}% ({}/{})",
         code_state.get_progress() * 100.0,
-        code_state.get_printed_code().len(),
+        code_state.get_printed_code().len_bytes(),
         code_state.get_total_length()
     );
 
@@ -142,7 +142,7 @@ fn print_state(code_state: &CodeState) {
         );
     }
 
-    println!("Remaining: {}", code_state.get_current_code().len());
+    println!("Remaining: {}", code_state.get_current_code().len_bytes());
     println!("---");
 }
 
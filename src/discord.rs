@@ -0,0 +1,294 @@
+//! Discord Rich Presence
+//!
+//! Shows what is being practiced ("Practicing main.rs", "64 WPM, 12 min session")
+//! on the user's Discord profile. Like the webhook it subscribes to the event bus
+//! and runs on its own thread; it talks to the local Discord client over its IPC
+//! socket (a named pipe on Windows), so nothing is sent when Discord isn't running.
+
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+use std::thread;
+
+use crate::challenge;
+use crate::config::DiscordConfig;
+use crate::drills;
+use crate::events::AppEvent;
+use crate::quotes;
+use crate::review;
+use crate::tutorial;
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+/// Discord IPC endpoints are numbered; a client listens on the first free one
+const MAX_PIPES: u32 = 10;
+
+/// What the presence shows, rebuilt from every event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activity {
+    pub details: String,
+    pub state: String,
+    /// Unix time the running session started, shown by Discord as elapsed time
+    pub started_at: Option<i64>,
+}
+
+impl Activity {
+    fn to_json(&self) -> serde_json::Value {
+        let mut activity = serde_json::json!({
+            "details": self.details,
+            "state": self.state,
+        });
+        if let Some(start) = self.started_at {
+            activity["timestamps"] = serde_json::json!({ "start": start });
+        }
+        activity
+    }
+}
+
+/// "Practicing main.rs", or without the name when `hide_file_name` is set
+///
+/// Only the file name is used, never the directories above it.
+fn practicing(file_path: &str, hide_file_name: bool) -> String {
+    // Generated texts are "loaded" under their source's name
+    let generated = [
+        (quotes::QUOTE_SOURCE, "a quote"),
+        (drills::DRILL_SOURCE, "a drill"),
+        (challenge::CHALLENGE_SOURCE, "a challenge"),
+        (tutorial::TUTORIAL_SOURCE, "the tutorial"),
+        (review::REVIEW_SOURCE, "mistyped lines"),
+    ];
+    if let Some((_, what)) = generated.iter().find(|(source, _)| *source == file_path) {
+        return format!("Practicing {}", what);
+    }
+    if hide_file_name {
+        return "Practicing code".to_string();
+    }
+    let name = Path::new(file_path)
+        .file_name()
+        .map_or(file_path.into(), |name| name.to_string_lossy());
+    format!("Practicing {}", name)
+}
+
+/// The presence for `event`, or None for events that don't change it
+pub fn activity_for(event: &AppEvent, settings: &DiscordConfig) -> Option<Activity> {
+    let now = || chrono::Utc::now().timestamp();
+    match event {
+        AppEvent::FileLoaded { path } => Some(Activity {
+            details: practicing(path, settings.hide_file_name),
+            state: "Between sessions".to_string(),
+            started_at: None,
+        }),
+        AppEvent::SessionStarted { file_path } => Some(Activity {
+            details: practicing(file_path, settings.hide_file_name),
+            state: "Session started".to_string(),
+            started_at: Some(now()),
+        }),
+        AppEvent::SessionProgress {
+            file_path,
+            words_per_minute,
+            elapsed_secs,
+        } => Some(Activity {
+            details: practicing(file_path, settings.hide_file_name),
+            state: format!(
+                "{:.0} WPM, {} min session",
+                words_per_minute,
+                (elapsed_secs / 60.0).floor()
+            ),
+            started_at: Some(now() - *elapsed_secs as i64),
+        }),
+        AppEvent::SessionFinished(stats) => Some(Activity {
+            details: practicing(&stats.file_path, settings.hide_file_name),
+            state: format!("Last session: {:.0} WPM", stats.words_per_minute),
+            started_at: None,
+        }),
+        AppEvent::ThemeChanged => None,
+    }
+}
+
+/// Keeps the presence up to date with the events received from the bus
+pub fn subscribe(events: Receiver<AppEvent>, settings: DiscordConfig) {
+    let Some(client_id) = settings.client_id.clone() else {
+        return;
+    };
+    thread::spawn(move || {
+        let mut connection: Option<Connection> = None;
+        for event in events {
+            let Some(activity) = activity_for(&event, &settings) else {
+                continue;
+            };
+            if connection.is_none() {
+                // Discord may have been started (or restarted) since the last event
+                connection = Connection::open(&client_id)
+                    .inspect_err(|e| log::debug!("Discord is not reachable: {}", e))
+                    .ok();
+            }
+            if let Some(conn) = &mut connection
+                && let Err(e) = conn.set_activity(&activity)
+            {
+                log::debug!("Lost the Discord connection: {}", e);
+                connection = None;
+            }
+        }
+    });
+}
+
+trait Pipe: Read + Write + Send {}
+impl<T: Read + Write + Send> Pipe for T {}
+
+struct Connection {
+    pipe: Box<dyn Pipe>,
+    nonce: u64,
+}
+
+impl Connection {
+    fn open(client_id: &str) -> io::Result<Self> {
+        let mut connection = Self {
+            pipe: connect()?,
+            nonce: 0,
+        };
+        connection.send(
+            OP_HANDSHAKE,
+            &serde_json::json!({ "v": 1, "client_id": client_id }),
+        )?;
+        log::info!("🎮 Connected to Discord");
+        Ok(connection)
+    }
+
+    fn set_activity(&mut self, activity: &Activity) -> io::Result<()> {
+        self.nonce += 1;
+        self.send(
+            OP_FRAME,
+            &serde_json::json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": std::process::id(), "activity": activity.to_json() },
+                "nonce": self.nonce.to_string(),
+            }),
+        )
+    }
+
+    /// Writes one frame and reads Discord's reply, which ends the connection on errors
+    fn send(&mut self, opcode: u32, payload: &serde_json::Value) -> io::Result<()> {
+        self.pipe.write_all(&encode_frame(opcode, payload))?;
+        let mut header = [0u8; 8];
+        self.pipe.read_exact(&mut header)?;
+        let (reply_opcode, len) = decode_header(header);
+        let mut reply = vec![0u8; len];
+        self.pipe.read_exact(&mut reply)?;
+        if reply_opcode != OP_FRAME {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                String::from_utf8_lossy(&reply).into_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A frame is the opcode and payload length (little endian u32s) followed by JSON
+fn encode_frame(opcode: u32, payload: &serde_json::Value) -> Vec<u8> {
+    let json = payload.to_string();
+    let mut frame = Vec::with_capacity(8 + json.len());
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    frame.extend_from_slice(json.as_bytes());
+    frame
+}
+
+fn decode_header(header: [u8; 8]) -> (u32, usize) {
+    let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    (opcode, len as usize)
+}
+
+#[cfg(unix)]
+fn connect() -> io::Result<Box<dyn Pipe>> {
+    use std::os::unix::net::UnixStream;
+
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .unwrap_or_else(|| "/tmp".to_string());
+    let mut last_error = io::Error::from(io::ErrorKind::NotFound);
+    for i in 0..MAX_PIPES {
+        match UnixStream::connect(Path::new(&dir).join(format!("discord-ipc-{}", i))) {
+            Ok(stream) => return Ok(Box::new(stream)),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(windows)]
+fn connect() -> io::Result<Box<dyn Pipe>> {
+    let mut last_error = io::Error::from(io::ErrorKind::NotFound);
+    for i in 0..MAX_PIPES {
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(format!(r"\\?\pipe\discord-ipc-{}", i))
+        {
+            Ok(pipe) => return Ok(Box::new(pipe)),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_state::SessionStats;
+
+    fn settings(hide_file_name: bool) -> DiscordConfig {
+        DiscordConfig {
+            enabled: true,
+            client_id: Some("123".to_string()),
+            hide_file_name,
+        }
+    }
+
+    #[test]
+    fn test_progress_activity() {
+        let event = AppEvent::SessionProgress {
+            file_path: "/home/me/secret-project/src/main.rs".to_string(),
+            words_per_minute: 63.7,
+            elapsed_secs: 750.0,
+        };
+        let activity = activity_for(&event, &settings(false)).unwrap();
+        assert_eq!(activity.details, "Practicing main.rs");
+        assert_eq!(activity.state, "64 WPM, 12 min session");
+        assert!(activity.started_at.is_some());
+
+        let hidden = activity_for(&event, &settings(true)).unwrap();
+        assert_eq!(hidden.details, "Practicing code");
+
+        let quote = AppEvent::SessionStarted {
+            file_path: quotes::QUOTE_SOURCE.to_string(),
+        };
+        let activity = activity_for(&quote, &settings(true)).unwrap();
+        assert_eq!(activity.details, "Practicing a quote");
+    }
+
+    #[test]
+    fn test_finished_and_ignored_events() {
+        let stats = SessionStats::new(300, 60.0, 0, 300, 3, "lib.rs".to_string());
+        let activity = activity_for(
+            &AppEvent::SessionFinished(Box::new(stats)),
+            &settings(false),
+        )
+        .unwrap();
+        assert_eq!(activity.state, "Last session: 60 WPM");
+        assert!(activity.to_json().get("timestamps").is_none());
+
+        assert!(activity_for(&AppEvent::ThemeChanged, &settings(false)).is_none());
+    }
+
+    #[test]
+    fn test_frame_layout() {
+        let frame = encode_frame(OP_HANDSHAKE, &serde_json::json!({ "v": 1 }));
+        let header: [u8; 8] = frame[..8].try_into().unwrap();
+        assert_eq!(decode_header(header), (OP_HANDSHAKE, frame.len() - 8));
+        assert_eq!(&frame[8..], br#"{"v":1}"#);
+    }
+}
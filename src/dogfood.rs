@@ -0,0 +1,101 @@
+//! Dogfood mode: practice on CargoTap's own source code
+//!
+//! The crate's `src/` tree is embedded at build time, so there is always
+//! realistic Rust to type even without a project on disk. Modules are addressed
+//! with a `cargotap://src/` path, which keeps their progress in the regular
+//! progress storage next to normal files.
+
+use include_dir::{Dir, include_dir};
+
+use crate::progress_storage::{ProgressStorage, compute_hash};
+
+/// Path prefix used for embedded source files
+pub const DOGFOOD_PREFIX: &str = "cargotap://src/";
+
+static SOURCE_DIR: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/src");
+
+/// Returns the embedded Rust files as `cargotap://src/...` paths, sorted
+pub fn module_paths() -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_rust_files(&SOURCE_DIR, &mut paths);
+    paths.sort();
+    paths
+}
+
+/// Returns the contents of an embedded file, or None if `path` is not a dogfood path
+pub fn read(path: &str) -> Option<&'static str> {
+    let relative = path.strip_prefix(DOGFOOD_PREFIX)?;
+    SOURCE_DIR.get_file(relative)?.contents_utf8()
+}
+
+/// How much of a module has been typed according to saved progress (0.0 to 1.0)
+pub fn completion(progress_storage: &ProgressStorage, path: &str) -> f32 {
+    let Some(contents) = read(path) else {
+        return 0.0;
+    };
+    let Some(progress) = progress_storage.get_progress(path) else {
+        return 0.0;
+    };
+
+    // Progress from an older build of the source no longer lines up
+    if progress.content_hash != compute_hash(contents) {
+        return 0.0;
+    }
+
+    // Saved positions are byte offsets, same as the typing cursor
+    let total = contents.len();
+    if total == 0 {
+        1.0
+    } else {
+        (progress.position as f32 / total as f32).min(1.0)
+    }
+}
+
+fn collect_rust_files(dir: &Dir<'static>, paths: &mut Vec<String>) {
+    for file in dir.files() {
+        if file.path().extension().is_some_and(|ext| ext == "rs") {
+            paths.push(format!("{}{}", DOGFOOD_PREFIX, file.path().display()));
+        }
+    }
+    for subdir in dir.dirs() {
+        collect_rust_files(subdir, paths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modules_include_own_source() {
+        let paths = module_paths();
+        assert!(paths.contains(&format!("{}main.rs", DOGFOOD_PREFIX)));
+        assert!(paths.contains(&format!("{}renderer/mod.rs", DOGFOOD_PREFIX)));
+        assert!(paths.iter().all(|p| p.ends_with(".rs")));
+    }
+
+    #[test]
+    fn test_read_embedded_file() {
+        let contents = read(&format!("{}dogfood.rs", DOGFOOD_PREFIX)).unwrap();
+        assert!(contents.contains("Dogfood mode"));
+        assert!(read("src/main.rs").is_none());
+        assert!(read(&format!("{}missing.rs", DOGFOOD_PREFIX)).is_none());
+    }
+
+    #[test]
+    fn test_completion_uses_saved_progress() {
+        let path = format!("{}webhook.rs", DOGFOOD_PREFIX);
+        let contents = read(&path).unwrap();
+        let mut storage =
+            ProgressStorage::new(std::env::temp_dir().join("cargotap_test_dogfood.json"));
+
+        assert_eq!(completion(&storage, &path), 0.0);
+
+        let half = contents.len() / 2;
+        storage.save_progress(path.clone(), compute_hash(contents), half);
+        assert!((completion(&storage, &path) - 0.5).abs() < 0.01);
+
+        storage.save_progress(path.clone(), "stale".to_string(), half);
+        assert_eq!(completion(&storage, &path), 0.0);
+    }
+}
@@ -0,0 +1,352 @@
+//! Procedural drills for the parts of Rust that plain prose never exercises
+//!
+//! Every drill is generated from a seed and a difficulty (1..=5), so the same
+//! pair always produces the same text and a tricky drill can be repeated.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::keyboard::Hand;
+
+/// Source name recorded in session history for drill sessions
+pub const DRILL_SOURCE: &str = "drill";
+
+pub const MIN_DIFFICULTY: u8 = 1;
+pub const MAX_DIFFICULTY: u8 = 5;
+
+/// Symbol tokens, ordered so each difficulty level unlocks the next slice
+const SYMBOLS: &[&str] = &[
+    "::", "->", "=>", "&&", "||", "::<>", "!=", "==", "<=", ">=", "+=", "..", "..=", "&mut", "?;",
+    "|x|", "#[]", "'a", "<T>", "-=", "<<", ">>", "::<T>()", "&'a", "#![]", "*const",
+];
+
+const IDENTS: &[&str] = &[
+    "x", "val", "self", "item", "buf", "map", "key", "len", "node", "ctx", "err", "iter",
+];
+
+const BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+/// Words typed with the left hand alone, shortest first so difficulty unlocks longer ones
+const LEFT_HAND_WORDS: &[&str] = &[
+    "as", "at", "we", "be", "red", "tax", "fed", "bad", "set", "get", "bag", "age", "art", "tag",
+    "war", "cab", "far", "gas", "tea", "vec", "str", "dbg", "east", "read", "fast", "card", "west",
+    "tree", "date", "cast", "gate", "base", "star", "vast", "ward", "area", "crew", "debt", "fade",
+    "draft", "trade", "water", "great", "extra", "grace", "react", "stage", "start", "state",
+    "asset", "badge", "crate", "craft", "assert", "reward", "extract", "careers", "reverse",
+    "abstract", "database",
+];
+
+/// Words typed with the right hand alone, shortest first
+const RIGHT_HAND_WORDS: &[&str] = &[
+    "in", "on", "no", "up", "my", "oh", "ok", "pi", "him", "hip", "ink", "joy", "kin", "lip",
+    "mop", "nil", "oil", "pun", "you", "yum", "pup", "hook", "hill", "join", "jump", "kill",
+    "link", "lion", "loop", "milk", "moon", "noon", "only", "pink", "plum", "poll", "pool", "pony",
+    "null", "upon", "holy", "hymn", "impl", "kilo", "monk", "onion", "union", "unpin", "phylum",
+    "opinion", "minimum", "million", "pumpkin", "monopoly", "lollipop",
+];
+
+/// Symbols from each hand's side of the keyboard, mixed in from difficulty 3
+const LEFT_HAND_SYMBOLS: &[&str] = &["!", "@", "#", "$", "%", "~", "`"];
+const RIGHT_HAND_SYMBOLS: &[&str] = &[
+    "()", "[]", "{}", "->", "=>", "::", "&&", "||", "+=", "::<>", "*", "?", ";",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DrillKind {
+    Numbers,
+    Symbols,
+    Brackets,
+    #[serde(rename = "left_hand")]
+    LeftHand,
+    #[serde(rename = "right_hand")]
+    RightHand,
+}
+
+impl DrillKind {
+    pub const ALL: [DrillKind; 5] = [
+        DrillKind::Numbers,
+        DrillKind::Symbols,
+        DrillKind::Brackets,
+        DrillKind::LeftHand,
+        DrillKind::RightHand,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DrillKind::Numbers => "Number row",
+            DrillKind::Symbols => "Rust symbols",
+            DrillKind::Brackets => "Bracket nesting",
+            DrillKind::LeftHand => "Left hand only",
+            DrillKind::RightHand => "Right hand only",
+        }
+    }
+}
+
+/// Parameters that fully determine a drill's text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drill {
+    pub kind: DrillKind,
+    pub difficulty: u8,
+    pub seed: u64,
+}
+
+impl Drill {
+    /// The drill that follows this one when the user asks for another
+    pub fn next(self) -> Self {
+        Self {
+            seed: self.seed.wrapping_add(1),
+            ..self
+        }
+    }
+
+    pub fn generate(&self) -> String {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let difficulty = self.difficulty.clamp(MIN_DIFFICULTY, MAX_DIFFICULTY) as usize;
+        let line_count = 3 + difficulty;
+
+        let lines: Vec<String> = (0..line_count)
+            .map(|_| match self.kind {
+                DrillKind::Numbers => number_line(&mut rng, difficulty),
+                DrillKind::Symbols => symbol_line(&mut rng, difficulty),
+                DrillKind::Brackets => bracket_line(&mut rng, difficulty),
+                DrillKind::LeftHand => one_hand_line(&mut rng, difficulty, Hand::Left),
+                DrillKind::RightHand => one_hand_line(&mut rng, difficulty, Hand::Right),
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+/// Selection state of the drills menu
+#[derive(Debug, Clone)]
+pub struct DrillMenu {
+    selected: usize,
+    difficulty: u8,
+}
+
+impl DrillMenu {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            difficulty: 2,
+        }
+    }
+
+    pub fn selected_kind(&self) -> DrillKind {
+        DrillKind::ALL[self.selected % DrillKind::ALL.len()]
+    }
+
+    pub fn difficulty(&self) -> u8 {
+        self.difficulty
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % DrillKind::ALL.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        let count = DrillKind::ALL.len();
+        self.selected = (self.selected + count - 1) % count;
+    }
+
+    pub fn adjust_difficulty(&mut self, delta: i8) {
+        self.difficulty = self
+            .difficulty
+            .saturating_add_signed(delta)
+            .clamp(MIN_DIFFICULTY, MAX_DIFFICULTY);
+    }
+}
+
+fn number_line(rng: &mut StdRng, difficulty: usize) -> String {
+    (0..6)
+        .map(|_| {
+            // Higher levels mix in literal forms that show up in real code
+            match rng.gen_range(0..difficulty.max(2)) {
+                0 | 1 => {
+                    let digits = rng.gen_range(1..=difficulty + 2);
+                    (0..digits)
+                        .map(|_| char::from(b'0' + rng.gen_range(0..10u8)))
+                        .collect()
+                }
+                2 => format!("{}_{:03}", rng.gen_range(1..100), rng.gen_range(0..1000)),
+                3 => format!("0x{:X}", rng.gen_range(0..0x10000)),
+                _ => format!(
+                    "{}.{}{}",
+                    rng.gen_range(0..100),
+                    rng.gen_range(0..100),
+                    ["u8", "i32", "f64", "usize"]
+                        .choose(rng)
+                        .copied()
+                        .unwrap_or("")
+                ),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn symbol_line(rng: &mut StdRng, difficulty: usize) -> String {
+    let pool = &SYMBOLS[..(5 * difficulty).min(SYMBOLS.len())];
+    (0..6)
+        .map(|_| {
+            let symbol = pool.choose(rng).copied().unwrap_or("::");
+            // From level 3 on, symbols sit between identifiers as they would in code
+            if difficulty >= 3 && rng.gen_bool(0.5) {
+                format!("{}{}{}", ident(rng), symbol, ident(rng))
+            } else {
+                symbol.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn one_hand_line(rng: &mut StdRng, difficulty: usize, hand: Hand) -> String {
+    let (words, symbols) = match hand {
+        Hand::Left => (LEFT_HAND_WORDS, LEFT_HAND_SYMBOLS),
+        Hand::Right => (RIGHT_HAND_WORDS, RIGHT_HAND_SYMBOLS),
+    };
+    let pool = &words[..(words.len() * difficulty / MAX_DIFFICULTY as usize).max(10)];
+    (0..6)
+        .map(|_| {
+            let word = pool.choose(rng).copied().unwrap_or("as");
+            if difficulty >= 3 && rng.gen_bool(0.3) {
+                let symbol = symbols.choose(rng).copied().unwrap_or("");
+                format!("{}{}", word, symbol)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn bracket_line(rng: &mut StdRng, difficulty: usize) -> String {
+    nested(rng, difficulty + 1)
+}
+
+fn nested(rng: &mut StdRng, depth: usize) -> String {
+    let (open, close) = BRACKETS.choose(rng).copied().unwrap_or(('(', ')'));
+    let inner = if depth <= 1 || rng.gen_bool(0.25) {
+        ident(rng).to_string()
+    } else {
+        let parts = rng.gen_range(1..=2);
+        (0..parts)
+            .map(|_| nested(rng, depth - 1))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    format!("{}{}{}{}", ident(rng), open, inner, close)
+}
+
+fn ident(rng: &mut StdRng) -> &'static str {
+    IDENTS.choose(rng).copied().unwrap_or("x")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drill(kind: DrillKind, difficulty: u8) -> Drill {
+        Drill {
+            kind,
+            difficulty,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_text() {
+        for kind in DrillKind::ALL {
+            assert_eq!(drill(kind, 3).generate(), drill(kind, 3).generate());
+        }
+        let first = drill(DrillKind::Symbols, 3);
+        assert_ne!(first.generate(), first.next().generate());
+    }
+
+    #[test]
+    fn test_difficulty_adds_lines() {
+        let easy = drill(DrillKind::Numbers, 1).generate();
+        let hard = drill(DrillKind::Numbers, 5).generate();
+        assert!(hard.lines().count() > easy.lines().count());
+    }
+
+    #[test]
+    fn test_numbers_at_lowest_difficulty_are_digits() {
+        let text = drill(DrillKind::Numbers, 1).generate();
+        assert!(
+            text.chars()
+                .all(|c| c.is_ascii_digit() || c == ' ' || c == '\n')
+        );
+    }
+
+    #[test]
+    fn test_brackets_are_balanced() {
+        for seed in 0..20 {
+            let text = Drill {
+                kind: DrillKind::Brackets,
+                difficulty: 5,
+                seed,
+            }
+            .generate();
+
+            for line in text.lines() {
+                let mut stack = Vec::new();
+                for c in line.chars() {
+                    if let Some(&(_, close)) = BRACKETS.iter().find(|(open, _)| *open == c) {
+                        stack.push(close);
+                    } else if BRACKETS.iter().any(|(_, close)| *close == c) {
+                        assert_eq!(stack.pop(), Some(c), "unbalanced: {}", line);
+                    }
+                }
+                assert!(stack.is_empty(), "unclosed: {}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_menu_difficulty_is_clamped() {
+        let mut menu = DrillMenu::new();
+        menu.adjust_difficulty(-10);
+        assert_eq!(menu.difficulty(), MIN_DIFFICULTY);
+        menu.adjust_difficulty(10);
+        assert_eq!(menu.difficulty(), MAX_DIFFICULTY);
+        menu.select_previous();
+        assert_eq!(menu.selected_kind(), DrillKind::RightHand);
+    }
+
+    #[test]
+    fn test_one_hand_drills_stay_on_one_hand() {
+        for (kind, hand) in [
+            (DrillKind::LeftHand, Hand::Left),
+            (DrillKind::RightHand, Hand::Right),
+        ] {
+            for difficulty in MIN_DIFFICULTY..=MAX_DIFFICULTY {
+                let text = Drill {
+                    kind,
+                    difficulty,
+                    seed: 7,
+                }
+                .generate();
+                for ch in text.chars().filter(|ch| *ch != ' ' && *ch != '\n') {
+                    assert_eq!(
+                        crate::keyboard::hand_for(ch),
+                        Some(hand),
+                        "{:?} in {}",
+                        ch,
+                        text
+                    );
+                }
+            }
+            let words = match hand {
+                Hand::Left => LEFT_HAND_WORDS,
+                Hand::Right => RIGHT_HAND_WORDS,
+            };
+            assert!(words.windows(2).all(|pair| pair[0].len() <= pair[1].len()));
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Embedding CargoTap in another Vulkan application.
+//!
+//! Instead of opening a window and running the winit event loop, the host hands over
+//! its device, queue and a color image. It then drives the trainer with `tick`, passing
+//! the input collected since the previous call, and records a frame into the image
+//! with `render` whenever it needs a fresh picture.
+//!
+//! `cargo run snapshot out.png [text]` uses the same API to render a single frame
+//! offscreen.
+
+use anyhow::Result;
+use image::{ImageBuffer, Rgba};
+use std::path::Path;
+use std::sync::Arc;
+use vulkano::{
+    buffer::{Buffer, BufferCreateInfo, BufferUsage},
+    command_buffer::{
+        AutoCommandBufferBuilder, CommandBufferUsage, CopyImageToBufferInfo,
+        allocator::StandardCommandBufferAllocator,
+    },
+    device::{Device, Queue},
+    format::Format,
+    image::{Image, ImageCreateInfo, ImageType, ImageUsage},
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
+    sync::{self, GpuFuture},
+};
+
+use crate::app::CargoTapApp;
+use crate::input::InputAction;
+use crate::renderer::VulkanRenderer;
+
+const SNAPSHOT_SIZE: [u32; 2] = [1280, 720];
+
+pub struct EmbeddedApp {
+    app: CargoTapApp,
+}
+
+impl EmbeddedApp {
+    /// Creates the trainer on the host's device. `target` must be a 2D color image
+    /// with `COLOR_ATTACHMENT` usage; every frame is drawn into it.
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>, target: Arc<Image>) -> Result<Self> {
+        let mut renderer = VulkanRenderer::from_device(device, queue);
+        renderer.set_target(target)?;
+
+        let mut app = CargoTapApp::with_renderer(renderer)?;
+        app.initialize_text_system()?;
+        app.try_initialize_text_pipeline();
+        app.update_text();
+        Ok(Self { app })
+    }
+
+    /// Applies `input` in order, then advances timers.
+    /// Returns false once the user has quit; the host should stop ticking then.
+    pub fn tick(&mut self, input: impl IntoIterator<Item = InputAction>) -> bool {
+        for action in input {
+            self.app.input_handler.inject(action);
+            if self.app.process_action() {
+                return false;
+            }
+        }
+        self.app.poll();
+        true
+    }
+
+    /// Blocks until the code colors for the current text are ready and its glyphs are
+    /// uploaded (both happen on worker threads), so the next `render` shows the final
+    /// layout
+    pub fn finish_layout(&mut self) {
+        self.app.finish_highlighting();
+        if let Some(text_system) = &self.app.text_system {
+            text_system.wait_until_drawn();
+        }
+    }
+
+    /// Records the current screen into the target image after `before` completes.
+    /// The returned future is not flushed.
+    pub fn render(&mut self, before: Box<dyn GpuFuture>) -> Result<Box<dyn GpuFuture>> {
+        self.app.render_engine.render_to_target(before)
+    }
+}
+
+/// Renders one frame offscreen, after typing `typed`, and saves it as a PNG
+pub fn snapshot(output: &Path, typed: &str) -> Result<()> {
+    let (device, queue) = VulkanRenderer::headless_device()?;
+    let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
+    let [width, height] = SNAPSHOT_SIZE;
+
+    let image = Image::new(
+        memory_allocator.clone(),
+        ImageCreateInfo {
+            image_type: ImageType::Dim2d,
+            format: Format::R8G8B8A8_UNORM,
+            extent: [width, height, 1],
+            usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC,
+            ..Default::default()
+        },
+        AllocationCreateInfo::default(),
+    )?;
+
+    let mut embedded = EmbeddedApp::new(device.clone(), queue.clone(), image.clone())?;
+    embedded.tick(typed.chars().map(|ch| match ch {
+        '\n' => InputAction::Enter,
+        '\t' => InputAction::Tab,
+        ch => InputAction::TypeCharacter(ch),
+    }));
+    embedded.finish_layout();
+
+    let pixels = Buffer::from_iter(
+        memory_allocator,
+        BufferCreateInfo {
+            usage: BufferUsage::TRANSFER_DST,
+            ..Default::default()
+        },
+        AllocationCreateInfo {
+            memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                | MemoryTypeFilter::HOST_RANDOM_ACCESS,
+            ..Default::default()
+        },
+        (0..width * height * 4).map(|_| 0u8),
+    )?;
+
+    let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+        device.clone(),
+        Default::default(),
+    ));
+    let mut builder = AutoCommandBufferBuilder::primary(
+        command_buffer_allocator,
+        queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )?;
+    builder.copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, pixels.clone()))?;
+    let copy = builder.build()?;
+
+    embedded
+        .render(sync::now(device).boxed())?
+        .then_execute(queue, copy)?
+        .then_signal_fence_and_flush()?
+        .wait(None)?;
+
+    let content = pixels.read()?;
+    let picture = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, &content[..])
+        .ok_or_else(|| anyhow::anyhow!("snapshot buffer has the wrong size"))?;
+    picture.save(output)?;
+    log::info!("📸 Saved snapshot to {}", output.display());
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Decoding of practice files into typeable text
+//!
+//! Files are read as bytes so non-UTF-8 sources (Latin-1, Shift_JIS, UTF-16
+//! with a BOM, ...) can be converted instead of failing outright. Binary files
+//! are refused with a readable message, and line endings are normalized to
+//! `\n` since a carriage return can't be typed.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// How many leading bytes are inspected when sniffing for binary content
+const SNIFF_LEN: usize = 8192;
+
+/// Text decoded from a file, plus what it looked like on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedText {
+    pub text: String,
+    /// Name of the source encoding (e.g. "UTF-8", "windows-1252")
+    pub encoding: &'static str,
+    /// True if the file used CRLF (or bare CR) line endings
+    pub had_crlf: bool,
+}
+
+impl DecodedText {
+    /// Wraps text that is already UTF-8 (embedded files, demo code)
+    pub fn utf8(text: String) -> Self {
+        Self {
+            text,
+            encoding: UTF_8.name(),
+            had_crlf: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// The file looks like a binary (NUL bytes or mostly control characters)
+    Binary,
+    /// The detected encoding could not decode the file without losing characters
+    Malformed(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Binary => write!(f, "this looks like a binary file, not text"),
+            DecodeError::Malformed(encoding) => {
+                write!(f, "the file is not valid text (best guess: {})", encoding)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Reads and decodes a file, with an error message fit to show in the app
+pub fn read_file(path: &str) -> anyhow::Result<DecodedText> {
+    let bytes =
+        fs::read(Path::new(path)).map_err(|e| anyhow::anyhow!("Can't open {}: {}", path, e))?;
+    decode(&bytes).map_err(|e| anyhow::anyhow!("Can't load {}: {}", path, e))
+}
+
+/// Detects the encoding of `bytes` and converts them to normalized UTF-8 text
+pub fn decode(bytes: &[u8]) -> Result<DecodedText, DecodeError> {
+    // A BOM is authoritative, and UTF-16 text is full of NUL bytes, so check it first
+    let (text, encoding) = if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        if had_errors {
+            return Err(DecodeError::Malformed(encoding.name()));
+        }
+        (text.into_owned(), encoding)
+    } else if looks_binary(bytes) {
+        return Err(DecodeError::Binary);
+    } else if let Ok(text) = std::str::from_utf8(bytes) {
+        (text.to_string(), UTF_8)
+    } else {
+        let mut detector = EncodingDetector::new();
+        detector.feed(bytes, true);
+        let encoding = detector.guess(None, true);
+        let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if had_errors {
+            return Err(DecodeError::Malformed(encoding.name()));
+        }
+        (text.into_owned(), encoding)
+    };
+
+    let had_crlf = text.contains('\r');
+    let text = if had_crlf {
+        text.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        text
+    };
+
+    Ok(DecodedText {
+        text,
+        encoding: encoding.name(),
+        had_crlf,
+    })
+}
+
+fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c))
+        .count();
+    control * 10 > sample.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_passes_through() {
+        let decoded = decode("fn main() { println!(\"héllo\"); }\n".as_bytes()).unwrap();
+        assert_eq!(decoded.encoding, "UTF-8");
+        assert!(decoded.text.contains("héllo"));
+        assert!(!decoded.had_crlf);
+    }
+
+    #[test]
+    fn test_crlf_is_normalized() {
+        let decoded = decode(b"let a = 1;\r\nlet b = 2;\r\n").unwrap();
+        assert_eq!(decoded.text, "let a = 1;\nlet b = 2;\n");
+        assert!(decoded.had_crlf);
+    }
+
+    #[test]
+    fn test_latin1_is_converted() {
+        // "// café au lait" encoded as windows-1252
+        let mut bytes = b"// caf".to_vec();
+        bytes.push(0xE9);
+        bytes.extend_from_slice(b" au lait, tr");
+        bytes.push(0xE8);
+        bytes.extend_from_slice(b"s bon\nfn main() {}\n");
+
+        let decoded = decode(&bytes).unwrap();
+        assert_ne!(decoded.encoding, "UTF-8");
+        assert!(decoded.text.contains("café"));
+    }
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "let x = 1;".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.encoding, "UTF-16LE");
+        assert_eq!(decoded.text, "let x = 1;");
+    }
+
+    #[test]
+    fn test_binary_is_refused() {
+        let bytes = [0x7f, b'E', b'L', b'F', 0x02, 0x01, 0x01, 0x00, 0x00];
+        assert_eq!(decode(&bytes), Err(DecodeError::Binary));
+        assert!(DecodeError::Binary.to_string().contains("binary"));
+    }
+}
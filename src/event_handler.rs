@@ -1,17 +1,26 @@
 use log::info;
 use std::time::Instant;
 use winit::{
-    application::ApplicationHandler, event::WindowEvent, event_loop::ActiveEventLoop,
+    application::ApplicationHandler,
+    event::{ElementState, WindowEvent},
+    event_loop::ActiveEventLoop,
     window::WindowId,
 };
 
 use crate::app::CargoTapApp;
+use crate::daemon;
 use crate::input;
+use crate::tutorial;
 use crate::typing_handler;
+use crate::ui_blocks;
 
 impl ApplicationHandler for CargoTapApp {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         self.render_engine.resumed(event_loop);
+        if !self.render_engine.is_ready() {
+            return;
+        }
+        self.detect_refresh_rate();
         if let Err(e) = self.initialize_text_system() {
             log::error!("Failed to initialize text system: {}", e);
         }
@@ -26,12 +35,38 @@ impl ApplicationHandler for CargoTapApp {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        // winit 0.30 key events carry no OS timestamp, so latency is measured from here
+        let received_at = Instant::now();
+
+        // The renderer exits on CloseRequested, so hold it back while the user decides,
+        // and for good when the daemon only hides the window
         if let WindowEvent::CloseRequested = &event {
-            self.save_progress();
+            if !self.request_exit() {
+                return;
+            }
+            if self.daemon.is_some() {
+                self.hide_window();
+                return;
+            }
         }
 
         let is_resized = matches!(&event, WindowEvent::Resized(_));
 
+        // The window may have moved to a monitor with another refresh rate
+        if matches!(
+            &event,
+            WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. }
+        ) {
+            self.detect_refresh_rate();
+        }
+
+        // Time spent in another window shouldn't count against the session
+        if let WindowEvent::Focused(false) = &event
+            && self.config.gameplay.pause_on_focus_loss
+        {
+            self.pause_session();
+        }
+
         if let WindowEvent::ModifiersChanged(modifiers) = &event {
             info!("Modifiers changed: {:?}", modifiers.state());
             self.input_handler.update_modifiers(modifiers.state());
@@ -41,40 +76,248 @@ impl ApplicationHandler for CargoTapApp {
             event: key_event, ..
         } = &event
         {
-            let start_time = Instant::now();
-
             self.input_handler.process_key_event(key_event.clone());
-
-            if let Some(input::InputAction::Quit) = self.input_handler.get_last_action() {
-                if self.show_statistics {
-                    self.show_statistics = false;
-                    log::info!("📊 Closed statistics screen");
-                    self.input_handler.clear_last_action();
-                    self.update_text();
+            if let Some(monitor) = &mut self.stutter_monitor
+                && let Some(action) = self.input_handler.get_last_action()
+            {
+                // Which key was typed doesn't matter for a stutter, and could be private
+                monitor.record_action(match action {
+                    input::InputAction::TypeCharacter(_) => "TypeCharacter".to_string(),
+                    other => format!("{:?}", other),
+                });
+            }
+            if self.process_action() {
+                if self.daemon.is_some() {
+                    self.hide_window();
+                } else {
+                    event_loop.exit();
                     return;
                 }
-
-                self.save_progress();
-                event_loop.exit();
-                return;
             }
 
-            typing_handler::handle_typing_input(self);
-            self.update_text();
-
-            let elapsed = start_time.elapsed();
+            let elapsed = received_at.elapsed();
             self.last_key_processing_time_ms = elapsed.as_secs_f64() * 1000.0;
+            self.perf_stats
+                .key_processing
+                .record(self.last_key_processing_time_ms);
+            if key_event.state == ElementState::Pressed {
+                self.perf_stats
+                    .key_latency
+                    .record(self.last_key_processing_time_ms);
+            }
         }
 
         self.render_engine
             .window_event(event_loop, _window_id, event);
 
         if is_resized {
+            if let Some(text_system) = &mut self.text_system
+                && let Some([width, height]) = self.render_engine.get_window_size()
+            {
+                text_system.update_window_size(width, height);
+            }
             self.update_text();
         }
     }
 
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        self.save_window_geometry();
+        self.perf_stats.report_all();
+    }
+
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        self.answer_daemon(_event_loop);
+        self.poll();
+        self.render_engine.about_to_wait(_event_loop);
+    }
+}
+
+impl CargoTapApp {
+    /// Answers the requests `cargotap open` and `cargotap daemon` sent since the last frame
+    fn answer_daemon(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(server) = &self.daemon else {
+            return;
+        };
+        let requests: Vec<daemon::Request> = server.requests().collect();
+        for request in requests {
+            info!("Daemon request: {:?}", request.command);
+            match request.command.clone() {
+                daemon::Command::Open(path) => {
+                    let path = path.map(|path| path.to_string_lossy().into_owned());
+                    match self.open_from_daemon(path) {
+                        Ok(()) => request.reply(format!("opened {}", self.current_file_path)),
+                        Err(e) => request.reply(format!("error: {:#}", e)),
+                    }
+                }
+                daemon::Command::Status => request.reply(self.daemon_status()),
+                daemon::Command::Stop => {
+                    self.save_progress();
+                    request.reply("stopping".to_string());
+                    event_loop.exit();
+                }
+            }
+        }
+    }
+
+    /// Handles the action the input handler produced for the last key.
+    /// Returns true when the app should exit.
+    pub fn process_action(&mut self) -> bool {
+        // Works on every screen, so the state can be captured wherever a bug shows
+        if let Some(input::InputAction::DumpState) = self.input_handler.get_last_action() {
+            self.dump_state();
+            self.input_handler.clear_last_action();
+            self.update_text();
+            return false;
+        }
+
+        if let Some(input::InputAction::ToggleFullscreen) = self.input_handler.get_last_action() {
+            self.render_engine.toggle_fullscreen();
+            self.input_handler.clear_last_action();
+            return false;
+        }
+
+        if let Some(input::InputAction::Quit) = self.input_handler.get_last_action() {
+            if self.practice_lock_mode {
+                self.practice_lock_mode = false;
+                log::info!("🔒 Staying to practice");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.exit_confirm_mode {
+                self.close_exit_confirm();
+                log::info!("🚪 Staying in the session");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.home_row_pending() {
+                self.home_row_check = None;
+                log::info!("🏠 Skipped the home row reminder");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.resume_offer.take().is_some() {
+                log::info!("↪ Starting the changed file from the beginning");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.whats_new.take().is_some() {
+                log::info!("✨ Closed what's new");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.note_edit_path.is_some() {
+                self.note_edit_path = None;
+                log::info!("📝 Discarded note edit");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.show_statistics {
+                self.show_statistics = false;
+                log::info!("📊 Closed statistics screen");
+                self.advance_tutorial(tutorial::Action::StatisticsClosed);
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.settings_mode {
+                self.color_picker.cancel(&mut self.config.colors);
+                self.settings_mode = false;
+                log::info!("🎨 Closed settings without saving");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.line_range_mode {
+                self.line_range_mode = false;
+                self.line_range_error = None;
+                log::info!("📏 Closed line range prompt");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.github_mode {
+                self.github_mode = false;
+                self.github_error = None;
+                // The download finishes on its own and is dropped
+                self.github_fetch = None;
+                log::info!("🐙 Closed GitHub prompt");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.goto_line_mode {
+                self.goto_line_mode = false;
+                self.goto_line_error = None;
+                log::info!("↪ Closed go-to-line prompt");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.end_condition_menu.take().is_some() {
+                log::info!("🏁 Closed session setup");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.dogfood_menu_mode {
+                self.dogfood_menu_mode = false;
+                log::info!("🐶 Closed dogfood menu");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.drill_menu_mode {
+                self.drill_menu_mode = false;
+                log::info!("🥁 Closed drills menu");
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            if self.tutorial.is_some() {
+                self.end_tutorial(true);
+                self.input_handler.clear_last_action();
+                self.update_text();
+                return false;
+            }
+
+            self.input_handler.clear_last_action();
+            return self.request_exit();
+        }
+
+        typing_handler::handle_typing_input(self);
+        if self.exit_requested {
+            self.save_progress();
+            return true;
+        }
+        self.update_text_after_input();
+        false
+    }
+
+    /// Advances everything driven by time rather than input: the session timer,
+    /// toasts, script events, reminders, the pomodoro clock and the frame-time graph.
+    pub fn poll(&mut self) {
+        self.collect_highlighting();
+
         if self.session_state.is_active() {
             let current_position = self.code_state.get_cursor_position();
             let session_just_finished = self.session_state.update(current_position);
@@ -82,12 +325,81 @@ impl ApplicationHandler for CargoTapApp {
             if session_just_finished {
                 log::info!("Session just finished (timer expired)!");
                 self.save_session_statistics();
+                self.update_text();
+            } else {
+                self.check_accuracy_floor();
+                self.tick_metronome();
+                self.publish_session_progress();
+                self.update_text_if_stale();
             }
+        } else if self.accuracy_alarm.is_tripped() {
+            self.accuracy_alarm.reset();
+        }
 
+        let toast_expired = self.toast.as_ref().is_some_and(|(_, shown_at)| {
+            shown_at.elapsed().as_secs_f64() >= ui_blocks::TOAST_DURATION_SECS
+        });
+        if toast_expired {
+            self.toast = None;
             self.update_text();
         }
 
+        if self.dispatch_script_events() {
+            self.update_text();
+        }
+
+        if self.poll_github_fetch() {
+            self.update_text();
+        }
+
+        if let Some(reminder) = &mut self.reminder {
+            reminder.poll(&self.session_history);
+        }
+
+        if self.pomodoro.as_mut().is_some_and(|p| p.poll()) {
+            self.resume_after_break();
+        }
+        // Keep the break countdown ticking
+        if self.pomodoro.as_ref().is_some_and(|p| p.is_on_break()) {
+            self.update_text();
+        }
+
+        if self.home_row_pending() {
+            if self.home_row_check.as_ref().is_some_and(|c| c.poll()) {
+                self.finish_home_row_check();
+                self.update_text();
+            } else if self
+                .home_row_check
+                .as_ref()
+                .is_some_and(|c| c.countdown_remaining().is_some())
+            {
+                self.update_text();
+            }
+        }
+
+        let dump_interval = self.config.debug.state_dump_interval_secs;
+        if dump_interval > 0.0
+            && self.last_state_dump.elapsed().as_secs_f64() >= dump_interval
+            && let Err(e) = self.write_state()
+        {
+            log::error!("Failed to save app state: {}", e);
+        }
+
+        // Scrolling and fading move on every frame until they are done
+        if self.animations.is_running() {
+            self.update_text();
+        }
+
+        // Scroll lock and follow scrolling take the view back to the cursor once a peek is over
+        if self.snap_scroll_to_cursor() {
+            self.update_text();
+        }
+
+        self.flush_deferred_relayout();
         self.update_frame_time();
-        self.render_engine.about_to_wait(_event_loop);
+        // Keep the frame-time graph moving outside of sessions too
+        if self.config.debug.show_frame_times {
+            self.update_text_if_stale();
+        }
     }
 }
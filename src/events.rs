@@ -0,0 +1,108 @@
+//! Event bus for app state changes
+//!
+//! The app publishes an `AppEvent` whenever something other parts of the
+//! program may care about happens. Integrations subscribe to get their own
+//! channel and react on their own schedule, so adding one does not mean
+//! touching the typing or session code.
+//!
+//! Subscribers whose receiver has been dropped are forgotten on the next publish.
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::session_state::SessionStats;
+
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    /// A file was opened for practice
+    FileLoaded { path: String },
+    /// The first keystroke of a session was typed
+    SessionStarted { file_path: String },
+    /// A running session's speed, published every few seconds
+    SessionProgress {
+        file_path: String,
+        words_per_minute: f64,
+        elapsed_secs: f64,
+    },
+    /// A session ended and its statistics are final
+    SessionFinished(Box<SessionStats>),
+    /// The color theme was changed and saved
+    ThemeChanged,
+}
+
+impl fmt::Display for AppEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppEvent::FileLoaded { path } => write!(f, "file loaded: {}", path),
+            AppEvent::SessionStarted { file_path } => write!(f, "session started: {}", file_path),
+            AppEvent::SessionProgress {
+                file_path,
+                words_per_minute,
+                ..
+            } => write!(
+                f,
+                "session progress: {:.0} WPM in {}",
+                words_per_minute, file_path
+            ),
+            AppEvent::SessionFinished(stats) => write!(
+                f,
+                "session finished: {:.0} WPM in {}",
+                stats.words_per_minute, stats.file_path
+            ),
+            AppEvent::ThemeChanged => write!(f, "theme changed"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EventBus {
+    subscribers: Vec<Sender<AppEvent>>,
+}
+
+impl EventBus {
+    /// Returns a receiver that gets every event published from now on
+    pub fn subscribe(&mut self) -> Receiver<AppEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    pub fn publish(&mut self, event: AppEvent) {
+        log::debug!("📣 {}", event);
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_subscriber_gets_events() {
+        let mut bus = EventBus::default();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+
+        bus.publish(AppEvent::ThemeChanged);
+        bus.publish(AppEvent::FileLoaded {
+            path: "main.rs".to_string(),
+        });
+
+        for receiver in [first, second] {
+            let events: Vec<String> = receiver.try_iter().map(|e| e.to_string()).collect();
+            assert_eq!(events, vec!["theme changed", "file loaded: main.rs"]);
+        }
+    }
+
+    #[test]
+    fn test_dropped_subscribers_are_forgotten() {
+        let mut bus = EventBus::default();
+        let kept = bus.subscribe();
+        drop(bus.subscribe());
+
+        bus.publish(AppEvent::ThemeChanged);
+        assert_eq!(bus.subscribers.len(), 1);
+        assert!(matches!(kept.try_recv(), Ok(AppEvent::ThemeChanged)));
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
 
 // Import the colored text types
-use crate::text::{ColoredChar, ColoredText};
+use crate::keywords::KeywordSet;
+use crate::language::{self, Language};
+use crate::text::ColoredText;
 
 /// Demonstrates various colored text effects
 pub struct ColoredTextDemo;
@@ -29,108 +31,271 @@ impl ColoredTextDemo {
     }
 
     /// Creates syntax highlighted Rust code
-    pub fn create_syntax_highlighted_rust(code: &str) -> ColoredText {
+    pub fn create_syntax_highlighted_rust(code: &str, keywords: &KeywordSet) -> ColoredText {
+        Self::create_syntax_highlighted(code, keywords, &language::RUST)
+    }
+
+    /// Creates syntax highlighted code, with the comment and string syntax of `language`
+    ///
+    /// Strings, raw strings, char literals and comments are scanned as whole
+    /// tokens, so quotes inside them never leak string state into the rest of
+    /// the file.
+    pub fn create_syntax_highlighted(
+        code: &str,
+        keywords: &KeywordSet,
+        language: &Language,
+    ) -> ColoredText {
+        let chars: Vec<char> = code.chars().collect();
         let mut colored_text = ColoredText::new();
         let mut current_word = String::new();
-        let mut in_string = false;
-        let mut in_comment = false;
-        let mut comment_type = CommentType::None;
-        let mut prev_char = '\0';
-
-        for ch in code.chars() {
-            // Handle string literals
-            if ch == '"' && !in_comment && prev_char != '\\' {
-                in_string = !in_string;
-                colored_text.push(ch, [1.0, 0.6, 0.6, 1.0]); // Pink for quotes
-                prev_char = ch;
-                continue;
-            }
+        let mut i = 0;
 
-            if in_string {
-                colored_text.push(ch, [1.0, 0.8, 0.4, 1.0]); // Light orange for string content
-                prev_char = ch;
-                continue;
-            }
+        while i < chars.len() {
+            let ch = chars[i];
 
-            // Handle comments
-            if !in_comment && ch == '/' && prev_char == '/' {
-                in_comment = true;
-                comment_type = CommentType::Line;
-                // Remove the previous '/' and add both as comment color
-                if let Some(last_line) = colored_text.lines.last_mut() {
-                    if let Some(_last_char) = last_line.chars.pop() {
-                        last_line.push('/', [0.5, 0.5, 0.5, 1.0]); // Gray for comments
-                    }
+            if ch.is_alphanumeric() || ch == '_' {
+                // b"..", r#".."#, br".." and b'.' start like identifiers
+                if current_word.is_empty()
+                    && let Some(end) = Self::highlight_prefixed_literal(
+                        &chars,
+                        i,
+                        language.char_literals,
+                        &mut colored_text,
+                    )
+                {
+                    i = end;
+                    continue;
                 }
-                colored_text.push(ch, [0.5, 0.5, 0.5, 1.0]);
-                prev_char = ch;
+                current_word.push(ch);
+                i += 1;
                 continue;
             }
 
-            if !in_comment && ch == '*' && prev_char == '/' {
-                in_comment = true;
-                comment_type = CommentType::Block;
-                // Remove the previous '/' and add both as comment color
-                if let Some(last_line) = colored_text.lines.last_mut() {
-                    if let Some(_last_char) = last_line.chars.pop() {
-                        last_line.push('/', [0.5, 0.5, 0.5, 1.0]);
-                    }
+            Self::flush_word(&mut current_word, keywords, &mut colored_text);
+
+            let next = chars.get(i + 1).copied();
+            let line_comment = language.code_comments().iter().any(|prefix| {
+                chars.len() - i >= prefix.len()
+                    && prefix.chars().zip(&chars[i..]).all(|(a, &b)| a == b)
+            });
+            i = match (ch, next) {
+                _ if line_comment => Self::highlight_line_comment(&chars, i, &mut colored_text),
+                ('/', Some('*')) if language.block_comments => Self::highlight_block_comment(
+                    &chars,
+                    i,
+                    language.nested_comments,
+                    &mut colored_text,
+                ),
+                ('"', _) => Self::highlight_string(&chars, i, i, None, &mut colored_text),
+                ('\'', _) if language.char_literals => {
+                    Self::highlight_tick(&chars, i, &mut colored_text)
                 }
-                colored_text.push(ch, [0.5, 0.5, 0.5, 1.0]);
-                prev_char = ch;
-                continue;
+                ('\'', _) => Self::highlight_string(&chars, i, i, None, &mut colored_text),
+                ('`', _) if language.backtick_strings => {
+                    Self::highlight_string(&chars, i, i, Some(0), &mut colored_text)
+                }
+                _ => {
+                    // Color special characters
+                    let char_color = match ch {
+                        '{' | '}' | '(' | ')' | '[' | ']' => [1.0, 0.8, 0.4, 1.0], // Orange brackets
+                        ';' | ',' => [0.8, 0.8, 0.8, 1.0], // Light gray punctuation
+                        '=' | '+' | '-' | '*' | '/' | '%' => [1.0, 1.0, 0.4, 1.0], // Yellow operators
+                        '!' => [1.0, 0.4, 1.0, 1.0], // Magenta exclamation
+                        '.' => [0.8, 0.8, 1.0, 1.0], // Light blue dots
+                        ':' => [1.0, 0.8, 0.8, 1.0], // Light red colons
+                        _ => [0.8, 0.8, 0.8, 1.0],   // Default light gray
+                    };
+                    colored_text.push(ch, char_color);
+                    i + 1
+                }
+            };
+        }
+
+        // Handle last word if any
+        Self::flush_word(&mut current_word, keywords, &mut colored_text);
+
+        colored_text
+    }
+
+    fn flush_word(word: &mut String, keywords: &KeywordSet, colored_text: &mut ColoredText) {
+        if word.is_empty() {
+            return;
+        }
+        let color = keywords.color(word);
+        colored_text.push_str(word, color);
+        word.clear();
+    }
+
+    /// Highlights a literal with a `b`/`r` prefix starting at `start`.
+    /// Returns the index after it, or None if no such literal starts there.
+    /// Without `char_literals`, single quotes start strings like double quotes.
+    fn highlight_prefixed_literal(
+        chars: &[char],
+        start: usize,
+        char_literals: bool,
+        colored_text: &mut ColoredText,
+    ) -> Option<usize> {
+        let mut i = start;
+        if chars[i] == 'b' {
+            i += 1;
+            if char_literals && chars.get(i) == Some(&'\'') {
+                colored_text.push('b', STRING_QUOTE_COLOR);
+                return Some(Self::highlight_tick(chars, i, colored_text));
             }
+        }
 
-            if in_comment {
-                if comment_type == CommentType::Line && ch == '\n' {
-                    in_comment = false;
-                    comment_type = CommentType::None;
-                } else if comment_type == CommentType::Block && ch == '/' && prev_char == '*' {
-                    in_comment = false;
-                    comment_type = CommentType::None;
-                }
-                colored_text.push(ch, [0.5, 0.5, 0.5, 1.0]); // Gray for comments
-                prev_char = ch;
-                continue;
+        let raw = chars.get(i) == Some(&'r');
+        let mut hashes = 0;
+        if raw {
+            i += 1;
+            while chars.get(i) == Some(&'#') {
+                hashes += 1;
+                i += 1;
             }
+        }
 
-            // Handle words (keywords, identifiers, etc.)
-            if ch.is_alphanumeric() || ch == '_' {
-                current_word.push(ch);
-            } else {
-                if !current_word.is_empty() {
-                    let color = Self::get_keyword_color(&current_word);
-                    for word_ch in current_word.chars() {
-                        colored_text.push(word_ch, color);
+        let quote = chars.get(i) == Some(&'"') || (!char_literals && chars.get(i) == Some(&'\''));
+        if !quote || i == start {
+            return None;
+        }
+        Some(Self::highlight_string(
+            chars,
+            start,
+            i,
+            raw.then_some(hashes),
+            colored_text,
+        ))
+    }
+
+    /// Highlights a string whose prefix starts at `start` and opening quote is at `quote`;
+    /// the same quote character closes it.
+    /// `raw_hashes` is the number of `#` around a raw string, None for an escaped string.
+    fn highlight_string(
+        chars: &[char],
+        start: usize,
+        quote: usize,
+        raw_hashes: Option<usize>,
+        colored_text: &mut ColoredText,
+    ) -> usize {
+        for &ch in &chars[start..=quote] {
+            colored_text.push(ch, STRING_QUOTE_COLOR);
+        }
+
+        let close = chars[quote];
+        let mut i = quote + 1;
+        while i < chars.len() {
+            let ch = chars[i];
+            match raw_hashes {
+                Some(hashes) if ch == close => {
+                    let closing = chars[i + 1..].iter().take(hashes);
+                    if closing.clone().count() == hashes && closing.clone().all(|&c| c == '#') {
+                        for &ch in &chars[i..=i + hashes] {
+                            colored_text.push(ch, STRING_QUOTE_COLOR);
+                        }
+                        return i + hashes + 1;
+                    }
+                }
+                None if ch == close => {
+                    colored_text.push(ch, STRING_QUOTE_COLOR);
+                    return i + 1;
+                }
+                None if ch == '\\' => {
+                    // The escaped character can't end the string
+                    colored_text.push(ch, STRING_COLOR);
+                    i += 1;
+                    if let Some(&escaped) = chars.get(i) {
+                        colored_text.push(escaped, STRING_COLOR);
+                        i += 1;
                     }
-                    current_word.clear();
+                    continue;
                 }
+                _ => {}
+            }
+            colored_text.push(ch, STRING_COLOR);
+            i += 1;
+        }
+        i
+    }
 
-                // Color special characters
-                let char_color = match ch {
-                    '{' | '}' | '(' | ')' | '[' | ']' => [1.0, 0.8, 0.4, 1.0], // Orange brackets
-                    ';' | ',' => [0.8, 0.8, 0.8, 1.0], // Light gray punctuation
-                    '=' | '+' | '-' | '*' | '/' | '%' => [1.0, 1.0, 0.4, 1.0], // Yellow operators
-                    '!' => [1.0, 0.4, 1.0, 1.0],       // Magenta exclamation
-                    '.' => [0.8, 0.8, 1.0, 1.0],       // Light blue dots
-                    ':' => [1.0, 0.8, 0.8, 1.0],       // Light red colons
-                    _ => [0.8, 0.8, 0.8, 1.0],         // Default light gray
-                };
-                colored_text.push(ch, char_color);
+    /// Highlights a char literal (`'a'`, `'\n'`, `'\u{1F980}'`) or a lifetime (`'a`) at `start`
+    fn highlight_tick(chars: &[char], start: usize, colored_text: &mut ColoredText) -> usize {
+        let end = match chars.get(start + 1) {
+            Some('\\') => chars
+                .iter()
+                .enumerate()
+                .skip(start + 3)
+                .take(MAX_ESCAPE_LEN)
+                .find(|&(_, &c)| c == '\'' || c == '\n')
+                .filter(|&(_, &c)| c == '\'')
+                .map(|(end, _)| end),
+            Some(&c) if c != '\'' && c != '\n' && chars.get(start + 2) == Some(&'\'') => {
+                Some(start + 2)
             }
+            _ => None,
+        };
 
-            prev_char = ch;
+        if let Some(end) = end {
+            colored_text.push('\'', STRING_QUOTE_COLOR);
+            for &ch in &chars[start + 1..end] {
+                colored_text.push(ch, STRING_COLOR);
+            }
+            colored_text.push('\'', STRING_QUOTE_COLOR);
+            return end + 1;
         }
 
-        // Handle last word if any
-        if !current_word.is_empty() {
-            let color = Self::get_keyword_color(&current_word);
-            for word_ch in current_word.chars() {
-                colored_text.push(word_ch, color);
-            }
+        // A lifetime or loop label
+        let mut i = start + 1;
+        colored_text.push('\'', LIFETIME_COLOR);
+        while let Some(&ch) = chars.get(i)
+            && (ch.is_alphanumeric() || ch == '_')
+        {
+            colored_text.push(ch, LIFETIME_COLOR);
+            i += 1;
         }
+        i
+    }
 
-        colored_text
+    fn highlight_line_comment(
+        chars: &[char],
+        start: usize,
+        colored_text: &mut ColoredText,
+    ) -> usize {
+        let mut i = start;
+        while let Some(&ch) = chars.get(i)
+            && ch != '\n'
+        {
+            colored_text.push(ch, COMMENT_COLOR);
+            i += 1;
+        }
+        i
+    }
+
+    /// Highlights a block comment, which may be `nested` like in Rust
+    fn highlight_block_comment(
+        chars: &[char],
+        start: usize,
+        nested: bool,
+        colored_text: &mut ColoredText,
+    ) -> usize {
+        let mut depth = 0;
+        let mut i = start;
+        while i < chars.len() {
+            let pair = (chars[i], chars.get(i + 1).copied());
+            let opens = pair == ('/', Some('*')) && (nested || i == start);
+            if opens || pair == ('*', Some('/')) {
+                depth = if opens { depth + 1 } else { depth - 1 };
+                colored_text.push(chars[i], COMMENT_COLOR);
+                colored_text.push(chars[i + 1], COMMENT_COLOR);
+                i += 2;
+                if depth == 0 {
+                    break;
+                }
+                continue;
+            }
+            colored_text.push(chars[i], COMMENT_COLOR);
+            i += 1;
+        }
+        i
     }
 
     /// Creates a gradient text effect
@@ -204,32 +369,6 @@ impl ColoredTextDemo {
         colored_text
     }
 
-    /// Gets the appropriate color for Rust keywords
-    fn get_keyword_color(word: &str) -> [f32; 4] {
-        match word {
-            // Control flow keywords - Blue
-            "if" | "else" | "match" | "loop" | "while" | "for" | "break" | "continue"
-            | "return" => [0.4, 0.6, 1.0, 1.0],
-            // Declaration keywords - Purple
-            "fn" | "let" | "mut" | "const" | "static" | "struct" | "enum" | "impl" | "trait" => {
-                [0.8, 0.4, 1.0, 1.0]
-            }
-            // Visibility and module keywords - Magenta
-            "pub" | "mod" | "use" | "crate" | "super" | "self" => [1.0, 0.4, 0.8, 1.0],
-            // Types - Green
-            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
-            | "u128" | "usize" | "f32" | "f64" | "bool" | "char" | "str" | "String" | "Vec"
-            | "Option" | "Result" => [0.4, 1.0, 0.4, 1.0],
-            // Functions and macros - Cyan
-            "println" | "print" | "dbg" | "panic" | "todo" | "unimplemented" | "main" | "new"
-            | "default" => [0.2, 1.0, 1.0, 1.0],
-            // Literals - Orange
-            "true" | "false" | "None" | "Some" | "Ok" | "Err" => [1.0, 0.7, 0.2, 1.0],
-            // Default identifier color - Light gray
-            _ => [0.9, 0.9, 0.9, 1.0],
-        }
-    }
-
     /// Creates a comprehensive demo combining multiple effects
     pub fn create_comprehensive_demo() -> ColoredText {
         let mut colored_text = ColoredText::new();
@@ -292,7 +431,7 @@ impl ColoredTextDemo {
         None => println!("Nothing here"),
     }
 }"#;
-        let highlighted = Self::create_syntax_highlighted_rust(sample_code);
+        let highlighted = Self::create_syntax_highlighted_rust(sample_code, &KeywordSet::default());
         for line in highlighted.lines {
             colored_text.lines.push(line);
         }
@@ -301,12 +440,13 @@ impl ColoredTextDemo {
     }
 }
 
-#[derive(PartialEq)]
-enum CommentType {
-    None,
-    Line,
-    Block,
-}
+const STRING_QUOTE_COLOR: [f32; 4] = [1.0, 0.6, 0.6, 1.0]; // Pink for quotes
+const STRING_COLOR: [f32; 4] = [1.0, 0.8, 0.4, 1.0]; // Light orange for string content
+const COMMENT_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0]; // Gray for comments
+const LIFETIME_COLOR: [f32; 4] = [0.6, 0.8, 1.0, 1.0]; // Light blue for lifetimes and labels
+
+/// Longest escape in a char literal, `\u{10FFFF}`, not counting the backslash
+const MAX_ESCAPE_LEN: usize = 9;
 
 /// Example usage function
 pub fn run_colored_text_examples() -> Result<()> {
@@ -336,7 +476,7 @@ pub fn run_colored_text_examples() -> Result<()> {
     let code = r#"pub fn hello() {
         println!("Hello, colored world!");
     }"#;
-    let highlighted = ColoredTextDemo::create_syntax_highlighted_rust(code);
+    let highlighted = ColoredTextDemo::create_syntax_highlighted_rust(code, &KeywordSet::default());
     let highlighted_char_count: usize = highlighted.lines.iter().map(|line| line.chars.len()).sum();
     println!(
         "Created syntax highlighted code with {} characters",
@@ -401,13 +541,14 @@ mod tests {
     #[test]
     fn test_syntax_highlighting() {
         let code = "fn main() {}";
-        let highlighted = ColoredTextDemo::create_syntax_highlighted_rust(code);
+        let highlighted =
+            ColoredTextDemo::create_syntax_highlighted_rust(code, &KeywordSet::default());
 
         let char_count: usize = highlighted.lines.iter().map(|line| line.chars.len()).sum();
         assert_eq!(char_count, code.len());
 
         // Check that 'fn' keyword has the expected color
-        let fn_color = ColoredTextDemo::get_keyword_color("fn");
+        let fn_color = KeywordSet::default().color("fn");
         if let Some(first_line) = highlighted.lines.first() {
             assert_eq!(first_line.chars[0].color, fn_color);
             assert_eq!(first_line.chars[1].color, fn_color);
@@ -417,13 +558,131 @@ mod tests {
     #[test]
     fn test_keyword_colors() {
         // Test different keyword categories
-        let control_color = ColoredTextDemo::get_keyword_color("if");
-        let declaration_color = ColoredTextDemo::get_keyword_color("fn");
-        let type_color = ColoredTextDemo::get_keyword_color("i32");
+        let control_color = KeywordSet::default().color("if");
+        let declaration_color = KeywordSet::default().color("fn");
+        let type_color = KeywordSet::default().color("i32");
 
         // All should be different colors
         assert_ne!(control_color, declaration_color);
         assert_ne!(declaration_color, type_color);
         assert_ne!(control_color, type_color);
     }
+
+    /// Colors of every character in a single-line snippet
+    fn colors(code: &str) -> Vec<[f32; 4]> {
+        ColoredTextDemo::create_syntax_highlighted_rust(code, &KeywordSet::default())
+            .iter_chars()
+            .map(|c| c.color)
+            .collect()
+    }
+
+    /// Color of the first character of the last occurrence of `needle`
+    fn color_of_last(code: &str, needle: &str) -> [f32; 4] {
+        let byte = code.rfind(needle).unwrap();
+        colors(code)[code[..byte].chars().count()]
+    }
+
+    #[test]
+    fn test_highlighting_keeps_every_character() {
+        let code = "let s = r#\"a\"b\"#; // é\n/* x */ 'a'";
+        let highlighted =
+            ColoredTextDemo::create_syntax_highlighted_rust(code, &KeywordSet::default());
+        let text: String = highlighted
+            .lines
+            .iter()
+            .map(|l| l.chars.iter().map(|c| c.ch).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(text, code);
+    }
+
+    #[test]
+    fn test_escaped_quotes_end_string_correctly() {
+        let keyword = KeywordSet::default().color("let");
+        assert_eq!(color_of_last("let s = \"a\\\"b\"; let", "let"), keyword);
+        // An escaped backslash right before the closing quote
+        assert_eq!(color_of_last("let s = \"a\\\\\"; let", "let"), keyword);
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let keyword = KeywordSet::default().color("let");
+        for literal in ["'\"'", "'a'", "'\\n'", "'\\''", "'\\u{1F980}'", "b'x'"] {
+            let code = format!("let q = {}; let", literal);
+            assert_eq!(color_of_last(&code, "let"), keyword, "after {}", literal);
+            let inner = &literal[literal.find('\'').unwrap() + 1..];
+            assert_eq!(color_of_last(&code, inner), STRING_COLOR, "in {}", literal);
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let keyword = KeywordSet::default().color("let");
+        let code = "let s = r#\"say \"hi\" \\\"#; let";
+        assert_eq!(color_of_last(code, "hi"), STRING_COLOR);
+        assert_eq!(color_of_last(code, "let"), keyword);
+
+        let code = "let s = br\"C:\\\"; let";
+        assert_eq!(color_of_last(code, "C"), STRING_COLOR);
+        assert_eq!(color_of_last(code, "let"), keyword);
+
+        // A raw identifier is not a string
+        assert_ne!(color_of_last("let r#type = 1;", "type"), STRING_COLOR);
+    }
+
+    #[test]
+    fn test_lifetimes_are_not_char_literals() {
+        let code = "fn f<'a>(x: &'a str) -> &'a str { 'outer: loop {} }";
+        let type_color = KeywordSet::default().color("str");
+        assert_eq!(color_of_last(code, "'a"), LIFETIME_COLOR);
+        assert_eq!(color_of_last(code, "str"), type_color);
+        assert_eq!(color_of_last(code, "outer"), LIFETIME_COLOR);
+        assert_eq!(
+            color_of_last(code, "loop"),
+            KeywordSet::default().color("loop")
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let keyword = KeywordSet::default().color("let");
+        assert_eq!(color_of_last("// \"unclosed\nlet", "let"), keyword);
+        assert_eq!(color_of_last("/* a /* b */ c */ let", "c"), COMMENT_COLOR);
+        assert_eq!(color_of_last("/* a /* b */ c */ let", "let"), keyword);
+    }
+
+    #[test]
+    fn test_other_languages() {
+        let highlight = |code: &str, language: &Language| -> Vec<[f32; 4]> {
+            let keywords = KeywordSet::for_language(language, 2024);
+            ColoredTextDemo::create_syntax_highlighted(code, &keywords, language)
+                .iter_chars()
+                .map(|c| c.color)
+                .collect()
+        };
+        let column = |code: &str, needle: &str| code[..code.rfind(needle).unwrap()].chars().count();
+
+        // Python: `#` comments and single-quoted strings
+        let code = "x = 'it # is' # done\nreturn";
+        let colors = highlight(code, &language::PYTHON);
+        assert_eq!(colors[column(code, "is")], STRING_COLOR);
+        assert_eq!(colors[column(code, "done")], COMMENT_COLOR);
+        let keywords = KeywordSet::for_language(&language::PYTHON, 2024);
+        assert_eq!(colors[column(code, "return")], keywords.color("return"));
+
+        // Go: raw strings in backticks, which don't take escapes
+        let code = "s := `C:\\` // path";
+        let colors = highlight(code, &language::GO);
+        assert_eq!(colors[column(code, "C")], STRING_COLOR);
+        assert_eq!(colors[column(code, "path")], COMMENT_COLOR);
+
+        // C: block comments don't nest
+        let code = "/* a /* b */ int c";
+        let colors = highlight(code, &language::C);
+        assert_ne!(colors[column(code, "int")], COMMENT_COLOR);
+        assert_eq!(
+            highlight("#include <x.h>", &language::C)[0],
+            [0.8, 0.8, 0.8, 1.0]
+        );
+    }
 }
@@ -0,0 +1,210 @@
+//! Quick look at a file before practicing it
+//!
+//! Opening a file from the picker shows what is in it: the share of letters,
+//! digits, brackets and other symbols, the symbols in it that the heatmap says
+//! are hardest for you, how long typing it would take at your average speed, and
+//! how many characters can't be typed on a US keyboard. Lines with those can be
+//! left out of files for good (`[gameplay] strip_untypeable_lines`, S on the panel).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::char_utils;
+use crate::session_state::KeyStat;
+
+/// Most symbols listed as the hardest ones
+pub const HARDEST_SHOWN: usize = 5;
+
+/// Attempts at a key before its miss rate is trusted
+const MIN_KEY_ATTEMPTS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Bracket,
+    Symbol,
+    /// Typeable characters outside ASCII, like accented letters
+    Other,
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 6] = [
+        CharClass::Letter,
+        CharClass::Digit,
+        CharClass::Space,
+        CharClass::Bracket,
+        CharClass::Symbol,
+        CharClass::Other,
+    ];
+
+    pub fn of(ch: char) -> Self {
+        match ch {
+            _ if ch.is_ascii_alphabetic() => CharClass::Letter,
+            _ if ch.is_ascii_digit() => CharClass::Digit,
+            _ if ch.is_whitespace() => CharClass::Space,
+            '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' => CharClass::Bracket,
+            _ if ch.is_ascii_punctuation() => CharClass::Symbol,
+            _ => CharClass::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CharClass::Letter => "Letters",
+            CharClass::Digit => "Digits",
+            CharClass::Space => "Spaces & newlines",
+            CharClass::Bracket => "Brackets",
+            CharClass::Symbol => "Symbols",
+            CharClass::Other => "Other",
+        }
+    }
+
+    fn index(self) -> usize {
+        CharClass::ALL
+            .iter()
+            .position(|class| *class == self)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileAnalysis {
+    /// Typeable characters of each class, in `CharClass::ALL` order; indentation
+    /// is left out as Enter types it
+    pub classes: [usize; 6],
+    /// The hardest symbols in the file and their miss rates, hardest first
+    pub hardest: Vec<(char, f64)>,
+    pub untypeable_chars: usize,
+    /// Lines with at least one untypeable character
+    pub untypeable_lines: usize,
+}
+
+impl FileAnalysis {
+    /// Analyzes `text` against the per-key statistics of past sessions
+    pub fn analyze(text: &str, key_stats: &BTreeMap<char, KeyStat>) -> Self {
+        let mut classes = [0; 6];
+        let mut untypeable_chars = 0;
+        let mut untypeable_lines = 0;
+        let mut present = BTreeSet::new();
+        for line in text.lines() {
+            let mut untypeable_line = false;
+            let content = line.trim_start();
+            // The newline ending the line is typed too
+            classes[CharClass::Space.index()] += 1;
+            for ch in content.chars() {
+                if !char_utils::is_typeable_on_us_keyboard(ch) {
+                    untypeable_chars += 1;
+                    untypeable_line = true;
+                    continue;
+                }
+                classes[CharClass::of(ch).index()] += 1;
+                present.insert(ch);
+            }
+            if untypeable_line {
+                untypeable_lines += 1;
+            }
+        }
+
+        let mut hardest: Vec<(char, f64)> = present
+            .iter()
+            .filter(|ch| !ch.is_whitespace())
+            .filter_map(|ch| key_stats.get(ch).map(|stat| (*ch, *stat)))
+            .filter(|(_, stat)| stat.hits + stat.misses >= MIN_KEY_ATTEMPTS && stat.misses > 0)
+            .map(|(ch, stat)| (ch, stat.error_rate()))
+            .collect();
+        hardest.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hardest.truncate(HARDEST_SHOWN);
+
+        Self {
+            classes,
+            hardest,
+            untypeable_chars,
+            untypeable_lines,
+        }
+    }
+
+    /// Characters a session over the whole file types
+    pub fn typed_chars(&self) -> usize {
+        self.classes.iter().sum()
+    }
+
+    /// Share of `class` among the typed characters (0.0 to 1.0)
+    pub fn share(&self, class: CharClass) -> f64 {
+        match self.typed_chars() {
+            0 => 0.0,
+            total => self.classes[class.index()] as f64 / total as f64,
+        }
+    }
+
+    /// Seconds typing the whole file takes at `chars_per_minute`
+    pub fn estimated_secs(&self, chars_per_minute: f64) -> Option<f64> {
+        (chars_per_minute > 0.0).then(|| self.typed_chars() as f64 / chars_per_minute * 60.0)
+    }
+}
+
+/// `text` without the lines that have untypeable characters, and how many were removed
+pub fn strip_untypeable_lines(text: &str) -> (String, usize) {
+    let mut removed = 0;
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if line
+            .chars()
+            .any(|ch| !char_utils::is_typeable_on_us_keyboard(ch))
+        {
+            removed += 1;
+        } else {
+            out.push_str(line);
+        }
+    }
+    (out, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "fn main() {\n    let x = 42; // ✓ done\n    println!(\"{}\", x);\n}\n";
+
+    #[test]
+    fn test_analyze() {
+        let mut key_stats = BTreeMap::new();
+        key_stats.insert('{', KeyStat { hits: 8, misses: 2 });
+        key_stats.insert(
+            '!',
+            KeyStat {
+                hits: 15,
+                misses: 5,
+            },
+        );
+        // Too few attempts to judge, and a key the file doesn't have
+        key_stats.insert('x', KeyStat { hits: 1, misses: 3 });
+        key_stats.insert(
+            '#',
+            KeyStat {
+                hits: 5,
+                misses: 50,
+            },
+        );
+
+        let analysis = FileAnalysis::analyze(TEXT, &key_stats);
+        assert_eq!(analysis.hardest, [('!', 0.25), ('{', 0.2)]);
+        assert_eq!(
+            (analysis.untypeable_chars, analysis.untypeable_lines),
+            (1, 1)
+        );
+        assert_eq!(analysis.classes[CharClass::Digit.index()], 2);
+        assert_eq!(analysis.classes[CharClass::Bracket.index()], 8);
+        // No indentation, the check mark left out
+        assert_eq!(analysis.typed_chars(), 54);
+        assert_eq!(analysis.estimated_secs(270.0), Some(12.0));
+        assert_eq!(analysis.estimated_secs(0.0), None);
+    }
+
+    #[test]
+    fn test_strip_untypeable_lines() {
+        let (stripped, removed) = strip_untypeable_lines(TEXT);
+        assert_eq!(removed, 1);
+        assert_eq!(stripped, "fn main() {\n    println!(\"{}\", x);\n}\n");
+    }
+}
@@ -0,0 +1,354 @@
+//! The file picker's listing
+//!
+//! File selection mode lists the directory of the typed path below the prompt,
+//! with recently opened files on top. Whatever follows the last `/` of a path that
+//! doesn't exist is a fuzzy search: entries whose names contain its letters in
+//! order are kept, best matches first. The arrow keys move through the listing and
+//! Ctrl+F only lists files with one extension.
+
+use std::cmp::Reverse;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::ignore::IgnoreList;
+use crate::keyboard;
+
+/// Recently opened files listed above the directory
+pub const RECENT_FILES: usize = 5;
+
+/// Bytes of a file read to judge which hand types it
+const HAND_SAMPLE_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntryKind {
+    Recent,
+    Directory,
+    File,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// Path to load, or to list for a directory
+    pub path: String,
+    /// What the listing shows: the whole path of a recent file, the name otherwise
+    pub name: String,
+    pub size: u64,
+    /// Share of the filtered hand, when the hand filter is on (see `passes_hand_filter`)
+    pub share: Option<Option<f64>>,
+}
+
+#[derive(Debug, Default)]
+pub struct Listing {
+    /// Directory the entries are in, "." for the working directory
+    pub dir: String,
+    /// Fuzzy search typed after the directory, empty when the path names an entry
+    pub query: String,
+    pub entries: Vec<Entry>,
+    /// Whether `dir` could be read
+    pub readable: bool,
+    pub ignored: usize,
+    pub other_hand: usize,
+    pub other_extension: usize,
+    /// Extensions of the files in `dir` and of recent files, for the extension filter
+    pub extensions: Vec<String>,
+}
+
+/// What the file picker has selected and filters on, beyond the typed path
+#[derive(Debug, Default)]
+pub struct FileBrowser {
+    /// Highlighted entry of the listing; None leaves Enter to the typed path
+    pub selected: Option<usize>,
+    /// Only files with this extension are listed
+    pub extension: Option<String>,
+}
+
+impl FileBrowser {
+    pub fn select_next(&mut self, len: usize) {
+        self.selected = match self.selected {
+            _ if len == 0 => None,
+            None => Some(0),
+            Some(index) => Some((index + 1).min(len - 1)),
+        };
+    }
+
+    /// Moving up from the first entry goes back to the typed path
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.and_then(|index| index.checked_sub(1));
+    }
+
+    /// Steps through `extensions` and back to listing every file
+    pub fn cycle_extension(&mut self, extensions: &[String]) {
+        let next = match &self.extension {
+            None => 0,
+            Some(current) => match extensions.iter().position(|ext| ext == current) {
+                Some(index) => index + 1,
+                None => 0,
+            },
+        };
+        self.extension = extensions.get(next).cloned();
+        self.selected = None;
+    }
+}
+
+/// How well `name` matches the fuzzy `query`: None unless every query character
+/// appears in order (ignoring case). Runs of characters and characters starting
+/// a word score higher.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut from = 0;
+    for wanted in query.chars().flat_map(char::to_lowercase) {
+        let found = from + name[from..].iter().position(|&ch| ch == wanted)?;
+        score += 1;
+        if found > 0 && found == from {
+            score += 4;
+        }
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        from = found + 1;
+    }
+    Some(score)
+}
+
+/// The directory a typed path lists: the path itself if it is a directory,
+/// otherwise the directory it is in
+pub fn directory_of(path: &str) -> String {
+    let path_obj = Path::new(path);
+
+    if path_obj.is_dir() {
+        return path.to_string();
+    }
+
+    if let Some(parent) = path_obj.parent() {
+        if parent.as_os_str().is_empty() {
+            return ".".to_string();
+        }
+        return parent.to_string_lossy().to_string();
+    }
+
+    ".".to_string()
+}
+
+/// A directory as typed into the prompt, ending in `/` so its contents are listed
+pub fn dir_input(dir: &Path) -> String {
+    let mut input = dir.to_string_lossy().to_string();
+    if !input.ends_with('/') {
+        input.push('/');
+    }
+    input
+}
+
+/// `hand`'s share of the start of a file; None when it can't be read or has no letters
+pub fn file_hand_share(path: &Path, hand: keyboard::Hand) -> Option<f64> {
+    let mut bytes = Vec::new();
+    fs::File::open(path)
+        .and_then(|file| file.take(HAND_SAMPLE_BYTES).read_to_end(&mut bytes))
+        .ok()?;
+    keyboard::hand_share(&String::from_utf8_lossy(&bytes), hand)
+}
+
+/// Whether a file is listed: always without a hand filter, otherwise when the hand's
+/// share (outer Some) is high enough
+pub fn passes_hand_filter(share: Option<Option<f64>>) -> bool {
+    match share {
+        None => true,
+        Some(share) => share.is_some_and(|share| share >= keyboard::ONE_HAND_SHARE),
+    }
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    Some(path.extension()?.to_string_lossy().to_lowercase())
+}
+
+/// Lists what the typed (and expanded) path `input` points into
+pub fn list(
+    input: &str,
+    recent: &[&str],
+    ignore_list: &IgnoreList,
+    hand: Option<keyboard::Hand>,
+    extension: Option<&str>,
+) -> Listing {
+    let path = Path::new(input);
+    // A path that names something is being browsed, not searched
+    let query = if input.ends_with('/') || path.exists() {
+        ""
+    } else {
+        input.rsplit_once('/').map_or(input, |(_, name)| name)
+    };
+    let mut listing = Listing {
+        dir: directory_of(input),
+        query: query.to_string(),
+        ..Default::default()
+    };
+    let mut extensions = Vec::new();
+    let mut scored = Vec::new();
+    let mut wanted = |path: &Path, listing: &mut Listing| {
+        let found = extension_of(path);
+        let keep = extension.is_none() || found.as_deref() == extension;
+        extensions.extend(found);
+        if !keep {
+            listing.other_extension += 1;
+        }
+        keep
+    };
+
+    for &file in recent {
+        let name = Path::new(file)
+            .file_name()
+            .map_or(file.into(), |name| name.to_string_lossy());
+        let Some(score) = fuzzy_score(query, &name) else {
+            continue;
+        };
+        if wanted(Path::new(file), &mut listing) {
+            let entry = Entry {
+                kind: EntryKind::Recent,
+                path: file.to_string(),
+                name: file.to_string(),
+                size: fs::metadata(file).map_or(0, |metadata| metadata.len()),
+                share: None,
+            };
+            scored.push((score, entry));
+        }
+    }
+
+    if let Ok(dir_entries) = fs::read_dir(&listing.dir) {
+        listing.readable = true;
+        let mut found = Vec::new();
+        for dir_entry in dir_entries.filter_map(|e| e.ok()) {
+            let entry_path = dir_entry.path();
+            let name = dir_entry.file_name().to_string_lossy().to_string();
+            let is_dir = entry_path.is_dir();
+            // Match on the entry name so browsing into an ignored directory still lists it
+            if ignore_list.is_ignored(Path::new(&name), is_dir) {
+                listing.ignored += 1;
+                continue;
+            }
+            let Some(score) = fuzzy_score(query, &name) else {
+                continue;
+            };
+            let shown_path = if listing.dir == "." {
+                name.clone()
+            } else {
+                entry_path.to_string_lossy().to_string()
+            };
+            if is_dir {
+                let entry = Entry {
+                    kind: EntryKind::Directory,
+                    path: shown_path,
+                    name,
+                    size: 0,
+                    share: None,
+                };
+                found.push((score, entry));
+            } else if entry_path.is_file() {
+                if !wanted(&entry_path, &mut listing) {
+                    continue;
+                }
+                let share = hand.map(|hand| file_hand_share(&entry_path, hand));
+                if !passes_hand_filter(share) {
+                    listing.other_hand += 1;
+                    continue;
+                }
+                let entry = Entry {
+                    kind: EntryKind::File,
+                    path: shown_path,
+                    name,
+                    size: dir_entry.metadata().map_or(0, |metadata| metadata.len()),
+                    share,
+                };
+                found.push((score, entry));
+            }
+        }
+        found.sort_by(|(_, a), (_, b)| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        scored.extend(found);
+    }
+
+    // Stable, so equal scores keep recent files, then directories, then files
+    if !query.is_empty() {
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+    }
+    listing.entries = scored.into_iter().map(|(_, entry)| entry).collect();
+    extensions.sort();
+    extensions.dedup();
+    listing.extensions = extensions;
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "main.rs"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "main.rs"), None);
+        assert_eq!(fuzzy_score("nm", "main.rs"), None);
+        assert!(fuzzy_score("TH", "typing_handler.rs").is_some());
+        // A word start beats letters scattered through a name
+        assert!(fuzzy_score("ui", "ui_blocks.rs") > fuzzy_score("ui", "build.rs"));
+        assert!(fuzzy_score("hand", "typing_handler.rs") > fuzzy_score("hand", "h_a_n_d.rs"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut browser = FileBrowser::default();
+        browser.select_next(0);
+        assert_eq!(browser.selected, None);
+        browser.select_next(2);
+        browser.select_next(2);
+        browser.select_next(2);
+        assert_eq!(browser.selected, Some(1));
+        browser.select_previous();
+        browser.select_previous();
+        assert_eq!(browser.selected, None);
+
+        let extensions = ["py".to_string(), "rs".to_string()];
+        browser.cycle_extension(&extensions);
+        assert_eq!(browser.extension.as_deref(), Some("py"));
+        browser.cycle_extension(&extensions);
+        browser.cycle_extension(&extensions);
+        assert_eq!(browser.extension, None);
+    }
+
+    #[test]
+    fn test_listing() {
+        let dir = std::env::temp_dir().join(format!("cargotap_browser_{}", std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("parser.py"), "pass").unwrap();
+        fs::write(dir.join("README.md"), "# hi").unwrap();
+        let ignore_list = IgnoreList::new(&["target/".to_string()]);
+        let input = dir_input(&dir);
+        let names = |listing: &Listing| -> Vec<String> {
+            listing.entries.iter().map(|e| e.name.clone()).collect()
+        };
+
+        let listing = list(&input, &["/x/recent.rs"], &ignore_list, None, None);
+        assert_eq!(listing.query, "");
+        assert_eq!(listing.ignored, 1);
+        assert_eq!(
+            names(&listing),
+            ["/x/recent.rs", "src", "README.md", "main.rs", "parser.py"]
+        );
+        assert_eq!(listing.extensions, ["md", "py", "rs"]);
+
+        let listing = list(&input, &["/x/recent.rs"], &ignore_list, None, Some("rs"));
+        assert_eq!(names(&listing), ["/x/recent.rs", "src", "main.rs"]);
+        assert_eq!(listing.other_extension, 2);
+
+        let searched = format!("{}pa", input);
+        let listing = list(&searched, &[], &ignore_list, None, None);
+        assert_eq!(listing.query, "pa");
+        assert_eq!(names(&listing), ["parser.py"]);
+
+        // An existing file lists its whole directory
+        let file = dir.join("main.rs").to_string_lossy().to_string();
+        assert_eq!(list(&file, &[], &ignore_list, None, None).entries.len(), 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! Function bodies in the code view
+//!
+//! The gutter marks the first line of each function body, and with folding on
+//! (`[text] fold_typed`, or Ctrl+Shift+F for the current file) bodies typed to the
+//! end collapse into one row, so the view keeps to the code still ahead. Functions
+//! are found by their keyword; a body ends where its braces balance, or, for
+//! languages without braces, where the indentation drops back to the header's.
+
+use crate::language::{Blocks, Language};
+
+/// Lines of a function, 0-based and inclusive: `start` is the header line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldRegion {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FoldRegion {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    /// Lines hidden when folded from `line` on, that one included
+    pub fn lines_from(&self, line: usize) -> usize {
+        self.end + 1 - line.max(self.start)
+    }
+}
+
+/// Words that start a function in the languages CargoTap highlights
+const FUNCTION_KEYWORDS: &[&str] = &["fn", "def", "func", "function", "fun"];
+
+/// Lines a signature may run over before its body opens (where clauses, long argument lists)
+const MAX_SIGNATURE_LINES: usize = 8;
+
+/// The functions in `lines` that span more than one line, ordered by their first
+/// line. Nested functions are listed too, after the one around them.
+pub fn function_bodies(lines: &[&str], language: &Language) -> Vec<FoldRegion> {
+    (0..lines.len())
+        .filter(|&start| is_function_header(lines[start], language))
+        .filter_map(|start| {
+            let indented = match language.blocks {
+                Blocks::Braces => false,
+                Blocks::Indentation => true,
+                Blocks::Guess => lines[start].trim_end().ends_with(':'),
+            };
+            let end = if indented {
+                indented_block_end(lines, start)
+            } else {
+                brace_block_end(lines, start, language)
+            }?;
+            (end > start).then_some(FoldRegion { start, end })
+        })
+        .collect()
+}
+
+/// True when a function keyword comes before the argument list, as in
+/// `pub async fn run(`, `def run(` or `export function run(`
+fn is_function_header(line: &str, language: &Language) -> bool {
+    let trimmed = line.trim_start();
+    if language
+        .line_comments
+        .iter()
+        .any(|comment| trimmed.starts_with(comment))
+        || (language.block_comments && (trimmed.starts_with("/*") || trimmed.starts_with('*')))
+    {
+        return false;
+    }
+    let Some((before_args, _)) = trimmed.split_once('(') else {
+        return false;
+    };
+    before_args
+        .split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .any(|word| FUNCTION_KEYWORDS.contains(&word))
+}
+
+/// Last line of the braced body opening at or after `start`. None for declarations
+/// without a body (`fn run();`) and bodies that never close.
+fn brace_block_end(lines: &[&str], start: usize, language: &Language) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut opened = false;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for ch in code_chars(line, language) {
+            match ch {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' if opened => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Some(index);
+                    }
+                }
+                ';' if !opened => return None,
+                _ => {}
+            }
+        }
+        if !opened && index >= start + MAX_SIGNATURE_LINES {
+            return None;
+        }
+    }
+    None
+}
+
+/// The characters of `line` outside string and character literals and comments
+fn code_chars(line: &str, language: &Language) -> Vec<char> {
+    let offsets: Vec<usize> = line.char_indices().map(|(offset, _)| offset).collect();
+    let chars: Vec<char> = line.chars().collect();
+    let mut code = Vec::with_capacity(chars.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let rest = &line[offsets[index]..];
+        if language
+            .code_comments()
+            .iter()
+            .any(|comment| rest.starts_with(comment))
+        {
+            break;
+        }
+        match chars[index] {
+            quote @ ('"' | '\'' | '`')
+                if quote == '"'
+                    || (quote == '\'' && !language.char_literals)
+                    || (quote == '`' && language.backtick_strings) =>
+            {
+                index += 1;
+                while index < chars.len() && chars[index] != quote {
+                    // Go's backtick strings are raw
+                    if chars[index] == '\\' && quote != '`' {
+                        index += 1;
+                    }
+                    index += 1;
+                }
+            }
+            // Character literals like '{' and '\''; a lone quote is a Rust lifetime
+            '\'' if chars.get(index + 2) == Some(&'\'') => index += 2,
+            '\'' if chars.get(index + 1) == Some(&'\\') && chars.get(index + 3) == Some(&'\'') => {
+                index += 3
+            }
+            ch => code.push(ch),
+        }
+        index += 1;
+    }
+    code
+}
+
+/// Last line indented deeper than the header at `start`, blank lines in between included
+fn indented_block_end(lines: &[&str], start: usize) -> Option<usize> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let header_indent = indent(lines[start]);
+    let mut end = None;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indent(line) <= header_indent {
+            break;
+        }
+        end = Some(index);
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language;
+
+    fn bodies(text: &str, language: &Language) -> Vec<(usize, usize)> {
+        let lines: Vec<&str> = text.lines().collect();
+        function_bodies(&lines, language)
+            .into_iter()
+            .map(|region| (region.start, region.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_function_bodies() {
+        let text = r#"trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub fn parse<T>(input: &str) -> T
+where
+    T: Default,
+{
+    let open = '{';
+    let text = "}";
+    helper(|x| { x }); // }
+    T::default()
+}
+
+fn one_liner() { }
+"#;
+        assert_eq!(bodies(text, &language::RUST), [(4, 12)]);
+        assert_eq!(bodies(text, &language::OTHER), [(4, 12)]);
+    }
+
+    #[test]
+    fn test_nested_and_indented_bodies() {
+        let text = "def outer(x):\n    def inner():\n        return x\n\n    return inner\n\nprint(outer(1))\n";
+        assert_eq!(bodies(text, &language::PYTHON), [(0, 4), (1, 2)]);
+        assert_eq!(bodies(text, &language::OTHER), [(0, 4), (1, 2)]);
+        assert!(bodies("# def old(x):\n#     pass\n", &language::PYTHON).is_empty());
+
+        let region = FoldRegion { start: 0, end: 4 };
+        assert!(region.contains(4) && !region.contains(5));
+        assert_eq!(region.lines_from(0), 5);
+        assert_eq!(region.lines_from(3), 2);
+    }
+
+    #[test]
+    fn test_go_function_bodies() {
+        let text = "// func old() {\nfunc main() {\n\tclose := '}'\n\tpath := `C:\\`\n\tfmt.Println(\"}\", close, path)\n}\n";
+        assert_eq!(bodies(text, &language::GO), [(1, 5)]);
+    }
+}
@@ -0,0 +1,331 @@
+//! Syntax highlighting engines
+//!
+//! Code is colored by a `SyntaxHighlighter`. The default engine is syntect, with
+//! the Sublime Text grammars it bundles: the language comes from the file's
+//! extension (or its name, for files like `Makefile`), and the scopes a grammar
+//! assigns are colored with the `syntax_*` entries of `[colors]`.
+//!
+//! The built-in keyword matcher knows the languages in `language` and treats other
+//! files like Rust. It colors files syntect has no grammar for, and every file with
+//! `engine = "keywords"` under `[syntax]`, which is also the engine the `[syntax]`
+//! word lists and `edition` apply to.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{
+    Color, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
+};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::config::{ColorConfig, Config, SyntaxConfig, SyntaxEngine};
+use crate::examples::colored_text_demo::ColoredTextDemo;
+use crate::keywords::KeywordSet;
+use crate::language::{self, Language};
+use crate::text::ColoredText;
+
+/// Colors code for display. Runs on the highlighter thread, so it only depends
+/// on itself and its argument.
+pub trait SyntaxHighlighter: Send + Sync + fmt::Debug {
+    fn highlight(&self, code: &str) -> ColoredText;
+}
+
+/// The keyword matcher
+#[derive(Debug)]
+pub struct KeywordHighlighter {
+    keywords: KeywordSet,
+    language: &'static Language,
+}
+
+impl KeywordHighlighter {
+    pub fn new(keywords: KeywordSet, language: &'static Language) -> Self {
+        Self { keywords, language }
+    }
+}
+
+impl SyntaxHighlighter for KeywordHighlighter {
+    fn highlight(&self, code: &str) -> ColoredText {
+        ColoredTextDemo::create_syntax_highlighted(code, &self.keywords, self.language)
+    }
+}
+
+/// The keyword matcher with the default Rust keywords, for texts nothing else was chosen for
+pub fn fallback() -> Arc<dyn SyntaxHighlighter> {
+    static FALLBACK: OnceLock<Arc<KeywordHighlighter>> = OnceLock::new();
+    FALLBACK
+        .get_or_init(|| {
+            Arc::new(KeywordHighlighter::new(
+                KeywordSet::default(),
+                &language::RUST,
+            ))
+        })
+        .clone()
+}
+
+/// A syntect grammar and the theme made from `[colors]`
+pub struct SyntectHighlighter {
+    syntaxes: Arc<SyntaxSet>,
+    syntax: SyntaxReference,
+    theme: Arc<Theme>,
+    default_color: [f32; 4],
+}
+
+impl fmt::Debug for SyntectHighlighter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyntectHighlighter")
+            .field("syntax", &self.syntax.name)
+            .finish()
+    }
+}
+
+impl SyntaxHighlighter for SyntectHighlighter {
+    fn highlight(&self, code: &str) -> ColoredText {
+        let mut lines = HighlightLines::new(&self.syntax, &self.theme);
+        let mut colored = ColoredText::new();
+        let mut done = 0;
+        for line in LinesWithEndings::from(code) {
+            match lines.highlight_line(line, &self.syntaxes) {
+                Ok(spans) => {
+                    for (style, text) in spans {
+                        colored.push_str(text, from_syntect(style.foreground));
+                    }
+                    done += line.len();
+                }
+                // A grammar that chokes on a line has lost its state, so the rest
+                // would be colored wrong anyway
+                Err(e) => {
+                    log::warn!("{} highlighting stopped: {}", self.syntax.name, e);
+                    colored.push_str(&code[done..], self.default_color);
+                    break;
+                }
+            }
+        }
+        colored
+    }
+}
+
+/// Scopes and the `[colors]` entry they are drawn in. A more specific selector
+/// wins over a shorter one, so operators aren't colored like keywords.
+fn theme_rules(colors: &ColorConfig) -> [(&'static str, [f32; 4]); 7] {
+    [
+        (
+            "comment, punctuation.definition.comment",
+            colors.syntax_comment,
+        ),
+        (
+            "string, constant.character, punctuation.definition.string",
+            colors.syntax_string,
+        ),
+        ("constant.numeric", colors.syntax_number),
+        (
+            "keyword, storage, constant.language, variable.language",
+            colors.syntax_keyword,
+        ),
+        ("keyword.operator", colors.text_default),
+        (
+            "entity.name.type, entity.name.class, entity.name.struct, entity.name.enum, \
+             entity.name.trait, entity.other.inherited-class, support.type, support.class",
+            colors.syntax_type,
+        ),
+        (
+            "entity.name.function, support.function, variable.function, support.macro",
+            colors.syntax_function,
+        ),
+    ]
+}
+
+fn theme(colors: &ColorConfig) -> Theme {
+    let scopes = theme_rules(colors)
+        .into_iter()
+        .map(|(selectors, color)| ThemeItem {
+            scope: ScopeSelectors::from_str(selectors).expect("bundled scope selectors are valid"),
+            style: StyleModifier {
+                foreground: Some(to_syntect(color)),
+                background: None,
+                font_style: None,
+            },
+        })
+        .collect();
+    Theme {
+        settings: ThemeSettings {
+            foreground: Some(to_syntect(colors.text_default)),
+            ..ThemeSettings::default()
+        },
+        scopes,
+        ..Theme::default()
+    }
+}
+
+fn to_syntect(color: [f32; 4]) -> Color {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color {
+        r: channel(color[0]),
+        g: channel(color[1]),
+        b: channel(color[2]),
+        a: channel(color[3]),
+    }
+}
+
+fn from_syntect(color: Color) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a].map(|channel| channel as f32 / 255.0)
+}
+
+/// Picks the highlighter for each file. Highlighters are kept per extension, so
+/// the same file gets the same one back and its colors aren't recomputed.
+pub struct Languages {
+    engine: SyntaxEngine,
+    /// Word lists and edition for the keyword matchers
+    syntax: SyntaxConfig,
+    /// Keyword matchers by language name, made when a file first needs one
+    keywords: HashMap<&'static str, Arc<dyn SyntaxHighlighter>>,
+    syntaxes: Arc<SyntaxSet>,
+    theme: Arc<Theme>,
+    /// The `[colors]` the theme was made from
+    theme_colors: [(&'static str, [f32; 4]); 7],
+    default_color: [f32; 4],
+    by_extension: HashMap<String, Arc<dyn SyntaxHighlighter>>,
+}
+
+impl Languages {
+    pub fn new(config: &Config) -> Self {
+        let syntaxes = match config.syntax.engine {
+            SyntaxEngine::Syntect => SyntaxSet::load_defaults_newlines(),
+            // Nothing is looked up, so the grammars aren't worth loading
+            SyntaxEngine::Keywords => SyntaxSet::new(),
+        };
+        Self {
+            engine: config.syntax.engine,
+            syntax: config.syntax.clone(),
+            keywords: HashMap::new(),
+            syntaxes: Arc::new(syntaxes),
+            theme: Arc::new(theme(&config.colors)),
+            theme_colors: theme_rules(&config.colors),
+            default_color: config.colors.text_default,
+            by_extension: HashMap::new(),
+        }
+    }
+
+    /// Remakes the theme if `[colors]` changed, e.g. in the settings screen
+    pub fn set_colors(&mut self, colors: &ColorConfig) {
+        if self.theme_colors == theme_rules(colors) && self.default_color == colors.text_default {
+            return;
+        }
+        self.theme = Arc::new(theme(colors));
+        self.theme_colors = theme_rules(colors);
+        self.default_color = colors.text_default;
+        self.by_extension.clear();
+    }
+
+    /// The keyword matcher for `language`
+    fn keywords(&mut self, language: &'static Language) -> Arc<dyn SyntaxHighlighter> {
+        let syntax = &self.syntax;
+        self.keywords
+            .entry(language.name)
+            .or_insert_with(|| {
+                Arc::new(KeywordHighlighter::new(
+                    KeywordSet::from_config(syntax, language),
+                    language,
+                ))
+            })
+            .clone()
+    }
+
+    /// The highlighter for the file at `path`
+    pub fn for_path(&mut self, path: &str) -> Arc<dyn SyntaxHighlighter> {
+        let language = language::for_path(path);
+        if self.engine == SyntaxEngine::Keywords {
+            return self.keywords(language);
+        }
+        let path = Path::new(path);
+        let key = path
+            .extension()
+            .or_else(|| path.file_name())
+            .map(|key| key.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if let Some(highlighter) = self.by_extension.get(&key) {
+            return highlighter.clone();
+        }
+
+        let highlighter: Arc<dyn SyntaxHighlighter> =
+            match self.syntaxes.find_syntax_by_extension(&key) {
+                Some(syntax) => Arc::new(SyntectHighlighter {
+                    syntaxes: self.syntaxes.clone(),
+                    syntax: syntax.clone(),
+                    theme: self.theme.clone(),
+                    default_color: self.default_color,
+                }),
+                None => self.keywords(language),
+            };
+        self.by_extension.insert(key, highlighter.clone());
+        highlighter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_of(colored: &ColoredText, line: usize, column: usize) -> [f32; 4] {
+        colored.lines[line].chars[column].color
+    }
+
+    fn close(a: [f32; 4], b: [f32; 4]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 0.01)
+    }
+
+    #[test]
+    fn test_languages_from_extension() {
+        let config = Config::default();
+        let colors = &config.colors;
+        let mut languages = Languages::new(&config);
+
+        let python = languages.for_path("tools/build.py");
+        assert!(format!("{:?}", python).contains("Python"));
+        let colored = python.highlight("def run():\n    # done\n    return 'ok'\n");
+        assert_eq!(colored.lines.len(), 4);
+        assert!(close(color_of(&colored, 0, 0), colors.syntax_keyword));
+        assert!(close(color_of(&colored, 0, 4), colors.syntax_function));
+        assert!(close(color_of(&colored, 1, 4), colors.syntax_comment));
+        assert!(close(color_of(&colored, 2, 11), colors.syntax_string));
+
+        // Same extension, same highlighter, so cached colors stay valid
+        assert!(Arc::ptr_eq(&python, &languages.for_path("other.py")));
+        // No grammar: the Rust keyword matcher
+        assert!(format!("{:?}", languages.for_path("notes.unknown")).contains("Keyword"));
+
+        let mut changed = colors.clone();
+        changed.syntax_keyword = [0.0, 0.0, 1.0, 1.0];
+        languages.set_colors(&changed);
+        let colored = languages.for_path("a.py").highlight("def f(): pass");
+        assert!(close(color_of(&colored, 0, 0), changed.syntax_keyword));
+    }
+
+    #[test]
+    fn test_keywords_engine() {
+        let mut config = Config::default();
+        config.syntax.engine = SyntaxEngine::Keywords;
+        let mut languages = Languages::new(&config);
+        let highlighter = languages.for_path("main.rs");
+        assert!(format!("{:?}", highlighter).contains("Keyword"));
+        let colored = highlighter.highlight("fn main() {}");
+        assert_eq!(
+            colored.lines[0].chars[0].color,
+            KeywordSet::default().color("fn")
+        );
+
+        // Each language gets its own words, and one matcher for all its files
+        let python = languages.for_path("main.py");
+        assert!(format!("{:?}", python).contains("Python"));
+        assert!(Arc::ptr_eq(&python, &languages.for_path("tools/run.py")));
+        let colored = python.highlight("def main(): pass");
+        assert_eq!(
+            colored.lines[0].chars[0].color,
+            KeywordSet::for_language(&language::PYTHON, 2024).color("def")
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Background syntax highlighting
+//!
+//! Coloring a large file takes long enough to drop frames, so it runs on a worker
+//! thread. Only the newest request matters: the worker skips requests that were
+//! superseded while it was busy, and the app keeps showing the previous frame until
+//! the colors for the current text arrive. If the thread can't be started, jobs are
+//! colored right away instead.
+
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::code_state;
+use crate::highlight::SyntaxHighlighter;
+use crate::text::ColoredText;
+
+/// Text to color, tagged with the `CodeState` generation it belongs to
+pub struct HighlightJob {
+    pub generation: u64,
+    pub code: String,
+    /// None when syntax highlighting is off
+    pub highlighter: Option<Arc<dyn SyntaxHighlighter>>,
+}
+
+/// Colors for the text of `generation`
+pub struct Highlighted {
+    pub generation: u64,
+    pub colored: ColoredText,
+}
+
+/// Where a request goes
+enum Worker {
+    Thread(Sender<HighlightJob>),
+    /// No thread: requests are colored on the spot and their results sent here
+    Inline(Sender<Highlighted>),
+}
+
+pub struct Highlighter {
+    worker: Worker,
+    results: Receiver<Highlighted>,
+    /// Generation of the newest job sent and not yet collected
+    in_flight: Option<u64>,
+}
+
+impl Highlighter {
+    /// Starts the worker thread; it exits when the Highlighter is dropped. Without
+    /// a thread, highlighting still works, only on the caller's thread.
+    pub fn spawn() -> Self {
+        let (jobs, job_receiver) = mpsc::channel();
+        let (result_sender, results) = mpsc::channel();
+        let inline_sender = result_sender.clone();
+        let worker = match thread::Builder::new()
+            .name("highlighter".to_string())
+            .spawn(move || run(job_receiver, result_sender))
+        {
+            Ok(_) => Worker::Thread(jobs),
+            Err(e) => {
+                log::warn!("Couldn't start the highlighter thread, highlighting inline: {e}");
+                Worker::Inline(inline_sender)
+            }
+        };
+
+        Self {
+            worker,
+            results,
+            in_flight: None,
+        }
+    }
+
+    /// True if colors for `generation` have been requested and not collected yet
+    pub fn is_pending(&self, generation: u64) -> bool {
+        self.in_flight == Some(generation)
+    }
+
+    /// Queues `job`, superseding any job still waiting
+    pub fn request(&mut self, job: HighlightJob) {
+        self.in_flight = Some(job.generation);
+        let sent = match &self.worker {
+            Worker::Thread(jobs) => jobs.send(job).is_ok(),
+            Worker::Inline(results) => results.send(color(job)).is_ok(),
+        };
+        if !sent {
+            log::error!("Highlighter thread is gone");
+        }
+    }
+
+    /// The newest finished result, without blocking
+    pub fn try_take(&mut self) -> Option<Highlighted> {
+        let newest = self.results.try_iter().last()?;
+        self.collected(&newest);
+        Some(newest)
+    }
+
+    /// Blocks until the newest requested job is done
+    pub fn wait(&mut self) -> Option<Highlighted> {
+        let generation = self.in_flight?;
+        while let Ok(result) = self.results.recv() {
+            if result.generation == generation {
+                self.collected(&result);
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn collected(&mut self, result: &Highlighted) {
+        if self.in_flight == Some(result.generation) {
+            self.in_flight = None;
+        }
+    }
+}
+
+fn run(jobs: Receiver<HighlightJob>, results: Sender<Highlighted>) {
+    while let Ok(mut job) = jobs.recv() {
+        // Latest wins: anything queued behind this job makes it obsolete
+        while let Ok(newer) = jobs.try_recv() {
+            job = newer;
+        }
+
+        if results.send(color(job)).is_err() {
+            break;
+        }
+    }
+}
+
+fn color(job: HighlightJob) -> Highlighted {
+    Highlighted {
+        generation: job.generation,
+        colored: code_state::highlight(&job.code, job.highlighter.as_deref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(generation: u64, code: &str) -> HighlightJob {
+        HighlightJob {
+            generation,
+            code: code.to_string(),
+            highlighter: Some(crate::highlight::fallback()),
+        }
+    }
+
+    #[test]
+    fn test_wait_returns_newest_request() {
+        let mut highlighter = Highlighter::spawn();
+        assert!(highlighter.wait().is_none());
+
+        highlighter.request(job(1, "fn a() {}"));
+        highlighter.request(job(2, "fn b() {}\nlet c = 1;"));
+        assert!(highlighter.is_pending(2));
+        assert!(!highlighter.is_pending(1));
+
+        let result = highlighter.wait().unwrap();
+        assert_eq!(result.generation, 2);
+        assert_eq!(result.colored.lines.len(), 2);
+        assert!(!highlighter.is_pending(2));
+    }
+
+    #[test]
+    fn test_inline_highlighting_without_a_thread() {
+        let (sender, results) = mpsc::channel();
+        let mut highlighter = Highlighter {
+            worker: Worker::Inline(sender),
+            results,
+            in_flight: None,
+        };
+        highlighter.request(job(1, "fn a() {}"));
+        assert!(highlighter.is_pending(1));
+        assert_eq!(
+            highlighter.try_take().map(|result| result.generation),
+            Some(1)
+        );
+        assert!(!highlighter.is_pending(1));
+    }
+
+    #[test]
+    fn test_try_take_keeps_only_the_newest_result() {
+        let mut highlighter = Highlighter::spawn();
+        highlighter.request(job(1, "a"));
+        highlighter.wait().unwrap();
+        highlighter.request(job(2, "b"));
+        highlighter.wait().unwrap();
+        assert!(highlighter.try_take().is_none());
+
+        highlighter.request(job(3, "c"));
+        while highlighter.is_pending(3) {
+            if let Some(result) = highlighter.try_take() {
+                assert_eq!(result.generation, 3);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Home row reminder between sessions
+//!
+//! Before a session starts the hands are shown on the home row of the configured
+//! layout. F and J (the keys with the bumps) have to be pressed before a short
+//! countdown, and the session's timer starts when the countdown is over.
+
+use std::time::{Duration, Instant};
+
+use crate::symbol_hints::KeyboardLayout;
+
+/// Seconds between pressing both anchor keys and the start of the session
+pub const COUNTDOWN_SECS: u64 = 3;
+
+/// Fingers of the left hand, left to right, as they rest on the home row
+/// (short enough to sit under a key on the reminder)
+pub const FINGERS: [&str; 4] = ["pinky", "ring", "mid", "index"];
+
+/// Home row keys under the left and right hand's fingers, left to right
+pub fn home_keys(layout: KeyboardLayout) -> ([char; 4], [char; 4]) {
+    let left = ['A', 'S', 'D', 'F'];
+    match layout {
+        KeyboardLayout::Us | KeyboardLayout::Uk => (left, ['J', 'K', 'L', ';']),
+        KeyboardLayout::De => (left, ['J', 'K', 'L', 'Ö']),
+    }
+}
+
+/// Progress through the reminder: which anchor keys were pressed, then the countdown
+#[derive(Debug, Clone, Default)]
+pub struct HomeRowCheck {
+    f_pressed: bool,
+    j_pressed: bool,
+    countdown_ends_at: Option<Instant>,
+}
+
+impl HomeRowCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a typed key; the countdown starts once both F and J were pressed
+    pub fn press(&mut self, key: char) {
+        if self.countdown_ends_at.is_some() {
+            return;
+        }
+        match key.to_ascii_lowercase() {
+            'f' => self.f_pressed = true,
+            'j' => self.j_pressed = true,
+            _ => return,
+        }
+        if self.f_pressed && self.j_pressed {
+            self.countdown_ends_at = Some(Instant::now() + Duration::from_secs(COUNTDOWN_SECS));
+        }
+    }
+
+    pub fn f_pressed(&self) -> bool {
+        self.f_pressed
+    }
+
+    pub fn j_pressed(&self) -> bool {
+        self.j_pressed
+    }
+
+    /// Time left before the session starts, or None while waiting for F and J
+    pub fn countdown_remaining(&self) -> Option<Duration> {
+        self.countdown_ends_at
+            .map(|ends_at| ends_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// True once the countdown is over
+    pub fn poll(&self) -> bool {
+        self.countdown_remaining()
+            .is_some_and(|left| left.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_countdown_needs_both_anchor_keys() {
+        let mut check = HomeRowCheck::new();
+        check.press('f');
+        check.press('k');
+        check.press('f');
+        assert!(check.f_pressed() && !check.j_pressed());
+        assert!(check.countdown_remaining().is_none());
+        assert!(!check.poll());
+
+        check.press('J');
+        let left = check.countdown_remaining().unwrap();
+        assert!(left <= Duration::from_secs(COUNTDOWN_SECS) && !left.is_zero());
+        assert!(!check.poll());
+    }
+
+    #[test]
+    fn test_home_keys_per_layout() {
+        assert_eq!(home_keys(KeyboardLayout::Us).1[3], ';');
+        assert_eq!(home_keys(KeyboardLayout::De).1[3], 'Ö');
+        assert_eq!(home_keys(KeyboardLayout::Uk).0[3], 'F');
+    }
+}
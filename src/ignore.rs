@@ -0,0 +1,111 @@
+//! Ignore list for generated and vendored files
+//!
+//! Patterns use a small gitignore-like syntax:
+//! - `target/` matches a directory with that name anywhere in a path
+//! - `*.lock` matches file names, `*` standing for any run of characters
+//! - anything else must equal the file or directory name
+
+use std::path::Path;
+
+pub fn default_patterns() -> Vec<String> {
+    [
+        "target/",
+        "vendor/",
+        "node_modules/",
+        ".git/",
+        "*.lock",
+        "*.min.js",
+        "*.min.css",
+    ]
+    .iter()
+    .map(|pattern| pattern.to_string())
+    .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    dirs: Vec<String>,
+    names: Vec<String>,
+}
+
+impl IgnoreList {
+    pub fn new(patterns: &[String]) -> Self {
+        let mut list = Self::default();
+        for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match pattern.strip_suffix('/') {
+                Some(dir) => list.dirs.push(dir.to_string()),
+                None => list.names.push(pattern.to_string()),
+            }
+        }
+        list
+    }
+
+    /// True if `path` is ignored, either itself or through one of its parent directories
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let components: Vec<String> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some((name, parents)) = components.split_last() else {
+            return false;
+        };
+
+        let ignored_dir =
+            |component: &String| self.dirs.iter().any(|dir| glob_match(dir, component));
+        if parents.iter().any(ignored_dir) || (is_dir && ignored_dir(name)) {
+            return true;
+        }
+        self.names.iter().any(|pattern| glob_match(pattern, name))
+    }
+}
+
+/// Matches `name` against a pattern where `*` stands for any run of characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard at all
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.lock", "Cargo.lock"));
+        assert!(!glob_match("*.lock", "lock.rs"));
+        assert!(glob_match("*.min.*", "app.min.js"));
+        assert!(glob_match("Makefile", "Makefile"));
+        assert!(!glob_match("Makefile", "Makefile.am"));
+        assert!(glob_match("*", "anything"));
+    }
+
+    #[test]
+    fn test_default_patterns() {
+        let list = IgnoreList::new(&default_patterns());
+        assert!(list.is_ignored(Path::new("project/target"), true));
+        assert!(list.is_ignored(Path::new("project/target/debug/build.rs"), false));
+        assert!(list.is_ignored(Path::new("vendor/serde/src/lib.rs"), false));
+        assert!(list.is_ignored(Path::new("Cargo.lock"), false));
+        assert!(list.is_ignored(Path::new("web/app.min.js"), false));
+
+        assert!(!list.is_ignored(Path::new("src/main.rs"), false));
+        // A file that merely shares a name with an ignored directory is kept
+        assert!(!list.is_ignored(Path::new("src/target"), false));
+    }
+}
@@ -14,6 +14,51 @@ pub enum InputAction {
     SkipCharacter,
     ShowStatistics,
     ChangeFile,
+    OpenSettings,
+    ToggleQuoteMode,
+    OpenDrills,
+    OpenDogfood,
+    /// Ask for a file from a GitHub repository
+    OpenGitHub,
+    SelectLineRange,
+    /// Scroll the view to a line typed into a prompt
+    GoToLine,
+    EditNote,
+    /// List only files with the next extension in the file picker
+    CycleExtensionFilter,
+    /// Change the line height multiplier by this much
+    AdjustLineSpacing(f32),
+    /// Change the extra space after each character by this many pixels
+    AdjustCharSpacing(f32),
+    /// Change the current file's zoom by this factor
+    AdjustZoom(f32),
+    /// Change the window background's opacity by this much
+    AdjustOpacity(f32),
+    /// Toggle line wrapping for the current file
+    ToggleWrap,
+    /// Toggle the line-number column for the current file
+    ToggleLineNumbers,
+    /// Toggle folding typed function bodies for the current file
+    ToggleFolding,
+    /// Toggle the marks for spaces, tabs and line breaks for the current file
+    ToggleWhitespace,
+    /// Drop the current file's view overrides and use the config defaults again
+    ResetView,
+    /// Turn the low-vision UI scale on or off
+    ToggleLowVision,
+    OpenSessionSetup,
+    /// Type the other file of a split
+    SwitchPane,
+    /// Write the app state to state.json
+    DumpState,
+    /// Pause or resume the running session
+    TogglePause,
+    /// Switch the window in and out of fullscreen
+    ToggleFullscreen,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
     Quit,
     Other,
 }
@@ -57,12 +102,19 @@ impl InputHandler {
                 }
 
                 // Check for Command+P to change file
-                if key == KeyCode::KeyP && self.modifiers.super_key() {
+                if key == KeyCode::KeyP && self.modifiers.super_key() && !self.modifiers.shift_key()
+                {
                     log::info!("Command+P detected - changing file!");
                     self.last_action = Some(InputAction::ChangeFile);
                     return;
                 }
 
+                // F11, or Command+Enter as on macOS, toggles fullscreen
+                if key == KeyCode::F11 || (key == KeyCode::Enter && self.modifiers.super_key()) {
+                    self.last_action = Some(InputAction::ToggleFullscreen);
+                    return;
+                }
+
                 // Also check for Escape as an alternative quit method OR to close statistics
                 if key == KeyCode::Escape {
                     // First check if we're showing stats - if so, just close them
@@ -98,6 +150,103 @@ impl InputHandler {
                     return;
                 }
 
+                // Check for Command+U (or Ctrl+U) to enter or leave quote mode
+                if key == KeyCode::KeyU && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::ToggleQuoteMode);
+                    return;
+                }
+
+                // Check for Command+D (or Ctrl+D) to open the drills menu or leave a drill
+                if key == KeyCode::KeyD && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::OpenDrills);
+                    return;
+                }
+
+                // Check for Command+O (or Ctrl+O) to pick a module of CargoTap's own source
+                if key == KeyCode::KeyO && is_cmd_or_ctrl && !self.modifiers.shift_key() {
+                    self.last_action = Some(InputAction::OpenDogfood);
+                    return;
+                }
+
+                // Check for Command+L (or Ctrl+L) to practice a range of lines
+                if key == KeyCode::KeyL && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::SelectLineRange);
+                    return;
+                }
+
+                // Check for Command+G (or Ctrl+G) to go to a line
+                if key == KeyCode::KeyG && is_cmd_or_ctrl && !self.modifiers.shift_key() {
+                    self.last_action = Some(InputAction::GoToLine);
+                    return;
+                }
+
+                // Check for Command+E (or Ctrl+E) to open session setup
+                if key == KeyCode::KeyE && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::OpenSessionSetup);
+                    return;
+                }
+
+                // Check for Command+N (or Ctrl+N) to edit the note attached to a file
+                if key == KeyCode::KeyN && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::EditNote);
+                    return;
+                }
+
+                // Check for Command+F (or Ctrl+F) to filter the file picker by extension
+                if key == KeyCode::KeyF && is_cmd_or_ctrl && !self.modifiers.shift_key() {
+                    self.last_action = Some(InputAction::CycleExtensionFilter);
+                    return;
+                }
+
+                // Check for Command+Shift+Arrows (or Ctrl+Shift+Arrows) to adjust text spacing,
+                // and Page Up/Down with the same modifiers for the window opacity
+                if is_cmd_or_ctrl && self.modifiers.shift_key() {
+                    let action = match key {
+                        KeyCode::ArrowUp => Some(InputAction::AdjustLineSpacing(0.1)),
+                        KeyCode::ArrowDown => Some(InputAction::AdjustLineSpacing(-0.1)),
+                        KeyCode::ArrowRight => Some(InputAction::AdjustCharSpacing(1.0)),
+                        KeyCode::ArrowLeft => Some(InputAction::AdjustCharSpacing(-1.0)),
+                        KeyCode::KeyR => Some(InputAction::ToggleWrap),
+                        KeyCode::KeyG => Some(InputAction::ToggleLineNumbers),
+                        KeyCode::KeyF => Some(InputAction::ToggleFolding),
+                        KeyCode::KeyH => Some(InputAction::ToggleWhitespace),
+                        KeyCode::KeyB => Some(InputAction::ToggleLowVision),
+                        KeyCode::KeyX => Some(InputAction::SwitchPane),
+                        KeyCode::KeyI => Some(InputAction::DumpState),
+                        KeyCode::KeyO => Some(InputAction::OpenGitHub),
+                        KeyCode::KeyP => Some(InputAction::TogglePause),
+                        KeyCode::PageUp => Some(InputAction::AdjustOpacity(0.1)),
+                        KeyCode::PageDown => Some(InputAction::AdjustOpacity(-0.1)),
+                        _ => None,
+                    };
+                    if action.is_some() {
+                        self.last_action = action;
+                        return;
+                    }
+                }
+
+                // Check for Command+= / Command+- / Command+0 (or Ctrl) to zoom the current file
+                if is_cmd_or_ctrl {
+                    let action = match key {
+                        KeyCode::Equal | KeyCode::NumpadAdd => Some(InputAction::AdjustZoom(0.1)),
+                        KeyCode::Minus | KeyCode::NumpadSubtract => {
+                            Some(InputAction::AdjustZoom(-0.1))
+                        }
+                        KeyCode::Digit0 | KeyCode::Numpad0 => Some(InputAction::ResetView),
+                        _ => None,
+                    };
+                    if action.is_some() {
+                        self.last_action = action;
+                        return;
+                    }
+                }
+
+                // Check for Command+, (or Ctrl+,) to open the settings screen
+                if key == KeyCode::Comma && is_cmd_or_ctrl {
+                    self.last_action = Some(InputAction::OpenSettings);
+                    return;
+                }
+
                 // Обработка специальных клавиш
                 match key {
                     KeyCode::Backspace => {
@@ -111,6 +260,18 @@ impl InputHandler {
                     KeyCode::Tab => {
                         self.last_action = Some(InputAction::Tab);
                     }
+                    KeyCode::ArrowUp => {
+                        self.last_action = Some(InputAction::ArrowUp);
+                    }
+                    KeyCode::ArrowDown => {
+                        self.last_action = Some(InputAction::ArrowDown);
+                    }
+                    KeyCode::ArrowLeft => {
+                        self.last_action = Some(InputAction::ArrowLeft);
+                    }
+                    KeyCode::ArrowRight => {
+                        self.last_action = Some(InputAction::ArrowRight);
+                    }
                     _ => {
                         if let Some(text) = &input.text {
                             if let Some(ch) = text.chars().next() {
@@ -124,6 +285,11 @@ impl InputHandler {
         }
     }
 
+    /// Queues an action that did not come from a winit key event (e.g. from an embedding host)
+    pub fn inject(&mut self, action: InputAction) {
+        self.last_action = Some(action);
+    }
+
     pub fn get_last_action(&self) -> Option<&InputAction> {
         self.last_action.as_ref()
     }
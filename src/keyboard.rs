@@ -0,0 +1,374 @@
+//! US keyboard layout geometry
+//!
+//! Keys are measured in text cells so screens can draw a keyboard out of
+//! characters with colored backgrounds. The heatmap and key speed views of the
+//! statistics screen map per-character stats onto these keys, and one-hand drills and file
+//! filters ask which hand types a character. Key transition statistics ask
+//! which finger does, to find same-finger bigrams and row jumps.
+
+use std::collections::BTreeMap;
+
+use crate::session_state::{KeyStat, SequenceStat};
+
+/// Width of a regular key in text cells, not counting the gap after it
+pub const KEY_WIDTH: usize = 3;
+
+/// (indent in cells, unshifted characters, shifted characters) for each character row
+const CHAR_ROWS: [(usize, &str, &str); 4] = [
+    (0, "`1234567890-=", "~!@#$%^&*()_+"),
+    (2, "qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    (3, "asdfghjkl;'", "ASDFGHJKL:\""),
+    (5, "zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+/// Keys of each character row typed with the left hand in touch typing (`12345`, `qwert`, ...)
+const LEFT_HAND_KEYS: [usize; 4] = [6, 5, 5, 5];
+
+/// Share of a text's one-hand characters that makes it "predominantly" one hand
+pub const ONE_HAND_SHARE: f64 = 0.65;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+impl Hand {
+    pub fn name(self) -> &'static str {
+        match self {
+            Hand::Left => "left",
+            Hand::Right => "right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finger {
+    Pinky,
+    Ring,
+    Middle,
+    Index,
+}
+
+/// Where the key for a character is and which finger presses it in touch typing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPlace {
+    pub hand: Hand,
+    pub finger: Finger,
+    /// Character row, 0 for the number row
+    pub row: usize,
+    pub column: usize,
+}
+
+/// The key for `ch`; None for the space bar (either thumb) and characters that
+/// aren't on the keyboard. Shift is left out, since it is pressed with the other hand.
+pub fn place_for(ch: char) -> Option<KeyPlace> {
+    if ch == '\n' {
+        // Enter is at the end of the home row
+        return Some(KeyPlace {
+            hand: Hand::Right,
+            finger: Finger::Pinky,
+            row: 2,
+            column: CHAR_ROWS[2].1.len(),
+        });
+    }
+    CHAR_ROWS.iter().zip(LEFT_HAND_KEYS).enumerate().find_map(
+        |(row, ((_, unshifted, shifted), left_keys))| {
+            let column = unshifted
+                .chars()
+                .position(|key| key == ch)
+                .or_else(|| shifted.chars().position(|key| key == ch))?;
+            // Each index finger takes the two columns next to the middle of the
+            // keyboard, and the pinkies everything beyond the ring fingers
+            let (hand, from_middle) = if column < left_keys {
+                (Hand::Left, left_keys - 1 - column)
+            } else {
+                (Hand::Right, column - left_keys)
+            };
+            let finger = match from_middle {
+                0 | 1 => Finger::Index,
+                2 => Finger::Middle,
+                3 => Finger::Ring,
+                _ => Finger::Pinky,
+            };
+            Some(KeyPlace {
+                hand,
+                finger,
+                row,
+                column,
+            })
+        },
+    )
+}
+
+/// The hand that presses the key for `ch` in touch typing (see `place_for`)
+pub fn hand_for(ch: char) -> Option<Hand> {
+    place_for(ch).map(|place| place.hand)
+}
+
+/// Two-key sequences that are slow to type because of how far a finger travels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Two different keys pressed by the same finger, like `ed` or `ju`
+    SameFinger,
+    /// Other fingers of one hand jumping over the home row, like `ex` or `cr`
+    RowJump,
+}
+
+impl Transition {
+    pub fn label(self) -> &'static str {
+        match self {
+            Transition::SameFinger => "same finger",
+            Transition::RowJump => "row jump",
+        }
+    }
+}
+
+/// What kind of transition typing `to` right after `from` is, if any
+pub fn transition(from: char, to: char) -> Option<Transition> {
+    let (from, to) = (place_for(from)?, place_for(to)?);
+    if from.hand != to.hand || (from.row, from.column) == (to.row, to.column) {
+        return None;
+    }
+    if from.finger == to.finger {
+        Some(Transition::SameFinger)
+    } else if from.row.abs_diff(to.row) >= 2 {
+        Some(Transition::RowJump)
+    } else {
+        None
+    }
+}
+
+/// Share of the characters in `text` that `hand` types, among those typed by one hand;
+/// None when there are none (e.g. only spaces)
+pub fn hand_share(text: &str, hand: Hand) -> Option<f64> {
+    let (mut total, mut matching) = (0usize, 0usize);
+    for attributed in text.chars().filter_map(hand_for) {
+        total += 1;
+        if attributed == hand {
+            matching += 1;
+        }
+    }
+    (total > 0).then(|| matching as f64 / total as f64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    pub label: String,
+    /// Characters this key produces, with and without shift
+    pub chars: Vec<char>,
+    /// Width in text cells
+    pub width: usize,
+}
+
+impl Key {
+    fn new(label: &str, chars: &[char], width: usize) -> Self {
+        Self {
+            label: label.to_string(),
+            chars: chars.to_vec(),
+            width,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRow {
+    /// Cells of empty space before the first key
+    pub indent: usize,
+    pub keys: Vec<Key>,
+}
+
+/// Builds the rows of a US ANSI keyboard, top to bottom
+pub fn us_layout() -> Vec<KeyRow> {
+    let mut rows: Vec<KeyRow> = CHAR_ROWS
+        .iter()
+        .map(|(indent, unshifted, shifted)| KeyRow {
+            indent: *indent,
+            keys: unshifted
+                .chars()
+                .zip(shifted.chars())
+                .map(|(base, shift)| {
+                    let label = base.to_ascii_uppercase().to_string();
+                    Key::new(&label, &[base, shift], KEY_WIDTH)
+                })
+                .collect(),
+        })
+        .collect();
+
+    rows[2].keys.push(Key::new("Enter", &['\n'], 7));
+    rows.push(KeyRow {
+        indent: 14,
+        keys: vec![Key::new("Space", &[' '], 23)],
+    });
+    rows
+}
+
+/// How a character is named in statistics lists
+pub fn key_name(ch: char) -> String {
+    match ch {
+        '\n' => "Enter".to_string(),
+        ' ' => "Space".to_string(),
+        other => format!("'{}'", other),
+    }
+}
+
+/// Adds up the stats of every character each key produces with `add`, laid out like `layout`
+pub fn key_totals<S: Copy + Default>(
+    layout: &[KeyRow],
+    stats: &BTreeMap<char, S>,
+    add: impl Fn(&mut S, S),
+) -> Vec<Vec<S>> {
+    layout
+        .iter()
+        .map(|row| {
+            row.keys
+                .iter()
+                .map(|key| {
+                    let mut total = S::default();
+                    for ch in &key.chars {
+                        if let Some(stat) = stats.get(ch) {
+                            add(&mut total, *stat);
+                        }
+                    }
+                    total
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Heatmap background for a key: brighter when used more, redder when missed more
+pub fn heatmap_color(stat: KeyStat, max_attempts: usize) -> [f32; 4] {
+    let attempts = stat.hits + stat.misses;
+    if attempts == 0 || max_attempts == 0 {
+        return [0.15, 0.15, 0.18, 1.0];
+    }
+
+    let usage = (attempts as f32 / max_attempts as f32).sqrt();
+    // A 20% miss rate is already fully red
+    let errors = (stat.error_rate() as f32 * 5.0).min(1.0);
+    let brightness = 0.25 + 0.55 * usage;
+
+    [
+        brightness * (0.2 + 0.8 * errors),
+        brightness * (0.8 - 0.6 * errors),
+        brightness * (0.5 - 0.4 * errors),
+        1.0,
+    ]
+}
+
+/// Key speed background for a key: green when reached quickly, red when it is
+/// as slow as the `slowest` key (mean seconds)
+pub fn latency_color(stat: SequenceStat, slowest: f64) -> [f32; 4] {
+    if stat.count == 0 || slowest <= 0.0 {
+        return [0.15, 0.15, 0.18, 1.0];
+    }
+
+    let slowness = (stat.mean_secs() / slowest).min(1.0) as f32;
+    [
+        0.15 + 0.6 * slowness,
+        0.6 - 0.45 * slowness,
+        0.3 - 0.15 * slowness,
+        1.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_covers_typeable_ascii() {
+        let layout = us_layout();
+        for ch in (' '..='~').chain(['\n']) {
+            assert!(
+                layout
+                    .iter()
+                    .flat_map(|row| &row.keys)
+                    .any(|key| key.chars.contains(&ch)),
+                "no key for {:?}",
+                ch
+            );
+        }
+    }
+
+    #[test]
+    fn test_hand_attribution() {
+        assert_eq!(hand_for('t'), Some(Hand::Left));
+        assert_eq!(hand_for('%'), Some(Hand::Left));
+        assert_eq!(hand_for('y'), Some(Hand::Right));
+        assert_eq!(hand_for('^'), Some(Hand::Right));
+        assert_eq!(hand_for('B'), Some(Hand::Left));
+        assert_eq!(hand_for(' '), None);
+        assert_eq!(hand_for('é'), None);
+
+        assert_eq!(hand_share("street", Hand::Left), Some(1.0));
+        assert_eq!(hand_share("as ok", Hand::Right), Some(0.5));
+        assert_eq!(hand_share("   ", Hand::Left), None);
+    }
+
+    #[test]
+    fn test_fingers_and_transitions() {
+        let finger = |ch| place_for(ch).map(|place| (place.hand, place.finger));
+        assert_eq!(finger('5'), Some((Hand::Left, Finger::Index)));
+        assert_eq!(finger('`'), Some((Hand::Left, Finger::Pinky)));
+        assert_eq!(finger('i'), Some((Hand::Right, Finger::Middle)));
+        assert_eq!(finger('.'), Some((Hand::Right, Finger::Ring)));
+        assert_eq!(finger('\''), Some((Hand::Right, Finger::Pinky)));
+        assert_eq!(finger('\n'), Some((Hand::Right, Finger::Pinky)));
+
+        assert_eq!(transition('e', 'd'), Some(Transition::SameFinger));
+        assert_eq!(transition('f', 'g'), Some(Transition::SameFinger));
+        assert_eq!(transition('E', 'c'), Some(Transition::SameFinger));
+        assert_eq!(transition('c', 'r'), Some(Transition::RowJump));
+        assert_eq!(transition('x', '3'), Some(Transition::RowJump));
+        // Repeated keys, neighbouring rows and the other hand are easy
+        assert_eq!(transition('e', 'E'), None);
+        assert_eq!(transition('a', 'w'), None);
+        assert_eq!(transition('e', 'n'), None);
+        assert_eq!(transition('e', ' '), None);
+    }
+
+    #[test]
+    fn test_totals_merge_shifted_characters() {
+        let layout = us_layout();
+        let mut stats = BTreeMap::new();
+        stats.insert('[', KeyStat { hits: 3, misses: 0 });
+        stats.insert('{', KeyStat { hits: 2, misses: 1 });
+
+        let totals = key_totals(&layout, &stats, KeyStat::add);
+        let index = layout[1].keys.iter().position(|k| k.label == "[").unwrap();
+        assert_eq!(totals[1][index], KeyStat { hits: 5, misses: 1 });
+    }
+
+    #[test]
+    fn test_missed_keys_are_redder() {
+        let clean = heatmap_color(
+            KeyStat {
+                hits: 10,
+                misses: 0,
+            },
+            10,
+        );
+        let missed = heatmap_color(KeyStat { hits: 8, misses: 2 }, 10);
+        assert!(missed[0] > clean[0]);
+        assert!(missed[1] < clean[1]);
+    }
+
+    #[test]
+    fn test_slow_keys_are_redder() {
+        let at = |total_secs| SequenceStat {
+            count: 2,
+            misses: 0,
+            total_secs,
+        };
+        let quick = latency_color(at(0.2), 0.5);
+        let slow = latency_color(at(1.0), 0.5);
+        assert!(slow[0] > quick[0]);
+        assert!(slow[1] < quick[1]);
+        assert_eq!(
+            latency_color(SequenceStat::default(), 0.5),
+            [0.15, 0.15, 0.18, 1.0]
+        );
+    }
+}
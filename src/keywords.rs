@@ -0,0 +1,227 @@
+//! Keyword sets for the syntax highlighter
+//!
+//! Each language's words are a TOML resource embedded at build time, with
+//! extra tables for words that only became keywords in a later edition.
+//! Users add their own words through the `[syntax]` config section, e.g. to
+//! color `tokio` or `serde` like types.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+
+use crate::config::SyntaxConfig;
+use crate::language::{self, Language};
+
+/// Color of identifiers that aren't in any set
+const IDENTIFIER_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    Control,
+    Declaration,
+    Module,
+    Type,
+    Function,
+    Literal,
+}
+
+impl WordKind {
+    pub fn color(self) -> [f32; 4] {
+        match self {
+            WordKind::Control => [0.4, 0.6, 1.0, 1.0],     // Blue
+            WordKind::Declaration => [0.8, 0.4, 1.0, 1.0], // Purple
+            WordKind::Module => [1.0, 0.4, 0.8, 1.0],      // Magenta
+            WordKind::Type => [0.4, 1.0, 0.4, 1.0],        // Green
+            WordKind::Function => [0.2, 1.0, 1.0, 1.0],    // Cyan
+            WordKind::Literal => [1.0, 0.7, 0.2, 1.0],     // Orange
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct WordLists {
+    control: Vec<String>,
+    declaration: Vec<String>,
+    module: Vec<String>,
+    types: Vec<String>,
+    functions: Vec<String>,
+    literals: Vec<String>,
+}
+
+impl WordLists {
+    fn kinds(&self) -> [(WordKind, &[String]); 6] {
+        [
+            (WordKind::Control, &self.control),
+            (WordKind::Declaration, &self.declaration),
+            (WordKind::Module, &self.module),
+            (WordKind::Type, &self.types),
+            (WordKind::Function, &self.functions),
+            (WordKind::Literal, &self.literals),
+        ]
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageWords {
+    #[serde(flatten)]
+    words: WordLists,
+    #[serde(default)]
+    editions: BTreeMap<String, WordLists>,
+}
+
+/// The bundled lists of every language by name, parsed once. A list that doesn't
+/// parse is logged and leaves its language with no keywords, rather than taking
+/// the app down.
+static BUNDLED_WORDS: LazyLock<HashMap<&'static str, LanguageWords>> = LazyLock::new(|| {
+    language::LANGUAGES
+        .iter()
+        .copied()
+        .chain([&language::OTHER])
+        .filter_map(|language| match toml::from_str(language.words) {
+            Ok(words) => Some((language.name, words)),
+            Err(e) => {
+                log::error!("Keyword list of {} is not valid TOML: {}", language.name, e);
+                None
+            }
+        })
+        .collect()
+});
+
+/// Rust keywords of the default edition, which every highlighter without a
+/// language of its own starts from
+static DEFAULT_SET: LazyLock<KeywordSet> =
+    LazyLock::new(|| KeywordSet::rust(crate::config::DEFAULT_RUST_EDITION));
+
+#[derive(Debug, Clone)]
+pub struct KeywordSet {
+    words: HashMap<String, WordKind>,
+}
+
+impl Default for KeywordSet {
+    fn default() -> Self {
+        DEFAULT_SET.clone()
+    }
+}
+
+impl KeywordSet {
+    /// Rust keywords as of `edition`
+    pub fn rust(edition: u32) -> Self {
+        Self::for_language(&language::RUST, edition)
+    }
+
+    /// Keywords of `language`; `edition` only matters to languages with editions
+    pub fn for_language(language: &Language, edition: u32) -> Self {
+        let mut set = Self {
+            words: HashMap::new(),
+        };
+        let Some(language) = BUNDLED_WORDS.get(language.name) else {
+            return set;
+        };
+        set.add(&language.words);
+        for (since, words) in &language.editions {
+            if since.parse::<u32>().is_ok_and(|since| since <= edition) {
+                set.add(words);
+            }
+        }
+        set
+    }
+
+    /// Keywords of `language` (for Rust, of the configured edition) plus the user's own words
+    pub fn from_config(config: &SyntaxConfig, language: &Language) -> Self {
+        let mut set = Self::for_language(language, config.edition);
+        set.add(&WordLists {
+            declaration: config.keywords.clone(),
+            types: config.types.clone(),
+            functions: config.functions.clone(),
+            ..WordLists::default()
+        });
+        set
+    }
+
+    fn add(&mut self, lists: &WordLists) {
+        for (kind, words) in lists.kinds() {
+            for word in words {
+                self.words.insert(word.clone(), kind);
+            }
+        }
+    }
+
+    pub fn kind(&self, word: &str) -> Option<WordKind> {
+        self.words.get(word).copied()
+    }
+
+    /// Highlight color for `word`
+    pub fn color(&self, word: &str) -> [f32; 4] {
+        self.kind(word).map_or(IDENTIFIER_COLOR, WordKind::color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_rust_words() {
+        let set = KeywordSet::default();
+        assert_eq!(set.kind("fn"), Some(WordKind::Declaration));
+        assert_eq!(set.kind("match"), Some(WordKind::Control));
+        assert_eq!(set.kind("Vec"), Some(WordKind::Type));
+        assert_eq!(set.kind("Some"), Some(WordKind::Literal));
+        assert_eq!(set.kind("greeting"), None);
+        assert_eq!(set.color("greeting"), IDENTIFIER_COLOR);
+    }
+
+    #[test]
+    fn test_edition_keywords() {
+        assert_eq!(KeywordSet::rust(2015).kind("async"), None);
+        assert_eq!(
+            KeywordSet::rust(2018).kind("async"),
+            Some(WordKind::Declaration)
+        );
+        assert_eq!(KeywordSet::rust(2021).kind("gen"), None);
+        assert_eq!(
+            KeywordSet::rust(2024).kind("gen"),
+            Some(WordKind::Declaration)
+        );
+    }
+
+    #[test]
+    fn test_user_words() {
+        let config: SyntaxConfig = toml::from_str(
+            r#"
+            types = ["tokio", "Arc"]
+            functions = ["serde", "println"]
+            "#,
+        )
+        .unwrap();
+        let set = KeywordSet::from_config(&config, &language::RUST);
+        assert_eq!(set.kind("tokio"), Some(WordKind::Type));
+        assert_eq!(set.kind("serde"), Some(WordKind::Function));
+        // Built-in words are still there
+        assert_eq!(set.kind("let"), Some(WordKind::Declaration));
+
+        // The user's words go with every language
+        let set = KeywordSet::from_config(&config, &language::PYTHON);
+        assert_eq!(set.kind("tokio"), Some(WordKind::Type));
+        assert_eq!(set.kind("def"), Some(WordKind::Declaration));
+        assert_eq!(set.kind("let"), None);
+    }
+
+    #[test]
+    fn test_bundled_words_of_every_language() {
+        // Every list parses, the catch-all language's included
+        assert_eq!(BUNDLED_WORDS.len(), language::LANGUAGES.len() + 1);
+        for language in language::LANGUAGES {
+            let set = KeywordSet::for_language(language, crate::config::DEFAULT_RUST_EDITION);
+            assert!(set.words.len() > 20, "{:?}", language);
+        }
+        let go = KeywordSet::for_language(&language::GO, 2024);
+        assert_eq!(go.kind("func"), Some(WordKind::Declaration));
+        assert_eq!(go.kind("nil"), Some(WordKind::Literal));
+        assert_eq!(
+            KeywordSet::for_language(&language::C, 2024).kind("uint8_t"),
+            Some(WordKind::Type)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Languages CargoTap knows how to type
+//!
+//! A file's language comes from its extension. It decides what the keyword
+//! highlighter treats as comments, strings and keywords, how function bodies are
+//! found for folding, which comments can make up a license header, and whether Tab
+//! may skip past the end of a line. Files in other languages get `OTHER`, which
+//! keeps the Rust-like rules CargoTap always had and guesses at the rest.
+
+use std::fmt;
+use std::path::Path;
+
+/// How a language marks where a block ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocks {
+    Braces,
+    /// Blocks are the deeper indented lines after a `:`, as in Python
+    Indentation,
+    /// Indentation after a line ending in `:`, braces otherwise
+    Guess,
+}
+
+#[derive(PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    /// What starts a comment that runs to the end of the line
+    pub line_comments: &'static [&'static str],
+    /// Has `/* */` comments
+    pub block_comments: bool,
+    /// Block comments nest, so `/* /* */ */` is one comment
+    pub nested_comments: bool,
+    /// `'x'` is a character (or a lifetime); otherwise single quotes delimit strings
+    pub char_literals: bool,
+    /// Backticks delimit raw strings, as in Go
+    pub backtick_strings: bool,
+    pub blocks: Blocks,
+    /// Keyword lists, a TOML resource from `src/languages`
+    pub words: &'static str,
+}
+
+// The word lists are long, so they are left out
+impl fmt::Debug for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Language").field(&self.name).finish()
+    }
+}
+
+impl Language {
+    /// The comment prefixes the highlighter colors in code. `OTHER` only gets `//`.
+    pub fn code_comments(&self) -> &'static [&'static str] {
+        if std::ptr::eq(self, &OTHER) {
+            &self.line_comments[..1]
+        } else {
+            self.line_comments
+        }
+    }
+}
+
+pub static RUST: Language = Language {
+    name: "Rust",
+    extensions: &["rs"],
+    line_comments: &["//"],
+    block_comments: true,
+    nested_comments: true,
+    char_literals: true,
+    backtick_strings: false,
+    blocks: Blocks::Braces,
+    words: include_str!("languages/rust.toml"),
+};
+
+pub static PYTHON: Language = Language {
+    name: "Python",
+    extensions: &["py", "pyw", "pyi"],
+    line_comments: &["#"],
+    block_comments: false,
+    nested_comments: false,
+    char_literals: false,
+    backtick_strings: false,
+    blocks: Blocks::Indentation,
+    words: include_str!("languages/python.toml"),
+};
+
+pub static GO: Language = Language {
+    name: "Go",
+    extensions: &["go"],
+    line_comments: &["//"],
+    block_comments: true,
+    nested_comments: false,
+    char_literals: true,
+    backtick_strings: true,
+    blocks: Blocks::Braces,
+    words: include_str!("languages/go.toml"),
+};
+
+pub static C: Language = Language {
+    name: "C",
+    extensions: &["c", "h"],
+    line_comments: &["//"],
+    block_comments: true,
+    nested_comments: false,
+    char_literals: true,
+    backtick_strings: false,
+    blocks: Blocks::Braces,
+    words: include_str!("languages/c.toml"),
+};
+
+/// Everything else. `#` and `--` only count as comments in license headers, where
+/// a whole line is checked; in code they are too often operators.
+pub static OTHER: Language = Language {
+    name: "Other",
+    extensions: &[],
+    line_comments: &["//", "#", "--"],
+    block_comments: true,
+    nested_comments: true,
+    char_literals: true,
+    backtick_strings: false,
+    blocks: Blocks::Guess,
+    words: include_str!("languages/rust.toml"),
+};
+
+pub static LANGUAGES: &[&Language] = &[&RUST, &PYTHON, &GO, &C];
+
+/// The language of the file at `path`, by its extension
+pub fn for_path(path: &str) -> &'static Language {
+    let Some(extension) = Path::new(path).extension() else {
+        return &OTHER;
+    };
+    let extension = extension.to_string_lossy().to_lowercase();
+    LANGUAGES
+        .iter()
+        .copied()
+        .find(|language| language.extensions.contains(&extension.as_str()))
+        .unwrap_or(&OTHER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_by_extension() {
+        assert_eq!(for_path("src/main.rs"), &RUST);
+        assert_eq!(for_path("tools/build.py"), &PYTHON);
+        assert_eq!(for_path("builtin://fizzbuzz.py"), &PYTHON);
+        assert_eq!(for_path("cmd/server/main.go"), &GO);
+        assert_eq!(for_path("include/list.H"), &C);
+        assert_eq!(for_path("notes.md"), &OTHER);
+        assert_eq!(for_path("Makefile"), &OTHER);
+        assert_eq!(OTHER.code_comments(), ["//"]);
+        assert_eq!(PYTHON.code_comments(), ["#"]);
+    }
+}
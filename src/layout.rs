@@ -0,0 +1,175 @@
+//! Wrapping of code lines to the window width
+//!
+//! With `wrap_lines` on, the code view lays a long line out as several rows: the
+//! first after the line's gutter, the rest after a continuation gutter of the same
+//! width with a ↪ where the fold marker goes, so line numbers stay in their column
+//! and the text of every row starts at the same x. Rows break after the last space
+//! or tab that fits, or at the last character that fits when the row has none.
+//!
+//! The caret is drawn as the background of the character it's on, so it stays on
+//! that character whichever row it lands in; the text system only has to know where
+//! a row ends so it doesn't slide the caret past a wrap (see `advance_caret`).
+
+use std::ops::Range;
+
+use crate::text::{ColoredChar, ColoredLine};
+
+/// Marks the rows a line continues on
+pub const CONTINUATION_MARKER: char = '↪';
+
+/// Splits `chars` into rows: the first at most `first_width` wide, the others at
+/// most `width`, measured with `advance`. Every row has at least one character, so
+/// a character wider than the row still gets one of its own.
+pub fn row_ranges(
+    chars: &[ColoredChar],
+    first_width: f32,
+    width: f32,
+    advance: impl Fn(char) -> f32,
+) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut used = 0.0;
+    // Where the row can break so a word isn't split, just after a space or tab
+    let mut last_break = None;
+    let mut index = 0;
+    while index < chars.len() {
+        let limit = if rows.is_empty() { first_width } else { width };
+        let ch = chars[index].ch;
+        let char_width = advance(ch);
+        if used + char_width > limit && index > start {
+            let end = last_break.unwrap_or(index);
+            rows.push(start..end);
+            start = end;
+            used = chars[start..index].iter().map(|c| advance(c.ch)).sum();
+            last_break = None;
+            continue;
+        }
+        used += char_width;
+        if ch == ' ' || ch == '\t' {
+            last_break = Some(index + 1);
+        }
+        index += 1;
+    }
+    rows.push(start..chars.len());
+    rows
+}
+
+/// Largest zoom, in steps of 10% and at most 100%, at which the widest of `lines`
+/// fits in `width` after a gutter `gutter_width` wide. Widths are measured with
+/// `advance` at `zoom` and are taken to grow with it.
+pub fn fitting_zoom(
+    lines: &[ColoredLine],
+    gutter_width: f32,
+    width: f32,
+    zoom: f32,
+    advance: impl Fn(char) -> f32,
+) -> f32 {
+    let widest = lines
+        .iter()
+        .map(|line| line.chars.iter().map(|c| advance(c.ch)).sum::<f32>())
+        .fold(0.0, f32::max);
+    let needed = (gutter_width + widest).max(f32::EPSILON);
+    // The small bias keeps an exact fit from being floored a step too far
+    ((zoom * width / needed * 10.0 + 1e-4).floor() / 10.0).min(1.0)
+}
+
+/// `line` laid out in rows no wider than `width`: the first one after `gutter`,
+/// the others after `continuation`
+pub fn wrap(
+    line: &ColoredLine,
+    gutter: Option<&ColoredLine>,
+    continuation: &ColoredLine,
+    width: f32,
+    advance: impl Fn(char) -> f32,
+) -> Vec<ColoredLine> {
+    let prefix_width = |prefix: &ColoredLine| prefix.chars.iter().map(|c| advance(c.ch)).sum();
+    let gutter_width: f32 = gutter.map_or(0.0, prefix_width);
+    let continuation_width: f32 = prefix_width(continuation);
+
+    row_ranges(
+        &line.chars,
+        width - gutter_width,
+        width - continuation_width,
+        &advance,
+    )
+    .into_iter()
+    .enumerate()
+    .map(|(index, range)| {
+        let prefix = if index == 0 {
+            gutter
+        } else {
+            Some(continuation)
+        };
+        let mut row = prefix.cloned().unwrap_or_else(ColoredLine::new);
+        row.chars.extend_from_slice(&line.chars[range]);
+        row
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> ColoredLine {
+        let mut line = ColoredLine::new();
+        line.push_str(text, [1.0, 1.0, 1.0, 1.0]);
+        line
+    }
+
+    fn text(line: &ColoredLine) -> String {
+        line.chars.iter().map(|c| c.ch).collect()
+    }
+
+    #[test]
+    fn test_rows_break_after_whitespace() {
+        let chars = line("let value = compute(a, b);").chars;
+        let rows = row_ranges(&chars, 12.0, 10.0, |_| 1.0);
+        let rows: Vec<String> = rows
+            .into_iter()
+            .map(|range| chars[range].iter().map(|c| c.ch).collect())
+            .collect();
+        // "compute(a," fills its row, so the space after it starts the next one
+        assert_eq!(rows, ["let value = ", "compute(a,", " b);"]);
+
+        // No whitespace to break at: cut at the last character that fits
+        let chars = line("abcdefghij").chars;
+        assert_eq!(row_ranges(&chars, 4.0, 4.0, |_| 1.0), [0..4, 4..8, 8..10]);
+        // A character wider than the row gets one to itself
+        assert_eq!(row_ranges(&chars[..2], 0.5, 0.5, |_| 1.0), [0..1, 1..2]);
+        let empty = row_ranges(&[], 4.0, 4.0, |_| 1.0);
+        assert_eq!(empty.len(), 1);
+        assert!(empty[0].is_empty());
+    }
+
+    #[test]
+    fn test_continuation_rows_keep_the_gutter_width() {
+        let gutter = line(" 12 │ ");
+        let continuation = line("   ↪│ ");
+        let rows = wrap(
+            &line("fn main() {}"),
+            Some(&gutter),
+            &continuation,
+            14.0,
+            |_| 1.0,
+        );
+        let rows: Vec<String> = rows.iter().map(text).collect();
+        assert_eq!(rows, [" 12 │ fn ", "   ↪│ main() ", "   ↪│ {}"]);
+
+        // Without line numbers only the continuation rows get a prefix
+        let rows = wrap(&line("abcdef"), None, &line("↪ "), 4.0, |_| 1.0);
+        let rows: Vec<String> = rows.iter().map(text).collect();
+        assert_eq!(rows, ["abcd", "↪ ef"]);
+    }
+
+    #[test]
+    fn test_fitting_zoom() {
+        let lines = [line("short"), line(&"x".repeat(96))];
+        // 4 + 96 units in 80: 80% of the current size
+        assert_eq!(fitting_zoom(&lines, 4.0, 80.0, 1.0, |_| 1.0), 0.8);
+        // Measured at 80% already, the same width fits at the next step down only
+        assert_eq!(fitting_zoom(&lines, 4.0, 79.0, 0.8, |_| 1.0), 0.6);
+        // Everything fits: the configured size, never larger
+        assert_eq!(fitting_zoom(&lines, 4.0, 200.0, 1.0, |_| 1.0), 1.0);
+    }
+}
@@ -0,0 +1,157 @@
+//! Leading comment banners (license headers)
+//!
+//! Many files open with a long license comment that is tedious to type. The banner is
+//! the run of comment lines at the very start of a file, after an optional shebang.
+//! It only counts as a header if it mentions a license or copyright, or is long enough
+//! that it can't be a short explanatory comment. Doc comments (`///`, `//!`, `/*!`)
+//! describe the code and are never skipped. Only the file language's comments count,
+//! so a C file's `#include` lines or a Python file's `//` can't pass for a banner.
+
+use serde::{Deserialize, Serialize};
+
+use crate::language::Language;
+
+/// Banners that don't mention a license need at least this many lines
+const MIN_UNLABELED_LINES: usize = 10;
+
+const KEYWORDS: &[&str] = &["license", "licence", "copyright", "spdx-license-identifier"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    /// Byte offset just past the header and the blank lines after it
+    pub end: usize,
+    /// Lines the header and the blank lines after it take up
+    pub lines: usize,
+}
+
+/// Finds the license header at the start of `code`, written in `language`, if it has one
+/// and some code after it
+pub fn detect(code: &str, language: &Language) -> Option<Header> {
+    let mut lines = code.split_inclusive('\n').peekable();
+    let mut end = 0;
+    let mut line_count = 0;
+    let mut banner = String::new();
+    let mut comment_lines = 0;
+    let mut in_block = false;
+
+    if let Some(first) = lines.peek()
+        && first.starts_with("#!")
+        && !first.starts_with("#![")
+    {
+        end += first.len();
+        line_count += 1;
+        lines.next();
+    }
+
+    while let Some(line) = lines.peek() {
+        let trimmed = line.trim();
+        if in_block {
+            in_block = !trimmed.contains("*/");
+        } else if language.block_comments
+            && trimmed.starts_with("/*")
+            && !trimmed.starts_with("/*!")
+        {
+            in_block = !trimmed[2..].contains("*/");
+        } else if !is_line_comment(trimmed, language) {
+            break;
+        }
+
+        banner.push_str(&line.to_lowercase());
+        comment_lines += 1;
+        end += line.len();
+        line_count += 1;
+        lines.next();
+    }
+
+    if in_block || comment_lines == 0 {
+        return None;
+    }
+    if comment_lines < MIN_UNLABELED_LINES && !KEYWORDS.iter().any(|word| banner.contains(word)) {
+        return None;
+    }
+
+    while let Some(line) = lines.next_if(|line| line.trim().is_empty()) {
+        end += line.len();
+        line_count += 1;
+    }
+    if end >= code.len() {
+        return None;
+    }
+
+    Some(Header {
+        end,
+        lines: line_count,
+    })
+}
+
+fn is_line_comment(trimmed: &str, language: &Language) -> bool {
+    if !language
+        .line_comments
+        .iter()
+        .any(|comment| trimmed.starts_with(comment))
+    {
+        return false;
+    }
+    if let Some(rest) = trimmed.strip_prefix("//") {
+        return !rest.starts_with('/') && !rest.starts_with('!');
+    }
+    // `#` comments, but not `#[attr]`, `#include` and the like
+    if let Some(rest) = trimmed.strip_prefix('#') {
+        return rest.is_empty() || rest.starts_with([' ', '#', '-', '=']);
+    }
+    trimmed.starts_with("--")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::{C, OTHER, PYTHON, RUST};
+
+    #[test]
+    fn test_detects_license_comments() {
+        let code = "// Copyright 2024 Someone\n// Licensed under MIT\n\nfn main() {}\n";
+        let header = detect(code, &RUST).unwrap();
+        assert_eq!(&code[header.end..], "fn main() {}\n");
+        assert_eq!(header.lines, 3);
+
+        let code = "#!/usr/bin/env python3\n# SPDX-License-Identifier: Apache-2.0\nimport os\n";
+        assert_eq!(&code[detect(code, &PYTHON).unwrap().end..], "import os\n");
+        // `#` isn't a comment in Rust
+        assert!(detect(code, &RUST).is_none());
+
+        let code = "/*\n * Copyright (c) 2020\n * All rights reserved.\n */\n#include <stdio.h>\n";
+        let header = detect(code, &C).unwrap();
+        assert_eq!(&code[header.end..], "#include <stdio.h>\n");
+        assert_eq!(header.lines, 4);
+    }
+
+    #[test]
+    fn test_long_banner_without_keyword() {
+        let banner = "// ===\n".repeat(MIN_UNLABELED_LINES);
+        let code = format!("{}use std::io;\n", banner);
+        assert_eq!(detect(&code, &RUST).unwrap().end, banner.len());
+
+        let short = "// Entry point\n// of the tool\nfn main() {}\n";
+        assert!(detect(short, &RUST).is_none());
+    }
+
+    #[test]
+    fn test_leaves_code_and_doc_comments_alone() {
+        assert!(detect("fn main() {}\n", &RUST).is_none());
+        assert!(detect("//! Crate docs, MIT license\nmod a;\n", &RUST).is_none());
+        assert!(detect("/// Copyright notice type\nstruct Copyright;\n", &RUST).is_none());
+        assert!(detect("#![allow(dead_code)]\n// Copyright\nfn a() {}\n", &RUST).is_none());
+        // Unterminated block comment or nothing but the banner
+        assert!(detect("/* Copyright\nfn a() {}\n", &RUST).is_none());
+        assert!(detect("// Copyright 2024\n\n", &RUST).is_none());
+    }
+
+    #[test]
+    fn test_comments_of_the_language() {
+        let code = "-- Copyright 2024\n-- MIT License\nSELECT 1;\n";
+        assert!(detect(code, &OTHER).is_some());
+        assert!(detect(code, &C).is_none());
+        // Python has no block comments
+        assert!(detect("/* Copyright */\nx = 1\n", &PYTHON).is_none());
+    }
+}
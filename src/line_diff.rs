@@ -0,0 +1,266 @@
+//! Resuming in a changed file
+//!
+//! Saved progress keeps a hash of every line from the top of the file to the cursor.
+//! When the file changed since, those lines are diffed against the new text to find
+//! where the typed part ended up, so the user can pick up there instead of at the top.
+
+use serde::{Deserialize, Serialize};
+
+/// Files with the cursor further down keep no line hashes, to keep progress small
+pub const MAX_ANCHOR_LINES: usize = 5000;
+
+/// Past this many added and removed lines the files are treated as unrelated
+const MAX_EDITS: usize = 500;
+
+/// The typed part of a file, line by line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineAnchor {
+    /// Hash of each line from the first one through the cursor line
+    pub hashes: Vec<u32>,
+    /// Byte offset of the cursor in its line
+    pub column: usize,
+}
+
+impl LineAnchor {
+    /// The anchor for `position` in `content`, unless the cursor is too far down
+    pub fn new(content: &str, position: usize) -> Option<Self> {
+        let typed = content.get(..position)?;
+        let cursor_line = typed.matches('\n').count();
+        if cursor_line >= MAX_ANCHOR_LINES {
+            return None;
+        }
+        Some(Self {
+            hashes: content
+                .split('\n')
+                .take(cursor_line + 1)
+                .map(line_hash)
+                .collect(),
+            column: typed.rfind('\n').map_or(position, |i| position - i - 1),
+        })
+    }
+}
+
+/// Where to continue in the new version of a file, and what changed above that point
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumeOffer {
+    pub position: usize,
+    /// 1-based line of `position`
+    pub line: usize,
+    /// The cursor line is unchanged, so the offer keeps the column
+    pub exact: bool,
+    /// Lines above the offer that weren't there before
+    pub added: usize,
+    /// Typed lines that are gone or were edited
+    pub removed: usize,
+    /// 1-based line of the first difference
+    pub first_change: usize,
+}
+
+impl ResumeOffer {
+    /// Whether everything that was typed is still there, i.e. only the rest of the file changed
+    pub fn typed_part_unchanged(&self) -> bool {
+        self.exact && self.added == 0 && self.removed == 0
+    }
+}
+
+/// Finds where the typed lines of `anchor` are in `content`
+///
+/// Returns None when none of them survived or the files differ too much.
+pub fn resume_offer(anchor: &LineAnchor, content: &str) -> Option<ResumeOffer> {
+    let mut line_starts = Vec::new();
+    let mut new = Vec::new();
+    let mut start = 0;
+    for line in content.split('\n') {
+        line_starts.push(start);
+        new.push(line_hash(line));
+        start += line.len() + 1;
+    }
+
+    let matches = prefix_alignment(&anchor.hashes, &new)?;
+    let &(last_old, last_new) = matches.last()?;
+    let exact = last_old + 1 == anchor.hashes.len();
+    let (position, line) = if exact {
+        let line_len = content[line_starts[last_new]..]
+            .find('\n')
+            .unwrap_or(content.len() - line_starts[last_new]);
+        (
+            line_starts[last_new] + anchor.column.min(line_len),
+            last_new + 1,
+        )
+    } else {
+        // The cursor line changed, so start over on the line after the last one that didn't
+        let next = last_new + 1;
+        (
+            line_starts.get(next).copied().unwrap_or(content.len()),
+            (next + 1).min(new.len()),
+        )
+    };
+    let unchanged_prefix = matches
+        .iter()
+        .take_while(|(old_index, new_index)| old_index == new_index)
+        .count();
+
+    Some(ResumeOffer {
+        position,
+        line,
+        exact,
+        added: last_new + 1 - matches.len(),
+        removed: anchor.hashes.len() - matches.len(),
+        first_change: unchanged_prefix + 1,
+    })
+}
+
+fn line_hash(line: &str) -> u32 {
+    // FNV-1a; trailing whitespace (and a stray \r) doesn't make a line different
+    line.trim_end().bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Myers' diff of `old` against the start of `new`: the shortest edit script that
+/// uses up all of `old`, with whatever follows in `new` left out
+///
+/// Returns the matching (old, new) index pairs in order.
+fn prefix_alignment(old: &[u32], new: &[u32]) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = MAX_EDITS as isize;
+    let offset = max + 1;
+    // Furthest x on each diagonal k = x - y, or -1 where no valid path reaches
+    let mut v = vec![-1isize; 2 * offset as usize + 1];
+    v[offset as usize + 1] = 0;
+    let mut trace = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let Some((mut x, _)) = step(&v, offset, d, k, n, m) else {
+                v[(offset + k) as usize] = -1;
+                continue;
+            };
+            while x < n && x - k < m && old[x as usize] == new[(x - k) as usize] {
+                x += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x == n {
+                return Some(backtrack(&trace, offset, d, k, x, n, m));
+            }
+        }
+    }
+    None
+}
+
+/// Where the edit on diagonal `k` in round `d` lands, and whether it came from diagonal k + 1
+fn step(
+    v: &[isize],
+    offset: isize,
+    d: isize,
+    k: isize,
+    n: isize,
+    m: isize,
+) -> Option<(isize, bool)> {
+    let at = |k: isize| v[(offset + k) as usize];
+    if d == 0 {
+        return Some((at(1), true));
+    }
+    // An insertion moves down from diagonal k + 1, a deletion right from k - 1
+    let insertion = (k < d && at(k + 1) >= 0 && at(k + 1) - k <= m).then(|| at(k + 1));
+    let deletion = (k > -d && at(k - 1) >= 0 && at(k - 1) < n).then(|| at(k - 1) + 1);
+    match (insertion, deletion) {
+        (Some(down), Some(right)) if right > down => Some((right, false)),
+        (Some(down), _) => Some((down, true)),
+        (None, Some(right)) => Some((right, false)),
+        (None, None) => None,
+    }
+}
+
+fn backtrack(
+    trace: &[Vec<isize>],
+    offset: isize,
+    d: isize,
+    k: isize,
+    x: isize,
+    n: isize,
+    m: isize,
+) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let (mut k, mut x) = (k, x);
+    for d in (0..=d).rev() {
+        let v = &trace[d as usize];
+        let (start, from_insertion) = step(v, offset, d, k, n, m).unwrap_or((0, true));
+        for matched in (start..x).rev() {
+            matches.push((matched as usize, (matched - k) as usize));
+        }
+        if d > 0 {
+            k = if from_insertion { k + 1 } else { k - 1 };
+            x = v[(offset + k) as usize];
+        }
+    }
+    matches.reverse();
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str =
+        "use std::io;\n\nfn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n";
+
+    fn offer(old: &str, position: usize, new: &str) -> Option<ResumeOffer> {
+        resume_offer(&LineAnchor::new(old, position).unwrap(), new)
+    }
+
+    #[test]
+    fn test_lines_added_above_the_cursor() {
+        let position = ORIGINAL.find("x);").unwrap();
+        let changed = ORIGINAL.replace("use std::io;\n", "use std::fs;\nuse std::io;\n");
+        let offer = offer(ORIGINAL, position, &changed).unwrap();
+
+        assert!(offer.exact);
+        assert_eq!(offer.line, 6);
+        assert_eq!(&changed[offer.position..offer.position + 3], "x);");
+        assert_eq!((offer.added, offer.removed, offer.first_change), (1, 0, 1));
+        assert!(!offer.typed_part_unchanged());
+    }
+
+    #[test]
+    fn test_cursor_line_edited() {
+        let position = ORIGINAL.find("x);").unwrap();
+        let changed = ORIGINAL
+            .replace("    let x = 1;\n", "")
+            .replace("x);", "2);");
+        let offer = offer(ORIGINAL, position, &changed).unwrap();
+
+        // Nothing typed is left after `fn main() {`, so the offer starts on the next line
+        assert!(!offer.exact);
+        assert_eq!(offer.line, 4);
+        assert!(changed[offer.position..].starts_with("    println!(\"{}\", 2);"));
+        assert_eq!((offer.added, offer.removed, offer.first_change), (0, 2, 4));
+    }
+
+    #[test]
+    fn test_changes_below_the_cursor() {
+        let position = ORIGINAL.find("let").unwrap();
+        let changed = format!("{}\nfn helper() {{}}\n", ORIGINAL);
+        let offer = offer(ORIGINAL, position, &changed).unwrap();
+
+        assert!(offer.typed_part_unchanged());
+        assert_eq!(offer.position, position);
+    }
+
+    #[test]
+    fn test_unrelated_file() {
+        assert!(offer(ORIGINAL, 20, "something\nelse entirely\n").is_none());
+    }
+
+    #[test]
+    fn test_anchor_column_and_limit() {
+        let anchor = LineAnchor::new("ab\ncd", 4).unwrap();
+        assert_eq!(anchor.hashes.len(), 2);
+        assert_eq!(anchor.column, 1);
+        assert_eq!(LineAnchor::new("abc", 2).unwrap().column, 2);
+
+        let long = "x\n".repeat(MAX_ANCHOR_LINES + 1);
+        assert!(LineAnchor::new(&long, long.len()).is_none());
+    }
+}
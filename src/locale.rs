@@ -0,0 +1,233 @@
+//! Locale-aware formatting of numbers, dates and times
+//!
+//! The statistics screen and the `analyze` report write counts ("1,234" or
+//! "1.234"), decimals, dates and times of day (24-hour or AM/PM) through a
+//! `Locale`. It comes from `[statistics] locale`, or from the system (`LC_ALL`,
+//! `LC_NUMERIC`, `LANG`) when that is "auto". Only the conventions in `KNOWN` are
+//! available; other locales use their language's, or en-US's.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+
+/// `[statistics] locale` value that follows the system locale
+pub const AUTO: &str = "auto";
+
+/// How numbers, dates and times are written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    /// Tag of these conventions, e.g. "de-DE"
+    pub tag: &'static str,
+    group_separator: &'static str,
+    decimal_separator: char,
+    date_format: &'static str,
+    time_format: &'static str,
+}
+
+const fn locale(
+    tag: &'static str,
+    group_separator: &'static str,
+    decimal_separator: char,
+    date_format: &'static str,
+    time_format: &'static str,
+) -> Locale {
+    Locale {
+        tag,
+        group_separator,
+        decimal_separator,
+        date_format,
+        time_format,
+    }
+}
+
+/// Conventions that can be configured; the first of a language is used for
+/// other countries speaking it
+const KNOWN: &[Locale] = &[
+    locale("en-US", ",", '.', "%m/%d/%Y", "%-I:%M %p"),
+    locale("en-GB", ",", '.', "%d/%m/%Y", "%H:%M"),
+    locale("de-DE", ".", ',', "%d.%m.%Y", "%H:%M"),
+    locale("fr-FR", " ", ',', "%d/%m/%Y", "%H:%M"),
+    locale("es-ES", ".", ',', "%d/%m/%Y", "%H:%M"),
+    locale("it-IT", ".", ',', "%d/%m/%Y", "%H:%M"),
+    locale("pt-BR", ".", ',', "%d/%m/%Y", "%H:%M"),
+    locale("nl-NL", ".", ',', "%d-%m-%Y", "%H:%M"),
+    locale("pl-PL", " ", ',', "%d.%m.%Y", "%H:%M"),
+    locale("ru-RU", " ", ',', "%d.%m.%Y", "%H:%M"),
+    locale("uk-UA", " ", ',', "%d.%m.%Y", "%H:%M"),
+    locale("sv-SE", " ", ',', "%Y-%m-%d", "%H:%M"),
+    locale("ja-JP", ",", '.', "%Y/%m/%d", "%H:%M"),
+    locale("zh-CN", ",", '.', "%Y/%m/%d", "%H:%M"),
+    locale("ko-KR", ",", '.', "%Y. %m. %d.", "%H:%M"),
+    // The "C" and "POSIX" locales: plain digits and ISO dates
+    locale("C", "", '.', "%Y-%m-%d", "%H:%M"),
+];
+
+impl Default for Locale {
+    fn default() -> Self {
+        KNOWN[0]
+    }
+}
+
+impl Locale {
+    /// Conventions for a tag like "de-DE", "de_DE.UTF-8" or "de", if known
+    pub fn parse(tag: &str) -> Option<Self> {
+        let tag = tag
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .replace('_', "-");
+        if tag.eq_ignore_ascii_case("POSIX") {
+            return Self::parse("C");
+        }
+        if let Some(exact) = KNOWN
+            .iter()
+            .find(|known| known.tag.eq_ignore_ascii_case(&tag))
+        {
+            return Some(*exact);
+        }
+        let language = tag.split('-').next().unwrap_or_default();
+        KNOWN
+            .iter()
+            .find(|known| {
+                known
+                    .tag
+                    .split('-')
+                    .next()
+                    .is_some_and(|known| known.eq_ignore_ascii_case(language))
+            })
+            .copied()
+    }
+
+    /// The locale for a `[statistics] locale` setting; unknown ones fall back to en-US
+    pub fn resolve(setting: &str) -> Self {
+        if setting.trim().eq_ignore_ascii_case(AUTO) {
+            return Self::system().unwrap_or_default();
+        }
+        Self::parse(setting).unwrap_or_default()
+    }
+
+    /// The locale of the environment, the way POSIX programs pick it
+    fn system() -> Option<Self> {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+
+    /// A count with its thousands grouped, e.g. "12,345"
+    pub fn count(&self, count: usize) -> String {
+        self.group(&count.to_string())
+    }
+
+    /// `value` rounded to `places` decimals, e.g. "1,234.5" or "1.234,5"
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let text = format!("{:.*}", places, value);
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let mut out = format!("{}{}", sign, self.group(whole));
+        if !fraction.is_empty() {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// Like `decimal`, with a "+" in front of values that aren't negative
+    pub fn signed(&self, value: f64, places: usize) -> String {
+        let text = self.decimal(value, places);
+        if text.starts_with('-') {
+            text
+        } else {
+            format!("+{}", text)
+        }
+    }
+
+    pub fn percent(&self, value: f64, places: usize) -> String {
+        format!("{}%", self.decimal(value, places))
+    }
+
+    pub fn date(&self, date: NaiveDate) -> String {
+        date.format(self.date_format).to_string()
+    }
+
+    pub fn time(&self, time: NaiveTime) -> String {
+        time.format(self.time_format).to_string()
+    }
+
+    /// Local date and time of a session timestamp (Unix seconds)
+    pub fn timestamp(&self, timestamp: u64) -> String {
+        match DateTime::from_timestamp(timestamp as i64, 0) {
+            Some(utc) => {
+                let local = utc.with_timezone(&Local);
+                format!(
+                    "{} {}",
+                    self.date(local.date_naive()),
+                    self.time(local.time())
+                )
+            }
+            None => "-".to_string(),
+        }
+    }
+
+    fn group(&self, digits: &str) -> String {
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, ch) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push_str(self.group_separator);
+            }
+            out.push(ch);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(Locale::parse("de_DE.UTF-8").unwrap().tag, "de-DE");
+        assert_eq!(Locale::parse("en-gb").unwrap().tag, "en-GB");
+        // Other countries get their language's conventions
+        assert_eq!(Locale::parse("de_AT").unwrap().tag, "de-DE");
+        assert_eq!(Locale::parse("POSIX").unwrap().tag, "C");
+        assert!(Locale::parse("xx-YY").is_none());
+        assert_eq!(Locale::resolve("xx-YY"), Locale::default());
+    }
+
+    #[test]
+    fn test_numbers() {
+        let us = Locale::parse("en-US").unwrap();
+        let de = Locale::parse("de-DE").unwrap();
+        let fr = Locale::parse("fr-FR").unwrap();
+        let c = Locale::parse("C").unwrap();
+
+        assert_eq!(us.count(1234567), "1,234,567");
+        assert_eq!(us.count(123), "123");
+        assert_eq!(fr.count(1234), "1 234");
+        assert_eq!(c.count(1234), "1234");
+        assert_eq!(de.decimal(1234.56, 1), "1.234,6");
+        assert_eq!(de.decimal(-1234.0, 0), "-1.234");
+        assert_eq!(us.percent(95.26, 1), "95.3%");
+        assert_eq!(de.signed(2.5, 1), "+2,5");
+        assert_eq!(us.signed(-2.5, 1), "-2.5");
+    }
+
+    #[test]
+    fn test_dates_and_times() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 7).unwrap();
+        let time = NaiveTime::from_hms_opt(14, 5, 0).unwrap();
+        let us = Locale::parse("en-US").unwrap();
+        let de = Locale::parse("de-DE").unwrap();
+
+        assert_eq!(us.date(date), "03/07/2026");
+        assert_eq!(us.time(time), "2:05 PM");
+        assert_eq!(de.date(date), "07.03.2026");
+        assert_eq!(de.time(time), "14:05");
+        assert_eq!(Locale::parse("C").unwrap().date(date), "2026-03-07");
+    }
+}
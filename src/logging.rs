@@ -0,0 +1,138 @@
+//! Log output
+//!
+//! `log_format` under `[debug]` picks how log records are written: "pretty"
+//! (colors and timestamps), "plain" (the same without colors, for redirecting to a
+//! file) or "json", one object per line on stderr for tools that ingest logs.
+//!
+//! Hot paths (keystrokes, progress, file loads, saves, slow frames) log with
+//! structured fields through the `log` crate's key-values: an `event` name and,
+//! where they apply, `file`, `position` and `duration_ms`. JSON lines carry them as
+//! fields of their own next to the message; the text formats show just the message.
+
+use std::io::Write;
+
+use log::kv::{self, VisitSource};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde_json::{Map, Value};
+use simple_logger::SimpleLogger;
+
+use crate::config::LogFormat;
+
+/// Installs the logger for `format`, letting through records at `level` and above
+pub fn init(level: Level, format: LogFormat) -> Result<(), SetLoggerError> {
+    let filter = level.to_level_filter();
+    match format {
+        LogFormat::Pretty => SimpleLogger::new().with_level(filter).init(),
+        LogFormat::Plain => SimpleLogger::new()
+            .with_level(filter)
+            .with_colors(false)
+            .init(),
+        LogFormat::Json => {
+            log::set_max_level(filter);
+            log::set_boxed_logger(Box::new(JsonLogger { filter }))
+        }
+    }
+}
+
+struct JsonLogger {
+    filter: LevelFilter,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        // A log line that can't be written has nowhere else to go
+        let _ = writeln!(
+            std::io::stderr().lock(),
+            "{}",
+            json_line(record, &timestamp)
+        );
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// One JSON object for `record`: timestamp, level, target and message, then its
+/// key-values as fields of their own
+fn json_line(record: &Record, timestamp: &str) -> String {
+    let mut object = Map::new();
+    object.insert("timestamp".to_string(), timestamp.into());
+    object.insert("level".to_string(), record.level().as_str().into());
+    object.insert("target".to_string(), record.target().into());
+    object.insert("message".to_string(), record.args().to_string().into());
+    // Visiting only fails if the visitor does, and this one never does
+    let _ = record.key_values().visit(&mut JsonFields(&mut object));
+    Value::Object(object).to_string()
+}
+
+struct JsonFields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.insert(key.as_str().to_string(), json_value(&value));
+        Ok(())
+    }
+}
+
+/// Numbers and booleans stay JSON numbers and booleans; anything else is a string
+fn json_value(value: &kv::Value) -> Value {
+    if let Some(flag) = value.to_bool() {
+        flag.into()
+    } else if let Some(number) = value.to_u64() {
+        number.into()
+    } else if let Some(number) = value.to_i64() {
+        number.into()
+    } else if let Some(number) = value.to_f64().and_then(serde_json::Number::from_f64) {
+        Value::Number(number)
+    } else if let Some(text) = value.to_borrowed_str() {
+        text.into()
+    } else {
+        value.to_string().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_line_carries_fields() {
+        let fields: &[(&str, kv::Value)] = &[
+            ("event", "file_loaded".into()),
+            ("file", "src/main.rs".into()),
+            ("position", 42usize.into()),
+            ("duration_ms", 1.5f64.into()),
+            ("expected", 'x'.into()),
+        ];
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("Loaded {}", "src/main.rs"))
+                .level(Level::Info)
+                .target("CargoTap::app")
+                .key_values(&fields)
+                .build(),
+            "2026-01-01T00:00:00.000Z",
+        );
+
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["timestamp"], "2026-01-01T00:00:00.000Z");
+        assert_eq!(parsed["level"], "INFO");
+        assert_eq!(parsed["target"], "CargoTap::app");
+        assert_eq!(parsed["message"], "Loaded src/main.rs");
+        assert_eq!(parsed["event"], "file_loaded");
+        assert_eq!(parsed["file"], "src/main.rs");
+        assert_eq!(parsed["position"], 42);
+        assert_eq!(parsed["duration_ms"], 1.5);
+        assert_eq!(parsed["expected"], "x");
+        assert!(!line.contains('\n'));
+    }
+}
@@ -1,24 +1,77 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use log::info;
+use std::io::IsTerminal;
 use winit::event_loop::EventLoop;
 
+mod aggregators;
+mod animation;
 mod app;
+mod audio;
+mod calendar;
+mod caret;
+mod challenge;
+mod changelog;
 mod char_utils;
+mod code_source;
 mod code_state;
+mod color_picker;
 mod config;
+mod daemon;
 mod demo_code_state;
+mod discord;
+mod dogfood;
+mod drills;
+mod embed;
+mod encoding;
 mod event_handler;
+mod events;
+mod file_analysis;
+mod file_browser;
+mod folding;
+mod highlight;
+mod highlighter;
+mod home_row;
+mod ignore;
 mod input;
+mod keyboard;
+mod keywords;
+mod language;
+mod layout;
+mod license_header;
+mod line_diff;
+mod locale;
+mod logging;
+mod metronome;
+mod paths;
+mod pomodoro;
+mod practice_lock;
+mod profile;
 mod profiling;
 mod progress_helper;
 mod progress_storage;
+mod quotes;
+mod refresh;
+mod reminder;
 mod renderer;
+mod report;
+mod result_card;
+mod review;
+mod samples;
+mod scripting;
+mod secrets;
 mod session_history;
 mod session_state;
+mod split;
+mod state_snapshot;
+mod symbol_hints;
 mod text;
+mod trace;
+mod tutorial;
+mod typing_engine;
 mod typing_handler;
 mod ui;
 mod ui_blocks;
+mod webhook;
 
 mod examples;
 use examples::colored_text_demo::ColoredTextDemo;
@@ -26,15 +79,24 @@ use examples::colored_text_demo::ColoredTextDemo;
 use app::CargoTapApp;
 
 fn main() -> Result<()> {
+    // The profile decides which config and data files are used, so it comes first
+    let args = profile::choose(std::env::args().collect())?;
     let config = config::Config::load();
-    simple_logger::init_with_level(config.get_log_level()).unwrap();
+    logging::init(config.get_log_level(), config.debug.log_format)?;
 
-    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "profiles" {
+        return profile::run(&args[2..]);
+    }
+
+    if let Some(dir) = profile::portable() {
+        info!("Portable mode: keeping data in {}", dir.display());
+    }
 
     if args.len() > 1 && args[1] == "gen-config" {
-        match config::Config::save_default("config.toml") {
+        let config_path = profile::shared_config_path();
+        match config::Config::save_default(&config_path) {
             Ok(_) => {
-                println!("✓ Default configuration saved to config.toml");
+                println!("✓ Default configuration saved to {}", config_path.display());
                 println!("  Edit this file to customize CargoTap settings.");
                 return Ok(());
             }
@@ -50,15 +112,156 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.len() > 1 && args[1] == "snapshot" {
+        let output = args.get(2).map_or("snapshot.png", String::as_str);
+        let typed = args.get(3).map_or("", String::as_str);
+        return embed::snapshot(std::path::Path::new(output), typed);
+    }
+
+    if args.len() > 1 && args[1] == "traces" {
+        return trace::run(&args[2..]);
+    }
+
+    if args.len() > 1 && args[1] == "analyze" {
+        return report::run(&args[2..], &config);
+    }
+
+    if args.len() > 1 && args[1] == "state" {
+        return state_snapshot::run(&args[2..]);
+    }
+
+    if args.len() > 1 && args[1] == "export" {
+        return calendar::run(&args[2..]);
+    }
+
+    if args.len() > 1 && args[1] == "daemon" && daemon::run(&args[2..])? {
+        return Ok(());
+    }
+    let daemon_mode = args.len() > 1 && args[1] == "daemon";
+    if daemon_mode && !config.daemon.enabled {
+        bail!("the daemon is disabled (enabled = false under [daemon] in config.toml)");
+    }
+
+    // With a daemon running, its window opens the file and this process is done
+    let open = if args.len() > 1 && args[1] == "open" {
+        let path = args.get(2).map(std::path::absolute).transpose()?;
+        if config.daemon.enabled
+            && let Some(reply) = daemon::send(&daemon::Command::Open(path.clone()))?
+        {
+            if reply.starts_with("error:") {
+                bail!("{}", reply.trim_start_matches("error:").trim());
+            }
+            println!("{}", reply);
+            return Ok(());
+        }
+        path
+    } else {
+        None
+    };
+
+    let challenge = if args.len() > 1 && args[1] == "challenge" {
+        match challenge::command(&args[2..], &config.gameplay)? {
+            Some(challenge) => Some(challenge),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+
+    let split = if args.len() > 1 && args[1] == "split" {
+        Some(split::parse_args(
+            &args[2..],
+            config.gameplay.split_switch_lines,
+        )?)
+    } else {
+        None
+    };
+
+    if args.iter().any(|arg| arg == "--no-gpu") {
+        info!("--no-gpu given, skipping Vulkan");
+        demo_code_state::run_demo();
+        return Ok(());
+    }
+
     info!("Starting CargoTap application");
     info!("Tip: Run with 'cargo run demo' for command-line demo");
     info!("Tip: Run with 'cargo run gen-config' to generate config.toml");
+    info!("Tip: Run with 'cargo run snapshot out.png [text]' to render a frame offscreen");
+    info!("Tip: Run with 'cargo run analyze [history.json]' for a practice report");
+    info!("Tip: Run with 'cargo run export --heatmap year.png' for a practice calendar");
+    info!("Tip: Run with 'cargo run challenge run FILE' to take a shared challenge");
+    info!("Tip: Run with 'cargo run split FILE_A FILE_B' to type two files in turns");
+    info!("Tip: Run with 'cargo run state' to print the app state saved with Ctrl+Shift+I");
+    info!("Tip: Run with 'cargo run -- --whats-new' to see what changed in this version");
+    info!("Tip: Run with 'cargo run -- --tutorial' to take the guided tour again");
+    info!("Tip: Run with 'cargo run daemon', then 'cargo run open FILE' for instant windows");
 
-    let event_loop = EventLoop::new()?;
-    let mut app = CargoTapApp::new(&event_loop)?;
+    let (event_loop, mut app) = match start_graphics() {
+        Ok(started) => started,
+        Err(error) => return safe_mode(error),
+    };
+    // A challenge or split given on the command line replaces the tutorial's text
+    if args.iter().any(|arg| arg == "--tutorial") || app.tutorial_due() {
+        app.start_tutorial();
+    }
+    if args.iter().any(|arg| arg == "--whats-new") {
+        app.open_whats_new();
+    } else {
+        app.offer_whats_new();
+    }
+    if let Some(challenge) = challenge {
+        app.start_challenge(challenge)?;
+    }
+    if let Some((left, right, switch_lines)) = split {
+        app.start_split(left, right, switch_lines)?;
+    }
+    if let Some(path) = open {
+        app.load_file(path.to_string_lossy().into_owned())?;
+    }
+    if daemon_mode {
+        let proxy = event_loop.create_proxy();
+        let server = daemon::Server::bind(&daemon::socket_path(), move || {
+            // Only fails once the event loop is gone
+            let _ = proxy.send_event(());
+        })?;
+        app.start_daemon(server);
+    }
 
     info!("Starting event loop");
     event_loop.run_app(&mut app)?;
+    if let Some(error) = app.render_engine.take_startup_error() {
+        return safe_mode(error);
+    }
     info!("Finished event loop");
     Ok(())
 }
+
+fn start_graphics() -> Result<(EventLoop<()>, CargoTapApp)> {
+    let event_loop = EventLoop::new()?;
+    let app = CargoTapApp::new(&event_loop)?;
+    Ok((event_loop, app))
+}
+
+/// Explains why the window could not open and, on a terminal, continues in the
+/// command-line mode instead
+fn safe_mode(error: anyhow::Error) -> Result<()> {
+    eprintln!("✗ Could not start the graphical mode: {:#}", error);
+    eprintln!();
+    eprintln!("Things to try:");
+    eprintln!("  • Install or update the Vulkan driver for your GPU");
+    eprintln!(
+        "  • Without a GPU, install a software driver such as lavapipe (mesa-vulkan-drivers)"
+    );
+    eprintln!("  • Make sure a display is available (DISPLAY or WAYLAND_DISPLAY over SSH)");
+    eprintln!("  • Run `vulkaninfo` to see what the Vulkan loader finds");
+    eprintln!("  • Start with --no-gpu to skip Vulkan entirely");
+
+    if !std::io::stdin().is_terminal() {
+        return Err(error);
+    }
+    eprintln!();
+    eprintln!("Falling back to the command-line mode.");
+    eprintln!();
+    demo_code_state::run_demo();
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Metronome pacing
+//!
+//! With `[metronome] enabled`, keystrokes that come sooner than `interval_ms` after
+//! the last one are held back (or, in "flag" mode, typed but counted), so speed
+//! can't run ahead of accuracy. The session HUD shows the beat, and `audible` ticks
+//! on each one. Held back keys never reach the typing rules, so
+//! they count neither as typed nor as errors; the session only records how many
+//! came too fast.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{MetronomeConfig, MetronomeMode};
+
+/// Keystrokes are never asked to be spaced closer than this, whatever the config says
+const MIN_INTERVAL_MS: u64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    InTime,
+    TooFast,
+}
+
+#[derive(Debug, Clone)]
+pub struct Metronome {
+    interval: Duration,
+    mode: MetronomeMode,
+    audible: bool,
+    /// The last keystroke that counted, which the next one is measured from
+    last_keystroke: Option<Instant>,
+    /// Beat reported by `new_beat` last
+    last_beat: Option<u64>,
+}
+
+impl Metronome {
+    pub fn new(config: &MetronomeConfig) -> Self {
+        Self {
+            interval: Duration::from_millis(config.interval_ms.max(MIN_INTERVAL_MS)),
+            mode: config.mode,
+            audible: config.audible,
+            last_keystroke: None,
+            last_beat: None,
+        }
+    }
+
+    pub fn mode(&self) -> MetronomeMode {
+        self.mode
+    }
+
+    pub fn audible(&self) -> bool {
+        self.audible
+    }
+
+    /// Starts over for a new session
+    pub fn reset(&mut self) {
+        self.last_keystroke = None;
+        self.last_beat = None;
+    }
+
+    /// Checks a keystroke at `now` against the pace. A held back keystroke doesn't
+    /// count, so the wait is measured from the last one that did.
+    pub fn keystroke(&mut self, now: Instant) -> Pace {
+        let too_fast = self
+            .last_keystroke
+            .is_some_and(|last| now.saturating_duration_since(last) < self.interval);
+        if too_fast && self.mode == MetronomeMode::Reject {
+            return Pace::TooFast;
+        }
+        self.last_keystroke = Some(now);
+        if too_fast {
+            Pace::TooFast
+        } else {
+            Pace::InTime
+        }
+    }
+
+    /// Beats since the session started, `elapsed` ago
+    pub fn beat(&self, elapsed: Duration) -> u64 {
+        (elapsed.as_nanos() / self.interval.as_nanos()) as u64
+    }
+
+    /// True when a beat was reached at `elapsed` that wasn't reported yet
+    pub fn new_beat(&mut self, elapsed: Duration) -> bool {
+        let beat = self.beat(elapsed);
+        let is_new = self.last_beat != Some(beat);
+        self.last_beat = Some(beat);
+        is_new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metronome(mode: MetronomeMode) -> Metronome {
+        Metronome::new(&MetronomeConfig {
+            enabled: true,
+            interval_ms: 200,
+            mode,
+            audible: false,
+        })
+    }
+
+    #[test]
+    fn test_keystrokes_held_to_the_pace() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        let mut reject = metronome(MetronomeMode::Reject);
+        assert_eq!(reject.keystroke(at(0)), Pace::InTime);
+        assert_eq!(reject.keystroke(at(150)), Pace::TooFast);
+        // Measured from the key that counted, not the one held back
+        assert_eq!(reject.keystroke(at(200)), Pace::InTime);
+        assert_eq!(reject.keystroke(at(399)), Pace::TooFast);
+
+        let mut flag = metronome(MetronomeMode::Flag);
+        assert_eq!(flag.keystroke(at(0)), Pace::InTime);
+        assert_eq!(flag.keystroke(at(150)), Pace::TooFast);
+        // The flagged key was typed, so the next one is measured from it
+        assert_eq!(flag.keystroke(at(300)), Pace::TooFast);
+
+        flag.reset();
+        assert_eq!(flag.keystroke(at(310)), Pace::InTime);
+    }
+
+    #[test]
+    fn test_beats() {
+        let mut metronome = metronome(MetronomeMode::Reject);
+        assert!(metronome.new_beat(Duration::from_millis(10)));
+        assert!(!metronome.new_beat(Duration::from_millis(199)));
+        assert!(metronome.new_beat(Duration::from_millis(200)));
+        assert_eq!(metronome.beat(Duration::from_millis(1250)), 6);
+    }
+}
@@ -0,0 +1,195 @@
+//! Paths typed into the file prompt
+//!
+//! The prompt takes more than literal paths: a leading `~` is the home directory,
+//! `$VAR` and `${VAR}` are environment variables, and relative paths start at
+//! `[gameplay] workspace_root` when it is set (the current directory otherwise).
+//! Built-in texts (`builtin://`, `cargotap://`) are taken as they are. Tab
+//! completes the directory name being typed.
+
+use anyhow::{Result, bail};
+use std::fs;
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+
+use crate::app::DEMO_CODE_PATH;
+use crate::dogfood::DOGFOOD_PREFIX;
+use crate::samples::SAMPLE_PREFIX;
+
+/// The file a prompt entry refers to, with `~`, variables and the workspace root applied
+pub fn expand(input: &str, workspace_root: Option<&str>) -> Result<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("File path cannot be empty");
+    }
+    if input == DEMO_CODE_PATH
+        || input.starts_with(SAMPLE_PREFIX)
+        || input.starts_with(DOGFOOD_PREFIX)
+    {
+        return Ok(input.to_string());
+    }
+
+    let path = PathBuf::from(expand_home(&expand_vars(input)?)?);
+    if path.is_relative()
+        && let Some(root) = workspace_root.filter(|root| !root.trim().is_empty())
+    {
+        let root = expand_home(&expand_vars(root.trim())?)?;
+        return Ok(Path::new(&root).join(path).to_string_lossy().into_owned());
+    }
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// `input` with the directory name after its last separator completed: fully when
+/// one directory matches, up to what all matches share otherwise. None when there
+/// is nothing to add.
+pub fn complete_dir(input: &str, workspace_root: Option<&str>) -> Option<String> {
+    let (dir_part, partial) = match input.rfind(['/', MAIN_SEPARATOR]) {
+        Some(index) => input.split_at(index + 1),
+        None => ("", input),
+    };
+    let dir = expand(
+        if dir_part.is_empty() { "." } else { dir_part },
+        workspace_root,
+    )
+    .ok()?;
+
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        // Hidden directories only when asked for with a leading dot
+        .filter(|name| {
+            name.starts_with(partial) && (partial.starts_with('.') || !name.starts_with('.'))
+        })
+        .collect();
+    names.sort();
+
+    let completed = match names.as_slice() {
+        [] => return None,
+        [only] => format!("{}/", only),
+        [first, rest @ ..] => rest.iter().fold(first.clone(), |prefix, name| {
+            let shared = prefix
+                .char_indices()
+                .zip(name.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(prefix.len().min(name.len()), |((index, _), _)| index);
+            prefix[..shared].to_string()
+        }),
+    };
+    (completed.len() > partial.len()).then(|| format!("{}{}", dir_part, completed))
+}
+
+/// Replaces `$VAR` and `${VAR}` with the variable's value
+fn expand_vars(input: &str) -> Result<String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(index) = rest.find('$') {
+        out.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, remaining) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => bail!("Missing }} after ${{ in {}", input),
+            }
+        } else {
+            let end = after
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if name.is_empty() {
+            // A lone `$` is part of the file name
+            out.push('$');
+        } else {
+            match std::env::var(name) {
+                Ok(value) => out.push_str(&value),
+                Err(_) => bail!("Environment variable ${} is not set", name),
+            }
+        }
+        rest = remaining;
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Replaces a leading `~` with the home directory
+fn expand_home(input: &str) -> Result<String> {
+    let Some(rest) = input.strip_prefix('~') else {
+        return Ok(input.to_string());
+    };
+    if !(rest.is_empty() || rest.starts_with(['/', MAIN_SEPARATOR])) {
+        bail!("~user paths are not supported, use the full path instead");
+    }
+    let Some(home) = dirs::home_dir() else {
+        bail!("Can't expand ~: the home directory is unknown");
+    };
+    Ok(format!("{}{}", home.to_string_lossy(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().into_owned();
+        assert_eq!(
+            expand("~/code/a.rs", None).unwrap(),
+            format!("{}/code/a.rs", home)
+        );
+        assert_eq!(expand("src/main.rs", None).unwrap(), "src/main.rs");
+        assert_eq!(expand("/tmp/a.rs", Some("/work")).unwrap(), "/tmp/a.rs");
+        assert_eq!(expand("a.rs", Some("/work")).unwrap(), "/work/a.rs");
+        assert_eq!(
+            expand("lib.rs", Some("~/projects")).unwrap(),
+            format!("{}/projects/lib.rs", home)
+        );
+        assert_eq!(
+            expand("builtin://fizzbuzz", Some("/work")).unwrap(),
+            "builtin://fizzbuzz"
+        );
+
+        let var_home = std::env::var("HOME").unwrap();
+        assert_eq!(
+            expand("$HOME/a.rs", None).unwrap(),
+            format!("{}/a.rs", var_home)
+        );
+        assert_eq!(
+            expand("${HOME}x/a.rs", None).unwrap(),
+            format!("{}x/a.rs", var_home)
+        );
+        assert_eq!(expand("price$.rs", None).unwrap(), "price$.rs");
+
+        let unset = expand("$CARGOTAP_SURELY_UNSET/a.rs", None).unwrap_err();
+        assert_eq!(
+            unset.to_string(),
+            "Environment variable $CARGOTAP_SURELY_UNSET is not set"
+        );
+        assert!(expand("~root/a.rs", None).is_err());
+        assert!(expand("  ", None).is_err());
+    }
+
+    #[test]
+    fn test_complete_dir() {
+        let root = std::env::temp_dir().join(format!("cargotap_paths_{}", std::process::id()));
+        for dir in ["src", "scripts", "tests", ".git"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("setup.rs"), "").unwrap();
+        let root_str = root.to_string_lossy().into_owned();
+        let root_opt = Some(root_str.as_str());
+
+        assert_eq!(complete_dir("t", root_opt).as_deref(), Some("tests/"));
+        // Shared prefix of "scripts" and "src", files left out
+        assert_eq!(complete_dir("s", root_opt), None);
+        assert_eq!(complete_dir("sc", root_opt).as_deref(), Some("scripts/"));
+        assert_eq!(complete_dir(".g", root_opt).as_deref(), Some(".git/"));
+        assert_eq!(complete_dir("x", root_opt), None);
+        let absolute = format!("{}/te", root_str);
+        assert_eq!(
+            complete_dir(&absolute, None),
+            Some(format!("{}/tests/", root_str))
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! Pomodoro-style work/break cycles
+//!
+//! Every timed session is a work period. When its timer runs out a break
+//! starts, and when the break is over the next session starts on its own.
+//! Every few cycles the break is a long one.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::PomodoroConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    Break { ends_at: Instant, long: bool },
+}
+
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    settings: PomodoroConfig,
+    phase: Phase,
+    /// Work periods finished since the app started
+    cycles_completed: usize,
+}
+
+impl Pomodoro {
+    pub fn new(settings: PomodoroConfig) -> Self {
+        Self {
+            settings,
+            phase: Phase::Work,
+            cycles_completed: 0,
+        }
+    }
+
+    pub fn work_minutes(&self) -> f64 {
+        self.settings.work_minutes
+    }
+
+    pub fn cycles_completed(&self) -> usize {
+        self.cycles_completed
+    }
+
+    pub fn is_on_break(&self) -> bool {
+        matches!(self.phase, Phase::Break { .. })
+    }
+
+    pub fn is_long_break(&self) -> bool {
+        matches!(self.phase, Phase::Break { long: true, .. })
+    }
+
+    /// Time left in the current break, or None while working
+    pub fn break_remaining(&self) -> Option<Duration> {
+        match self.phase {
+            Phase::Break { ends_at, .. } => Some(ends_at.saturating_duration_since(Instant::now())),
+            Phase::Work => None,
+        }
+    }
+
+    /// Ends a work period and starts the break that follows it
+    pub fn start_break(&mut self) {
+        let minutes = self.start_break_at(Instant::now());
+        log::info!(
+            "🍅 Pomodoro {} done, {:.0} minute break",
+            self.cycles_completed,
+            minutes
+        );
+        self.notify(&format!(
+            "Pomodoro {} done. Take a {:.0} minute break!",
+            self.cycles_completed, minutes
+        ));
+    }
+
+    /// Returns the length of the break in minutes
+    fn start_break_at(&mut self, now: Instant) -> f64 {
+        self.cycles_completed += 1;
+        let every = self.settings.cycles_before_long_break;
+        let long = every > 0 && self.cycles_completed.is_multiple_of(every);
+        let minutes = if long {
+            self.settings.long_break_minutes
+        } else {
+            self.settings.break_minutes
+        };
+
+        self.phase = Phase::Break {
+            ends_at: now + Duration::from_secs_f64(minutes.max(0.0) * 60.0),
+            long,
+        };
+        minutes
+    }
+
+    /// Returns true once when the break is over and work should resume
+    pub fn poll(&mut self) -> bool {
+        self.poll_at(Instant::now())
+    }
+
+    fn poll_at(&mut self, now: Instant) -> bool {
+        match self.phase {
+            Phase::Break { ends_at, .. } if now >= ends_at => {
+                self.phase = Phase::Work;
+                self.notify("Break is over, back to typing!");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Cuts the current break short
+    pub fn skip_break(&mut self) {
+        if self.is_on_break() {
+            self.phase = Phase::Work;
+            log::info!("🍅 Break skipped");
+        }
+    }
+
+    fn notify(&self, body: &str) {
+        if !self.settings.notify {
+            return;
+        }
+
+        let body = body.to_string();
+        thread::spawn(move || {
+            if let Err(e) = notify_rust::Notification::new()
+                .summary("CargoTap")
+                .body(&body)
+                .show()
+            {
+                log::warn!("Couldn't show pomodoro notification: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pomodoro() -> Pomodoro {
+        Pomodoro::new(PomodoroConfig {
+            enabled: true,
+            work_minutes: 25.0,
+            break_minutes: 5.0,
+            long_break_minutes: 15.0,
+            cycles_before_long_break: 2,
+            notify: false,
+        })
+    }
+
+    #[test]
+    fn test_break_then_work() {
+        let mut pomodoro = pomodoro();
+        let start = Instant::now();
+        assert!(!pomodoro.is_on_break());
+
+        pomodoro.start_break_at(start);
+        assert!(pomodoro.is_on_break());
+        assert!(!pomodoro.is_long_break());
+        assert!(!pomodoro.poll_at(start + Duration::from_secs(60)));
+        assert!(pomodoro.poll_at(start + Duration::from_secs(5 * 60)));
+        assert!(!pomodoro.is_on_break());
+        assert!(!pomodoro.poll_at(start + Duration::from_secs(6 * 60)));
+    }
+
+    #[test]
+    fn test_every_nth_break_is_long() {
+        let mut pomodoro = pomodoro();
+        let start = Instant::now();
+
+        pomodoro.start_break_at(start);
+        pomodoro.skip_break();
+        pomodoro.start_break_at(start);
+        assert!(pomodoro.is_long_break());
+        assert_eq!(pomodoro.cycles_completed(), 2);
+        assert!(!pomodoro.poll_at(start + Duration::from_secs(10 * 60)));
+        assert!(pomodoro.poll_at(start + Duration::from_secs(15 * 60)));
+    }
+}
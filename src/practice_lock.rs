@@ -0,0 +1,89 @@
+//! Practice lock
+//!
+//! An opt-in commitment mode: until the day's practice goal is met, quitting asks
+//! for a confirmation phrase instead of closing right away. It is only friction,
+//! not enforcement. Everything stays on this machine and the lock is turned off in
+//! the config like any other setting.
+
+use chrono::NaiveDate;
+
+use crate::config::PracticeLockConfig;
+use crate::session_history::SessionHistory;
+
+/// Minutes of practice recorded on `date`, plus `current_secs` from a running session
+pub fn minutes_practiced(history: &SessionHistory, date: NaiveDate, current_secs: f64) -> f64 {
+    let recorded: f64 = history
+        .get_sessions_on(date)
+        .iter()
+        .map(|session| session.time_elapsed_secs)
+        .sum();
+    (recorded + current_secs) / 60.0
+}
+
+/// Minutes still missing from today's goal, or None when quitting is not locked
+pub fn remaining_minutes(config: &PracticeLockConfig, practiced_minutes: f64) -> Option<f64> {
+    if !config.enabled || config.daily_minutes <= 0.0 {
+        return None;
+    }
+    let remaining = config.daily_minutes - practiced_minutes;
+    (remaining > 0.0).then_some(remaining)
+}
+
+/// Whether `typed` is the confirmation phrase; case and surrounding spaces don't matter
+pub fn phrase_matches(typed: &str, phrase: &str) -> bool {
+    typed.trim().to_lowercase() == phrase.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_history::local_date;
+    use crate::session_state::SessionStats;
+
+    fn session(timestamp: u64, secs: f64) -> SessionStats {
+        let mut stats = SessionStats::new(100, secs, 0, 100, 0, "a.rs".to_string());
+        stats.timestamp = timestamp;
+        stats
+    }
+
+    #[test]
+    fn test_minutes_practiced_counts_only_that_day() {
+        let mut history = SessionHistory::new("test_practice_lock.json");
+        history.add_session(session(1_700_000_000, 300.0));
+        history.add_session(session(1_700_000_060, 120.0));
+        history.add_session(session(1_700_000_000 - 3 * 24 * 3600, 600.0));
+
+        let date = local_date(1_700_000_000).unwrap();
+        assert_eq!(minutes_practiced(&history, date, 60.0), 8.0);
+        assert_eq!(
+            minutes_practiced(&history, date.succ_opt().unwrap(), 0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_remaining_minutes() {
+        let mut config = PracticeLockConfig {
+            enabled: true,
+            daily_minutes: 15.0,
+            ..Default::default()
+        };
+        assert_eq!(remaining_minutes(&config, 5.0), Some(10.0));
+        assert_eq!(remaining_minutes(&config, 15.0), None);
+
+        config.daily_minutes = 0.0;
+        assert_eq!(remaining_minutes(&config, 0.0), None);
+
+        config.daily_minutes = 15.0;
+        config.enabled = false;
+        assert_eq!(remaining_minutes(&config, 0.0), None);
+    }
+
+    #[test]
+    fn test_phrase_matches() {
+        let phrase = "I am choosing to stop early";
+        assert!(phrase_matches("i am choosing to stop early ", phrase));
+        assert!(!phrase_matches("I am choosing to stop", phrase));
+        assert!(!phrase_matches("", phrase));
+    }
+}
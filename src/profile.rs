@@ -0,0 +1,345 @@
+//! User profiles
+//!
+//! Several people can share one machine: each profile keeps its own progress and
+//! session history in `<data dir>/cargo_tap/profiles/<name>/`, and may put a
+//! `config.toml` there whose settings override the shared `config.toml`. Without a
+//! profile everything lives directly in `<data dir>/cargo_tap/` as before.
+//!
+//! The profile is picked once at startup, before the configuration is loaded, from
+//! `--profile NAME`, the `CARGOTAP_PROFILE` environment variable, or a prompt on the
+//! terminal when profiles exist.
+//!
+//! In portable mode (`--portable`, or a `portable.toml` beside the executable) the
+//! data directory and the shared `config.toml` are in `cargotap_data/` beside the
+//! executable instead, so CargoTap can be carried between machines on a USB stick.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const PROFILES_DIR: &str = "profiles";
+const CONFIG_FILE: &str = "config.toml";
+const ENV_VAR: &str = "CARGOTAP_PROFILE";
+
+/// Marker beside the executable that turns on portable mode
+pub const PORTABLE_MARKER: &str = "portable.toml";
+
+/// Data directory of portable mode, beside the executable
+const PORTABLE_DIR: &str = "cargotap_data";
+
+static ACTIVE: OnceLock<String> = OnceLock::new();
+
+/// Data directory of this run when in portable mode
+static PORTABLE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Settings in `PORTABLE_MARKER`; an empty file is fine
+#[derive(Debug, Default, Deserialize)]
+struct PortableMarker {
+    /// Data directory, relative to the executable
+    data_dir: Option<String>,
+}
+
+/// Profile names become directory names, so only plain names are allowed
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if !valid {
+        bail!(
+            "invalid profile name {:?}: use up to 32 letters, digits, '-' or '_'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Makes `name` the profile for the rest of this run
+pub fn select(name: &str) -> Result<()> {
+    validate_name(name)?;
+    if ACTIVE.set(name.to_string()).is_err() {
+        bail!("a profile was already selected");
+    }
+    Ok(())
+}
+
+/// The profile of this run, if any
+pub fn active() -> Option<&'static str> {
+    ACTIVE.get().map(String::as_str)
+}
+
+/// The data directory of portable mode, if this run is in it
+pub fn portable() -> Option<&'static Path> {
+    PORTABLE.get().map(PathBuf::as_path)
+}
+
+/// Where the data of portable mode lives for an executable in `exe_dir`, or None
+/// when portable mode wasn't `requested` and there is no marker file
+fn portable_dir(exe_dir: &Path, requested: bool) -> Result<Option<PathBuf>> {
+    let marker_path = exe_dir.join(PORTABLE_MARKER);
+    let marker = match fs::read_to_string(&marker_path) {
+        Ok(text) => toml::from_str::<PortableMarker>(&text)
+            .with_context(|| format!("invalid {}", marker_path.display()))?,
+        Err(_) if requested => PortableMarker::default(),
+        Err(_) => return Ok(None),
+    };
+    let dir = marker.data_dir.as_deref().unwrap_or(PORTABLE_DIR);
+    Ok(Some(exe_dir.join(dir)))
+}
+
+fn base_dir() -> Option<PathBuf> {
+    match portable() {
+        Some(dir) => Some(dir.to_path_buf()),
+        None => dirs::data_dir().map(|dir| dir.join("cargo_tap")),
+    }
+}
+
+/// Path of a data file shared by all profiles (user quotes, ...)
+pub fn shared_data_file(name: &str) -> PathBuf {
+    data_file_in(base_dir(), None, name)
+}
+
+/// Path of a data file (progress, history, ...) for the active profile
+pub fn data_file(name: &str) -> PathBuf {
+    data_file_in(base_dir(), active(), name)
+}
+
+fn data_file_in(base: Option<PathBuf>, profile: Option<&str>, name: &str) -> PathBuf {
+    match (base, profile) {
+        (Some(base), Some(profile)) => base.join(PROFILES_DIR).join(profile).join(name),
+        (Some(base), None) => base.join(name),
+        (None, Some(profile)) => PathBuf::from(format!("cargo_tap_{}_{}", profile, name)),
+        (None, None) => PathBuf::from(format!("cargo_tap_{}", name)),
+    }
+}
+
+/// Profile settings layered over the shared config.toml
+pub fn config_overrides() -> Option<PathBuf> {
+    active().map(|_| data_file(CONFIG_FILE))
+}
+
+/// The config.toml all profiles share: in the working directory, or in the data
+/// directory in portable mode
+pub fn shared_config_path() -> PathBuf {
+    match portable() {
+        Some(dir) => dir.join(CONFIG_FILE),
+        None => PathBuf::from(CONFIG_FILE),
+    }
+}
+
+/// Where settings changed from inside the app are saved
+pub fn config_path() -> PathBuf {
+    config_overrides().unwrap_or_else(shared_config_path)
+}
+
+/// Names of the existing profiles, sorted
+pub fn list() -> Vec<String> {
+    let Some(base) = base_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(base.join(PROFILES_DIR)) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| validate_name(name).is_ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Picks the data directory and profile for this run and returns `args` without
+/// `--portable` and `--profile NAME`
+pub fn choose(args: Vec<String>) -> Result<Vec<String>> {
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut requested = None;
+    let mut portable_requested = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            match args.next() {
+                Some(name) => requested = Some(name),
+                None => bail!("--profile needs a name"),
+            }
+        } else if arg == "--portable" {
+            portable_requested = true;
+        } else {
+            remaining.push(arg);
+        }
+    }
+
+    // Decided first: the profiles to choose from live in the data directory
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    match exe_dir {
+        Some(exe_dir) => {
+            if let Some(dir) = portable_dir(&exe_dir, portable_requested)? {
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("can't create {}", dir.display()))?;
+                let _ = PORTABLE.set(dir);
+            }
+        }
+        None if portable_requested => bail!("--portable: can't tell where the executable is"),
+        None => {}
+    }
+
+    let requested = requested.or_else(|| std::env::var(ENV_VAR).ok());
+    // Only ask when starting the trainer itself, never for subcommands or from scripts
+    let interactive = remaining.iter().skip(1).all(|arg| arg.starts_with("--"))
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal();
+
+    let name = match requested {
+        Some(name) => Some(name),
+        None if interactive => prompt(&list())?,
+        None => None,
+    };
+    if let Some(name) = name {
+        select(&name)?;
+    }
+    Ok(remaining)
+}
+
+fn prompt(profiles: &[String]) -> Result<Option<String>> {
+    if profiles.is_empty() {
+        return Ok(None);
+    }
+
+    let choices: Vec<String> = profiles
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{}) {}", i + 1, name))
+        .collect();
+    println!("Profiles: {}", choices.join("  "));
+
+    let stdin = io::stdin();
+    loop {
+        print!("Choose a profile (number or name, Enter for the shared default): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        if let Some(name) = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| profiles.get(number.wrapping_sub(1)))
+            .or_else(|| profiles.iter().find(|name| *name == input))
+        {
+            return Ok(Some(name.clone()));
+        }
+        println!("No profile {:?}", input);
+    }
+}
+
+/// Runs the `profiles` subcommand: lists profiles, or creates one with `add NAME`
+pub fn run(args: &[String]) -> Result<()> {
+    match args {
+        [] => {
+            let profiles = list();
+            if profiles.is_empty() {
+                println!("No profiles yet. Create one with: cargo run profiles add NAME");
+            }
+            for name in profiles {
+                println!(
+                    "{}  ({})",
+                    name,
+                    data_file_in(base_dir(), Some(&name), "").display()
+                );
+            }
+            Ok(())
+        }
+        [command, name] if command == "add" => {
+            validate_name(name)?;
+            let Some(base) = base_dir() else {
+                bail!("this system has no data directory to keep profiles in");
+            };
+            let dir = data_file_in(Some(base), Some(name), "");
+            fs::create_dir_all(&dir)?;
+            println!("✓ Profile {} created in {}", name, dir.display());
+            println!(
+                "  Put a config.toml there to override settings, then start with --profile {}",
+                name
+            );
+            Ok(())
+        }
+        _ => bail!("usage: profiles [add NAME]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("alice").is_ok());
+        assert!(validate_name("kid-2_b").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../bob").is_err());
+        assert!(validate_name("a b").is_err());
+        assert!(validate_name(&"x".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn test_portable_dir() {
+        let exe_dir =
+            std::env::temp_dir().join(format!("cargotap_portable_{}", std::process::id()));
+        fs::create_dir_all(&exe_dir).unwrap();
+
+        assert_eq!(portable_dir(&exe_dir, false).unwrap(), None);
+        assert_eq!(
+            portable_dir(&exe_dir, true).unwrap(),
+            Some(exe_dir.join("cargotap_data"))
+        );
+
+        let marker = exe_dir.join(PORTABLE_MARKER);
+        fs::write(&marker, "").unwrap();
+        assert_eq!(
+            portable_dir(&exe_dir, false).unwrap(),
+            Some(exe_dir.join("cargotap_data"))
+        );
+        fs::write(&marker, "data_dir = \"../data\"\n").unwrap();
+        assert_eq!(
+            portable_dir(&exe_dir, false).unwrap(),
+            Some(exe_dir.join("../data"))
+        );
+        fs::write(&marker, "data_dir = 3\n").unwrap();
+        assert!(portable_dir(&exe_dir, false).is_err());
+
+        fs::remove_dir_all(&exe_dir).unwrap();
+    }
+
+    #[test]
+    fn test_data_file_paths() {
+        let base = Some(PathBuf::from("/data/cargo_tap"));
+        assert_eq!(
+            data_file_in(base.clone(), None, "progress.json"),
+            PathBuf::from("/data/cargo_tap/progress.json")
+        );
+        assert_eq!(
+            data_file_in(base, Some("alice"), "progress.json"),
+            PathBuf::from("/data/cargo_tap/profiles/alice/progress.json")
+        );
+        assert_eq!(
+            data_file_in(None, Some("alice"), "progress.json"),
+            PathBuf::from("cargo_tap_alice_progress.json")
+        );
+        assert_eq!(
+            data_file_in(None, None, "session_history.json"),
+            PathBuf::from("cargo_tap_session_history.json")
+        );
+    }
+}
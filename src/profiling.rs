@@ -3,7 +3,12 @@
 //! This module provides simple utilities for measuring and tracking performance
 //! of different parts of the application.
 
-use std::time::Instant;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 /// A simple timer for measuring execution time of code blocks
 pub struct ScopedTimer {
@@ -128,21 +133,190 @@ impl PerfCounter {
         self.max_ms = 0.0;
     }
 
+    /// "Name - avg: ..., min: ..., max: ..., samples: N"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} - avg: {:.3}ms, min: {:.3}ms, max: {:.3}ms, samples: {}",
+            self.name,
+            self.avg_ms(),
+            self.min_ms(),
+            self.max_ms(),
+            self.count
+        )
+    }
+
     /// Print a summary report
     pub fn report(&self) {
         if self.count > 0 {
+            log::info!("[PERF] {}", self.summary());
+        }
+    }
+}
+
+/// Keystrokes kept for latency percentiles
+pub const LATENCY_WINDOW: usize = 200;
+
+/// Keystrokes needed before the median latency is trusted for the slow-input warning
+pub const LATENCY_MIN_SAMPLES: usize = 20;
+
+/// Rolling window of per-keystroke latencies, for percentiles
+#[derive(Debug, Clone, Default)]
+pub struct LatencySamples {
+    samples_ms: VecDeque<f64>,
+}
+
+impl LatencySamples {
+    pub fn record(&mut self, latency_ms: f64) {
+        if self.samples_ms.len() == LATENCY_WINDOW {
+            self.samples_ms.pop_front();
+        }
+        self.samples_ms.push_back(latency_ms);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples_ms.len()
+    }
+
+    /// Nearest-rank percentile (`percent` in 0..=100), None before the first sample
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.samples_ms.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.samples_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (percent.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+
+    /// Print a p50/p95/p99 summary
+    pub fn report(&self) {
+        if let (Some(p50), Some(p95), Some(p99)) = (
+            self.percentile(50.0),
+            self.percentile(95.0),
+            self.percentile(99.0),
+        ) {
             log::info!(
-                "[PERF] {} - avg: {:.3}ms, min: {:.3}ms, max: {:.3}ms, samples: {}",
-                self.name,
-                self.avg_ms(),
-                self.min_ms(),
-                self.max_ms(),
-                self.count
+                "[PERF] Key Latency - p50: {:.3}ms, p95: {:.3}ms, p99: {:.3}ms, samples: {}",
+                p50,
+                p95,
+                p99,
+                self.len()
             );
         }
     }
 }
 
+/// Over-budget text updates in a row before layout is degraded another step
+pub const BUDGET_OVERRUN_STREAK: usize = 3;
+
+/// Text updates in a row taking under half the budget before a step is undone
+pub const BUDGET_RECOVERY_STREAK: usize = 200;
+
+/// How far text layout is cut back to stay within its time budget; each step
+/// keeps the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Degradation {
+    #[default]
+    None,
+    /// The rainbow effects line isn't drawn
+    NoEffects,
+    /// Fewer lines of code are laid out
+    ShortWindow,
+    /// Keystrokes only relayout every so often, the rest waits for idle polls
+    DeferredLayout,
+}
+
+impl Degradation {
+    fn worse(self) -> Self {
+        match self {
+            Degradation::None => Degradation::NoEffects,
+            Degradation::NoEffects => Degradation::ShortWindow,
+            Degradation::ShortWindow | Degradation::DeferredLayout => Degradation::DeferredLayout,
+        }
+    }
+
+    fn better(self) -> Self {
+        match self {
+            Degradation::None | Degradation::NoEffects => Degradation::None,
+            Degradation::ShortWindow => Degradation::NoEffects,
+            Degradation::DeferredLayout => Degradation::ShortWindow,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Degradation::None => "full layout",
+            Degradation::NoEffects => "rainbow effects off",
+            Degradation::ShortWindow => "fewer code lines shown",
+            Degradation::DeferredLayout => "relayout deferred while typing",
+        }
+    }
+}
+
+/// Time budget for a text update, degrading the layout while it is overrun
+///
+/// A few slow updates in a row (huge files, weak hardware) move one step down;
+/// a long run of fast ones moves back up, so a single hiccup changes nothing.
+#[derive(Debug, Clone, Default)]
+pub struct TextBudget {
+    /// 0 disables the budget
+    budget_ms: f64,
+    level: Degradation,
+    over_streak: usize,
+    under_streak: usize,
+}
+
+impl TextBudget {
+    pub fn new(budget_ms: f64) -> Self {
+        Self {
+            budget_ms,
+            ..Default::default()
+        }
+    }
+
+    pub fn budget_ms(&self) -> f64 {
+        self.budget_ms
+    }
+
+    pub fn level(&self) -> Degradation {
+        self.level
+    }
+
+    /// Records how long a text update took; returns the new level when it changed
+    pub fn record(&mut self, update_ms: f64) -> Option<Degradation> {
+        if self.budget_ms <= 0.0 {
+            return None;
+        }
+        if update_ms > self.budget_ms {
+            self.under_streak = 0;
+            self.over_streak += 1;
+            if self.over_streak >= BUDGET_OVERRUN_STREAK {
+                self.over_streak = 0;
+                return self.change(self.level.worse());
+            }
+        } else {
+            self.over_streak = 0;
+            if update_ms < self.budget_ms / 2.0 {
+                self.under_streak += 1;
+                if self.under_streak >= BUDGET_RECOVERY_STREAK {
+                    self.under_streak = 0;
+                    return self.change(self.level.better());
+                }
+            } else {
+                self.under_streak = 0;
+            }
+        }
+        None
+    }
+
+    fn change(&mut self, level: Degradation) -> Option<Degradation> {
+        (level != self.level).then(|| {
+            self.level = level;
+            level
+        })
+    }
+}
+
 /// A collection of performance counters
 #[derive(Debug, Default)]
 pub struct PerfStats {
@@ -151,6 +325,10 @@ pub struct PerfStats {
     pub text_update: PerfCounter,
     pub ui_generation: PerfCounter,
     pub render: PerfCounter,
+    /// Time from receiving a key event to the end of the text update it caused
+    pub key_latency: LatencySamples,
+    /// Text updates whose layout matched the previous one, so nothing was uploaded
+    pub skipped_text_uploads: usize,
 }
 
 impl PerfStats {
@@ -162,9 +340,22 @@ impl PerfStats {
             text_update: PerfCounter::new("Text Update"),
             ui_generation: PerfCounter::new("UI Generation"),
             render: PerfCounter::new("Render"),
+            key_latency: LatencySamples::default(),
+            skipped_text_uploads: 0,
         }
     }
 
+    /// Median key latency, if enough keystrokes were seen and it is above `threshold_ms`
+    /// (0 disables the check)
+    pub fn slow_input(&self, threshold_ms: f64) -> Option<f64> {
+        if threshold_ms <= 0.0 || self.key_latency.len() < LATENCY_MIN_SAMPLES {
+            return None;
+        }
+        self.key_latency
+            .percentile(50.0)
+            .filter(|&median| median > threshold_ms)
+    }
+
     /// Print a full performance report
     pub fn report_all(&self) {
         log::info!("=== Performance Report ===");
@@ -173,6 +364,12 @@ impl PerfStats {
         self.text_update.report();
         self.ui_generation.report();
         self.render.report();
+        self.key_latency.report();
+        log::info!(
+            "[PERF] Skipped Text Uploads - {} of {} updates were unchanged",
+            self.skipped_text_uploads,
+            self.text_update.count()
+        );
     }
 
     /// Reset all counters
@@ -182,6 +379,8 @@ impl PerfStats {
         self.text_update.reset();
         self.ui_generation.reset();
         self.render.reset();
+        self.key_latency = LatencySamples::default();
+        self.skipped_text_uploads = 0;
     }
 }
 
@@ -206,12 +405,350 @@ where
     result
 }
 
+/// Frames shown in the frame-time graph
+pub const FRAME_HISTORY_LEN: usize = 240;
+
+/// Frame budgets at 60 Hz and 120 Hz, drawn as lines across the graph
+pub const FRAME_BUDGETS_MS: [f64; 2] = [1000.0 / 60.0, 1000.0 / 120.0];
+
+/// Height of one graph row; rows start on budget lines
+pub const GRAPH_ROW_MS: f64 = 1000.0 / 120.0;
+
+/// Eighth blocks used for the partially filled top of a bar
+const BAR_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Rolling window of recent frame times
+#[derive(Debug, Default)]
+pub struct FrameTimeHistory {
+    samples_ms: VecDeque<f64>,
+}
+
+impl FrameTimeHistory {
+    pub fn record(&mut self, frame_ms: f64) {
+        if self.samples_ms.len() == FRAME_HISTORY_LEN {
+            self.samples_ms.pop_front();
+        }
+        self.samples_ms.push_back(frame_ms);
+    }
+
+    /// Frame times in milliseconds, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = f64> + '_ {
+        self.samples_ms.iter().copied()
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.samples().fold(0.0, f64::max)
+    }
+
+    /// Number of frames that took longer than `budget_ms`
+    pub fn over_budget(&self, budget_ms: f64) -> usize {
+        self.samples().filter(|&ms| ms > budget_ms).count()
+    }
+}
+
+/// Stutter snapshots are appended to this file in the data directory
+pub const STUTTER_FILE: &str = "stutters.log";
+
+/// Input actions kept for stutter snapshots
+pub const STUTTER_RECENT_ACTIONS: usize = 20;
+
+/// Frame times listed in a stutter snapshot, newest last
+const STUTTER_RECENT_FRAMES: usize = 30;
+
+/// What the text system drew at the time of a snapshot
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RenderStats {
+    /// Glyph records on the GPU, shadow copies included; six vertices each
+    pub glyph_records: usize,
+    /// Characters rasterized into the atlas
+    pub atlas_glyphs: usize,
+    /// Side of the square atlas texture in pixels
+    pub atlas_size: u32,
+}
+
+/// Spots frames over a threshold and decides when they are worth a diagnostic
+/// snapshot, at most one per `min_interval` so a slow stretch doesn't flood the log
+#[derive(Debug)]
+pub struct StutterMonitor {
+    threshold_ms: f64,
+    min_interval: Duration,
+    last_capture: Option<Instant>,
+    actions: VecDeque<(Instant, String)>,
+}
+
+impl StutterMonitor {
+    pub fn new(threshold_ms: f64, min_interval: Duration) -> Self {
+        Self {
+            threshold_ms,
+            min_interval,
+            last_capture: None,
+            actions: VecDeque::with_capacity(STUTTER_RECENT_ACTIONS),
+        }
+    }
+
+    /// Remembers an input action for the next snapshot
+    pub fn record_action(&mut self, action: String) {
+        if self.actions.len() == STUTTER_RECENT_ACTIONS {
+            self.actions.pop_front();
+        }
+        self.actions.push_back((Instant::now(), action));
+    }
+
+    /// True when a frame of `frame_ms` ending at `now` should be captured; the
+    /// capture counts towards the rate limit right away
+    pub fn should_capture(&mut self, frame_ms: f64, now: Instant) -> bool {
+        if self.threshold_ms <= 0.0 || frame_ms < self.threshold_ms {
+            return false;
+        }
+        if self
+            .last_capture
+            .is_some_and(|last| now.duration_since(last) < self.min_interval)
+        {
+            return false;
+        }
+        self.last_capture = Some(now);
+        true
+    }
+
+    /// Diagnostic snapshot of a stutter of `frame_ms`, for the log and stutters.log
+    ///
+    /// `context` lines describe what the app was doing (file, screen, layout).
+    pub fn report(
+        &self,
+        frame_ms: f64,
+        frames: &FrameTimeHistory,
+        stats: &PerfStats,
+        render: Option<RenderStats>,
+        context: &[String],
+    ) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "=== Stutter: {:.1}ms frame (threshold {:.0}ms) at {} ===",
+            frame_ms,
+            self.threshold_ms,
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f")
+        );
+        for line in context {
+            let _ = writeln!(out, "{}", line);
+        }
+
+        let samples: Vec<f64> = frames.samples().collect();
+        let recent: Vec<String> = samples[samples.len().saturating_sub(STUTTER_RECENT_FRAMES)..]
+            .iter()
+            .map(|ms| format!("{:.1}", ms))
+            .collect();
+        let _ = writeln!(out, "Last frames (ms): {}", recent.join(" "));
+        for counter in [
+            &stats.frame_time,
+            &stats.key_processing,
+            &stats.text_update,
+            &stats.ui_generation,
+            &stats.render,
+        ] {
+            let _ = writeln!(out, "{}", counter.summary());
+        }
+        if let (Some(p50), Some(p99)) = (
+            stats.key_latency.percentile(50.0),
+            stats.key_latency.percentile(99.0),
+        ) {
+            let _ = writeln!(out, "Key latency - p50: {:.3}ms, p99: {:.3}ms", p50, p99);
+        }
+        match render {
+            Some(render) => {
+                let _ = writeln!(
+                    out,
+                    "Glyphs: {} records ({} vertices), atlas {}x{} with {} glyphs",
+                    render.glyph_records,
+                    render.glyph_records * 6,
+                    render.atlas_size,
+                    render.atlas_size,
+                    render.atlas_glyphs
+                );
+            }
+            None => {
+                let _ = writeln!(out, "Glyphs: text system not available");
+            }
+        }
+
+        let now = Instant::now();
+        let actions: Vec<String> = self
+            .actions
+            .iter()
+            .map(|(at, action)| {
+                format!("-{:.2}s {}", now.duration_since(*at).as_secs_f64(), action)
+            })
+            .collect();
+        if actions.is_empty() {
+            let _ = writeln!(out, "Recent actions: none");
+        } else {
+            let _ = writeln!(out, "Recent actions: {}", actions.join(", "));
+        }
+        out
+    }
+}
+
+/// Appends a stutter `report` to the file at `path`
+pub fn append_report(report: &str, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", report)
+}
+
+/// The character a bar of `frame_ms` shows in graph row `row` (0 is the bottom row),
+/// or None if the bar doesn't reach that row
+pub fn bar_cell(frame_ms: f64, row: usize) -> Option<char> {
+    let fill = (frame_ms / GRAPH_ROW_MS - row as f64).min(1.0);
+    if fill <= 0.0 {
+        return None;
+    }
+    let level = ((fill * 8.0).ceil() as usize).clamp(1, 8);
+    Some(BAR_BLOCKS[level - 1])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::thread;
     use std::time::Duration;
 
+    #[test]
+    fn test_frame_history_is_bounded() {
+        let mut history = FrameTimeHistory::default();
+        for i in 0..FRAME_HISTORY_LEN + 10 {
+            history.record(i as f64);
+        }
+        assert_eq!(history.samples().count(), FRAME_HISTORY_LEN);
+        assert_eq!(history.samples().next(), Some(10.0));
+        assert_eq!(history.max_ms(), (FRAME_HISTORY_LEN + 9) as f64);
+        assert_eq!(history.over_budget(FRAME_HISTORY_LEN as f64), 9);
+    }
+
+    #[test]
+    fn test_bar_cells() {
+        // A frame right on the 120 Hz budget fills exactly the bottom row
+        assert_eq!(bar_cell(GRAPH_ROW_MS, 0), Some('█'));
+        assert_eq!(bar_cell(GRAPH_ROW_MS, 1), None);
+
+        // Half of the second row
+        assert_eq!(bar_cell(GRAPH_ROW_MS * 1.5, 1), Some('▄'));
+        // Any frame at all shows up
+        assert_eq!(bar_cell(0.01, 0), Some('▁'));
+        assert_eq!(bar_cell(0.0, 0), None);
+        // Long stalls are clipped to full cells
+        assert_eq!(bar_cell(500.0, 3), Some('█'));
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut latency = LatencySamples::default();
+        assert_eq!(latency.percentile(50.0), None);
+
+        for ms in (1..=100).rev() {
+            latency.record(ms as f64);
+        }
+        assert_eq!(latency.percentile(50.0), Some(50.0));
+        assert_eq!(latency.percentile(95.0), Some(95.0));
+        assert_eq!(latency.percentile(100.0), Some(100.0));
+        assert_eq!(latency.percentile(0.0), Some(1.0));
+
+        for _ in 0..LATENCY_WINDOW {
+            latency.record(2.0);
+        }
+        assert_eq!(latency.len(), LATENCY_WINDOW);
+        assert_eq!(latency.percentile(99.0), Some(2.0));
+    }
+
+    #[test]
+    fn test_slow_input_needs_enough_samples() {
+        let mut stats = PerfStats::new();
+        for _ in 0..LATENCY_MIN_SAMPLES - 1 {
+            stats.key_latency.record(40.0);
+        }
+        assert_eq!(stats.slow_input(16.0), None);
+
+        stats.key_latency.record(40.0);
+        assert_eq!(stats.slow_input(16.0), Some(40.0));
+        assert_eq!(stats.slow_input(50.0), None);
+        assert_eq!(stats.slow_input(0.0), None);
+    }
+
+    #[test]
+    fn test_text_budget_degrades_and_recovers() {
+        let mut budget = TextBudget::new(2.0);
+        // A single slow update is tolerated
+        assert_eq!(budget.record(5.0), None);
+        assert_eq!(budget.record(1.0), None);
+        for _ in 0..BUDGET_OVERRUN_STREAK - 1 {
+            assert_eq!(budget.record(5.0), None);
+        }
+        assert_eq!(budget.record(5.0), Some(Degradation::NoEffects));
+        for _ in 0..BUDGET_OVERRUN_STREAK * 3 {
+            budget.record(5.0);
+        }
+        assert_eq!(budget.level(), Degradation::DeferredLayout);
+
+        for _ in 0..BUDGET_RECOVERY_STREAK - 1 {
+            assert_eq!(budget.record(0.5), None);
+        }
+        assert_eq!(budget.record(0.5), Some(Degradation::ShortWindow));
+
+        let mut disabled = TextBudget::new(0.0);
+        for _ in 0..BUDGET_OVERRUN_STREAK {
+            assert_eq!(disabled.record(50.0), None);
+        }
+    }
+
+    #[test]
+    fn test_stutter_capture_is_rate_limited() {
+        let mut monitor = StutterMonitor::new(50.0, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(!monitor.should_capture(20.0, start));
+        assert!(monitor.should_capture(80.0, start));
+        assert!(!monitor.should_capture(80.0, start + Duration::from_secs(10)));
+        assert!(monitor.should_capture(80.0, start + Duration::from_secs(61)));
+
+        let mut disabled = StutterMonitor::new(0.0, Duration::ZERO);
+        assert!(!disabled.should_capture(500.0, start));
+    }
+
+    #[test]
+    fn test_stutter_report() {
+        let mut monitor = StutterMonitor::new(50.0, Duration::from_secs(60));
+        for i in 0..STUTTER_RECENT_ACTIONS + 5 {
+            monitor.record_action(format!("Action{}", i));
+        }
+        let mut frames = FrameTimeHistory::default();
+        frames.record(8.0);
+        frames.record(75.5);
+        let render = RenderStats {
+            glyph_records: 100,
+            atlas_glyphs: 90,
+            atlas_size: 512,
+        };
+        let report = monitor.report(
+            75.5,
+            &frames,
+            &PerfStats::new(),
+            Some(render),
+            &["File: main.rs".to_string()],
+        );
+
+        assert!(report.starts_with("=== Stutter: 75.5ms frame (threshold 50ms)"));
+        assert!(report.contains("\nFile: main.rs\n"));
+        assert!(report.contains("Last frames (ms): 8.0 75.5\n"));
+        assert!(report.contains("Text Update - avg"));
+        assert!(
+            report.contains("Glyphs: 100 records (600 vertices), atlas 512x512 with 90 glyphs")
+        );
+        // Only the latest actions are kept
+        assert!(!report.contains("Action4,"));
+        assert!(report.contains("Action5,"));
+        assert!(report.contains("Action24\n"));
+    }
+
     #[test]
     fn test_perf_counter() {
         let mut counter = PerfCounter::new("test");
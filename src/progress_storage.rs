@@ -9,6 +9,9 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::license_header::Header;
+use crate::line_diff::LineAnchor;
+
 /// Represents the progress for a single file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileProgress {
@@ -24,6 +27,59 @@ pub struct FileProgress {
     /// Optional: timestamp of last access
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_accessed: Option<u64>,
+    /// Encoding the file had on disk; the hash is taken over the decoded text,
+    /// so a re-encoded file must not silently reuse the old position
+    #[serde(default = "default_encoding")]
+    pub encoding: String,
+    /// Hash of the content with all whitespace removed, so reformatting the
+    /// file (e.g. with rustfmt) doesn't lose the saved position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_hash: Option<String>,
+    /// Number of non-whitespace characters before the saved position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub significant_offset: Option<usize>,
+    /// Short note shown in the file picker (e.g. "resume at parser section")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Display overrides for this file (zoom, wrapping, line numbers)
+    #[serde(default, skip_serializing_if = "ViewPreferences::is_default")]
+    pub view: ViewPreferences,
+    /// License header at the start of the file that was skipped rather than typed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped_header: Option<Header>,
+    /// The typed lines, to find the position again after the file was edited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_anchor: Option<LineAnchor>,
+}
+
+/// Per-file display overrides; unset fields fall back to the `[text]` config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewPreferences {
+    /// Font size multiplier applied to `text.font_size`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f32>,
+    /// Overrides `text.wrap_lines`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrap_lines: Option<bool>,
+    /// Overrides `text.show_line_numbers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_line_numbers: Option<bool>,
+    /// Overrides `text.fold_typed`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fold_typed: Option<bool>,
+    /// Overrides `text.show_whitespace`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_whitespace: Option<bool>,
+}
+
+impl ViewPreferences {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_encoding() -> String {
+    "UTF-8".to_string()
 }
 
 impl FileProgress {
@@ -35,9 +91,22 @@ impl FileProgress {
             position,
             scroll_offset: 0,
             last_accessed: None,
+            encoding: default_encoding(),
+            normalized_hash: None,
+            significant_offset: None,
+            note: None,
+            view: ViewPreferences::default(),
+            skipped_header: None,
+            line_anchor: None,
         }
     }
 
+    /// False for an entry that only holds a note or view overrides, made for a
+    /// file before any of it was typed; such an entry has no content hash
+    pub fn has_position(&self) -> bool {
+        !self.content_hash.is_empty()
+    }
+
     /// Creates a new FileProgress with scroll offset
     pub fn with_scroll_offset(
         file_path: String,
@@ -51,6 +120,13 @@ impl FileProgress {
             position,
             scroll_offset,
             last_accessed: None,
+            encoding: default_encoding(),
+            normalized_hash: None,
+            significant_offset: None,
+            note: None,
+            view: ViewPreferences::default(),
+            skipped_header: None,
+            line_anchor: None,
         }
     }
 
@@ -67,6 +143,13 @@ impl FileProgress {
             position,
             scroll_offset: 0,
             last_accessed: Some(timestamp),
+            encoding: default_encoding(),
+            normalized_hash: None,
+            significant_offset: None,
+            note: None,
+            view: ViewPreferences::default(),
+            skipped_header: None,
+            line_anchor: None,
         }
     }
 
@@ -84,8 +167,47 @@ impl FileProgress {
             position,
             scroll_offset,
             last_accessed: Some(timestamp),
+            encoding: default_encoding(),
+            normalized_hash: None,
+            significant_offset: None,
+            note: None,
+            view: ViewPreferences::default(),
+            skipped_header: None,
+            line_anchor: None,
         }
     }
+
+    /// Checks whether this progress still applies to content with the given hash and encoding
+    pub fn matches(&self, content_hash: &str, encoding: &str) -> bool {
+        self.content_hash == content_hash && self.encoding == encoding
+    }
+
+    /// Finds the saved position in `content` if it only differs in whitespace
+    /// from the text the progress was saved for
+    pub fn remap(&self, content: &str, encoding: &str) -> Option<usize> {
+        if self.encoding != encoding {
+            return None;
+        }
+        let normalized_hash = self.normalized_hash.as_deref()?;
+        let significant_offset = self.significant_offset?;
+        if compute_normalized_hash(content) != normalized_hash {
+            return None;
+        }
+        Some(position_for_significant_offset(content, significant_offset))
+    }
+}
+
+/// Size and position of the window in physical pixels, restored on the next start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub width: u32,
+    pub height: u32,
+    /// Outer position; None where the platform doesn't tell (Wayland)
+    #[serde(default)]
+    pub position: Option<[i32; 2]>,
+    /// The window was fullscreen; the size and position are from before that
+    #[serde(default)]
+    pub fullscreen: bool,
 }
 
 /// Storage manager for file progress
@@ -96,6 +218,9 @@ pub struct ProgressStorage {
     /// Last opened file path
     #[serde(default)]
     pub last_opened_file: Option<String>,
+    /// Where the window was when CargoTap last closed
+    #[serde(default)]
+    pub window: Option<WindowGeometry>,
     /// Path to the storage file
     #[serde(skip)]
     storage_path: PathBuf,
@@ -107,19 +232,15 @@ impl ProgressStorage {
         Self {
             progress_map: HashMap::new(),
             last_opened_file: None,
+            window: None,
             storage_path: storage_path.as_ref().to_path_buf(),
         }
     }
 
     /// Creates a ProgressStorage with the default storage path
-    /// Default: Uses data directory or falls back to current directory
+    /// Default: Uses the active profile's data directory or falls back to current directory
     pub fn default() -> Self {
-        let storage_path = if let Some(data_dir) = dirs::data_dir() {
-            data_dir.join("cargo_tap").join("progress.json")
-        } else {
-            PathBuf::from("cargo_tap_progress.json")
-        };
-        Self::new(storage_path)
+        Self::new(crate::profile::data_file("progress.json"))
     }
 
     /// Loads progress from disk
@@ -135,6 +256,7 @@ impl ProgressStorage {
 
         self.progress_map = loaded.progress_map;
         self.last_opened_file = loaded.last_opened_file;
+        self.window = loaded.window;
         Ok(())
     }
 
@@ -143,6 +265,7 @@ impl ProgressStorage {
         let storage_data = serde_json::json!({
             "progress_map": self.progress_map,
             "last_opened_file": self.last_opened_file,
+            "window": self.window,
         });
 
         let json = serde_json::to_string_pretty(&storage_data)
@@ -157,10 +280,61 @@ impl ProgressStorage {
         Ok(())
     }
 
+    /// Stores progress for a file, keeping the note and view preferences of its previous entry.
+    /// Progress without a timestamp is stamped now, so the file counts as recently opened.
+    fn insert_progress(&mut self, mut progress: FileProgress) {
+        progress.last_accessed.get_or_insert_with(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+        if let Some(previous) = self.progress_map.get_mut(&progress.file_path) {
+            progress.note = previous.note.take();
+            progress.view = previous.view;
+        }
+        self.progress_map
+            .insert(progress.file_path.clone(), progress);
+    }
+
+    /// Attaches a note to a file, creating an entry if it has no progress yet;
+    /// an empty note removes it
+    pub fn set_note(&mut self, file_path: &str, note: &str) {
+        let note = note.trim();
+        let progress = self
+            .progress_map
+            .entry(file_path.to_string())
+            .or_insert_with(|| FileProgress::new(file_path.to_string(), String::new(), 0));
+        progress.note = (!note.is_empty()).then(|| note.to_string());
+    }
+
+    /// Gets the note attached to a file, if any
+    pub fn get_note(&self, file_path: &str) -> Option<&str> {
+        self.progress_map.get(file_path)?.note.as_deref()
+    }
+
+    /// Stores display overrides for a file, creating an entry without a position
+    /// (see `FileProgress::has_position`) if it has no progress yet
+    pub fn set_view(&mut self, file_path: &str, view: ViewPreferences) {
+        let progress = self
+            .progress_map
+            .entry(file_path.to_string())
+            .or_insert_with(|| FileProgress::new(file_path.to_string(), String::new(), 0));
+        progress.view = view;
+    }
+
+    /// Gets the display overrides for a file (all unset if it has none)
+    pub fn get_view(&self, file_path: &str) -> ViewPreferences {
+        self.progress_map
+            .get(file_path)
+            .map(|progress| progress.view)
+            .unwrap_or_default()
+    }
+
     /// Saves or updates progress for a file
     pub fn save_progress(&mut self, file_path: String, content_hash: String, position: usize) {
         let progress = FileProgress::new(file_path.clone(), content_hash, position);
-        self.progress_map.insert(file_path, progress);
+        self.insert_progress(progress);
     }
 
     /// Saves or updates progress with scroll offset
@@ -177,7 +351,44 @@ impl ProgressStorage {
             position,
             scroll_offset,
         );
-        self.progress_map.insert(file_path, progress);
+        self.insert_progress(progress);
+    }
+
+    /// Saves or updates progress with scroll offset and the file's original encoding
+    pub fn save_progress_with_scroll_offset_and_encoding(
+        &mut self,
+        file_path: String,
+        content_hash: String,
+        position: usize,
+        scroll_offset: usize,
+        encoding: String,
+    ) {
+        self.save_progress_with_scroll_offset(
+            file_path.clone(),
+            content_hash,
+            position,
+            scroll_offset,
+        );
+        if let Some(progress) = self.progress_map.get_mut(&file_path) {
+            progress.encoding = encoding;
+        }
+    }
+
+    /// Records where the saved position is in whitespace-insensitive terms and
+    /// line by line, so it survives formatting changes and edits to the file
+    pub fn set_normalized_anchor(&mut self, file_path: &str, content: &str, position: usize) {
+        if let Some(progress) = self.progress_map.get_mut(file_path) {
+            progress.normalized_hash = Some(compute_normalized_hash(content));
+            progress.significant_offset = Some(significant_offset(content, position));
+            progress.line_anchor = LineAnchor::new(content, position);
+        }
+    }
+
+    /// Records the license header that was skipped in a file with saved progress
+    pub fn set_skipped_header(&mut self, file_path: &str, header: Option<Header>) {
+        if let Some(progress) = self.progress_map.get_mut(file_path) {
+            progress.skipped_header = header;
+        }
     }
 
     /// Saves or updates progress with timestamp
@@ -190,7 +401,7 @@ impl ProgressStorage {
     ) {
         let progress =
             FileProgress::with_timestamp(file_path.clone(), content_hash, position, timestamp);
-        self.progress_map.insert(file_path, progress);
+        self.insert_progress(progress);
     }
 
     /// Saves or updates progress with scroll offset and timestamp
@@ -209,12 +420,15 @@ impl ProgressStorage {
             scroll_offset,
             timestamp,
         );
-        self.progress_map.insert(file_path, progress);
+        self.insert_progress(progress);
     }
 
-    /// Gets progress for a specific file
+    /// Gets progress for a specific file; None for a file that only has a note
+    /// or view overrides, so it isn't marked as started or resumed
     pub fn get_progress(&self, file_path: &str) -> Option<&FileProgress> {
-        self.progress_map.get(file_path)
+        self.progress_map
+            .get(file_path)
+            .filter(|progress| progress.has_position())
     }
 
     /// Removes progress for a specific file
@@ -227,9 +441,12 @@ impl ProgressStorage {
         self.progress_map.clear();
     }
 
-    /// Returns the number of files being tracked
+    /// Returns the number of files with saved progress
     pub fn count(&self) -> usize {
-        self.progress_map.len()
+        self.progress_map
+            .values()
+            .filter(|progress| progress.has_position())
+            .count()
     }
 
     /// Checks if a file's content has changed by comparing hashes
@@ -255,6 +472,29 @@ impl ProgressStorage {
     pub fn get_last_opened_file(&self) -> Option<&String> {
         self.last_opened_file.as_ref()
     }
+
+    pub fn set_window_geometry(&mut self, geometry: WindowGeometry) {
+        self.window = Some(geometry);
+    }
+
+    pub fn window_geometry(&self) -> Option<WindowGeometry> {
+        self.window
+    }
+
+    /// Up to `limit` files with saved progress, most recently opened first
+    pub fn recent_files(&self, limit: usize) -> Vec<&str> {
+        let mut files: Vec<_> = self
+            .progress_map
+            .values()
+            .filter_map(|progress| Some((progress.last_accessed?, progress.file_path.as_str())))
+            .collect();
+        files.sort_by(|a, b| b.cmp(a));
+        files
+            .into_iter()
+            .map(|(_, path)| path)
+            .take(limit)
+            .collect()
+    }
 }
 
 /// Utility function to compute SHA256 hash of a string
@@ -267,12 +507,64 @@ pub fn compute_hash(content: &str) -> String {
     format!("{:x}", hasher.finish())
 }
 
+/// Hashes content with all whitespace removed, so reformatting keeps the same hash
+pub fn compute_normalized_hash(content: &str) -> String {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    for ch in content.chars().filter(|c| !c.is_whitespace()) {
+        ch.hash(&mut hasher);
+    }
+    format!("{:x}", hasher.finish())
+}
+
+/// Counts the non-whitespace characters before byte `position`
+pub fn significant_offset(content: &str, position: usize) -> usize {
+    content
+        .char_indices()
+        .take_while(|(index, _)| *index < position)
+        .filter(|(_, ch)| !ch.is_whitespace())
+        .count()
+}
+
+/// Byte position just after the `offset`-th non-whitespace character
+pub fn position_for_significant_offset(content: &str, offset: usize) -> usize {
+    if offset == 0 {
+        return 0;
+    }
+    content
+        .char_indices()
+        .filter(|(_, ch)| !ch.is_whitespace())
+        .nth(offset - 1)
+        .map_or(content.len(), |(index, ch)| index + ch.len_utf8())
+}
+
 /// Utility function to compute SHA256 hash of a file
 pub fn compute_file_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
     let content = fs::read_to_string(path)?;
     Ok(compute_hash(&content))
 }
 
+/// Hashes a file's raw bytes without loading it whole (for streamed files)
+pub fn compute_stream_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finish()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -396,9 +688,145 @@ mod tests {
 
             let progress = storage.get_progress("file2.txt").unwrap();
             assert_eq!(progress.position, 200);
+            assert_eq!(storage.window_geometry(), None);
         }
 
         // Clean up
         let _ = fs::remove_file(temp_path);
     }
+
+    #[test]
+    fn test_window_geometry_round_trip() {
+        let temp_path = "/tmp/test_progress_window.json";
+        let geometry = WindowGeometry {
+            width: 1600,
+            height: 900,
+            position: Some([-1600, 40]),
+            fullscreen: true,
+        };
+        let mut storage = ProgressStorage::new(temp_path);
+        storage.set_window_geometry(geometry);
+        storage.save().expect("Failed to save");
+
+        let mut loaded = ProgressStorage::new(temp_path);
+        loaded.load().expect("Failed to load");
+        assert_eq!(loaded.window_geometry(), Some(geometry));
+        let _ = fs::remove_file(temp_path);
+    }
+
+    #[test]
+    fn test_progress_matches_encoding() {
+        let mut storage = ProgressStorage::new("/tmp/test_progress_encoding.json");
+        storage.save_progress_with_scroll_offset_and_encoding(
+            "legacy.c".to_string(),
+            "hash1".to_string(),
+            10,
+            2,
+            "windows-1252".to_string(),
+        );
+
+        let progress = storage.get_progress("legacy.c").unwrap();
+        assert!(progress.matches("hash1", "windows-1252"));
+        assert!(!progress.matches("hash1", "UTF-8"));
+        assert!(!progress.matches("hash2", "windows-1252"));
+
+        let old_entry: FileProgress =
+            serde_json::from_str(r#"{"file_path": "a.rs", "content_hash": "h", "position": 1}"#)
+                .unwrap();
+        assert_eq!(old_entry.encoding, "UTF-8");
+    }
+
+    #[test]
+    fn test_progress_survives_reformatting() {
+        let original = "fn main() {\n    let x=1;\n    println!(\"{}\", x);\n}\n";
+        let formatted = "fn main() {\n    let x = 1;\n\n    println!(\"{}\", x);\n}\n";
+        let position = original.find("println").unwrap();
+
+        let mut storage = ProgressStorage::new("/tmp/test_progress_reformat.json");
+        storage.save_progress("main.rs".to_string(), compute_hash(original), position);
+        storage.set_normalized_anchor("main.rs", original, position);
+
+        let progress = storage.get_progress("main.rs").unwrap();
+        assert!(!progress.matches(&compute_hash(formatted), "UTF-8"));
+        let remapped = progress.remap(formatted, "UTF-8").unwrap();
+        // Lands right after the last character typed before the cursor
+        assert_eq!(&formatted[..remapped], "fn main() {\n    let x = 1;");
+        assert!(formatted[remapped..].trim_start().starts_with("println"));
+
+        assert!(progress.remap("fn main() {}", "UTF-8").is_none());
+        assert!(progress.remap(formatted, "windows-1252").is_none());
+    }
+
+    #[test]
+    fn test_significant_offsets_round_trip() {
+        let text = "a b\n  c";
+        assert_eq!(significant_offset(text, 0), 0);
+        assert_eq!(significant_offset(text, text.len()), 3);
+        assert_eq!(position_for_significant_offset(text, 0), 0);
+        assert_eq!(position_for_significant_offset(text, 2), 3);
+        assert_eq!(position_for_significant_offset(text, 3), text.len());
+        assert_eq!(position_for_significant_offset(text, 10), text.len());
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let mut storage = ProgressStorage::new("/tmp/test_progress_recent.json");
+        storage.save_progress_with_timestamp("old.rs".to_string(), "a".to_string(), 1, 100);
+        storage.save_progress_with_timestamp("new.rs".to_string(), "b".to_string(), 2, 300);
+        storage.save_progress_with_timestamp("mid.rs".to_string(), "c".to_string(), 3, 200);
+        // Only opened files count, not ones that merely have a note
+        storage.set_note("noted.rs", "later");
+        assert_eq!(storage.recent_files(2), ["new.rs", "mid.rs"]);
+
+        storage.save_progress("old.rs".to_string(), "a".to_string(), 5);
+        assert_eq!(storage.recent_files(10), ["old.rs", "new.rs", "mid.rs"]);
+    }
+
+    #[test]
+    fn test_note_survives_progress_updates() {
+        let mut storage = ProgressStorage::new("/tmp/test_progress_note.json");
+        storage.set_note("lib.rs", "  resume at parser section ");
+        assert_eq!(storage.get_note("lib.rs"), Some("resume at parser section"));
+        // A note alone doesn't make the file count as started
+        assert!(storage.get_progress("lib.rs").is_none());
+        assert_eq!(storage.count(), 0);
+
+        storage.save_progress_with_scroll_offset("lib.rs".to_string(), "hash".to_string(), 50, 2);
+        assert_eq!(storage.get_note("lib.rs"), Some("resume at parser section"));
+        assert_eq!(storage.get_progress("lib.rs").unwrap().position, 50);
+
+        storage.set_note("lib.rs", "");
+        assert_eq!(storage.get_note("lib.rs"), None);
+    }
+
+    #[test]
+    fn test_view_preferences_survive_progress_updates() {
+        let mut storage = ProgressStorage::new("/tmp/test_progress_view.json");
+        assert!(storage.get_view("wide.rs").is_default());
+
+        let view = ViewPreferences {
+            zoom: Some(0.7),
+            wrap_lines: Some(false),
+            show_line_numbers: None,
+            fold_typed: Some(true),
+            show_whitespace: None,
+        };
+        storage.set_view("wide.rs", view);
+        assert_eq!(storage.get_view("wide.rs"), view);
+        assert!(storage.get_progress("wide.rs").is_none());
+        storage.save_progress("wide.rs".to_string(), "hash".to_string(), 10);
+        assert_eq!(storage.get_view("wide.rs"), view);
+
+        let json = serde_json::to_string(storage.get_progress("wide.rs").unwrap()).unwrap();
+        assert!(json.contains("\"zoom\":0.7"));
+        assert!(json.contains("\"fold_typed\":true"));
+        assert!(!json.contains("show_line_numbers"));
+    }
+
+    #[test]
+    fn test_view_preferences_default_when_missing() {
+        let json = r#"{"file_path":"a.rs","content_hash":"h","position":3}"#;
+        let progress: FileProgress = serde_json::from_str(json).unwrap();
+        assert!(progress.view.is_default());
+    }
 }
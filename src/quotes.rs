@@ -0,0 +1,207 @@
+//! Quote database for quote practice mode
+//!
+//! Quotes come from a bundled JSON list plus an optional user file with the
+//! same format (`[{ "text": "...", "author": "..." }]`), which is appended to
+//! the bundled set.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Source name recorded in session history for quote sessions
+pub const QUOTE_SOURCE: &str = "quote";
+
+const BUNDLED_QUOTES: &str = include_str!("quotes.json");
+
+/// A single quote with its attribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub text: String,
+    pub author: String,
+}
+
+/// Length class used to pick quotes
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteLength {
+    Short,
+    #[default]
+    Medium,
+    Long,
+    Any,
+}
+
+impl QuoteLength {
+    /// Checks whether a quote with `len` characters belongs to this class
+    pub fn matches(self, len: usize) -> bool {
+        match self {
+            QuoteLength::Short => len <= 60,
+            QuoteLength::Medium => (61..=150).contains(&len),
+            QuoteLength::Long => len > 150,
+            QuoteLength::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QuoteDatabase {
+    quotes: Vec<Quote>,
+}
+
+impl QuoteDatabase {
+    /// Loads the bundled quotes plus the user quotes file, if present
+    pub fn load(user_path: Option<&Path>) -> Self {
+        let mut quotes: Vec<Quote> = match serde_json::from_str(BUNDLED_QUOTES) {
+            Ok(quotes) => quotes,
+            Err(e) => {
+                log::error!("Failed to parse bundled quotes: {}", e);
+                Vec::new()
+            }
+        };
+
+        let user_path = user_path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(default_user_quotes_path);
+        match load_user_quotes(&user_path) {
+            Ok(user_quotes) => {
+                if !user_quotes.is_empty() {
+                    log::info!(
+                        "Loaded {} user quotes from {:?}",
+                        user_quotes.len(),
+                        user_path
+                    );
+                }
+                quotes.extend(user_quotes);
+            }
+            Err(e) => log::warn!("Failed to load user quotes from {:?}: {}", user_path, e),
+        }
+
+        Self { quotes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.quotes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quotes.is_empty()
+    }
+
+    /// Picks a random quote of the requested length, falling back to any
+    /// quote when no quote of that length exists
+    pub fn pick<R: Rng + ?Sized>(&self, length: QuoteLength, rng: &mut R) -> Option<&Quote> {
+        let matching: Vec<&Quote> = self
+            .quotes
+            .iter()
+            .filter(|quote| length.matches(quote.text.chars().count()))
+            .collect();
+
+        if matching.is_empty() {
+            self.quotes.choose(rng)
+        } else {
+            matching.choose(rng).copied()
+        }
+    }
+}
+
+/// Default location of the user quotes file
+pub fn default_user_quotes_path() -> PathBuf {
+    crate::profile::shared_data_file("quotes.json")
+}
+
+fn load_user_quotes(path: &Path) -> io::Result<Vec<Quote>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn quote(text: &str) -> Quote {
+        Quote {
+            text: text.to_string(),
+            author: "Someone".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_bundled_quotes_parse() {
+        let quotes: Vec<Quote> = serde_json::from_str(BUNDLED_QUOTES).unwrap();
+        assert!(!quotes.is_empty());
+        assert!(
+            quotes
+                .iter()
+                .all(|q| !q.text.is_empty() && !q.author.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_length_classes() {
+        assert!(QuoteLength::Short.matches(20));
+        assert!(!QuoteLength::Short.matches(100));
+        assert!(QuoteLength::Medium.matches(100));
+        assert!(QuoteLength::Long.matches(200));
+        assert!(QuoteLength::Any.matches(0));
+    }
+
+    #[test]
+    fn test_pick_respects_length() {
+        let db = QuoteDatabase {
+            quotes: vec![quote("short one"), quote(&"x".repeat(200))],
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..10 {
+            let picked = db.pick(QuoteLength::Long, &mut rng).unwrap();
+            assert_eq!(picked.text.len(), 200);
+        }
+    }
+
+    #[test]
+    fn test_pick_falls_back_to_any_length() {
+        let db = QuoteDatabase {
+            quotes: vec![quote("short one")],
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        assert_eq!(
+            db.pick(QuoteLength::Long, &mut rng).unwrap().text,
+            "short one"
+        );
+    }
+
+    #[test]
+    fn test_same_seed_picks_the_same_quote() {
+        let db = QuoteDatabase::load(None);
+        for seed in [0, 7, 12345] {
+            let first = db.pick(QuoteLength::Any, &mut StdRng::seed_from_u64(seed));
+            let second = db.pick(QuoteLength::Any, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(
+                first.map(|q| &q.text),
+                second.map(|q| &q.text),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_user_quotes_are_appended() {
+        let path = std::env::temp_dir().join("cargotap_test_user_quotes.json");
+        fs::write(&path, r#"[{"text": "Mine", "author": "Me"}]"#).unwrap();
+
+        let db = QuoteDatabase::load(Some(&path));
+        let bundled: Vec<Quote> = serde_json::from_str(BUNDLED_QUOTES).unwrap();
+        assert_eq!(db.len(), bundled.len() + 1);
+
+        let _ = fs::remove_file(&path);
+    }
+}
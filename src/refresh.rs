@@ -0,0 +1,94 @@
+//! Refresh rate of the window's monitor and the pace of HUD updates
+//!
+//! The session timer and HUD are laid out again every few refreshes of the monitor
+//! the window is on (`[window] hud_refresh_divisor`), so they tick in step with what
+//! the display can show: 10 times a second at 60 Hz with the default of 6, more
+//! often on faster monitors. A higher divisor saves power. The rate is read again
+//! when the window moves, as it may have moved to another monitor. When the system
+//! doesn't report it, `target_fps` (or 60 Hz) is assumed.
+
+use std::time::Duration;
+
+/// Assumed when neither the monitor nor `target_fps` gives a rate
+const FALLBACK_HZ: f64 = 60.0;
+
+/// Rates outside this range are taken as bogus reports
+const MIN_HZ: f64 = 20.0;
+const MAX_HZ: f64 = 1000.0;
+
+#[derive(Debug, Clone)]
+pub struct RefreshSchedule {
+    /// Rate reported for the monitor the window is on
+    detected_hz: Option<f64>,
+    /// Rate used while none is reported
+    fallback_hz: f64,
+    /// HUD updates happen every this many refreshes
+    divisor: u32,
+}
+
+impl RefreshSchedule {
+    pub fn new(divisor: u32, target_fps: u32) -> Self {
+        Self {
+            detected_hz: None,
+            fallback_hz: if target_fps > 0 {
+                target_fps as f64
+            } else {
+                FALLBACK_HZ
+            },
+            divisor: divisor.max(1),
+        }
+    }
+
+    /// Takes the rate reported for the window's monitor. Returns true when it
+    /// differs from the one used so far.
+    pub fn set_detected(&mut self, hz: Option<f64>) -> bool {
+        let hz = hz.filter(|hz| (MIN_HZ..=MAX_HZ).contains(hz));
+        let changed = hz != self.detected_hz;
+        self.detected_hz = hz;
+        changed
+    }
+
+    pub fn detected_hz(&self) -> Option<f64> {
+        self.detected_hz
+    }
+
+    /// The rate updates are aligned to: the detected one, or the assumed one
+    pub fn rate_hz(&self) -> f64 {
+        self.detected_hz.unwrap_or(self.fallback_hz)
+    }
+
+    /// Time between two refreshes of the monitor
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.rate_hz())
+    }
+
+    /// Time between two HUD and timer updates
+    pub fn hud_interval(&self) -> Duration {
+        Duration::from_secs_f64(self.divisor as f64 / self.rate_hz())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hud_interval_follows_the_monitor() {
+        let mut schedule = RefreshSchedule::new(6, 60);
+        assert_eq!(schedule.hud_interval().as_millis(), 100);
+
+        assert!(schedule.set_detected(Some(144.0)));
+        assert_eq!(schedule.rate_hz(), 144.0);
+        assert_eq!(schedule.hud_interval().as_micros(), 41_666);
+        assert!(!schedule.set_detected(Some(144.0)));
+
+        // Moved to a monitor that doesn't say
+        assert!(schedule.set_detected(None));
+        assert_eq!(schedule.rate_hz(), 60.0);
+        assert!(!schedule.set_detected(Some(0.0)));
+
+        let unlimited = RefreshSchedule::new(0, 0);
+        assert_eq!(unlimited.rate_hz(), FALLBACK_HZ);
+        assert_eq!(unlimited.hud_interval(), unlimited.frame_interval());
+    }
+}
@@ -0,0 +1,165 @@
+//! Daily practice reminder
+//!
+//! While the app is open, a desktop notification is shown once the configured
+//! time of day has passed and no session has been recorded today. On Linux the
+//! notification offers a "remind me later" action that snoozes the reminder.
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::session_history::SessionHistory;
+
+/// How often the scheduler looks at the clock
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Action identifier for the snooze button
+const SNOOZE_ACTION: &str = "snooze";
+
+/// Parses a time of day like "19:30"
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").ok()
+}
+
+pub struct ReminderScheduler {
+    time: NaiveTime,
+    snooze: TimeDelta,
+    /// Day the reminder was last shown, so it fires at most once per day
+    last_shown: Option<NaiveDate>,
+    snoozed_until: Option<NaiveDateTime>,
+    last_poll: Option<Instant>,
+    snooze_tx: Sender<()>,
+    snooze_rx: Receiver<()>,
+}
+
+impl ReminderScheduler {
+    pub fn new(time: NaiveTime, snooze_minutes: u32) -> Self {
+        let (snooze_tx, snooze_rx) = channel();
+        Self {
+            time,
+            snooze: TimeDelta::minutes(snooze_minutes as i64),
+            last_shown: None,
+            snoozed_until: None,
+            last_poll: None,
+            snooze_tx,
+            snooze_rx,
+        }
+    }
+
+    /// Decides whether the reminder should be shown at `now`
+    fn is_due(&self, now: NaiveDateTime, practiced_today: bool) -> bool {
+        if practiced_today {
+            return false;
+        }
+
+        if let Some(until) = self.snoozed_until {
+            return now >= until;
+        }
+
+        now.time() >= self.time && self.last_shown != Some(now.date())
+    }
+
+    fn mark_shown(&mut self, now: NaiveDateTime) {
+        self.last_shown = Some(now.date());
+        self.snoozed_until = None;
+    }
+
+    fn snooze(&mut self, now: NaiveDateTime) {
+        self.snoozed_until = Some(now + self.snooze);
+    }
+
+    /// Checks the clock and shows the reminder when it is due; call this from the event loop
+    pub fn poll(&mut self, history: &SessionHistory) {
+        if self
+            .last_poll
+            .is_some_and(|last| last.elapsed() < POLL_INTERVAL)
+        {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let now = Local::now().naive_local();
+        if self.snooze_rx.try_iter().count() > 0 {
+            log::info!("⏰ Practice reminder snoozed");
+            self.snooze(now);
+        }
+
+        let practiced_today = !history.get_sessions_on(now.date()).is_empty();
+        if self.is_due(now, practiced_today) {
+            self.mark_shown(now);
+            show_notification(self.snooze, self.snooze_tx.clone());
+        }
+    }
+}
+
+fn show_notification(snooze: TimeDelta, snooze_tx: Sender<()>) {
+    let snooze_label = if snooze.num_minutes() == 60 {
+        "Remind me in an hour".to_string()
+    } else {
+        format!("Remind me in {} minutes", snooze.num_minutes())
+    };
+
+    // Waiting for the user's choice blocks, so keep it off the render loop
+    thread::spawn(move || {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .summary("CargoTap")
+            .body("You haven't practiced today. Time for a quick session?")
+            .action(SNOOZE_ACTION, &snooze_label);
+
+        match notification.show() {
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Ok(handle) => handle.wait_for_action(|action| {
+                if action == SNOOZE_ACTION {
+                    let _ = snooze_tx.send(());
+                }
+            }),
+            #[cfg(not(all(unix, not(target_os = "macos"))))]
+            Ok(_) => drop(snooze_tx),
+            Err(e) => log::warn!("Couldn't show practice reminder: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_time(parse_time(time).unwrap())
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("19:30"), NaiveTime::from_hms_opt(19, 30, 0));
+        assert_eq!(parse_time(" 07:05 "), NaiveTime::from_hms_opt(7, 5, 0));
+        assert!(parse_time("25:00").is_none());
+        assert!(parse_time("evening").is_none());
+    }
+
+    #[test]
+    fn test_fires_once_per_day_after_time() {
+        let mut scheduler = ReminderScheduler::new(parse_time("19:00").unwrap(), 60);
+        assert!(!scheduler.is_due(at(1, "18:59"), false));
+        assert!(scheduler.is_due(at(1, "19:00"), false));
+        assert!(!scheduler.is_due(at(1, "19:00"), true));
+
+        scheduler.mark_shown(at(1, "19:00"));
+        assert!(!scheduler.is_due(at(1, "22:00"), false));
+        assert!(scheduler.is_due(at(2, "19:10"), false));
+    }
+
+    #[test]
+    fn test_snooze_delays_reminder() {
+        let mut scheduler = ReminderScheduler::new(parse_time("19:00").unwrap(), 60);
+        scheduler.mark_shown(at(1, "19:00"));
+        scheduler.snooze(at(1, "19:05"));
+
+        assert!(!scheduler.is_due(at(1, "19:30"), false));
+        assert!(scheduler.is_due(at(1, "20:05"), false));
+        assert!(!scheduler.is_due(at(1, "20:05"), true));
+    }
+}
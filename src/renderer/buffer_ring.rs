@@ -0,0 +1,148 @@
+//! Reusable GPU storage for data that changes every few frames
+//!
+//! Creating a new buffer on every text update allocates constantly during long
+//! sessions. A ring keeps a few host-visible buffers and writes each update into
+//! the next one the GPU has finished with, so frames still in flight keep reading
+//! the data they were recorded with.
+//!
+//! Whether the GPU is done with a buffer comes from the frame fences: vulkano holds
+//! a read lock on every buffer a submitted frame uses until that frame's fence has
+//! signaled and `cleanup_finished` ran, and `Subbuffer::write` fails until then.
+//!
+//! Each slot remembers what was last written to it, and a buffer that is reused only
+//! gets the elements that differ. A keystroke changes a few rows of text, so most of
+//! the buffer is left as it is.
+
+use anyhow::Result;
+use std::sync::Arc;
+use vulkano::{
+    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
+    memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
+};
+
+/// Enough buffers for the frame being recorded plus two in flight
+pub const DEFAULT_SLOTS: usize = 3;
+
+/// Smallest buffer allocated, in elements, so small screens don't grow the buffer step by step
+const MIN_CAPACITY: u64 = 1024;
+
+pub struct BufferRing<T: BufferContents + Copy + PartialEq> {
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    usage: BufferUsage,
+    slots: Vec<Option<Subbuffer<[T]>>>,
+    /// What each slot's buffer holds, so a reuse only writes what changed
+    written: Vec<Vec<T>>,
+    next: usize,
+}
+
+impl<T: BufferContents + Copy + PartialEq> BufferRing<T> {
+    pub fn new(
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        usage: BufferUsage,
+        slots: usize,
+    ) -> Self {
+        Self {
+            memory_allocator,
+            usage,
+            slots: vec![None; slots.max(1)],
+            written: vec![Vec::new(); slots.max(1)],
+            next: 0,
+        }
+    }
+
+    /// Copies `items` into a buffer the GPU isn't reading and returns the part holding them
+    pub fn upload(&mut self, items: &[T]) -> Result<Subbuffer<[T]>> {
+        let len = items.len() as u64;
+
+        // Slot to (re)allocate if no existing buffer can take the items
+        let mut replace = self.next;
+
+        // Prefer the oldest slot; it is the one most likely to be finished
+        for offset in 0..self.slots.len() {
+            let index = (self.next + offset) % self.slots.len();
+            let Some(buffer) = &self.slots[index] else {
+                replace = index;
+                continue;
+            };
+            if buffer.len() < len {
+                replace = index;
+                continue;
+            }
+
+            let Ok(mut guard) = buffer.write() else {
+                // Still used by a frame in flight
+                continue;
+            };
+            write_changed(&mut guard[..items.len()], &self.written[index], items);
+            drop(guard);
+            let written = &mut self.written[index];
+            written.clear();
+            written.extend_from_slice(items);
+
+            self.next = (index + 1) % self.slots.len();
+            return Ok(buffer.clone().slice(0..len));
+        }
+
+        // Nothing free is big enough: replace an empty or too small slot, or else the
+        // oldest one. Frames still using the old buffer keep it alive until they finish.
+        let index = replace;
+        let buffer = self.allocate(len.max(1).next_power_of_two().max(MIN_CAPACITY))?;
+        buffer.write()?[..items.len()].copy_from_slice(items);
+        log::debug!(
+            "Allocated buffer for {} elements in slot {}",
+            buffer.len(),
+            index
+        );
+
+        self.slots[index] = Some(buffer.clone());
+        self.written[index] = items.to_vec();
+        self.next = (index + 1) % self.slots.len();
+        Ok(buffer.slice(0..len))
+    }
+
+    fn allocate(&self, capacity: u64) -> Result<Subbuffer<[T]>> {
+        Ok(Buffer::new_slice(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: self.usage,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            capacity,
+        )?)
+    }
+}
+
+/// Writes the `items` that differ from what `dst` held, `written`; returns how many
+fn write_changed<T: Copy + PartialEq>(dst: &mut [T], written: &[T], items: &[T]) -> usize {
+    let mut changed = 0;
+    for (index, (slot, item)) in dst.iter_mut().zip(items).enumerate() {
+        if written.get(index) != Some(item) {
+            *slot = *item;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_changed_elements_are_written() {
+        let written = [1, 2, 3];
+        let mut dst = written;
+        assert_eq!(write_changed(&mut dst, &written, &[1, 9, 3]), 1);
+        assert_eq!(dst, [1, 9, 3]);
+
+        // Past what was written before, everything is new
+        let mut dst = [1, 2, 3, 0, 0];
+        assert_eq!(write_changed(&mut dst, &written, &[1, 2, 3, 4, 5]), 2);
+        assert_eq!(dst, [1, 2, 3, 4, 5]);
+    }
+}
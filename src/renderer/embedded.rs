@@ -0,0 +1,53 @@
+use anyhow::{Result, bail};
+use std::sync::Arc;
+use vulkano::{
+    device::Device,
+    image::{Image, ImageUsage, view::ImageView},
+    pipeline::{GraphicsPipeline, PipelineLayout, graphics::viewport::Viewport},
+};
+
+use super::pipeline;
+
+/// A caller-owned image the renderer draws into instead of a swapchain image
+pub struct ImageTarget {
+    pub view: Arc<ImageView>,
+    pub viewport: Viewport,
+    pub pipeline: Arc<GraphicsPipeline>,
+    pub text_pipeline: Arc<GraphicsPipeline>,
+    pub text_pipeline_layout: Arc<PipelineLayout>,
+}
+
+impl ImageTarget {
+    /// Creates the view and pipelines for drawing into `image`.
+    ///
+    /// The image must be a 2D color image created with `COLOR_ATTACHMENT` usage.
+    pub fn new(device: &Arc<Device>, image: Arc<Image>) -> Result<Self> {
+        if !image.usage().intersects(ImageUsage::COLOR_ATTACHMENT) {
+            bail!("target image must be created with COLOR_ATTACHMENT usage");
+        }
+
+        let format = image.format();
+        let [width, height, _] = image.extent();
+        let view = ImageView::new_default(image)?;
+
+        let pipeline = pipeline::create_graphics_pipeline(device.clone(), format)?;
+        let (text_pipeline, text_pipeline_layout) =
+            pipeline::create_text_pipeline(device.clone(), format)?;
+
+        Ok(Self {
+            view,
+            viewport: Viewport {
+                offset: [0.0, 0.0],
+                extent: [width as f32, height as f32],
+                depth_range: 0.0..=1.0,
+            },
+            pipeline,
+            text_pipeline,
+            text_pipeline_layout,
+        })
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        self.viewport.extent
+    }
+}
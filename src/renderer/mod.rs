@@ -1,15 +1,17 @@
-use std::sync::Arc;
+use anyhow::{Context, Result};
+use std::sync::{Arc, mpsc::Receiver};
 use vulkano::{
     Validated, VulkanError,
     buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{
-        AutoCommandBufferBuilder, CommandBufferUsage, RenderingAttachmentInfo, RenderingInfo,
-        allocator::StandardCommandBufferAllocator,
+        AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+        RenderingAttachmentInfo, RenderingInfo, allocator::StandardCommandBufferAllocator,
     },
     device::{Device, Queue},
+    image::{Image, view::ImageView},
     instance::Instance,
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
-    pipeline::{GraphicsPipeline, PipelineLayout},
+    pipeline::{GraphicsPipeline, PipelineLayout, graphics::viewport::Viewport},
     render_pass::{AttachmentLoadOp, AttachmentStoreOp},
     swapchain::SwapchainPresentInfo,
     sync::{self, GpuFuture},
@@ -18,31 +20,80 @@ use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
     event_loop::{ActiveEventLoop, EventLoop},
-    window::WindowId,
+    window::{Fullscreen, WindowId},
 };
 
+use crate::progress_storage::WindowGeometry;
+use crate::text::TextFrame;
+
+pub mod buffer_ring;
+mod embedded;
 mod pipeline;
 mod swapchain;
 mod vulkan_init;
 
+use embedded::ImageTarget;
 use pipeline::MyVertex;
 use swapchain::RenderContext;
 
+/// How the window opens
+#[derive(Debug, Clone)]
+pub struct WindowOptions {
+    pub title: String,
+    /// Size in logical pixels, used when there is no saved geometry
+    pub size: [u32; 2],
+    /// Where the window was when CargoTap last closed
+    pub saved: Option<WindowGeometry>,
+}
+
 pub struct VulkanRenderer {
-    instance: Arc<Instance>,
+    /// Only set when the renderer owns a window; embedded renderers borrow a device instead
+    instance: Option<Arc<Instance>>,
     pub device: Arc<Device>,
     pub queue: Arc<Queue>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     vertex_buffer: Subbuffer<[MyVertex]>,
     rcx: Option<RenderContext>,
+    target: Option<ImageTarget>,
     pub memory_allocator: Arc<StandardMemoryAllocator>,
-    text_system: Option<Arc<std::sync::Mutex<crate::text::TextSystem>>>,
+    /// Frames sent by the app's text system
+    text_frames: Option<Receiver<TextFrame>>,
+    /// Latest frame received, drawn until a newer one arrives
+    text_frame: Option<TextFrame>,
+    /// Alpha of the background each frame is cleared to
+    opacity: f32,
+    /// The window is hidden (the daemon waiting to be opened), so no frames are drawn
+    hidden: bool,
+    /// Title and placement of the window; winit's defaults when not set
+    window_options: Option<WindowOptions>,
+    /// Size and position the window had before it went fullscreen
+    windowed: Option<WindowGeometry>,
+    /// Why the window couldn't be opened; the event loop exits when this is set
+    startup_error: Option<anyhow::Error>,
 }
 
 impl VulkanRenderer {
-    pub fn new(event_loop: &EventLoop<()>) -> Self {
-        let (instance, device, queue) = vulkan_init::initialize_vulkan(event_loop);
+    pub fn new(event_loop: &EventLoop<()>) -> Result<Self> {
+        let (instance, device, queue) = vulkan_init::initialize_vulkan(event_loop)?;
+        Ok(Self::with_device(Some(instance), device, queue))
+    }
 
+    /// Creates a renderer on a device owned by the caller. It never opens a window;
+    /// frames are drawn into the image given to `set_target` by `render_to_target`.
+    pub fn from_device(device: Arc<Device>, queue: Arc<Queue>) -> Self {
+        Self::with_device(None, device, queue)
+    }
+
+    /// Creates a device and queue that can render offscreen without a window system
+    pub fn headless_device() -> Result<(Arc<Device>, Arc<Queue>)> {
+        vulkan_init::initialize_headless()
+    }
+
+    fn with_device(
+        instance: Option<Arc<Instance>>,
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+    ) -> Self {
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
 
         let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
@@ -85,17 +136,102 @@ impl VulkanRenderer {
             command_buffer_allocator,
             vertex_buffer,
             rcx: None,
+            target: None,
             memory_allocator,
-            text_system: None,
+            text_frames: None,
+            text_frame: None,
+            opacity: 1.0,
+            hidden: false,
+            window_options: None,
+            windowed: None,
+            startup_error: None,
+        }
+    }
+
+    /// Shows or hides the window; before it exists, whether it starts hidden
+    pub fn set_window_visible(&mut self, visible: bool) {
+        self.hidden = !visible;
+        if let Some(rcx) = &self.rcx {
+            rcx.window.set_visible(visible);
+            if visible {
+                rcx.window.focus_window();
+            }
+        }
+    }
+
+    /// Sets how the window opens; only takes effect before it is created
+    pub fn set_window_options(&mut self, options: WindowOptions) {
+        self.window_options = Some(options);
+    }
+
+    /// Switches the window between borderless fullscreen on its monitor and the
+    /// size and position it had before
+    pub fn toggle_fullscreen(&mut self) {
+        let Some(rcx) = &self.rcx else {
+            return;
+        };
+        if rcx.window.fullscreen().is_some() {
+            rcx.window.set_fullscreen(None);
+        } else {
+            self.windowed = Some(swapchain::window_geometry(&rcx.window));
+            rcx.window
+                .set_fullscreen(Some(Fullscreen::Borderless(None)));
         }
     }
 
-    pub fn set_text_system(&mut self, text_system: Arc<std::sync::Mutex<crate::text::TextSystem>>) {
-        self.text_system = Some(text_system);
+    /// Size and position to open the window at next time; while fullscreen, the
+    /// ones from before it went fullscreen
+    pub fn window_geometry(&self) -> Option<WindowGeometry> {
+        let rcx = self.rcx.as_ref()?;
+        if rcx.window.fullscreen().is_some() {
+            return self.windowed.map(|geometry| WindowGeometry {
+                fullscreen: true,
+                ..geometry
+            });
+        }
+        let geometry = swapchain::window_geometry(&rcx.window);
+        // A minimized window reports no size on some platforms
+        (geometry.width > 0 && geometry.height > 0).then_some(geometry)
+    }
+
+    pub fn is_window_visible(&self) -> bool {
+        !self.hidden
+    }
+
+    /// Sets how opaque the background is; what is behind the window shows through below 1.0
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Draws the text frames that arrive on `frames` from now on
+    pub fn set_text_frames(&mut self, frames: Receiver<TextFrame>) {
+        self.text_frames = Some(frames);
+        self.text_frame = None;
+    }
+
+    /// Takes the newest frame waiting in the channel. Older ones were never drawn
+    /// and are dropped, so the queue stays short even while nothing is drawn.
+    fn receive_text_frames(&mut self) {
+        if let Some(frames) = &self.text_frames
+            && let Some(frame) = frames.try_iter().last()
+        {
+            self.text_frame = Some(frame);
+        }
+    }
+
+    /// Makes `image` the destination of `render_to_target`
+    pub fn set_target(&mut self, image: Arc<Image>) -> Result<()> {
+        self.target = Some(ImageTarget::new(&self.device, image)?);
+        Ok(())
     }
 
     pub fn is_ready(&self) -> bool {
-        self.rcx.is_some()
+        self.rcx.is_some() || self.target.is_some()
+    }
+
+    /// The error that stopped `resumed` from opening the window, once the event loop is over
+    pub fn take_startup_error(&mut self) -> Option<anyhow::Error> {
+        self.startup_error.take()
     }
 
     pub fn get_text_pipeline(&self) -> Option<Arc<GraphicsPipeline>> {
@@ -106,23 +242,130 @@ impl VulkanRenderer {
         self.rcx
             .as_ref()
             .map(|rcx| rcx.text_pipeline_layout.clone())
+            .or_else(|| {
+                self.target
+                    .as_ref()
+                    .map(|target| target.text_pipeline_layout.clone())
+            })
+    }
+
+    /// Refresh rate of the monitor the window is on, if the system reports it
+    pub fn monitor_refresh_hz(&self) -> Option<f64> {
+        let monitor = self.rcx.as_ref()?.window.current_monitor()?;
+        monitor
+            .refresh_rate_millihertz()
+            .map(|millihertz| millihertz as f64 / 1000.0)
     }
 
+    /// Size of the window, or of the target image when embedded
     pub fn get_window_size(&self) -> Option<[f32; 2]> {
-        self.rcx.as_ref().map(|rcx| {
-            let size = rcx.window.inner_size();
-            [size.width as f32, size.height as f32]
-        })
+        self.rcx
+            .as_ref()
+            .map(|rcx| {
+                let size = rcx.window.inner_size();
+                [size.width as f32, size.height as f32]
+            })
+            .or_else(|| self.target.as_ref().map(ImageTarget::size))
+    }
+
+    /// Draws a frame into the target image once `before` has completed.
+    ///
+    /// The returned future is not flushed, so the caller can chain its own work
+    /// (sampling or copying the image) before submitting.
+    pub fn render_to_target(&mut self, before: Box<dyn GpuFuture>) -> Result<Box<dyn GpuFuture>> {
+        self.receive_text_frames();
+        let target = self
+            .target
+            .as_ref()
+            .context("no render target has been set")?;
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.command_buffer_allocator.clone(),
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )?;
+        self.record_frame(
+            &mut builder,
+            target.view.clone(),
+            &target.viewport,
+            &target.pipeline,
+            &target.text_pipeline,
+            &target.text_pipeline_layout,
+        );
+        let command_buffer = builder.build()?;
+
+        Ok(before
+            .then_execute(self.queue.clone(), command_buffer)?
+            .boxed())
+    }
+
+    /// Records the clear, the triangle and the text into `view`
+    fn record_frame(
+        &self,
+        builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        view: Arc<ImageView>,
+        viewport: &Viewport,
+        pipeline: &Arc<GraphicsPipeline>,
+        text_pipeline: &Arc<GraphicsPipeline>,
+        text_pipeline_layout: &Arc<PipelineLayout>,
+    ) {
+        builder
+            .begin_rendering(RenderingInfo {
+                color_attachments: vec![Some(RenderingAttachmentInfo {
+                    load_op: AttachmentLoadOp::Clear,
+                    store_op: AttachmentStoreOp::Store,
+                    // Black stays black when premultiplied by the opacity
+                    clear_value: Some([0.0, 0.0, 0.0, self.opacity].into()),
+                    ..RenderingAttachmentInfo::image_view(view)
+                })],
+                ..Default::default()
+            })
+            .unwrap()
+            .set_viewport(0, [viewport.clone()].into_iter().collect())
+            .unwrap()
+            .bind_pipeline_graphics(pipeline.clone())
+            .unwrap()
+            .bind_vertex_buffers(0, self.vertex_buffer.clone())
+            .unwrap();
+
+        if let Some(frame) = &self.text_frame {
+            log::debug!("Drawing text to screen {:?}", viewport.extent);
+            if let Err(e) = frame.draw(builder, text_pipeline.clone(), text_pipeline_layout.clone())
+            {
+                log::warn!("Failed to draw text: {}", e);
+            }
+        }
+
+        builder.end_rendering().unwrap();
     }
 }
 
 impl ApplicationHandler for VulkanRenderer {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        self.rcx = Some(swapchain::create_render_context(
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        match swapchain::create_render_context(
             event_loop,
-            &self.instance,
+            instance,
             &self.device,
-        ));
+            !self.hidden,
+            self.window_options.as_ref(),
+        ) {
+            Ok(rcx) => self.rcx = Some(rcx),
+            Err(error) => {
+                // main() picks the error up and falls back to the command-line mode
+                self.startup_error = Some(error);
+                event_loop.exit();
+                return;
+            }
+        }
+        // Leaving a fullscreen it started in goes back to the saved size
+        self.windowed = self
+            .window_options
+            .as_ref()
+            .and_then(|options| options.saved)
+            .filter(|saved| saved.fullscreen);
     }
 
     fn window_event(
@@ -131,22 +374,17 @@ impl ApplicationHandler for VulkanRenderer {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
-        let rcx = self.rcx.as_mut().unwrap();
+        let Some(rcx) = self.rcx.as_mut() else {
+            return;
+        };
 
         match event {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
-            WindowEvent::Resized(new_size) => {
+            WindowEvent::Resized(_) => {
+                // The app lays the text out for the new size once this returns
                 rcx.recreate_swapchain = true;
-
-                // Update TextSystem with new window size
-                if let Some(text_system) = &self.text_system {
-                    if let Ok(mut text_system) = text_system.lock() {
-                        text_system
-                            .update_window_size(new_size.width as f32, new_size.height as f32);
-                    }
-                }
             }
             WindowEvent::RedrawRequested => {
                 let window_size = rcx.window.inner_size();
@@ -166,18 +404,12 @@ impl ApplicationHandler for VulkanRenderer {
                 // Whenever the window resizes we need to recreate everything dependent on the
                 // window size. In this example that includes the swapchain, the framebuffers and
                 // the dynamic state viewport.
-                if rcx.recreate_swapchain {
-                    swapchain::recreate_swapchain(rcx, window_size);
-
-                    // Update TextSystem with current window size after swapchain recreation
-                    if let Some(text_system) = &self.text_system {
-                        if let Ok(mut text_system) = text_system.lock() {
-                            text_system.update_window_size(
-                                window_size.width as f32,
-                                window_size.height as f32,
-                            );
-                        }
-                    }
+                if rcx.recreate_swapchain
+                    && let Err(e) = swapchain::recreate_swapchain(rcx, window_size)
+                {
+                    // Often a resize still in progress; tried again on the next frame
+                    log::warn!("{e:#}");
+                    return;
                 }
 
                 // Acquire next image from swapchain
@@ -202,53 +434,26 @@ impl ApplicationHandler for VulkanRenderer {
                 )
                 .unwrap();
 
-                builder
-                    .begin_rendering(RenderingInfo {
-                        color_attachments: vec![Some(RenderingAttachmentInfo {
-                            load_op: AttachmentLoadOp::Clear,
-                            store_op: AttachmentStoreOp::Store,
-                            clear_value: Some([0.0, 0.0, 0.0, 1.0].into()),
-                            ..RenderingAttachmentInfo::image_view(
-                                rcx.attachment_image_views[image_index as usize].clone(),
-                            )
-                        })],
-                        ..Default::default()
-                    })
-                    .unwrap()
-                    .set_viewport(0, [rcx.viewport.clone()].into_iter().collect())
-                    .unwrap()
-                    .bind_pipeline_graphics(rcx.pipeline.clone())
-                    .unwrap()
-                    .bind_vertex_buffers(0, self.vertex_buffer.clone())
-                    .unwrap();
-
-                // Draw text if available
-                if let Some(text_system) = &self.text_system {
-                    log::debug!("Text system is available, attempting to acquire lock");
-                    if let Ok(text_system) = text_system.lock() {
-                        if text_system.has_text() {
-                            log::debug!("Drawing text to screen {:?}", window_size);
-                            if let Err(e) = text_system.draw(
-                                &mut builder,
-                                rcx.text_pipeline.clone(),
-                                rcx.text_pipeline_layout.clone(),
-                            ) {
-                                log::warn!("Failed to draw text: {}", e);
-                            }
-                        } else {
-                            log::debug!("Text system has no text to draw");
-                        }
-                    } else {
-                        log::warn!("Failed to acquire lock on text system");
-                    }
-                } else {
-                    log::debug!("No text system available for drawing");
-                }
-
-                builder.end_rendering().unwrap();
+                // Clone what the frame needs so `record_frame` can borrow the renderer
+                let view = rcx.attachment_image_views[image_index as usize].clone();
+                let viewport = rcx.viewport.clone();
+                let pipeline = rcx.pipeline.clone();
+                let text_pipeline = rcx.text_pipeline.clone();
+                let text_pipeline_layout = rcx.text_pipeline_layout.clone();
+                self.receive_text_frames();
+                self.record_frame(
+                    &mut builder,
+                    view,
+                    &viewport,
+                    &pipeline,
+                    &text_pipeline,
+                    &text_pipeline_layout,
+                );
 
                 let command_buffer = builder.build().unwrap();
 
+                let rcx = self.rcx.as_mut().unwrap();
+
                 let future = rcx
                     .previous_frame_end
                     .take()
@@ -284,7 +489,14 @@ impl ApplicationHandler for VulkanRenderer {
     }
 
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        let rcx = self.rcx.as_mut().unwrap();
-        rcx.window.request_redraw();
+        // Also while minimized, when no frame is drawn to take them
+        self.receive_text_frames();
+        if self.hidden {
+            return;
+        }
+        // None when the window couldn't be opened and the loop is exiting
+        if let Some(rcx) = &self.rcx {
+            rcx.window.request_redraw();
+        }
     }
 }
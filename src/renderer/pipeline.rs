@@ -1,7 +1,9 @@
+use anyhow::{Context, Result};
 use std::sync::Arc;
 use vulkano::{
     buffer::BufferContents,
     device::Device,
+    format::Format,
     pipeline::{
         DynamicState, GraphicsPipeline, PipelineLayout, PipelineShaderStageCreateInfo,
         graphics::{
@@ -13,16 +15,13 @@ use vulkano::{
             multisample::MultisampleState,
             rasterization::RasterizationState,
             subpass::PipelineRenderingCreateInfo,
-            vertex_input::{Vertex, VertexDefinition},
+            vertex_input::{Vertex, VertexDefinition, VertexInputState},
             viewport::ViewportState,
         },
         layout::PipelineDescriptorSetLayoutCreateInfo,
     },
-    swapchain::Swapchain,
 };
 
-use crate::text::TextVertex;
-
 // We use `#[repr(C)]` here to force rustc to use a defined layout for our data, as the default
 // representation has *no guarantees*.
 #[derive(BufferContents, Vertex)]
@@ -71,26 +70,54 @@ mod text_vs {
         src: r"
             #version 450
 
-            layout(location = 0) in vec2 position;
-            layout(location = 1) in vec2 tex_coords;
-            layout(location = 2) in vec4 color;
-
+            // Mirrors GlyphRecord in text.rs (32 bytes)
+            struct GlyphRecord {
+                vec2 position;
+                vec2 size;
+                uint uv_min;
+                uint uv_max;
+                uint color;
+                uint flags;
+            };
+
+            layout(std430, set = 1, binding = 0) readonly buffer Glyphs {
+                GlyphRecord glyphs[];
+            };
+
+            // Atlas pixel coordinates; the fragment shader divides by the atlas size
             layout(location = 0) out vec2 frag_tex_coords;
             layout(location = 1) out vec4 frag_color;
+            layout(location = 2) flat out uint frag_flags;
 
             layout(push_constant) uniform PushConstants {
                 vec2 screen_size;
+                vec2 offset;
                 vec4 text_color;
             } pc;
 
+            // Two 16-bit values, x in the low bits
+            vec2 unpack_pixel(uint packed) {
+                return vec2(float(packed & 0xFFFFu), float(packed >> 16));
+            }
+
+            // Two triangles covering the unit square; every glyph record is drawn with them
+            const vec2 CORNERS[6] = vec2[](
+                vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0),
+                vec2(1.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)
+            );
+
             void main() {
+                GlyphRecord glyph = glyphs[gl_InstanceIndex];
+                vec2 corner = CORNERS[gl_VertexIndex];
+                vec2 position = glyph.position + pc.offset + corner * glyph.size;
+
                 // Convert screen coordinates to normalized device coordinates
                 vec2 normalized_pos = (position / pc.screen_size) * 2.0 - 1.0;
-                // normalized_pos.y = -normalized_pos.y; // Flip Y coordinate
 
                 gl_Position = vec4(normalized_pos, 0.0, 1.0);
-                frag_tex_coords = tex_coords;
-                frag_color = color;
+                frag_tex_coords = mix(unpack_pixel(glyph.uv_min), unpack_pixel(glyph.uv_max), corner);
+                frag_color = unpackUnorm4x8(glyph.color);
+                frag_flags = glyph.flags;
             }
         ",
     }
@@ -105,6 +132,7 @@ mod text_fs {
 
             layout(location = 0) in vec2 frag_tex_coords;
             layout(location = 1) in vec4 frag_color;
+            layout(location = 2) flat in uint frag_flags;
 
             layout(location = 0) out vec4 f_color;
 
@@ -112,20 +140,22 @@ mod text_fs {
 
             layout(push_constant) uniform PushConstants {
                 vec2 screen_size;
+                vec2 offset;
                 vec4 text_color;
             } pc;
 
             void main() {
-                // Check if this is a solid color quad (background) vs textured glyph
-                // Background quads use UV [0,0] for all vertices
-                if (frag_tex_coords.x == 0.0 && frag_tex_coords.y == 0.0) {
+                // Solid records (backgrounds, caret) skip the atlas
+                if ((frag_flags & 1u) != 0u) {
                     // Render solid color without texture sampling (for backgrounds)
                     f_color = frag_color;
                 } else {
                     // Normal glyph rendering with texture
-                    float alpha = texture(glyph_texture, frag_tex_coords).r;
+                    // The atlas grows as glyphs are added, so its size is only known here
+                    vec2 uv = frag_tex_coords / vec2(textureSize(glyph_texture, 0));
+                    float alpha = texture(glyph_texture, uv).r;
 
-                    // Use per-vertex color instead of push constant color
+                    // Use the record color instead of push constant color
                     f_color = vec4(frag_color.rgb * alpha, frag_color.a);
 
                     // Discard fully transparent pixels
@@ -141,20 +171,18 @@ mod text_fs {
 /// Creates the main graphics pipeline for triangle rendering
 pub fn create_graphics_pipeline(
     device: Arc<Device>,
-    swapchain: Arc<Swapchain>,
-) -> Arc<GraphicsPipeline> {
+    image_format: Format,
+) -> Result<Arc<GraphicsPipeline>> {
     // Load shaders
-    let vs = vs::load(device.clone())
-        .unwrap()
+    let vs = vs::load(device.clone())?
         .entry_point("main")
-        .unwrap();
-    let fs = fs::load(device.clone())
-        .unwrap()
+        .context("vs shader has no main")?;
+    let fs = fs::load(device.clone())?
         .entry_point("main")
-        .unwrap();
+        .context("fs shader has no main")?;
 
     // Automatically generate vertex input state from the vertex shader's input interface
-    let vertex_input_state = MyVertex::per_vertex().definition(&vs).unwrap();
+    let vertex_input_state = MyVertex::per_vertex().definition(&vs)?;
 
     // Make a list of the shader stages that the pipeline will have
     let stages = [
@@ -166,19 +194,17 @@ pub fn create_graphics_pipeline(
     let layout = PipelineLayout::new(
         device.clone(),
         PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
-            .into_pipeline_layout_create_info(device.clone())
-            .unwrap(),
-    )
-    .unwrap();
+            .into_pipeline_layout_create_info(device.clone())?,
+    )?;
 
     // Describe the formats of attachment images
     let subpass = PipelineRenderingCreateInfo {
-        color_attachment_formats: vec![Some(swapchain.image_format())],
+        color_attachment_formats: vec![Some(image_format)],
         ..Default::default()
     };
 
     // Create the pipeline
-    GraphicsPipeline::new(
+    Ok(GraphicsPipeline::new(
         device.clone(),
         None,
         GraphicsPipelineCreateInfo {
@@ -196,27 +222,24 @@ pub fn create_graphics_pipeline(
             subpass: Some(subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(layout)
         },
-    )
-    .unwrap()
+    )?)
 }
 
 /// Creates the text rendering pipeline
 pub fn create_text_pipeline(
     device: Arc<Device>,
-    swapchain: Arc<Swapchain>,
-) -> (Arc<GraphicsPipeline>, Arc<PipelineLayout>) {
+    image_format: Format,
+) -> Result<(Arc<GraphicsPipeline>, Arc<PipelineLayout>)> {
     // Load text shaders
-    let text_vs = text_vs::load(device.clone())
-        .unwrap()
+    let text_vs = text_vs::load(device.clone())?
         .entry_point("main")
-        .unwrap();
-    let text_fs = text_fs::load(device.clone())
-        .unwrap()
+        .context("text_vs shader has no main")?;
+    let text_fs = text_fs::load(device.clone())?
         .entry_point("main")
-        .unwrap();
+        .context("text_fs shader has no main")?;
 
-    // Create vertex input state for text
-    let text_vertex_input_state = TextVertex::per_vertex().definition(&text_vs).unwrap();
+    // Text has no vertex input: quads come from gl_VertexIndex, glyphs from a storage buffer
+    let text_vertex_input_state = VertexInputState::new();
 
     let text_stages = [
         PipelineShaderStageCreateInfo::new(text_vs),
@@ -227,13 +250,11 @@ pub fn create_text_pipeline(
     let text_layout = PipelineLayout::new(
         device.clone(),
         PipelineDescriptorSetLayoutCreateInfo::from_stages(&text_stages)
-            .into_pipeline_layout_create_info(device.clone())
-            .unwrap(),
-    )
-    .unwrap();
+            .into_pipeline_layout_create_info(device.clone())?,
+    )?;
 
     let text_subpass = PipelineRenderingCreateInfo {
-        color_attachment_formats: vec![Some(swapchain.image_format())],
+        color_attachment_formats: vec![Some(image_format)],
         ..Default::default()
     };
 
@@ -266,8 +287,7 @@ pub fn create_text_pipeline(
             subpass: Some(text_subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(text_layout.clone())
         },
-    )
-    .unwrap();
+    )?;
 
-    (text_pipeline, text_layout)
+    Ok((text_pipeline, text_layout))
 }
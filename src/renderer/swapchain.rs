@@ -1,3 +1,4 @@
+use anyhow::{Context, Result};
 use std::sync::Arc;
 use vulkano::{
     Validated, VulkanError,
@@ -7,12 +8,20 @@ use vulkano::{
     instance::Instance,
     pipeline::graphics::viewport::Viewport,
     pipeline::{GraphicsPipeline, PipelineLayout},
-    swapchain::{Surface, Swapchain, SwapchainCreateInfo, acquire_next_image},
+    swapchain::{
+        CompositeAlpha, CompositeAlphas, Surface, Swapchain, SwapchainCreateInfo,
+        acquire_next_image,
+    },
     sync::{self, GpuFuture},
 };
-use winit::{event_loop::ActiveEventLoop, window::Window};
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event_loop::ActiveEventLoop,
+    window::{Fullscreen, Window, WindowAttributes},
+};
 
-use super::pipeline;
+use super::{WindowOptions, pipeline};
+use crate::progress_storage::WindowGeometry;
 
 pub struct RenderContext {
     pub window: Arc<Window>,
@@ -31,22 +40,22 @@ pub fn create_swapchain(
     device: Arc<Device>,
     surface: Arc<Surface>,
     window_size: winit::dpi::PhysicalSize<u32>,
-) -> (Arc<Swapchain>, Vec<Arc<Image>>) {
+) -> Result<(Arc<Swapchain>, Vec<Arc<Image>>)> {
     // Querying the capabilities of the surface. When we create the swapchain we can only
     // pass values that are allowed by the capabilities.
     let surface_capabilities = device
         .physical_device()
-        .surface_capabilities(&surface, Default::default())
-        .unwrap();
+        .surface_capabilities(&surface, Default::default())?;
 
     // Choosing the internal format that the images will have.
-    let (image_format, _) = device
+    let (image_format, _) = *device
         .physical_device()
-        .surface_formats(&surface, Default::default())
-        .unwrap()[0];
+        .surface_formats(&surface, Default::default())?
+        .first()
+        .context("the surface supports no image formats")?;
 
     // Please take a look at the docs for the meaning of the parameters we didn't mention.
-    Swapchain::new(
+    Ok(Swapchain::new(
         device,
         surface,
         SwapchainCreateInfo {
@@ -75,55 +84,117 @@ pub fn create_swapchain(
             image_usage: ImageUsage::COLOR_ATTACHMENT,
 
             // The alpha mode indicates how the alpha value of the final image will behave.
-            // For example, you can choose whether the window will be opaque or
-            // transparent.
-            composite_alpha: surface_capabilities
-                .supported_composite_alpha
-                .into_iter()
-                .next()
-                .unwrap(),
+            // Blending with what is behind the window lets `window.opacity` show it through.
+            composite_alpha: pick_composite_alpha(surface_capabilities.supported_composite_alpha),
 
             ..Default::default()
         },
-    )
-    .unwrap()
+    )?)
+}
+
+/// A mode that blends the window with the desktop when the surface supports one, so the
+/// opacity can change at runtime without a new window. With an opaque clear color
+/// these look the same as `Opaque`.
+fn pick_composite_alpha(supported: CompositeAlphas) -> CompositeAlpha {
+    [
+        CompositeAlpha::PreMultiplied,
+        CompositeAlpha::PostMultiplied,
+        CompositeAlpha::Inherit,
+    ]
+    .into_iter()
+    .find(|mode| supported.contains_enum(*mode))
+    .or_else(|| supported.into_iter().next())
+    .unwrap_or(CompositeAlpha::Opaque)
 }
 
 /// This function is called once during initialization, then again whenever the window is resized.
-pub fn window_size_dependent_setup(images: &[Arc<Image>]) -> Vec<Arc<ImageView>> {
+pub fn window_size_dependent_setup(images: &[Arc<Image>]) -> Result<Vec<Arc<ImageView>>> {
     images
         .iter()
-        .map(|image| ImageView::new_default(image.clone()).unwrap())
-        .collect::<Vec<_>>()
+        .map(|image| Ok(ImageView::new_default(image.clone())?))
+        .collect()
+}
+
+/// Size and position of `window` as it is now
+pub fn window_geometry(window: &Window) -> WindowGeometry {
+    let size = window.inner_size();
+    WindowGeometry {
+        width: size.width,
+        height: size.height,
+        position: window
+            .outer_position()
+            .ok()
+            .map(|position| [position.x, position.y]),
+        fullscreen: window.fullscreen().is_some(),
+    }
 }
 
-/// Creates a complete render context with window, swapchain, and pipelines
+/// Window attributes for `options`. A saved position is only used when it is on one
+/// of the monitors, so a window closed on an unplugged screen doesn't open off-screen.
+fn window_attributes(event_loop: &ActiveEventLoop, options: &WindowOptions) -> WindowAttributes {
+    let attributes = Window::default_attributes().with_title(options.title.clone());
+    let Some(saved) = options.saved else {
+        let [width, height] = options.size;
+        return attributes.with_inner_size(LogicalSize::new(width, height));
+    };
+
+    let mut attributes = attributes.with_inner_size(PhysicalSize::new(saved.width, saved.height));
+    if let Some([x, y]) = saved.position {
+        let on_a_monitor = event_loop.available_monitors().any(|monitor| {
+            let origin = monitor.position();
+            let size = monitor.size();
+            (origin.x..origin.x + size.width as i32).contains(&x)
+                && (origin.y..origin.y + size.height as i32).contains(&y)
+        });
+        if on_a_monitor {
+            attributes = attributes.with_position(PhysicalPosition::new(x, y));
+        }
+    }
+    if saved.fullscreen {
+        attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+    }
+    attributes
+}
+
+/// Creates a complete render context with window, swapchain, and pipelines. Fails when
+/// there is no display to open the window on or the GPU can't present to it.
 pub fn create_render_context(
     event_loop: &ActiveEventLoop,
     instance: &Arc<Instance>,
     device: &Arc<Device>,
-) -> RenderContext {
+    visible: bool,
+    options: Option<&WindowOptions>,
+) -> Result<RenderContext> {
     // Create window
+    let attributes = match options {
+        Some(options) => window_attributes(event_loop, options),
+        None => Window::default_attributes(),
+    };
     let window = Arc::new(
         event_loop
-            .create_window(Window::default_attributes())
-            .unwrap(),
+            // Transparent so a translucent background shows what is behind the window
+            .create_window(attributes.with_transparent(true).with_visible(visible))
+            .context("failed to create the window")?,
     );
-    let surface = Surface::from_window(instance.clone(), window.clone()).unwrap();
+    let surface = Surface::from_window(instance.clone(), window.clone())
+        .context("failed to create a Vulkan surface for the window")?;
     let window_size = window.inner_size();
 
     // Create swapchain
-    let (swapchain, images) = create_swapchain(device.clone(), surface, window_size);
+    let (swapchain, images) = create_swapchain(device.clone(), surface, window_size)
+        .context("failed to create the swapchain")?;
 
     // Create image views
-    let attachment_image_views = window_size_dependent_setup(&images);
+    let attachment_image_views = window_size_dependent_setup(&images)?;
 
     // Create graphics pipeline
-    let pipeline = pipeline::create_graphics_pipeline(device.clone(), swapchain.clone());
+    let pipeline = pipeline::create_graphics_pipeline(device.clone(), swapchain.image_format())
+        .context("failed to create the graphics pipeline")?;
 
     // Create text pipeline
     let (text_pipeline, text_pipeline_layout) =
-        pipeline::create_text_pipeline(device.clone(), swapchain.clone());
+        pipeline::create_text_pipeline(device.clone(), swapchain.image_format())
+            .context("failed to create the text pipeline")?;
 
     // Create viewport
     let viewport = Viewport {
@@ -135,7 +206,7 @@ pub fn create_render_context(
     // Initialize frame synchronization
     let previous_frame_end = Some(sync::now(device.clone()).boxed());
 
-    RenderContext {
+    Ok(RenderContext {
         window,
         swapchain,
         attachment_image_views,
@@ -145,28 +216,32 @@ pub fn create_render_context(
         viewport,
         recreate_swapchain: false,
         previous_frame_end,
-    }
+    })
 }
 
 /// Recreates the swapchain when the window is resized
-pub fn recreate_swapchain(rcx: &mut RenderContext, window_size: winit::dpi::PhysicalSize<u32>) {
+pub fn recreate_swapchain(
+    rcx: &mut RenderContext,
+    window_size: winit::dpi::PhysicalSize<u32>,
+) -> Result<()> {
     let (new_swapchain, new_images) = rcx
         .swapchain
         .recreate(SwapchainCreateInfo {
             image_extent: window_size.into(),
             ..rcx.swapchain.create_info()
         })
-        .expect("failed to recreate swapchain");
+        .context("failed to recreate the swapchain")?;
 
     rcx.swapchain = new_swapchain;
 
     // Now that we have new swapchain images, we must create new image views from
     // them as well.
-    rcx.attachment_image_views = window_size_dependent_setup(&new_images);
+    rcx.attachment_image_views = window_size_dependent_setup(&new_images)?;
 
     rcx.viewport.extent = window_size.into();
 
     rcx.recreate_swapchain = false;
+    Ok(())
 }
 
 /// Acquires the next image from the swapchain
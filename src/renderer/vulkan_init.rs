@@ -1,3 +1,4 @@
+use anyhow::{Context, Result};
 use std::sync::Arc;
 use vulkano::{
     Version, VulkanLibrary,
@@ -11,8 +12,8 @@ use vulkano::{
 use winit::event_loop::EventLoop;
 
 /// Initializes Vulkan instance with required extensions for window rendering
-pub fn create_instance(event_loop: &EventLoop<()>) -> Arc<Instance> {
-    let library = VulkanLibrary::new().unwrap();
+pub fn create_instance(event_loop: &EventLoop<()>) -> Result<Arc<Instance>> {
+    let library = VulkanLibrary::new().context("Vulkan loader not found")?;
 
     // The first step of any Vulkan program is to create an instance.
     //
@@ -21,7 +22,8 @@ pub fn create_instance(event_loop: &EventLoop<()>) -> Arc<Instance> {
     // All the window-drawing functionalities are part of non-core extensions that we need to
     // enable manually. To do so, we ask `Surface` for the list of extensions required to draw
     // to a window.
-    let required_extensions = Surface::required_extensions(event_loop).unwrap();
+    let required_extensions = Surface::required_extensions(event_loop)
+        .context("window system does not support Vulkan surfaces")?;
 
     // Now creating the instance.
     Instance::new(
@@ -34,18 +36,35 @@ pub fn create_instance(event_loop: &EventLoop<()>) -> Arc<Instance> {
             ..Default::default()
         },
     )
-    .unwrap()
+    .context("failed to create Vulkan instance")
 }
 
-/// Selects the best physical device and creates a logical device with a graphics queue
+/// Initializes a Vulkan instance without any window-system extensions
+pub fn create_headless_instance() -> Result<Arc<Instance>> {
+    let library = VulkanLibrary::new().context("Vulkan loader not found")?;
+
+    Instance::new(
+        library,
+        InstanceCreateInfo {
+            flags: InstanceCreateFlags::ENUMERATE_PORTABILITY,
+            ..Default::default()
+        },
+    )
+    .context("failed to create Vulkan instance")
+}
+
+/// Selects the best physical device and creates a logical device with a graphics queue.
+///
+/// With an event loop the queue must also be able to present to its windows; without one
+/// the device only renders into images (see `initialize_headless`).
 pub fn create_device(
     instance: Arc<Instance>,
-    event_loop: &EventLoop<()>,
-) -> (Arc<Device>, Arc<Queue>) {
+    event_loop: Option<&EventLoop<()>>,
+) -> Result<(Arc<Device>, Arc<Queue>)> {
     // Choose device extensions that we're going to use. In order to present images to a
     // surface, we need a `Swapchain`, which is provided by the `khr_swapchain` extension.
     let mut device_extensions = DeviceExtensions {
-        khr_swapchain: true,
+        khr_swapchain: event_loop.is_some(),
         ..DeviceExtensions::empty()
     };
 
@@ -54,7 +73,7 @@ pub fn create_device(
     // needs.
     let (physical_device, queue_family_index) = instance
         .enumerate_physical_devices()
-        .unwrap()
+        .context("failed to list Vulkan devices")?
         .filter(|p| {
             // For this example, we require at least Vulkan 1.3, or a device that has the
             // `khr_dynamic_rendering` extension available.
@@ -88,7 +107,10 @@ pub fn create_device(
                     // that queues in this queue family are capable of presenting images to the
                     // surface.
                     q.queue_flags.intersects(QueueFlags::GRAPHICS)
-                        && p.presentation_support(i as u32, event_loop).unwrap()
+                        && event_loop.is_none_or(|event_loop| {
+                            p.presentation_support(i as u32, event_loop)
+                                .unwrap_or(false)
+                        })
                 })
                 // The code here searches for the first queue family that is suitable. If none
                 // is found, `None` is returned to `filter_map`, which disqualifies this
@@ -114,7 +136,7 @@ pub fn create_device(
                 _ => 5,
             }
         })
-        .expect("no suitable physical device found");
+        .context("no Vulkan device supports graphics with dynamic rendering")?;
 
     // Some little debug infos.
     println!(
@@ -166,19 +188,26 @@ pub fn create_device(
             ..Default::default()
         },
     )
-    .unwrap();
+    .context("failed to create Vulkan device")?;
 
     // Since we can request multiple queues, the `queues` variable is in fact an iterator. We
     // only use one queue in this example, so we just retrieve the first and only element of
     // the iterator.
-    let queue = queues.next().unwrap();
+    let queue = queues.next().context("Vulkan device has no queue")?;
 
-    (device, queue)
+    Ok((device, queue))
 }
 
 /// Initializes Vulkan and returns instance, device, and queue
-pub fn initialize_vulkan(event_loop: &EventLoop<()>) -> (Arc<Instance>, Arc<Device>, Arc<Queue>) {
-    let instance = create_instance(event_loop);
-    let (device, queue) = create_device(instance.clone(), event_loop);
-    (instance, device, queue)
+pub fn initialize_vulkan(
+    event_loop: &EventLoop<()>,
+) -> Result<(Arc<Instance>, Arc<Device>, Arc<Queue>)> {
+    let instance = create_instance(event_loop)?;
+    let (device, queue) = create_device(instance.clone(), Some(event_loop))?;
+    Ok((instance, device, queue))
+}
+
+/// Initializes Vulkan for offscreen rendering and returns device and queue
+pub fn initialize_headless() -> Result<(Arc<Device>, Arc<Queue>)> {
+    create_device(create_headless_instance()?, None)
 }
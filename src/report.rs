@@ -0,0 +1,583 @@
+//! Headless practice reports
+//!
+//! `cargo run analyze [history.json] [--markdown report.md]` prints the summaries of
+//! the statistics screen plus weak keys, per-language and weekly breakdowns without
+//! opening a window, so a weekly report can be produced from cron. Numbers and
+//! dates are written for `[statistics] locale`.
+
+use anyhow::{Result, bail};
+use chrono::{Datelike, Local};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, ScoringConfig};
+use crate::drills::DRILL_SOURCE;
+use crate::locale::Locale;
+use crate::quotes::QUOTE_SOURCE;
+use crate::session_history::{self, HistoryRange, SessionHistory, SessionSummary};
+use crate::session_state::{FILE_SOURCE, SessionStats};
+
+/// Keys with fewer attempts than this are too noisy to call weak
+const WEAK_KEY_MIN_ATTEMPTS: usize = 10;
+const WEAK_KEY_COUNT: usize = 10;
+const TREND_WEEKS: usize = 8;
+const RECENT_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Markdown,
+}
+
+/// Runs the `analyze` subcommand; `args` are the arguments after `analyze`
+pub fn run(args: &[String], config: &Config) -> Result<()> {
+    let scoring = &config.scoring;
+    let locale = Locale::resolve(&config.statistics.locale);
+    let mut history_path: Option<PathBuf> = None;
+    let mut markdown_path: Option<PathBuf> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--markdown" => match args.next() {
+                Some(path) => markdown_path = Some(PathBuf::from(path)),
+                None => bail!("--markdown needs an output file"),
+            },
+            flag if flag.starts_with("--") => {
+                bail!(
+                    "unknown option {}; usage: analyze [history.json] [--markdown OUT.md]",
+                    flag
+                )
+            }
+            path if history_path.is_none() => history_path = Some(PathBuf::from(path)),
+            extra => bail!("unexpected argument {}", extra),
+        }
+    }
+
+    let mut history = match &history_path {
+        Some(path) if !path.exists() => bail!("history file {} not found", path.display()),
+        Some(path) => SessionHistory::new(path),
+        None => SessionHistory::default(),
+    };
+    history.load()?;
+
+    match markdown_path {
+        Some(output) => {
+            fs::write(&output, build(&history, scoring, &locale, Format::Markdown))?;
+            println!("✓ Report written to {}", output.display());
+        }
+        None => print!("{}", build(&history, scoring, &locale, Format::Text)),
+    }
+    Ok(())
+}
+
+/// Renders the full report for `history`
+pub fn build(
+    history: &SessionHistory,
+    scoring: &ScoringConfig,
+    locale: &Locale,
+    format: Format,
+) -> String {
+    let mut report = Report::new(format);
+    report.title(&format!(
+        "CargoTap practice report ({})",
+        locale.date(Local::now().date_naive())
+    ));
+
+    if history.count() == 0 {
+        report.line("No sessions recorded yet.");
+        return report.out;
+    }
+
+    write_overview(&mut report, history, scoring, locale);
+    write_languages(&mut report, history, locale);
+    write_setups(&mut report, history, locale);
+    write_trends(&mut report, history, locale);
+    write_weak_keys(&mut report, history, locale);
+    write_recent_sessions(&mut report, history, scoring, locale);
+    report.out
+}
+
+fn write_overview(
+    report: &mut Report,
+    history: &SessionHistory,
+    scoring: &ScoringConfig,
+    locale: &Locale,
+) {
+    let summary = history.get_summary();
+    report.heading(&format!(
+        "All-time statistics ({} sessions)",
+        locale.count(summary.total_sessions)
+    ));
+    report.line(&format!(
+        "Total Characters: {}",
+        locale.count(summary.total_chars)
+    ));
+    report.line(&format!(
+        "Total Time: {} minutes",
+        locale.decimal(summary.total_time / 60.0, 1)
+    ));
+    write_speed(report, &summary, locale);
+    report.line(&format!(
+        "Total Errors: {}",
+        locale.count(summary.total_errors)
+    ));
+    report.end_section();
+
+    report.heading("Best performances");
+    match history
+        .get_best_score_session(scoring.accuracy_exponent)
+        .filter(|_| scoring.enabled)
+    {
+        Some(best) => report.line(&format!(
+            "Best Score: {} ({} WPM at {})",
+            locale.decimal(best.score_or_compute(scoring.accuracy_exponent), 1),
+            locale.decimal(best.words_per_minute, 0),
+            locale.percent(best.accuracy, 1)
+        )),
+        None => report.line(&format!(
+            "Best Speed: {} CPM / {} WPM",
+            locale.decimal(summary.best_cpm, 0),
+            locale.decimal(summary.best_wpm, 0)
+        )),
+    }
+    report.line(&format!(
+        "Best Accuracy: {}",
+        locale.percent(summary.best_accuracy, 1)
+    ));
+    report.end_section();
+
+    let recent = history.get_recent_summary(RECENT_COUNT);
+    report.heading(&format!(
+        "Recent performance (last {} sessions)",
+        recent.total_sessions
+    ));
+    write_speed(report, &recent, locale);
+    let (improved, improvement) = history.analyze_improvement(RECENT_COUNT);
+    if improved {
+        report.line(&format!("Improvement: {}%", locale.signed(improvement, 1)));
+    } else if improvement < 0.0 {
+        report.line(&format!("Change: {}", locale.percent(improvement, 1)));
+    }
+    report.end_section();
+}
+
+fn write_speed(report: &mut Report, summary: &SessionSummary, locale: &Locale) {
+    report.line(&format!(
+        "Avg Speed: {} CPM / {} WPM",
+        locale.decimal(summary.avg_cpm, 0),
+        locale.decimal(summary.avg_wpm, 0)
+    ));
+    report.line(&format!(
+        "Avg Accuracy: {}",
+        locale.percent(summary.avg_accuracy, 1)
+    ));
+}
+
+fn write_languages(report: &mut Report, history: &SessionHistory, locale: &Locale) {
+    report.heading("By language");
+    let rows = history
+        .summarize_by(language_of)
+        .into_iter()
+        .map(|(language, summary)| {
+            vec![
+                language,
+                locale.count(summary.total_sessions),
+                locale.decimal(summary.avg_wpm, 0),
+                locale.decimal(summary.best_wpm, 0),
+                locale.percent(summary.avg_accuracy, 1),
+                locale.decimal(summary.total_time / 60.0, 1),
+            ]
+        })
+        .collect();
+    report.table(
+        &[
+            "Language", "Sessions", "Avg WPM", "Best WPM", "Accuracy", "Minutes",
+        ],
+        rows,
+    );
+}
+
+/// Speed on each keyboard and font size, once sessions record their setup
+fn write_setups(report: &mut Report, history: &SessionHistory, locale: &Locale) {
+    let setups = history.summarize_by(|session| {
+        session
+            .context
+            .as_ref()
+            .map(|context| context.setup_label())
+    });
+    if setups.iter().all(|(setup, _)| setup.is_none()) {
+        return;
+    }
+    report.heading("By setup");
+    let rows = setups
+        .into_iter()
+        .map(|(setup, summary)| {
+            vec![
+                setup.unwrap_or_else(|| "Not recorded".to_string()),
+                locale.count(summary.total_sessions),
+                locale.decimal(summary.avg_wpm, 0),
+                locale.percent(summary.avg_accuracy, 1),
+            ]
+        })
+        .collect();
+    report.table(&["Setup", "Sessions", "Avg WPM", "Accuracy"], rows);
+}
+
+fn write_trends(report: &mut Report, history: &SessionHistory, locale: &Locale) {
+    report.heading(&format!("Weekly trend (last {} weeks)", TREND_WEEKS));
+    let weeks = history.summarize_by(|session| {
+        session_history::local_date(session.timestamp).map(|date| {
+            let week = date.iso_week();
+            (week.year(), week.week())
+        })
+    });
+
+    let mut rows = Vec::new();
+    let mut previous_wpm: Option<f64> = None;
+    for (week, summary) in weeks {
+        let Some((year, week)) = week else {
+            continue;
+        };
+        let change = previous_wpm.map_or("-".to_string(), |wpm| {
+            locale.signed(summary.avg_wpm - wpm, 1)
+        });
+        previous_wpm = Some(summary.avg_wpm);
+        rows.push(vec![
+            format!("{}-W{:02}", year, week),
+            locale.count(summary.total_sessions),
+            locale.decimal(summary.avg_wpm, 0),
+            change,
+            locale.percent(summary.avg_accuracy, 1),
+        ]);
+    }
+    let skip = rows.len().saturating_sub(TREND_WEEKS);
+    report.table(
+        &["Week", "Sessions", "Avg WPM", "WPM change", "Accuracy"],
+        rows.into_iter().skip(skip).collect(),
+    );
+}
+
+fn write_weak_keys(report: &mut Report, history: &SessionHistory, locale: &Locale) {
+    report.heading("Weak keys");
+    let mut keys: Vec<_> = history
+        .get_key_stats(HistoryRange::All)
+        .into_iter()
+        .filter(|(_, stat)| stat.misses > 0 && stat.hits + stat.misses >= WEAK_KEY_MIN_ATTEMPTS)
+        .collect();
+    if keys.is_empty() {
+        report.line(&format!(
+            "No key with at least {} attempts has misses.",
+            WEAK_KEY_MIN_ATTEMPTS
+        ));
+        report.end_section();
+        return;
+    }
+
+    keys.sort_by(|(_, a), (_, b)| b.error_rate().total_cmp(&a.error_rate()));
+    let rows = keys
+        .into_iter()
+        .take(WEAK_KEY_COUNT)
+        .map(|(ch, stat)| {
+            vec![
+                key_label(ch),
+                locale.count(stat.hits + stat.misses),
+                locale.count(stat.misses),
+                locale.percent(stat.error_rate() * 100.0, 1),
+            ]
+        })
+        .collect();
+    report.table(&["Key", "Attempts", "Misses", "Error rate"], rows);
+}
+
+fn write_recent_sessions(
+    report: &mut Report,
+    history: &SessionHistory,
+    scoring: &ScoringConfig,
+    locale: &Locale,
+) {
+    report.heading("Recent sessions");
+    let mut header = vec!["Date", "Source", "WPM", "Accuracy", "Chars"];
+    if scoring.enabled {
+        header.push("Score");
+    }
+    let rows = history
+        .get_recent_sessions(RECENT_COUNT)
+        .into_iter()
+        .map(|session| {
+            let mut row = vec![
+                locale.timestamp(session.timestamp),
+                language_of(session),
+                locale.decimal(session.words_per_minute, 0),
+                locale.percent(session.accuracy, 1),
+                locale.count(session.chars_typed),
+            ];
+            if scoring.enabled {
+                row.push(locale.decimal(session.score_or_compute(scoring.accuracy_exponent), 1));
+            }
+            row
+        })
+        .collect();
+    report.table(&header, rows);
+}
+
+/// Groups file sessions by the language of the file and the rest by their source
+fn language_of(session: &SessionStats) -> String {
+    match session.source.as_str() {
+        FILE_SOURCE => {}
+        QUOTE_SOURCE => return "Quotes".to_string(),
+        DRILL_SOURCE => return "Drills".to_string(),
+        other => return other.to_string(),
+    }
+
+    let extension = Path::new(&session.file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("rs") => "Rust",
+        Some("py") => "Python",
+        Some("js" | "mjs") => "JavaScript",
+        Some("ts" | "tsx") => "TypeScript",
+        Some("go") => "Go",
+        Some("c" | "h") => "C",
+        Some("cpp" | "cc" | "hpp") => "C++",
+        Some("java") => "Java",
+        Some("rb") => "Ruby",
+        Some("toml") => "TOML",
+        Some("md") => "Markdown",
+        Some(other) => return other.to_string(),
+        None => "Other",
+    }
+    .to_string()
+}
+
+fn key_label(ch: char) -> String {
+    match ch {
+        ' ' => "Space".to_string(),
+        '\n' => "Enter".to_string(),
+        '\t' => "Tab".to_string(),
+        ch => format!("'{}'", ch),
+    }
+}
+
+/// Accumulates the report in either output format
+struct Report {
+    format: Format,
+    out: String,
+}
+
+impl Report {
+    fn new(format: Format) -> Self {
+        Self {
+            format,
+            out: String::new(),
+        }
+    }
+
+    fn title(&mut self, title: &str) {
+        match self.format {
+            Format::Text => self.out.push_str(&format!("{}\n\n", title.to_uppercase())),
+            Format::Markdown => self.out.push_str(&format!("# {}\n\n", title)),
+        }
+    }
+
+    fn heading(&mut self, title: &str) {
+        match self.format {
+            Format::Text => self.out.push_str(&format!(
+                "{}\n{}\n",
+                title.to_uppercase(),
+                "━".repeat(title.chars().count())
+            )),
+            Format::Markdown => self.out.push_str(&format!("## {}\n\n", title)),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        match self.format {
+            Format::Text => self.out.push_str(&format!("  {}\n", text)),
+            Format::Markdown => self.out.push_str(&format!("- {}\n", text)),
+        }
+    }
+
+    fn end_section(&mut self) {
+        self.out.push('\n');
+    }
+
+    fn table(&mut self, header: &[&str], rows: Vec<Vec<String>>) {
+        match self.format {
+            Format::Text => {
+                let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+                for row in &rows {
+                    for (width, cell) in widths.iter_mut().zip(row) {
+                        *width = (*width).max(cell.chars().count());
+                    }
+                }
+                let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+                for row in std::iter::once(&header).chain(&rows) {
+                    let cells: Vec<String> = row
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                        .collect();
+                    self.out
+                        .push_str(&format!("  {}\n", cells.join("  ").trim_end()));
+                }
+            }
+            Format::Markdown => {
+                self.out.push_str(&format!("| {} |\n", header.join(" | ")));
+                self.out
+                    .push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+                for row in &rows {
+                    let cells: Vec<String> =
+                        row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+            }
+        }
+        self.end_section();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_state::{KeyStat, SessionContext};
+
+    fn session(source: &str, file_path: &str, wpm: f64, timestamp: u64) -> SessionStats {
+        SessionStats {
+            chars_typed: 100,
+            time_elapsed_secs: 60.0,
+            chars_per_minute: wpm * 5.0,
+            words_per_minute: wpm,
+            start_position: 0,
+            end_position: 100,
+            errors: 2,
+            accuracy: 98.0,
+            timestamp,
+            file_path: file_path.to_string(),
+            source: source.to_string(),
+            dead_zones: 0,
+            line_range: None,
+            key_stats: Default::default(),
+            transitions: Default::default(),
+            bigrams: Default::default(),
+            words: Default::default(),
+            error_timeline: Default::default(),
+            error_lines: Default::default(),
+            review_of: None,
+            pomodoro: false,
+            score: None,
+            paused_secs: 0.0,
+            incomplete: false,
+            seed: None,
+            challenge: None,
+            pane_switches: 0,
+            too_fast_keys: 0,
+            end_condition: Default::default(),
+            warmup: None,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn test_language_of() {
+        assert_eq!(
+            language_of(&session(FILE_SOURCE, "src/main.rs", 40.0, 0)),
+            "Rust"
+        );
+        assert_eq!(
+            language_of(&session(FILE_SOURCE, "a/b.PY", 40.0, 0)),
+            "Python"
+        );
+        assert_eq!(language_of(&session(FILE_SOURCE, "x.zig", 40.0, 0)), "zig");
+        assert_eq!(
+            language_of(&session(FILE_SOURCE, "Makefile", 40.0, 0)),
+            "Other"
+        );
+        assert_eq!(language_of(&session(QUOTE_SOURCE, "", 40.0, 0)), "Quotes");
+        assert_eq!(language_of(&session(DRILL_SOURCE, "", 40.0, 0)), "Drills");
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let mut history = SessionHistory::new("test_report_history.json");
+        // Two ISO weeks apart: 2024-01-01 (Monday) and 2024-01-15
+        history.add_session(session(FILE_SOURCE, "main.rs", 40.0, 1_704_110_400));
+        let mut quote = session(QUOTE_SOURCE, "", 50.0, 1_705_320_000);
+        quote.key_stats.insert('|', KeyStat { hits: 6, misses: 4 });
+        quote.key_stats.insert(
+            'e',
+            KeyStat {
+                hits: 50,
+                misses: 0,
+            },
+        );
+        history.add_session(quote);
+
+        let report = build(
+            &history,
+            &ScoringConfig::default(),
+            &Locale::default(),
+            Format::Markdown,
+        );
+        assert!(report.contains("## All-time statistics (2 sessions)"));
+        assert!(report.contains("| Rust | 1 | 40 |"));
+        assert!(report.contains("| Quotes | 1 | 50 |"));
+        assert!(report.contains("| +10.0 |"));
+        assert!(report.contains("| '\\|' | 10 | 4 | 40.0% |"));
+        assert!(!report.contains("'e'"));
+        assert!(!report.contains("Score"));
+
+        let german = build(
+            &history,
+            &ScoringConfig::default(),
+            &Locale::parse("de-DE").unwrap(),
+            Format::Markdown,
+        );
+        assert!(german.contains("| +10,0 |"));
+        assert!(german.contains("Avg Accuracy: 98,0%"));
+        assert!(!report.contains("By setup"));
+    }
+
+    #[test]
+    fn test_setup_breakdown() {
+        let mut history = SessionHistory::new("test_report_history.json");
+        history.add_session(session(FILE_SOURCE, "main.rs", 40.0, 1_704_110_400));
+        for (keyboard, wpm) in [(None, 50.0), (Some("Keychron K2"), 60.0)] {
+            let mut typed = session(FILE_SOURCE, "main.rs", wpm, 1_705_320_000);
+            typed.context = Some(SessionContext {
+                keyboard: keyboard.map(str::to_string),
+                window_size: Some([1280, 720]),
+                font_size: 28.0,
+                theme: "default".to_string(),
+                app_version: "0.1.0".to_string(),
+            });
+            history.add_session(typed);
+        }
+
+        let report = build(
+            &history,
+            &ScoringConfig::default(),
+            &Locale::default(),
+            Format::Markdown,
+        );
+        assert!(report.contains("## By setup"));
+        assert!(report.contains("| Keychron K2, 28 px | 1 | 60 |"));
+        assert!(report.contains("| Unnamed keyboard, 28 px | 1 | 50 |"));
+        assert!(report.contains("| Not recorded | 1 | 40 |"));
+    }
+
+    #[test]
+    fn test_empty_text_report() {
+        let history = SessionHistory::new("test_report_history.json");
+        let report = build(
+            &history,
+            &ScoringConfig::default(),
+            &Locale::default(),
+            Format::Text,
+        );
+        assert!(report.starts_with("CARGOTAP PRACTICE REPORT"));
+        assert!(report.contains("No sessions recorded yet."));
+    }
+}
@@ -0,0 +1,187 @@
+//! Shareable result card
+//!
+//! C on the results screen copies a small framed card with the session's file,
+//! duration, speed, accuracy, date and the app version to the clipboard. It is
+//! wrapped in a Markdown code block so chats and READMEs keep its columns lined up.
+//! With `ascii_ui` the frame is drawn with `+`, `-` and `|`, and any other
+//! non-ASCII character (a locale's separators, a file name) becomes `?`.
+
+use anyhow::Result;
+
+use crate::locale::Locale;
+use crate::session_state::{SessionStats, format_clock};
+
+/// Longest file path shown; longer ones keep their end, which names the file
+const MAX_FILE_CHARS: usize = 40;
+
+/// Characters the frame is drawn with
+struct Frame {
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+    horizontal: char,
+    vertical: char,
+    ellipsis: &'static str,
+}
+
+const UNICODE_FRAME: Frame = Frame {
+    top_left: '╭',
+    top_right: '╮',
+    bottom_left: '╰',
+    bottom_right: '╯',
+    horizontal: '─',
+    vertical: '│',
+    ellipsis: "…",
+};
+
+const ASCII_FRAME: Frame = Frame {
+    top_left: '+',
+    top_right: '+',
+    bottom_left: '+',
+    bottom_right: '+',
+    horizontal: '-',
+    vertical: '|',
+    ellipsis: "...",
+};
+
+/// The card for `stats`, ready to paste
+pub fn render(stats: &SessionStats, locale: &Locale, version: &str, ascii: bool) -> String {
+    let frame = if ascii { &ASCII_FRAME } else { &UNICODE_FRAME };
+    let rows = [
+        ("File", shorten(&stats.file_path, frame.ellipsis)),
+        ("Duration", format_clock(stats.time_elapsed_secs)),
+        (
+            "Speed",
+            format!(
+                "{} WPM ({} CPM)",
+                locale.decimal(stats.words_per_minute, 0),
+                locale.decimal(stats.chars_per_minute, 0)
+            ),
+        ),
+        ("Accuracy", locale.percent(stats.accuracy, 1)),
+        ("Date", locale.timestamp(stats.timestamp)),
+        ("Version", format!("CargoTap {}", version)),
+    ];
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|(label, value)| format!("{:<label_width$}  {}", label, value))
+        .collect();
+    if ascii {
+        for line in &mut lines {
+            *line = line
+                .chars()
+                .map(|ch| if ch.is_ascii() { ch } else { '?' })
+                .collect();
+        }
+    }
+
+    let title = " CargoTap result ";
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .chain(std::iter::once(title.len()))
+        .max()
+        .unwrap_or(0);
+    let horizontal = |count: usize| frame.horizontal.to_string().repeat(count);
+
+    let mut card = String::from("```text\n");
+    card.push_str(&format!(
+        "{}{}{}{}{}\n",
+        frame.top_left,
+        horizontal(1),
+        title,
+        horizontal(width + 1 - title.len()),
+        frame.top_right
+    ));
+    for line in &lines {
+        let padding = width - line.chars().count();
+        card.push_str(&format!(
+            "{} {}{} {}\n",
+            frame.vertical,
+            line,
+            " ".repeat(padding),
+            frame.vertical
+        ));
+    }
+    card.push_str(&format!(
+        "{}{}{}\n",
+        frame.bottom_left,
+        horizontal(width + 2),
+        frame.bottom_right
+    ));
+    card.push_str("```\n");
+    card
+}
+
+/// `path` cut to `MAX_FILE_CHARS` characters from the end
+fn shorten(path: &str, ellipsis: &str) -> String {
+    let count = path.chars().count();
+    if count <= MAX_FILE_CHARS {
+        return path.to_string();
+    }
+    let keep = MAX_FILE_CHARS - ellipsis.chars().count();
+    let tail: String = path.chars().skip(count - keep).collect();
+    format!("{}{}", ellipsis, tail)
+}
+
+/// Puts `text` on the system clipboard. The clipboard is kept by the caller:
+/// on X11 and Wayland the text is served by the process that set it, so it would
+/// be gone as soon as the `Clipboard` was dropped.
+pub fn copy(clipboard: &mut Option<arboard::Clipboard>, text: &str) -> Result<()> {
+    let clipboard = match clipboard {
+        Some(clipboard) => clipboard,
+        None => clipboard.insert(arboard::Clipboard::new()?),
+    };
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(file_path: &str) -> SessionStats {
+        let mut stats = SessionStats::new(600, 125.0, 0, 600, 12, file_path.to_string());
+        stats.accuracy = 98.04;
+        stats
+    }
+
+    #[test]
+    fn test_card_rows_line_up() {
+        let card = render(&stats("src/main.rs"), &Locale::default(), "0.1.0", false);
+        let lines: Vec<&str> = card.lines().collect();
+        assert_eq!(lines[0], "```text");
+        assert_eq!(lines[lines.len() - 1], "```");
+        assert!(lines[1].starts_with("╭─ CargoTap result ─"));
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("│ File      src/main.rs "))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("│ Duration  02:05 "))
+        );
+        assert!(lines.iter().any(|line| line.contains("58 WPM (288 CPM)")));
+        assert!(lines.iter().any(|line| line.contains("98.0%")));
+        assert!(lines.iter().any(|line| line.contains("CargoTap 0.1.0")));
+        let frame = &lines[1..lines.len() - 1];
+        let width = frame[0].chars().count();
+        assert!(frame.iter().all(|line| line.chars().count() == width));
+    }
+
+    #[test]
+    fn test_ascii_card() {
+        let card = render(&stats("src/zürich.rs"), &Locale::default(), "0.1.0", true);
+        assert!(card.is_ascii());
+        assert!(card.contains("+- CargoTap result -"));
+        assert!(card.contains("| File      src/z?rich.rs"));
+
+        let long = format!("{}/main.rs", "deeply/nested".repeat(5));
+        let card = render(&stats(&long), &Locale::default(), "0.1.0", true);
+        assert!(card.contains(&format!("...{}", &long[long.len() - 37..])));
+    }
+}
@@ -0,0 +1,104 @@
+//! Review rounds for mistyped lines
+//!
+//! After a session, the lines where mistakes were made (mismatched keys or
+//! corrections) can be typed again on their own with V on the results screen,
+//! each `review_repetitions` times in a row. Lines still mistyped in a round make
+//! up the next one, until a round is typed without errors. Each round is saved to the history like any session, linked to
+//! the session it reviews.
+
+use crate::code_state::CodeState;
+
+/// Source name recorded for review sessions
+pub const REVIEW_SOURCE: &str = "review";
+
+/// The lines being re-typed and the session they came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    pub lines: Vec<String>,
+    /// Timestamp of the session the lines were mistyped in
+    pub review_of: u64,
+    /// 1 for the first round, counting up while lines are still mistyped
+    pub round: usize,
+    /// Times each line is typed in a round
+    pub repetitions: usize,
+}
+
+impl Review {
+    /// A review of `lines`, or None if there is nothing to re-type
+    pub fn new(lines: Vec<String>, review_of: u64, repetitions: usize) -> Option<Self> {
+        (!lines.is_empty()).then_some(Self {
+            lines,
+            review_of,
+            round: 1,
+            repetitions: repetitions.max(1),
+        })
+    }
+
+    /// The next round over the lines still mistyped, or None once a round was error-free
+    pub fn next_round(&self, lines: Vec<String>) -> Option<Self> {
+        let mut next = Self::new(lines, self.review_of, self.repetitions)?;
+        next.round = self.round + 1;
+        Some(next)
+    }
+
+    /// What is typed in the round: each line its number of times, one after another
+    pub fn text(&self) -> String {
+        let repeated: Vec<&str> = self
+            .lines
+            .iter()
+            .flat_map(|line| std::iter::repeat_n(line.as_str(), self.repetitions))
+            .collect();
+        repeated.join("\n")
+    }
+}
+
+/// Text of the 1-based `error_lines` of `code`, in order and without repeats
+///
+/// Indentation is left out (Tab and Enter type it anyway), and so are lines that
+/// are blank or no longer loaded.
+pub fn mistyped_lines(code: &CodeState, error_lines: &[usize]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for &line in error_lines {
+        let Some(text) = code.line_text(line) else {
+            continue;
+        };
+        let text = text.trim();
+        if !text.is_empty() && !lines.iter().any(|seen| seen == text) {
+            lines.push(text.to_string());
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mistyped_lines() {
+        let code = CodeState::new("fn main() {\n    let x = 1;\n\n    let x = 1;\n}\n".to_string());
+        assert_eq!(mistyped_lines(&code, &[2, 3, 4, 5, 9]), ["let x = 1;", "}"]);
+    }
+
+    #[test]
+    fn test_rounds_until_error_free() {
+        assert!(Review::new(Vec::new(), 7, 1).is_none());
+
+        let review = Review::new(vec!["a".to_string(), "b".to_string()], 7, 1).unwrap();
+        assert_eq!(review.text(), "a\nb");
+        let next = review.next_round(vec!["b".to_string()]).unwrap();
+        assert_eq!((next.round, next.review_of), (2, 7));
+        assert!(next.next_round(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_lines_repeat_in_a_round() {
+        let review = Review::new(vec!["a".to_string(), "b".to_string()], 7, 2).unwrap();
+        assert_eq!(review.text(), "a\na\nb\nb");
+        // Still repeated in later rounds, and at least once
+        let next = review.next_round(vec!["b".to_string()]).unwrap();
+        assert_eq!(next.text(), "b\nb");
+        let once = Review::new(vec!["a".to_string()], 7, 0).unwrap();
+        assert_eq!(once.text(), "a");
+    }
+}
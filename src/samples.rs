@@ -0,0 +1,116 @@
+//! Built-in practice samples
+//!
+//! A small corpus of varied texts is embedded so there is something to type
+//! besides the demo before any files are picked. Samples are addressed with a
+//! `builtin://` path and their progress is saved like that of any other file.
+
+use std::fmt;
+
+/// Path prefix used for built-in samples
+pub const SAMPLE_PREFIX: &str = "builtin://";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug)]
+pub struct Sample {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub difficulty: Difficulty,
+    contents: &'static str,
+}
+
+impl Sample {
+    /// The path the sample is loaded and tracked under
+    pub fn path(&self) -> String {
+        format!("{}{}", SAMPLE_PREFIX, self.name)
+    }
+}
+
+pub static SAMPLES: &[Sample] = &[
+    Sample {
+        name: "short.rs",
+        description: "Short Rust function",
+        difficulty: Difficulty::Easy,
+        contents: include_str!("../samples/short.rs"),
+    },
+    Sample {
+        name: "notes.md",
+        description: "Markdown checklist",
+        difficulty: Difficulty::Easy,
+        contents: include_str!("../samples/notes.md"),
+    },
+    Sample {
+        name: "fizzbuzz.py",
+        description: "Python script",
+        difficulty: Difficulty::Medium,
+        contents: include_str!("../samples/fizzbuzz.py"),
+    },
+    Sample {
+        name: "settings.json",
+        description: "JSON document",
+        difficulty: Difficulty::Medium,
+        contents: include_str!("../samples/settings.json"),
+    },
+    Sample {
+        name: "long.rs",
+        description: "Longer Rust module with structs and traits",
+        difficulty: Difficulty::Hard,
+        contents: include_str!("../samples/long.rs"),
+    },
+    Sample {
+        name: "symbols.rs",
+        description: "Symbol-heavy Rust",
+        difficulty: Difficulty::Hard,
+        contents: include_str!("../samples/symbols.rs"),
+    },
+];
+
+/// Returns the contents of a built-in sample, or None if `path` is not a sample path
+pub fn read(path: &str) -> Option<&'static str> {
+    let name = path.strip_prefix(SAMPLE_PREFIX)?;
+    SAMPLES
+        .iter()
+        .find(|sample| sample.name == name)
+        .map(|sample| sample.contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::char_utils::is_typeable_on_us_keyboard;
+
+    #[test]
+    fn test_every_sample_reads_back() {
+        for sample in SAMPLES {
+            let contents = read(&sample.path()).unwrap();
+            assert!(!contents.trim().is_empty(), "{} is empty", sample.name);
+            assert!(
+                contents.chars().all(is_typeable_on_us_keyboard),
+                "{} has characters that can't be typed",
+                sample.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_paths() {
+        assert!(read("builtin://missing.rs").is_none());
+        assert!(read("short.rs").is_none());
+    }
+}
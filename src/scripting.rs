@@ -0,0 +1,283 @@
+//! Optional event scripting through an embedded Rhai engine
+//!
+//! When `scripting.enabled` is set, the script at `scripting.script_path` is
+//! compiled once at startup. It may define any of these hooks:
+//!
+//! - `on_char_accepted(ch)`
+//! - `on_char_rejected(expected, typed)`
+//! - `on_session_finished(stats)` (stats is a map with the SessionStats fields)
+//!
+//! Scripts can call `show_toast(msg)`, `write_file(path, text)`,
+//! `append_file(path, text)` and `http_post(url, body)`.
+
+use anyhow::{Context, Result};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::sync::{
+    Arc, Mutex,
+    mpsc::{self, SyncSender, TrySendError},
+};
+
+use crate::session_state::SessionStats;
+
+/// Upper bound on operations per hook call so a runaway script can't freeze the UI
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Posts waiting for the request thread; more than this are dropped, so a hook that
+/// posts on every key can't pile up requests behind a slow endpoint
+const HTTP_QUEUE: usize = 32;
+
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    hooks: HashSet<String>,
+    toasts: Arc<Mutex<Vec<String>>>,
+    /// Why the last hook failed, until the app shows it
+    error: Option<String>,
+}
+
+impl ScriptHost {
+    /// Compiles the script at `path` and registers the scripting API
+    pub fn load(path: &str) -> Result<Self> {
+        let source =
+            fs::read_to_string(path).with_context(|| format!("Failed to read script: {}", path))?;
+        Self::from_source(&source)
+    }
+
+    pub fn from_source(source: &str) -> Result<Self> {
+        let toasts = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register_api(&mut engine, toasts.clone());
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow::anyhow!("Failed to compile script: {}", e))?;
+
+        let hooks = ast.iter_functions().map(|f| f.name.to_string()).collect();
+
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| anyhow::anyhow!("Failed to run script: {}", e))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            hooks,
+            toasts,
+            error: None,
+        })
+    }
+
+    pub fn on_char_accepted(&mut self, ch: char) {
+        self.call("on_char_accepted", (ch,));
+    }
+
+    pub fn on_char_rejected(&mut self, expected: char, typed: char) {
+        self.call("on_char_rejected", (expected, typed));
+    }
+
+    pub fn on_session_finished(&mut self, stats: &SessionStats) {
+        match rhai::serde::to_dynamic(stats) {
+            Ok(stats) => self.call("on_session_finished", (stats,)),
+            Err(e) => log::warn!("Failed to convert session stats for script: {}", e),
+        }
+    }
+
+    /// Returns and clears toasts requested by the script since the last call
+    pub fn take_toasts(&self) -> Vec<String> {
+        self.toasts
+            .lock()
+            .map(|mut toasts| std::mem::take(&mut *toasts))
+            .unwrap_or_default()
+    }
+
+    /// Returns and clears the error of the last failed hook
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn call(&mut self, hook: &str, args: impl FuncArgs) {
+        if !self.hooks.contains(hook) {
+            return;
+        }
+
+        // The top-level statements already ran once in from_source; running them again
+        // on every keystroke would redo the script's setup each time
+        let options = CallFnOptions::new().eval_ast(false);
+        if let Err(e) = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            hook,
+            args,
+        ) {
+            log::warn!("Script hook '{}' failed: {}", hook, e);
+            // A hook that ran out of operations once would stall every keystroke after it
+            if let EvalAltResult::ErrorTooManyOperations(_) = *e {
+                self.hooks.remove(hook);
+                self.error = Some(format!(
+                    "Script hook {} was stopped: too many operations",
+                    hook
+                ));
+            } else {
+                self.error = Some(format!("Script hook {} failed: {}", hook, e));
+            }
+        }
+    }
+}
+
+fn register_api(engine: &mut Engine, toasts: Arc<Mutex<Vec<String>>>) {
+    engine.register_fn("show_toast", move |message: &str| {
+        if let Ok(mut toasts) = toasts.lock() {
+            toasts.push(message.to_string());
+        }
+    });
+
+    engine.register_fn(
+        "write_file",
+        |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+            fs::write(path, text).map_err(|e| e.to_string().into())
+        },
+    );
+
+    engine.register_fn(
+        "append_file",
+        |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .map_err(|e| e.to_string().into())
+        },
+    );
+
+    // Requests run on a background thread so a slow endpoint never stalls typing
+    let posts = spawn_http_worker();
+    engine.register_fn("http_post", move |url: &str, body: &str| {
+        let Some(posts) = &posts else {
+            log::warn!("Script http_post to {} skipped: no request thread", url);
+            return;
+        };
+        match posts.try_send((url.to_string(), body.to_string())) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                log::warn!("Script http_post to {} dropped: too many waiting", url)
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                log::warn!(
+                    "Script http_post to {} skipped: request thread stopped",
+                    url
+                )
+            }
+        }
+    });
+}
+
+/// Starts the thread that sends the script's posts one after another. It ends when
+/// the engine holding the sender is dropped.
+fn spawn_http_worker() -> Option<SyncSender<(String, String)>> {
+    let (sender, receiver) = mpsc::sync_channel::<(String, String)>(HTTP_QUEUE);
+    let spawned = std::thread::Builder::new()
+        .name("script-http".to_string())
+        .spawn(move || {
+            for (url, body) in receiver {
+                if let Err(e) = ureq::post(&url)
+                    .set("Content-Type", "application/json")
+                    .send_string(&body)
+                {
+                    log::warn!("Script http_post to {} failed: {}", url, e);
+                }
+            }
+        });
+    match spawned {
+        Ok(_) => Some(sender),
+        Err(e) => {
+            log::warn!("Couldn't start the script request thread: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_hooks_are_ignored() {
+        let mut host = ScriptHost::from_source("let x = 1;").unwrap();
+        host.on_char_accepted('a');
+        host.on_char_rejected('a', 'b');
+        assert!(host.take_toasts().is_empty());
+    }
+
+    #[test]
+    fn test_hooks_can_show_toasts() {
+        let script = r#"
+            fn on_char_rejected(expected, typed) {
+                show_toast("expected " + expected + " got " + typed);
+            }
+            fn on_session_finished(stats) {
+                show_toast("done " + stats.chars_typed);
+            }
+        "#;
+        let mut host = ScriptHost::from_source(script).unwrap();
+
+        host.on_char_rejected('a', 'b');
+        let stats = SessionStats::new(42, 60.0, 0, 42, 0, "test.rs".to_string());
+        host.on_session_finished(&stats);
+
+        assert_eq!(
+            host.take_toasts(),
+            vec!["expected a got b".to_string(), "done 42".to_string()]
+        );
+        assert!(host.take_toasts().is_empty());
+    }
+
+    #[test]
+    fn test_top_level_runs_once() {
+        let script = r#"
+            show_toast("loaded");
+            fn on_char_accepted(ch) {
+                show_toast("key " + ch);
+            }
+        "#;
+        let mut host = ScriptHost::from_source(script).unwrap();
+        host.on_char_accepted('a');
+        host.on_char_accepted('b');
+        assert_eq!(
+            host.take_toasts(),
+            vec![
+                "loaded".to_string(),
+                "key a".to_string(),
+                "key b".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runaway_script_is_stopped() {
+        let script = "fn on_char_accepted(ch) { loop {} }";
+        let mut host = ScriptHost::from_source(script).unwrap();
+        host.on_char_accepted('a');
+        let error = host
+            .take_error()
+            .expect("the runaway hook reports an error");
+        assert!(error.contains("on_char_accepted was stopped"), "{error}");
+
+        // The hook is disabled, so the next key doesn't run it again
+        host.on_char_accepted('b');
+        assert_eq!(host.take_error(), None);
+    }
+
+    #[test]
+    fn test_compile_error_is_reported() {
+        assert!(ScriptHost::from_source("fn broken( {").is_err());
+    }
+}
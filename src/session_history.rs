@@ -3,8 +3,12 @@
 //! This module provides functionality to persist session statistics across
 //! application runs and analyze performance trends over time.
 
-use crate::session_state::SessionStats;
+use crate::aggregators::{self, Aggregator};
+use crate::locale::Locale;
+use crate::session_state::{KeyStat, SequenceStat, SessionStats, TransitionStat};
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -19,6 +23,83 @@ pub struct SessionHistory {
     storage_path: PathBuf,
 }
 
+/// Local calendar date of a session timestamp (Unix seconds)
+pub fn local_date(timestamp: u64) -> Option<NaiveDate> {
+    DateTime::from_timestamp(timestamp as i64, 0).map(|utc| utc.with_timezone(&Local).date_naive())
+}
+
+/// Which part of the history a statistics view covers
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HistoryRange {
+    #[default]
+    All,
+    /// The most recent N sessions
+    Recent(usize),
+}
+
+impl HistoryRange {
+    /// Ranges offered by the statistics screen, widest first
+    pub const CHOICES: [HistoryRange; 4] = [
+        HistoryRange::All,
+        HistoryRange::Recent(50),
+        HistoryRange::Recent(10),
+        HistoryRange::Recent(1),
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::CHOICES.iter().position(|r| *r == self).unwrap_or(0);
+        Self::CHOICES[(index + 1) % Self::CHOICES.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        let count = Self::CHOICES.len();
+        let index = Self::CHOICES.iter().position(|r| *r == self).unwrap_or(0);
+        Self::CHOICES[(index + count - 1) % count]
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            HistoryRange::All => "all sessions".to_string(),
+            HistoryRange::Recent(1) => "last session".to_string(),
+            HistoryRange::Recent(count) => format!("last {} sessions", count),
+        }
+    }
+}
+
+/// Per-minute speed of earlier sessions on a file, to set a new one against
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedCurves {
+    /// Mean WPM in each minute, over the sessions that lasted that long
+    pub average: Vec<f64>,
+    /// The curve of the fastest session
+    pub best: Vec<f64>,
+    /// Overall WPM of the fastest session
+    pub best_wpm: f64,
+    /// How many sessions the average is over
+    pub sessions: usize,
+}
+
+/// What the statistics screen shows; Tab goes through them in order
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StatsView {
+    #[default]
+    Summary,
+    /// Keys colored by how often they were typed and missed
+    Heatmap,
+    /// Keys colored by how long they take, with the slowest bigrams and words
+    Speed,
+}
+
+impl StatsView {
+    pub fn next(self) -> Self {
+        match self {
+            StatsView::Summary => StatsView::Heatmap,
+            StatsView::Heatmap => StatsView::Speed,
+            StatsView::Speed => StatsView::Summary,
+        }
+    }
+}
+
 /// Summary statistics across multiple sessions
 #[derive(Debug, Clone)]
 pub struct SessionSummary {
@@ -53,14 +134,9 @@ impl SessionHistory {
         }
     }
 
-    /// Creates a SessionHistory with the default storage path
+    /// Creates a SessionHistory with the default storage path (per profile)
     pub fn default() -> Self {
-        let storage_path = if let Some(data_dir) = dirs::data_dir() {
-            data_dir.join("cargo_tap").join("session_history.json")
-        } else {
-            PathBuf::from("cargo_tap_session_history.json")
-        };
-        Self::new(storage_path)
+        Self::new(crate::profile::data_file("session_history.json"))
     }
 
     /// Loads session history from disk
@@ -105,6 +181,14 @@ impl SessionHistory {
         self.sessions.iter().rev().take(count).collect()
     }
 
+    /// Gets the sessions recorded on `date` (local time)
+    pub fn get_sessions_on(&self, date: NaiveDate) -> Vec<&SessionStats> {
+        self.sessions
+            .iter()
+            .filter(|s| local_date(s.timestamp) == Some(date))
+            .collect()
+    }
+
     /// Gets sessions for a specific file
     pub fn get_sessions_for_file(&self, file_path: &str) -> Vec<&SessionStats> {
         self.sessions
@@ -113,6 +197,42 @@ impl SessionHistory {
             .collect()
     }
 
+    /// Per-minute speed curves of the sessions on `file_path` other than the one at
+    /// `except` (timestamp). None when there are none with a timeline.
+    pub fn speed_curves(&self, file_path: &str, except: u64) -> Option<SpeedCurves> {
+        let sessions: Vec<(&SessionStats, Vec<f64>)> = self
+            .get_sessions_for_file(file_path)
+            .into_iter()
+            .filter(|session| session.timestamp != except)
+            .map(|session| (session, session.wpm_curve()))
+            .filter(|(_, curve)| !curve.is_empty())
+            .collect();
+        let (best, best_curve) = sessions
+            .iter()
+            .max_by(|a, b| a.0.words_per_minute.total_cmp(&b.0.words_per_minute))?;
+
+        let minutes = sessions
+            .iter()
+            .map(|(_, curve)| curve.len())
+            .max()
+            .unwrap_or(0);
+        let average = (0..minutes)
+            .map(|minute| {
+                let speeds: Vec<f64> = sessions
+                    .iter()
+                    .filter_map(|(_, curve)| curve.get(minute).copied())
+                    .collect();
+                speeds.iter().sum::<f64>() / speeds.len() as f64
+            })
+            .collect();
+        Some(SpeedCurves {
+            average,
+            best: best_curve.clone(),
+            best_wpm: best.words_per_minute,
+            sessions: sessions.len(),
+        })
+    }
+
     /// Gets the session with the best CPM
     pub fn get_best_cpm_session(&self) -> Option<&SessionStats> {
         self.sessions
@@ -134,147 +254,126 @@ impl SessionHistory {
             .max_by(|a, b| a.accuracy.partial_cmp(&b.accuracy).unwrap())
     }
 
+    /// Gets the session with the best accuracy-weighted score
+    pub fn get_best_score_session(&self, accuracy_exponent: f64) -> Option<&SessionStats> {
+        self.sessions.iter().max_by(|a, b| {
+            a.score_or_compute(accuracy_exponent)
+                .total_cmp(&b.score_or_compute(accuracy_exponent))
+        })
+    }
+
+    /// Best score before the newest session, to tell whether the session just
+    /// saved beat it; None when there is no earlier session
+    pub fn previous_best_score(&self, accuracy_exponent: f64) -> Option<f64> {
+        let (_, earlier) = self.sessions.split_last()?;
+        earlier
+            .iter()
+            .map(|session| session.score_or_compute(accuracy_exponent))
+            .max_by(f64::total_cmp)
+    }
+
     /// Computes summary statistics for all sessions
     pub fn get_summary(&self) -> SessionSummary {
-        if self.sessions.is_empty() {
-            return SessionSummary {
-                total_sessions: 0,
-                total_chars: 0,
-                total_time: 0.0,
-                avg_cpm: 0.0,
-                avg_wpm: 0.0,
-                avg_accuracy: 0.0,
-                best_cpm: 0.0,
-                best_wpm: 0.0,
-                best_accuracy: 0.0,
-                total_errors: 0,
-            };
-        }
+        let sessions: Vec<_> = self.sessions.iter().rev().collect();
+        summarize(&sessions)
+    }
 
-        let total_sessions = self.sessions.len();
-        let total_chars = self.sessions.iter().map(|s| s.chars_typed).sum();
-        let total_time = self.sessions.iter().map(|s| s.time_elapsed_secs).sum();
-        let total_errors = self.sessions.iter().map(|s| s.errors).sum();
+    /// Runs `aggregator` over the sessions in `range`; None when there are none
+    pub fn aggregate(&self, aggregator: &dyn Aggregator, range: HistoryRange) -> Option<f64> {
+        aggregator.compute(&self.get_sessions_in_range(range))
+    }
 
-        let avg_cpm = self
-            .sessions
-            .iter()
-            .map(|s| s.chars_per_minute)
-            .sum::<f64>()
-            / total_sessions as f64;
-        let avg_wpm = self
+    /// Computes summary statistics for the last N sessions
+    pub fn get_recent_summary(&self, count: usize) -> SessionSummary {
+        let recent_sessions: Vec<_> = self.get_recent_sessions(count);
+        summarize(&recent_sessions)
+    }
+
+    /// Computes summary statistics for sessions of one source ("file", "quote", ...)
+    pub fn get_summary_for_source(&self, source: &str) -> SessionSummary {
+        let sessions: Vec<_> = self
             .sessions
             .iter()
-            .map(|s| s.words_per_minute)
-            .sum::<f64>()
-            / total_sessions as f64;
-        let avg_accuracy =
-            self.sessions.iter().map(|s| s.accuracy).sum::<f64>() / total_sessions as f64;
-
-        let best_cpm = self
-            .get_best_cpm_session()
-            .map(|s| s.chars_per_minute)
-            .unwrap_or(0.0);
-        let best_wpm = self
-            .get_best_wpm_session()
-            .map(|s| s.words_per_minute)
-            .unwrap_or(0.0);
-        let best_accuracy = self
-            .get_best_accuracy_session()
-            .map(|s| s.accuracy)
-            .unwrap_or(0.0);
-
-        SessionSummary {
-            total_sessions,
-            total_chars,
-            total_time,
-            avg_cpm,
-            avg_wpm,
-            avg_accuracy,
-            best_cpm,
-            best_wpm,
-            best_accuracy,
-            total_errors,
+            .filter(|s| s.source == source)
+            .collect();
+        summarize(&sessions)
+    }
+
+    /// Computes summary statistics per group, with `key` picking each session's group
+    pub fn summarize_by<K: Ord>(
+        &self,
+        key: impl Fn(&SessionStats) -> K,
+    ) -> BTreeMap<K, SessionSummary> {
+        let mut groups: BTreeMap<K, Vec<&SessionStats>> = BTreeMap::new();
+        for session in &self.sessions {
+            groups.entry(key(session)).or_default().push(session);
         }
+        groups
+            .into_iter()
+            .map(|(key, sessions)| (key, summarize(&sessions)))
+            .collect()
     }
 
-    /// Computes summary statistics for the last N sessions
-    pub fn get_recent_summary(&self, count: usize) -> SessionSummary {
-        if self.sessions.is_empty() {
-            return SessionSummary {
-                total_sessions: 0,
-                total_chars: 0,
-                total_time: 0.0,
-                avg_cpm: 0.0,
-                avg_wpm: 0.0,
-                avg_accuracy: 0.0,
-                best_cpm: 0.0,
-                best_wpm: 0.0,
-                best_accuracy: 0.0,
-                total_errors: 0,
-            };
+    /// Minutes practiced on each day (local time) that has sessions
+    pub fn minutes_by_day(&self) -> BTreeMap<NaiveDate, f64> {
+        self.summarize_by(|session| local_date(session.timestamp))
+            .into_iter()
+            .filter_map(|(date, summary)| Some((date?, summary.total_time / 60.0)))
+            .collect()
+    }
+
+    /// Gets the sessions covered by `range`, most recent first
+    pub fn get_sessions_in_range(&self, range: HistoryRange) -> Vec<&SessionStats> {
+        match range {
+            HistoryRange::All => self.sessions.iter().rev().collect(),
+            HistoryRange::Recent(count) => self.get_recent_sessions(count),
         }
+    }
 
-        let recent_sessions: Vec<_> = self.get_recent_sessions(count);
-        let total_sessions = recent_sessions.len();
-
-        if total_sessions == 0 {
-            return SessionSummary {
-                total_sessions: 0,
-                total_chars: 0,
-                total_time: 0.0,
-                avg_cpm: 0.0,
-                avg_wpm: 0.0,
-                avg_accuracy: 0.0,
-                best_cpm: 0.0,
-                best_wpm: 0.0,
-                best_accuracy: 0.0,
-                total_errors: 0,
-            };
+    /// Adds up per-character hits and misses over the sessions in `range`
+    pub fn get_key_stats(&self, range: HistoryRange) -> BTreeMap<char, KeyStat> {
+        let mut totals: BTreeMap<char, KeyStat> = BTreeMap::new();
+        for session in self.get_sessions_in_range(range) {
+            for (ch, stat) in &session.key_stats {
+                totals.entry(*ch).or_default().add(*stat);
+            }
         }
+        totals
+    }
 
-        let total_chars = recent_sessions.iter().map(|s| s.chars_typed).sum();
-        let total_time = recent_sessions.iter().map(|s| s.time_elapsed_secs).sum();
-        let total_errors = recent_sessions.iter().map(|s| s.errors).sum();
+    /// Adds up the timing of same-finger bigrams and row jumps over the sessions in `range`
+    pub fn get_transition_stats(&self, range: HistoryRange) -> BTreeMap<String, TransitionStat> {
+        let mut totals: BTreeMap<String, TransitionStat> = BTreeMap::new();
+        for session in self.get_sessions_in_range(range) {
+            for (bigram, stat) in &session.transitions {
+                totals.entry(bigram.clone()).or_default().add(*stat);
+            }
+        }
+        totals
+    }
 
-        let avg_cpm = recent_sessions
-            .iter()
-            .map(|s| s.chars_per_minute)
-            .sum::<f64>()
-            / total_sessions as f64;
-        let avg_wpm = recent_sessions
-            .iter()
-            .map(|s| s.words_per_minute)
-            .sum::<f64>()
-            / total_sessions as f64;
-        let avg_accuracy =
-            recent_sessions.iter().map(|s| s.accuracy).sum::<f64>() / total_sessions as f64;
+    /// Adds up the timing and misses of every bigram over the sessions in `range`
+    pub fn get_bigram_stats(&self, range: HistoryRange) -> BTreeMap<String, SequenceStat> {
+        self.sum_sequences(range, |session| &session.bigrams)
+    }
 
-        let best_cpm = recent_sessions
-            .iter()
-            .map(|s| s.chars_per_minute)
-            .fold(0.0_f64, |acc, cpm| acc.max(cpm));
-        let best_wpm = recent_sessions
-            .iter()
-            .map(|s| s.words_per_minute)
-            .fold(0.0_f64, |acc, wpm| acc.max(wpm));
-        let best_accuracy = recent_sessions
-            .iter()
-            .map(|s| s.accuracy)
-            .fold(0.0_f64, |acc, acc_val| acc.max(acc_val));
-
-        SessionSummary {
-            total_sessions,
-            total_chars,
-            total_time,
-            avg_cpm,
-            avg_wpm,
-            avg_accuracy,
-            best_cpm,
-            best_wpm,
-            best_accuracy,
-            total_errors,
+    /// Adds up the timing and misses of the words typed over the sessions in `range`
+    pub fn get_word_stats(&self, range: HistoryRange) -> BTreeMap<String, SequenceStat> {
+        self.sum_sequences(range, |session| &session.words)
+    }
+
+    fn sum_sequences(
+        &self,
+        range: HistoryRange,
+        sequences: impl Fn(&SessionStats) -> &BTreeMap<String, SequenceStat>,
+    ) -> BTreeMap<String, SequenceStat> {
+        let mut totals: BTreeMap<String, SequenceStat> = BTreeMap::new();
+        for session in self.get_sessions_in_range(range) {
+            for (sequence, stat) in sequences(session) {
+                totals.entry(sequence.clone()).or_default().add(*stat);
+            }
         }
+        totals
     }
 
     /// Clears all session history
@@ -318,8 +417,8 @@ impl SessionHistory {
         }
     }
 
-    /// Formats a detailed statistics report
-    pub fn format_statistics_report(&self) -> String {
+    /// Formats a detailed statistics report, with numbers and dates written for `locale`
+    pub fn format_statistics_report(&self, locale: &Locale) -> String {
         if self.sessions.is_empty() {
             return "No sessions recorded yet. Start typing to track your progress!".to_string();
         }
@@ -335,30 +434,41 @@ impl SessionHistory {
 
         report.push_str(&format!(
             "📊 ALL-TIME STATS ({} sessions)\n",
-            summary.total_sessions
+            locale.count(summary.total_sessions)
         ));
         report.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
-        report.push_str(&format!("  Total Characters: {}\n", summary.total_chars));
         report.push_str(&format!(
-            "  Total Time: {:.1} minutes\n",
-            summary.total_time / 60.0
+            "  Total Characters: {}\n",
+            locale.count(summary.total_chars)
+        ));
+        report.push_str(&format!(
+            "  Total Time: {} minutes\n",
+            locale.decimal(summary.total_time / 60.0, 1)
+        ));
+        report.push_str(&format!(
+            "  Avg Speed: {} CPM / {} WPM\n",
+            locale.decimal(summary.avg_cpm, 0),
+            locale.decimal(summary.avg_wpm, 0)
         ));
         report.push_str(&format!(
-            "  Avg Speed: {:.0} CPM / {:.0} WPM\n",
-            summary.avg_cpm, summary.avg_wpm
+            "  Avg Accuracy: {}\n",
+            locale.percent(summary.avg_accuracy, 1)
+        ));
+        report.push_str(&format!(
+            "  Total Errors: {}\n\n",
+            locale.count(summary.total_errors)
         ));
-        report.push_str(&format!("  Avg Accuracy: {:.1}%\n", summary.avg_accuracy));
-        report.push_str(&format!("  Total Errors: {}\n\n", summary.total_errors));
 
         report.push_str("🏆 BEST PERFORMANCES\n");
         report.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
         report.push_str(&format!(
-            "  Best Speed: {:.0} CPM / {:.0} WPM\n",
-            summary.best_cpm, summary.best_wpm
+            "  Best Speed: {} CPM / {} WPM\n",
+            locale.decimal(summary.best_cpm, 0),
+            locale.decimal(summary.best_wpm, 0)
         ));
         report.push_str(&format!(
-            "  Best Accuracy: {:.1}%\n\n",
-            summary.best_accuracy
+            "  Best Accuracy: {}\n\n",
+            locale.percent(summary.best_accuracy, 1)
         ));
 
         if recent_summary.total_sessions > 0 {
@@ -368,18 +478,25 @@ impl SessionHistory {
             ));
             report.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
             report.push_str(&format!(
-                "  Avg Speed: {:.0} CPM / {:.0} WPM\n",
-                recent_summary.avg_cpm, recent_summary.avg_wpm
+                "  Avg Speed: {} CPM / {} WPM\n",
+                locale.decimal(recent_summary.avg_cpm, 0),
+                locale.decimal(recent_summary.avg_wpm, 0)
             ));
             report.push_str(&format!(
-                "  Avg Accuracy: {:.1}%\n",
-                recent_summary.avg_accuracy
+                "  Avg Accuracy: {}\n",
+                locale.percent(recent_summary.avg_accuracy, 1)
             ));
 
             if improved {
-                report.push_str(&format!("  📊 Improvement: +{:.1}% 🎉\n", improvement));
+                report.push_str(&format!(
+                    "  📊 Improvement: {}% 🎉\n",
+                    locale.signed(improvement, 1)
+                ));
             } else if improvement < 0.0 {
-                report.push_str(&format!("  📊 Change: {:.1}%\n", improvement));
+                report.push_str(&format!(
+                    "  📊 Change: {}\n",
+                    locale.percent(improvement, 1)
+                ));
             }
             report.push_str("\n");
         }
@@ -388,12 +505,13 @@ impl SessionHistory {
         report.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
         for (i, session) in self.get_recent_sessions(5).iter().enumerate() {
             report.push_str(&format!(
-                "  {}. {:.0} CPM / {:.0} WPM | {:.1}% accuracy | {} chars\n",
+                "  {}. {} | {} CPM / {} WPM | {} accuracy | {} chars\n",
                 i + 1,
-                session.chars_per_minute,
-                session.words_per_minute,
-                session.accuracy,
-                session.chars_typed
+                locale.timestamp(session.timestamp),
+                locale.decimal(session.chars_per_minute, 0),
+                locale.decimal(session.words_per_minute, 0),
+                locale.percent(session.accuracy, 1),
+                locale.count(session.chars_typed)
             ));
         }
 
@@ -404,9 +522,27 @@ impl SessionHistory {
     }
 }
 
+fn summarize(sessions: &[&SessionStats]) -> SessionSummary {
+    let value =
+        |aggregator: aggregators::MetricAggregator| aggregator.compute(sessions).unwrap_or(0.0);
+    SessionSummary {
+        total_sessions: sessions.len(),
+        total_chars: value(aggregators::TOTAL_CHARS) as usize,
+        total_time: value(aggregators::TOTAL_TIME),
+        avg_cpm: value(aggregators::AVG_CPM),
+        avg_wpm: value(aggregators::AVG_WPM),
+        avg_accuracy: value(aggregators::AVG_ACCURACY),
+        best_cpm: value(aggregators::BEST_CPM),
+        best_wpm: value(aggregators::BEST_WPM),
+        best_accuracy: value(aggregators::BEST_ACCURACY),
+        total_errors: value(aggregators::TOTAL_ERRORS) as usize,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session_state::{ErrorTimeline, TimelineWindow};
 
     fn create_test_stats(cpm: f64, accuracy: f64, chars: usize, errors: usize) -> SessionStats {
         let wpm = cpm / 5.0;
@@ -422,7 +558,178 @@ mod tests {
             accuracy,
             timestamp: 0,
             file_path: "test.rs".to_string(),
+            source: "file".to_string(),
+            dead_zones: 0,
+            line_range: None,
+            key_stats: Default::default(),
+            transitions: Default::default(),
+            bigrams: Default::default(),
+            words: Default::default(),
+            error_timeline: Default::default(),
+            error_lines: Default::default(),
+            review_of: None,
+            pomodoro: false,
+            score: None,
+            paused_secs: 0.0,
+            incomplete: false,
+            seed: None,
+            challenge: None,
+            pane_switches: 0,
+            too_fast_keys: 0,
+            end_condition: Default::default(),
+            warmup: None,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn test_key_stats_over_range() {
+        let mut history = SessionHistory::new("test_key_stats.json");
+        for misses in [1, 3] {
+            let mut session = create_test_stats(100.0, 95.0, 100, 2);
+            session.key_stats.insert('a', KeyStat { hits: 10, misses });
+            history.add_session(session);
         }
+
+        assert_eq!(
+            history.get_key_stats(HistoryRange::All)[&'a'],
+            KeyStat {
+                hits: 20,
+                misses: 4
+            }
+        );
+        assert_eq!(
+            history.get_key_stats(HistoryRange::Recent(1))[&'a'].misses,
+            3
+        );
+        assert_eq!(HistoryRange::All.previous().next(), HistoryRange::All);
+    }
+
+    #[test]
+    fn test_transition_stats_over_range() {
+        let mut history = SessionHistory::new("test_transitions.json");
+        for secs in [0.3, 0.5] {
+            let mut session = create_test_stats(100.0, 95.0, 100, 2);
+            session.transitions.insert(
+                "ed".to_string(),
+                TransitionStat {
+                    count: 2,
+                    total_secs: secs,
+                },
+            );
+            history.add_session(session);
+        }
+
+        let all = history.get_transition_stats(HistoryRange::All);
+        assert_eq!(all["ed"].count, 4);
+        assert!((all["ed"].mean_secs() - 0.2).abs() < 1e-9);
+        let recent = history.get_transition_stats(HistoryRange::Recent(1));
+        assert_eq!(recent["ed"].total_secs, 0.5);
+    }
+
+    #[test]
+    fn test_speed_curves_for_file() {
+        let mut history = SessionHistory::new("test_speed_curves.json");
+        let minutes = |chars: &[usize]| ErrorTimeline {
+            window_secs: 60,
+            windows: chars
+                .iter()
+                .map(|&chars| TimelineWindow { chars, errors: 0 })
+                .collect(),
+        };
+        for (timestamp, wpm, chars) in [
+            (1, 30.0, &[100, 150][..]),
+            (2, 40.0, &[200, 200, 250][..]),
+            (3, 50.0, &[300][..]),
+        ] {
+            let mut session = create_test_stats(wpm * 5.0, 95.0, 100, 2);
+            session.words_per_minute = wpm;
+            session.timestamp = timestamp;
+            session.time_elapsed_secs = chars.len() as f64 * 60.0;
+            session.error_timeline = minutes(chars);
+            history.add_session(session);
+        }
+        let mut other_file = create_test_stats(500.0, 95.0, 100, 2);
+        other_file.file_path = "other.rs".to_string();
+        other_file.error_timeline = minutes(&[900]);
+        history.add_session(other_file);
+
+        // The session just finished (3) is left out
+        let curves = history.speed_curves("test.rs", 3).unwrap();
+        assert_eq!(curves.sessions, 2);
+        assert_eq!(curves.average, [30.0, 35.0, 50.0]);
+        assert_eq!(curves.best, [40.0, 40.0, 50.0]);
+        assert_eq!(curves.best_wpm, 40.0);
+        assert!(history.speed_curves("missing.rs", 0).is_none());
+    }
+
+    #[test]
+    fn test_bigram_and_word_stats_over_range() {
+        let mut history = SessionHistory::new("test_sequences.json");
+        for (secs, misses) in [(0.4, 1), (0.2, 0)] {
+            let mut session = create_test_stats(100.0, 95.0, 100, 2);
+            let stat = SequenceStat {
+                count: 2,
+                misses,
+                total_secs: secs,
+            };
+            session.bigrams.insert("fn".to_string(), stat);
+            session.words.insert("self".to_string(), stat);
+            history.add_session(session);
+        }
+
+        let bigrams = history.get_bigram_stats(HistoryRange::All);
+        assert_eq!(bigrams["fn"].count, 4);
+        assert_eq!(bigrams["fn"].misses, 1);
+        assert!((bigrams["fn"].mean_secs() - 0.15).abs() < 1e-9);
+        assert!((bigrams["fn"].error_rate() - 0.2).abs() < 1e-9);
+        let words = history.get_word_stats(HistoryRange::Recent(1));
+        assert_eq!(words["self"].misses, 0);
+        assert_eq!(words["self"].total_secs, 0.2);
+    }
+
+    #[test]
+    fn test_best_score_weighs_accuracy() {
+        let mut history = SessionHistory::new("test_best_score.json");
+        history.add_session(create_test_stats(400.0, 80.0, 400, 100));
+        history.add_session(create_test_stats(350.0, 99.0, 350, 3));
+
+        // Raw speed favours the sloppy session, the score favours the accurate one
+        assert_eq!(history.get_best_wpm_session().unwrap().accuracy, 80.0);
+        assert_eq!(history.get_best_score_session(2.0).unwrap().accuracy, 99.0);
+        assert_eq!(history.get_best_score_session(0.0).unwrap().accuracy, 80.0);
+
+        // The newest session is the one compared against the earlier best
+        let sloppy = create_test_stats(400.0, 80.0, 400, 100).score_or_compute(2.0);
+        assert_eq!(history.previous_best_score(2.0), Some(sloppy));
+        assert_eq!(SessionHistory::new("x.json").previous_best_score(2.0), None);
+    }
+
+    #[test]
+    fn test_sessions_on_date() {
+        let mut history = SessionHistory::new("test_sessions_on.json");
+        let mut session = create_test_stats(100.0, 95.0, 100, 2);
+        session.timestamp = 1_700_000_000;
+        session.pomodoro = true;
+        history.add_session(session);
+
+        let date = local_date(1_700_000_000).unwrap();
+        assert_eq!(history.get_sessions_on(date).len(), 1);
+        assert!(history.get_sessions_on(date.succ_opt().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_minutes_by_day() {
+        let mut history = SessionHistory::new("test_minutes_by_day.json");
+        for (timestamp, secs) in [(1_700_000_000, 120.0), (1_700_000_060, 60.0), (0, 600.0)] {
+            let mut session = SessionStats::new(100, secs, 0, 100, 0, "a.rs".to_string());
+            session.timestamp = timestamp;
+            history.add_session(session);
+        }
+
+        let minutes = history.minutes_by_day();
+        assert_eq!(minutes[&local_date(1_700_000_000).unwrap()], 3.0);
+        assert_eq!(minutes.len(), 2);
     }
 
     #[test]
@@ -452,6 +759,37 @@ mod tests {
         assert_eq!(summary.best_accuracy, 95.0);
     }
 
+    #[test]
+    fn test_aggregate_over_range() {
+        let mut history = SessionHistory::new("test_history.json");
+        for cpm in [100.0, 300.0, 200.0] {
+            history.add_session(create_test_stats(cpm, 95.0, 100, 0));
+        }
+
+        let median = &aggregators::MEDIAN_WPM;
+        assert_eq!(history.aggregate(median, HistoryRange::All), Some(40.0));
+        assert_eq!(
+            history.aggregate(median, HistoryRange::Recent(2)),
+            Some(50.0)
+        );
+        assert_eq!(
+            SessionHistory::new("empty.json").aggregate(median, HistoryRange::All),
+            None
+        );
+    }
+
+    #[test]
+    fn test_statistics_report_follows_locale() {
+        let mut history = SessionHistory::new("test_history.json");
+        history.add_session(create_test_stats(250.0, 95.5, 1500, 5));
+
+        let report = history.format_statistics_report(&Locale::parse("de-DE").unwrap());
+        assert!(report.contains("Total Characters: 1.500"));
+        assert!(report.contains("Avg Accuracy: 95,5%"));
+        let report = history.format_statistics_report(&Locale::parse("en-US").unwrap());
+        assert!(report.contains("Total Characters: 1,500"));
+    }
+
     #[test]
     fn test_get_recent_sessions() {
         let mut history = SessionHistory::new("test_history.json");
@@ -479,6 +817,39 @@ mod tests {
         assert_eq!(best_acc.accuracy, 98.0);
     }
 
+    #[test]
+    fn test_summary_for_source() {
+        let mut history = SessionHistory::new("test_history.json");
+        history.add_session(create_test_stats(300.0, 95.0, 150, 5));
+        let mut quote = create_test_stats(500.0, 99.0, 80, 1);
+        quote.source = "quote".to_string();
+        history.add_session(quote);
+
+        let quotes = history.get_summary_for_source("quote");
+        assert_eq!(quotes.total_sessions, 1);
+        assert_eq!(quotes.best_cpm, 500.0);
+
+        let files = history.get_summary_for_source("file");
+        assert_eq!(files.total_sessions, 1);
+        assert_eq!(files.total_chars, 150);
+    }
+
+    #[test]
+    fn test_summarize_by() {
+        let mut history = SessionHistory::new("test_history.json");
+        history.add_session(create_test_stats(300.0, 95.0, 150, 5));
+        history.add_session(create_test_stats(400.0, 97.0, 200, 2));
+        let mut quote = create_test_stats(500.0, 99.0, 80, 1);
+        quote.source = "quote".to_string();
+        history.add_session(quote);
+
+        let groups = history.summarize_by(|s| s.source.clone());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["file"].total_sessions, 2);
+        assert_eq!(groups["file"].avg_cpm, 350.0);
+        assert_eq!(groups["quote"].total_chars, 80);
+    }
+
     #[test]
     fn test_analyze_improvement() {
         let mut history = SessionHistory::new("test_history.json");
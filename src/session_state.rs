@@ -4,15 +4,488 @@
 //! tracking progress, statistics, and time remaining.
 
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use crate::keyboard;
+
+/// Source name recorded for sessions typed from a regular file
+pub const FILE_SOURCE: &str = "file";
+
+/// Longest plausible gap between two frames of an active session. Anything longer
+/// means the machine slept or the process was suspended.
+const MAX_TICK_GAP: Duration = Duration::from_secs(30);
+
+/// Gaps between two keys longer than this are pauses (reading ahead, thinking),
+/// not the time it takes to reach the second key
+const MAX_TRANSITION_SECS: f64 = 2.0;
+
+/// Words shorter than this are left out of the word statistics (`if`, `a`, `->`)
+const MIN_WORD_CHARS: usize = 3;
+
+/// Length of the windows keystrokes are counted in for the error timeline
+pub const TIMELINE_WINDOW_SECS: u32 = 10;
+
+/// Sessions longer than this many windows get a timeline per minute instead, so it
+/// still fits on one line
+const TIMELINE_MAX_WINDOWS: usize = 30;
+
+/// Windows with fewer keystrokes say too little about accuracy to be the lowest point
+pub const TIMELINE_MIN_KEYSTROKES: usize = 5;
+
+/// A last, partial minute shorter than this is left off the speed curve; a few
+/// seconds of typing make a wild WPM
+const MIN_CURVE_TAIL_SECS: f64 = 15.0;
+
+fn default_source() -> String {
+    FILE_SOURCE.to_string()
+}
+
+/// An inclusive, 1-based range of lines a session is restricted to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Parses ranges like "120-180" or "120..180"
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let (start, end) = input.split_once("..").or_else(|| input.split_once('-'))?;
+        let start = start.trim().parse().ok()?;
+        let end = end.trim().trim_start_matches('=').parse().ok()?;
+
+        if start == 0 || end < start {
+            return None;
+        }
+        Some(Self { start, end })
+    }
+}
+
+impl std::fmt::Display for LineRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// What ends a session, besides reaching the end of the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndCondition {
+    /// The session timer runs out
+    #[default]
+    Time,
+    /// This many characters were typed
+    Chars(usize),
+    /// This many line breaks were typed
+    Lines(usize),
+    /// The error count went over this cap
+    Errors(usize),
+}
+
+impl EndCondition {
+    pub fn is_time(&self) -> bool {
+        *self == EndCondition::Time
+    }
+
+    /// Whether a session with these counts is over (never for `Time`, which is up to the clock)
+    pub fn reached(&self, chars: usize, lines: usize, errors: usize) -> bool {
+        match *self {
+            EndCondition::Time => false,
+            EndCondition::Chars(limit) => chars >= limit,
+            EndCondition::Lines(limit) => lines >= limit,
+            EndCondition::Errors(cap) => errors > cap,
+        }
+    }
+
+    /// How far along the session is, e.g. "120/300 chars"; None for `Time`
+    pub fn progress(&self, chars: usize, lines: usize, errors: usize) -> Option<String> {
+        match *self {
+            EndCondition::Time => None,
+            EndCondition::Chars(limit) => Some(format!("{}/{} chars", chars, limit)),
+            EndCondition::Lines(limit) => Some(format!("{}/{} lines", lines, limit)),
+            EndCondition::Errors(cap) => Some(format!("{}/{} errors", errors, cap)),
+        }
+    }
+}
+
+impl std::fmt::Display for EndCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndCondition::Time => write!(f, "time limit"),
+            EndCondition::Chars(limit) => write!(f, "{} characters", limit),
+            EndCondition::Lines(limit) => write!(f, "{} lines", limit),
+            EndCondition::Errors(cap) => write!(f, "more than {} errors", cap),
+        }
+    }
+}
+
+/// Selection state of the session setup overlay: one row per kind of end condition,
+/// each with its own limit
+#[derive(Debug, Clone)]
+pub struct EndConditionMenu {
+    selected: usize,
+    chars: usize,
+    lines: usize,
+    errors: usize,
+}
+
+impl EndConditionMenu {
+    pub const ROWS: usize = 4;
+
+    /// Opens the menu on `current`, with its limit kept
+    pub fn new(current: EndCondition) -> Self {
+        let mut menu = Self {
+            selected: 0,
+            chars: 300,
+            lines: 20,
+            errors: 5,
+        };
+        match current {
+            EndCondition::Time => {}
+            EndCondition::Chars(limit) => (menu.selected, menu.chars) = (1, limit),
+            EndCondition::Lines(limit) => (menu.selected, menu.lines) = (2, limit),
+            EndCondition::Errors(cap) => (menu.selected, menu.errors) = (3, cap),
+        }
+        menu
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The condition offered on `row`
+    pub fn condition(&self, row: usize) -> EndCondition {
+        match row {
+            1 => EndCondition::Chars(self.chars),
+            2 => EndCondition::Lines(self.lines),
+            3 => EndCondition::Errors(self.errors),
+            _ => EndCondition::Time,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % Self::ROWS;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + Self::ROWS - 1) % Self::ROWS;
+    }
+
+    /// Raises or lowers the limit of the selected row by one step
+    pub fn adjust(&mut self, up: bool) {
+        let (limit, step, min) = match self.selected {
+            1 => (&mut self.chars, 50, 50),
+            2 => (&mut self.lines, 5, 5),
+            3 => (&mut self.errors, 1, 0),
+            _ => return,
+        };
+        *limit = if up {
+            *limit + step
+        } else {
+            limit.saturating_sub(step).max(min)
+        };
+    }
+}
+
+/// How often a character was expected and how often the wrong key was pressed for it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyStat {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl KeyStat {
+    pub fn add(&mut self, other: KeyStat) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+
+    /// Share of attempts that were misses (0.0 to 1.0)
+    pub fn error_rate(&self) -> f64 {
+        let attempts = self.hits + self.misses;
+        if attempts == 0 {
+            0.0
+        } else {
+            self.misses as f64 / attempts as f64
+        }
+    }
+}
+
+/// How often a two-key sequence was typed and the time between its keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransitionStat {
+    pub count: usize,
+    pub total_secs: f64,
+}
+
+impl TransitionStat {
+    pub fn add(&mut self, other: TransitionStat) {
+        self.count += other.count;
+        self.total_secs += other.total_secs;
+    }
+
+    pub fn mean_secs(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_secs / self.count as f64
+        }
+    }
+}
+
+/// How a bigram or a word was typed: times typed through without a miss, misses
+/// on the way and the time it took. A bigram's time runs from its first key to
+/// its second; a word's from the key before it to its last character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SequenceStat {
+    pub count: usize,
+    pub misses: usize,
+    pub total_secs: f64,
+}
+
+impl SequenceStat {
+    pub fn add(&mut self, other: SequenceStat) {
+        self.count += other.count;
+        self.misses += other.misses;
+        self.total_secs += other.total_secs;
+    }
+
+    pub fn mean_secs(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_secs / self.count as f64
+        }
+    }
+
+    /// Misses per attempt (0.0 to 1.0)
+    pub fn error_rate(&self) -> f64 {
+        let attempts = self.count + self.misses;
+        if attempts == 0 {
+            0.0
+        } else {
+            self.misses as f64 / attempts as f64
+        }
+    }
+}
+
+/// Latency of each key: the bigrams ending in it, added up
+pub fn key_latencies(bigrams: &BTreeMap<String, SequenceStat>) -> BTreeMap<char, SequenceStat> {
+    let mut latencies: BTreeMap<char, SequenceStat> = BTreeMap::new();
+    for (bigram, stat) in bigrams {
+        if let Some(last) = bigram.chars().last() {
+            latencies.entry(last).or_default().add(*stat);
+        }
+    }
+    latencies
+}
+
+/// Characters that make up the words in the word statistics
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// The word being typed, for the word statistics
+#[derive(Debug, Clone)]
+struct WordInProgress {
+    text: String,
+    started: Instant,
+    /// When its last character so far was typed
+    typed_at: Instant,
+    misses: usize,
+}
+
+/// Characters and errors typed in one window of a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimelineWindow {
+    pub chars: usize,
+    pub errors: usize,
+}
+
+impl TimelineWindow {
+    pub fn keystrokes(&self) -> usize {
+        self.chars + self.errors
+    }
+
+    /// Accuracy in the window, or None if nothing was typed (a pause)
+    pub fn accuracy(&self) -> Option<f64> {
+        let keystrokes = self.keystrokes();
+        (keystrokes > 0).then(|| self.chars as f64 / keystrokes as f64 * 100.0)
+    }
+}
+
+/// When the errors of a session happened, in windows of equal length from its start
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorTimeline {
+    pub window_secs: u32,
+    pub windows: Vec<TimelineWindow>,
+}
+
+impl ErrorTimeline {
+    /// Builds the timeline from `TIMELINE_WINDOW_SECS` windows, merging them into
+    /// minutes when there are too many to show
+    fn from_windows(windows: &[TimelineWindow]) -> Self {
+        if windows.len() <= TIMELINE_MAX_WINDOWS {
+            return Self {
+                window_secs: TIMELINE_WINDOW_SECS,
+                windows: windows.to_vec(),
+            };
+        }
+        let per_minute = (60 / TIMELINE_WINDOW_SECS) as usize;
+        Self {
+            window_secs: 60,
+            windows: windows
+                .chunks(per_minute)
+                .map(|chunk| TimelineWindow {
+                    chars: chunk.iter().map(|w| w.chars).sum(),
+                    errors: chunk.iter().map(|w| w.errors).sum(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Index and accuracy of the window where accuracy dipped lowest, ignoring
+    /// windows with too few keystrokes to tell
+    pub fn lowest(&self) -> Option<(usize, f64)> {
+        self.windows
+            .iter()
+            .enumerate()
+            .filter(|(_, window)| window.keystrokes() >= TIMELINE_MIN_KEYSTROKES)
+            .filter_map(|(index, window)| Some((index, window.accuracy()?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Words per minute in each minute of a session `duration_secs` long (warm-up
+    /// included, like the timeline). Only the last minute can be partial; its speed
+    /// is for the time it lasted.
+    pub fn wpm_per_minute(&self, duration_secs: f64) -> Vec<f64> {
+        if self.window_secs == 0 {
+            return Vec::new();
+        }
+        let per_minute = (60 / self.window_secs).max(1) as usize;
+        self.windows
+            .chunks(per_minute)
+            .enumerate()
+            .map_while(|(minute, chunk)| {
+                let secs = (duration_secs - minute as f64 * 60.0).min(60.0);
+                (secs >= MIN_CURVE_TAIL_SECS).then(|| {
+                    let chars: usize = chunk.iter().map(|window| window.chars).sum();
+                    chars as f64 / 5.0 * 60.0 / secs
+                })
+            })
+            .collect()
+    }
+
+    /// "02:10-02:20" for the window at `index`
+    pub fn window_label(&self, index: usize) -> String {
+        let start = index as f64 * self.window_secs as f64;
+        format!(
+            "{}-{}",
+            format_clock(start),
+            format_clock(start + self.window_secs as f64)
+        )
+    }
+}
+
+/// Accuracy-weighted score: `wpm * (accuracy / 100)^accuracy_exponent`
+pub fn accuracy_weighted_score(wpm: f64, accuracy: f64, accuracy_exponent: f64) -> f64 {
+    wpm * (accuracy / 100.0).clamp(0.0, 1.0).powf(accuracy_exponent)
+}
+
+/// Seconds needed to type `remaining_chars` at `wpm` (5 characters per word)
+pub fn estimate_secs_to_finish(remaining_chars: usize, wpm: f64) -> Option<f64> {
+    (wpm > 0.0).then(|| remaining_chars as f64 / (wpm * 5.0) * 60.0)
+}
+
+/// The start of every session, left out of its official speed and accuracy
+///
+/// Warm-up is over once any limit that is set (non-zero) is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Warmup {
+    pub secs: f64,
+    pub chars: usize,
+}
+
+impl Warmup {
+    pub fn is_enabled(&self) -> bool {
+        self.secs > 0.0 || self.chars > 0
+    }
+
+    fn is_over(&self, secs: f64, chars: usize) -> bool {
+        (self.secs > 0.0 && secs >= self.secs) || (self.chars > 0 && chars >= self.chars)
+    }
+}
+
+/// The setup a session was typed on, so a change in speed can be put down to a new
+/// keyboard or a bigger font rather than to skill
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionContext {
+    /// `[gameplay] keyboard_name`, if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyboard: Option<String>,
+    /// Window size in pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_size: Option<[u32; 2]>,
+    /// Size the text was shown at, with zoom and the low-vision scale
+    pub font_size: f32,
+    /// "default", or "custom-" and a hash of the colors
+    pub theme: String,
+    pub app_version: String,
+}
+
+impl SessionContext {
+    /// Keyboard and font size, the parts of a setup that change typing speed
+    pub fn setup_label(&self) -> String {
+        format!(
+            "{}, {:.0} px",
+            self.keyboard.as_deref().unwrap_or("Unnamed keyboard"),
+            self.font_size
+        )
+    }
+}
+
+/// What was typed during a session's warm-up, shown apart from the official numbers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WarmupStats {
+    pub chars_typed: usize,
+    pub errors: usize,
+    pub time_elapsed_secs: f64,
+}
+
+impl WarmupStats {
+    pub fn words_per_minute(&self) -> f64 {
+        if self.time_elapsed_secs > 0.0 {
+            self.chars_typed as f64 / self.time_elapsed_secs * 60.0 / 5.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.chars_typed + self.errors > 0 {
+            self.chars_typed as f64 / (self.chars_typed + self.errors) as f64 * 100.0
+        } else {
+            100.0
+        }
+    }
+}
 
 /// Represents the current state of a typing session
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SessionStatus {
     /// Session has not started yet (waiting for first input)
     NotStarted,
     /// Session is currently active
     Active,
+    /// Session clock is stopped (pause key or the window losing focus) until resumed
+    Paused,
     /// Session time has expired
     Finished,
 }
@@ -32,7 +505,7 @@ pub struct SessionStats {
     pub start_position: usize,
     /// Ending position in the code
     pub end_position: usize,
-    /// Number of errors (backspaces/corrections made)
+    /// Number of errors (mismatched keys and backspaces)
     pub errors: usize,
     /// Accuracy percentage (0.0 to 100.0)
     pub accuracy: f64,
@@ -40,6 +513,73 @@ pub struct SessionStats {
     pub timestamp: u64,
     /// File path that was being typed
     pub file_path: String,
+    /// What was being practiced ("file", "quote", ...)
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// Long mismatch streaks (hands shifted over) that were forgiven instead of counted as errors
+    #[serde(default)]
+    pub dead_zones: usize,
+    /// Lines the session was restricted to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_range: Option<LineRange>,
+    /// Hits and misses per expected character
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub key_stats: BTreeMap<char, KeyStat>,
+    /// Timing of same-finger bigrams and row jumps, keyed by the two characters
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transitions: BTreeMap<String, TransitionStat>,
+    /// Timing and misses of every bigram typed without a line break in it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bigrams: BTreeMap<String, SequenceStat>,
+    /// Timing and misses of the words typed, at least `MIN_WORD_CHARS` long
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub words: BTreeMap<String, SequenceStat>,
+    /// Characters and errors over the session's time, warm-up included
+    #[serde(default, skip_serializing_if = "ErrorTimeline::is_empty")]
+    pub error_timeline: ErrorTimeline,
+    /// 1-based lines where a key was mismatched or corrected, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error_lines: Vec<usize>,
+    /// Timestamp of the session whose mistyped lines this review round re-typed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_of: Option<u64>,
+    /// Session was a full pomodoro work period
+    #[serde(default)]
+    pub pomodoro: bool,
+    /// Accuracy-weighted score, computed with the exponent configured when the session was saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// Seconds left out of `time_elapsed_secs` because the machine slept mid-session
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub paused_secs: f64,
+    /// Session was cut short by closing the app and saved anyway
+    #[serde(default)]
+    pub incomplete: bool,
+    /// Seed the practiced text was generated from (quotes and drills)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Id of the challenge file the session was run from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    /// Times the active pane changed in a split session
+    #[serde(default)]
+    pub pane_switches: usize,
+    /// Keystrokes that came sooner than the metronome allows
+    #[serde(default)]
+    pub too_fast_keys: usize,
+    /// What the session was set to end on, so only like sessions are compared
+    #[serde(default, skip_serializing_if = "EndCondition::is_time")]
+    pub end_condition: EndCondition,
+    /// The warm-up at the start, already left out of the numbers above
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warmup: Option<WarmupStats>,
+    /// The setup the session was typed on, when `record_session_context` is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<SessionContext>,
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
 }
 
 impl SessionStats {
@@ -82,20 +622,85 @@ impl SessionStats {
             accuracy,
             timestamp,
             file_path,
+            source: default_source(),
+            dead_zones: 0,
+            line_range: None,
+            key_stats: BTreeMap::new(),
+            transitions: BTreeMap::new(),
+            bigrams: BTreeMap::new(),
+            words: BTreeMap::new(),
+            error_timeline: ErrorTimeline::default(),
+            error_lines: Vec::new(),
+            review_of: None,
+            pomodoro: false,
+            score: None,
+            paused_secs: 0.0,
+            incomplete: false,
+            seed: None,
+            challenge: None,
+            pane_switches: 0,
+            too_fast_keys: 0,
+            end_condition: EndCondition::Time,
+            warmup: None,
+            context: None,
         }
     }
 
+    /// The stored score, or one computed now for sessions saved before scoring existed
+    pub fn score_or_compute(&self, accuracy_exponent: f64) -> f64 {
+        self.score.unwrap_or_else(|| {
+            accuracy_weighted_score(self.words_per_minute, self.accuracy, accuracy_exponent)
+        })
+    }
+
+    /// Words per minute in each minute of the session, from its error timeline
+    pub fn wpm_curve(&self) -> Vec<f64> {
+        let warmup_secs = self.warmup.map_or(0.0, |warmup| warmup.time_elapsed_secs);
+        self.error_timeline
+            .wpm_per_minute(self.time_elapsed_secs + warmup_secs)
+    }
+
+    /// True if the session was interrupted by system sleep or a suspended process
+    pub fn was_interrupted(&self) -> bool {
+        self.paused_secs > 0.0
+    }
+
     /// Format statistics for display
     pub fn format_summary(&self) -> String {
+        let paused = if self.was_interrupted() {
+            format!(
+                "Interrupted: {:.0}s of sleep not counted\n",
+                self.paused_secs
+            )
+        } else {
+            String::new()
+        };
+        let warmup = match &self.warmup {
+            Some(warmup) => format!(
+                "Warm-up (not counted): {:.0}s, {:.0} WPM, {:.1}%\n",
+                warmup.time_elapsed_secs,
+                warmup.words_per_minute(),
+                warmup.accuracy()
+            ),
+            None => String::new(),
+        };
+        let title = if self.incomplete {
+            "Session Incomplete"
+        } else {
+            "Session Complete!"
+        };
         format!(
-            "Session Complete!\n\
+            "{}\n\
              ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n\
              Time: {:.1}s\n\
              Characters: {} (pos {} → {})\n\
              Speed: {:.0} CPM / {:.0} WPM\n\
              Accuracy: {:.1}% ({} errors)\n\
+             {}\
+             {}\
              ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n\
              Press SPACE to start new session",
+            title,
             self.time_elapsed_secs,
             self.chars_typed,
             self.start_position,
@@ -103,11 +708,62 @@ impl SessionStats {
             self.chars_per_minute,
             self.words_per_minute,
             self.accuracy,
-            self.errors
+            self.errors,
+            warmup,
+            paused
         )
     }
 }
 
+/// Keystrokes before the accuracy alarm starts judging; early on one typo swings accuracy wildly
+pub const ACCURACY_ALARM_MIN_KEYSTROKES: usize = 20;
+
+/// Watches live accuracy against a configured floor
+#[derive(Debug, Clone, Default)]
+pub struct AccuracyAlarm {
+    floor: Option<f64>,
+    tripped: bool,
+}
+
+impl AccuracyAlarm {
+    pub fn new(floor: Option<f64>) -> Self {
+        Self {
+            floor,
+            tripped: false,
+        }
+    }
+
+    pub fn floor(&self) -> Option<f64> {
+        self.floor
+    }
+
+    /// True while accuracy is below the floor
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Checks the session's live stats. Returns `Some(true)` when accuracy just fell
+    /// below the floor, `Some(false)` when it just recovered and `None` otherwise.
+    pub fn update(&mut self, stats: &SessionStats) -> Option<bool> {
+        let floor = self.floor?;
+        if stats.chars_typed + stats.errors < ACCURACY_ALARM_MIN_KEYSTROKES {
+            return None;
+        }
+
+        let below = stats.accuracy < floor;
+        if below == self.tripped {
+            return None;
+        }
+        self.tripped = below;
+        Some(below)
+    }
+
+    /// Clears the alarm for the next session
+    pub fn reset(&mut self) {
+        self.tripped = false;
+    }
+}
+
 /// Manages a timed typing session
 #[derive(Debug, Clone)]
 pub struct SessionState {
@@ -115,18 +771,70 @@ pub struct SessionState {
     status: SessionStatus,
     /// When the session started
     start_time: Option<Instant>,
+    /// Last time the session clock was checked, to spot jumps from system sleep
+    last_tick: Option<Instant>,
+    /// Time left out of the session because of such jumps
+    paused: Duration,
+    /// Time the session was paused on purpose, left out of it as well
+    time_on_pause: Duration,
+    /// When the current pause started
+    paused_at: Option<Instant>,
     /// Duration of the session in seconds
     duration_secs: f64,
     /// Position where the session started
     start_position: usize,
     /// Number of characters typed in this session
     chars_typed_in_session: usize,
-    /// Number of errors in this session (backspaces)
+    /// Number of errors in this session (mismatches and backspaces)
     errors_in_session: usize,
+    /// Consecutive mismatches since the last correct character
+    mismatch_streak: usize,
+    /// Streak length that triggers the dead zone (0 disables it)
+    dead_zone_streak: usize,
+    /// Input is paused until the user acknowledges the hand position hint
+    in_dead_zone: bool,
+    /// Number of dead zones hit in this session
+    dead_zones_in_session: usize,
     /// Statistics from the last completed session
     last_session_stats: Option<SessionStats>,
     /// File path being typed
     file_path: String,
+    /// What is being practiced, copied into the session statistics
+    source: String,
+    /// Line range being practiced, copied into the session statistics
+    line_range: Option<LineRange>,
+    /// Seed of generated text, copied into the session statistics
+    seed: Option<u64>,
+    /// Challenge being run, copied into the session statistics
+    challenge: Option<String>,
+    /// Times the active pane changed in this split session
+    pane_switches_in_session: usize,
+    /// Keystrokes the metronome found too fast in this session
+    too_fast_in_session: usize,
+    /// Line breaks typed in this session
+    lines_in_session: usize,
+    /// What ends this and following sessions besides the end of the text
+    end_condition: EndCondition,
+    /// Hits and misses per expected character in this session
+    key_stats: BTreeMap<char, KeyStat>,
+    /// Timing of same-finger bigrams and row jumps in this session
+    transitions: BTreeMap<String, TransitionStat>,
+    bigrams: BTreeMap<String, SequenceStat>,
+    words: BTreeMap<String, SequenceStat>,
+    /// The word at the cursor, until the key after it
+    word: Option<WordInProgress>,
+    /// Characters and errors per `TIMELINE_WINDOW_SECS` of this session
+    timeline: Vec<TimelineWindow>,
+    /// Lines where errors were made in this session
+    error_lines: BTreeSet<usize>,
+    /// Session being reviewed, copied into the session statistics
+    review_of: Option<u64>,
+    /// Last key typed right and when, while no mistake or correction came after it
+    last_key: Option<(char, Instant)>,
+    /// Start of each session left out of its official numbers
+    warmup: Warmup,
+    /// Counts at the moment this session's warm-up ended
+    warmup_stats: Option<WarmupStats>,
 }
 
 impl SessionState {
@@ -135,12 +843,39 @@ impl SessionState {
         Self {
             status: SessionStatus::NotStarted,
             start_time: None,
+            last_tick: None,
+            paused: Duration::ZERO,
+            time_on_pause: Duration::ZERO,
+            paused_at: None,
             duration_secs: duration_minutes * 60.0,
             start_position: 0,
             chars_typed_in_session: 0,
             errors_in_session: 0,
+            mismatch_streak: 0,
+            dead_zone_streak: 0,
+            in_dead_zone: false,
+            dead_zones_in_session: 0,
             last_session_stats: None,
             file_path: String::new(),
+            source: default_source(),
+            line_range: None,
+            seed: None,
+            challenge: None,
+            pane_switches_in_session: 0,
+            too_fast_in_session: 0,
+            lines_in_session: 0,
+            end_condition: EndCondition::Time,
+            key_stats: BTreeMap::new(),
+            transitions: BTreeMap::new(),
+            bigrams: BTreeMap::new(),
+            words: BTreeMap::new(),
+            word: None,
+            timeline: Vec::new(),
+            error_lines: BTreeSet::new(),
+            review_of: None,
+            last_key: None,
+            warmup: Warmup::default(),
+            warmup_stats: None,
         }
     }
 
@@ -149,15 +884,40 @@ impl SessionState {
         if self.status == SessionStatus::NotStarted {
             self.status = SessionStatus::Active;
             self.start_time = Some(Instant::now());
+            self.last_tick = self.start_time;
+            self.paused = Duration::ZERO;
+            self.time_on_pause = Duration::ZERO;
+            self.paused_at = None;
             self.start_position = current_position;
             self.chars_typed_in_session = 0;
             self.errors_in_session = 0;
+            self.key_stats.clear();
+            self.transitions.clear();
+            self.bigrams.clear();
+            self.words.clear();
+            self.word = None;
+            self.timeline.clear();
+            self.error_lines.clear();
+            self.last_key = None;
+            self.clear_dead_zone_state();
+            self.pane_switches_in_session = 0;
+            self.too_fast_in_session = 0;
+            self.lines_in_session = 0;
+            self.warmup_stats = None;
             self.file_path = file_path;
-            log::info!(
-                "🎯 Session started! Duration: {:.1} minutes (starting at position {})",
-                self.duration_secs / 60.0,
-                current_position
-            );
+            if self.end_condition.is_time() {
+                log::info!(
+                    "🎯 Session started! Duration: {:.1} minutes (starting at position {})",
+                    self.duration_secs / 60.0,
+                    current_position
+                );
+            } else {
+                log::info!(
+                    "🎯 Session started! Ends after {} (starting at position {})",
+                    self.end_condition,
+                    current_position
+                );
+            }
         }
     }
 
@@ -165,16 +925,215 @@ impl SessionState {
     pub fn record_char_typed(&mut self) {
         if self.status == SessionStatus::Active {
             self.chars_typed_in_session += 1;
+            self.mismatch_streak = 0;
+            self.timeline_window().chars += 1;
+        }
+    }
+
+    /// The timeline window the session clock is in now
+    fn timeline_window(&mut self) -> &mut TimelineWindow {
+        let index = (self.time_elapsed() / TIMELINE_WINDOW_SECS as f64) as usize;
+        if self.timeline.len() <= index {
+            self.timeline.resize(index + 1, TimelineWindow::default());
+        }
+        &mut self.timeline[index]
+    }
+
+    /// Record whether the key pressed for `expected` was right, for the keyboard heatmap
+    pub fn record_key(&mut self, expected: char, correct: bool) {
+        if self.status != SessionStatus::Active || self.in_dead_zone {
+            return;
+        }
+
+        let stat = self.key_stats.entry(expected).or_default();
+        if correct {
+            stat.hits += 1;
+        } else {
+            stat.misses += 1;
+        }
+
+        let now = Instant::now();
+        self.record_word_key(expected, correct, now);
+        if let Some((previous, at)) = self.last_key
+            && previous != '\n'
+            && expected != '\n'
+        {
+            let mut bigram = String::from(previous);
+            bigram.push(expected);
+            let secs = now.duration_since(at).as_secs_f64();
+            let stat = self.bigrams.entry(bigram).or_default();
+            if !correct {
+                stat.misses += 1;
+            } else if secs <= MAX_TRANSITION_SECS {
+                stat.count += 1;
+                stat.total_secs += secs;
+            }
+        }
+
+        if !correct {
+            self.last_key = None;
+            return;
+        }
+        if let Some((previous, at)) = self.last_key
+            && keyboard::transition(previous, expected).is_some()
+        {
+            let secs = now.duration_since(at).as_secs_f64();
+            if secs <= MAX_TRANSITION_SECS {
+                let mut bigram = String::from(previous);
+                bigram.push(expected);
+                self.transitions
+                    .entry(bigram)
+                    .or_default()
+                    .add(TransitionStat {
+                        count: 1,
+                        total_secs: secs,
+                    });
+            }
+        }
+        self.last_key = Some((expected, now));
+    }
+
+    /// Adds `expected` to the word being typed, or counts the word once a key
+    /// outside it comes
+    fn record_word_key(&mut self, expected: char, correct: bool, now: Instant) {
+        if !is_word_char(expected) {
+            if let Some(word) = self.word.take()
+                && word.text.chars().count() >= MIN_WORD_CHARS
+            {
+                let secs = word.typed_at.duration_since(word.started).as_secs_f64();
+                // A pause within the word says nothing about how hard it is
+                if secs <= MAX_TRANSITION_SECS * word.text.chars().count() as f64 {
+                    self.words.entry(word.text).or_default().add(SequenceStat {
+                        count: 1,
+                        misses: word.misses,
+                        total_secs: secs,
+                    });
+                }
+            }
+            return;
+        }
+        let started = self.last_key.map_or(now, |(_, at)| at);
+        let word = self.word.get_or_insert_with(|| WordInProgress {
+            text: String::new(),
+            started,
+            typed_at: started,
+            misses: 0,
+        });
+        if correct {
+            word.text.push(expected);
+            word.typed_at = now;
+        } else {
+            word.misses += 1;
         }
     }
 
+    /// Breaks the sequence of keys, so the next key isn't timed against the last one
+    /// (after a correction or whitespace typed with Tab). The word being typed is
+    /// dropped too.
+    pub fn forget_last_key(&mut self) {
+        self.last_key = None;
+        self.word = None;
+    }
+
+    /// Hits and misses for `expected` in this session so far
+    pub fn key_stat(&self, expected: char) -> KeyStat {
+        self.key_stats.get(&expected).copied().unwrap_or_default()
+    }
+
+    /// Record a line break being typed, for sessions that end after a number of lines
+    pub fn record_line_typed(&mut self) {
+        if self.status == SessionStatus::Active {
+            self.lines_in_session += 1;
+        }
+    }
+
+    /// Record a switch to the other pane of a split session
+    pub fn record_pane_switch(&mut self) {
+        if self.status == SessionStatus::Active {
+            self.pane_switches_in_session += 1;
+        }
+    }
+
+    /// Record a keystroke that came sooner than the metronome allows
+    pub fn record_too_fast(&mut self) {
+        if self.status == SessionStatus::Active {
+            self.too_fast_in_session += 1;
+        }
+    }
+
+    /// Set how many consecutive mismatches trigger the dead zone (0 disables it)
+    pub fn set_dead_zone_streak(&mut self, streak: usize) {
+        self.dead_zone_streak = streak;
+    }
+
+    /// Record the line an error is about to be counted on, for reviewing it later
+    pub fn record_error_line(&mut self, line: usize) {
+        if self.status == SessionStatus::Active && !self.in_dead_zone {
+            self.error_lines.insert(line);
+        }
+    }
+
+    /// Record a key that didn't match the expected character
+    /// Returns true if this mismatch started a dead zone
+    pub fn record_mismatch(&mut self) -> bool {
+        if self.status != SessionStatus::Active || self.in_dead_zone {
+            return false;
+        }
+
+        self.errors_in_session += 1;
+        self.mismatch_streak += 1;
+        self.timeline_window().errors += 1;
+
+        if self.dead_zone_streak > 0 && self.mismatch_streak >= self.dead_zone_streak {
+            self.in_dead_zone = true;
+            self.dead_zones_in_session += 1;
+            return true;
+        }
+
+        false
+    }
+
+    /// Check if input is paused because of a long mismatch streak
+    pub fn in_dead_zone(&self) -> bool {
+        self.in_dead_zone
+    }
+
+    /// Resume input after a dead zone, forgiving the burst of mismatches
+    pub fn acknowledge_dead_zone(&mut self) {
+        if self.in_dead_zone {
+            self.errors_in_session = self.errors_in_session.saturating_sub(self.mismatch_streak);
+            // The streak may have run over into the next window; forgive the latest first
+            let mut forgiven = self.mismatch_streak;
+            for window in self.timeline.iter_mut().rev() {
+                let taken = window.errors.min(forgiven);
+                window.errors -= taken;
+                forgiven -= taken;
+                if forgiven == 0 {
+                    break;
+                }
+            }
+            self.mismatch_streak = 0;
+            self.in_dead_zone = false;
+        }
+    }
+
+    fn clear_dead_zone_state(&mut self) {
+        self.mismatch_streak = 0;
+        self.in_dead_zone = false;
+        self.dead_zones_in_session = 0;
+    }
+
     /// Record a backspace (decrements typed count and increments errors)
     pub fn record_backspace(&mut self) {
+        self.forget_last_key();
         if self.status == SessionStatus::Active {
             if self.chars_typed_in_session > 0 {
                 self.chars_typed_in_session -= 1;
             }
             self.errors_in_session += 1;
+            let window = self.timeline_window();
+            window.chars = window.chars.saturating_sub(1);
+            window.errors += 1;
         }
     }
 
@@ -185,31 +1144,181 @@ impl SessionState {
             return false;
         }
 
-        if let Some(start) = self.start_time {
-            let elapsed = start.elapsed().as_secs_f64();
-
-            if elapsed >= self.duration_secs {
-                // Session finished!
-                self.status = SessionStatus::Finished;
-                self.last_session_stats = Some(SessionStats::new(
-                    self.chars_typed_in_session,
-                    elapsed,
-                    self.start_position,
-                    current_position,
-                    self.errors_in_session,
-                    self.file_path.clone(),
-                ));
-
-                log::info!("⏰ Session time expired!");
-                if let Some(stats) = &self.last_session_stats {
-                    log::info!("{}", stats.format_summary());
-                }
+        self.tick(Instant::now());
+        self.check_warmup();
+        if self.end_condition.is_time() && self.time_elapsed() >= self.duration_secs {
+            log::info!("⏰ Session time expired!");
+            return self.finish(current_position);
+        }
+        if self.end_condition.reached(
+            self.chars_typed_in_session,
+            self.lines_in_session,
+            self.errors_in_session,
+        ) {
+            log::info!("🏁 Session ended after {}", self.end_condition);
+            return self.finish(current_position);
+        }
+
+        false
+    }
+
+    /// Stops the session clock until `resume`; returns false unless a session was running
+    pub fn pause(&mut self) -> bool {
+        self.pause_at(Instant::now())
+    }
+
+    fn pause_at(&mut self, now: Instant) -> bool {
+        if self.status != SessionStatus::Active {
+            return false;
+        }
+        self.tick(now);
+        self.status = SessionStatus::Paused;
+        self.paused_at = Some(now);
+        // The typing rhythm starts over after the pause
+        self.last_key = None;
+        self.word = None;
+        log::info!("⏸️  Session paused at {}", self.format_time_elapsed());
+        true
+    }
+
+    /// Starts the session clock again; returns false unless the session was paused
+    pub fn resume(&mut self) -> bool {
+        self.resume_at(Instant::now())
+    }
+
+    fn resume_at(&mut self, now: Instant) -> bool {
+        let Some(paused_at) = self.paused_at.take() else {
+            return false;
+        };
+        self.time_on_pause += now.saturating_duration_since(paused_at);
+        self.status = SessionStatus::Active;
+        // The pause isn't a clock jump from system sleep
+        self.last_tick = Some(now);
+        log::info!(
+            "▶️  Session resumed after {:.0}s",
+            now.saturating_duration_since(paused_at).as_secs_f64()
+        );
+        true
+    }
+
+    /// End the active session immediately (e.g. when the practice text is complete)
+    /// Returns true if a session was active and is now finished
+    pub fn finish(&mut self, current_position: usize) -> bool {
+        // A paused session ends with the time it had when it was paused
+        self.resume();
+        if self.status != SessionStatus::Active {
+            return false;
+        }
+
+        self.tick(Instant::now());
+        self.status = SessionStatus::Finished;
+        self.last_session_stats = Some(self.current_stats(current_position));
+
+        if let Some(stats) = &self.last_session_stats {
+            log::info!("{}", stats.format_summary());
+        }
+
+        true
+    }
+
+    /// End the active session because the app is closing, flagging the stats as incomplete
+    /// Returns true if a session was active and is now finished
+    pub fn finish_incomplete(&mut self, current_position: usize) -> bool {
+        if !self.finish(current_position) {
+            return false;
+        }
+        if let Some(stats) = &mut self.last_session_stats {
+            stats.incomplete = true;
+        }
+        true
+    }
 
-                return true;
+    /// Leaves a suspiciously long gap since the last check out of the session time
+    fn tick(&mut self, now: Instant) {
+        if let Some(last) = self.last_tick {
+            let gap = now.saturating_duration_since(last);
+            if gap > MAX_TICK_GAP {
+                self.paused += gap;
+                log::warn!(
+                    "💤 Session clock jumped {:.0}s (system sleep?), not counting it",
+                    gap.as_secs_f64()
+                );
             }
         }
+        self.last_tick = Some(now);
+    }
 
-        false
+    /// Set what is being practiced for this and following sessions
+    pub fn set_source(&mut self, source: &str) {
+        self.source = source.to_string();
+    }
+
+    /// Set the seed the practiced text was generated from (None for files)
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Seed of the text being practiced, if it was generated
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Set the id of the challenge being run (None outside challenges)
+    pub fn set_challenge(&mut self, challenge: Option<String>) {
+        self.challenge = challenge;
+    }
+
+    /// Set the session being reviewed (None outside review rounds)
+    pub fn set_review_of(&mut self, review_of: Option<u64>) {
+        self.review_of = review_of;
+    }
+
+    /// Set the warm-up left out of this and following sessions' numbers
+    pub fn set_warmup(&mut self, warmup: Warmup) {
+        self.warmup = warmup;
+    }
+
+    /// True while the running session is still in its warm-up
+    pub fn in_warmup(&self) -> bool {
+        self.status == SessionStatus::Active
+            && self.warmup.is_enabled()
+            && self.warmup_stats.is_none()
+    }
+
+    /// Remembers the counts once the warm-up is over, so they can be taken out later
+    fn check_warmup(&mut self) {
+        let elapsed = self.time_elapsed();
+        if self.in_warmup() && self.warmup.is_over(elapsed, self.chars_typed_in_session) {
+            self.warmup_stats = Some(WarmupStats {
+                chars_typed: self.chars_typed_in_session,
+                errors: self.errors_in_session,
+                time_elapsed_secs: elapsed,
+            });
+            log::info!("🔥 Warm-up over after {:.0}s, counting from here", elapsed);
+        }
+    }
+
+    /// Set what ends this and following sessions
+    pub fn set_end_condition(&mut self, end_condition: EndCondition) {
+        self.end_condition = end_condition;
+    }
+
+    pub fn end_condition(&self) -> EndCondition {
+        self.end_condition
+    }
+
+    /// Progress toward a character, line or error limit, e.g. "120/300 chars"
+    pub fn format_goal_progress(&self) -> Option<String> {
+        self.end_condition.progress(
+            self.chars_typed_in_session,
+            self.lines_in_session,
+            self.errors_in_session,
+        )
+    }
+
+    /// Restrict this and following sessions to a line range (None for the whole text)
+    pub fn set_line_range(&mut self, line_range: Option<LineRange>) {
+        self.line_range = line_range;
     }
 
     /// Get the current status of the session
@@ -219,18 +1328,23 @@ impl SessionState {
 
     /// Get time remaining in seconds
     pub fn time_remaining(&self) -> f64 {
-        if let Some(start) = self.start_time {
-            let elapsed = start.elapsed().as_secs_f64();
-            (self.duration_secs - elapsed).max(0.0)
+        if self.start_time.is_some() {
+            (self.duration_secs - self.time_elapsed()).max(0.0)
         } else {
             self.duration_secs
         }
     }
 
-    /// Get time elapsed in seconds
+    /// Get time elapsed in seconds, without sleep and pauses
     pub fn time_elapsed(&self) -> f64 {
+        self.time_elapsed_at(self.paused_at.unwrap_or_else(Instant::now))
+    }
+
+    fn time_elapsed_at(&self, now: Instant) -> f64 {
         if let Some(start) = self.start_time {
-            start.elapsed().as_secs_f64()
+            now.saturating_duration_since(start)
+                .saturating_sub(self.paused + self.time_on_pause)
+                .as_secs_f64()
         } else {
             0.0
         }
@@ -238,10 +1352,12 @@ impl SessionState {
 
     /// Get formatted time remaining as MM:SS
     pub fn format_time_remaining(&self) -> String {
-        let remaining = self.time_remaining();
-        let minutes = (remaining / 60.0).floor() as u32;
-        let seconds = (remaining % 60.0).floor() as u32;
-        format!("{:02}:{:02}", minutes, seconds)
+        format_clock(self.time_remaining())
+    }
+
+    /// Get formatted time elapsed as MM:SS, for sessions without a time limit
+    pub fn format_time_elapsed(&self) -> String {
+        format_clock(self.time_elapsed())
     }
 
     /// Get statistics from the last completed session
@@ -253,9 +1369,18 @@ impl SessionState {
     pub fn start_new_session(&mut self, current_position: usize, file_path: String) {
         self.status = SessionStatus::NotStarted;
         self.start_time = None;
+        self.last_tick = None;
+        self.paused = Duration::ZERO;
+        self.time_on_pause = Duration::ZERO;
+        self.paused_at = None;
         self.start_position = current_position;
         self.chars_typed_in_session = 0;
         self.errors_in_session = 0;
+        self.clear_dead_zone_state();
+        self.pane_switches_in_session = 0;
+        self.too_fast_in_session = 0;
+        self.lines_in_session = 0;
+        self.warmup_stats = None;
         self.file_path = file_path;
         // Note: last_session_stats is kept so it can be displayed until next session completes
         log::info!(
@@ -268,18 +1393,36 @@ impl SessionState {
     pub fn reset(&mut self) {
         self.status = SessionStatus::NotStarted;
         self.start_time = None;
+        self.last_tick = None;
+        self.paused = Duration::ZERO;
+        self.time_on_pause = Duration::ZERO;
+        self.paused_at = None;
         self.start_position = 0;
         self.chars_typed_in_session = 0;
         self.errors_in_session = 0;
+        self.clear_dead_zone_state();
+        self.pane_switches_in_session = 0;
+        self.too_fast_in_session = 0;
+        self.lines_in_session = 0;
         self.last_session_stats = None;
         self.file_path = String::new();
     }
 
+    /// What is being practiced, as set by `set_source`
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
     /// Check if the session is active
     pub fn is_active(&self) -> bool {
         self.status == SessionStatus::Active
     }
 
+    /// Check if the session is paused
+    pub fn is_paused(&self) -> bool {
+        self.status == SessionStatus::Paused
+    }
+
     /// Check if the session is finished
     pub fn is_finished(&self) -> bool {
         self.status == SessionStatus::Finished
@@ -287,14 +1430,39 @@ impl SessionState {
 
     /// Get current session statistics (even if not finished)
     pub fn current_stats(&self, current_position: usize) -> SessionStats {
-        SessionStats::new(
-            self.chars_typed_in_session,
-            self.time_elapsed(),
+        // After the warm-up only what followed it counts
+        let warmup = self.warmup_stats.unwrap_or(WarmupStats {
+            chars_typed: 0,
+            errors: 0,
+            time_elapsed_secs: 0.0,
+        });
+        let mut stats = SessionStats::new(
+            self.chars_typed_in_session
+                .saturating_sub(warmup.chars_typed),
+            (self.time_elapsed() - warmup.time_elapsed_secs).max(0.0),
             self.start_position,
             current_position,
-            self.errors_in_session,
+            self.errors_in_session.saturating_sub(warmup.errors),
             self.file_path.clone(),
-        )
+        );
+        stats.warmup = self.warmup_stats;
+        stats.source = self.source.clone();
+        stats.dead_zones = self.dead_zones_in_session;
+        stats.line_range = self.line_range;
+        stats.seed = self.seed;
+        stats.challenge = self.challenge.clone();
+        stats.pane_switches = self.pane_switches_in_session;
+        stats.too_fast_keys = self.too_fast_in_session;
+        stats.end_condition = self.end_condition;
+        stats.key_stats = self.key_stats.clone();
+        stats.transitions = self.transitions.clone();
+        stats.bigrams = self.bigrams.clone();
+        stats.words = self.words.clone();
+        stats.error_timeline = ErrorTimeline::from_windows(&self.timeline);
+        stats.error_lines = self.error_lines.iter().copied().collect();
+        stats.review_of = self.review_of;
+        stats.paused_secs = self.paused.as_secs_f64();
+        stats
     }
 
     /// Get the session duration in seconds
@@ -308,12 +1476,271 @@ impl SessionState {
     }
 }
 
+/// `secs` as MM:SS
+pub fn format_clock(secs: f64) -> String {
+    let minutes = (secs / 60.0).floor() as u32;
+    let seconds = (secs % 60.0).floor() as u32;
+    format!("{:02}:{:02}", minutes, seconds)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::thread;
     use std::time::Duration;
 
+    #[test]
+    fn test_stats_without_time_or_keystrokes() {
+        let stats = SessionStats::new(0, 0.0, 0, 0, 0, "empty.rs".to_string());
+        assert_eq!(stats.chars_per_minute, 0.0);
+        assert_eq!(stats.words_per_minute, 0.0);
+        assert_eq!(stats.accuracy, 100.0);
+
+        // Keystrokes in no measurable time don't make an infinite speed
+        let stats = SessionStats::new(5, 0.0, 0, 5, 5, "a.rs".to_string());
+        assert_eq!(stats.words_per_minute, 0.0);
+        assert_eq!(stats.accuracy, 50.0);
+
+        let pause = TimelineWindow {
+            chars: 0,
+            errors: 0,
+        };
+        assert_eq!(pause.accuracy(), None);
+        assert_eq!(estimate_secs_to_finish(100, 0.0), None);
+        assert_eq!(estimate_secs_to_finish(0, 60.0), Some(0.0));
+    }
+
+    #[test]
+    fn test_warmup_is_left_out_of_the_numbers() {
+        let mut session = SessionState::new(1.0);
+        session.set_warmup(Warmup {
+            secs: 0.0,
+            chars: 3,
+        });
+        session.start(0, "test.rs".to_string());
+
+        session.record_mismatch();
+        for _ in 0..3 {
+            session.record_char_typed();
+        }
+        assert!(session.in_warmup());
+        session.update(3);
+        assert!(!session.in_warmup());
+
+        for _ in 0..10 {
+            session.record_char_typed();
+        }
+        let stats = session.current_stats(13);
+        assert_eq!(stats.chars_typed, 10);
+        assert_eq!(stats.errors, 0);
+        assert_eq!(stats.accuracy, 100.0);
+        let warmup = stats.warmup.unwrap();
+        assert_eq!((warmup.chars_typed, warmup.errors), (3, 1));
+        assert_eq!(warmup.accuracy(), 75.0);
+        assert!(stats.format_summary().contains("Warm-up (not counted)"));
+
+        // A new session warms up again
+        session.start_new_session(13, "test.rs".to_string());
+        session.start(13, "test.rs".to_string());
+        assert!(session.in_warmup());
+        assert!(session.current_stats(13).warmup.is_none());
+    }
+
+    #[test]
+    fn test_accuracy_alarm_trips_and_recovers() {
+        let stats = |chars_typed, errors| {
+            SessionStats::new(
+                chars_typed,
+                60.0,
+                0,
+                chars_typed,
+                errors,
+                "a.rs".to_string(),
+            )
+        };
+        let mut alarm = AccuracyAlarm::new(Some(97.0));
+
+        // Too few keystrokes to judge
+        assert_eq!(alarm.update(&stats(5, 5)), None);
+        assert!(!alarm.is_tripped());
+
+        assert_eq!(alarm.update(&stats(90, 10)), Some(true));
+        assert!(alarm.is_tripped());
+        assert_eq!(alarm.update(&stats(91, 10)), None);
+
+        assert_eq!(alarm.update(&stats(990, 10)), Some(false));
+        assert!(!alarm.is_tripped());
+
+        let mut disabled = AccuracyAlarm::new(None);
+        assert_eq!(disabled.update(&stats(10, 90)), None);
+    }
+
+    #[test]
+    fn test_estimate_secs_to_finish() {
+        assert_eq!(estimate_secs_to_finish(500, 60.0), Some(100.0));
+        assert_eq!(estimate_secs_to_finish(0, 60.0), Some(0.0));
+        assert_eq!(estimate_secs_to_finish(500, 0.0), None);
+    }
+
+    #[test]
+    fn test_accuracy_weighted_score() {
+        assert_eq!(accuracy_weighted_score(60.0, 100.0, 2.0), 60.0);
+        assert!((accuracy_weighted_score(60.0, 90.0, 2.0) - 48.6).abs() < 1e-9);
+        assert_eq!(accuracy_weighted_score(60.0, 90.0, 0.0), 60.0);
+
+        let mut stats = SessionStats::new(300, 60.0, 0, 300, 0, "test.rs".to_string());
+        assert_eq!(stats.score_or_compute(2.0), 60.0);
+        stats.score = Some(42.0);
+        assert_eq!(stats.score_or_compute(2.0), 42.0);
+    }
+
+    #[test]
+    fn test_key_stats_recorded() {
+        let mut session = SessionState::new(1.0);
+        session.record_key('a', true);
+        session.start(0, "test.rs".to_string());
+        session.record_key('a', true);
+        session.record_key('a', false);
+        session.record_key('{', true);
+
+        let stats = session.current_stats(2);
+        assert_eq!(stats.key_stats[&'a'], KeyStat { hits: 1, misses: 1 });
+        assert_eq!(stats.key_stats[&'a'].error_rate(), 0.5);
+        assert_eq!(stats.key_stats[&'{'].hits, 1);
+
+        let json = serde_json::to_string(&stats).unwrap();
+        let loaded: SessionStats = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.key_stats, stats.key_stats);
+    }
+
+    #[test]
+    fn test_transitions_recorded() {
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test.rs".to_string());
+        // "ed" twice, then "ex" broken up by a mistake and "as", which is easy
+        for (expected, correct) in [
+            ('e', true),
+            ('d', true),
+            ('e', true),
+            ('d', true),
+            ('e', true),
+            ('q', false),
+            ('x', true),
+            ('c', true),
+            ('r', true),
+            ('a', true),
+            ('s', true),
+        ] {
+            session.record_key(expected, correct);
+        }
+
+        let stats = session.current_stats(11);
+        let keys: Vec<&str> = stats.transitions.keys().map(String::as_str).collect();
+        assert_eq!(keys, ["cr", "de", "ed"]);
+        assert_eq!(stats.transitions["ed"].count, 2);
+        assert!(stats.transitions["ed"].mean_secs() < MAX_TRANSITION_SECS);
+    }
+
+    #[test]
+    fn test_bigrams_and_words_recorded() {
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test.rs".to_string());
+        // "let x val;" with a miss on the "a" of "val", then "ok" broken up by a backspace
+        for (expected, correct) in [
+            ('l', true),
+            ('e', true),
+            ('t', true),
+            (' ', true),
+            ('x', true),
+            (' ', true),
+            ('v', true),
+            ('a', false),
+            ('a', true),
+            ('l', true),
+            (';', true),
+        ] {
+            session.record_key(expected, correct);
+        }
+        session.record_key('o', true);
+        session.record_backspace();
+        session.record_key('o', true);
+        session.record_key('k', true);
+        session.record_key('\n', true);
+
+        let stats = session.current_stats(14);
+        let words: Vec<&str> = stats.words.keys().map(String::as_str).collect();
+        // "x" is too short and "ok" was broken up by the backspace
+        assert_eq!(words, ["let", "val"]);
+        assert_eq!(stats.words["let"].misses, 0);
+        assert_eq!(stats.words["val"].misses, 1);
+
+        assert_eq!(stats.bigrams["va"].misses, 1);
+        // After the miss the sequence starts over
+        assert!(!stats.bigrams.contains_key("aa"));
+        assert_eq!(stats.bigrams["al"].count, 1);
+        assert!(!stats.bigrams.keys().any(|bigram| bigram.contains('\n')));
+
+        let latencies = key_latencies(&stats.bigrams);
+        assert_eq!(latencies[&'a'].misses, 1);
+        assert_eq!(latencies[&' '].count, 2);
+    }
+
+    #[test]
+    fn test_clock_jump_is_not_counted() {
+        let mut session = SessionState::new(3.0);
+        session.start(0, "test.rs".to_string());
+        let start = session.start_time.unwrap();
+
+        session.tick(start + Duration::from_secs(10));
+        session.tick(start + Duration::from_secs(20));
+        assert!(!session.current_stats(0).was_interrupted());
+
+        // Lid closed for an hour
+        session.tick(start + Duration::from_secs(3620));
+        session.tick(start + Duration::from_secs(3630));
+        let stats = session.current_stats(0);
+        assert!(stats.was_interrupted());
+        assert_eq!(stats.paused_secs, 3600.0);
+        assert!(stats.format_summary().contains("Interrupted: 3600s"));
+
+        session.start_new_session(0, "test.rs".to_string());
+        session.start(0, "test.rs".to_string());
+        assert!(!session.current_stats(0).was_interrupted());
+    }
+
+    #[test]
+    fn test_pause_stops_the_clock() {
+        let mut session = SessionState::new(3.0);
+        assert!(!session.pause());
+        session.start(0, "test.rs".to_string());
+        let start = session.start_time.unwrap();
+        session.record_char_typed();
+
+        assert!(session.pause_at(start + Duration::from_secs(10)));
+        assert!(session.is_paused());
+        assert_eq!(session.time_elapsed(), 10.0);
+        // Keys don't count while paused
+        session.record_char_typed();
+        session.record_mismatch();
+
+        // A long pause isn't mistaken for system sleep
+        assert!(session.resume_at(start + Duration::from_secs(610)));
+        assert!(!session.resume());
+        session.tick(start + Duration::from_secs(615));
+        assert_eq!(
+            session.time_elapsed_at(start + Duration::from_secs(620)),
+            20.0
+        );
+        let stats = session.current_stats(1);
+        assert_eq!((stats.chars_typed, stats.errors), (1, 0));
+        assert!(!stats.was_interrupted());
+
+        // A paused session can still be finished, e.g. when the app closes
+        session.pause();
+        assert!(session.finish(1));
+        assert!(session.is_finished());
+    }
+
     #[test]
     fn test_new_session() {
         let session = SessionState::new(3.0);
@@ -398,4 +1825,284 @@ mod tests {
         assert_eq!(session.status(), &SessionStatus::NotStarted);
         assert!(session.last_stats().is_some());
     }
+
+    #[test]
+    fn test_dead_zone_forgives_burst() {
+        let mut session = SessionState::new(1.0);
+        session.set_dead_zone_streak(3);
+        session.start(0, "test.rs".to_string());
+
+        session.record_mismatch();
+        session.record_char_typed();
+        assert!(!session.record_mismatch());
+        assert!(!session.record_mismatch());
+        assert!(session.record_mismatch());
+        assert!(session.in_dead_zone());
+
+        // Keys pressed while paused are ignored
+        assert!(!session.record_mismatch());
+
+        session.acknowledge_dead_zone();
+        assert!(!session.in_dead_zone());
+
+        let stats = session.current_stats(1);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.dead_zones, 1);
+    }
+
+    #[test]
+    fn test_errors_are_put_on_the_timeline() {
+        let mut session = SessionState::new(1.0);
+        session.set_dead_zone_streak(3);
+        session.start(0, "test.rs".to_string());
+        for _ in 0..10 {
+            session.record_char_typed();
+        }
+
+        // Move the clock into the third window
+        session.start_time = Some(Instant::now() - Duration::from_secs(25));
+        for _ in 0..6 {
+            session.record_char_typed();
+        }
+        session.record_mismatch();
+        session.record_backspace();
+        session.record_char_typed();
+        // A forgiven burst leaves no trace
+        for _ in 0..3 {
+            session.record_mismatch();
+        }
+        session.acknowledge_dead_zone();
+
+        let timeline = session.current_stats(16).error_timeline;
+        assert_eq!(timeline.window_secs, TIMELINE_WINDOW_SECS);
+        assert_eq!(
+            timeline.windows,
+            [
+                TimelineWindow {
+                    chars: 10,
+                    errors: 0
+                },
+                TimelineWindow::default(),
+                TimelineWindow {
+                    chars: 6,
+                    errors: 2
+                },
+            ]
+        );
+        let (index, accuracy) = timeline.lowest().unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(accuracy, 75.0);
+        assert_eq!(timeline.window_label(index), "00:20-00:30");
+    }
+
+    #[test]
+    fn test_wpm_per_minute() {
+        let window = |chars| TimelineWindow { chars, errors: 0 };
+        // Two and a half minutes of 10 s windows: 30, then 60 chars a minute
+        let mut windows = vec![window(5); 6];
+        windows.extend(vec![window(10); 6]);
+        windows.extend(vec![window(10); 3]);
+        let timeline = ErrorTimeline::from_windows(&windows);
+        assert_eq!(timeline.wpm_per_minute(150.0), [6.0, 12.0, 12.0]);
+        // A tail too short to say much is dropped
+        assert_eq!(timeline.wpm_per_minute(130.0), [6.0, 12.0]);
+
+        let minutes = ErrorTimeline {
+            window_secs: 60,
+            windows: vec![window(200), window(250)],
+        };
+        assert_eq!(minutes.wpm_per_minute(120.0), [40.0, 50.0]);
+        assert!(ErrorTimeline::default().wpm_per_minute(60.0).is_empty());
+    }
+
+    #[test]
+    fn test_long_timelines_are_per_minute() {
+        let windows = vec![
+            TimelineWindow {
+                chars: 9,
+                errors: 1
+            };
+            TIMELINE_MAX_WINDOWS + 1
+        ];
+        let timeline = ErrorTimeline::from_windows(&windows);
+        assert_eq!(timeline.window_secs, 60);
+        assert_eq!(timeline.windows.len(), 6);
+        assert_eq!(timeline.windows[0].errors, 6);
+        assert_eq!(timeline.windows[5].keystrokes(), 10);
+        assert_eq!(timeline.window_label(1), "01:00-02:00");
+    }
+
+    #[test]
+    fn test_dead_zone_disabled_by_default() {
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test.rs".to_string());
+        for _ in 0..20 {
+            assert!(!session.record_mismatch());
+        }
+        assert_eq!(session.current_stats(0).errors, 20);
+    }
+
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(
+            LineRange::parse("120-180"),
+            Some(LineRange {
+                start: 120,
+                end: 180
+            })
+        );
+        assert_eq!(
+            LineRange::parse(" 5 .. 5 "),
+            Some(LineRange { start: 5, end: 5 })
+        );
+        assert_eq!(
+            LineRange::parse("3..=9"),
+            Some(LineRange { start: 3, end: 9 })
+        );
+        assert_eq!(LineRange::parse("180-120"), None);
+        assert_eq!(LineRange::parse("0-10"), None);
+        assert_eq!(LineRange::parse("abc"), None);
+        assert_eq!(LineRange { start: 1, end: 2 }.to_string(), "1-2");
+    }
+
+    #[test]
+    fn test_line_range_recorded_in_stats() {
+        let mut session = SessionState::new(1.0);
+        session.set_line_range(LineRange::parse("10-20"));
+        session.start(0, "test.rs".to_string());
+
+        let stats = session.current_stats(0);
+        assert_eq!(stats.line_range, Some(LineRange { start: 10, end: 20 }));
+
+        let json =
+            serde_json::to_string(&SessionStats::new(1, 1.0, 0, 1, 0, "a.rs".into())).unwrap();
+        assert!(!json.contains("line_range"));
+    }
+
+    #[test]
+    fn test_finish_early_records_source() {
+        let mut session = SessionState::new(1.0);
+        session.set_source("quote");
+        session.set_seed(Some(42));
+        session.start(0, "quote".to_string());
+        session.record_char_typed();
+
+        assert!(session.finish(1));
+        assert!(session.is_finished());
+        assert!(!session.finish(1));
+
+        let stats = session.last_stats().unwrap();
+        assert_eq!(stats.source, "quote");
+        assert_eq!(stats.seed, Some(42));
+        assert_eq!(stats.chars_typed, 1);
+    }
+
+    #[test]
+    fn test_pane_switches_counted_while_active() {
+        let mut session = SessionState::new(1.0);
+        session.record_pane_switch();
+        session.start(0, "a.rs | b.rs".to_string());
+        session.record_pane_switch();
+        session.record_pane_switch();
+
+        assert!(session.finish(0));
+        assert_eq!(session.last_stats().unwrap().pane_switches, 2);
+
+        session.start_new_session(0, "a.rs | b.rs".to_string());
+        session.start(0, "a.rs | b.rs".to_string());
+        assert_eq!(session.current_stats(0).pane_switches, 0);
+    }
+
+    #[test]
+    fn test_too_fast_keys_counted_while_active() {
+        let mut session = SessionState::new(1.0);
+        session.record_too_fast();
+        session.start(0, "a.rs".to_string());
+        session.record_too_fast();
+        assert_eq!(session.current_stats(0).too_fast_keys, 1);
+
+        session.start_new_session(0, "a.rs".to_string());
+        assert_eq!(session.current_stats(0).too_fast_keys, 0);
+    }
+
+    #[test]
+    fn test_finish_incomplete() {
+        let mut session = SessionState::new(5.0);
+        assert!(!session.finish_incomplete(0));
+
+        session.start(0, "main.rs".to_string());
+        session.record_char_typed();
+        assert!(session.finish_incomplete(1));
+        assert!(session.is_finished());
+
+        let stats = session.last_stats().unwrap();
+        assert!(stats.incomplete);
+        assert!(stats.format_summary().starts_with("Session Incomplete"));
+
+        let json = serde_json::to_string(stats).unwrap();
+        let restored: SessionStats = serde_json::from_str(&json).unwrap();
+        assert!(restored.incomplete);
+    }
+
+    #[test]
+    fn test_stats_without_source_default_to_file() {
+        let json = r#"{"chars_typed":1,"time_elapsed_secs":1.0,"chars_per_minute":60.0,
+            "words_per_minute":12.0,"start_position":0,"end_position":1,"errors":0,
+            "accuracy":100.0,"timestamp":0,"file_path":"a.rs"}"#;
+        let stats: SessionStats = serde_json::from_str(json).unwrap();
+        assert_eq!(stats.source, FILE_SOURCE);
+        assert!(stats.end_condition.is_time());
+    }
+
+    #[test]
+    fn test_session_ends_on_its_condition() {
+        let mut session = SessionState::new(1.0);
+        session.set_end_condition(EndCondition::Lines(2));
+        session.start(0, "a.rs".to_string());
+        session.record_line_typed();
+        assert!(!session.update(5));
+        assert_eq!(session.format_goal_progress().unwrap(), "1/2 lines");
+        session.record_line_typed();
+        assert!(session.update(10));
+        assert_eq!(
+            session.last_stats().unwrap().end_condition,
+            EndCondition::Lines(2)
+        );
+
+        // The error cap has to be exceeded, not just reached
+        session.set_end_condition(EndCondition::Errors(1));
+        session.start_new_session(10, "a.rs".to_string());
+        session.start(10, "a.rs".to_string());
+        session.record_mismatch();
+        assert!(!session.update(10));
+        session.record_mismatch();
+        assert!(session.update(10));
+
+        let json = serde_json::to_string(&EndCondition::Chars(300)).unwrap();
+        assert_eq!(json, r#"{"chars":300}"#);
+        assert_eq!(
+            serde_json::to_string(&EndCondition::Time).unwrap(),
+            r#""time""#
+        );
+    }
+
+    #[test]
+    fn test_end_condition_menu() {
+        let mut menu = EndConditionMenu::new(EndCondition::Errors(3));
+        assert_eq!(menu.selected(), 3);
+        menu.adjust(false);
+        menu.adjust(false);
+        menu.adjust(false);
+        menu.adjust(false);
+        assert_eq!(menu.condition(3), EndCondition::Errors(0));
+
+        menu.select_next();
+        assert_eq!(menu.condition(menu.selected()), EndCondition::Time);
+        menu.select_next();
+        menu.adjust(true);
+        assert_eq!(menu.condition(menu.selected()), EndCondition::Chars(350));
+        menu.select_previous();
+        menu.select_previous();
+        assert_eq!(menu.selected(), 3);
+    }
 }
@@ -0,0 +1,143 @@
+//! Split practice
+//!
+//! Two files side by side, typed in turns: the active pane changes every few lines
+//! or on Ctrl+Shift+X, like jumping between two parts of a codebase. Each file keeps
+//! its own progress, and the time spent in both makes up one session.
+
+use anyhow::{Context, Result, bail};
+
+use crate::code_state::CodeState;
+use crate::license_header;
+
+/// Source name recorded in session history for split sessions
+pub const SPLIT_SOURCE: &str = "split";
+
+/// The file that is not being typed right now, with what `CargoTapApp` keeps per file
+pub struct Pane {
+    pub code_state: CodeState,
+    pub file_path: String,
+    pub file_hash: String,
+    pub file_encoding: String,
+    pub scroll_offset: usize,
+    pub license_header: Option<license_header::Header>,
+}
+
+pub struct SplitPractice {
+    /// The inactive file; the active one lives in the app like any other file
+    pub other: Pane,
+    /// The active file is the right pane
+    pub right_active: bool,
+    /// Lines to type before switching (0 switches on the hotkey only)
+    pub switch_lines: usize,
+    /// Cursor line of the active pane when it became active
+    start_line: usize,
+}
+
+impl SplitPractice {
+    pub fn new(other: Pane, switch_lines: usize, start_line: usize) -> Self {
+        Self {
+            other,
+            right_active: false,
+            switch_lines,
+            start_line,
+        }
+    }
+
+    /// Whether enough lines were typed in the active pane to switch
+    pub fn switch_due(&self, cursor_line: usize) -> bool {
+        self.switch_lines > 0 && cursor_line >= self.start_line + self.switch_lines
+    }
+
+    /// Flips the active side; `start_line` is the cursor line of the newly active pane
+    pub fn switched(&mut self, start_line: usize) {
+        self.right_active = !self.right_active;
+        self.start_line = start_line;
+    }
+
+    /// Session label naming both files, left first
+    pub fn label(&self, active_path: &str) -> String {
+        if self.right_active {
+            format!("{} | {}", self.other.file_path, active_path)
+        } else {
+            format!("{} | {}", active_path, self.other.file_path)
+        }
+    }
+}
+
+/// Reads `split FILE_A FILE_B [--every N]` arguments: the left and right file and
+/// the lines per turn, `switch_lines` unless given
+pub fn parse_args(args: &[String], switch_lines: usize) -> Result<(String, String, usize)> {
+    let mut files = Vec::new();
+    let mut switch_lines = switch_lines;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--every" => {
+                switch_lines = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .with_context(|| format!("{} needs a number of lines", arg))?
+            }
+            flag if flag.starts_with("--") => bail!("unknown option {}", flag),
+            path => files.push(path.to_string()),
+        }
+    }
+    match <[String; 2]>::try_from(files) {
+        Ok([left, right]) => Ok((left, right, switch_lines)),
+        Err(_) => bail!("usage: split FILE_A FILE_B [--every N]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(switch_lines: usize) -> SplitPractice {
+        let other = Pane {
+            code_state: CodeState::new("b\n".to_string()),
+            file_path: "b.rs".to_string(),
+            file_hash: String::new(),
+            file_encoding: "UTF-8".to_string(),
+            scroll_offset: 0,
+            license_header: None,
+        };
+        SplitPractice::new(other, switch_lines, 1)
+    }
+
+    #[test]
+    fn test_switch_every_n_lines() {
+        let mut split = split(3);
+        assert!(!split.switch_due(3));
+        assert!(split.switch_due(4));
+        assert_eq!(split.label("a.rs"), "a.rs | b.rs");
+
+        // The app swaps the files, then tells the split where the new pane's cursor is
+        split.other.file_path = "a.rs".to_string();
+        split.switched(10);
+        assert!(split.right_active);
+        assert!(!split.switch_due(12));
+        assert!(split.switch_due(13));
+        // Left stays left whichever pane is active
+        assert_eq!(split.label("b.rs"), "a.rs | b.rs");
+
+        assert!(!self::split(0).switch_due(100));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+        assert_eq!(
+            parse_args(&args(&["a.rs", "b.rs"]), 5).unwrap(),
+            ("a.rs".to_string(), "b.rs".to_string(), 5)
+        );
+        assert_eq!(
+            parse_args(&args(&["a.rs", "--every", "2", "b.rs"]), 5)
+                .unwrap()
+                .2,
+            2
+        );
+        assert!(parse_args(&args(&["a.rs"]), 5).is_err());
+        assert!(parse_args(&args(&["a.rs", "b.rs", "--every", "x"]), 5).is_err());
+        assert!(parse_args(&args(&["a.rs", "b.rs", "--fast"]), 5).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! The whole app's state as JSON
+//!
+//! `CargoTapApp::snapshot` sums up what the app is doing: the screen, the file and
+//! the cursor in it, the session with its live numbers, and a digest of the config.
+//! Ctrl+Shift+I writes it to `state.json` in the data directory for bug reports,
+//! and with `[debug] state_dump_interval_secs` set the file is kept up to date for
+//! overlays and integration tests to read. `cargo run state` prints the last one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::progress_storage;
+use crate::session_state::SessionStatus;
+
+/// Where snapshots are written, in the data directory
+pub const STATE_FILE: &str = "state.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// CargoTap version that wrote the snapshot
+    pub version: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub profile: Option<String>,
+    /// The screen or overlay in front, `typing` when none is open
+    pub screen: String,
+    pub file: FileState,
+    pub session: SessionSnapshot,
+    pub config: ConfigDigest,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    pub path: String,
+    pub hash: String,
+    pub encoding: String,
+    /// Bytes typed from the start of the file
+    pub position: usize,
+    pub length: usize,
+    /// 1-based line of the cursor
+    pub line: usize,
+    /// 0-based column of the cursor, in characters
+    pub column: usize,
+    /// None while a streamed file is still being read
+    pub total_lines: Option<usize>,
+    /// 0.0 to 1.0
+    pub progress: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub status: SessionStatus,
+    /// What is being practiced: a file, a quote, a drill, ...
+    pub source: String,
+    pub chars_typed: usize,
+    pub errors: usize,
+    pub elapsed_secs: f64,
+    pub words_per_minute: f64,
+    pub accuracy: f64,
+}
+
+/// Enough of the config to tell setups apart without copying it into bug reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigDigest {
+    /// Hash of the whole config
+    pub hash: String,
+    /// Settings that differ from the defaults, as `section.key`; values are left
+    /// out as they can hold paths and webhook URLs
+    pub changed: Vec<String>,
+}
+
+impl ConfigDigest {
+    pub fn of(config: &Config) -> Self {
+        let hash = toml::to_string(config)
+            .map(|text| progress_storage::compute_hash(&text))
+            .unwrap_or_default();
+        let mut changed = Vec::new();
+        if let (Ok(current), Ok(default)) = (
+            toml::Value::try_from(config),
+            toml::Value::try_from(Config::default()),
+        ) {
+            collect_changes("", &current, &default, &mut changed);
+        }
+        Self { hash, changed }
+    }
+}
+
+/// Adds the paths of the values that differ between `current` and `default` to `changed`
+fn collect_changes(
+    prefix: &str,
+    current: &toml::Value,
+    default: &toml::Value,
+    changed: &mut Vec<String>,
+) {
+    let path = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+    match (current, default) {
+        (toml::Value::Table(current), toml::Value::Table(default)) => {
+            for (key, value) in current {
+                match default.get(key) {
+                    Some(default_value) => {
+                        collect_changes(&path(key), value, default_value, changed)
+                    }
+                    None => changed.push(path(key)),
+                }
+            }
+            // Options that are set by default but were unset
+            changed.extend(
+                default
+                    .keys()
+                    .filter(|key| !current.contains_key(*key))
+                    .map(|key| path(key)),
+            );
+        }
+        _ if current != default => changed.push(prefix.to_string()),
+        _ => {}
+    }
+}
+
+/// Writes `snapshot` to `path` as pretty JSON
+pub fn save(snapshot: &StateSnapshot, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(snapshot)?;
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// `cargo run state [FILE]`: prints the last saved snapshot
+pub fn run(args: &[String]) -> Result<()> {
+    let path = args
+        .first()
+        .map_or_else(|| crate::profile::data_file(STATE_FILE), Into::into);
+    if !path.exists() {
+        anyhow::bail!(
+            "No app state saved at {} yet: press Ctrl+Shift+I in CargoTap, or set [debug] state_dump_interval_secs",
+            path.display()
+        );
+    }
+    println!("{}", fs::read_to_string(&path)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_digest_lists_changed_keys() {
+        let default = ConfigDigest::of(&Config::default());
+        assert!(default.changed.is_empty());
+
+        let mut config = Config::default();
+        config.debug.show_fps = !config.debug.show_fps;
+        config.gameplay.custom_code_path = Some("/secret/project/main.rs".to_string());
+        let digest = ConfigDigest::of(&config);
+        assert_eq!(
+            digest.changed,
+            ["debug.show_fps", "gameplay.custom_code_path"]
+        );
+        assert_ne!(digest.hash, default.hash);
+    }
+
+    #[test]
+    fn test_snapshot_round_trips_through_json() {
+        let snapshot = StateSnapshot {
+            version: "0.1.0".to_string(),
+            timestamp: 1,
+            profile: None,
+            screen: "typing".to_string(),
+            file: FileState {
+                path: "demo_code.rs".to_string(),
+                hash: "abc".to_string(),
+                encoding: "UTF-8".to_string(),
+                position: 12,
+                length: 100,
+                line: 2,
+                column: 3,
+                total_lines: Some(10),
+                progress: 0.12,
+            },
+            session: SessionSnapshot {
+                status: SessionStatus::Active,
+                source: "file".to_string(),
+                chars_typed: 12,
+                errors: 1,
+                elapsed_secs: 4.0,
+                words_per_minute: 36.0,
+                accuracy: 92.3,
+            },
+            config: ConfigDigest::of(&Config::default()),
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"status\":\"active\""));
+        assert_eq!(
+            serde_json::from_str::<StateSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+}
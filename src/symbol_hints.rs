@@ -0,0 +1,231 @@
+//! Hints for symbols that keep going wrong
+//!
+//! When a symbol is missed again and again (in this session and on the keyboard
+//! heatmap), a toast names the keys that type it on the configured layout. Each
+//! symbol gets its hint once; the ones already shown are kept in the data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::session_state::KeyStat;
+
+/// Where the symbols that already got a hint are listed, in the data directory
+pub const SHOWN_HINTS_FILE: &str = "symbol_hints.json";
+
+/// Misses in the current session before a hint is considered
+const SESSION_MISSES: usize = 3;
+
+/// Error rate over all recorded sessions (this one included) that counts as struggling
+const ERROR_RATE: f64 = 0.2;
+
+/// Keyboard layout the hints describe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    #[default]
+    Us,
+    Uk,
+    De,
+}
+
+impl KeyboardLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyboardLayout::Us => "US",
+            KeyboardLayout::Uk => "UK",
+            KeyboardLayout::De => "German",
+        }
+    }
+
+    fn combos(self) -> &'static [(char, &'static str)] {
+        match self {
+            KeyboardLayout::Us => US_COMBOS,
+            KeyboardLayout::Uk => UK_COMBOS,
+            KeyboardLayout::De => DE_COMBOS,
+        }
+    }
+}
+
+const US_COMBOS: &[(char, &str)] = &[
+    ('`', "` (left of 1)"),
+    ('~', "Shift+` (left of 1)"),
+    ('!', "Shift+1"),
+    ('@', "Shift+2"),
+    ('#', "Shift+3"),
+    ('$', "Shift+4"),
+    ('%', "Shift+5"),
+    ('^', "Shift+6"),
+    ('&', "Shift+7"),
+    ('*', "Shift+8"),
+    ('(', "Shift+9"),
+    (')', "Shift+0"),
+    ('_', "Shift+-"),
+    ('+', "Shift+="),
+    ('{', "Shift+["),
+    ('}', "Shift+]"),
+    ('\\', "\\ (above Enter)"),
+    ('|', "Shift+\\ (above Enter)"),
+    (':', "Shift+;"),
+    ('"', "Shift+'"),
+    ('<', "Shift+,"),
+    ('>', "Shift+."),
+    ('?', "Shift+/"),
+];
+
+const UK_COMBOS: &[(char, &str)] = &[
+    ('`', "` (left of 1)"),
+    ('¬', "Shift+` (left of 1)"),
+    ('!', "Shift+1"),
+    ('"', "Shift+2"),
+    ('£', "Shift+3"),
+    ('$', "Shift+4"),
+    ('%', "Shift+5"),
+    ('^', "Shift+6"),
+    ('&', "Shift+7"),
+    ('*', "Shift+8"),
+    ('(', "Shift+9"),
+    (')', "Shift+0"),
+    ('_', "Shift+-"),
+    ('+', "Shift+="),
+    ('{', "Shift+["),
+    ('}', "Shift+]"),
+    ('#', "# (left of Enter)"),
+    ('~', "Shift+# (left of Enter)"),
+    ('@', "Shift+' (right of ;)"),
+    (':', "Shift+;"),
+    ('\\', "\\ (right of left Shift)"),
+    ('|', "Shift+\\ (right of left Shift)"),
+    ('<', "Shift+,"),
+    ('>', "Shift+."),
+    ('?', "Shift+/"),
+];
+
+const DE_COMBOS: &[(char, &str)] = &[
+    ('^', "^ (left of 1)"),
+    ('!', "Shift+1"),
+    ('"', "Shift+2"),
+    ('§', "Shift+3"),
+    ('$', "Shift+4"),
+    ('%', "Shift+5"),
+    ('&', "Shift+6"),
+    ('/', "Shift+7"),
+    ('(', "Shift+8"),
+    (')', "Shift+9"),
+    ('=', "Shift+0"),
+    ('?', "Shift+ß"),
+    ('{', "AltGr+7"),
+    ('[', "AltGr+8"),
+    (']', "AltGr+9"),
+    ('}', "AltGr+0"),
+    ('\\', "AltGr+ß"),
+    ('`', "Shift+´ (right of ß)"),
+    ('@', "AltGr+Q"),
+    ('*', "Shift++"),
+    ('~', "AltGr++"),
+    ('\'', "Shift+# (left of Enter)"),
+    ('<', "< (right of left Shift)"),
+    ('>', "Shift+< (right of left Shift)"),
+    ('|', "AltGr+< (right of left Shift)"),
+    (';', "Shift+,"),
+    (':', "Shift+."),
+    ('_', "Shift+-"),
+];
+
+/// The hint for `symbol` on `layout`, or None for keys that need no explaining
+pub fn hint(layout: KeyboardLayout, symbol: char) -> Option<String> {
+    let (_, combo) = layout.combos().iter().find(|(ch, _)| *ch == symbol)?;
+    Some(format!(
+        "{} is {} on a {} keyboard",
+        symbol,
+        combo,
+        layout.name()
+    ))
+}
+
+/// Whether the misses for a symbol call for a hint: several in this session, and
+/// a high error rate on the heatmap once this session is added in
+pub fn struggling(session: KeyStat, overall: KeyStat) -> bool {
+    session.misses >= SESSION_MISSES && overall.error_rate() >= ERROR_RATE
+}
+
+/// Symbols that already got their hint
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShownHints {
+    shown: BTreeSet<char>,
+}
+
+impl ShownHints {
+    /// Reads the list, starting empty when there is none yet
+    pub fn load(path: &Path) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, json)
+    }
+
+    pub fn contains(&self, symbol: char) -> bool {
+        self.shown.contains(&symbol)
+    }
+
+    pub fn insert(&mut self, symbol: char) {
+        self.shown.insert(symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hints_per_layout() {
+        assert_eq!(
+            hint(KeyboardLayout::Us, '~').unwrap(),
+            "~ is Shift+` (left of 1) on a US keyboard"
+        );
+        assert_eq!(
+            hint(KeyboardLayout::De, '{').unwrap(),
+            "{ is AltGr+7 on a German keyboard"
+        );
+        assert!(hint(KeyboardLayout::Uk, '@').unwrap().contains("Shift+'"));
+        assert!(hint(KeyboardLayout::Us, 'a').is_none());
+        assert!(hint(KeyboardLayout::Us, '[').is_none());
+    }
+
+    #[test]
+    fn test_struggling() {
+        let stat = |hits, misses| KeyStat { hits, misses };
+        assert!(struggling(stat(2, 3), stat(20, 8)));
+        // Misses today, but usually fine
+        assert!(!struggling(stat(2, 3), stat(100, 5)));
+        // A bad history alone doesn't interrupt a session going well
+        assert!(!struggling(stat(10, 1), stat(10, 10)));
+    }
+
+    #[test]
+    fn test_shown_hints_round_trip() {
+        let path = std::env::temp_dir().join("cargotap_symbol_hints_test.json");
+        let _ = fs::remove_file(&path);
+        assert!(!ShownHints::load(&path).contains('~'));
+
+        let mut shown = ShownHints::default();
+        shown.insert('~');
+        shown.save(&path).unwrap();
+        assert!(ShownHints::load(&path).contains('~'));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,15 +1,26 @@
 use ab_glyph::{Font, FontArc, PxScale, ScaleFont, point};
 use anyhow::Result;
 use image::{ImageBuffer, Luma};
-use std::{collections::HashMap, env, sync::Arc};
+use std::{
+    collections::HashMap,
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+};
 use vulkano::{
-    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
+    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage},
     command_buffer::{
         AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, PrimaryCommandBufferAbstract,
         allocator::StandardCommandBufferAllocator,
     },
     descriptor_set::{
         DescriptorSet, WriteDescriptorSet, allocator::StandardDescriptorSetAllocator,
+        layout::DescriptorSetLayout,
     },
     device::{Device, Queue},
     format::Format,
@@ -19,15 +30,21 @@ use vulkano::{
         view::ImageView,
     },
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
-    pipeline::{GraphicsPipeline, graphics::vertex_input::Vertex, layout::PipelineLayout},
+    pipeline::{GraphicsPipeline, layout::PipelineLayout},
     sync::GpuFuture,
 };
 
+use crate::caret::{self, CaretMotion};
+use crate::config::{CaretConfig, CaretStyle, Easing, TextShadowConfig};
+use crate::renderer::buffer_ring::{self, BufferRing};
+
 #[derive(BufferContents, Clone, Copy)]
 #[repr(C)]
 pub struct TextPushConstants {
     pub screen_size: [f32; 2],
-    pub _padding: [f32; 2], // Padding to align text_color to 16-byte boundary
+    /// Moves every record drawn; only the caret uses it, to slide between characters.
+    /// Also keeps text_color on the 16-byte boundary the shader expects
+    pub offset: [f32; 2],
     pub text_color: [f32; 4],
 }
 
@@ -36,6 +53,14 @@ pub struct TextRenderSettings {
     pub color: [f32; 4],
     pub font_size: f32,
     pub position: [f32; 2],
+    pub shadow: TextShadowConfig,
+    /// Multiplier for the font's line height
+    pub line_spacing: f32,
+    /// Extra pixels after every character
+    pub char_spacing: f32,
+    pub caret: CaretConfig,
+    /// Easing of the caret slide
+    pub caret_easing: Easing,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +68,9 @@ pub struct ColoredChar {
     pub ch: char,
     pub color: [f32; 4],
     pub background_color: Option<[f32; 4]>,
+    /// The caret is on this character. The text system draws it over the cell in the
+    /// configured style; the flag travels with the character through wrapping and marks.
+    pub caret: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +88,7 @@ impl ColoredLine {
             ch,
             color,
             background_color: None,
+            caret: false,
         });
     }
 
@@ -68,6 +97,7 @@ impl ColoredLine {
             ch,
             color,
             background_color: Some(background_color),
+            caret: false,
         });
     }
 
@@ -88,6 +118,22 @@ pub trait TextSurface {
     fn write_line_wordwrap(&mut self, line: &ColoredLine) -> WriteResult;
     fn write_char(&mut self, ch: &ColoredChar) -> WriteResult;
     fn write_break(&mut self) -> WriteResult;
+    /// Size of the text written from now on, relative to the font size
+    fn set_scale(&mut self, scale: f32);
+    /// Keeps room for `lines` lines at `scale`, plus one normal line, free at the
+    /// bottom of the window; text above stops there as if the window ended
+    fn reserve_bottom(&mut self, lines: usize, scale: f32);
+    /// Moves the pen to the start of the reserved bottom area and releases it
+    fn start_bottom_area(&mut self);
+    /// Moves the pen to column `index` of `count` equal columns on the current row.
+    /// Text is cut off at the column's right edge until the next break.
+    fn start_column(&mut self, index: usize, count: usize);
+    /// Horizontal space `ch` takes at the current scale
+    fn measure(&self, ch: char) -> f32;
+    /// Space left on the current row (or column) to the right of the pen
+    fn remaining_width(&self) -> f32;
+    /// Whole rows that still fit from the pen's row down, at the current scale
+    fn rows_left(&self) -> usize;
 }
 
 #[derive(Debug, Clone)]
@@ -200,50 +246,445 @@ impl Default for TextRenderSettings {
             color: [1.0, 1.0, 1.0, 1.0], // White
             font_size: 32.0,
             position: [10.0, 30.0],
+            shadow: TextShadowConfig::default(),
+            line_spacing: 1.0,
+            char_spacing: 0.0,
+            caret: CaretConfig::default(),
+            caret_easing: Easing::default(),
+        }
+    }
+}
+
+/// Side of the square glyph atlas: 512 pixels fit the printable ASCII glyphs up to
+/// a font size of about 64, larger fonts (zoom, low-vision mode) get a bigger atlas
+fn atlas_size_for(font_size: f32) -> u32 {
+    ((font_size * 8.0).ceil() as u32)
+        .next_power_of_two()
+        .clamp(512, MAX_ATLAS_SIZE)
+}
+
+/// Side the atlas may double up to as glyphs come in; glyphs that don't fit then are left out
+const MAX_ATLAS_SIZE: u32 = 4096;
+
+/// Rasterized with the atlas, before any text asks for them: printable ASCII, the
+/// newline mark and the gutter's fold markers
+fn preloaded_chars() -> impl Iterator<Item = char> {
+    (32u8..127u8)
+        .map(char::from)
+        .chain(['↩', '▾', '▸', '⋯', '↪'])
+}
+
+/// Glyph pixels on the CPU side, packed row by row ("shelves") into a square. Kept
+/// after the upload so glyphs can be added whenever text needs them; when the square
+/// is full it doubles, and glyphs already placed keep their pixel coordinates.
+struct AtlasPixels {
+    size: u32,
+    max_size: u32,
+    pixels: Vec<u8>,
+    /// Where the next glyph goes
+    cursor: [u32; 2],
+    /// Height of the tallest glyph in the current row
+    row_height: u32,
+    /// Pixels changed since the last upload
+    dirty: bool,
+}
+
+impl AtlasPixels {
+    fn new(size: u32, max_size: u32) -> Self {
+        Self {
+            size,
+            max_size,
+            pixels: vec![0; (size * size) as usize],
+            cursor: [0, 0],
+            row_height: 0,
+            dirty: true,
+        }
+    }
+
+    /// Top-left corner of a free `width` by `height` spot, growing the atlas when it
+    /// is full. None once it can't grow anymore.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+        loop {
+            if self.cursor[0] + width > self.size {
+                self.cursor = [0, self.cursor[1] + self.row_height];
+                self.row_height = 0;
+            }
+            if width <= self.size && self.cursor[1] + height <= self.size {
+                let origin = self.cursor;
+                // 1 pixel of padding, so linear filtering doesn't bleed in the neighbours
+                self.cursor[0] += width + 1;
+                self.row_height = self.row_height.max(height + 1);
+                self.dirty = true;
+                return Some(origin);
+            }
+            if !self.grow() {
+                return None;
+            }
+        }
+    }
+
+    /// Doubles the side, keeping every pixel where it was
+    fn grow(&mut self) -> bool {
+        if self.size >= self.max_size {
+            return false;
+        }
+        let old_size = self.size as usize;
+        let size = (self.size * 2).min(self.max_size);
+        let mut pixels = vec![0; (size * size) as usize];
+        for (row, old_row) in self.pixels.chunks_exact(old_size).enumerate() {
+            let start = row * size as usize;
+            pixels[start..start + old_size].copy_from_slice(old_row);
+        }
+        log::info!("Glyph atlas grew to {}x{}", size, size);
+        self.size = size;
+        self.pixels = pixels;
+        self.dirty = true;
+        true
+    }
+
+    fn set(&mut self, x: u32, y: u32, coverage: f32) {
+        if x < self.size && y < self.size {
+            self.pixels[(y * self.size + x) as usize] = (coverage * 255.0) as u8;
         }
     }
+
+    /// How far down the atlas glyphs reach, in percent
+    fn utilization(&self) -> f32 {
+        ((self.cursor[1] + self.row_height) as f32 / self.size as f32 * 100.0).min(100.0)
+    }
 }
 
+/// Fonts loaded from `paths` that exist and parse. The defaults list fonts of several
+/// systems, so missing files are expected and only logged.
+fn load_fallback_fonts(paths: &[String]) -> Vec<FontArc> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let data = fs::read(path)
+                .map_err(|e| log::debug!("No fallback font at {}: {}", path, e))
+                .ok()?;
+            match FontArc::try_from_vec(data) {
+                Ok(font) => {
+                    log::info!("Loaded fallback font {}", path);
+                    Some(font)
+                }
+                Err(e) => {
+                    log::warn!("Can't read fallback font {}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Color of the block caret, translucent so the character under it shows. The caret
+/// is drawn from a separate overlay buffer so it can move without a new layout.
+const CARET_BLOCK_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 0.5];
+
+/// Underline and bar carets are thin, so they are drawn opaque
+const CARET_LINE_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+
+/// Screen rectangle of the caret quad
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CaretCell {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// Right edge of the row the caret is in: the column's, or where the line
+    /// wraps to the next row
+    right: f32,
+}
+
+/// A character rasterized into the atlas. Characters no font has an outline for
+/// (spaces) or that didn't fit are kept with a zero size, so they aren't tried again.
 #[derive(Clone, Copy)]
 struct GlyphInfo {
-    uv_min: [f32; 2],
-    uv_max: [f32; 2],
+    /// Atlas pixel rectangle
+    atlas_min: [u32; 2],
+    atlas_max: [u32; 2],
     size: [f32; 2],
     bearing: [f32; 2],
-    advance: f32,
 }
 
-// Text rendering will be simplified for now - just vertex processing
+/// Flag for records drawn as a solid rectangle instead of a glyph from the atlas
+const SOLID_RECORD: u32 = 1;
 
-#[derive(BufferContents, Vertex, Clone, Copy)]
+/// One glyph or solid rectangle on screen. The text vertex shader reads these from a
+/// storage buffer by `gl_InstanceIndex` and draws each one as the same six-vertex quad,
+/// so the layout is 32 bytes per character instead of six full vertices.
+#[derive(BufferContents, Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
-pub struct TextVertex {
-    #[format(R32G32_SFLOAT)]
+pub struct GlyphRecord {
+    /// Top-left corner in pixels
     pub position: [f32; 2],
-    #[format(R32G32_SFLOAT)]
-    pub tex_coords: [f32; 2],
-    #[format(R32G32B32A32_SFLOAT)]
-    pub color: [f32; 4],
+    /// Width and height in pixels
+    pub size: [f32; 2],
+    /// Atlas pixel of the top-left corner, x in the low 16 bits. Pixels rather than
+    /// UVs, so records stay valid when the atlas grows.
+    pub uv_min: u32,
+    /// Atlas pixel of the bottom-right corner, packed the same way
+    pub uv_max: u32,
+    /// RGBA color, 8 bits per channel
+    pub color: u32,
+    pub flags: u32,
+}
+
+impl GlyphRecord {
+    fn glyph(position: [f32; 2], glyph_info: &GlyphInfo, color: [f32; 4], scale: f32) -> Self {
+        Self {
+            position,
+            size: [glyph_info.size[0] * scale, glyph_info.size[1] * scale],
+            uv_min: pack_u16x2(glyph_info.atlas_min),
+            uv_max: pack_u16x2(glyph_info.atlas_max),
+            color: pack_unorm4x8(color),
+            flags: 0,
+        }
+    }
+
+    /// True if the middle of the record lies in `cell`. Bearings can push a glyph a
+    /// little outside its cell, but not its middle.
+    fn centered_in(&self, cell: &CaretCell) -> bool {
+        let x = self.position[0] + self.size[0] / 2.0;
+        let y = self.position[1] + self.size[1] / 2.0;
+        (cell.x..cell.x + cell.width).contains(&x) && (cell.y..cell.y + cell.height).contains(&y)
+    }
+
+    fn solid(position: [f32; 2], size: [f32; 2], color: [f32; 4]) -> Self {
+        Self {
+            position,
+            size,
+            uv_min: 0,
+            uv_max: 0,
+            color: pack_unorm4x8(color),
+            flags: SOLID_RECORD,
+        }
+    }
+}
+
+/// Packs two 16-bit values, the first in the low bits; atlas coordinates fit as
+/// the atlas is at most `MAX_ATLAS_SIZE` wide
+fn pack_u16x2(value: [u32; 2]) -> u32 {
+    (value[0] & 0xFFFF) | ((value[1] & 0xFFFF) << 16)
+}
+
+/// Packs like GLSL `packUnorm4x8`: red goes in the low byte
+fn pack_unorm4x8(color: [f32; 4]) -> u32 {
+    color
+        .iter()
+        .enumerate()
+        .map(|(i, v)| ((v.clamp(0.0, 1.0) * 255.0).round() as u32) << (8 * i))
+        .fold(0, |packed, channel| packed | channel)
+}
+
+/// Hash of a frame's records. The UI writes straight into the text system, so the
+/// records are the colored text with every setting (font size, spacing, scale, window
+/// size) already applied: equal hashes mean the frame would look the same.
+fn records_hash(shadow_records: &[GlyphRecord], records: &[GlyphRecord]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for record in shadow_records.iter().chain(records) {
+        let [x, y] = record.position;
+        let [width, height] = record.size;
+        [x, y, width, height].map(f32::to_bits).hash(&mut hasher);
+        [record.uv_min, record.uv_max, record.color, record.flags].hash(&mut hasher);
+    }
+    // Separates "a shadow followed by glyphs" from the same records split differently
+    shadow_records.len().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Glyph records uploaded for drawing, with the descriptor set that binds them
+#[derive(Clone)]
+struct GlyphBatch {
+    count: u32,
+    descriptor_set: Arc<DescriptorSet>,
+}
+
+/// One laid out screen, ready to draw. The glyph worker sends a frame after every
+/// change; the renderer keeps the latest one it received and draws it until the
+/// next arrives. Frames only hold handles to what is already on
+/// the GPU, so nothing is shared that both sides could change.
+#[derive(Clone)]
+pub struct TextFrame {
+    atlas: Arc<DescriptorSet>,
+    glyphs: Option<GlyphBatch>,
+    /// Just the caret rectangle, drawn on top of `glyphs`
+    caret: Option<GlyphBatch>,
+    caret_motion: CaretMotion,
+    push_constants: TextPushConstants,
+}
+
+impl TextFrame {
+    pub fn draw(
+        &self,
+        command_buffer: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        text_pipeline: Arc<GraphicsPipeline>,
+        text_pipeline_layout: Arc<PipelineLayout>,
+    ) -> Result<()> {
+        let Some(glyphs) = &self.glyphs else {
+            return Ok(());
+        };
+
+        // Bind text pipeline, atlas and push constants, then draw one quad per glyph record
+        unsafe {
+            command_buffer
+                .bind_pipeline_graphics(text_pipeline)
+                .map_err(|e| anyhow::anyhow!("Failed to bind text pipeline: {}", e))?
+                .bind_descriptor_sets(
+                    vulkano::pipeline::PipelineBindPoint::Graphics,
+                    text_pipeline_layout.clone(),
+                    0,
+                    self.atlas.clone(),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to bind descriptor set: {}", e))?
+                .push_constants(text_pipeline_layout.clone(), 0, self.push_constants)
+                .map_err(|e| anyhow::anyhow!("Failed to set push constants: {}", e))?;
+
+            Self::draw_batch(command_buffer, &text_pipeline_layout, glyphs)?;
+
+            // Blinking and sliding are worked out per frame, so the caret moves smoothly
+            // between layouts
+            let now = std::time::Instant::now();
+            if let Some(caret) = &self.caret
+                && self.caret_motion.visible(now)
+            {
+                let push_constants = TextPushConstants {
+                    offset: self.caret_motion.offset(now),
+                    ..self.push_constants
+                };
+                command_buffer
+                    .push_constants(text_pipeline_layout.clone(), 0, push_constants)
+                    .map_err(|e| anyhow::anyhow!("Failed to set push constants: {}", e))?;
+                Self::draw_batch(command_buffer, &text_pipeline_layout, caret)?;
+            }
+        }
+
+        log::debug!("Drew {} glyphs to screen", glyphs.count);
+        Ok(())
+    }
+
+    /// Binds the records of `batch` and draws one quad per record
+    ///
+    /// # Safety
+    /// The text pipeline has to be bound, as for `draw`
+    unsafe fn draw_batch(
+        command_buffer: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
+        text_pipeline_layout: &Arc<PipelineLayout>,
+        batch: &GlyphBatch,
+    ) -> Result<()> {
+        unsafe {
+            command_buffer
+                .bind_descriptor_sets(
+                    vulkano::pipeline::PipelineBindPoint::Graphics,
+                    text_pipeline_layout.clone(),
+                    1,
+                    batch.descriptor_set.clone(),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to bind glyph records: {}", e))?
+                .draw(6, batch.count, 0, 0)
+                .map_err(|e| anyhow::anyhow!("Failed to draw glyphs: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// What a layout put where, for the glyph worker to turn into glyph records
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placed {
+    /// `ch` with the pen on the baseline at `pen`, drawn at `scale`
+    Glyph {
+        ch: char,
+        pen: [f32; 2],
+        scale: f32,
+        color: [f32; 4],
+    },
+    Solid {
+        position: [f32; 2],
+        size: [f32; 2],
+        color: [f32; 4],
+    },
+}
+
+/// One row of a layout and the records built from it
+struct CachedRow {
+    placed: Vec<Placed>,
+    shadows: Vec<GlyphRecord>,
+    records: Vec<GlyphRecord>,
+}
+
+/// The parts of `len` placed things that `row_starts` divides into rows
+fn row_ranges(len: usize, row_starts: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for &end in row_starts.iter().chain([&len]) {
+        if end > start && end <= len {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    ranges
+}
+
+/// Work for the glyph worker, done in the order it was sent
+enum GlyphJob {
+    /// Everything one layout placed, in drawing order, where each row of it starts,
+    /// and the caret's cell
+    Layout {
+        placed: Vec<Placed>,
+        row_starts: Vec<usize>,
+        caret: Option<CaretCell>,
+    },
+    /// The caret slid from one cell to the next; the character it left was typed
+    MoveCaret {
+        from: CaretCell,
+        to: CaretCell,
+        typed_color: [f32; 4],
+    },
+    /// Starts a new atlas at `font_size`, answering on `done` once it is on the GPU
+    CreateAtlas {
+        pipeline_layout: Arc<PipelineLayout>,
+        font_size: f32,
+        done: Sender<Result<()>>,
+    },
+    WindowSize([f32; 2]),
+    /// Where frames go from now on
+    Frames(Sender<TextFrame>),
+    /// Answered once every job sent before it is done
+    Sync(Sender<()>),
+}
+
+/// What the glyph worker reports back to the app
+#[derive(Debug, Default)]
+struct WorkerStats {
+    render: crate::profiling::RenderStats,
+    /// Layouts that matched what was already on the GPU, since the app last asked
+    skipped_uploads: usize,
 }
 
+/// Lays text out on the event loop, where the UI asks how much fits, and leaves the
+/// rest to a worker thread: rasterizing glyphs, building their records and uploading
+/// them. The worker only draws the newest layout it has, so a slow upload doesn't
+/// queue up behind the typist, and the renderer shows the previous frame meanwhile.
 pub struct TextSystem {
     font: FontArc,
-    device: Arc<Device>,
-    queue: Arc<Queue>,
-    memory_allocator: Arc<StandardMemoryAllocator>,
-    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
-    descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
-    vertex_buffer: Option<Subbuffer<[TextVertex]>>,
+    /// Tried in order for characters `font` doesn't have
+    fallback_fonts: Vec<FontArc>,
+    worker: Sender<GlyphJob>,
+    stats: Arc<Mutex<WorkerStats>>,
+    caret: Option<CaretCell>,
     pub is_pipeline_ready: bool,
-
-    // Texture atlas
-    atlas_texture: Option<Arc<ImageView>>,
-    atlas_sampler: Option<Arc<Sampler>>,
-    glyph_infos: HashMap<char, GlyphInfo>,
-    descriptor_set: Option<Arc<DescriptorSet>>,
     current_settings: TextRenderSettings,
+    /// Magnification of the glyphs being laid out; the atlas is rasterized at scale 1
+    scale: f32,
+    /// Height at the bottom of the window kept free by `reserve_bottom`
+    reserved_bottom: f32,
+    /// Right edge of the column set by `start_column`; the window edge when None
+    column_right: Option<f32>,
     window_size: [f32; 2],
-    vertices: Vec<TextVertex>,
+    /// What the layout in progress placed so far
+    placed: Vec<Placed>,
+    /// Index in `placed` where each row after the first starts
+    row_starts: Vec<usize>,
 }
 
 impl TextSystem {
@@ -252,166 +693,83 @@ impl TextSystem {
         queue: Arc<Queue>,
         memory_allocator: Arc<StandardMemoryAllocator>,
         settings: TextRenderSettings,
+        fallback_font_paths: &[String],
     ) -> Result<Self> {
         // Load font
         let font_data = include_bytes!("../fonts/JetBrainsMono-Light.ttf");
         let font = FontArc::try_from_slice(font_data)?;
+        let fallback_fonts = load_fallback_fonts(fallback_font_paths);
 
-        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
-            device.clone(),
-            Default::default(),
-        ));
-        let descriptor_set_allocator = Arc::new(StandardDescriptorSetAllocator::new(
-            device.clone(),
-            Default::default(),
-        ));
-
-        Ok(Self {
-            font,
+        let stats = Arc::new(Mutex::new(WorkerStats::default()));
+        let assembler = GlyphAssembler::new(
             device,
             queue,
             memory_allocator,
-            command_buffer_allocator,
-            descriptor_set_allocator,
-            vertex_buffer: None,
-            is_pipeline_ready: false, // Will be ready after atlas creation
+            settings,
+            font.clone(),
+            fallback_fonts.clone(),
+            stats.clone(),
+        );
+        let (worker, jobs) = mpsc::channel();
+        thread::Builder::new()
+            .name("glyphs".to_string())
+            .spawn(move || assembler.run(jobs))
+            .map_err(|e| anyhow::anyhow!("Failed to start the glyph worker: {}", e))?;
 
-            atlas_texture: None,
-            atlas_sampler: None,
-            glyph_infos: HashMap::new(),
-            descriptor_set: None,
+        Ok(Self {
+            font,
+            fallback_fonts,
+            worker,
+            stats,
+            caret: None,
+            is_pipeline_ready: false, // Will be ready after atlas creation
             current_settings: settings,
+            scale: 1.0,
+            reserved_bottom: 0.0,
+            column_right: None,
             window_size: [800.0, 600.0],
-            vertices: Vec::new(),
+            placed: Vec::new(),
+            row_starts: Vec::new(),
         })
     }
 
-    pub fn update_text_with_settings(&mut self, colored_text: &ColoredText) -> Result<()> {
-        let total_chars: usize = colored_text.lines.iter().map(|line| line.chars.len()).sum();
-        let mut vertices = Vec::with_capacity(total_chars * 6);
-        let scale = PxScale::from(self.current_settings.font_size);
-        let scaled_font = self.font.as_scaled(scale);
+    /// Hands `job` to the worker; false (after logging) when it is gone
+    fn send(&self, job: GlyphJob) -> bool {
+        let sent = self.worker.send(job).is_ok();
+        if !sent {
+            log::error!("Glyph worker is gone, the text can't be updated");
+        }
+        sent
+    }
 
-        let mut cursor_x = self.current_settings.position[0];
-        let mut cursor_y = self.current_settings.position[1];
-        let line_height = scaled_font.height();
+    /// Starts sending a frame after every change, replacing any earlier receiver.
+    /// The current frame, if there is one, is the first to arrive.
+    pub fn frames(&mut self) -> Receiver<TextFrame> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(GlyphJob::Frames(sender));
+        receiver
+    }
+
+    pub fn update_text_with_settings(&mut self, colored_text: &ColoredText) -> Result<()> {
+        self.clear();
+        let line_height = self.get_line_height();
 
         for line in &colored_text.lines {
             for colored_char in &line.chars {
-                let ch = colored_char.ch;
-                let char_color = colored_char.color;
-
-                if ch == '\r' {
+                if colored_char.ch == '\r' {
                     continue;
                 }
-
-                // Calculate advance width for this character (needed for both glyph and background)
-                let advance_width = if let Some(glyph_info) = self.glyph_infos.get(&ch) {
-                    glyph_info.advance
-                } else {
-                    let glyph_id = self.font.glyph_id(ch);
-                    scaled_font.h_advance(glyph_id)
-                };
-
-                // Get glyph info from atlas
-                if let Some(glyph_info) = self.glyph_infos.get(&ch) {
-                    let pos_x = cursor_x + glyph_info.bearing[0];
-                    let pos_y = cursor_y + glyph_info.bearing[1];
-
-                    // Create quad vertices for this glyph using atlas UV coordinates
-                    let glyph_vertices = [
-                        TextVertex {
-                            position: [pos_x, pos_y],
-                            tex_coords: [glyph_info.uv_min[0], glyph_info.uv_min[1]],
-                            color: char_color,
-                        },
-                        TextVertex {
-                            position: [pos_x + glyph_info.size[0], pos_y],
-                            tex_coords: [glyph_info.uv_max[0], glyph_info.uv_min[1]],
-                            color: char_color,
-                        },
-                        TextVertex {
-                            position: [pos_x, pos_y + glyph_info.size[1]],
-                            tex_coords: [glyph_info.uv_min[0], glyph_info.uv_max[1]],
-                            color: char_color,
-                        },
-                        TextVertex {
-                            position: [pos_x + glyph_info.size[0], pos_y],
-                            tex_coords: [glyph_info.uv_max[0], glyph_info.uv_min[1]],
-                            color: char_color,
-                        },
-                        TextVertex {
-                            position: [pos_x + glyph_info.size[0], pos_y + glyph_info.size[1]],
-                            tex_coords: [glyph_info.uv_max[0], glyph_info.uv_max[1]],
-                            color: char_color,
-                        },
-                        TextVertex {
-                            position: [pos_x, pos_y + glyph_info.size[1]],
-                            tex_coords: [glyph_info.uv_min[0], glyph_info.uv_max[1]],
-                            color: char_color,
-                        },
-                    ];
-
-                    vertices.extend_from_slice(&glyph_vertices);
-                }
-
-                // Render caret overlay AFTER the character (or for whitespace without glyphs)
-                if let Some(bg_color) = colored_char.background_color {
-                    // Create a caret overlay quad that covers the character advance
-                    // The background should start at the top of the line, not at cursor_y
-                    let bg_x = cursor_x;
-                    let bg_y = cursor_y - scaled_font.ascent();
-                    let bg_width = advance_width;
-                    let bg_height = line_height;
-
-                    // Use solid color rendering (UV [0,0] is handled specially in shader)
-                    let bg_uv = [0.0, 0.0];
-
-                    let bg_vertices = [
-                        TextVertex {
-                            position: [bg_x, bg_y],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                        TextVertex {
-                            position: [bg_x + bg_width, bg_y],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                        TextVertex {
-                            position: [bg_x, bg_y + bg_height],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                        TextVertex {
-                            position: [bg_x + bg_width, bg_y],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                        TextVertex {
-                            position: [bg_x + bg_width, bg_y + bg_height],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                        TextVertex {
-                            position: [bg_x, bg_y + bg_height],
-                            tex_coords: bg_uv,
-                            color: bg_color,
-                        },
-                    ];
-
-                    vertices.extend_from_slice(&bg_vertices);
-                }
-
-                cursor_x += advance_width;
+                self.place_char(colored_char);
+                self.current_settings.position[0] += self.advance_width(colored_char.ch);
             }
 
             // Move to next line
-            cursor_x = 10.0;
-            cursor_y += line_height;
+            self.current_settings.position[0] = 10.0;
+            self.current_settings.position[1] += line_height;
+            self.start_row();
         }
 
-        self.update_vertex_buffer(vertices)?;
+        self.flush_records();
         Ok(())
     }
 
@@ -421,31 +779,91 @@ impl TextSystem {
         self.update_text_with_settings(&colored_text)
     }
 
-    fn update_vertex_buffer(&mut self, vertices: Vec<TextVertex>) -> Result<()> {
-        if vertices.is_empty() {
-            return Ok(());
+    /// Moves the caret one character to the right, onto `next_char`, without laying out
+    /// the text again, and gives the character it leaves `typed_color`: a character the
+    /// caret marked as missed, or an untyped whitespace mark, changes color once typed.
+    /// Returns false when the caret can't simply slide over (it would wrap or land on a
+    /// line break); the caller should then update the whole text.
+    pub fn advance_caret(&mut self, next_char: char, typed_color: [f32; 4]) -> bool {
+        let Some(cell) = self.caret else {
+            return false;
+        };
+        if next_char == '\n' || next_char == '\r' {
+            return false;
         }
 
-        let vertex_buffer = Buffer::from_iter(
-            self.memory_allocator.clone(),
-            BufferCreateInfo {
-                usage: BufferUsage::VERTEX_BUFFER,
-                ..Default::default()
-            },
-            AllocationCreateInfo {
-                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
-                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
-                ..Default::default()
-            },
-            vertices,
-        )?;
+        let x = cell.x + cell.width;
+        let width = self.advance_width(next_char);
+        if x + width > cell.right {
+            return false;
+        }
 
-        self.vertex_buffer = Some(vertex_buffer);
-        Ok(())
+        let to = CaretCell { x, width, ..cell };
+        self.caret = Some(to);
+        self.send(GlyphJob::MoveCaret {
+            from: cell,
+            to,
+            typed_color,
+        })
+    }
+
+    /// Horizontal space taken by `ch`, character spacing included
+    fn advance_width(&self, ch: char) -> f32 {
+        let font = self.font_for(ch);
+        let scaled_font = font.as_scaled(PxScale::from(self.current_settings.font_size));
+        let advance = scaled_font.h_advance(font.glyph_id(ch));
+        (advance + self.current_settings.char_spacing) * self.scale
+    }
+
+    /// The font that draws `ch`: the main one, or the first fallback that has it.
+    /// When none has it, the main font's "missing glyph" box shows.
+    fn font_for(&self, ch: char) -> &FontArc {
+        font_for(&self.font, &self.fallback_fonts, ch)
+    }
+
+    fn get_ascent(&self) -> f32 {
+        let scaled_font = self
+            .font
+            .as_scaled(PxScale::from(self.current_settings.font_size));
+        scaled_font.ascent() * self.scale
+    }
+
+    /// Lowest y text may reach before it overflows
+    fn bottom_limit(&self) -> f32 {
+        self.window_size[1] - self.reserved_bottom
+    }
+
+    /// Rightmost x text may reach before it overflows
+    fn right_limit(&self) -> f32 {
+        self.column_right.unwrap_or(self.window_size[0])
+    }
+
+    /// Called where the pen leaves a row (or column). When the caret was written on
+    /// it, the row ends at the pen, which can be short of the right edge when the
+    /// line wraps there; the caret mustn't slide past it.
+    fn end_caret_row(&mut self) {
+        let pen = self.current_settings.position;
+        let row_top = pen[1] - self.get_ascent();
+        if let Some(caret) = self.caret.as_mut()
+            && caret.y == row_top
+            && caret.x < pen[0]
+        {
+            caret.right = caret.right.min(pen[0]);
+        }
+    }
+
+    /// Changes the font size. The atlas is rasterized at a fixed size, so a change
+    /// marks the pipeline as not ready until `create_text_atlas` runs again.
+    pub fn set_font_size(&mut self, font_size: f32) {
+        if self.current_settings.font_size != font_size {
+            self.current_settings.font_size = font_size;
+            self.is_pipeline_ready = false;
+        }
     }
 
-    pub fn get_vertex_buffer(&self) -> Option<&Subbuffer<[TextVertex]>> {
-        self.vertex_buffer.as_ref()
+    pub fn set_spacing(&mut self, line_spacing: f32, char_spacing: f32) {
+        self.current_settings.line_spacing = line_spacing;
+        self.current_settings.char_spacing = char_spacing;
     }
 
     pub fn rasterize_text_to_console(&self, text: &str) -> Result<()> {
@@ -478,116 +896,626 @@ impl TextSystem {
         Ok(())
     }
 
+    /// Starts a new atlas for the current font size and binds it for the text pipeline.
+    /// Printable ASCII is rasterized right away, other characters as text needs them.
+    /// Waits for the worker, so layouts sent after this use the new atlas.
     pub fn create_text_atlas(&mut self, pipeline_layout: Arc<PipelineLayout>) -> Result<()> {
-        // Create texture atlas
-        const ATLAS_SIZE: u32 = 512;
-        let font_size = self.current_settings.font_size;
+        let (done, result) = mpsc::channel();
+        let job = GlyphJob::CreateAtlas {
+            pipeline_layout,
+            font_size: self.current_settings.font_size,
+            done,
+        };
+        if !self.send(job) {
+            anyhow::bail!("Glyph worker is gone");
+        }
+        result
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Glyph worker stopped while creating the atlas"))??;
+        self.is_pipeline_ready = true;
+        Ok(())
+    }
+
+    pub fn create_text_pipeline(&mut self) -> Result<()> {
+        // This is now handled by create_text_atlas
+        log::info!("Text rendering pipeline marked as ready");
+        Ok(())
+    }
+
+    /// Manually save the current atlas as a debug bitmap
+    /// This can be called at any time after the atlas has been created
+    pub fn save_atlas_debug(&self) -> Result<()> {
+        if !self.is_pipeline_ready {
+            return Err(anyhow::anyhow!("Atlas has not been created yet"));
+        }
+
+        // We need to recreate the atlas data since it's already been moved to GPU
+        // This is a limitation - we can't easily read back from GPU memory
+        log::warn!("Manual atlas debug save requires recreating atlas data");
 
+        // For now, just log that this feature would need GPU readback
         log::info!(
-            "Creating font atlas with size {}x{} for font size {}",
-            ATLAS_SIZE,
-            ATLAS_SIZE,
-            font_size
+            "To manually save atlas debug bitmap, enable CARGOTAP_DEBUG_ATLAS=true when creating the atlas"
         );
 
-        // Create atlas texture
-        let atlas_image = Image::new(
-            self.memory_allocator.clone(),
-            ImageCreateInfo {
-                image_type: ImageType::Dim2d,
-                format: Format::R8_UNORM, // Single channel for alpha
-                extent: [ATLAS_SIZE, ATLAS_SIZE, 1],
-                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
-                ..Default::default()
-            },
-            AllocationCreateInfo::default(),
-        )?;
+        Ok(())
+    }
 
-        let atlas_view = ImageView::new_default(atlas_image)?;
+    /// What is on the GPU right now, for stutter snapshots
+    pub fn render_stats(&self) -> crate::profiling::RenderStats {
+        self.stats
+            .lock()
+            .map(|stats| stats.render)
+            .unwrap_or_default()
+    }
 
-        // Create sampler
-        let sampler = Sampler::new(
-            self.device.clone(),
-            SamplerCreateInfo::simple_repeat_linear(),
-        )?;
+    /// Blocks until the worker has sent the frames of everything laid out so far
+    pub fn wait_until_drawn(&self) {
+        let (done, drawn) = mpsc::channel();
+        if self.send(GlyphJob::Sync(done)) {
+            let _ = drawn.recv();
+        }
+    }
 
-        // Rasterize glyphs to atlas
-        let mut atlas_data = vec![0u8; (ATLAS_SIZE * ATLAS_SIZE) as usize];
-        let scale = PxScale::from(font_size);
-        let scaled_font = self.font.as_scaled(scale);
+    /// Layouts the worker didn't upload since the last call, because the GPU already
+    /// had the same records
+    pub fn take_skipped_uploads(&self) -> usize {
+        self.stats
+            .lock()
+            .map(|mut stats| std::mem::take(&mut stats.skipped_uploads))
+            .unwrap_or_default()
+    }
 
-        let mut current_x = 0;
-        let mut current_y = 0;
-        let mut row_height = 0;
+    pub fn update_window_size(&mut self, width: f32, height: f32) {
+        if self.window_size != [width, height] {
+            self.window_size = [width, height];
+            self.send(GlyphJob::WindowSize(self.window_size));
+        }
+    }
 
-        const EXTRA_CHARS: &[char] = &['↩'];
+    pub fn get_window_size(&self) -> [f32; 2] {
+        self.window_size
+    }
 
-        for ch in (32u8..127u8)
-            .map(|c| c as char)
-            .chain(EXTRA_CHARS.iter().copied())
-        {
-            let glyph_id = self.font.glyph_id(ch);
-            let glyph = glyph_id.with_scale(scale);
-
-            if let Some(outlined) = scaled_font.outline_glyph(glyph) {
-                let bounds = outlined.px_bounds();
-                let width = bounds.width() as u32;
-                let height = bounds.height() as u32;
-
-                // Check if we need to move to next row
-                if current_x + width > ATLAS_SIZE {
-                    current_x = 0;
-                    current_y += row_height;
-                    row_height = 0;
-                }
+    fn calculate_line_width(&self, line: &ColoredLine) -> f32 {
+        line.chars
+            .iter()
+            .filter(|colored_char| colored_char.ch != '\r')
+            .map(|colored_char| self.advance_width(colored_char.ch))
+            .sum()
+    }
 
-                // Check if we have space
-                if current_y + height > ATLAS_SIZE {
-                    break;
-                }
+    fn get_line_height(&self) -> f32 {
+        let scale = PxScale::from(self.current_settings.font_size);
+        let scaled_font = self.font.as_scaled(scale);
+        scaled_font.height() * self.current_settings.line_spacing * self.scale
+    }
 
-                // Rasterize glyph
-                outlined.draw(|x, y, coverage| {
-                    let atlas_x = current_x + x;
-                    let atlas_y = current_y + y;
-                    if atlas_x < ATLAS_SIZE && atlas_y < ATLAS_SIZE {
-                        let index = (atlas_y * ATLAS_SIZE + atlas_x) as usize;
-                        atlas_data[index] = (coverage * 255.0) as u8;
-                    }
-                });
-
-                // Store glyph info
-                let glyph_info = GlyphInfo {
-                    uv_min: [
-                        current_x as f32 / ATLAS_SIZE as f32,
-                        current_y as f32 / ATLAS_SIZE as f32,
-                    ],
-                    uv_max: [
-                        (current_x + width) as f32 / ATLAS_SIZE as f32,
-                        (current_y + height) as f32 / ATLAS_SIZE as f32,
-                    ],
-                    size: [bounds.width(), bounds.height()],
-                    bearing: [bounds.min.x, bounds.min.y],
-                    advance: scaled_font.h_advance(glyph_id),
-                };
+    fn get_current_cursor_y(&self) -> f32 {
+        self.current_settings.position[1]
+    }
 
-                self.glyph_infos.insert(ch, glyph_info);
+    /// Places `colored_char` at the pen: its glyph, then its background
+    fn place_char(&mut self, colored_char: &ColoredChar) {
+        let cursor_x = self.current_settings.position[0];
+        let cursor_y = self.current_settings.position[1];
+        let line_height = self.get_line_height();
+        let ch = colored_char.ch;
 
-                current_x += width + 1; // Add 1 pixel padding
-                row_height = row_height.max(height + 1);
+        if ch == '\r' {
+            return;
+        }
+
+        let advance_width = self.advance_width(ch);
+
+        self.placed.push(Placed::Glyph {
+            ch,
+            pen: [cursor_x, cursor_y],
+            scale: self.scale,
+            color: colored_char.color,
+        });
+
+        // Backgrounds go after the glyph so they tint it
+        let bg_position = [cursor_x, cursor_y - self.get_ascent()];
+        if colored_char.caret {
+            self.caret = Some(CaretCell {
+                x: bg_position[0],
+                y: bg_position[1],
+                width: advance_width,
+                height: line_height,
+                right: self.right_limit(),
+            });
+        }
+        if let Some(bg_color) = colored_char.background_color {
+            self.placed.push(Placed::Solid {
+                position: bg_position,
+                size: [advance_width, line_height],
+                color: bg_color,
+            });
+        }
+    }
+
+    /// Ends the row being placed. The worker keeps the records of each row and only
+    /// builds them again for rows that changed since the last layout.
+    fn start_row(&mut self) {
+        if self.row_starts.last() != Some(&self.placed.len()) {
+            self.row_starts.push(self.placed.len());
+        }
+    }
+
+    /// Hands what was written since `clear` to the worker, which builds the glyph
+    /// records and uploads them unless the GPU already has the same ones
+    pub fn flush_records(&mut self) {
+        if self.placed.is_empty() {
+            return;
+        }
+        let placed = std::mem::take(&mut self.placed);
+        let row_starts = std::mem::take(&mut self.row_starts);
+        self.send(GlyphJob::Layout {
+            placed,
+            row_starts,
+            caret: self.caret,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.placed.clear();
+        self.row_starts.clear();
+        self.caret = None;
+        self.scale = 1.0;
+        self.reserved_bottom = 0.0;
+        self.column_right = None;
+        self.current_settings.position = [10.0, 30.0];
+    }
+}
+
+/// The font that draws `ch`: `font`, or the first of `fallbacks` that has it
+fn font_for<'a>(font: &'a FontArc, fallbacks: &'a [FontArc], ch: char) -> &'a FontArc {
+    std::iter::once(font)
+        .chain(fallbacks)
+        .find(|font| font.glyph_id(ch).0 != 0)
+        .unwrap_or(font)
+}
+
+/// The glyph worker: owns the atlas and everything on the GPU, and turns layouts
+/// into glyph records and frames for the renderer
+struct GlyphAssembler {
+    font: FontArc,
+    fallback_fonts: Vec<FontArc>,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+    glyphs: Option<GlyphBatch>,
+    glyph_ring: BufferRing<GlyphRecord>,
+    /// Just the caret rectangle, drawn on top of `glyphs`
+    caret_glyphs: Option<GlyphBatch>,
+    caret_ring: BufferRing<GlyphRecord>,
+    caret: Option<CaretCell>,
+    /// `records_hash` of what `glyphs` holds, to skip uploading an identical frame
+    uploaded_hash: Option<u64>,
+    /// What `glyphs` holds, kept to recolor a typed character without a new layout
+    uploaded_records: Vec<GlyphRecord>,
+    /// How many of `uploaded_records` are shadows, which come first
+    uploaded_shadow_count: usize,
+    /// Caret that `caret_glyphs` holds
+    uploaded_caret: Option<CaretCell>,
+    caret_style: CaretStyle,
+    caret_motion: CaretMotion,
+    /// Layout of the descriptor set holding glyph records, known once the pipeline exists
+    glyph_set_layout: Option<Arc<DescriptorSetLayout>>,
+
+    // Texture atlas
+    atlas: Option<AtlasPixels>,
+    atlas_texture: Option<Arc<ImageView>>,
+    atlas_sampler: Option<Arc<Sampler>>,
+    /// Layout of the descriptor set binding the atlas, to bind it again after it changes
+    atlas_set_layout: Option<Arc<DescriptorSetLayout>>,
+    glyph_infos: HashMap<char, GlyphInfo>,
+    descriptor_set: Option<Arc<DescriptorSet>>,
+    /// Font size the atlas is rasterized at
+    font_size: f32,
+    text_color: [f32; 4],
+    shadow_color: [f32; 4],
+    window_size: [f32; 2],
+    records: Vec<GlyphRecord>,
+    /// Shadow or outline copies of the glyphs, drawn before all of `records`
+    shadow_records: Vec<GlyphRecord>,
+    /// Records of each row of the last layout, to reuse for rows that didn't change
+    rows: Vec<CachedRow>,
+    shadow_offsets: Vec<[f32; 2]>,
+    /// Where finished frames go, once the renderer asked for them with `frames`
+    frames: Option<Sender<TextFrame>>,
+    stats: Arc<Mutex<WorkerStats>>,
+}
+
+impl GlyphAssembler {
+    fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        settings: TextRenderSettings,
+        font: FontArc,
+        fallback_fonts: Vec<FontArc>,
+        stats: Arc<Mutex<WorkerStats>>,
+    ) -> Self {
+        let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
+            device.clone(),
+            Default::default(),
+        ));
+        let descriptor_set_allocator = Arc::new(StandardDescriptorSetAllocator::new(
+            device.clone(),
+            Default::default(),
+        ));
+
+        Self {
+            font,
+            fallback_fonts,
+            device,
+            queue,
+            glyph_ring: BufferRing::new(
+                memory_allocator.clone(),
+                BufferUsage::STORAGE_BUFFER,
+                buffer_ring::DEFAULT_SLOTS,
+            ),
+            caret_ring: BufferRing::new(
+                memory_allocator.clone(),
+                BufferUsage::STORAGE_BUFFER,
+                buffer_ring::DEFAULT_SLOTS,
+            ),
+            memory_allocator,
+            command_buffer_allocator,
+            descriptor_set_allocator,
+            glyphs: None,
+            caret_glyphs: None,
+            caret: None,
+            uploaded_hash: None,
+            uploaded_records: Vec::new(),
+            uploaded_shadow_count: 0,
+            uploaded_caret: None,
+            caret_style: settings.caret.style,
+            caret_motion: CaretMotion::new(
+                settings.caret.blink_ms,
+                settings.caret.slide_ms,
+                settings.caret_easing,
+            ),
+            glyph_set_layout: None,
+            atlas: None,
+            atlas_texture: None,
+            atlas_sampler: None,
+            atlas_set_layout: None,
+            glyph_infos: HashMap::new(),
+            descriptor_set: None,
+            font_size: settings.font_size,
+            text_color: settings.color,
+            shadow_color: settings.shadow.color,
+            window_size: [800.0, 600.0],
+            records: Vec::new(),
+            shadow_records: Vec::new(),
+            rows: Vec::new(),
+            shadow_offsets: settings.shadow.offsets(),
+            frames: None,
+            stats,
+        }
+    }
+
+    /// Works through `jobs` until the text system is dropped
+    fn run(mut self, jobs: Receiver<GlyphJob>) {
+        while let Ok(job) = jobs.recv() {
+            let mut batch = vec![job];
+            batch.extend(jobs.try_iter());
+            // Latest wins: the newest layout already has every earlier change in it
+            let newest_layout = batch
+                .iter()
+                .rposition(|job| matches!(job, GlyphJob::Layout { .. }));
+            for (index, job) in batch.into_iter().enumerate() {
+                let superseded = newest_layout.is_some_and(|newest| index < newest);
+                if let Err(e) = self.handle(job, superseded) {
+                    log::error!("Failed to update main text: {}", e);
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, job: GlyphJob, superseded: bool) -> Result<()> {
+        match job {
+            GlyphJob::Layout { .. } | GlyphJob::MoveCaret { .. } if superseded => Ok(()),
+            GlyphJob::Layout {
+                placed,
+                row_starts,
+                caret,
+            } => {
+                self.caret = caret;
+                self.assemble_rows(&placed, &row_starts);
+                self.flush_records()
+            }
+            GlyphJob::MoveCaret {
+                from,
+                to,
+                typed_color,
+            } => {
+                self.recolor_glyph_in(&from, typed_color)?;
+                self.caret = Some(to);
+                self.update_caret_buffer()?;
+                self.publish();
+                Ok(())
             }
+            GlyphJob::CreateAtlas {
+                pipeline_layout,
+                font_size,
+                done,
+            } => {
+                let result = self.create_text_atlas(pipeline_layout, font_size);
+                // Failures go back to the caller, which logs them
+                let _ = done.send(result);
+                Ok(())
+            }
+            GlyphJob::WindowSize(size) => {
+                self.window_size = size;
+                self.publish();
+                Ok(())
+            }
+            GlyphJob::Frames(sender) => {
+                self.frames = Some(sender);
+                self.publish();
+                Ok(())
+            }
+            GlyphJob::Sync(done) => {
+                let _ = done.send(());
+                Ok(())
+            }
+        }
+    }
+
+    /// Sends what is uploaded now to the renderer
+    fn publish(&mut self) {
+        let (Some(sender), Some(atlas)) = (&self.frames, &self.descriptor_set) else {
+            return;
+        };
+        let frame = TextFrame {
+            atlas: atlas.clone(),
+            glyphs: self.glyphs.clone(),
+            caret: self.caret_glyphs.clone(),
+            caret_motion: self.caret_motion,
+            push_constants: TextPushConstants {
+                screen_size: self.window_size,
+                offset: [0.0, 0.0],
+                text_color: self.text_color,
+            },
+        };
+        // A dropped receiver means nothing draws this text anymore
+        if sender.send(frame).is_err() {
+            self.frames = None;
+        }
+    }
+
+    fn update_stats(&self, update: impl FnOnce(&mut WorkerStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            update(&mut stats);
+        }
+    }
+
+    /// What is on the GPU right now
+    fn render_stats(&self) -> crate::profiling::RenderStats {
+        crate::profiling::RenderStats {
+            glyph_records: self.glyphs.as_ref().map_or(0, |batch| batch.count as usize),
+            atlas_glyphs: self.glyph_infos.len(),
+            atlas_size: self
+                .atlas_texture
+                .as_ref()
+                .map_or(0, |view| view.image().extent()[0]),
+        }
+    }
+
+    /// Uploads glyph records and binds them for the text shader; None until the pipeline exists
+    fn upload_glyphs(
+        ring: &mut BufferRing<GlyphRecord>,
+        allocator: &Arc<StandardDescriptorSetAllocator>,
+        set_layout: Option<&Arc<DescriptorSetLayout>>,
+        records: &[GlyphRecord],
+    ) -> Result<Option<GlyphBatch>> {
+        let Some(set_layout) = set_layout else {
+            return Ok(None);
+        };
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        let buffer = ring.upload(records)?;
+        let descriptor_set = DescriptorSet::new(
+            allocator.clone(),
+            set_layout.clone(),
+            [WriteDescriptorSet::buffer(0, buffer)],
+            [],
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to create glyph descriptor set: {}", e))?;
+
+        Ok(Some(GlyphBatch {
+            count: records.len() as u32,
+            descriptor_set,
+        }))
+    }
+
+    fn update_caret_buffer(&mut self) -> Result<()> {
+        if self.caret != self.uploaded_caret {
+            let delta = self
+                .uploaded_caret
+                .zip(self.caret)
+                .map(|(old, new)| [new.x - old.x, new.y - old.y]);
+            self.caret_motion.moved(delta, std::time::Instant::now());
+        }
+        let style = self.caret_style;
+        let records: Vec<GlyphRecord> = self
+            .caret
+            .map(|cell| {
+                let (position, size) =
+                    caret::caret_rect(style, [cell.x, cell.y], [cell.width, cell.height]);
+                let color = match style {
+                    CaretStyle::Block => CARET_BLOCK_COLOR,
+                    CaretStyle::Underline | CaretStyle::Bar => CARET_LINE_COLOR,
+                };
+                GlyphRecord::solid(position, size, color)
+            })
+            .into_iter()
+            .collect();
+        self.caret_glyphs = Self::upload_glyphs(
+            &mut self.caret_ring,
+            &self.descriptor_set_allocator,
+            self.glyph_set_layout.as_ref(),
+            &records,
+        )?;
+        self.uploaded_caret = self.caret;
+        Ok(())
+    }
+
+    /// Gives the glyph drawn in `cell` (if any; spaces have none) `color`, uploading the
+    /// records again only when that changes anything
+    fn recolor_glyph_in(&mut self, cell: &CaretCell, color: [f32; 4]) -> Result<()> {
+        let color = pack_unorm4x8(color);
+        let Some(record) = self.uploaded_records[self.uploaded_shadow_count..]
+            .iter_mut()
+            .find(|record| record.flags & SOLID_RECORD == 0 && record.centered_in(cell))
+        else {
+            return Ok(());
+        };
+        if record.color == color {
+            return Ok(());
+        }
+        record.color = color;
+        self.glyphs = Self::upload_glyphs(
+            &mut self.glyph_ring,
+            &self.descriptor_set_allocator,
+            self.glyph_set_layout.as_ref(),
+            &self.uploaded_records,
+        )?;
+        // The records on the GPU are no longer those of any layout
+        self.uploaded_hash = None;
+        Ok(())
+    }
+
+    /// The atlas entry for `ch`, rasterizing it on first use. None before the atlas exists.
+    fn glyph_info(&mut self, ch: char) -> Option<GlyphInfo> {
+        if let Some(glyph_info) = self.glyph_infos.get(&ch) {
+            return Some(*glyph_info);
+        }
+        let scale = PxScale::from(self.font_size);
+        let font = font_for(&self.font, &self.fallback_fonts, ch).clone();
+        let atlas = self.atlas.as_mut()?;
+        let scaled_font = font.as_scaled(scale);
+        let glyph_id = font.glyph_id(ch);
+
+        let mut glyph_info = GlyphInfo {
+            atlas_min: [0, 0],
+            atlas_max: [0, 0],
+            size: [0.0, 0.0],
+            bearing: [0.0, 0.0],
+        };
+        if let Some(outlined) = scaled_font.outline_glyph(glyph_id.with_scale(scale)) {
+            let bounds = outlined.px_bounds();
+            let width = bounds.width() as u32;
+            let height = bounds.height() as u32;
+            match atlas.allocate(width, height) {
+                Some([x, y]) => {
+                    outlined.draw(|glyph_x, glyph_y, coverage| {
+                        atlas.set(x + glyph_x, y + glyph_y, coverage)
+                    });
+                    glyph_info.atlas_min = [x, y];
+                    glyph_info.atlas_max = [x + width, y + height];
+                    glyph_info.size = [bounds.width(), bounds.height()];
+                    glyph_info.bearing = [bounds.min.x, bounds.min.y];
+                }
+                None => log::warn!("Glyph atlas is full, '{}' is left out", ch),
+            }
+        }
+        self.glyph_infos.insert(ch, glyph_info);
+        Some(glyph_info)
+    }
+
+    /// Starts a new atlas for `font_size` and binds it for the text pipeline
+    fn create_text_atlas(
+        &mut self,
+        pipeline_layout: Arc<PipelineLayout>,
+        font_size: f32,
+    ) -> Result<()> {
+        self.font_size = font_size;
+        let atlas_size = atlas_size_for(font_size);
+
+        log::info!(
+            "Creating font atlas with size {}x{} for font size {}",
+            atlas_size,
+            atlas_size,
+            font_size
+        );
+
+        self.atlas_set_layout = pipeline_layout.set_layouts().first().cloned();
+        self.glyph_set_layout = pipeline_layout.set_layouts().get(1).cloned();
+        self.atlas = Some(AtlasPixels::new(atlas_size, MAX_ATLAS_SIZE));
+        // Glyphs of the previous font size have other sizes and places
+        self.glyph_infos.clear();
+        self.rows.clear();
+        for ch in preloaded_chars() {
+            self.glyph_info(ch);
         }
 
-        // Save atlas as bitmap for debugging (before moving atlas_data)
-        if self.should_save_debug_atlas() {
-            if let Err(e) = self.save_atlas_debug_bitmap(&atlas_data, ATLAS_SIZE) {
+        if self.should_save_debug_atlas()
+            && let Some(atlas) = &self.atlas
+        {
+            if let Err(e) = self.save_atlas_debug_bitmap(&atlas.pixels, atlas.size) {
                 log::warn!("Failed to save atlas debug bitmap: {}", e);
             } else {
                 log::info!("Atlas debug bitmap saved successfully");
             }
         }
 
-        // Upload atlas data to GPU
+        self.upload_atlas()?;
+        // Glyphs bound with the old layout have to be uploaded again
+        self.uploaded_hash = None;
+        self.uploaded_caret = None;
+        let render = self.render_stats();
+        self.update_stats(|stats| stats.render = render);
+
+        log::info!(
+            "Text atlas created successfully with {} glyphs, atlas utilization: {:.1}%",
+            self.glyph_infos.len(),
+            self.calculate_atlas_utilization()
+        );
+
+        Ok(())
+    }
+
+    /// Uploads the atlas if glyphs were added since the last upload. Every upload goes
+    /// to a new image: frames the renderer still draws keep the one they were laid out
+    /// with, and the new glyphs are only in frames sent after this.
+    fn upload_atlas(&mut self) -> Result<()> {
+        let (Some(atlas), Some(set_layout)) = (&mut self.atlas, &self.atlas_set_layout) else {
+            return Ok(());
+        };
+        if !atlas.dirty {
+            return Ok(());
+        }
+
+        let atlas_image = Image::new(
+            self.memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format: Format::R8_UNORM, // Single channel for alpha
+                extent: [atlas.size, atlas.size, 1],
+                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )?;
+        let atlas_view = ImageView::new_default(atlas_image)?;
+
+        let sampler = match &self.atlas_sampler {
+            Some(sampler) => sampler.clone(),
+            None => Sampler::new(
+                self.device.clone(),
+                SamplerCreateInfo::simple_repeat_linear(),
+            )?,
+        };
+
         let mut builder = AutoCommandBufferBuilder::primary(
             self.command_buffer_allocator.clone(),
             self.queue.queue_family_index(),
@@ -607,7 +1535,7 @@ impl TextSystem {
                             | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                         ..Default::default()
                     },
-                    atlas_data,
+                    atlas.pixels.iter().copied(),
                 )?,
                 atlas_view.image().clone(),
             ),
@@ -619,10 +1547,9 @@ impl TextSystem {
             .then_signal_fence_and_flush()?
             .wait(None)?;
 
-        // Create descriptor set
         let descriptor_set = DescriptorSet::new(
             self.descriptor_set_allocator.clone(),
-            pipeline_layout.set_layouts().get(0).unwrap().clone(),
+            set_layout.clone(),
             [WriteDescriptorSet::image_view_sampler(
                 0,
                 atlas_view.clone(),
@@ -632,17 +1559,10 @@ impl TextSystem {
         )
         .map_err(|e| anyhow::anyhow!("Failed to create descriptor set: {}", e))?;
 
+        atlas.dirty = false;
         self.atlas_texture = Some(atlas_view);
         self.atlas_sampler = Some(sampler);
         self.descriptor_set = Some(descriptor_set);
-        self.is_pipeline_ready = true;
-
-        log::info!(
-            "Text atlas created successfully with {} glyphs, atlas utilization: {:.1}%",
-            self.glyph_infos.len(),
-            (current_y as f32 / ATLAS_SIZE as f32) * 100.0
-        );
-
         Ok(())
     }
 
@@ -668,7 +1588,7 @@ impl TextSystem {
 
         let filename = format!(
             "atlas_debug_{}x{}_size{}_{}.png",
-            atlas_size, atlas_size, self.current_settings.font_size, timestamp
+            atlas_size, atlas_size, self.font_size, timestamp
         );
 
         img_buffer
@@ -678,265 +1598,128 @@ impl TextSystem {
         log::info!(
             "Atlas debug bitmap saved as: {} (utilization: {:.1}%)",
             filename,
-            self.calculate_atlas_utilization(atlas_size)
+            self.calculate_atlas_utilization()
         );
         Ok(())
     }
 
     /// Calculate atlas utilization percentage for debugging
-    fn calculate_atlas_utilization(&self, _atlas_size: u32) -> f32 {
-        if self.glyph_infos.is_empty() {
-            return 0.0;
-        }
-
-        let mut max_y = 0.0_f32;
-        for glyph_info in self.glyph_infos.values() {
-            max_y = max_y.max(glyph_info.uv_max[1]);
-        }
-
-        (max_y * 100.0).min(100.0)
-    }
-
-    pub fn create_text_pipeline(&mut self) -> Result<()> {
-        // This is now handled by create_text_atlas
-        log::info!("Text rendering pipeline marked as ready");
-        Ok(())
-    }
-
-    /// Manually save the current atlas as a debug bitmap
-    /// This can be called at any time after the atlas has been created
-    pub fn save_atlas_debug(&self) -> Result<()> {
-        if self.atlas_texture.is_none() {
-            return Err(anyhow::anyhow!("Atlas has not been created yet"));
-        }
-
-        // We need to recreate the atlas data since it's already been moved to GPU
-        // This is a limitation - we can't easily read back from GPU memory
-        log::warn!("Manual atlas debug save requires recreating atlas data");
-
-        // For now, just log that this feature would need GPU readback
-        log::info!(
-            "To manually save atlas debug bitmap, enable CARGOTAP_DEBUG_ATLAS=true when creating the atlas"
-        );
-
-        Ok(())
+    fn calculate_atlas_utilization(&self) -> f32 {
+        self.atlas.as_ref().map_or(0.0, AtlasPixels::utilization)
     }
 
-    pub fn draw(
-        &self,
-        command_buffer: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
-        text_pipeline: Arc<GraphicsPipeline>,
-        text_pipeline_layout: Arc<PipelineLayout>,
-    ) -> Result<()> {
-        if let (Some(vertex_buffer), Some(descriptor_set)) =
-            (&self.vertex_buffer, &self.descriptor_set)
-        {
-            log::debug!(
-                "TextSystem::draw() called with {} vertices",
-                vertex_buffer.len()
-            );
-
-            if !vertex_buffer.len() > 0 {
-                // Set push constants
-                let push_constants = TextPushConstants {
-                    screen_size: self.window_size,
-                    _padding: [0.0, 0.0],
-                    text_color: self.current_settings.color,
+    /// Collects the records of a layout row by row into `shadow_records` and `records`.
+    /// A row that places the same things as the cached row at its index keeps its
+    /// records; only rows that changed (the typed character's, the caret's, the
+    /// header's) are built again.
+    fn assemble_rows(&mut self, placed: &[Placed], row_starts: &[usize]) {
+        let ranges = row_ranges(placed.len(), row_starts);
+        for (index, range) in ranges.iter().enumerate() {
+            let row = &placed[range.clone()];
+            if self
+                .rows
+                .get(index)
+                .is_none_or(|cached| cached.placed != row)
+            {
+                let mut built = CachedRow {
+                    placed: row.to_vec(),
+                    shadows: Vec::new(),
+                    records: Vec::new(),
                 };
-
-                // Bind text pipeline, descriptor set, set push constants, bind vertex buffer, then draw
-                unsafe {
-                    command_buffer
-                        .bind_pipeline_graphics(text_pipeline)
-                        .map_err(|e| anyhow::anyhow!("Failed to bind text pipeline: {}", e))?
-                        .bind_descriptor_sets(
-                            vulkano::pipeline::PipelineBindPoint::Graphics,
-                            text_pipeline_layout.clone(),
-                            0,
-                            descriptor_set.clone(),
-                        )
-                        .map_err(|e| anyhow::anyhow!("Failed to bind descriptor set: {}", e))?
-                        .push_constants(text_pipeline_layout, 0, push_constants)
-                        .map_err(|e| anyhow::anyhow!("Failed to set push constants: {}", e))?
-                        .bind_vertex_buffers(0, vertex_buffer.clone())
-                        .map_err(|e| anyhow::anyhow!("Failed to bind vertex buffer: {}", e))?
-                        .draw(vertex_buffer.len() as u32, 1, 0, 0)
-                        .map_err(|e| anyhow::anyhow!("Failed to draw vertices: {}", e))?;
+                for placed in row {
+                    self.add_records(*placed, &mut built.shadows, &mut built.records);
+                }
+                if index < self.rows.len() {
+                    self.rows[index] = built;
+                } else {
+                    self.rows.push(built);
                 }
-
-                log::debug!(
-                    "Successfully drew {} text vertices to screen with text pipeline",
-                    vertex_buffer.len()
-                );
             }
-        } else {
-            log::debug!("Vertex buffer or descriptor set not available for text rendering");
+            let cached = &self.rows[index];
+            self.shadow_records.extend_from_slice(&cached.shadows);
+            self.records.extend_from_slice(&cached.records);
         }
-        Ok(())
-    }
-
-    pub fn has_text(&self) -> bool {
-        self.vertex_buffer.is_some() && self.descriptor_set.is_some()
-    }
-
-    pub fn update_window_size(&mut self, width: f32, height: f32) {
-        self.window_size = [width, height];
-    }
-
-    pub fn get_window_size(&self) -> [f32; 2] {
-        self.window_size
+        self.rows.truncate(ranges.len());
     }
 
-    fn calculate_line_width(&self, line: &ColoredLine) -> f32 {
-        let scale = PxScale::from(self.current_settings.font_size);
-        let scaled_font = self.font.as_scaled(scale);
-
-        let mut width = 0.0;
-        for colored_char in &line.chars {
-            let ch = colored_char.ch;
-            if ch == '\r' {
-                continue;
+    /// Turns one placed thing into records: a glyph with its shadows, or a rectangle
+    fn add_records(
+        &mut self,
+        placed: Placed,
+        shadows: &mut Vec<GlyphRecord>,
+        records: &mut Vec<GlyphRecord>,
+    ) {
+        match placed {
+            Placed::Glyph {
+                ch,
+                pen,
+                scale,
+                color,
+            } => {
+                let Some(glyph_info) = self.glyph_info(ch).filter(|info| info.size[0] > 0.0) else {
+                    return;
+                };
+                let position = [
+                    pen[0] + glyph_info.bearing[0] * scale,
+                    pen[1] + glyph_info.bearing[1] * scale,
+                ];
+                for offset in &self.shadow_offsets {
+                    shadows.push(GlyphRecord::glyph(
+                        [position[0] + offset[0], position[1] + offset[1]],
+                        &glyph_info,
+                        self.shadow_color,
+                        scale,
+                    ));
+                }
+                records.push(GlyphRecord::glyph(position, &glyph_info, color, scale));
             }
-
-            let advance_width = if let Some(glyph_info) = self.glyph_infos.get(&ch) {
-                glyph_info.advance
-            } else {
-                let glyph_id = self.font.glyph_id(ch);
-                scaled_font.h_advance(glyph_id)
-            };
-
-            width += advance_width;
+            Placed::Solid {
+                position,
+                size,
+                color,
+            } => records.push(GlyphRecord::solid(position, size, color)),
         }
-
-        width
     }
 
-    fn get_line_height(&self) -> f32 {
-        let scale = PxScale::from(self.current_settings.font_size);
-        let scaled_font = self.font.as_scaled(scale);
-        scaled_font.height()
-    }
-
-    fn get_current_cursor_y(&self) -> f32 {
-        self.current_settings.position[1]
-    }
-
-    fn add_char_vertices(&mut self, colored_char: &ColoredChar) {
-        let scale = PxScale::from(self.current_settings.font_size);
-        let scaled_font = self.font.as_scaled(scale);
-        let cursor_x = self.current_settings.position[0];
-        let cursor_y = self.current_settings.position[1];
-        let line_height = scaled_font.height();
-        let ch = colored_char.ch;
-        let char_color = colored_char.color;
-
-        if ch == '\r' {
-            return;
-        }
-
-        let advance_width = if let Some(glyph_info) = self.glyph_infos.get(&ch) {
-            glyph_info.advance
-        } else {
-            let glyph_id = self.font.glyph_id(ch);
-            scaled_font.h_advance(glyph_id)
-        };
-
-        if let Some(glyph_info) = self.glyph_infos.get(&ch) {
-            let pos_x = cursor_x + glyph_info.bearing[0];
-            let pos_y = cursor_y + glyph_info.bearing[1];
-
-            let glyph_vertices = [
-                TextVertex {
-                    position: [pos_x, pos_y],
-                    tex_coords: [glyph_info.uv_min[0], glyph_info.uv_min[1]],
-                    color: char_color,
-                },
-                TextVertex {
-                    position: [pos_x + glyph_info.size[0], pos_y],
-                    tex_coords: [glyph_info.uv_max[0], glyph_info.uv_min[1]],
-                    color: char_color,
-                },
-                TextVertex {
-                    position: [pos_x, pos_y + glyph_info.size[1]],
-                    tex_coords: [glyph_info.uv_min[0], glyph_info.uv_max[1]],
-                    color: char_color,
-                },
-                TextVertex {
-                    position: [pos_x + glyph_info.size[0], pos_y],
-                    tex_coords: [glyph_info.uv_max[0], glyph_info.uv_min[1]],
-                    color: char_color,
-                },
-                TextVertex {
-                    position: [pos_x + glyph_info.size[0], pos_y + glyph_info.size[1]],
-                    tex_coords: [glyph_info.uv_max[0], glyph_info.uv_max[1]],
-                    color: char_color,
-                },
-                TextVertex {
-                    position: [pos_x, pos_y + glyph_info.size[1]],
-                    tex_coords: [glyph_info.uv_min[0], glyph_info.uv_max[1]],
-                    color: char_color,
-                },
-            ];
-
-            self.vertices.extend_from_slice(&glyph_vertices);
-        }
-
-        if let Some(bg_color) = colored_char.background_color {
-            let bg_x = cursor_x;
-            let bg_y = cursor_y - scaled_font.ascent();
-            let bg_width = advance_width;
-            let bg_height = line_height;
-            let bg_uv = [0.0, 0.0];
-
-            let bg_vertices = [
-                TextVertex {
-                    position: [bg_x, bg_y],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-                TextVertex {
-                    position: [bg_x + bg_width, bg_y],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-                TextVertex {
-                    position: [bg_x, bg_y + bg_height],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-                TextVertex {
-                    position: [bg_x + bg_width, bg_y],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-                TextVertex {
-                    position: [bg_x + bg_width, bg_y + bg_height],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-                TextVertex {
-                    position: [bg_x, bg_y + bg_height],
-                    tex_coords: bg_uv,
-                    color: bg_color,
-                },
-            ];
-
-            self.vertices.extend_from_slice(&bg_vertices);
-        }
-    }
-
-    pub fn flush_vertices(&mut self) -> Result<()> {
-        if self.vertices.is_empty() {
+    /// Uploads the records of the last layout for drawing, unless they are the same
+    /// as the ones already on the GPU
+    fn flush_records(&mut self) -> Result<()> {
+        let hash = records_hash(&self.shadow_records, &self.records);
+        if self.glyphs.is_some() && self.uploaded_hash == Some(hash) {
+            self.records.clear();
+            self.shadow_records.clear();
+            self.update_stats(|stats| stats.skipped_uploads += 1);
+            if self.caret != self.uploaded_caret {
+                self.update_caret_buffer()?;
+                self.publish();
+            }
             return Ok(());
         }
-        let vertices = std::mem::take(&mut self.vertices);
-        self.update_vertex_buffer(vertices)?;
-        Ok(())
-    }
 
-    pub fn clear(&mut self) {
-        self.vertices.clear();
-        self.current_settings.position = [10.0, 30.0];
+        // New glyphs in these records are only in the atlas on the CPU side so far
+        self.upload_atlas()?;
+
+        // Shadows go first so no glyph is covered by its neighbour's shadow
+        let shadow_count = self.shadow_records.len();
+        let mut records = std::mem::take(&mut self.shadow_records);
+        records.append(&mut self.records);
+        self.glyphs = Self::upload_glyphs(
+            &mut self.glyph_ring,
+            &self.descriptor_set_allocator,
+            self.glyph_set_layout.as_ref(),
+            &records,
+        )?;
+        self.uploaded_hash = self.glyphs.is_some().then_some(hash);
+        self.uploaded_shadow_count = shadow_count;
+        // Hand the old copy's allocation back so the next layout doesn't grow it again
+        let mut spare = std::mem::replace(&mut self.uploaded_records, records);
+        spare.clear();
+        self.shadow_records = spare;
+        self.update_caret_buffer()?;
+        let render = self.render_stats();
+        self.update_stats(|stats| stats.render = render);
+        self.publish();
+        Ok(())
     }
 }
 
@@ -989,59 +1772,132 @@ impl TextSurface for TextSystem {
             return WriteResult::Written;
         }
 
-        let scale = PxScale::from(self.current_settings.font_size);
-
-        let advance_width = if let Some(glyph_info) = self.glyph_infos.get(&ch.ch) {
-            glyph_info.advance
-        } else {
-            let glyph_id = self.font.glyph_id(ch.ch);
-            let scaled_font = self.font.as_scaled(scale);
-            scaled_font.h_advance(glyph_id)
-        };
+        let advance_width = self.advance_width(ch.ch);
 
         let cursor_x = self.current_settings.position[0];
         let cursor_y = self.current_settings.position[1];
         let line_height = self.get_line_height();
-        let ascent = {
-            let scaled_font = self.font.as_scaled(scale);
-            scaled_font.ascent()
-        };
-        let total_line_height = cursor_y + line_height - ascent;
+        let total_line_height = cursor_y + line_height - self.get_ascent();
 
-        if cursor_x + advance_width > self.window_size[0] || total_line_height > self.window_size[1]
+        if cursor_x + advance_width > self.right_limit() || total_line_height > self.bottom_limit()
         {
             return WriteResult::Overflow { writed: 0 };
         }
 
-        self.add_char_vertices(ch);
+        self.place_char(ch);
         self.current_settings.position[0] += advance_width;
         WriteResult::Written
     }
 
     fn write_break(&mut self) -> WriteResult {
+        self.end_caret_row();
+        self.start_row();
         let line_height = self.get_line_height();
         self.current_settings.position[0] = 10.0;
         self.current_settings.position[1] += line_height;
+        self.column_right = None;
 
-        let scale = PxScale::from(self.current_settings.font_size);
-        let ascent = {
-            let scaled_font = self.font.as_scaled(scale);
-            scaled_font.ascent()
-        };
-        let total_height = self.current_settings.position[1] + line_height - ascent;
+        let total_height = self.current_settings.position[1] + line_height - self.get_ascent();
 
-        if total_height > self.window_size[1] {
+        if total_height > self.bottom_limit() {
             return WriteResult::Overflow { writed: 0 };
         }
 
         return WriteResult::Written;
     }
+
+    fn set_scale(&mut self, scale: f32) {
+        // The pen sits on the baseline, so keep the top of the next line in place
+        let ascent = self.get_ascent();
+        self.scale = scale;
+        self.current_settings.position[1] += self.get_ascent() - ascent;
+    }
+
+    fn reserve_bottom(&mut self, lines: usize, scale: f32) {
+        let line_height = self.get_line_height() / self.scale;
+        self.reserved_bottom = line_height * (1.0 + lines as f32 * scale);
+    }
+
+    fn start_bottom_area(&mut self) {
+        self.start_row();
+        self.current_settings.position = [10.0, self.bottom_limit() + self.get_ascent()];
+        self.reserved_bottom = 0.0;
+    }
+
+    fn start_column(&mut self, index: usize, count: usize) {
+        const MARGIN: f32 = 10.0;
+        let width = (self.window_size[0] - MARGIN) / count.max(1) as f32;
+        let left = MARGIN + width * index as f32;
+        self.end_caret_row();
+        self.start_row();
+        self.current_settings.position[0] = left;
+        // Keep a margin free before the next column
+        self.column_right = Some(left + width - MARGIN);
+    }
+
+    fn measure(&self, ch: char) -> f32 {
+        self.advance_width(ch)
+    }
+
+    fn remaining_width(&self) -> f32 {
+        (self.right_limit() - self.current_settings.position[0]).max(0.0)
+    }
+
+    fn rows_left(&self) -> usize {
+        let line_height = self.get_line_height();
+        if line_height <= 0.0 {
+            return 0;
+        }
+        let row_top = self.current_settings.position[1] - self.get_ascent();
+        ((self.bottom_limit() - row_top) / line_height).max(0.0) as usize
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_records_hash_follows_content() {
+        let glyph = GlyphRecord::solid([10.0, 30.0], [8.0, 16.0], [1.0, 1.0, 1.0, 1.0]);
+        let moved = GlyphRecord::solid([11.0, 30.0], [8.0, 16.0], [1.0, 1.0, 1.0, 1.0]);
+        let red = GlyphRecord::solid([10.0, 30.0], [8.0, 16.0], [1.0, 0.0, 0.0, 1.0]);
+
+        let frame = records_hash(&[], &[glyph, moved]);
+        assert_eq!(frame, records_hash(&[], &[glyph, moved]));
+        assert_ne!(frame, records_hash(&[], &[glyph, red]));
+        assert_ne!(frame, records_hash(&[], &[moved, glyph]));
+        assert_ne!(frame, records_hash(&[glyph], &[moved]));
+    }
+
+    #[test]
+    fn test_atlas_grows_with_font_size() {
+        assert_eq!(atlas_size_for(32.0), 512);
+        assert_eq!(atlas_size_for(64.0), 512);
+        assert_eq!(atlas_size_for(96.0), 1024);
+        assert_eq!(atlas_size_for(160.0), 2048);
+        assert_eq!(atlas_size_for(2000.0), 4096);
+    }
+
+    #[test]
+    fn test_atlas_packs_rows_and_grows() {
+        let mut atlas = AtlasPixels::new(8, 16);
+        assert_eq!(atlas.allocate(3, 2), Some([0, 0]));
+        assert_eq!(atlas.allocate(3, 4), Some([4, 0]));
+        atlas.set(4, 3, 1.0);
+        // Past the right edge, onto the next row under the tallest glyph
+        assert_eq!(atlas.allocate(3, 2), Some([0, 5]));
+
+        // Too tall for what is left: doubles, with the old pixels in the same place
+        assert_eq!(atlas.allocate(2, 4), Some([4, 5]));
+        assert_eq!(atlas.size, 16);
+        assert_eq!(atlas.pixels[3 * 16 + 4], 255);
+
+        // Nothing bigger than the largest atlas fits
+        assert_eq!(atlas.allocate(17, 1), None);
+        assert_eq!(atlas.size, 16);
+    }
+
     #[test]
     fn test_colored_line_basic() {
         let mut line = ColoredLine::new();
@@ -1111,6 +1967,45 @@ mod tests {
         assert_eq!(text.total_char_count(), 10);
     }
 
+    #[test]
+    fn test_glyph_record_packing() {
+        assert_eq!(std::mem::size_of::<GlyphRecord>(), 32);
+        assert_eq!(pack_u16x2([0, 4095]), 0x0FFF_0000);
+        assert_eq!(pack_u16x2([300, 2]), 0x0002_012C);
+        assert_eq!(pack_unorm4x8([1.0, 0.0, 0.0, 1.0]), 0xFF00_00FF);
+        assert_eq!(pack_unorm4x8(CARET_BLOCK_COLOR), 0x8000_FF00);
+
+        let caret = GlyphRecord::solid([1.0, 2.0], [3.0, 4.0], CARET_BLOCK_COLOR);
+        assert_eq!(caret.flags, SOLID_RECORD);
+    }
+
+    #[test]
+    fn test_row_ranges() {
+        assert_eq!(row_ranges(5, &[2, 4]), vec![0..2, 2..4, 4..5]);
+        // Rows that placed nothing (blank lines) and a start at the very end
+        assert_eq!(row_ranges(5, &[0, 2, 2, 5]), vec![0..2, 2..5]);
+        assert_eq!(row_ranges(3, &[]), vec![0..3]);
+        assert!(row_ranges(0, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_glyph_centered_in_cell() {
+        let cell = CaretCell {
+            x: 10.0,
+            y: 0.0,
+            width: 8.0,
+            height: 16.0,
+            right: 100.0,
+        };
+        // A glyph reaching a little into the cell on the left
+        let overhang = GlyphRecord::solid([9.0, 4.0], [6.0, 10.0], [1.0; 4]);
+        assert!(overhang.centered_in(&cell));
+        let neighbour = GlyphRecord::solid([17.0, 4.0], [6.0, 10.0], [1.0; 4]);
+        assert!(!neighbour.centered_in(&cell));
+        let next_row = GlyphRecord::solid([10.0, 20.0], [6.0, 10.0], [1.0; 4]);
+        assert!(!next_row.centered_in(&cell));
+    }
+
     #[test]
     fn test_write_result_overflow() {
         let result = WriteResult::Overflow { writed: 5 };
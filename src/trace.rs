@@ -0,0 +1,355 @@
+//! Input traces
+//!
+//! With `record_traces` under `[debug]`, every session on a built-in text (the demo
+//! code or a `builtin://` sample) is appended to `traces.jsonl` in the data
+//! directory. A trace lists the keys pressed and the statistics the session ended
+//! with. Keys are reduced to what the typing rules see ("the expected key", "a wrong
+//! key", Backspace, ...), and no typed text, file names or timing are kept.
+//!
+//! Traces copied into `traces/` at the repository root are replayed against the
+//! typing rules by the tests. A change that alters the result of a real session
+//! then fails a test instead of going unnoticed.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::app::DEMO_CODE_PATH;
+use crate::challenge::content_hash;
+use crate::code_state::CodeState;
+use crate::samples;
+use crate::session_state::{SessionState, SessionStats};
+use crate::typing_engine::{self, Outcome, Rules};
+
+/// Where recorded traces are appended, in the data directory of the active profile
+pub const TRACES_FILE: &str = "traces.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// The key that was expected
+    Correct,
+    /// Any other character key
+    Wrong,
+    Enter,
+    Tab,
+    Backspace,
+    /// Manual skip (Ctrl+S)
+    Skip,
+    /// SPACE after a dead zone
+    Resume,
+}
+
+impl Key {
+    /// The key recorded for a typed character
+    pub fn typed(outcome: &Outcome) -> Self {
+        match outcome {
+            Outcome::Accepted(_) => Key::Correct,
+            Outcome::Rejected { .. } | Outcome::Done => Key::Wrong,
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Key::Correct => 'c',
+            Key::Wrong => 'w',
+            Key::Enter => 'e',
+            Key::Tab => 't',
+            Key::Backspace => 'b',
+            Key::Skip => 's',
+            Key::Resume => 'r',
+        }
+    }
+
+    fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter {
+            'c' => Key::Correct,
+            'w' => Key::Wrong,
+            'e' => Key::Enter,
+            't' => Key::Tab,
+            'b' => Key::Backspace,
+            's' => Key::Skip,
+            'r' => Key::Resume,
+            _ => return None,
+        })
+    }
+}
+
+/// The statistics a replay has to reproduce; anything that depends on timing is left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Results {
+    pub chars_typed: usize,
+    pub errors: usize,
+    pub dead_zones: usize,
+    pub end_position: usize,
+    pub key_hits: usize,
+    pub key_misses: usize,
+}
+
+impl Results {
+    fn from_stats(stats: &SessionStats) -> Self {
+        Self {
+            chars_typed: stats.chars_typed,
+            errors: stats.errors,
+            dead_zones: stats.dead_zones,
+            end_position: stats.end_position,
+            key_hits: stats.key_stats.values().map(|stat| stat.hits).sum(),
+            key_misses: stats.key_stats.values().map(|stat| stat.misses).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+    /// Built-in text the session was typed on
+    pub source: String,
+    /// Hash of that text, so a trace of an older version fails loudly instead of
+    /// replaying keys against different characters
+    pub text_hash: String,
+    pub start_position: usize,
+    pub rules: Rules,
+    /// One letter per key: c(orrect), w(rong), e(nter), t(ab), b(ackspace), s(kip), r(esume)
+    pub keys: String,
+    pub results: Results,
+}
+
+/// Collects the keys of the running session
+pub struct Recorder {
+    start_position: usize,
+    rules: Rules,
+    keys: String,
+}
+
+impl Recorder {
+    pub fn new(start_position: usize, rules: Rules) -> Self {
+        Self {
+            start_position,
+            rules,
+            keys: String::new(),
+        }
+    }
+
+    pub fn push(&mut self, key: Key) {
+        self.keys.push(key.letter());
+    }
+
+    /// The trace of a finished session, or None if it wasn't on a built-in text
+    pub fn finish(self, source: &str, stats: &SessionStats) -> Option<Trace> {
+        let text = builtin_text(source)?;
+        Some(Trace {
+            source: source.to_string(),
+            text_hash: content_hash(text),
+            start_position: self.start_position,
+            rules: self.rules,
+            keys: self.keys,
+            results: Results::from_stats(stats),
+        })
+    }
+}
+
+/// Texts that are the same for everyone, so sessions on them can be replayed anywhere
+fn builtin_text(source: &str) -> Option<&'static str> {
+    if source == DEMO_CODE_PATH {
+        Some(include_str!("demo_code.rs"))
+    } else {
+        samples::read(source)
+    }
+}
+
+/// Appends `trace` as one line of JSON
+pub fn append(trace: &Trace, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(trace)?)?;
+    Ok(())
+}
+
+/// Reads every trace in a `.jsonl` file
+pub fn load(path: &Path) -> Result<Vec<Trace>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Can't read traces {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid trace", path.display(), number + 1))
+        })
+        .collect()
+}
+
+/// Presses the keys of `trace` on its text and returns what the session ends with
+pub fn replay(trace: &Trace) -> Result<Results> {
+    let Some(text) = builtin_text(&trace.source) else {
+        bail!("{} is not a built-in text", trace.source);
+    };
+    if content_hash(text) != trace.text_hash {
+        bail!("{} changed since the trace was recorded", trace.source);
+    }
+
+    let mut code = CodeState::new(text.to_string());
+    if !code.set_cursor_position(trace.start_position) {
+        bail!(
+            "position {} is past the end of {}",
+            trace.start_position,
+            trace.source
+        );
+    }
+    let rules = &trace.rules;
+    let mut session = SessionState::new(f64::INFINITY);
+    session.set_dead_zone_streak(rules.dead_zone_streak);
+    session.start(trace.start_position, trace.source.clone());
+
+    for letter in trace.keys.chars() {
+        // Keys after the end go to the session summary, not the text
+        if session.is_finished() {
+            break;
+        }
+        let Some(key) = Key::from_letter(letter) else {
+            bail!("unknown key {:?} in trace", letter);
+        };
+        match key {
+            Key::Correct => {
+                // A character that can't be expected stands in at the end of the text
+                let expected = typing_engine::next_expected(&mut code, &mut session, rules);
+                typing_engine::type_character(
+                    &mut code,
+                    &mut session,
+                    rules,
+                    expected.unwrap_or('\0'),
+                );
+            }
+            Key::Wrong => {
+                typing_engine::type_character(&mut code, &mut session, rules, '\0');
+            }
+            Key::Enter => {
+                typing_engine::enter(&mut code, &mut session, rules);
+            }
+            Key::Tab => {
+                typing_engine::tab(&mut code, &mut session);
+            }
+            Key::Backspace => {
+                typing_engine::backspace(&mut code, &mut session, rules);
+            }
+            Key::Skip => {
+                typing_engine::skip(&mut code, &mut session, rules);
+            }
+            Key::Resume => session.acknowledge_dead_zone(),
+        }
+    }
+
+    Ok(Results::from_stats(
+        &session.current_stats(code.get_cursor_position()),
+    ))
+}
+
+/// Runs the `traces` subcommand: `replay FILE` checks every trace in a file
+pub fn run(args: &[String]) -> Result<()> {
+    let [command, path] = args else {
+        bail!("usage: traces replay FILE");
+    };
+    if command != "replay" {
+        bail!("usage: traces replay FILE");
+    }
+
+    let traces = load(Path::new(path))?;
+    let mut failures = 0;
+    for (number, trace) in traces.iter().enumerate() {
+        match replay(trace) {
+            Ok(results) if results == trace.results => {
+                println!(
+                    "✓ {}: {} ({} keys)",
+                    number + 1,
+                    trace.source,
+                    trace.keys.len()
+                )
+            }
+            Ok(results) => {
+                failures += 1;
+                println!(
+                    "✗ {}: {} recorded {:?}, replayed {:?}",
+                    number + 1,
+                    trace.source,
+                    trace.results,
+                    results
+                );
+            }
+            Err(e) => {
+                failures += 1;
+                println!("✗ {}: {}", number + 1, e);
+            }
+        }
+    }
+    if failures > 0 {
+        bail!("{} of {} traces no longer replay", failures, traces.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameplayConfig;
+
+    #[test]
+    fn test_recorded_traces_replay() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("traces");
+        let mut replayed = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "jsonl") {
+                continue;
+            }
+            for trace in load(&path).unwrap() {
+                let results = replay(&trace).unwrap();
+                assert_eq!(
+                    results,
+                    trace.results,
+                    "{} ({}) replays differently",
+                    path.display(),
+                    trace.source
+                );
+                replayed += 1;
+            }
+        }
+        assert!(replayed > 0, "no traces in {}", dir.display());
+    }
+
+    #[test]
+    fn test_recorder_round_trip() {
+        let source = "builtin://short.rs";
+        let rules = Rules::new(&GameplayConfig::default(), false);
+        let mut recorder = Recorder::new(0, rules);
+        for key in [
+            Key::Correct,
+            Key::Wrong,
+            Key::Backspace,
+            Key::Skip,
+            Key::Correct,
+        ] {
+            recorder.push(key);
+        }
+
+        let mut stats = SessionStats::new(3, 10.0, 0, 3, 2, source.to_string());
+        stats.key_stats.insert('H', Default::default());
+        let trace = recorder.finish(source, &stats).unwrap();
+        assert_eq!(trace.keys, "cwbsc");
+
+        let path = std::env::temp_dir().join("cargotap_traces_test.jsonl");
+        let _ = fs::remove_file(&path);
+        append(&trace, &path).unwrap();
+        append(&trace, &path).unwrap();
+        assert_eq!(load(&path).unwrap(), vec![trace.clone(), trace.clone()]);
+        let _ = fs::remove_file(&path);
+
+        assert!(
+            Recorder::new(0, rules)
+                .finish("src/main.rs", &stats)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Guided first session
+//!
+//! The first time CargoTap is started (no tutorial record and no recorded sessions
+//! in the profile) it opens a short text and walks through the basics one step at
+//! a time: typing, Tab over whitespace, scrolling and the statistics screen. Each
+//! step is shown over the text and moves on when its action is done. Finishing or
+//! skipping with Esc is recorded in the profile's data directory, so it is shown
+//! only once; `--tutorial` runs it again.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Source name recorded for the tutorial session
+pub const TUTORIAL_SOURCE: &str = "tutorial";
+
+/// Record of the finished tutorial, in the data directory of the active profile
+pub const TUTORIAL_FILE: &str = "tutorial.json";
+
+/// What is typed during the tutorial; the runs of spaces are there to try Tab on
+pub const TEXT: &str = r#"// Welcome to CargoTap! Type this text exactly as you see it.
+let name    = "CargoTap";
+let version = 1;
+let answer  = 42;
+
+fn main() {
+    println!("{} v{} says {}", name, version, answer);
+}
+"#;
+
+/// Something done during the tutorial that a step may be waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// A space was typed, ending a word
+    WordTyped,
+    /// Tab typed a run of whitespace
+    Tab,
+    Scrolled,
+    StatisticsOpened,
+    StatisticsClosed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    TypeWord,
+    Tab,
+    Scroll,
+    OpenStatistics,
+    CloseStatistics,
+    Done,
+}
+
+impl Step {
+    /// Steps in order, without `Done`
+    pub const ALL: [Step; 5] = [
+        Step::TypeWord,
+        Step::Tab,
+        Step::Scroll,
+        Step::OpenStatistics,
+        Step::CloseStatistics,
+    ];
+
+    /// The action that completes this step
+    fn completed_by(self) -> Option<Action> {
+        match self {
+            Step::TypeWord => Some(Action::WordTyped),
+            Step::Tab => Some(Action::Tab),
+            Step::Scroll => Some(Action::Scrolled),
+            Step::OpenStatistics => Some(Action::StatisticsOpened),
+            Step::CloseStatistics => Some(Action::StatisticsClosed),
+            Step::Done => None,
+        }
+    }
+
+    fn next(self) -> Step {
+        Step::ALL
+            .iter()
+            .position(|step| *step == self)
+            .and_then(|index| Step::ALL.get(index + 1))
+            .copied()
+            .unwrap_or(Step::Done)
+    }
+
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Step::TypeWord => {
+                "Type the text exactly as it appears, starting with the first word. \
+                 Mistakes are shown in red."
+            }
+            Step::Tab => {
+                "When you reach a space, press Tab: it types all the whitespace up to \
+                 the next character at once. Enter does the same with indentation."
+            }
+            Step::Scroll => {
+                "Ctrl+J (Cmd+J on macOS) scrolls the text down and Ctrl+K scrolls it \
+                 back up. Try it."
+            }
+            Step::OpenStatistics => {
+                "Press Ctrl+T to open your statistics. Sessions are saved when their \
+                 time runs out."
+            }
+            Step::CloseStatistics => {
+                "Here are your statistics; TAB shows the keyboard heatmap. Press Esc \
+                 or Ctrl+T to get back to typing."
+            }
+            Step::Done => "",
+        }
+    }
+}
+
+/// Where the user is in the tutorial
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    step: Step,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Self {
+            step: Step::TypeWord,
+        }
+    }
+}
+
+impl Tutorial {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// 1-based number of the current step
+    pub fn number(&self) -> usize {
+        Step::ALL
+            .iter()
+            .position(|step| *step == self.step)
+            .map_or(Step::ALL.len(), |index| index + 1)
+    }
+
+    /// Moves to the next step if `action` completes the current one; returns true if it did
+    pub fn advance(&mut self, action: Action) -> bool {
+        if self.step.completed_by() != Some(action) {
+            return false;
+        }
+        self.step = self.step.next();
+        true
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step == Step::Done
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Completion {
+    /// Unix timestamp of finishing or skipping the tutorial
+    completed_at: u64,
+    skipped: bool,
+}
+
+/// True if the tutorial was finished or skipped in the profile whose record is at `path`
+pub fn is_completed(path: &Path) -> bool {
+    path.exists()
+}
+
+/// Records that the tutorial was finished (or skipped) so it isn't offered again
+pub fn mark_completed(path: &Path, skipped: bool) -> io::Result<()> {
+    let completion = Completion {
+        completed_at: chrono::Utc::now().timestamp().max(0) as u64,
+        skipped,
+    };
+    let json = serde_json::to_string_pretty(&completion)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_advance_only_on_their_action() {
+        let mut tutorial = Tutorial::new();
+        assert_eq!(tutorial.number(), 1);
+        assert!(!tutorial.advance(Action::StatisticsOpened));
+        assert_eq!(tutorial.step(), Step::TypeWord);
+
+        for action in [
+            Action::WordTyped,
+            Action::Tab,
+            Action::Scrolled,
+            Action::StatisticsOpened,
+        ] {
+            assert!(tutorial.advance(action));
+        }
+        assert_eq!(tutorial.number(), 5);
+        assert!(!tutorial.is_done());
+        assert!(tutorial.advance(Action::StatisticsClosed));
+        assert!(tutorial.is_done());
+        assert!(!tutorial.advance(Action::WordTyped));
+    }
+
+    #[test]
+    fn test_completion_is_recorded() {
+        let path = std::env::temp_dir().join(format!("cargotap_tutorial_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(!is_completed(&path));
+        mark_completed(&path, true).unwrap();
+        assert!(is_completed(&path));
+        let recorded: Completion =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(recorded.skipped);
+        fs::remove_file(&path).unwrap();
+    }
+}
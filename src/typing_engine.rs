@@ -0,0 +1,319 @@
+//! Typing rules
+//!
+//! What each key does to the text and the session, without the window around it.
+//! The typing handler adds logging, script hooks and redraw hints on top, and trace
+//! replays (see `trace`) call the same functions, so a recorded session can be
+//! checked against the current rules.
+
+use serde::{Deserialize, Serialize};
+
+use crate::char_utils;
+use crate::code_state::CodeState;
+use crate::config::{ErrorMode, GameplayConfig};
+use crate::language::Blocks;
+use crate::session_state::SessionState;
+
+/// The settings that change what a key does
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    pub auto_skip_untypeable: bool,
+    pub allow_backspace: bool,
+    pub manual_skip: bool,
+    /// No backspace, and the first mistake ends the session
+    pub strict: bool,
+    pub dead_zone_streak: usize,
+    #[serde(default)]
+    pub error_mode: ErrorMode,
+}
+
+impl Rules {
+    pub fn new(gameplay: &GameplayConfig, strict: bool) -> Self {
+        Self {
+            auto_skip_untypeable: gameplay.auto_skip_untypeable,
+            allow_backspace: gameplay.allow_backspace,
+            manual_skip: gameplay.enable_manual_skip,
+            strict,
+            dead_zone_streak: gameplay.dead_zone_streak,
+            error_mode: gameplay.error_mode,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The expected character was typed
+    Accepted(char),
+    /// A different key was pressed
+    Rejected {
+        expected: char,
+        /// The mismatch started a dead zone
+        dead_zone: bool,
+        /// Strict rules ended the session
+        ended: bool,
+    },
+    /// Nothing left to type
+    Done,
+}
+
+/// Skips characters that can't be typed (when enabled) and returns the next expected one
+pub fn next_expected(
+    code: &mut CodeState,
+    session: &mut SessionState,
+    rules: &Rules,
+) -> Option<char> {
+    if rules.auto_skip_untypeable {
+        while let Some(expected_char) = code.peek_next_character() {
+            if !char_utils::is_typeable_on_us_keyboard(expected_char) || code.in_rtl_run() {
+                if let Some(description) = char_utils::get_untypeable_description(expected_char) {
+                    log::info!("⏭️  Auto-skipping {}", description);
+                }
+                code.type_character();
+                session.record_char_typed();
+            } else {
+                break;
+            }
+        }
+    }
+    code.peek_next_character()
+}
+
+pub fn type_character(
+    code: &mut CodeState,
+    session: &mut SessionState,
+    rules: &Rules,
+    typed_char: char,
+) -> Outcome {
+    let Some(expected_char) = next_expected(code, session, rules) else {
+        return Outcome::Done;
+    };
+    // Past a mistake every key is wrong, and not a miss of the key that was expected
+    if !code.pending_errors().is_empty() {
+        return reject(code, session, rules, expected_char, typed_char);
+    }
+    session.record_key(expected_char, typed_char == expected_char);
+    if typed_char == expected_char {
+        match code.type_character() {
+            Some(ch) => {
+                session.record_char_typed();
+                if ch == '\n' {
+                    session.record_line_typed();
+                }
+                Outcome::Accepted(ch)
+            }
+            None => Outcome::Done,
+        }
+    } else {
+        reject(code, session, rules, expected_char, typed_char)
+    }
+}
+
+/// Types a line break; the indentation of the next line is typed along with it
+pub fn enter(code: &mut CodeState, session: &mut SessionState, rules: &Rules) -> Outcome {
+    let Some(expected_char) = code.peek_next_character() else {
+        return Outcome::Done;
+    };
+    if !code.pending_errors().is_empty() {
+        return reject(code, session, rules, expected_char, '\n');
+    }
+    session.record_key(expected_char, expected_char == '\n');
+    if expected_char != '\n' {
+        return reject(code, session, rules, expected_char, '\n');
+    }
+    if code.type_character().is_none() {
+        return Outcome::Done;
+    }
+    session.record_char_typed();
+    session.record_line_typed();
+    let consumed = code.consume_whitespace();
+    if consumed > 0 {
+        log::debug!(
+            "⇥ Auto-consumed {} whitespace character(s) after newline",
+            consumed
+        );
+    }
+    Outcome::Accepted('\n')
+}
+
+/// Counts a wrong key and shows it in the text the way the error mode wants
+fn reject(
+    code: &mut CodeState,
+    session: &mut SessionState,
+    rules: &Rules,
+    expected: char,
+    typed: char,
+) -> Outcome {
+    match rules.error_mode {
+        ErrorMode::Block => code.mark_missed(),
+        ErrorMode::Insert => {
+            code.push_error(typed);
+        }
+    }
+    session.record_error_line(code.get_cursor_line());
+    let dead_zone = session.record_mismatch();
+    let ended = rules.strict && session.finish(code.get_cursor_position());
+    Outcome::Rejected {
+        expected,
+        dead_zone,
+        ended,
+    }
+}
+
+/// Types the whitespace at the cursor as one character; returns how much was consumed.
+/// Where indentation marks the blocks, Tab stops at the end of the line: jumping into
+/// the next line would skip how deep it is indented, which is the part that matters.
+pub fn tab(code: &mut CodeState, session: &mut SessionState) -> usize {
+    if !code.pending_errors().is_empty() {
+        return 0;
+    }
+    let consumed = match code.language().blocks {
+        Blocks::Indentation => code.consume_line_whitespace(),
+        Blocks::Braces | Blocks::Guess => code.consume_whitespace(),
+    };
+    if consumed > 0 {
+        session.record_char_typed();
+        session.forget_last_key();
+    }
+    consumed
+}
+
+/// Takes back the last wrong key typed over the text, or else the last typed
+/// character unless the rules forbid it. Wrong keys can always be taken back, or
+/// the text could never be finished; they were counted as errors when typed.
+pub fn backspace(code: &mut CodeState, session: &mut SessionState, rules: &Rules) -> Option<char> {
+    if let Some(typed) = code.pop_error() {
+        session.forget_last_key();
+        return Some(typed);
+    }
+    if !rules.allow_backspace || rules.strict {
+        return None;
+    }
+    let ch = code.backspace()?;
+    session.record_error_line(code.get_cursor_line());
+    session.record_backspace();
+    Some(ch)
+}
+
+/// Counts the next character as typed without typing it, if manual skipping is on
+pub fn skip(code: &mut CodeState, session: &mut SessionState, rules: &Rules) -> Option<char> {
+    if !rules.manual_skip || !code.pending_errors().is_empty() {
+        return None;
+    }
+    let expected_char = code.peek_next_character()?;
+    code.type_character();
+    session.record_char_typed();
+    session.forget_last_key();
+    if expected_char == '\n' {
+        session.record_line_typed();
+    }
+    Some(expected_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_update_text_and_session() {
+        let rules = Rules::new(&GameplayConfig::default(), false);
+        let mut code = CodeState::new("ab\n    c".to_string());
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test".to_string());
+
+        assert_eq!(
+            type_character(&mut code, &mut session, &rules, 'a'),
+            Outcome::Accepted('a')
+        );
+        assert!(matches!(
+            type_character(&mut code, &mut session, &rules, 'x'),
+            Outcome::Rejected {
+                expected: 'b',
+                ended: false,
+                ..
+            }
+        ));
+        assert_eq!(backspace(&mut code, &mut session, &rules), Some('a'));
+        assert_eq!(skip(&mut code, &mut session, &rules), Some('a'));
+        assert_eq!(skip(&mut code, &mut session, &rules), Some('b'));
+        assert_eq!(
+            enter(&mut code, &mut session, &rules),
+            Outcome::Accepted('\n')
+        );
+        // The indentation went with the line break
+        assert_eq!(code.peek_next_character(), Some('c'));
+        assert_eq!(tab(&mut code, &mut session), 0);
+        type_character(&mut code, &mut session, &rules, 'x');
+
+        let stats = session.current_stats(code.get_cursor_position());
+        assert_eq!(stats.chars_typed, 3);
+        assert_eq!(stats.errors, 3);
+        assert_eq!(stats.error_lines, [1, 2]);
+    }
+
+    #[test]
+    fn test_strict_rules() {
+        let rules = Rules {
+            strict: true,
+            ..Rules::new(&GameplayConfig::default(), false)
+        };
+        let mut code = CodeState::new("ab".to_string());
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test".to_string());
+
+        type_character(&mut code, &mut session, &rules, 'a');
+        assert_eq!(backspace(&mut code, &mut session, &rules), None);
+        assert!(matches!(
+            enter(&mut code, &mut session, &rules),
+            Outcome::Rejected { ended: true, .. }
+        ));
+        assert!(session.is_finished());
+    }
+
+    #[test]
+    fn test_insert_errors_are_backspaced_first() {
+        let rules = Rules {
+            allow_backspace: false,
+            manual_skip: true,
+            error_mode: ErrorMode::Insert,
+            ..Rules::new(&GameplayConfig::default(), false)
+        };
+        let mut code = CodeState::new("abc  d".to_string());
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test".to_string());
+
+        type_character(&mut code, &mut session, &rules, 'x');
+        // The right key doesn't count while a wrong one is in the way
+        assert!(matches!(
+            type_character(&mut code, &mut session, &rules, 'a'),
+            Outcome::Rejected { expected: 'a', .. }
+        ));
+        assert_eq!(code.pending_errors(), ['x', 'a']);
+        assert_eq!(skip(&mut code, &mut session, &rules), None);
+        assert_eq!(code.get_cursor_position(), 0);
+
+        assert_eq!(backspace(&mut code, &mut session, &rules), Some('a'));
+        assert_eq!(backspace(&mut code, &mut session, &rules), Some('x'));
+        assert_eq!(backspace(&mut code, &mut session, &rules), None);
+        assert_eq!(
+            type_character(&mut code, &mut session, &rules, 'a'),
+            Outcome::Accepted('a')
+        );
+        assert_eq!(session.current_stats(1).errors, 2);
+    }
+
+    #[test]
+    fn test_tab_stops_at_line_end_in_python() {
+        let mut session = SessionState::new(1.0);
+        session.start(0, "test".to_string());
+
+        let mut code = CodeState::new("x = 1  \n    y".to_string());
+        code.set_language(&crate::language::PYTHON);
+        code.set_cursor_position("x = 1".len());
+        assert_eq!(tab(&mut code, &mut session), 2);
+        assert_eq!(code.peek_next_character(), Some('\n'));
+
+        let mut code = CodeState::new("x = 1;  \n    y".to_string());
+        code.set_cursor_position("x = 1;".len());
+        assert_eq!(tab(&mut code, &mut session), 7);
+        assert_eq!(code.peek_next_character(), Some('y'));
+    }
+}
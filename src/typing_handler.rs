@@ -1,15 +1,108 @@
+use std::path::Path;
+
 use log::info;
 
 use crate::app::CargoTapApp;
+use crate::audio;
+use crate::changelog;
 use crate::char_utils;
+use crate::config::MetronomeMode;
+use crate::drills;
+use crate::events::AppEvent;
+use crate::file_browser::{self, EntryKind};
 use crate::input;
+use crate::keyboard;
+use crate::metronome;
+use crate::paths;
+use crate::practice_lock;
+use crate::progress_storage::ViewPreferences;
+use crate::session_history::StatsView;
+use crate::session_state;
+use crate::trace;
+use crate::tutorial;
+use crate::typing_engine;
 
 pub fn handle_typing_input(app: &mut CargoTapApp) {
+    if app.practice_lock_mode {
+        handle_practice_lock_input(app);
+        return;
+    }
+
+    if app.exit_confirm_mode {
+        handle_exit_confirm_input(app);
+        return;
+    }
+
+    if app.resume_offer.is_some() {
+        handle_resume_offer_input(app);
+        return;
+    }
+
+    if app.whats_new.is_some() {
+        handle_whats_new_input(app);
+        return;
+    }
+
+    if app.file_analysis.is_some() {
+        handle_file_analysis_input(app);
+        return;
+    }
+
+    if app.note_edit_path.is_some() {
+        handle_note_input(app);
+        return;
+    }
+
     if app.file_selection_mode {
         handle_file_selection_input(app);
         return;
     }
 
+    if app.settings_mode {
+        handle_settings_input(app);
+        return;
+    }
+
+    if app.drill_menu_mode {
+        handle_drill_menu_input(app);
+        return;
+    }
+
+    if app.end_condition_menu.is_some() {
+        handle_end_condition_menu_input(app);
+        return;
+    }
+
+    if app.dogfood_menu_mode {
+        handle_dogfood_menu_input(app);
+        return;
+    }
+
+    if app.line_range_mode {
+        handle_line_range_input(app);
+        return;
+    }
+
+    if app.goto_line_mode {
+        handle_goto_line_input(app);
+        return;
+    }
+
+    if app.github_mode {
+        handle_github_input(app);
+        return;
+    }
+
+    if app.show_statistics {
+        handle_statistics_input(app);
+        return;
+    }
+
+    if app.pomodoro.as_ref().is_some_and(|p| p.is_on_break()) {
+        handle_break_input(app);
+        return;
+    }
+
     let current_position = app.code_state.get_cursor_position();
     let session_just_finished = app.session_state.update(current_position);
 
@@ -24,7 +117,31 @@ pub fn handle_typing_input(app: &mut CargoTapApp) {
         return;
     }
 
+    if app.session_state.is_paused() {
+        handle_paused_input(app);
+        return;
+    }
+
+    if app.home_row_pending() {
+        handle_home_row_input(app);
+        return;
+    }
+
+    if app.session_state.in_dead_zone() && handle_dead_zone_input(app) {
+        return;
+    }
+
     if let Some(action) = app.input_handler.get_last_action() {
+        // Typing ends a peek, so the view is back on the cursor for the next character
+        if matches!(
+            action,
+            input::InputAction::TypeCharacter(_)
+                | input::InputAction::Backspace
+                | input::InputAction::Enter
+                | input::InputAction::Tab
+        ) {
+            app.scroll_peek_until = None;
+        }
         match action {
             input::InputAction::ScrollDown => handle_scroll_down(app),
             input::InputAction::ScrollUp => handle_scroll_up(app),
@@ -37,7 +154,154 @@ pub fn handle_typing_input(app: &mut CargoTapApp) {
             input::InputAction::Tab => handle_tab(app),
             input::InputAction::ShowStatistics => handle_show_statistics(app),
             input::InputAction::ChangeFile => handle_change_file(app),
-            input::InputAction::Quit | input::InputAction::Other => {}
+            input::InputAction::OpenSettings => handle_open_settings(app),
+            input::InputAction::ToggleQuoteMode => handle_toggle_quote_mode(app),
+            input::InputAction::OpenDrills => handle_open_drills(app),
+            input::InputAction::OpenDogfood => handle_open_dogfood(app),
+            input::InputAction::SelectLineRange => handle_select_line_range(app),
+            input::InputAction::GoToLine => handle_go_to_line(app),
+            input::InputAction::OpenGitHub => handle_open_github(app),
+            input::InputAction::EditNote => handle_edit_note(app),
+            input::InputAction::AdjustLineSpacing(delta) => {
+                let line_spacing = app.config.text.line_spacing + delta;
+                app.set_text_spacing(line_spacing, app.config.text.char_spacing);
+            }
+            input::InputAction::AdjustCharSpacing(delta) => {
+                let char_spacing = app.config.text.char_spacing + delta;
+                app.set_text_spacing(app.config.text.line_spacing, char_spacing);
+            }
+            input::InputAction::AdjustOpacity(delta) => {
+                app.set_opacity(app.config.window.opacity + delta);
+            }
+            input::InputAction::AdjustZoom(delta) => {
+                let zoom = app.view.zoom.unwrap_or(1.0) + delta;
+                app.set_view(ViewPreferences {
+                    zoom: Some(zoom),
+                    ..app.view
+                });
+            }
+            input::InputAction::ToggleWrap => app.set_view(ViewPreferences {
+                wrap_lines: Some(!app.wrap_lines()),
+                ..app.view
+            }),
+            input::InputAction::ToggleLineNumbers => app.set_view(ViewPreferences {
+                show_line_numbers: Some(!app.show_line_numbers()),
+                ..app.view
+            }),
+            input::InputAction::ToggleFolding => app.set_view(ViewPreferences {
+                fold_typed: Some(!app.fold_typed()),
+                ..app.view
+            }),
+            input::InputAction::ToggleWhitespace => app.set_view(ViewPreferences {
+                show_whitespace: Some(!app.show_whitespace()),
+                ..app.view
+            }),
+            input::InputAction::ResetView => app.set_view(ViewPreferences::default()),
+            input::InputAction::ToggleLowVision => app.toggle_low_vision(),
+            input::InputAction::OpenSessionSetup => handle_open_session_setup(app),
+            input::InputAction::SwitchPane => handle_switch_pane(app),
+            input::InputAction::TogglePause => app.pause_session(),
+            input::InputAction::ArrowUp
+            | input::InputAction::ArrowDown
+            | input::InputAction::ArrowLeft
+            | input::InputAction::ArrowRight
+            | input::InputAction::Quit
+            | input::InputAction::DumpState
+            | input::InputAction::ToggleFullscreen
+            | input::InputAction::CycleExtensionFilter
+            | input::InputAction::Other => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+
+    // Streaming drops typed lines off the top; keep the view on the same text
+    let dropped_lines = app.code_state.take_dropped_lines();
+    app.scroll_offset = app.scroll_offset.saturating_sub(dropped_lines);
+
+    // A caret-only redraw wouldn't show the view following the cursor
+    if app.snap_scroll_to_cursor() {
+        app.caret_only_change = false;
+    }
+
+    // A split hands over to the other file every few lines, and for good once this one is done
+    if let Some(split) = &app.split
+        && !split.other.code_state.is_complete()
+        && (app.code_state.is_complete() || split.switch_due(app.code_state.get_cursor_line()))
+    {
+        app.switch_pane();
+    }
+
+    // Quotes, drills, splits and line ranges end as soon as they are typed instead of waiting for the timer
+    let current_position = app.code_state.get_cursor_position();
+    let split_done = app
+        .split
+        .as_ref()
+        .is_some_and(|split| split.other.code_state.is_complete());
+    let text_done = (app.is_generated_text() || split_done) && app.code_state.is_complete();
+    let range_done = app
+        .line_range_bounds()
+        .is_some_and(|(_, end)| current_position >= end);
+    if text_done || range_done {
+        if app.session_state.finish(current_position) {
+            info!("🎉 {} completed!", app.current_file_path);
+            app.save_session_statistics();
+        }
+    }
+}
+
+fn handle_practice_lock_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(ch) => app.practice_lock_input.push(*ch),
+            input::InputAction::Backspace => {
+                app.practice_lock_input.pop();
+            }
+            input::InputAction::Enter => {
+                if practice_lock::phrase_matches(
+                    &app.practice_lock_input,
+                    &app.config.practice_lock.phrase,
+                ) {
+                    info!("🔓 Practice lock phrase typed, quitting early");
+                    app.practice_lock_mode = false;
+                    app.practice_lock_passed = true;
+                    if app.session_state.is_active() {
+                        app.open_exit_confirm();
+                    } else {
+                        app.exit_requested = true;
+                    }
+                } else {
+                    info!("🔒 That is not the practice lock phrase");
+                    app.practice_lock_input.clear();
+                }
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_exit_confirm_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::Enter | input::InputAction::TypeCharacter('s' | 'S') => {
+                let current_position = app.code_state.get_cursor_position();
+                if app.session_state.finish_incomplete(current_position) {
+                    app.save_session_statistics();
+                    info!("🚪 Saved partial session before quitting");
+                }
+                app.exit_requested = true;
+            }
+            input::InputAction::TypeCharacter('d' | 'D') => {
+                info!("🚪 Discarded session before quitting");
+                app.exit_requested = true;
+            }
+            input::InputAction::TypeCharacter('c' | 'C') => {
+                app.close_exit_confirm();
+                info!("🚪 Staying in the session");
+            }
+            _ => {}
         }
 
         app.input_handler.clear_last_action();
@@ -47,10 +311,60 @@ pub fn handle_typing_input(app: &mut CargoTapApp) {
 fn handle_finished_session(app: &mut CargoTapApp) {
     if let Some(action) = app.input_handler.get_last_action() {
         match action {
+            input::InputAction::TypeCharacter(' ') if app.active_quote.is_some() => {
+                let seed = app
+                    .session_state
+                    .seed()
+                    .map_or(0, |seed| seed.wrapping_add(1));
+                app.start_quote(seed);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter('r' | 'R') if app.active_quote.is_some() => {
+                if let Some(seed) = app.session_state.seed() {
+                    app.start_quote(seed);
+                }
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter('r' | 'R') if app.active_drill.is_some() => {
+                if let Some(drill) = app.active_drill {
+                    app.start_drill(drill);
+                }
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter(' ') if app.active_drill.is_some() => {
+                if let Some(drill) = app.active_drill {
+                    app.start_drill(drill.next());
+                }
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter(' ') if app.active_review.is_some() => {
+                // Lines still mistyped get another round; an error-free one is the end
+                if !app.start_review() {
+                    info!("🔁 Review complete");
+                    app.return_to_file();
+                }
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter('c' | 'C') => {
+                app.copy_result_card();
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter('v' | 'V') if app.active_review.is_none() => {
+                app.start_review();
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::TypeCharacter(' ') if app.line_range.is_some() => {
+                if let Some(range) = app.line_range
+                    && let Err(e) = app.start_line_range(range)
+                {
+                    info!("❌ Can't repeat lines {}: {}", range, e);
+                }
+                app.input_handler.clear_last_action();
+            }
             input::InputAction::TypeCharacter(' ') => {
                 let current_pos = app.code_state.get_cursor_position();
                 app.session_state
-                    .start_new_session(current_pos, app.current_file_path.clone());
+                    .start_new_session(current_pos, app.session_file_label());
                 info!("Starting new session from position {}", current_pos);
                 app.input_handler.clear_last_action();
             }
@@ -70,6 +384,34 @@ fn handle_finished_session(app: &mut CargoTapApp) {
                 handle_change_file(app);
                 app.input_handler.clear_last_action();
             }
+            input::InputAction::OpenSettings => {
+                handle_open_settings(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::ToggleQuoteMode => {
+                handle_toggle_quote_mode(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::OpenDrills => {
+                handle_open_drills(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::OpenDogfood => {
+                handle_open_dogfood(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::OpenGitHub => {
+                handle_open_github(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::SelectLineRange => {
+                handle_select_line_range(app);
+                app.input_handler.clear_last_action();
+            }
+            input::InputAction::EditNote => {
+                handle_edit_note(app);
+                app.input_handler.clear_last_action();
+            }
             _ => {
                 app.input_handler.clear_last_action();
             }
@@ -77,10 +419,45 @@ fn handle_finished_session(app: &mut CargoTapApp) {
     }
 }
 
+/// Keeps the session paused until SPACE or the pause key
+fn handle_paused_input(app: &mut CargoTapApp) {
+    if let Some(input::InputAction::TypeCharacter(' ') | input::InputAction::TogglePause) =
+        app.input_handler.get_last_action()
+    {
+        app.resume_session();
+    }
+    app.input_handler.clear_last_action();
+}
+
+/// Swallows typing while paused after a mismatch streak; SPACE resumes
+/// Returns true if the input was consumed
+fn handle_dead_zone_input(app: &mut CargoTapApp) -> bool {
+    let consumed = match app.input_handler.get_last_action() {
+        Some(input::InputAction::TypeCharacter(' ')) => {
+            app.session_state.acknowledge_dead_zone();
+            app.record_trace_key(trace::Key::Resume);
+            info!("✋ Hand position confirmed, resuming");
+            true
+        }
+        Some(
+            input::InputAction::TypeCharacter(_)
+            | input::InputAction::Enter
+            | input::InputAction::Tab
+            | input::InputAction::Backspace
+            | input::InputAction::SkipCharacter,
+        ) => true,
+        _ => false,
+    };
+
+    if consumed {
+        app.input_handler.clear_last_action();
+    }
+    consumed
+}
+
 fn handle_scroll_down(app: &mut CargoTapApp) {
     let scroll_lines = app.config.gameplay.scroll_lines;
-    let full_code = app.code_state.get_full_code();
-    let total_lines = full_code.chars().filter(|&c| c == '\n').count();
+    let total_lines = app.code_state.get_line_break_count();
 
     if app.scroll_offset + scroll_lines <= total_lines {
         app.scroll_offset += scroll_lines;
@@ -98,12 +475,14 @@ fn handle_scroll_down(app: &mut CargoTapApp) {
     } else {
         info!("⬇️ Already at the end of the code");
     }
+    app.start_scroll_peek();
+    app.advance_tutorial(tutorial::Action::Scrolled);
 
     if app.config.gameplay.show_statistics {
         info!(
             "Typing Progress: {:.1}% ({}/{}) | View Offset: {} lines",
             app.code_state.get_progress() * 100.0,
-            app.code_state.get_printed_code().len(),
+            app.code_state.get_cursor_position(),
             app.code_state.get_total_length(),
             app.scroll_offset
         );
@@ -129,12 +508,14 @@ fn handle_scroll_up(app: &mut CargoTapApp) {
     } else {
         info!("⬆️ Already at the beginning of the code");
     }
+    app.start_scroll_peek();
+    app.advance_tutorial(tutorial::Action::Scrolled);
 
     if app.config.gameplay.show_statistics {
         info!(
             "Typing Progress: {:.1}% ({}/{}) | View Offset: {} lines",
             app.code_state.get_progress() * 100.0,
-            app.code_state.get_printed_code().len(),
+            app.code_state.get_cursor_position(),
             app.code_state.get_total_length(),
             app.scroll_offset
         );
@@ -142,110 +523,188 @@ fn handle_scroll_up(app: &mut CargoTapApp) {
 }
 
 fn handle_skip_character(app: &mut CargoTapApp) {
-    if app.config.gameplay.enable_manual_skip {
-        if let Some(expected_char) = app.code_state.peek_next_character() {
-            if let Some(description) = char_utils::get_untypeable_description(expected_char) {
-                info!("⏭️  Manually skipping {}", description);
+    let rules = app.typing_rules();
+    if !rules.manual_skip {
+        if app.config.debug.log_code_state {
+            info!("⛔ Manual skip is disabled in configuration");
+        }
+        return;
+    }
+
+    match typing_engine::skip(&mut app.code_state, &mut app.session_state, &rules) {
+        Some(skipped) => {
+            app.record_trace_key(trace::Key::Skip);
+            if let Some(description) = char_utils::get_untypeable_description(skipped) {
+                info!("⏭️  Manually skipped {}", description);
             } else {
-                info!("⏭️  Manually skipping character: '{}'", expected_char);
+                info!("⏭️  Manually skipped character: '{}'", skipped);
             }
-
-            app.code_state.type_character();
-            app.session_state.record_char_typed();
-        } else {
-            info!("⏭️  No character to skip");
         }
-    } else if app.config.debug.log_code_state {
-        info!("⛔ Manual skip is disabled in configuration");
+        None => info!("⏭️  No character to skip"),
     }
 }
 
 fn handle_type_character(app: &mut CargoTapApp, typed_char: char) {
+    let window_position = app.code_state.get_window_cursor_position();
     if !app.session_state.is_active() {
-        let current_pos = app.code_state.get_cursor_position();
-        app.session_state
-            .start(current_pos, app.current_file_path.clone());
+        app.start_session();
     }
-
-    if app.config.gameplay.auto_skip_untypeable {
-        while let Some(expected_char) = app.code_state.peek_next_character() {
-            if !char_utils::is_typeable_on_us_keyboard(expected_char) {
-                if let Some(description) = char_utils::get_untypeable_description(expected_char) {
-                    info!("⏭️  Auto-skipping {}", description);
-                }
-                app.code_state.type_character();
-                app.session_state.record_char_typed();
-            } else {
-                break;
-            }
-        }
+    if held_back_by_metronome(app) {
+        return;
     }
 
-    if let Some(expected_char) = app.code_state.peek_next_character() {
-        if typed_char == expected_char {
-            let advanced_char = app.code_state.type_character();
-            if let Some(ch) = advanced_char {
-                app.session_state.record_char_typed();
-                if app.config.debug.log_code_state {
-                    info!("✓ Correctly typed: '{}'", ch);
-                }
-                if app.config.gameplay.show_statistics {
-                    info!(
-                        "Progress: {:.1}% ({}/{})",
-                        app.code_state.get_progress() * 100.0,
-                        app.code_state.get_printed_code().len(),
-                        app.code_state.get_total_length()
-                    );
-                }
+    let rules = app.typing_rules();
+    let outcome = typing_engine::type_character(
+        &mut app.code_state,
+        &mut app.session_state,
+        &rules,
+        typed_char,
+    );
+    app.record_trace_key(trace::Key::typed(&outcome));
+    match outcome {
+        typing_engine::Outcome::Accepted(ch) => {
+            app.play_sound(audio::Cue::Key);
+            // Skipped characters or a shifted stream window need a full redraw
+            app.caret_only_change = ch != '\n'
+                && !app.code_state.is_complete()
+                && app.code_state.get_window_cursor_position() == window_position + ch.len_utf8();
+            app.run_script_hook(|host| host.on_char_accepted(ch));
+            if ch == ' ' {
+                app.advance_tutorial(tutorial::Action::WordTyped);
+            }
+            if app.config.debug.log_code_state {
+                info!(
+                    event = "key_accepted",
+                    key = ch,
+                    position = app.code_state.get_cursor_position();
+                    "✓ Correctly typed: '{}'",
+                    ch
+                );
+            }
+            log_progress(app);
 
-                if app.code_state.is_complete() {
-                    info!("🎉 Code typing completed!");
-                } else if let Some(next_char) = app.code_state.peek_next_character() {
-                    if !app.config.gameplay.auto_skip_untypeable
-                        && !char_utils::is_typeable_on_us_keyboard(next_char)
-                    {
-                        if let Some(description) = char_utils::get_untypeable_description(next_char)
-                        {
-                            info!("⚠️  Next character is {}", description);
-                            if app.config.gameplay.enable_manual_skip {
-                                info!("💡 Press Ctrl+S (or Cmd+S) to skip it");
-                            }
+            if app.code_state.is_complete() {
+                info!(
+                    event = "file_completed",
+                    file = app.current_file_path.as_str();
+                    "🎉 Code typing completed!"
+                );
+            } else if let Some(next_char) = app.code_state.peek_next_character() {
+                if !rules.auto_skip_untypeable && !char_utils::is_typeable_on_us_keyboard(next_char)
+                {
+                    if let Some(description) = char_utils::get_untypeable_description(next_char) {
+                        info!("⚠️  Next character is {}", description);
+                        if rules.manual_skip {
+                            info!("💡 Press Ctrl+S (or Cmd+S) to skip it");
                         }
-                    } else if app.config.gameplay.show_next_char_hint {
-                        info!("Next character: '{}'", next_char);
                     }
+                } else if app.config.gameplay.show_next_char_hint {
+                    info!("Next character: '{}'", next_char);
                 }
             }
-        } else if app.config.debug.log_code_state {
-            info!(
-                "❌ Incorrect character! Expected '{}', got '{}'",
-                expected_char, typed_char
-            );
         }
+        typing_engine::Outcome::Rejected {
+            expected,
+            dead_zone,
+            ended,
+        } => {
+            app.play_sound(audio::Cue::Error);
+            log_rejection(app, expected, typed_char, dead_zone);
+            if ended {
+                info!("⛔ Strict mode: the mistake ends the session");
+                app.save_session_statistics();
+            }
+        }
+        typing_engine::Outcome::Done => {}
+    }
+}
+
+/// Checks a keystroke against the metronome's pace. A key that comes too soon is
+/// counted; it is held back (true) unless the metronome only flags it.
+fn held_back_by_metronome(app: &mut CargoTapApp) -> bool {
+    let Some(metronome) = &mut app.metronome else {
+        return false;
+    };
+    if metronome.keystroke(std::time::Instant::now()) == metronome::Pace::InTime {
+        return false;
+    }
+    let held_back = metronome.mode() == MetronomeMode::Reject;
+    app.session_state.record_too_fast();
+    if app.config.debug.log_code_state {
+        info!(
+            event = "too_fast",
+            held_back = held_back;
+            "🥁 Too fast for the metronome{}",
+            if held_back { ", key ignored" } else { "" }
+        );
+    }
+    held_back
+}
+
+/// Reports a wrong key to the log and to scripts, and explains the key if it keeps going wrong
+fn log_rejection(app: &mut CargoTapApp, expected: char, typed_char: char, dead_zone: bool) {
+    if dead_zone {
+        info!("✋ Too many mismatches in a row - check your hand position");
+    } else {
+        app.offer_symbol_hint(expected);
+    }
+    app.run_script_hook(|host| host.on_char_rejected(expected, typed_char));
+    if app.config.debug.log_code_state {
+        info!(
+            event = "key_rejected",
+            expected = expected,
+            typed = typed_char,
+            position = app.code_state.get_cursor_position();
+            "❌ Incorrect character! Expected '{}', got '{}'",
+            expected, typed_char
+        );
+    }
+}
+
+fn log_progress(app: &CargoTapApp) {
+    if app.config.gameplay.show_statistics {
+        info!(
+            event = "progress",
+            position = app.code_state.get_cursor_position(),
+            total = app.code_state.get_total_length();
+            "Progress: {:.1}% ({}/{})",
+            app.code_state.get_progress() * 100.0,
+            app.code_state.get_cursor_position(),
+            app.code_state.get_total_length()
+        );
+    }
+}
+
+fn handle_switch_pane(app: &mut CargoTapApp) {
+    if app.split.is_some() {
+        app.switch_pane();
+    } else {
+        info!("Not in split practice; start it with: cargo run split FILE_A FILE_B");
     }
 }
 
 fn handle_backspace(app: &mut CargoTapApp) {
-    if !app.config.gameplay.allow_backspace {
+    let rules = app.typing_rules();
+    if (!rules.allow_backspace || rules.strict) && app.code_state.pending_errors().is_empty() {
         if app.config.debug.log_code_state {
             info!("⛔ Backspace is disabled in configuration");
         }
         return;
     }
 
-    if let Some(ch) = app.code_state.backspace() {
-        app.session_state.record_backspace();
+    if let Some(ch) = typing_engine::backspace(&mut app.code_state, &mut app.session_state, &rules)
+    {
+        app.record_trace_key(trace::Key::Backspace);
         if app.config.debug.log_code_state {
-            info!("⬅️ Backspace: moved '{}' back to current code", ch);
-        }
-        if app.config.gameplay.show_statistics {
             info!(
-                "Progress: {:.1}% ({}/{})",
-                app.code_state.get_progress() * 100.0,
-                app.code_state.get_printed_code().len(),
-                app.code_state.get_total_length()
+                event = "backspace",
+                key = ch,
+                position = app.code_state.get_cursor_position();
+                "⬅️ Backspace: moved '{}' back to current code",
+                ch
             );
         }
+        log_progress(app);
         if app.config.gameplay.show_next_char_hint {
             if let Some(next_char) = app.code_state.peek_next_character() {
                 info!("Next character: '{}'", next_char);
@@ -256,61 +715,61 @@ fn handle_backspace(app: &mut CargoTapApp) {
 
 fn handle_enter(app: &mut CargoTapApp) {
     if !app.session_state.is_active() {
-        let current_pos = app.code_state.get_cursor_position();
-        app.session_state
-            .start(current_pos, app.current_file_path.clone());
+        app.start_session();
+    }
+    if held_back_by_metronome(app) {
+        return;
     }
 
-    if let Some(expected_char) = app.code_state.peek_next_character() {
-        if expected_char == '\n' {
-            let advanced_char = app.code_state.type_character();
-            if advanced_char.is_some() {
-                app.session_state.record_char_typed();
-
-                if app.config.debug.log_code_state {
-                    info!("✓ Correctly typed newline");
-                }
-
-                let consumed = app.code_state.consume_whitespace();
-                if consumed > 0 && app.config.debug.log_code_state {
-                    info!(
-                        "⇥ Auto-consumed {} whitespace character(s) after newline",
-                        consumed
-                    );
-                }
-
-                if app.config.gameplay.show_statistics {
-                    info!(
-                        "Progress: {:.1}% ({}/{})",
-                        app.code_state.get_progress() * 100.0,
-                        app.code_state.get_printed_code().len(),
-                        app.code_state.get_total_length()
-                    );
-                }
+    let rules = app.typing_rules();
+    let outcome = typing_engine::enter(&mut app.code_state, &mut app.session_state, &rules);
+    app.record_trace_key(trace::Key::Enter);
+    match outcome {
+        typing_engine::Outcome::Accepted(_) => {
+            app.play_sound(audio::Cue::Key);
+            app.run_script_hook(|host| host.on_char_accepted('\n'));
+            if app.config.debug.log_code_state {
+                info!(
+                    event = "key_accepted",
+                    key = '\n',
+                    position = app.code_state.get_cursor_position();
+                    "✓ Correctly typed newline"
+                );
             }
-        } else if app.config.debug.log_code_state {
-            info!("❌ Incorrect! Expected '{}', got newline", expected_char);
+            log_progress(app);
         }
+        typing_engine::Outcome::Rejected {
+            expected,
+            dead_zone,
+            ended,
+        } => {
+            app.play_sound(audio::Cue::Error);
+            log_rejection(app, expected, '\n', dead_zone);
+            if ended {
+                info!("⛔ Strict mode: the mistake ends the session");
+                app.save_session_statistics();
+            }
+        }
+        typing_engine::Outcome::Done => {}
     }
 }
 
 fn handle_tab(app: &mut CargoTapApp) {
-    let consumed = app.code_state.consume_whitespace();
+    let consumed = typing_engine::tab(&mut app.code_state, &mut app.session_state);
 
     if consumed > 0 {
-        app.session_state.record_char_typed();
-
+        app.record_trace_key(trace::Key::Tab);
+        app.advance_tutorial(tutorial::Action::Tab);
         if app.config.debug.log_code_state {
-            info!("⇥ Tab: consumed {} whitespace character(s)", consumed);
-        }
-        if app.config.gameplay.show_statistics {
             info!(
-                "Progress: {:.1}% ({}/{})",
-                app.code_state.get_progress() * 100.0,
-                app.code_state.get_printed_code().len(),
-                app.code_state.get_total_length()
+                event = "tab",
+                consumed = consumed,
+                position = app.code_state.get_cursor_position();
+                "⇥ Tab: consumed {} whitespace character(s)",
+                consumed
             );
         }
+        log_progress(app);
         if app.config.gameplay.show_next_char_hint {
             if let Some(next_char) = app.code_state.peek_next_character() {
                 info!("Next character: '{}'", next_char);
@@ -321,12 +780,117 @@ fn handle_tab(app: &mut CargoTapApp) {
     }
 }
 
+fn handle_break_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(' ') => {
+                if let Some(pomodoro) = &mut app.pomodoro {
+                    pomodoro.skip_break();
+                }
+                app.resume_after_break();
+            }
+            input::InputAction::ShowStatistics => handle_show_statistics(app),
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_home_row_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(ch) => {
+                if let Some(check) = &mut app.home_row_check {
+                    check.press(*ch);
+                }
+            }
+            input::InputAction::ShowStatistics => handle_show_statistics(app),
+            input::InputAction::ChangeFile => handle_change_file(app),
+            input::InputAction::OpenSettings => handle_open_settings(app),
+            input::InputAction::OpenDrills => handle_open_drills(app),
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_statistics_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::ShowStatistics => handle_show_statistics(app),
+            input::InputAction::Tab => {
+                app.stats_view = app.stats_view.next();
+                info!("⌨️  Statistics view: {:?}", app.stats_view);
+            }
+            input::InputAction::ArrowLeft if app.stats_view != StatsView::Summary => {
+                app.stats_range = app.stats_range.previous();
+            }
+            input::InputAction::ArrowRight if app.stats_view != StatsView::Summary => {
+                app.stats_range = app.stats_range.next();
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
 fn handle_show_statistics(app: &mut CargoTapApp) {
     app.show_statistics = !app.show_statistics;
     if app.show_statistics {
         info!("📊 Showing statistics screen");
+        app.advance_tutorial(tutorial::Action::StatisticsOpened);
     } else {
         info!("📊 Hiding statistics screen");
+        app.advance_tutorial(tutorial::Action::StatisticsClosed);
+    }
+}
+
+fn handle_edit_note(app: &mut CargoTapApp) {
+    if app.is_generated_text() {
+        info!("📝 Notes can only be attached to files");
+        return;
+    }
+    open_note_editor(app, app.current_file_path.clone());
+}
+
+fn open_note_editor(app: &mut CargoTapApp, file_path: String) {
+    if file_path.is_empty() {
+        return;
+    }
+
+    app.note_input = app
+        .progress_storage
+        .get_note(&file_path)
+        .unwrap_or_default()
+        .to_string();
+    info!("📝 Editing note for {}", file_path);
+    app.note_edit_path = Some(file_path);
+}
+
+fn handle_note_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(ch) => app.note_input.push(*ch),
+            input::InputAction::Backspace => {
+                app.note_input.pop();
+            }
+            input::InputAction::Enter => {
+                if let Some(file_path) = app.note_edit_path.take() {
+                    app.progress_storage.set_note(&file_path, &app.note_input);
+                    if let Err(e) = app.progress_storage.save() {
+                        log::error!("Failed to save note: {}", e);
+                    } else {
+                        info!("📝 Saved note for {}", file_path);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
     }
 }
 
@@ -351,32 +915,487 @@ fn handle_file_selection_input(app: &mut CargoTapApp) {
         match action {
             input::InputAction::TypeCharacter(ch) => {
                 app.file_input_buffer.push(*ch);
+                app.file_error = None;
+                select_best_match(app);
             }
             input::InputAction::Backspace => {
                 app.file_input_buffer.pop();
+                app.file_error = None;
+                select_best_match(app);
             }
-            input::InputAction::Enter => {
-                let file_path = app.file_input_buffer.trim().to_string();
-                if !file_path.is_empty() {
-                    info!("📂 Attempting to load file: {}", file_path);
-                    match app.load_file(file_path.clone()) {
-                        Ok(_) => {
-                            info!("✅ Successfully loaded file: {}", file_path);
-                            app.file_selection_mode = false;
-                            app.file_input_buffer.clear();
-                        }
+            input::InputAction::ArrowDown => {
+                let len = app.file_listing().entries.len();
+                app.file_browser.select_next(len);
+            }
+            input::InputAction::ArrowUp => app.file_browser.select_previous(),
+            input::InputAction::ArrowRight => {
+                if let Some(entry) = selected_entry(app)
+                    && entry.kind == EntryKind::Directory
+                {
+                    browse_to(app, file_browser::dir_input(Path::new(&entry.path)));
+                }
+            }
+            input::InputAction::ArrowLeft => {
+                let dir = app.file_listing().dir;
+                let dir = std::fs::canonicalize(&dir).unwrap_or_else(|_| dir.into());
+                if let Some(parent) = dir.parent() {
+                    browse_to(app, file_browser::dir_input(parent));
+                }
+            }
+            input::InputAction::Enter => match selected_entry(app) {
+                Some(entry) if entry.kind == EntryKind::Directory => {
+                    browse_to(app, file_browser::dir_input(Path::new(&entry.path)));
+                }
+                Some(entry) => load_selected_file(app, entry.path),
+                None => {
+                    let expanded = paths::expand(
+                        &app.file_input_buffer,
+                        app.config.gameplay.workspace_root.as_deref(),
+                    );
+                    match expanded {
+                        Ok(file_path) => load_selected_file(app, file_path),
                         Err(e) => {
-                            info!("❌ Failed to load file: {}", e);
+                            info!("❌ {}", e);
+                            app.file_error = Some(e.to_string());
                         }
                     }
-                } else {
-                    info!("❌ File path cannot be empty");
+                }
+            },
+            input::InputAction::EditNote => {
+                let file_path = match selected_entry(app) {
+                    Some(entry) if entry.kind != EntryKind::Directory => entry.path,
+                    _ => paths::expand(
+                        &app.file_input_buffer,
+                        app.config.gameplay.workspace_root.as_deref(),
+                    )
+                    .unwrap_or_else(|_| app.file_input_buffer.trim().to_string()),
+                };
+                open_note_editor(app, file_path);
+            }
+            input::InputAction::CycleExtensionFilter => {
+                let extensions = app.file_listing().extensions;
+                app.file_browser.cycle_extension(&extensions);
+                match &app.file_browser.extension {
+                    Some(extension) => info!("📂 Listing only .{} files", extension),
+                    None => info!("📂 Listing files of every extension"),
+                }
+            }
+            input::InputAction::Tab => {
+                // Complete a directory name if there is one, otherwise change the hand filter
+                if let Some(completed) = paths::complete_dir(
+                    &app.file_input_buffer,
+                    app.config.gameplay.workspace_root.as_deref(),
+                ) {
+                    browse_to(app, completed);
+                    app.input_handler.clear_last_action();
+                    return;
+                }
+                app.file_browser.selected = None;
+                app.file_hand_filter = match app.file_hand_filter {
+                    None => Some(keyboard::Hand::Left),
+                    Some(keyboard::Hand::Left) => Some(keyboard::Hand::Right),
+                    Some(keyboard::Hand::Right) => None,
+                };
+                match app.file_hand_filter {
+                    Some(hand) => info!(
+                        "✋ Listing files typed mostly with the {} hand",
+                        hand.name()
+                    ),
+                    None => info!("✋ Listing all files"),
                 }
             }
             input::InputAction::Quit => {
                 info!("📂 Exiting file selection mode");
                 app.file_selection_mode = false;
                 app.file_input_buffer.clear();
+                app.file_error = None;
+                app.file_browser.selected = None;
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+/// The file picker entry the arrow keys are on
+fn selected_entry(app: &CargoTapApp) -> Option<file_browser::Entry> {
+    let index = app.file_browser.selected?;
+    app.file_listing().entries.into_iter().nth(index)
+}
+
+/// Highlights the best match of a fuzzy search, so Enter opens it
+fn select_best_match(app: &mut CargoTapApp) {
+    let listing = app.file_listing();
+    app.file_browser.selected =
+        (!listing.query.is_empty() && !listing.entries.is_empty()).then_some(0);
+}
+
+/// Puts `input` into the prompt and lists it from the top
+fn browse_to(app: &mut CargoTapApp, input: String) {
+    app.file_input_buffer = input;
+    app.file_error = None;
+    app.file_browser.selected = None;
+}
+
+fn load_selected_file(app: &mut CargoTapApp, file_path: String) {
+    info!("📂 Attempting to load file: {}", file_path);
+    match app.load_file(file_path.clone()) {
+        Ok(_) => {
+            info!("✅ Successfully loaded file: {}", file_path);
+            app.file_selection_mode = false;
+            app.file_input_buffer.clear();
+            app.file_error = None;
+            app.file_browser.selected = None;
+            app.show_file_analysis();
+        }
+        Err(e) => {
+            info!("❌ Failed to load file: {}", e);
+            app.file_error = Some(e.to_string());
+        }
+    }
+}
+
+fn handle_toggle_quote_mode(app: &mut CargoTapApp) {
+    if app.active_quote.is_some() {
+        app.return_to_file();
+        info!("💬 Leaving quote mode");
+    } else {
+        app.start_quote(app.next_seed());
+        info!("💬 Entering quote mode");
+    }
+}
+
+fn handle_open_drills(app: &mut CargoTapApp) {
+    if app.active_drill.is_some() {
+        app.return_to_file();
+        info!("🥁 Leaving drill mode");
+    } else {
+        app.drill_menu_mode = true;
+        info!("🥁 Opening drills menu");
+    }
+}
+
+fn handle_drill_menu_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::ArrowUp => app.drill_menu.select_previous(),
+            input::InputAction::ArrowDown => app.drill_menu.select_next(),
+            input::InputAction::ArrowLeft => app.drill_menu.adjust_difficulty(-1),
+            input::InputAction::ArrowRight => app.drill_menu.adjust_difficulty(1),
+            input::InputAction::Enter => {
+                let seed = app.next_seed();
+                app.drill_menu_mode = false;
+                app.start_drill(drills::Drill {
+                    kind: app.drill_menu.selected_kind(),
+                    difficulty: app.drill_menu.difficulty(),
+                    seed,
+                });
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_open_session_setup(app: &mut CargoTapApp) {
+    if app.end_condition_locked() {
+        let message = "Pomodoro and challenge sessions always end on their timer";
+        info!("🏁 {}", message);
+        app.toast = Some((message.to_string(), std::time::Instant::now()));
+        return;
+    }
+    app.end_condition_menu = Some(session_state::EndConditionMenu::new(
+        app.session_state.end_condition(),
+    ));
+    info!("🏁 Opening session setup");
+}
+
+fn handle_end_condition_menu_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action()
+        && let Some(menu) = &mut app.end_condition_menu
+    {
+        match action {
+            input::InputAction::ArrowUp => menu.select_previous(),
+            input::InputAction::ArrowDown => menu.select_next(),
+            input::InputAction::ArrowLeft => menu.adjust(false),
+            input::InputAction::ArrowRight => menu.adjust(true),
+            input::InputAction::Enter => {
+                let end_condition = menu.condition(menu.selected());
+                app.end_condition_menu = None;
+                app.set_end_condition(end_condition);
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_open_dogfood(app: &mut CargoTapApp) {
+    if app.dogfood_modules.is_empty() {
+        info!("🐶 No embedded source files available");
+        return;
+    }
+
+    // Start from the module being typed, if it is one of ours
+    if let Some(index) = app
+        .dogfood_modules
+        .iter()
+        .position(|path| *path == app.current_file_path)
+    {
+        app.dogfood_selected = index;
+    }
+    app.dogfood_menu_mode = true;
+    info!("🐶 Opening dogfood menu");
+}
+
+fn handle_dogfood_menu_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        let count = app.dogfood_modules.len().max(1);
+        match action {
+            input::InputAction::ArrowUp => {
+                app.dogfood_selected = (app.dogfood_selected + count - 1) % count;
+            }
+            input::InputAction::ArrowDown => {
+                app.dogfood_selected = (app.dogfood_selected + 1) % count;
+            }
+            input::InputAction::Enter => {
+                if let Some(path) = app.dogfood_modules.get(app.dogfood_selected).cloned() {
+                    match app.load_file(path.clone()) {
+                        Ok(_) => {
+                            info!("🐶 Practicing {}", path);
+                            app.dogfood_menu_mode = false;
+                            app.show_file_analysis();
+                        }
+                        Err(e) => info!("❌ Failed to load module: {}", e),
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_resume_offer_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        if let input::InputAction::Enter = action {
+            app.accept_resume_offer();
+        }
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_whats_new_input(app: &mut CargoTapApp) {
+    let Some(action) = app.input_handler.get_last_action() else {
+        return;
+    };
+    match action {
+        input::InputAction::ArrowUp => {
+            if let Some(whats_new) = &mut app.whats_new {
+                whats_new.select_previous();
+            }
+        }
+        input::InputAction::ArrowDown => {
+            if let Some(whats_new) = &mut app.whats_new {
+                whats_new.select_next();
+            }
+        }
+        input::InputAction::Enter => {
+            let target = app
+                .whats_new
+                .take()
+                .and_then(|whats_new| whats_new.selected_entry().and_then(|entry| entry.open));
+            info!("✨ Closed what's new");
+            match target {
+                Some(changelog::Target::Settings) => handle_open_settings(app),
+                Some(changelog::Target::SessionSetup) => handle_open_session_setup(app),
+                Some(changelog::Target::Drills) => handle_open_drills(app),
+                Some(changelog::Target::Dogfood) => handle_open_dogfood(app),
+                Some(changelog::Target::FilePicker) => handle_change_file(app),
+                Some(changelog::Target::LineRange) => handle_select_line_range(app),
+                None => {}
+            }
+        }
+        input::InputAction::TypeCharacter(' ') => {
+            app.whats_new = None;
+            info!("✨ Closed what's new");
+        }
+        _ => {}
+    }
+    app.input_handler.clear_last_action();
+}
+
+fn handle_file_analysis_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter('s' | 'S')
+                if app
+                    .file_analysis
+                    .as_ref()
+                    .is_some_and(|analysis| analysis.untypeable_lines > 0) =>
+            {
+                app.strip_untypeable_lines();
+            }
+            input::InputAction::Enter
+            | input::InputAction::Quit
+            | input::InputAction::TypeCharacter(' ') => {
+                app.file_analysis = None;
+                info!("📋 Closed the file analysis");
+            }
+            _ => {}
+        }
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_select_line_range(app: &mut CargoTapApp) {
+    if app.is_generated_text() {
+        info!("📏 Line ranges are only available for files");
+        return;
+    }
+
+    app.line_range_mode = true;
+    app.line_range_error = None;
+    app.line_range_input = app.line_range.map(|r| r.to_string()).unwrap_or_default();
+    info!("📏 Entering line range prompt");
+}
+
+fn handle_line_range_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(ch) => {
+                app.line_range_input.push(*ch);
+                app.line_range_error = None;
+            }
+            input::InputAction::Backspace => {
+                app.line_range_input.pop();
+                app.line_range_error = None;
+            }
+            input::InputAction::Enter => {
+                let input = app.line_range_input.trim().to_string();
+                if input.is_empty() {
+                    app.clear_line_range();
+                    app.line_range_mode = false;
+                    info!("📏 Practicing the whole file again");
+                } else {
+                    match session_state::LineRange::parse(&input) {
+                        Some(range) => match app.start_line_range(range) {
+                            Ok(_) => app.line_range_mode = false,
+                            Err(e) => app.line_range_error = Some(e.to_string()),
+                        },
+                        None => {
+                            app.line_range_error =
+                                Some(format!("'{}' is not a range like 120-180", input));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_go_to_line(app: &mut CargoTapApp) {
+    app.goto_line_mode = true;
+    app.goto_line_error = None;
+    app.goto_line_input.clear();
+    info!("↪ Entering go-to-line prompt");
+}
+
+fn handle_goto_line_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::TypeCharacter(ch) if ch.is_ascii_digit() => {
+                app.goto_line_input.push(*ch);
+                app.goto_line_error = None;
+            }
+            input::InputAction::Backspace => {
+                app.goto_line_input.pop();
+                app.goto_line_error = None;
+            }
+            input::InputAction::Enter => match app.goto_line_input.parse::<usize>() {
+                Ok(line) => match app.go_to_line(line) {
+                    Ok(_) => app.goto_line_mode = false,
+                    Err(e) => app.goto_line_error = Some(e.to_string()),
+                },
+                Err(_) => app.goto_line_error = Some("Type a line number".to_string()),
+            },
+            _ => {}
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_open_github(app: &mut CargoTapApp) {
+    app.github_mode = true;
+    app.github_error = None;
+    info!("🐙 Entering GitHub prompt");
+}
+
+fn handle_github_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        // The prompt is read-only while a download runs
+        if app.github_fetch.is_none() {
+            match action {
+                input::InputAction::TypeCharacter(ch) => {
+                    app.github_input.push(*ch);
+                    app.github_error = None;
+                }
+                input::InputAction::Backspace => {
+                    app.github_input.pop();
+                    app.github_error = None;
+                }
+                input::InputAction::Enter => app.start_github_fetch(),
+                _ => {}
+            }
+        }
+
+        app.input_handler.clear_last_action();
+    }
+}
+
+fn handle_open_settings(app: &mut CargoTapApp) {
+    app.settings_mode = true;
+    app.color_picker.open(&app.config.colors);
+    info!("🎨 Entering settings screen");
+}
+
+fn handle_settings_input(app: &mut CargoTapApp) {
+    if let Some(action) = app.input_handler.get_last_action() {
+        match action {
+            input::InputAction::ArrowUp => {
+                app.color_picker.select_previous_entry(&app.config.colors);
+            }
+            input::InputAction::ArrowDown => {
+                app.color_picker.select_next_entry(&app.config.colors);
+            }
+            input::InputAction::ArrowLeft => {
+                app.color_picker.adjust(-1, &mut app.config.colors);
+            }
+            input::InputAction::ArrowRight => {
+                app.color_picker.adjust(1, &mut app.config.colors);
+            }
+            input::InputAction::Tab => {
+                app.color_picker.select_next_component();
+            }
+            input::InputAction::Enter => {
+                app.color_picker.confirm();
+                app.settings_mode = false;
+                let config_path = crate::profile::config_path();
+                match app.config.save_setting(&config_path, "colors") {
+                    Ok(_) => {
+                        info!("🎨 Theme saved to {}", config_path.display());
+                        app.events.publish(AppEvent::ThemeChanged);
+                    }
+                    Err(e) => log::error!("Failed to save theme: {}", e),
+                }
             }
             _ => {}
         }
@@ -1,45 +1,165 @@
+use crate::aggregators;
 use crate::app::CargoTapApp;
+use crate::color_picker::{Hsv, HsvComponent};
+use crate::config::ColorConfig;
+use crate::dogfood::{self, DOGFOOD_PREFIX};
+use crate::drills::{DRILL_SOURCE, DrillKind, MAX_DIFFICULTY, MIN_DIFFICULTY};
+use crate::file_analysis::CharClass;
+use crate::file_browser::passes_hand_filter;
+use crate::home_row;
+use crate::keyboard;
+use crate::locale::Locale;
+use crate::paths;
+use crate::profiling::Degradation;
+use crate::quotes::QUOTE_SOURCE;
+use crate::samples;
+use crate::session_history::{HistoryRange, SessionSummary, StatsView};
+use crate::session_state::{EndConditionMenu, KeyStat};
 use crate::text::{ColoredLine, TextSurface};
 use crate::ui_blocks::{
-    CodeDisplayBlock, FileInfoBlock, FooterBlock, FpsBlock, HeaderBlock, ProgressBlock,
-    RainbowEffectsBlock, SeparatorBlock, SessionStateBlock, UiBlock,
+    CodeDisplayBlock, CursorInfoBlock, FileBrowserBlock, FileInfoBlock, FooterBlock, FpsBlock,
+    FrameGraphBlock, HeaderBlock, KeySpeedBlock, PreviewPaneBlock, ProgressBlock,
+    RainbowEffectsBlock, SeparatorBlock, SessionStateBlock, SplitCodeBlock, ToastBlock,
+    TutorialBlock, UiBlock, format_eta, note_color, push_hand_share, write_keyboard,
 };
-use std::fs;
-use std::path::Path;
 
+/// Times a key transition has to be typed before its speed is worth reporting
+const MIN_TRANSITION_SAMPLES: usize = 3;
+
+/// Writes `text` line by line. Lines too long for the window continue on the next
+/// row (large fonts or low-vision mode); box drawing rules are cut off instead.
 fn write_text(surface: &mut dyn TextSurface, text: &str, color: [f32; 4]) {
     for line_text in text.split('\n') {
         let mut line = ColoredLine::new();
         line.push_str(line_text, color);
-        surface.write_line(&line);
+        if line_text.starts_with(|ch| ('\u{2500}'..='\u{257F}').contains(&ch)) {
+            surface.write_line(&line);
+        } else {
+            surface.write_line_wordwrap(&line);
+        }
         surface.write_break();
     }
 }
 
 pub fn create_colored_text(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    if app.practice_lock_mode {
+        create_practice_lock_screen(app, surface);
+        return;
+    }
+
+    if app.exit_confirm_mode {
+        create_exit_confirm_screen(app, surface);
+        return;
+    }
+
+    if app.resume_offer.is_some() {
+        create_resume_offer_screen(app, surface);
+        return;
+    }
+
+    if app.whats_new.is_some() {
+        create_whats_new_screen(app, surface);
+        return;
+    }
+
+    if app.file_analysis.is_some() {
+        create_file_analysis_screen(app, surface);
+        return;
+    }
+
+    if app.note_edit_path.is_some() {
+        create_note_screen(app, surface);
+        return;
+    }
+
     if app.file_selection_mode {
         create_file_selection_screen(app, surface);
         return;
     }
 
+    if app.settings_mode {
+        create_settings_screen(app, surface);
+        return;
+    }
+
+    if app.drill_menu_mode {
+        create_drill_menu_screen(app, surface);
+        return;
+    }
+
+    if app.end_condition_menu.is_some() {
+        create_session_setup_screen(app, surface);
+        return;
+    }
+
+    if app.dogfood_menu_mode {
+        create_dogfood_menu_screen(app, surface);
+        return;
+    }
+
+    if app.line_range_mode {
+        create_line_range_screen(app, surface);
+        return;
+    }
+
+    if app.goto_line_mode {
+        create_goto_line_screen(app, surface);
+        return;
+    }
+
+    if app.github_mode {
+        create_github_screen(app, surface);
+        return;
+    }
+
     if app.show_statistics {
-        create_statistics_screen(app, surface);
+        TutorialBlock.render(app, surface);
+        match app.stats_view {
+            StatsView::Summary => create_statistics_screen(app, surface),
+            StatsView::Heatmap => create_key_heatmap_screen(app, surface),
+            StatsView::Speed => create_key_speed_screen(app, surface),
+        }
+        return;
+    }
+
+    if app.pomodoro.as_ref().is_some_and(|p| p.is_on_break()) {
+        create_break_screen(app, surface);
+        return;
+    }
+
+    if app.session_state.is_paused() {
+        create_pause_screen(app, surface);
+        return;
+    }
+
+    if app.home_row_pending() {
+        create_home_row_screen(app, surface);
         return;
     }
 
+    PreviewPaneBlock::reserve(app, surface);
     HeaderBlock.render(app, surface);
+    ToastBlock.render(app, surface);
+    TutorialBlock.render(app, surface);
     FileInfoBlock.render(app, surface);
     ProgressBlock.render(app, surface);
+    CursorInfoBlock.render(app, surface);
     FpsBlock.render(app, surface);
+    FrameGraphBlock.render(app, surface);
     SeparatorBlock { width: 50 }.render(app, surface);
     SessionStateBlock.render(app, surface);
-    CodeDisplayBlock.render(app, surface);
+    if app.split.is_some() {
+        SplitCodeBlock.render(app, surface);
+    } else {
+        CodeDisplayBlock.render(app, surface);
+    }
 
-    if app.config.text.rainbow_effects {
+    if app.config.text.rainbow_effects && app.text_budget.level() < Degradation::NoEffects {
         RainbowEffectsBlock.render(app, surface);
     }
 
     FooterBlock.render(app, surface);
+    PreviewPaneBlock.render(app, surface);
 }
 
 fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
@@ -67,13 +187,17 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
             [0.7, 0.7, 0.7, 1.0],
         );
     } else {
+        let locale = app.locale;
         let summary = app.session_history.get_summary();
         let recent_summary = app.session_history.get_recent_summary(5);
         let (improved, improvement) = app.session_history.analyze_improvement(5);
 
         write_text(
             surface,
-            &format!("📊 ALL-TIME STATS ({} sessions)\n", summary.total_sessions),
+            &format!(
+                "📊 ALL-TIME STATS ({} sessions)\n",
+                locale.count(summary.total_sessions)
+            ),
             [1.0, 1.0, 0.0, 1.0],
         );
         write_text(
@@ -83,30 +207,40 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
         );
         write_text(
             surface,
-            &format!("  Total Characters: {}\n", summary.total_chars),
+            &format!(
+                "  Total Characters: {}\n",
+                locale.count(summary.total_chars)
+            ),
             app.config.colors.text_default,
         );
         write_text(
             surface,
-            &format!("  Total Time: {:.1} minutes\n", summary.total_time / 60.0),
+            &format!(
+                "  Total Time: {} minutes\n",
+                locale.decimal(summary.total_time / 60.0, 1)
+            ),
             app.config.colors.text_default,
         );
         write_text(
             surface,
             &format!(
-                "  Avg Speed: {:.0} CPM / {:.0} WPM\n",
-                summary.avg_cpm, summary.avg_wpm
+                "  Avg Speed: {} CPM / {} WPM\n",
+                locale.decimal(summary.avg_cpm, 0),
+                locale.decimal(summary.avg_wpm, 0)
             ),
             [0.0, 1.0, 0.0, 1.0],
         );
         write_text(
             surface,
-            &format!("  Avg Accuracy: {:.1}%\n", summary.avg_accuracy),
+            &format!(
+                "  Avg Accuracy: {}\n",
+                locale.percent(summary.avg_accuracy, 1)
+            ),
             [0.0, 1.0, 0.0, 1.0],
         );
         write_text(
             surface,
-            &format!("  Total Errors: {}\n\n", summary.total_errors),
+            &format!("  Total Errors: {}\n\n", locale.count(summary.total_errors)),
             app.config.colors.text_default,
         );
 
@@ -116,19 +250,71 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
             "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
             [0.5, 0.8, 1.0, 1.0],
         );
+        // With scoring on, the score ranks the best session instead of raw speed
+        let scoring = &app.config.scoring;
+        match app
+            .session_history
+            .get_best_score_session(scoring.accuracy_exponent)
+            .filter(|_| scoring.enabled)
+        {
+            Some(best) => write_text(
+                surface,
+                &format!(
+                    "  Best Score: {} ({} WPM at {})\n",
+                    locale.decimal(best.score_or_compute(scoring.accuracy_exponent), 1),
+                    locale.decimal(best.words_per_minute, 0),
+                    locale.percent(best.accuracy, 1)
+                ),
+                [1.0, 0.5, 0.0, 1.0],
+            ),
+            None => write_text(
+                surface,
+                &format!(
+                    "  Best Speed: {} CPM / {} WPM\n",
+                    locale.decimal(summary.best_cpm, 0),
+                    locale.decimal(summary.best_wpm, 0)
+                ),
+                [1.0, 0.5, 0.0, 1.0],
+            ),
+        }
         write_text(
             surface,
             &format!(
-                "  Best Speed: {:.0} CPM / {:.0} WPM\n",
-                summary.best_cpm, summary.best_wpm
+                "  Best Accuracy: {}\n\n",
+                locale.percent(summary.best_accuracy, 1)
             ),
             [1.0, 0.5, 0.0, 1.0],
         );
-        write_text(
-            surface,
-            &format!("  Best Accuracy: {:.1}%\n\n", summary.best_accuracy),
-            [1.0, 0.5, 0.0, 1.0],
-        );
+
+        let metrics: Vec<_> = app
+            .config
+            .statistics
+            .metrics
+            .iter()
+            .filter_map(|id| aggregators::find(id))
+            .collect();
+        if !metrics.is_empty() {
+            write_text(surface, "📐 MORE METRICS\n", [0.5, 0.8, 1.0, 1.0]);
+            write_text(
+                surface,
+                "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+                [0.5, 0.8, 1.0, 1.0],
+            );
+            for aggregator in metrics {
+                if let Some(value) = app.session_history.aggregate(aggregator, HistoryRange::All) {
+                    write_text(
+                        surface,
+                        &format!(
+                            "  {}: {}\n",
+                            aggregator.label(),
+                            aggregator.format(value, &locale)
+                        ),
+                        app.config.colors.text_default,
+                    );
+                }
+            }
+            write_text(surface, "", app.config.colors.text_default);
+        }
 
         if recent_summary.total_sessions > 0 {
             write_text(
@@ -147,33 +333,69 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
             write_text(
                 surface,
                 &format!(
-                    "  Avg Speed: {:.0} CPM / {:.0} WPM\n",
-                    recent_summary.avg_cpm, recent_summary.avg_wpm
+                    "  Avg Speed: {} CPM / {} WPM\n",
+                    locale.decimal(recent_summary.avg_cpm, 0),
+                    locale.decimal(recent_summary.avg_wpm, 0)
                 ),
                 [0.0, 1.0, 0.0, 1.0],
             );
             write_text(
                 surface,
-                &format!("  Avg Accuracy: {:.1}%\n", recent_summary.avg_accuracy),
+                &format!(
+                    "  Avg Accuracy: {}\n",
+                    locale.percent(recent_summary.avg_accuracy, 1)
+                ),
                 [0.0, 1.0, 0.0, 1.0],
             );
 
             if improved {
                 write_text(
                     surface,
-                    &format!("  📊 Improvement: +{:.1}% 🎉\n", improvement),
+                    &format!("  📊 Improvement: {}% 🎉\n", locale.signed(improvement, 1)),
                     [0.0, 1.0, 0.5, 1.0],
                 );
             } else if improvement < 0.0 {
                 write_text(
                     surface,
-                    &format!("  📊 Change: {:.1}%\n", improvement),
+                    &format!("  📊 Change: {}\n", locale.percent(improvement, 1)),
                     [1.0, 0.5, 0.0, 1.0],
                 );
             }
             write_text(surface, "\n", app.config.colors.text_default);
         }
 
+        let today = app
+            .session_history
+            .get_sessions_on(chrono::Local::now().date_naive());
+        let pomodoros_today = today.iter().filter(|s| s.pomodoro).count();
+        if app.pomodoro.is_some() || pomodoros_today > 0 {
+            write_text(surface, "🍅 TODAY", [1.0, 0.4, 0.3, 1.0]);
+            write_text(
+                surface,
+                "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+                [0.5, 0.8, 1.0, 1.0],
+            );
+            write_text(
+                surface,
+                &format!(
+                    "  Sessions: {} | Pomodoros: {}\n\n",
+                    today.len(),
+                    pomodoros_today
+                ),
+                app.config.colors.text_default,
+            );
+        }
+
+        for (source, title) in [
+            (QUOTE_SOURCE, "💬 QUOTE SESSIONS"),
+            (DRILL_SOURCE, "🥁 DRILL SESSIONS"),
+        ] {
+            let source_summary = app.session_history.get_summary_for_source(source);
+            if source_summary.total_sessions > 0 {
+                write_source_summary(surface, title, &source_summary, &locale);
+            }
+        }
+
         write_text(surface, "📝 RECENT SESSIONS\n", [0.7, 0.7, 1.0, 1.0]);
         write_text(
             surface,
@@ -186,18 +408,44 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
             .iter()
             .enumerate()
         {
-            write_text(
-                surface,
-                &format!(
-                    "  {}. {:.0} CPM / {:.0} WPM | {:.1}% acc | {} chars\n",
-                    i + 1,
-                    session.chars_per_minute,
-                    session.words_per_minute,
-                    session.accuracy,
-                    session.chars_typed
-                ),
-                app.config.colors.text_default,
+            let mut line = format!(
+                "  {}. {} | {} CPM / {} WPM | {} acc | {} chars",
+                i + 1,
+                locale.timestamp(session.timestamp),
+                locale.decimal(session.chars_per_minute, 0),
+                locale.decimal(session.words_per_minute, 0),
+                locale.percent(session.accuracy, 1),
+                locale.count(session.chars_typed)
             );
+            if app.config.scoring.enabled {
+                line.push_str(&format!(
+                    " | score {}",
+                    locale.decimal(
+                        session.score_or_compute(app.config.scoring.accuracy_exponent),
+                        1
+                    )
+                ));
+            }
+            if let Some(seed) = session.seed {
+                line.push_str(&format!(" | seed {}", seed));
+            }
+            if let Some(challenge) = &session.challenge {
+                line.push_str(&format!(" | challenge {}", challenge));
+            }
+            if session.pane_switches > 0 {
+                line.push_str(&format!(" | {} pane switches", session.pane_switches));
+            }
+            if session.too_fast_keys > 0 {
+                line.push_str(&format!(" | {} too fast", session.too_fast_keys));
+            }
+            if !session.end_condition.is_time() {
+                line.push_str(&format!(" | ended after {}", session.end_condition));
+            }
+            if session.review_of.is_some() {
+                line.push_str(" | review");
+            }
+            line.push('\n');
+            write_text(surface, &line, app.config.colors.text_default);
         }
     }
 
@@ -208,207 +456,1473 @@ fn create_statistics_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface
     );
     write_text(
         surface,
-        "Press ESC to return | Press Ctrl+T / Cmd+T to view stats\n",
+        "Press ESC to return | TAB: keyboard heatmap\n",
         [0.7, 0.7, 0.7, 1.0],
     );
 }
 
-fn create_file_selection_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+fn create_break_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(pomodoro) = app.pomodoro.as_ref() else {
+        return;
+    };
+    let remaining = pomodoro.break_remaining().unwrap_or_default().as_secs();
+    let title = if pomodoro.is_long_break() {
+        "║                  LONG BREAK                   ║\n"
+    } else {
+        "║                  BREAK TIME                   ║\n"
+    };
+
     write_text(
         surface,
         "╔═══════════════════════════════════════════════╗\n",
         [0.0, 1.0, 1.0, 1.0],
     );
+    write_text(surface, title, [0.0, 1.0, 1.0, 1.0]);
     write_text(
         surface,
-        "║              FILE SELECTION MODE              ║\n",
-        [0.0, 1.0, 1.0, 1.0],
-    );
-    write_text(
-        surface,
-        "╚═══════════════════════════════════════════════╝\n\n",
+        "╚═══════════════════════════════════════════════╝\n",
         [0.0, 1.0, 1.0, 1.0],
     );
 
     write_text(
         surface,
-        "Enter file path to load:\n\n",
-        [1.0, 1.0, 1.0, 1.0],
+        &format!(
+            "Stretch, look away from the screen. Back in {:02}:{:02}\n",
+            remaining / 60,
+            remaining % 60
+        ),
+        [1.0, 1.0, 0.0, 1.0],
     );
 
-    let mut line = ColoredLine::new();
-    line.push_str("📝 ", [1.0, 0.84, 0.0, 1.0]);
-    line.push_str(&app.file_input_buffer, [0.0, 1.0, 0.0, 1.0]);
-    line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
-    surface.write_line(&line);
-    surface.write_break();
-
-    write_text(surface, "\n\n", app.config.colors.text_default);
+    let pomodoros_today = app
+        .session_history
+        .get_sessions_on(chrono::Local::now().date_naive())
+        .iter()
+        .filter(|s| s.pomodoro)
+        .count();
     write_text(
         surface,
-        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
-        [0.5, 0.8, 1.0, 1.0],
+        &format!(
+            "Pomodoro {} done | {} today",
+            pomodoro.cycles_completed(),
+            pomodoros_today
+        ),
+        app.config.colors.text_default,
     );
 
-    let dir_path = get_directory_from_path(&app.file_input_buffer);
-
-    if let Ok(entries) = fs::read_dir(&dir_path) {
-        write_text(
-            surface,
-            &format!("Contents of directory: {}\n", dir_path),
-            [0.7, 0.7, 0.7, 1.0],
-        );
+    if let Some(stats) = app.session_state.last_stats() {
         write_text(
             surface,
-            "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
-            [0.5, 0.8, 1.0, 1.0],
+            &format!(
+                "Last work period: {:.0} WPM, {:.1}% accuracy\n",
+                stats.words_per_minute, stats.accuracy
+            ),
+            [0.0, 1.0, 0.0, 1.0],
         );
+    }
 
-        let mut dirs: Vec<_> = Vec::new();
-        let mut files: Vec<_> = Vec::new();
-
-        for entry in entries.filter_map(|e| e.ok()) {
-            if entry.path().is_dir() {
-                dirs.push(entry);
-            } else if entry.path().is_file() {
-                files.push(entry);
-            }
-        }
+    write_text(
+        surface,
+        "The next session starts on its own when the break ends.",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "SPACE: skip break | Ctrl+T: statistics | ESC: quit\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
 
-        dirs.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+fn create_key_heatmap_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║               KEYBOARD HEATMAP                ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
 
-        for entry in dirs.iter().take(10) {
-            let file_name = entry.file_name();
-            let file_name_str = file_name.to_string_lossy();
+    let sessions = app.session_history.get_sessions_in_range(app.stats_range);
+    write_text(
+        surface,
+        &format!(
+            "< {} ({} recorded) >\n",
+            app.stats_range.label(),
+            sessions.len()
+        ),
+        [1.0, 1.0, 0.0, 1.0],
+    );
 
-            let mut line = ColoredLine::new();
-            line.push_str("  📁 ", [0.5, 0.7, 1.0, 1.0]);
-            line.push_str(&file_name_str, [0.5, 0.7, 1.0, 1.0]);
-            line.push_str("/", [0.5, 0.7, 1.0, 1.0]);
+    let stats = app.session_history.get_key_stats(app.stats_range);
+    let layout = keyboard::us_layout();
+    let totals = keyboard::key_totals(&layout, &stats, KeyStat::add);
+    let max_attempts = totals
+        .iter()
+        .flatten()
+        .map(|stat| stat.hits + stat.misses)
+        .max()
+        .unwrap_or(0);
+    write_keyboard(surface, &layout, |row, key| {
+        keyboard::heatmap_color(totals[row][key], max_attempts)
+    });
 
-            let padding = 40_usize.saturating_sub(file_name_str.len() + 1);
-            line.push_str(&" ".repeat(padding), [0.7, 0.7, 0.7, 1.0]);
-            line.push_str("<DIR>", [0.5, 0.7, 1.0, 1.0]);
-            surface.write_line(&line);
-            surface.write_break();
-        }
+    write_text(
+        surface,
+        "\nBrighter: typed more often | Redder: missed more often\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
 
-        if dirs.len() > 10 {
+    let mut missed: Vec<_> = stats.iter().filter(|(_, stat)| stat.misses > 0).collect();
+    missed.sort_by(|a, b| b.1.error_rate().total_cmp(&a.1.error_rate()));
+    if missed.is_empty() {
+        write_text(
+            surface,
+            "No missed keys in this range.\n",
+            app.config.colors.text_default,
+        );
+    } else {
+        write_text(surface, "MOST MISSED", [1.0, 0.5, 0.0, 1.0]);
+        for (ch, stat) in missed.iter().take(5) {
             write_text(
                 surface,
-                &format!("  ... and {} more directories\n", dirs.len() - 10),
-                [0.6, 0.6, 0.6, 1.0],
+                &format!(
+                    "  {:<7} {:.1}% missed ({} of {})",
+                    keyboard::key_name(**ch),
+                    stat.error_rate() * 100.0,
+                    stat.misses,
+                    stat.hits + stat.misses
+                ),
+                app.config.colors.text_default,
             );
         }
+        write_text(surface, "", app.config.colors.text_default);
+    }
 
-        for entry in files.iter().take(20) {
-            let path = entry.path();
-            let file_name = entry.file_name();
-            let file_name_str = file_name.to_string_lossy();
-
-            let file_size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-
-            let size_str = format_file_size(file_size);
-
-            let full_path = path.to_string_lossy().to_string();
-            let has_progress = app.progress_storage.get_progress(&full_path).is_some();
-
-            let mut line = ColoredLine::new();
-            if has_progress {
-                line.push_str("  ★ ", [1.0, 0.84, 0.0, 1.0]);
-                line.push_str(&file_name_str, [1.0, 1.0, 0.0, 1.0]);
-            } else {
-                line.push_str("    ", [0.7, 0.7, 0.7, 1.0]);
-                line.push_str(&file_name_str, [0.9, 0.9, 0.9, 1.0]);
-            }
-
-            let padding = 40_usize.saturating_sub(file_name_str.len());
-            line.push_str(&" ".repeat(padding), [0.7, 0.7, 0.7, 1.0]);
-            line.push_str(&size_str, [0.5, 0.8, 1.0, 1.0]);
-            surface.write_line(&line);
-        }
-
-        if files.len() > 20 {
+    let transitions = app.session_history.get_transition_stats(app.stats_range);
+    let mut slowest: Vec<_> = transitions
+        .iter()
+        .filter(|(_, stat)| stat.count >= MIN_TRANSITION_SAMPLES)
+        .filter_map(|(bigram, stat)| {
+            let mut chars = bigram.chars();
+            let (from, to) = (chars.next()?, chars.next()?);
+            Some((from, to, keyboard::transition(from, to)?, stat))
+        })
+        .collect();
+    slowest.sort_by(|a, b| b.3.mean_secs().total_cmp(&a.3.mean_secs()));
+    if !slowest.is_empty() {
+        write_text(surface, "SLOWEST KEY TRANSITIONS", [1.0, 0.5, 0.0, 1.0]);
+        for (from, to, kind, stat) in slowest.iter().take(5) {
             write_text(
                 surface,
-                &format!("  ... and {} more files\n", files.len() - 20),
-                [0.6, 0.6, 0.6, 1.0],
+                &format!(
+                    "  {:<7} → {:<7} {:>4.0} ms  {} ({} times)",
+                    keyboard::key_name(*from),
+                    keyboard::key_name(*to),
+                    stat.mean_secs() * 1000.0,
+                    kind.label(),
+                    stat.count
+                ),
+                app.config.colors.text_default,
             );
         }
-
-        write_text(surface, "\n", app.config.colors.text_default);
+        write_text(surface, "", app.config.colors.text_default);
     }
 
     write_text(
         surface,
-        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
-        [0.5, 0.8, 1.0, 1.0],
+        "Left/Right: change range | TAB: key speed | ESC: return",
+        [0.7, 0.7, 0.7, 1.0],
     );
+}
 
-    let mut line = ColoredLine::new();
-    line.push_str("Current file: ", [0.7, 0.7, 0.7, 1.0]);
-    line.push_str(&app.current_file_path, [0.5, 1.0, 1.0, 1.0]);
-    surface.write_line(&line);
-    surface.write_break();
-
-    write_text(surface, "\n\n", app.config.colors.text_default);
-
-    write_text(surface, "Instructions:\n", [1.0, 1.0, 0.0, 1.0]);
+fn create_key_speed_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
     write_text(
         surface,
-        "  • Edit the file path below (pre-filled with current path)\n",
-        [0.7, 0.7, 0.7, 1.0],
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
     );
     write_text(
         surface,
-        "  • Press ENTER to load the file\n",
-        [0.7, 0.7, 0.7, 1.0],
+        "║                   KEY SPEED                   ║\n",
+        [0.0, 1.0, 1.0, 1.0],
     );
-    write_text(surface, "  • Press ESC to cancel\n", [0.7, 0.7, 0.7, 1.0]);
     write_text(
         surface,
-        "  • Use BACKSPACE to delete characters\n",
-        [0.7, 0.7, 0.7, 1.0],
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
     );
+
+    let sessions = app.session_history.get_sessions_in_range(app.stats_range);
     write_text(
         surface,
-        "  • Files with ★ have saved progress\n\n",
-        [0.7, 0.7, 0.7, 1.0],
+        &format!(
+            "< {} ({} recorded) >\n",
+            app.stats_range.label(),
+            sessions.len()
+        ),
+        [1.0, 1.0, 0.0, 1.0],
     );
-}
 
-fn get_directory_from_path(path: &str) -> String {
-    let path_obj = Path::new(path);
-
-    if path_obj.is_dir() {
-        return path.to_string();
-    }
-
-    if let Some(parent) = path_obj.parent() {
-        if parent.as_os_str().is_empty() {
-            return ".".to_string();
-        }
-        return parent.to_string_lossy().to_string();
-    }
+    KeySpeedBlock.render(app, surface);
 
-    ".".to_string()
+    write_text(
+        surface,
+        "Left/Right: change range | TAB: summary | ESC: return",
+        [0.7, 0.7, 0.7, 1.0],
+    );
 }
 
-fn format_file_size(size: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
-    if size >= GB {
-        format!("{:.2} GB", size as f64 / GB as f64)
-    } else if size >= MB {
-        format!("{:.2} MB", size as f64 / MB as f64)
-    } else if size >= KB {
-        format!("{:.2} KB", size as f64 / KB as f64)
-    } else {
-        format!("{} B", size)
-    }
+fn create_file_selection_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║              FILE SELECTION MODE              ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    write_text(
+        surface,
+        "Enter file path to load:\n\n",
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("📝 ", [1.0, 0.84, 0.0, 1.0]);
+    line.push_str(&app.file_input_buffer, [0.0, 1.0, 0.0, 1.0]);
+    line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+
+    let expanded = paths::expand(
+        &app.file_input_buffer,
+        app.config.gameplay.workspace_root.as_deref(),
+    )
+    .ok();
+    if let Some(expanded) = expanded
+        .as_deref()
+        .filter(|expanded| *expanded != app.file_input_buffer.trim())
+    {
+        write_text(
+            surface,
+            &format!(
+                "   → {}
+",
+                expanded
+            ),
+            [0.6, 0.6, 0.6, 1.0],
+        );
+    }
+
+    if let Some(error) = &app.file_error {
+        write_text(surface, "", app.config.colors.text_default);
+        write_text(surface, &format!("X {}", error), [1.0, 0.3, 0.3, 1.0]);
+    }
+
+    write_text(surface, "\n\n", app.config.colors.text_default);
+    write_text(
+        surface,
+        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+        [0.5, 0.8, 1.0, 1.0],
+    );
+
+    let hand_filter = app.file_hand_filter;
+    if let Some(hand) = hand_filter {
+        write_text(
+            surface,
+            &format!(
+                "✋ Only files typed at least {:.0}% with the {} hand (Tab: change)\n",
+                keyboard::ONE_HAND_SHARE * 100.0,
+                hand.name()
+            ),
+            [1.0, 0.84, 0.0, 1.0],
+        );
+    }
+
+    write_text(surface, "Built-in:\n", [0.7, 0.7, 0.7, 1.0]);
+    for sample in samples::SAMPLES {
+        let path = sample.path();
+        let share = hand_filter
+            .map(|hand| samples::read(&path).and_then(|text| keyboard::hand_share(text, hand)));
+        if !passes_hand_filter(share) {
+            continue;
+        }
+        let mut line = ColoredLine::new();
+        if app.progress_storage.get_progress(&path).is_some() {
+            line.push_str("  ★ ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(&format!("{:<28}", path), [1.0, 1.0, 0.0, 1.0]);
+        } else {
+            line.push_str("    ", [0.7, 0.7, 0.7, 1.0]);
+            line.push_str(&format!("{:<28}", path), [0.9, 0.9, 0.9, 1.0]);
+        }
+        let difficulty_color = match sample.difficulty {
+            samples::Difficulty::Easy => [0.4, 1.0, 0.4, 1.0],
+            samples::Difficulty::Medium => [1.0, 0.84, 0.0, 1.0],
+            samples::Difficulty::Hard => [1.0, 0.4, 0.4, 1.0],
+        };
+        line.push_str(&format!("{:<8}", sample.difficulty), difficulty_color);
+        push_hand_share(&mut line, share);
+        line.push_str(sample.description, [0.6, 0.6, 0.6, 1.0]);
+        surface.write_line(&line);
+        surface.write_break();
+    }
+    write_text(
+        surface,
+        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+        [0.5, 0.8, 1.0, 1.0],
+    );
+
+    FileBrowserBlock.render(app, surface);
+
+    write_text(
+        surface,
+        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+        [0.5, 0.8, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("Current file: ", [0.7, 0.7, 0.7, 1.0]);
+    line.push_str(&app.current_file_path, [0.5, 1.0, 1.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+    if let Some(note) = app.progress_storage.get_note(&app.current_file_path) {
+        write_text(surface, &format!("Note: {}", note), note_color(note));
+    }
+
+    write_text(surface, "\n\n", app.config.colors.text_default);
+
+    write_text(surface, "Instructions:\n", [1.0, 1.0, 0.0, 1.0]);
+    write_text(
+        surface,
+        "  • Edit the file path above (pre-filled with current path)\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Press ENTER to load the file\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • ↑/↓ select an entry, → or ENTER opens a directory, ← goes up one\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Type part of a name to search the directory; ENTER opens the best match\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Press Ctrl+F to list only files with one extension\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(surface, "  • Press ESC to cancel\n", [0.7, 0.7, 0.7, 1.0]);
+    write_text(
+        surface,
+        "  • Use BACKSPACE to delete characters\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Files with ★ have saved progress\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • ~, $VARIABLES and paths relative to the workspace root work; TAB completes directories\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Press TAB with nothing to complete to list only files typed mostly with one hand\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "  • Press Ctrl+N to add a note to the file path above\n\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_note_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                   FILE NOTE                   ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let file_path = app.note_edit_path.as_deref().unwrap_or_default();
+    write_text(
+        surface,
+        &format!("Note for {}:\n", file_path),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+    line.push_str(&app.note_input, [0.0, 1.0, 0.0, 1.0]);
+    line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Start a note with TODO to highlight it in the file picker",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "Enter: save (empty removes the note) | ESC: cancel",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn write_source_summary(
+    surface: &mut dyn TextSurface,
+    title: &str,
+    summary: &SessionSummary,
+    locale: &Locale,
+) {
+    write_text(
+        surface,
+        &format!(
+            "{} ({} sessions)\n",
+            title,
+            locale.count(summary.total_sessions)
+        ),
+        [1.0, 0.85, 0.2, 1.0],
+    );
+    write_text(
+        surface,
+        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n",
+        [0.5, 0.8, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        &format!(
+            "  Avg Speed: {} CPM / {} WPM\n",
+            locale.decimal(summary.avg_cpm, 0),
+            locale.decimal(summary.avg_wpm, 0)
+        ),
+        [0.0, 1.0, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        &format!(
+            "  Best Speed: {} WPM | Avg Accuracy: {}\n\n",
+            locale.decimal(summary.best_wpm, 0),
+            locale.percent(summary.avg_accuracy, 1)
+        ),
+        [1.0, 0.5, 0.0, 1.0],
+    );
+}
+
+fn create_drill_menu_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                    DRILLS                     ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let selected = app.drill_menu.selected_kind();
+    for kind in DrillKind::ALL {
+        let mut line = ColoredLine::new();
+        if kind == selected {
+            line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(kind.name(), [1.0, 1.0, 0.0, 1.0]);
+        } else {
+            line.push_str("  ", app.config.colors.text_default);
+            line.push_str(kind.name(), [0.7, 0.7, 0.7, 1.0]);
+        }
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+
+    let difficulty = app.drill_menu.difficulty();
+    let mut line = ColoredLine::new();
+    line.push_str("Difficulty: ", app.config.colors.text_default);
+    for level in MIN_DIFFICULTY..=MAX_DIFFICULTY {
+        let color = if level <= difficulty {
+            [1.0, 0.5, 0.0, 1.0]
+        } else {
+            [0.3, 0.3, 0.3, 1.0]
+        };
+        line.push_str("# ", color);
+    }
+    line.push_str(&format!("({})", difficulty), app.config.colors.text_default);
+    surface.write_line(&line);
+    surface.write_break();
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Up/Down: choose drill | Left/Right: difficulty | Enter: start | ESC: back\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_session_setup_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(menu) = app.end_condition_menu.clone() else {
+        return;
+    };
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                 SESSION SETUP                 ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "End each session after:\n",
+        app.config.colors.text_default,
+    );
+
+    for row in 0..EndConditionMenu::ROWS {
+        let label = match menu.condition(row) {
+            crate::session_state::EndCondition::Time => format!(
+                "{:.1} minutes (session_duration_minutes)",
+                app.config.gameplay.session_duration_minutes
+            ),
+            condition => condition.to_string(),
+        };
+        let mut line = ColoredLine::new();
+        if row == menu.selected() {
+            line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(&label, [1.0, 1.0, 0.0, 1.0]);
+        } else {
+            line.push_str("  ", app.config.colors.text_default);
+            line.push_str(&label, [0.7, 0.7, 0.7, 1.0]);
+        }
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Finishing the text always ends the session. Saved to config.toml.",
+        app.config.colors.text_default,
+    );
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Up/Down: choose | Left/Right: limit | Enter: apply | ESC: back\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_resume_offer_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(offer) = app.resume_offer.clone() else {
+        return;
+    };
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║       FILE CHANGED SINCE THE LAST SESSION     ║\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+
+    write_text(
+        surface,
+        &format!(
+            "{} was edited above where you stopped:\n",
+            app.current_file_path
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    if offer.added > 0 {
+        write_text(
+            surface,
+            &format!("  + {} line{} added", offer.added, plural(offer.added)),
+            [0.0, 1.0, 0.0, 1.0],
+        );
+    }
+    if offer.removed > 0 {
+        write_text(
+            surface,
+            &format!(
+                "  - {} typed line{} removed or edited",
+                offer.removed,
+                plural(offer.removed)
+            ),
+            [1.0, 0.3, 0.3, 1.0],
+        );
+    }
+    write_text(
+        surface,
+        &format!("  First change on line {}", offer.first_change),
+        app.config.colors.text_default,
+    );
+    write_text(surface, "", app.config.colors.text_default);
+
+    let resume = if offer.exact {
+        format!("Resume on line {} where you stopped", offer.line)
+    } else {
+        format!(
+            "The line you stopped on changed; resume on line {}, after the last unchanged one",
+            offer.line
+        )
+    };
+    write_text(surface, &resume, [0.0, 1.0, 1.0, 1.0]);
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Enter: resume | ESC: start from the beginning",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_whats_new_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(whats_new) = app.whats_new.clone() else {
+        return;
+    };
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        &format!(
+            "║{:^47}║\n",
+            format!("WHAT'S NEW IN CARGOTAP {}", env!("CARGO_PKG_VERSION"))
+        ),
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    for (i, entry) in whats_new.entries.iter().enumerate() {
+        let selected = i == whats_new.selected;
+        let mut line = ColoredLine::new();
+        if selected {
+            line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(&entry.title, [1.0, 1.0, 0.0, 1.0]);
+        } else {
+            line.push_str("  ", app.config.colors.text_default);
+            line.push_str(&entry.title, [0.8, 0.8, 0.8, 1.0]);
+        }
+        if let Some(keys) = &entry.keys {
+            line.push_str(&format!("  {}", keys), [0.5, 0.8, 1.0, 1.0]);
+        }
+        surface.write_line(&line);
+        surface.write_break();
+
+        // Details only for the selected entry, so the list fits on one screen
+        if !selected {
+            continue;
+        }
+        write_text(
+            surface,
+            &format!("    {}", entry.text),
+            [0.7, 0.7, 0.7, 1.0],
+        );
+        if let Some(location) = entry.setting_location() {
+            write_text(
+                surface,
+                &format!("    ⚙ {} in config.toml", location),
+                [0.6, 0.6, 0.6, 1.0],
+            );
+        }
+        if let Some(target) = entry.open {
+            write_text(
+                surface,
+                &format!("    Enter opens the {}", target.label()),
+                [0.0, 1.0, 0.5, 1.0],
+            );
+        }
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Up/Down: browse | Enter: open | Space/Esc: close",
+        [0.6, 0.6, 0.6, 1.0],
+    );
+}
+
+fn create_file_analysis_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(analysis) = app.file_analysis.clone() else {
+        return;
+    };
+    let locale = app.locale;
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                 FILE ANALYSIS                 ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        &format!(
+            "{}: {} characters to type\n",
+            app.current_file_path,
+            locale.count(analysis.typed_chars())
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    for class in CharClass::ALL {
+        let share = analysis.share(class);
+        if share == 0.0 {
+            continue;
+        }
+        let mut line = ColoredLine::new();
+        line.push_str(&format!("  {:<18}", class.label()), [0.7, 0.7, 0.7, 1.0]);
+        line.push_str(
+            &"█".repeat((share * 30.0).round() as usize),
+            [0.5, 0.8, 1.0, 1.0],
+        );
+        line.push_str(
+            &format!(" {}", locale.percent(share * 100.0, 0)),
+            app.config.colors.text_default,
+        );
+        surface.write_line(&line);
+        surface.write_break();
+    }
+    write_text(surface, "", app.config.colors.text_default);
+
+    if analysis.hardest.is_empty() {
+        write_text(
+            surface,
+            "Hardest symbols: not enough key statistics yet",
+            [0.6, 0.6, 0.6, 1.0],
+        );
+    } else {
+        let mut line = ColoredLine::new();
+        line.push_str("Hardest symbols for you: ", app.config.colors.text_default);
+        for (ch, rate) in &analysis.hardest {
+            line.push_str(
+                &format!("{} ", keyboard::key_name(*ch)),
+                [1.0, 0.4, 0.4, 1.0],
+            );
+            line.push_str(
+                &format!("({} missed)  ", locale.percent(rate * 100.0, 0)),
+                [0.7, 0.7, 0.7, 1.0],
+            );
+        }
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    let summary = app.session_history.get_summary();
+    match analysis.estimated_secs(summary.avg_cpm) {
+        Some(secs) => write_text(
+            surface,
+            &format!(
+                "Typing all of it: about {} at your average {} WPM",
+                format_eta(secs),
+                locale.decimal(summary.avg_wpm, 0)
+            ),
+            app.config.colors.text_default,
+        ),
+        None => write_text(
+            surface,
+            "Typing all of it: finish a session to get an estimate",
+            [0.6, 0.6, 0.6, 1.0],
+        ),
+    }
+
+    if analysis.untypeable_chars > 0 {
+        write_text(
+            surface,
+            &format!(
+                "⚠ {} characters on {} lines can't be typed on a US keyboard",
+                locale.count(analysis.untypeable_chars),
+                locale.count(analysis.untypeable_lines)
+            ),
+            [1.0, 0.6, 0.2, 1.0],
+        );
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    let keys = if analysis.untypeable_lines > 0 {
+        "Enter: start | S: leave lines with untypeable characters out of files"
+    } else {
+        "Enter: start"
+    };
+    write_text(surface, keys, [0.7, 0.7, 0.7, 1.0]);
+}
+
+fn create_home_row_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    let Some(check) = app.home_row_check.clone() else {
+        return;
+    };
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                HANDS ON HOME ROW              ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let layout = app.config.gameplay.keyboard_layout;
+    let (left, right) = home_row::home_keys(layout);
+    let pressed = |key: char| match key {
+        'F' => check.f_pressed(),
+        'J' => check.j_pressed(),
+        _ => false,
+    };
+    let mut keys = ColoredLine::new();
+    for (i, key) in left.iter().chain(right.iter()).enumerate() {
+        let color = match key {
+            'F' | 'J' if pressed(*key) => [0.0, 1.0, 0.0, 1.0],
+            'F' | 'J' => [1.0, 1.0, 0.0, 1.0],
+            _ => app.config.colors.text_default,
+        };
+        keys.push_str(&format!(" [{}] ", key), color);
+        if i == 3 {
+            keys.push_str("    ", color);
+        }
+    }
+    surface.write_line(&keys);
+    surface.write_break();
+    let left_fingers: String = home_row::FINGERS
+        .iter()
+        .map(|finger| format!("{:^5}", finger))
+        .collect();
+    let right_fingers: String = home_row::FINGERS
+        .iter()
+        .rev()
+        .map(|finger| format!("{:^5}", finger))
+        .collect();
+    write_text(
+        surface,
+        &format!("{}    {}\n", left_fingers, right_fingers),
+        [0.6, 0.6, 0.6, 1.0],
+    );
+    write_text(
+        surface,
+        &format!(
+            "Left fingers on A S D F, right fingers on {} {} {} {} ({} layout), thumbs on Space.",
+            right[0],
+            right[1],
+            right[2],
+            right[3],
+            layout.name()
+        ),
+        app.config.colors.text_default,
+    );
+
+    match check.countdown_remaining() {
+        Some(left) => write_text(
+            surface,
+            &format!("\nStarting in {}...", left.as_secs() + 1),
+            [0.0, 1.0, 0.0, 1.0],
+        ),
+        None => write_text(
+            surface,
+            "\nFeel the bumps on F and J, then press F and J to start",
+            [1.0, 1.0, 0.0, 1.0],
+        ),
+    }
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "ESC: skip the reminder | Ctrl+D: drills | Ctrl+T: statistics",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_line_range_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║             PRACTICE A LINE RANGE             ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    write_text(
+        surface,
+        &format!(
+            "Enter lines to practice in {} (e.g. 120-180), or leave empty for the whole file:\n",
+            app.current_file_path
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+    line.push_str(&app.line_range_input, [0.0, 1.0, 0.0, 1.0]);
+    line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+
+    if let Some(error) = &app.line_range_error {
+        write_text(surface, "", app.config.colors.text_default);
+        write_text(surface, &format!("X {}", error), [1.0, 0.3, 0.3, 1.0]);
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        &format!(
+            "Cursor is on line {} | Enter: start | ESC: back",
+            app.code_state.get_cursor_line()
+        ),
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_goto_line_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                  GO TO LINE                   ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let last_line = app.code_state.first_line_number() + app.code_state.get_line_break_count() + 1;
+    write_text(
+        surface,
+        &format!(
+            "Line to show at the top of {} (up to {}):\n",
+            app.current_file_path, last_line
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+    line.push_str(&app.goto_line_input, [0.0, 1.0, 0.0, 1.0]);
+    line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+
+    if let Some(error) = &app.goto_line_error {
+        write_text(surface, "", app.config.colors.text_default);
+        write_text(surface, &format!("X {}", error), [1.0, 0.3, 0.3, 1.0]);
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        &format!(
+            "Cursor is on line {} and stays there | Enter: go | ESC: back",
+            app.code_state.get_cursor_line()
+        ),
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+/// Covers the code while the session is paused, so the next lines can't be read ahead
+fn create_pause_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                    PAUSED                     ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let stats = app
+        .session_state
+        .current_stats(app.code_state.get_cursor_position());
+    let clock = match app.session_state.format_goal_progress() {
+        Some(goal) => format!(
+            "Stopped at {} ({})",
+            app.session_state.format_time_elapsed(),
+            goal
+        ),
+        None => format!(
+            "Stopped with {} left",
+            app.session_state.format_time_remaining()
+        ),
+    };
+    write_text(surface, &clock, [1.0, 1.0, 0.0, 1.0]);
+    write_text(
+        surface,
+        &format!(
+            "So far: {:.0} WPM, {:.1}% accuracy, {} characters",
+            stats.words_per_minute, stats.accuracy, stats.chars_typed
+        ),
+        app.config.colors.text_default,
+    );
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "SPACE or Ctrl+Shift+P: resume | ESC: quit",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_github_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║               OPEN FROM GITHUB                ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    write_text(
+        surface,
+        "File to practice, as owner/repo/path (or owner/repo for a random file):\n",
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let mut line = ColoredLine::new();
+    line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+    line.push_str(&app.github_input, [0.0, 1.0, 0.0, 1.0]);
+    if app.github_fetch.is_none() {
+        line.push_str("█", [0.0, 1.0, 0.0, 1.0]);
+    }
+    surface.write_line(&line);
+    surface.write_break();
+
+    if let Some((file, _)) = &app.github_fetch {
+        write_text(surface, "", app.config.colors.text_default);
+        write_text(
+            surface,
+            &format!("Fetching {} ...", file),
+            [1.0, 0.84, 0.0, 1.0],
+        );
+    }
+    if let Some(error) = &app.github_error {
+        write_text(surface, "", app.config.colors.text_default);
+        write_text(surface, &format!("X {}", error), [1.0, 0.3, 0.3, 1.0]);
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "e.g. rust-lang/regex/src/lib.rs, rust-lang/regex@master or a github.com link",
+        [0.6, 0.6, 0.6, 1.0],
+    );
+    write_text(surface, "Enter: fetch | ESC: back", [0.7, 0.7, 0.7, 1.0]);
+}
+
+fn create_practice_lock_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║           TODAY'S PRACTICE ISN'T DONE         ║\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+
+    let goal = app.config.practice_lock.daily_minutes;
+    let remaining = app.practice_lock_remaining().unwrap_or(0.0);
+    write_text(
+        surface,
+        &format!(
+            "Practiced {:.0} of {:.0} minutes today, {:.0} to go.\n",
+            (goal - remaining).floor(),
+            goal,
+            remaining.ceil()
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "To quit anyway, type this phrase and press Enter:",
+        app.config.colors.text_default,
+    );
+
+    let phrase = app.config.practice_lock.phrase.clone();
+    let mut line = ColoredLine::new();
+    line.push_str("  ", app.config.colors.text_default);
+    line.push_str(&phrase, [0.0, 1.0, 1.0, 1.0]);
+    surface.write_line(&line);
+    surface.write_break();
+    surface.write_break();
+
+    // Typed characters turn green while they follow the phrase, red from the first slip
+    let mut line = ColoredLine::new();
+    line.push_str("> ", app.config.colors.text_default);
+    let mut expected = phrase.chars().flat_map(char::to_lowercase);
+    let mut on_track = true;
+    for ch in app.practice_lock_input.chars() {
+        on_track = on_track
+            && ch
+                .to_lowercase()
+                .all(|lower| expected.next() == Some(lower));
+        let color = if on_track {
+            [0.0, 1.0, 0.0, 1.0]
+        } else {
+            [1.0, 0.3, 0.3, 1.0]
+        };
+        line.push_str(&ch.to_string(), color);
+    }
+    line.push_str("_", app.config.colors.text_default);
+    surface.write_line(&line);
+    surface.write_break();
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Enter: quit | ESC: keep practicing",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_exit_confirm_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║            QUIT DURING A SESSION?             ║\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n\n",
+        [1.0, 0.84, 0.0, 1.0],
+    );
+
+    let stats = app
+        .session_state
+        .current_stats(app.code_state.get_cursor_position());
+    write_text(
+        surface,
+        &format!(
+            "Session in progress: {:.0}s, {} characters, {:.0} WPM, {:.1}% accuracy\n",
+            stats.time_elapsed_secs, stats.chars_typed, stats.words_per_minute, stats.accuracy
+        ),
+        [1.0, 1.0, 1.0, 1.0],
+    );
+
+    let options = [
+        ("S", "Save partial session (marked incomplete in history)"),
+        ("D", "Discard session and quit"),
+        ("C", "Cancel and keep typing"),
+    ];
+    for (key, label) in options {
+        let mut line = ColoredLine::new();
+        line.push_str(&format!("  [{}] ", key), [0.0, 1.0, 0.0, 1.0]);
+        line.push_str(label, app.config.colors.text_default);
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Enter: save and quit | ESC: cancel",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_dogfood_menu_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    const VISIBLE_ROWS: usize = 20;
+    const BAR_CELLS: usize = 20;
+
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║        DOGFOOD MODE: CARGOTAP'S SOURCE        ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    let selected = app.dogfood_selected;
+    let first = selected.saturating_sub(VISIBLE_ROWS / 2);
+    let name_width = app
+        .dogfood_modules
+        .iter()
+        .map(|path| path.len() - DOGFOOD_PREFIX.len())
+        .max()
+        .unwrap_or(0);
+
+    for (i, path) in app
+        .dogfood_modules
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(VISIBLE_ROWS)
+    {
+        let completion = dogfood::completion(&app.progress_storage, path);
+        let filled = (completion * BAR_CELLS as f32).round() as usize;
+        let name = &path[DOGFOOD_PREFIX.len()..];
+
+        let mut line = ColoredLine::new();
+        if i == selected {
+            line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(&format!("{:<name_width$} ", name), [1.0, 1.0, 0.0, 1.0]);
+        } else {
+            line.push_str("  ", app.config.colors.text_default);
+            line.push_str(&format!("{:<name_width$} ", name), [0.7, 0.7, 0.7, 1.0]);
+        }
+        line.push_str(&"#".repeat(filled), [0.0, 1.0, 0.5, 1.0]);
+        line.push_str(
+            &".".repeat(BAR_CELLS.saturating_sub(filled)),
+            [0.3, 0.3, 0.3, 1.0],
+        );
+        line.push_str(
+            &format!(" {:.0}%", completion * 100.0),
+            app.config.colors.text_default,
+        );
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+    write_text(
+        surface,
+        "Up/Down: choose module | Enter: practice | ESC: back\n",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+}
+
+fn create_settings_screen(app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+    const BAR_CELLS: usize = 36;
+
+    write_text(
+        surface,
+        "╔═══════════════════════════════════════════════╗\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "║                THEME SETTINGS                 ║\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "╚═══════════════════════════════════════════════╝\n",
+        [0.0, 1.0, 1.0, 1.0],
+    );
+
+    for (i, name) in ColorConfig::FIELD_NAMES.iter().enumerate() {
+        let color = app
+            .config
+            .colors
+            .field(name)
+            .copied()
+            .unwrap_or(app.config.colors.text_default);
+        let is_selected = i == app.color_picker.selected_entry();
+
+        let mut line = ColoredLine::new();
+        if is_selected {
+            line.push_str("> ", [1.0, 0.84, 0.0, 1.0]);
+        } else {
+            line.push_str("  ", app.config.colors.text_default);
+        }
+        line.push_with_background(' ', color, color);
+        line.push_with_background(' ', color, color);
+        line.push(' ', app.config.colors.text_default);
+        let name_color = if is_selected {
+            [1.0, 1.0, 0.0, 1.0]
+        } else {
+            [0.7, 0.7, 0.7, 1.0]
+        };
+        line.push_str(name, name_color);
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    write_text(surface, "", app.config.colors.text_default);
+
+    let hsv = app.color_picker.hsv();
+    let bars = [
+        (HsvComponent::Hue, "H", hsv.h / 360.0),
+        (HsvComponent::Saturation, "S", hsv.s),
+        (HsvComponent::Value, "V", hsv.v),
+    ];
+
+    for (component, label, current) in bars {
+        let is_active = app.color_picker.component() == component;
+        let mut line = ColoredLine::new();
+        let label_color = if is_active {
+            [1.0, 1.0, 0.0, 1.0]
+        } else {
+            [0.6, 0.6, 0.6, 1.0]
+        };
+        line.push_str(if is_active { "> " } else { "  " }, label_color);
+        line.push_str(label, label_color);
+        line.push(' ', label_color);
+
+        let marker = ((current * (BAR_CELLS - 1) as f32).round() as usize).min(BAR_CELLS - 1);
+        for cell in 0..BAR_CELLS {
+            let t = cell as f32 / (BAR_CELLS - 1) as f32;
+            let cell_hsv = match component {
+                HsvComponent::Hue => Hsv {
+                    h: t * 360.0,
+                    s: 1.0,
+                    v: 1.0,
+                },
+                HsvComponent::Saturation => Hsv { s: t, ..hsv },
+                HsvComponent::Value => Hsv { v: t, ..hsv },
+            };
+            let cell_color = cell_hsv.to_rgba(1.0);
+            if cell == marker {
+                // The glyph atlas only covers ASCII, so the marker must be ASCII to be visible
+                line.push_with_background('|', [0.0, 0.0, 0.0, 1.0], cell_color);
+            } else {
+                line.push_with_background(' ', cell_color, cell_color);
+            }
+        }
+        surface.write_line(&line);
+        surface.write_break();
+    }
+
+    let preview = hsv.to_rgba(app.color_picker.alpha());
+    let mut line = ColoredLine::new();
+    line.push_str("  Preview ", [0.7, 0.7, 0.7, 1.0]);
+    for _ in 0..6 {
+        line.push_with_background(' ', preview, preview);
+    }
+    line.push_str(
+        &format!(
+            "  {} = [{:.2}, {:.2}, {:.2}, {:.2}]",
+            app.color_picker.selected_name(),
+            preview[0],
+            preview[1],
+            preview[2],
+            preview[3]
+        ),
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    surface.write_line(&line);
+    surface.write_break();
+
+    write_text(
+        surface,
+        "\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━",
+        [0.5, 0.8, 1.0, 1.0],
+    );
+    write_text(
+        surface,
+        "↑/↓ select color | TAB switch H/S/V | ←/→ adjust",
+        [0.7, 0.7, 0.7, 1.0],
+    );
+    write_text(
+        surface,
+        "ENTER save to config.toml | ESC discard changes",
+        [0.7, 0.7, 0.7, 1.0],
+    );
 }
 
 #[cfg(test)]
@@ -1,6 +1,19 @@
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
 use crate::app::CargoTapApp;
+use crate::code_state::CodeState;
+use crate::config::ColorConfig;
 use crate::examples::colored_text_demo::ColoredTextDemo;
+use crate::file_browser::EntryKind;
+use crate::folding::FoldRegion;
+use crate::keyboard::{self, KeyRow};
+use crate::layout;
+use crate::profiling::{self, Degradation, FRAME_BUDGETS_MS, GRAPH_ROW_MS};
+use crate::session_history::SpeedCurves;
+use crate::session_state::{self, SequenceStat};
 use crate::text::{ColoredChar, ColoredLine, ColoredText, TextSurface, WriteResult};
+use crate::tutorial::Step;
 
 pub trait UiBlock {
     fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface);
@@ -13,21 +26,391 @@ impl UiBlock for HeaderBlock {
         let mut line = ColoredLine::new();
         line.push_str("🦀 CargoTap ", [1.0, 0.5, 0.0, 1.0]);
         line.push_str("Live Demo", app.config.colors.text_header);
-        surface.write_line(&line);
+        if let Some(profile) = crate::profile::active() {
+            line.push_str(&format!(" · {}", profile), [0.5, 1.0, 1.0, 1.0]);
+        }
+        surface.write_line_wordwrap(&line);
         surface.write_break();
     }
 }
 
-pub struct FileInfoBlock;
+/// How long a script toast stays on screen
+pub const TOAST_DURATION_SECS: f64 = 4.0;
 
-impl UiBlock for FileInfoBlock {
+/// Session HUD color while live accuracy is below the configured floor
+const ACCURACY_ALARM_COLOR: [f32; 4] = [1.0, 0.25, 0.25, 1.0];
+
+/// Accuracy at the bottom of the error timeline's bars; anything lower is the shortest bar
+const TIMELINE_FLOOR_ACCURACY: f64 = 80.0;
+
+/// One bar of the error timeline, taller for better accuracy; a blank for a pause
+fn timeline_bar(window: &session_state::TimelineWindow) -> (char, [f32; 4]) {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let Some(accuracy) = window.accuracy() else {
+        return (' ', [0.5, 0.5, 0.5, 1.0]);
+    };
+    let share =
+        ((accuracy - TIMELINE_FLOOR_ACCURACY) / (100.0 - TIMELINE_FLOOR_ACCURACY)).clamp(0.0, 1.0);
+    let bar = BARS[(share * (BARS.len() - 1) as f64).round() as usize];
+    let color = if accuracy >= 95.0 {
+        [0.0, 1.0, 0.0, 1.0]
+    } else if accuracy >= 90.0 {
+        [1.0, 1.0, 0.0, 1.0]
+    } else {
+        [1.0, 0.3, 0.3, 1.0]
+    };
+    (bar, color)
+}
+
+/// A finished session's speed per minute drawn over the average and best curves of
+/// the earlier sessions on the same file, so a change shows in its shape and not
+/// just in the final number
+struct SpeedChart<'a> {
+    current: &'a [f64],
+    curves: &'a SpeedCurves,
+}
+
+impl SpeedChart<'_> {
+    const ROWS: usize = 5;
+    const CELLS_PER_MINUTE: usize = 6;
+    const LABEL_WIDTH: usize = 5;
+    const LABEL_COLOR: [f32; 4] = [0.7, 0.7, 0.7, 1.0];
+    /// Marks of the average, best and current curves, drawn in that order so the
+    /// current session is on top where they meet
+    const AVERAGE: (char, [f32; 4]) = ('·', [0.6, 0.6, 0.7, 1.0]);
+    const BEST: (char, [f32; 4]) = ('○', [1.0, 0.85, 0.2, 1.0]);
+    const CURRENT: (char, [f32; 4]) = ('●', [0.0, 1.0, 0.0, 1.0]);
+
+    /// Columns the chart takes: the minutes of the current session
+    fn columns(&self) -> usize {
+        self.current.len().saturating_sub(1) * Self::CELLS_PER_MINUTE + 1
+    }
+
+    /// `curve` at `column`, between its minutes in a straight line; None past its end
+    fn value_at(curve: &[f64], column: usize) -> Option<f64> {
+        let minute = column / Self::CELLS_PER_MINUTE;
+        let offset = column % Self::CELLS_PER_MINUTE;
+        let from = *curve.get(minute)?;
+        if offset == 0 {
+            return Some(from);
+        }
+        let to = *curve.get(minute + 1)?;
+        Some(from + (to - from) * offset as f64 / Self::CELLS_PER_MINUTE as f64)
+    }
+
+    fn lines(&self) -> Vec<ColoredLine> {
+        let columns = self.columns();
+        let curves = [
+            (self.curves.average.as_slice(), Self::AVERAGE),
+            (self.curves.best.as_slice(), Self::BEST),
+            (self.current, Self::CURRENT),
+        ];
+        let values: Vec<f64> = curves
+            .iter()
+            .flat_map(|(curve, _)| (0..columns).filter_map(|column| Self::value_at(curve, column)))
+            .collect();
+        let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let mut high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if high - low < 1.0 {
+            high = low + 1.0;
+        }
+
+        let mut grid = vec![vec![(' ', Self::LABEL_COLOR); columns]; Self::ROWS];
+        for (curve, mark) in curves {
+            for (column, cell) in (0..columns).map(|column| (column, Self::value_at(curve, column)))
+            {
+                if let Some(value) = cell {
+                    let row = ((value - low) / (high - low) * (Self::ROWS - 1) as f64).round();
+                    grid[Self::ROWS - 1 - row as usize][column] = mark;
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut legend = ColoredLine::new();
+        legend.push_str("WPM per minute: ", Self::LABEL_COLOR);
+        legend.push_str(
+            &format!("{} this session  ", Self::CURRENT.0),
+            Self::CURRENT.1,
+        );
+        legend.push_str(
+            &format!(
+                "{} average of {} earlier  ",
+                Self::AVERAGE.0,
+                self.curves.sessions
+            ),
+            Self::AVERAGE.1,
+        );
+        legend.push_str(
+            &format!("{} best ({:.0} WPM)", Self::BEST.0, self.curves.best_wpm),
+            Self::BEST.1,
+        );
+        lines.push(legend);
+
+        for (index, row) in grid.into_iter().enumerate() {
+            let label = match index {
+                0 => format!("{:.0}", high),
+                _ if index == Self::ROWS - 1 => format!("{:.0}", low),
+                _ => String::new(),
+            };
+            let mut line = ColoredLine::new();
+            line.push_str(
+                &format!("{:>width$}│", label, width = Self::LABEL_WIDTH),
+                Self::LABEL_COLOR,
+            );
+            for (ch, color) in row {
+                line.push(ch, color);
+            }
+            lines.push(line);
+        }
+
+        // Minute numbers under the points they belong to
+        let mut axis = vec![' '; columns];
+        for minute in 0..self.current.len() {
+            let label = (minute + 1).to_string();
+            for (offset, ch) in label.chars().enumerate() {
+                if let Some(cell) = axis.get_mut(minute * Self::CELLS_PER_MINUTE + offset) {
+                    *cell = ch;
+                }
+            }
+        }
+        let mut line = ColoredLine::new();
+        line.push_str(&" ".repeat(Self::LABEL_WIDTH + 1), Self::LABEL_COLOR);
+        line.push_str(&axis.into_iter().collect::<String>(), Self::LABEL_COLOR);
+        line.push_str(" min", Self::LABEL_COLOR);
+        lines.push(line);
+        lines
+    }
+}
+
+pub struct ToastBlock;
+
+impl UiBlock for ToastBlock {
     fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        if let Some((message, shown_at)) = &app.toast
+            && shown_at.elapsed().as_secs_f64() < TOAST_DURATION_SECS
+        {
+            let mut line = ColoredLine::new();
+            line.push_str("💬 ", [1.0, 0.84, 0.0, 1.0]);
+            line.push_str(message, [1.0, 1.0, 0.6, 1.0]);
+            surface.write_line_wordwrap(&line);
+            surface.write_break();
+        }
+    }
+}
+
+/// The current tutorial step, kept on screen until it is done
+pub struct TutorialBlock;
+
+impl UiBlock for TutorialBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let Some(tutorial) = &app.tutorial else {
+            return;
+        };
         let mut line = ColoredLine::new();
         line.push_str(
-            &format!("📄 File: {} ", app.current_file_path),
-            [0.5, 1.0, 1.0, 1.0],
+            &format!("🎓 Tutorial {}/{}: ", tutorial.number(), Step::ALL.len()),
+            [0.0, 1.0, 1.0, 1.0],
         );
-        surface.write_line(&line);
+        line.push_str(tutorial.step().instruction(), [1.0, 1.0, 1.0, 1.0]);
+        line.push_str(" (Esc skips the tutorial)", [0.7, 0.7, 0.7, 1.0]);
+        surface.write_line_wordwrap(&line);
+        surface.write_break();
+    }
+}
+
+/// Notes starting with TODO stand out in the file picker
+pub fn note_color(note: &str) -> [f32; 4] {
+    if note.starts_with("TODO") {
+        [1.0, 0.6, 0.2, 1.0]
+    } else {
+        [0.6, 0.9, 0.6, 1.0]
+    }
+}
+
+pub fn push_hand_share(line: &mut ColoredLine, share: Option<Option<f64>>) {
+    if let Some(Some(share)) = share {
+        line.push_str(
+            &format!("✋{:>3.0}%  ", share * 100.0),
+            [1.0, 0.84, 0.0, 1.0],
+        );
+    }
+}
+
+fn format_file_size(size: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if size >= GB {
+        format!("{:.2} GB", size as f64 / GB as f64)
+    } else if size >= MB {
+        format!("{:.2} MB", size as f64 / MB as f64)
+    } else if size >= KB {
+        format!("{:.2} KB", size as f64 / KB as f64)
+    } else {
+        format!("{} B", size)
+    }
+}
+
+/// The file picker's listing of the typed path, scrolled to keep the selection in view
+pub struct FileBrowserBlock;
+
+impl FileBrowserBlock {
+    const ROWS: usize = 24;
+    const DIR_COLOR: [f32; 4] = [0.5, 0.7, 1.0, 1.0];
+    const SELECTED_COLOR: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+    const DIM_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+}
+
+impl UiBlock for FileBrowserBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let listing = app.file_listing();
+        if !listing.readable && listing.entries.is_empty() {
+            return;
+        }
+        let mut line = ColoredLine::new();
+        if listing.readable {
+            line.push_str(
+                &format!("Contents of directory: {}", listing.dir),
+                [0.7, 0.7, 0.7, 1.0],
+            );
+        } else {
+            line.push_str("Recent files", [0.7, 0.7, 0.7, 1.0]);
+        }
+        if !listing.query.is_empty() {
+            line.push_str(&format!("  🔍 {}", listing.query), Self::SELECTED_COLOR);
+        }
+        surface.write_line_wordwrap(&line);
+        surface.write_break();
+        if let Some(extension) = &app.file_browser.extension {
+            let mut line = ColoredLine::new();
+            line.push_str(
+                &format!("Only .{} files (Ctrl+F: change)", extension),
+                [1.0, 0.84, 0.0, 1.0],
+            );
+            surface.write_line(&line);
+            surface.write_break();
+        }
+
+        let selected = app.file_browser.selected;
+        let first = selected
+            .map_or(0, |index| index.saturating_sub(Self::ROWS / 2))
+            .min(listing.entries.len().saturating_sub(Self::ROWS));
+        if first > 0 {
+            let mut line = ColoredLine::new();
+            line.push_str(&format!("  ↑ {} more", first), Self::DIM_COLOR);
+            surface.write_line(&line);
+            surface.write_break();
+        }
+        for (index, entry) in listing
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(Self::ROWS)
+        {
+            let is_selected = selected == Some(index);
+            let mut line = ColoredLine::new();
+            line.push_str(if is_selected { "▶" } else { " " }, Self::SELECTED_COLOR);
+            let has_progress = app.progress_storage.get_progress(&entry.path).is_some();
+            let (marker, color) = match entry.kind {
+                EntryKind::Directory => (" 📁 ", Self::DIR_COLOR),
+                EntryKind::Recent => (" 🕘 ", [1.0, 1.0, 0.0, 1.0]),
+                EntryKind::File if has_progress => (" ★ ", [1.0, 1.0, 0.0, 1.0]),
+                EntryKind::File => ("   ", [0.9, 0.9, 0.9, 1.0]),
+            };
+            line.push_str(marker, [1.0, 0.84, 0.0, 1.0]);
+            let name = match entry.kind {
+                EntryKind::Directory => format!("{}/", entry.name),
+                _ => entry.name.clone(),
+            };
+            let color = if is_selected {
+                Self::SELECTED_COLOR
+            } else {
+                color
+            };
+            line.push_str(&name, color);
+            let padding = 40_usize.saturating_sub(name.chars().count());
+            line.push_str(&" ".repeat(padding), [0.7, 0.7, 0.7, 1.0]);
+            match entry.kind {
+                EntryKind::Directory => line.push_str("<DIR>", Self::DIR_COLOR),
+                _ => line.push_str(&format_file_size(entry.size), [0.5, 0.8, 1.0, 1.0]),
+            }
+            if entry.share.is_some() {
+                line.push_str("  ", [0.7, 0.7, 0.7, 1.0]);
+                push_hand_share(&mut line, entry.share);
+            }
+            if let Some(note) = app.progress_storage.get_note(&entry.path) {
+                let short: String = note.chars().take(40).collect();
+                line.push_str(&format!("  # {}", short), note_color(note));
+            }
+            surface.write_line(&line);
+            surface.write_break();
+        }
+        let below = listing.entries.len().saturating_sub(first + Self::ROWS);
+        if below > 0 {
+            let mut line = ColoredLine::new();
+            line.push_str(&format!("  ↓ {} more", below), Self::DIM_COLOR);
+            surface.write_line(&line);
+            surface.write_break();
+        }
+
+        let hidden = [
+            (listing.ignored, "generated or vendored entries"),
+            (listing.other_hand, "files not mostly typed with that hand"),
+            (listing.other_extension, "files with other extensions"),
+        ];
+        for (count, what) in hidden {
+            if count > 0 {
+                let mut line = ColoredLine::new();
+                line.push_str(&format!("  ({} {} hidden)", count, what), Self::DIM_COLOR);
+                surface.write_line(&line);
+                surface.write_break();
+            }
+        }
+        surface.write_break();
+    }
+}
+
+pub struct FileInfoBlock;
+
+impl UiBlock for FileInfoBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let label = if app.active_quote.is_some() {
+            "💬 Quote mode (Ctrl+U to leave) ".to_string()
+        } else if let Some(drill) = &app.active_drill {
+            format!(
+                "🥁 Drill: {} | difficulty {} | seed {} (Ctrl+D to leave) ",
+                drill.kind.name(),
+                drill.difficulty,
+                drill.seed
+            )
+        } else if let Some(split) = &app.split {
+            let turns = match split.switch_lines {
+                0 => "Ctrl+Shift+X to switch".to_string(),
+                lines => format!("switching every {} lines or on Ctrl+Shift+X", lines),
+            };
+            format!(
+                "🔀 Split: {} | other file {:.1}% done ({}) ",
+                app.session_file_label(),
+                split.other.code_state.get_progress() * 100.0,
+                turns
+            )
+        } else if let Some(review) = &app.active_review {
+            format!(
+                "🔁 Review round {}: {} mistyped lines (Ctrl+P to open a file) ",
+                review.round,
+                review.lines.len()
+            )
+        } else if app.code_state.is_streaming() {
+            format!("📄 File: {} (streaming) ", app.current_file_path)
+        } else {
+            format!("📄 File: {} ", app.current_file_path)
+        };
+        let mut line = ColoredLine::new();
+        line.push_str(&label, [0.5, 1.0, 1.0, 1.0]);
+        surface.write_line_wordwrap(&line);
         surface.write_break();
     }
 }
@@ -47,11 +430,95 @@ impl UiBlock for ProgressBlock {
             ),
             [0.0, 1.0, 0.5, 1.0],
         );
-        surface.write_line(&line);
+        surface.write_line_wordwrap(&line);
+        surface.write_break();
+
+        if let Some(header) = app.license_header {
+            let mut line = ColoredLine::new();
+            line.push_str(
+                &format!(
+                    "| License header skipped: lines 1-{} (not typed)",
+                    header.lines
+                ),
+                [0.6, 0.6, 0.6, 1.0],
+            );
+            surface.write_line_wordwrap(&line);
+            surface.write_break();
+        }
+
+        if let (Some(range), Some((start, end))) = (app.line_range, app.line_range_bounds()) {
+            let done = cursor_pos.clamp(start, end) - start;
+            let range_percent = if end > start {
+                done as f32 / (end - start) as f32 * 100.0
+            } else {
+                100.0
+            };
+            let mut line = ColoredLine::new();
+            line.push_str(
+                &format!(
+                    "| Lines {}: {:.1}% (Ctrl+L to change)",
+                    range, range_percent
+                ),
+                [1.0, 0.85, 0.2, 1.0],
+            );
+            surface.write_line_wordwrap(&line);
+            surface.write_break();
+        }
+    }
+}
+
+pub struct CursorInfoBlock;
+
+impl UiBlock for CursorInfoBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let code = &app.code_state;
+        let mut info = format!(
+            "| Ln {}, Col {}",
+            code.get_cursor_line(),
+            code.get_cursor_column() + 1
+        );
+        if let Some(total_lines) = code.get_total_lines() {
+            info.push_str(&format!(" of {} lines", total_lines));
+        }
+        if let Some(words) = code.get_word_count() {
+            info.push_str(&format!(" | {} words", words));
+        }
+
+        // Prefer the live speed, fall back to recent sessions before typing starts
+        let current_pos = code.get_cursor_position();
+        let live = app.session_state.current_stats(current_pos);
+        let wpm = if app.session_state.is_active() && live.time_elapsed_secs >= 5.0 {
+            live.words_per_minute
+        } else {
+            app.session_history.get_recent_summary(5).avg_wpm
+        };
+        match session_state::estimate_secs_to_finish(code.get_remaining_chars(), wpm) {
+            Some(secs) => info.push_str(&format!(
+                " | ~{} to finish at {:.0} WPM",
+                format_eta(secs),
+                wpm
+            )),
+            None => info.push_str(" | finish time shown once you type"),
+        }
+
+        let mut line = ColoredLine::new();
+        line.push_str(&info, [0.6, 0.8, 1.0, 1.0]);
+        surface.write_line_wordwrap(&line);
         surface.write_break();
     }
 }
 
+pub fn format_eta(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
 pub struct FpsBlock;
 
 impl UiBlock for FpsBlock {
@@ -77,8 +544,121 @@ impl UiBlock for FpsBlock {
                 [0.8, 0.8, 0.8, 1.0],
             );
         }
+        if app.config.debug.show_profiling_info
+            && let (Some(p50), Some(p95)) = (
+                app.perf_stats.key_latency.percentile(50.0),
+                app.perf_stats.key_latency.percentile(95.0),
+            )
+        {
+            line.push_str(
+                &format!(" | Latency p50 {:.1}ms p95 {:.1}ms", p50, p95),
+                [0.8, 0.8, 0.8, 1.0],
+            );
+        }
+        let refresh = match app.refresh.detected_hz() {
+            Some(hz) => format!(" | 🖥 {:.0} Hz", hz),
+            None => format!(" | 🖥 {:.0} Hz?", app.refresh.rate_hz()),
+        };
+        line.push_str(&refresh, [0.8, 0.8, 0.8, 1.0]);
+        let degradation = app.text_budget.level();
+        if degradation != Degradation::None {
+            line.push_str(
+                &format!(" | 🐢 {}", degradation.label()),
+                [1.0, 0.6, 0.2, 1.0],
+            );
+        }
+        surface.write_line(&line);
+        surface.write_break();
+
+        if let Some(median) = app
+            .perf_stats
+            .slow_input(app.config.debug.key_latency_warn_ms)
+        {
+            let mut line = ColoredLine::new();
+            line.push_str(
+                &format!(
+                    "⚠ Slow input: median key latency {:.1}ms (limit {:.0}ms)",
+                    median, app.config.debug.key_latency_warn_ms
+                ),
+                [1.0, 0.6, 0.2, 1.0],
+            );
+            surface.write_line(&line);
+            surface.write_break();
+        }
+    }
+}
+
+/// Rolling graph of recent frame times, one bar per frame
+pub struct FrameGraphBlock;
+
+impl FrameGraphBlock {
+    const ROWS: usize = 4;
+    const LABEL_WIDTH: usize = 8;
+
+    fn bar_color(frame_ms: f64) -> [f32; 4] {
+        if frame_ms <= FRAME_BUDGETS_MS[1] {
+            [0.3, 0.9, 0.3, 1.0]
+        } else if frame_ms <= FRAME_BUDGETS_MS[0] {
+            [1.0, 0.84, 0.0, 1.0]
+        } else {
+            [1.0, 0.3, 0.3, 1.0]
+        }
+    }
+}
+
+impl UiBlock for FrameGraphBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        if !app.config.debug.show_frame_times {
+            return;
+        }
+
+        let label_color = [0.6, 0.6, 0.6, 1.0];
+        let budget_color = [0.4, 0.4, 0.5, 1.0];
+        let history = &app.frame_time_history;
+
+        let mut line = ColoredLine::new();
+        line.push_str(
+            &format!(
+                "Frame times (last {}): max {:.1}ms | {} over {:.1}ms",
+                profiling::FRAME_HISTORY_LEN,
+                history.max_ms(),
+                history.over_budget(FRAME_BUDGETS_MS[0]),
+                FRAME_BUDGETS_MS[0]
+            ),
+            label_color,
+        );
         surface.write_line(&line);
         surface.write_break();
+
+        // Only the newest frames that fit next to the labels; the bars would be cut
+        // off at the window edge anyway
+        let columns = (surface.remaining_width() / surface.measure('█')) as usize;
+        let shown = columns.saturating_sub(Self::LABEL_WIDTH);
+        let skipped = history.samples().count().saturating_sub(shown);
+
+        for row in (0..Self::ROWS).rev() {
+            // Budget lines run along the bottom of the row they start
+            let row_start_ms = row as f64 * GRAPH_ROW_MS;
+            let budget = FRAME_BUDGETS_MS
+                .into_iter()
+                .find(|budget| (budget - row_start_ms).abs() < 0.01);
+
+            let mut line = ColoredLine::new();
+            let label = budget.map_or(String::new(), |ms| format!("{:.1}ms", ms));
+            line.push_str(
+                &format!("{:>width$} ", label, width = Self::LABEL_WIDTH - 1),
+                label_color,
+            );
+            for frame_ms in history.samples().skip(skipped) {
+                match profiling::bar_cell(frame_ms, row) {
+                    Some(cell) => line.push(cell, Self::bar_color(frame_ms)),
+                    None if budget.is_some() => line.push('▁', budget_color),
+                    None => line.push(' ', budget_color),
+                }
+            }
+            surface.write_line(&line);
+            surface.write_break();
+        }
     }
 }
 
@@ -103,10 +683,10 @@ impl UiBlock for SessionStateBlock {
             if let Some(stats) = app.session_state.last_stats() {
                 let mut line = ColoredLine::new();
                 line.push_str("⏰ SESSION COMPLETE! ", [1.0, 1.0, 0.0, 1.0]);
-                surface.write_line(&line);
+                surface.write_line_wordwrap(&line);
                 surface.write_break();
 
-                let summary = format!(
+                let mut summary = format!(
                     "Time: {:.1}s | Chars: {} | Speed: {:.0} CPM / {:.0} WPM | Accuracy: {:.1}%",
                     stats.time_elapsed_secs,
                     stats.chars_typed,
@@ -114,43 +694,233 @@ impl UiBlock for SessionStateBlock {
                     stats.words_per_minute,
                     stats.accuracy
                 );
+                if stats.dead_zones > 0 {
+                    summary.push_str(&format!(" | Dead zones: {}", stats.dead_zones));
+                }
+                if stats.was_interrupted() {
+                    summary.push_str(&format!(
+                        " | Interrupted: {:.0}s of sleep not counted",
+                        stats.paused_secs
+                    ));
+                }
+                if let Some(range) = stats.line_range {
+                    summary.push_str(&format!(" | Lines {}", range));
+                }
+                if let Some(seed) = stats.seed {
+                    summary.push_str(&format!(" | Seed {}", seed));
+                }
+                if let Some(challenge) = &stats.challenge {
+                    summary.push_str(&format!(" | Challenge {}", challenge));
+                }
+                if stats.pane_switches > 0 {
+                    summary.push_str(&format!(" | Pane switches: {}", stats.pane_switches));
+                }
+                if stats.too_fast_keys > 0 {
+                    summary.push_str(&format!(" | Too fast: {}", stats.too_fast_keys));
+                }
+                if !stats.end_condition.is_time() {
+                    summary.push_str(&format!(" | Ended after {}", stats.end_condition));
+                }
+                if let Some(warmup) = &stats.warmup {
+                    summary.push_str(&format!(
+                        " | Warm-up (not counted): {:.0}s at {:.0} WPM, {:.1}%",
+                        warmup.time_elapsed_secs,
+                        warmup.words_per_minute(),
+                        warmup.accuracy()
+                    ));
+                }
+                let scoring = &app.config.scoring;
+                if scoring.enabled {
+                    summary.push_str(&format!(
+                        " | Score: {:.1}",
+                        stats.score_or_compute(scoring.accuracy_exponent)
+                    ));
+                    let score = stats.score_or_compute(scoring.accuracy_exponent);
+                    let is_best = app
+                        .session_history
+                        .previous_best_score(scoring.accuracy_exponent)
+                        .is_some_and(|best| score > best);
+                    if is_best {
+                        summary.push_str(" | New personal best!");
+                    }
+                }
                 let mut line = ColoredLine::new();
                 line.push_str(&summary, [0.0, 1.0, 0.0, 1.0]);
-                surface.write_line(&line);
+                surface.write_line_wordwrap(&line);
                 surface.write_break();
 
+                // A single window says nothing about how accuracy changed
+                let timeline = &stats.error_timeline;
+                if timeline.windows.len() > 1 {
+                    let mut line = ColoredLine::new();
+                    line.push_str(
+                        &format!("Accuracy per {}s: ", timeline.window_secs),
+                        [0.7, 0.7, 0.7, 1.0],
+                    );
+                    for window in &timeline.windows {
+                        let (bar, color) = timeline_bar(window);
+                        line.push(bar, color);
+                    }
+                    if let Some((index, accuracy)) = timeline.lowest()
+                        && accuracy < 100.0
+                    {
+                        line.push_str(
+                            &format!(
+                                "  lowest {:.0}% at {}",
+                                accuracy,
+                                timeline.window_label(index)
+                            ),
+                            [0.7, 0.7, 0.7, 1.0],
+                        );
+                    }
+                    surface.write_line_wordwrap(&line);
+                    surface.write_break();
+                }
+
+                // One minute is a point, not a curve
+                let current = stats.wpm_curve();
+                if current.len() > 1
+                    && let Some(curves) = app
+                        .session_history
+                        .speed_curves(&stats.file_path, stats.timestamp)
+                {
+                    let chart = SpeedChart {
+                        current: &current,
+                        curves: &curves,
+                    };
+                    for line in chart.lines() {
+                        surface.write_line(&line);
+                        surface.write_break();
+                    }
+                }
+
+                let reviewable = app.reviewable_lines().len();
+                let prompt: Cow<str> = if let Some(quote) = &app.active_quote {
+                    let mut line = ColoredLine::new();
+                    line.push_str(&format!("- {}", quote.author), [1.0, 0.85, 0.2, 1.0]);
+                    surface.write_line_wordwrap(&line);
+                    surface.write_break();
+                    "Press SPACE for the next quote, R to retry this one, Ctrl+U to leave quote mode"
+                        .into()
+                } else if app.active_drill.is_some() {
+                    "Press SPACE for the next drill, R to retry this one, Ctrl+D to leave drill mode"
+                        .into()
+                } else if app.active_review.is_some() {
+                    match reviewable {
+                        0 => "Review complete, every line typed without errors! Press SPACE to go back to your file".into(),
+                        lines => format!("Press SPACE to type the {} lines still mistyped again", lines).into(),
+                    }
+                } else if app.line_range.is_some() {
+                    "Press SPACE to repeat these lines, Ctrl+L to pick other lines".into()
+                } else {
+                    "Press SPACE to start new session".into()
+                };
+                let prompt = format!("{}, C to copy a result card", prompt);
+
                 let mut line = ColoredLine::new();
-                line.push_str("Press SPACE to start new session", [0.0, 1.0, 1.0, 1.0]);
-                surface.write_line(&line);
+                line.push_str(&prompt, [0.0, 1.0, 1.0, 1.0]);
+                surface.write_line_wordwrap(&line);
                 surface.write_break();
 
+                if reviewable > 0 && app.active_review.is_none() {
+                    let mut line = ColoredLine::new();
+                    line.push_str(
+                        &format!(
+                            "Press V to type the {} lines with errors again until they are right",
+                            reviewable
+                        ),
+                        [0.0, 1.0, 1.0, 1.0],
+                    );
+                    surface.write_line_wordwrap(&line);
+                    surface.write_break();
+                }
+
                 let mut line = ColoredLine::new();
                 line.push_str(&"─".repeat(30), [0.5, 0.8, 1.0, 1.0]);
                 surface.write_line(&line);
                 surface.write_break();
             }
         } else if app.session_state.is_active() {
-            let time_str = format!("⏱️  Time: {} ", app.session_state.format_time_remaining());
+            // Below the accuracy floor the whole HUD turns red
+            let alarm = app.accuracy_alarm.is_tripped();
+            let tint = |color: [f32; 4]| if alarm { ACCURACY_ALARM_COLOR } else { color };
+
             let mut line = ColoredLine::new();
-            line.push_str(&time_str, [1.0, 1.0, 0.0, 1.0]);
+            match app.session_state.format_goal_progress() {
+                Some(goal) => {
+                    let time_str = format!("⏱️  {} ", app.session_state.format_time_elapsed());
+                    line.push_str(&time_str, tint([1.0, 1.0, 0.0, 1.0]));
+                    line.push_str(&format!("| 🏁 {} ", goal), tint([1.0, 1.0, 0.0, 1.0]));
+                }
+                None => {
+                    let time_str =
+                        format!("⏱️  Time: {} ", app.session_state.format_time_remaining());
+                    line.push_str(&time_str, tint([1.0, 1.0, 0.0, 1.0]));
+                }
+            }
 
             let current_pos = app.code_state.get_cursor_position();
             let stats = app.session_state.current_stats(current_pos);
-            if stats.time_elapsed_secs > 0.0 {
+            if app.session_state.in_warmup() {
+                line.push_str("| 🔥 warming up ", tint([1.0, 0.6, 0.2, 1.0]));
+            } else if stats.time_elapsed_secs > 0.0 {
                 let speed_str = format!("| {:.0} CPM ", stats.chars_per_minute);
-                line.push_str(&speed_str, [0.0, 1.0, 0.0, 1.0]);
+                line.push_str(&speed_str, tint([0.0, 1.0, 0.0, 1.0]));
             }
-            surface.write_line(&line);
+            if app.accuracy_alarm.floor().is_some() {
+                let accuracy_str = format!("| {:.1}% ", stats.accuracy);
+                line.push_str(&accuracy_str, tint([0.0, 1.0, 0.0, 1.0]));
+            }
+            if let Some(metronome) = &app.metronome {
+                // One dot per beat of a four-beat bar, the current one filled
+                let elapsed = Duration::from_secs_f64(app.session_state.time_elapsed());
+                let beat = metronome.beat(elapsed) % 4;
+                let dots: String = (0..4)
+                    .map(|index| if index == beat { '●' } else { '·' })
+                    .collect();
+                line.push_str(&format!("| {} ", dots), tint([0.5, 0.8, 1.0, 1.0]));
+                if stats.too_fast_keys > 0 {
+                    line.push_str(
+                        &format!("{} too fast ", stats.too_fast_keys),
+                        tint([1.0, 0.6, 0.2, 1.0]),
+                    );
+                }
+            }
+            surface.write_line_wordwrap(&line);
             surface.write_break();
 
+            if alarm && let Some(floor) = app.accuracy_alarm.floor() {
+                let mut line = ColoredLine::new();
+                line.push_str(
+                    &format!("Accuracy below {:.1}%, slow down!", floor),
+                    ACCURACY_ALARM_COLOR,
+                );
+                surface.write_line_wordwrap(&line);
+                surface.write_break();
+            }
+
+            if app.session_state.in_dead_zone() {
+                // Blink twice a second; the active session redraws every frame
+                let flash_on = ((app.session_state.time_elapsed() * 2.0) as u64).is_multiple_of(2);
+                let color = if flash_on {
+                    [1.0, 0.3, 0.3, 1.0]
+                } else {
+                    [1.0, 0.8, 0.3, 1.0]
+                };
+                let mut line = ColoredLine::new();
+                line.push_str("Check your hand position! Press SPACE to continue", color);
+                surface.write_line_wordwrap(&line);
+                surface.write_break();
+            }
+
             let mut line = ColoredLine::new();
-            line.push_str(&"─".repeat(30), [0.5, 0.8, 1.0, 1.0]);
+            line.push_str(&"─".repeat(30), tint([0.5, 0.8, 1.0, 1.0]));
             surface.write_line(&line);
             surface.write_break();
         } else {
             let mut line = ColoredLine::new();
             line.push_str("Start typing to begin session...", [0.7, 0.7, 0.7, 1.0]);
-            surface.write_line(&line);
+            surface.write_line_wordwrap(&line);
             surface.write_break();
 
             let mut line = ColoredLine::new();
@@ -161,86 +931,663 @@ impl UiBlock for SessionStateBlock {
     }
 }
 
-pub struct CodeDisplayBlock;
+/// Lines of a text as the code view shows them, starting below the scrolled-away ones:
+/// the line-number gutter (if shown) and the line, with the caret marked when `caret` is set.
+/// A folded function body takes a single row.
+struct CodeRows<'a> {
+    lines: std::iter::Enumerate<std::slice::Iter<'a, ColoredLine>>,
+    /// Byte offset of the cursor from the start of the next line
+    cursor_position: i32,
+    scroll_offset: usize,
+    first_line: usize,
+    /// Width of the line numbers, None without a gutter
+    num_digits: Option<usize>,
+    caret: bool,
+    newline_color: [f32; 4],
+    /// Functions in the text, marked in the gutter
+    bodies: Vec<FoldRegion>,
+    /// Bodies that end above this line of the text are folded; None folds nothing
+    fold_before: Option<usize>,
+    /// Colors of the whitespace marks, None leaves whitespace blank
+    whitespace: Option<WhitespaceMarks>,
+    /// Mistakes shown at the caret
+    errors: ErrorMarks,
+    /// Lines of the text fading in, with their opacity
+    fades: Vec<(usize, f32)>,
+}
 
-impl UiBlock for CodeDisplayBlock {
-    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
-        let line_number_color = [0.5, 0.5, 0.6, 1.0];
-        let current_line_color = [1.0, 0.85, 0.2, 1.0];
-        let separator_color = [0.4, 0.4, 0.5, 1.0];
-        let caret_bg_color = [0.0, 1.0, 0.0, 0.5];
-
-        let mut cursor_position = app.code_state.get_cursor_position() as i32;
-        let mut scroll_offset = app.scroll_offset;
-        let full_code_colored = app.code_state.get_full_code_colored();
-
-        let num_digits = (full_code_colored.lines.len() + app.scroll_offset)
-            .to_string()
-            .len()
-            .max(3);
-
-        let mut current_line = ColoredLine::new();
-        for (num, line) in full_code_colored.lines.iter().enumerate() {
+/// Mistakes at the caret: characters a wrong key was typed over, with the caret
+/// after them, or the character the last wrong key missed, with the caret on it
+#[derive(Debug, Clone, Copy, Default)]
+struct ErrorMarks {
+    /// Characters typed over
+    covered: usize,
+    missed: bool,
+    color: [f32; 4],
+}
+
+impl ErrorMarks {
+    fn of(code_state: &CodeState, color: [f32; 4]) -> Self {
+        Self {
+            covered: code_state.pending_errors().len(),
+            missed: code_state.is_missed(),
+            color,
+        }
+    }
+}
+
+/// Colors of the whitespace marks. Spaces and tabs already typed keep the correct or
+/// incorrect color, so a missed space stands out like any other mistake.
+#[derive(Debug, Clone, Copy)]
+struct WhitespaceMarks {
+    space: [f32; 4],
+    newline: [f32; 4],
+}
+
+impl WhitespaceMarks {
+    fn from_colors(colors: &ColorConfig) -> Self {
+        Self {
+            space: colors.text_whitespace,
+            newline: colors.text_newline,
+        }
+    }
+
+    /// `line` with marks for its spaces and tabs, and for its line break when
+    /// `line_break` is set. The first `typed` bytes of the line are typed.
+    fn apply(&self, line: &ColoredLine, typed: usize, line_break: bool) -> ColoredLine {
+        let mut marked = line.clone();
+        let mut offset = 0;
+        for colored_char in &mut marked.chars {
+            let is_typed = offset < typed;
+            offset += colored_char.ch.len_utf8();
+            let mark = match colored_char.ch {
+                ' ' => '·',
+                '\t' => '→',
+                _ => continue,
+            };
+            colored_char.ch = mark;
+            if !is_typed {
+                colored_char.color = self.space;
+            }
+        }
+        if line_break {
+            marked.push('¶', self.newline);
+        }
+        marked
+    }
+}
+
+impl<'a> CodeRows<'a> {
+    const LINE_NUMBER_COLOR: [f32; 4] = [0.5, 0.5, 0.6, 1.0];
+    const CURRENT_LINE_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+    const SEPARATOR_COLOR: [f32; 4] = [0.4, 0.4, 0.5, 1.0];
+    const FOLD_COLOR: [f32; 4] = [0.45, 0.7, 0.9, 1.0];
+    const UNFOLDED_MARKER: char = '▾';
+    const FOLDED_MARKER: char = '▸';
+
+    fn new(
+        text: &'a ColoredText,
+        cursor_position: usize,
+        scroll_offset: usize,
+        first_line: usize,
+        show_line_numbers: bool,
+        caret: bool,
+        newline_color: [f32; 4],
+    ) -> Self {
+        let num_digits = (text.lines.len() + first_line).to_string().len().max(3);
+        Self {
+            lines: text.lines.iter().enumerate(),
+            cursor_position: cursor_position as i32,
+            scroll_offset,
+            first_line,
+            num_digits: show_line_numbers.then_some(num_digits),
+            caret,
+            newline_color,
+            bodies: Vec::new(),
+            fold_before: None,
+            whitespace: None,
+            errors: ErrorMarks::default(),
+            fades: Vec::new(),
+        }
+    }
+
+    /// Draws the given lines of the text with their opacity
+    fn with_fades(mut self, fades: Vec<(usize, f32)>) -> Self {
+        self.fades = fades;
+        self
+    }
+
+    /// `gutter` and `code_line` of line `num` of the text, faded if it is fading in
+    fn faded(
+        &self,
+        num: usize,
+        gutter: Option<ColoredLine>,
+        code_line: Cow<'a, ColoredLine>,
+    ) -> (Option<ColoredLine>, Cow<'a, ColoredLine>) {
+        let Some(&(_, opacity)) = self.fades.iter().find(|(line, _)| *line == num) else {
+            return (gutter, code_line);
+        };
+        let fade = |line: &mut ColoredLine| {
+            for colored_char in &mut line.chars {
+                colored_char.color[3] *= opacity;
+            }
+        };
+        let gutter = gutter.map(|mut gutter| {
+            fade(&mut gutter);
+            gutter
+        });
+        let mut code_line = code_line.into_owned();
+        fade(&mut code_line);
+        (gutter, Cow::Owned(code_line))
+    }
+
+    /// Colors the mistakes at the caret
+    fn with_errors(mut self, errors: ErrorMarks) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Marks spaces, tabs and line breaks in the given colors
+    fn with_whitespace(mut self, marks: Option<WhitespaceMarks>) -> Self {
+        self.whitespace = marks;
+        self
+    }
+
+    /// Marks the function `bodies` in the gutter and folds those that end above line
+    /// `fold_before` of the text, when it is set
+    fn with_folds(mut self, bodies: &[FoldRegion], fold_before: Option<usize>) -> Self {
+        self.bodies = bodies.to_vec();
+        self.fold_before = fold_before;
+        self
+    }
+
+    /// The outermost folded body around line `num` of the text
+    fn folded_region(&self, num: usize) -> Option<FoldRegion> {
+        let fold_before = self.fold_before?;
+        self.bodies
+            .iter()
+            .find(|region| region.contains(num) && region.end < fold_before)
+            .copied()
+    }
+
+    fn gutter(&self, number: usize, digits: usize, is_current: bool, marker: char) -> ColoredLine {
+        let mut num_line = ColoredLine::new();
+        let num_color = if is_current {
+            Self::CURRENT_LINE_COLOR
+        } else {
+            Self::LINE_NUMBER_COLOR
+        };
+        num_line.push_str(&format!("{:>width$}", number, width = digits), num_color);
+        num_line.push(marker, Self::FOLD_COLOR);
+        num_line.push_str("│ ", Self::SEPARATOR_COLOR);
+        num_line
+    }
+
+    /// What goes before the rows a wrapped line continues on: a gutter as wide as the
+    /// line-number one with the continuation marker, or just the marker without one
+    fn continuation_gutter(&self) -> ColoredLine {
+        let mut gutter = ColoredLine::new();
+        match self.num_digits {
+            Some(digits) => {
+                gutter.push_str(&" ".repeat(digits), Self::LINE_NUMBER_COLOR);
+                gutter.push(layout::CONTINUATION_MARKER, Self::FOLD_COLOR);
+                gutter.push_str("│ ", Self::SEPARATOR_COLOR);
+            }
+            None => {
+                gutter.push(layout::CONTINUATION_MARKER, Self::FOLD_COLOR);
+                gutter.push(' ', Self::FOLD_COLOR);
+            }
+        }
+        gutter
+    }
+
+    /// `line` with the caret on the character at byte offset `cursor`, or after the
+    /// characters wrong keys were typed over
+    fn with_caret(&self, line: &ColoredLine, cursor: usize) -> ColoredLine {
+        let mut current_line = line.clone();
+        // Index of the character at the cursor
+        let mut index = cursor;
+        let mut start = None;
+        for (i, colored_char) in line.chars.iter().enumerate() {
+            if index == 0 {
+                start = Some(i);
+                break;
+            }
+            if colored_char.ch.len_utf8() > index {
+                break;
+            }
+            index -= colored_char.ch.len_utf8();
+        }
+        if index == 0 && start.is_none() {
+            start = Some(line.chars.len());
+        }
+        let Some(start) = start else {
+            return current_line;
+        };
+
+        let caret = start + self.errors.covered;
+        if caret == line.chars.len() {
+            current_line.push('↩', self.newline_color);
+        }
+        let marked = if self.errors.missed {
+            1
+        } else {
+            self.errors.covered
+        };
+        for colored_char in current_line.chars.iter_mut().skip(start).take(marked) {
+            colored_char.color = self.errors.color;
+        }
+        if let Some(colored_char) = current_line.chars.get_mut(caret) {
+            colored_char.caret = true;
+        }
+        current_line
+    }
+}
+
+impl<'a> Iterator for CodeRows<'a> {
+    type Item = (Option<ColoredLine>, Cow<'a, ColoredLine>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (num, line) = self.lines.next()?;
             let line_len = line.chars.iter().map(|ch| ch.ch.len_utf8()).sum::<usize>();
             let break_len = '\n'.len_utf8();
-            if scroll_offset > 0 {
-                cursor_position -= (line_len + break_len) as i32;
-                scroll_offset -= 1;
+            let cursor_position = self.cursor_position;
+            self.cursor_position -= (line_len + break_len) as i32;
+            if self.scroll_offset > 0 {
+                self.scroll_offset -= 1;
                 continue;
             }
 
-            let (code_line, is_current) =
-                if cursor_position >= 0 && line_len + break_len > cursor_position as usize {
-                    current_line = line.clone();
-                    if line_len == cursor_position as usize {
-                        current_line.chars.push(crate::text::ColoredChar {
-                            ch: '↩',
-                            color: app.config.colors.text_default,
-                            background_color: None,
-                        });
+            if let Some(region) = self.folded_region(num) {
+                for _ in num..region.end {
+                    if let Some((_, hidden)) = self.lines.next() {
+                        let hidden_len = hidden
+                            .chars
+                            .iter()
+                            .map(|ch| ch.ch.len_utf8())
+                            .sum::<usize>();
+                        self.cursor_position -= (hidden_len + break_len) as i32;
                     }
-
-                    let mut index = cursor_position as usize;
-                    for i in 0..current_line.chars.len() {
-                        if index == 0 {
-                            if let Some(ch_mut) = current_line.chars.get_mut(i) {
-                                ch_mut.background_color = Some(caret_bg_color);
-                            }
-                            break;
-                        }
-                        if line.chars[i].ch.len_utf8() > index {
-                            break;
-                        }
-                        index -= line.chars[i].ch.len_utf8();
-                    }
-                    (&current_line, true)
-                } else {
-                    (line, false)
+                }
+                // Everything in a folded body is typed
+                let mut code_line = match self.whitespace {
+                    Some(marks) => marks.apply(line, usize::MAX, false),
+                    None => line.clone(),
                 };
+                code_line.push_str(
+                    &format!("  ⋯ {} lines typed", region.lines_from(num)),
+                    Self::FOLD_COLOR,
+                );
+                let gutter = self.num_digits.map(|digits| {
+                    self.gutter(
+                        self.first_line + num + 1,
+                        digits,
+                        false,
+                        Self::FOLDED_MARKER,
+                    )
+                });
+                return Some(self.faded(num, gutter, Cow::Owned(code_line)));
+            }
+
+            let is_current =
+                cursor_position >= 0 && line_len + break_len > cursor_position as usize;
+            let caret_at_break = is_current && cursor_position as usize == line_len;
+            let code_line = if is_current && self.caret {
+                Cow::Owned(self.with_caret(line, cursor_position as usize))
+            } else {
+                Cow::Borrowed(line)
+            };
+            let code_line = match self.whitespace {
+                Some(marks) => {
+                    // The caret on a line break already shows it (↩); the last line has none
+                    let line_break = self.lines.len() > 0 && !(caret_at_break && self.caret);
+                    let typed = cursor_position.max(0) as usize;
+                    Cow::Owned(marks.apply(&code_line, typed, line_break))
+                }
+                None => code_line,
+            };
+            let marker = if self.bodies.iter().any(|region| region.start == num) {
+                Self::UNFOLDED_MARKER
+            } else {
+                ' '
+            };
+            let gutter = self
+                .num_digits
+                .map(|digits| self.gutter(self.first_line + num + 1, digits, is_current, marker));
+            return Some(self.faded(num, gutter, code_line));
+        }
+    }
+}
+
+/// The rows `code_line` takes from the pen on: wrapped to the space left on the row
+/// when `wrap` is set, otherwise one row that is cut off at the edge
+fn code_line_rows(
+    surface: &dyn TextSurface,
+    gutter: Option<ColoredLine>,
+    code_line: &ColoredLine,
+    continuation: &ColoredLine,
+    wrap: bool,
+) -> Vec<ColoredLine> {
+    if wrap {
+        return layout::wrap(
+            code_line,
+            gutter.as_ref(),
+            continuation,
+            surface.remaining_width(),
+            |ch| surface.measure(ch),
+        );
+    }
+    let mut row = gutter.unwrap_or_else(ColoredLine::new);
+    row.chars.extend_from_slice(&code_line.chars);
+    vec![row]
+}
+
+/// Line of the cursor within the loaded text; function bodies above it are typed
+fn fold_line(code_state: &CodeState) -> usize {
+    (code_state.get_cursor_line() - 1).saturating_sub(code_state.first_line_number())
+}
+
+/// Rows of code laid out while the text budget shortens the window
+pub const SHORT_WINDOW_ROWS: usize = 12;
+
+/// How many rows of code to lay out, fewer when the text budget asks for it
+fn code_row_limit(app: &CargoTapApp) -> usize {
+    if app.text_budget.level() >= Degradation::ShortWindow {
+        SHORT_WINDOW_ROWS
+    } else {
+        usize::MAX
+    }
+}
+
+pub struct CodeDisplayBlock;
+
+impl UiBlock for CodeDisplayBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let cursor_position = app.code_state.get_window_cursor_position();
+        let first_line = app.code_state.first_line_number();
+        let show_line_numbers = app.show_line_numbers();
+        let wrap_lines = app.wrap_lines();
+        let row_limit = code_row_limit(app);
+        let fold_before = app.fold_typed().then(|| fold_line(&app.code_state));
+        let whitespace = app
+            .show_whitespace()
+            .then(|| WhitespaceMarks::from_colors(&app.config.colors));
+        let bodies = app.code_state.function_bodies().to_vec();
+        let errors = ErrorMarks::of(&app.code_state, app.config.colors.text_incorrect);
+        let zoom = app.view.zoom.unwrap_or(1.0);
+        let scroll_offset = app.animated_scroll_offset();
+        let fades = app.animations.fading(Instant::now());
+        let colored = app.code_state.get_full_code_colored();
+        let rows = CodeRows::new(
+            colored,
+            cursor_position,
+            scroll_offset,
+            first_line,
+            show_line_numbers,
+            true,
+            app.config.colors.text_default,
+        )
+        .with_folds(&bodies, fold_before)
+        .with_whitespace(whitespace)
+        .with_errors(errors)
+        .with_fades(fades);
+        let continuation = rows.continuation_gutter();
 
-            let mut num_line = ColoredLine::new();
-            let line_num_str = format!("{:>width$}", num + 1, width = num_digits);
-            let num_color = if is_current {
-                current_line_color
+        if app.fit_font_pending {
+            app.fit_font_pending = false;
+            // The continuation gutter is as wide as the line-number one
+            let gutter_width = if show_line_numbers {
+                continuation
+                    .chars
+                    .iter()
+                    .map(|c| surface.measure(c.ch))
+                    .sum()
             } else {
-                line_number_color
+                0.0
             };
+            app.fitted_zoom = Some(layout::fitting_zoom(
+                &colored.lines,
+                gutter_width,
+                surface.remaining_width(),
+                zoom,
+                |ch| surface.measure(ch),
+            ));
+        }
 
-            for ch in line_num_str.chars() {
-                num_line.push(ch, num_color);
+        // The row limit counts rows on screen, so wrapped lines use it up faster
+        let mut rows_left = row_limit;
+        // Lines shown whole, for follow scrolling
+        let mut lines_shown = 0;
+        for (gutter, code_line) in rows {
+            let laid_out = code_line_rows(surface, gutter, &code_line, &continuation, wrap_lines);
+            let row_count = laid_out.len();
+            for (index, row) in laid_out.into_iter().enumerate() {
+                surface.write_line(&row);
+                let full = matches!(surface.write_break(), WriteResult::Overflow { writed: _ });
+                rows_left -= 1;
+                if index + 1 == row_count {
+                    lines_shown += 1;
+                }
+                if full || rows_left == 0 {
+                    app.code_view_lines = lines_shown;
+                    return;
+                }
             }
-            num_line.push(' ', separator_color);
-            num_line.push('│', separator_color);
-            num_line.push(' ', separator_color);
+        }
+        // The text ended above the bottom; lines after it would have had the rest
+        app.code_view_lines = lines_shown + surface.rows_left().min(rows_left);
+    }
+}
+
+/// Both files of a split practice side by side, the left file in the left column.
+/// Only the active pane has a caret; long lines wrap within their pane, and the
+/// other pane's next line waits until the taller of the two is done.
+pub struct SplitCodeBlock;
+
+impl UiBlock for SplitCodeBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let show_line_numbers = app.show_line_numbers();
+        let wrap_lines = app.wrap_lines();
+        let newline_color = app.config.colors.text_default;
+        let row_limit = code_row_limit(app);
+        let fold_typed = app.fold_typed();
+        let whitespace = app
+            .show_whitespace()
+            .then(|| WhitespaceMarks::from_colors(&app.config.colors));
+        let scroll_offset = app.animated_scroll_offset();
+        let fades = app.animations.fading(Instant::now());
+        let Some(split) = app.split.as_mut() else {
+            return;
+        };
+        let active_bodies = app.code_state.function_bodies().to_vec();
+        let other_bodies = split.other.code_state.function_bodies().to_vec();
+        let active_fold = fold_typed.then(|| fold_line(&app.code_state));
+        let other_fold = fold_typed.then(|| fold_line(&split.other.code_state));
 
-            surface.write_line(&num_line);
-            surface.write_line_wordwrap(code_line);
+        let active_errors = ErrorMarks::of(&app.code_state, app.config.colors.text_incorrect);
+        let active_cursor = app.code_state.get_window_cursor_position();
+        let active_first_line = app.code_state.first_line_number();
+        let other_cursor = split.other.code_state.get_window_cursor_position();
+        let other_first_line = split.other.code_state.first_line_number();
 
-            if matches!(surface.write_break(), WriteResult::Overflow { writed: _ }) {
+        let mut titles = [
+            (app.current_file_path.as_str(), true),
+            (split.other.file_path.as_str(), false),
+        ];
+        let active = CodeRows::new(
+            app.code_state.get_full_code_colored(),
+            active_cursor,
+            scroll_offset,
+            active_first_line,
+            show_line_numbers,
+            true,
+            newline_color,
+        )
+        .with_folds(&active_bodies, active_fold)
+        .with_whitespace(whitespace)
+        .with_errors(active_errors)
+        .with_fades(fades);
+        let other = CodeRows::new(
+            split.other.code_state.get_full_code_colored(),
+            other_cursor,
+            split.other.scroll_offset,
+            other_first_line,
+            show_line_numbers,
+            false,
+            newline_color,
+        )
+        .with_folds(&other_bodies, other_fold)
+        .with_whitespace(whitespace);
+        let mut panes = [active, other];
+        if split.right_active {
+            titles.swap(0, 1);
+            panes.swap(0, 1);
+        }
+
+        for (index, (path, is_active)) in titles.into_iter().enumerate() {
+            surface.start_column(index, 2);
+            let mut title = ColoredLine::new();
+            if is_active {
+                title.push_str(&format!("▶ {}", path), [1.0, 0.85, 0.2, 1.0]);
+            } else {
+                title.push_str(&format!("  {}", path), [0.5, 0.5, 0.6, 1.0]);
+            }
+            surface.write_line(&title);
+        }
+        surface.write_break();
+
+        let continuations = panes.each_ref().map(CodeRows::continuation_gutter);
+        let [mut left, mut right] = panes;
+        let mut rows_left = row_limit;
+        // Pairs of lines shown whole; the active pane scrolls by them
+        let mut lines_shown = 0;
+        loop {
+            let lines = [left.next(), right.next()];
+            if lines.iter().all(Option::is_none) {
+                app.code_view_lines = lines_shown + surface.rows_left().min(rows_left);
                 return;
             }
-            cursor_position -= (line_len + break_len) as i32;
+            let mut laid_out: [Vec<ColoredLine>; 2] = Default::default();
+            for (index, line) in lines.into_iter().enumerate() {
+                surface.start_column(index, 2);
+                if let Some((gutter, code_line)) = line {
+                    laid_out[index] = code_line_rows(
+                        surface,
+                        gutter,
+                        &code_line,
+                        &continuations[index],
+                        wrap_lines,
+                    );
+                }
+            }
+
+            let height = laid_out.iter().map(Vec::len).max().unwrap_or(0);
+            for row in 0..height {
+                for (index, rows) in laid_out.iter().enumerate() {
+                    surface.start_column(index, 2);
+                    if let Some(row) = rows.get(row) {
+                        surface.write_line(row);
+                    }
+                }
+                let full = matches!(surface.write_break(), WriteResult::Overflow { writed: _ });
+                rows_left -= 1;
+                if row + 1 == height {
+                    lines_shown += 1;
+                }
+                if full || rows_left == 0 {
+                    app.code_view_lines = lines_shown;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Most lines the preview pane shows
+pub const MAX_PREVIEW_LINES: usize = 2;
+
+/// Tint over the next character in the preview pane
+const PREVIEW_NEXT_BACKGROUND: [f32; 4] = [1.0, 0.85, 0.2, 0.35];
+
+/// The rest of the line under the caret and the lines after it, `count` lines in all.
+/// `cursor` is a byte offset into `text`, as in `CodeDisplayBlock`.
+fn upcoming_lines(text: &ColoredText, mut cursor: usize, count: usize) -> Vec<ColoredLine> {
+    let mut lines = text.lines.iter();
+    let mut upcoming = Vec::new();
+    for line in lines.by_ref() {
+        let line_len = line.chars.iter().map(|ch| ch.ch.len_utf8()).sum::<usize>();
+        if cursor > line_len {
+            cursor -= line_len + '\n'.len_utf8();
+            continue;
+        }
+
+        let mut offset = 0;
+        let rest: Vec<ColoredChar> = line
+            .chars
+            .iter()
+            .skip_while(|ch| {
+                offset += ch.ch.len_utf8();
+                offset <= cursor
+            })
+            .cloned()
+            .collect();
+        upcoming.push(ColoredLine { chars: rest });
+        break;
+    }
+    upcoming.extend(lines.take(count.saturating_sub(1)).cloned());
+    upcoming.truncate(count);
+    upcoming
+}
+
+/// Teleprompter-style pane at the bottom of the window with the next lines magnified.
+/// It follows the caret on its own, whatever the code view above is scrolled to.
+pub struct PreviewPaneBlock;
+
+impl PreviewPaneBlock {
+    fn line_count(app: &CargoTapApp) -> usize {
+        app.config.text.preview_lines.min(MAX_PREVIEW_LINES)
+    }
+
+    /// Keeps the bottom of the window free for the pane; call before the other blocks
+    pub fn reserve(app: &CargoTapApp, surface: &mut dyn TextSurface) {
+        let lines = Self::line_count(app);
+        if lines > 0 {
+            surface.reserve_bottom(lines, app.config.text.preview_scale);
+        }
+    }
+}
+
+impl UiBlock for PreviewPaneBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let count = Self::line_count(app);
+        if count == 0 {
+            return;
+        }
+
+        surface.start_bottom_area();
+        let mut rule = ColoredLine::new();
+        rule.push_str("── next ", [0.4, 0.4, 0.5, 1.0]);
+        rule.push_str(&"─".repeat(40), [0.4, 0.4, 0.5, 1.0]);
+        surface.write_line(&rule);
+        surface.write_break();
+
+        let cursor = app.code_state.get_window_cursor_position();
+        let mut lines = upcoming_lines(app.code_state.get_full_code_colored(), cursor, count);
+        if let Some(first) = lines.first_mut() {
+            if first.chars.is_empty() {
+                first.push('↩', app.config.colors.text_default);
+            }
+            first.chars[0].background_color = Some(PREVIEW_NEXT_BACKGROUND);
+        }
+
+        surface.set_scale(app.config.text.preview_scale);
+        for line in &lines {
+            // Long lines are cut off; the pane starts at the caret, so what's next always fits
+            surface.write_line(line);
+            surface.write_break();
         }
+        surface.set_scale(1.0);
     }
 }
 
@@ -264,6 +1611,198 @@ impl UiBlock for RainbowEffectsBlock {
     }
 }
 
+/// Bigrams, keys and words with fewer timed samples than this stay off the
+/// key speed lists, so one stumble doesn't top them
+const MIN_SEQUENCE_SAMPLES: usize = 3;
+
+/// Draws `layout` as blocks of text cells, each key on the background `color`
+/// gives for it (row index, key index)
+pub fn write_keyboard(
+    surface: &mut dyn TextSurface,
+    layout: &[KeyRow],
+    color: impl Fn(usize, usize) -> [f32; 4],
+) {
+    let label_color = [1.0, 1.0, 1.0, 1.0];
+    for (row_index, row) in layout.iter().enumerate() {
+        // Two lines per row so keys read as blocks rather than highlighted text
+        let mut label_line = ColoredLine::new();
+        let mut base_line = ColoredLine::new();
+        label_line.push_str(&" ".repeat(row.indent), label_color);
+        base_line.push_str(&" ".repeat(row.indent), label_color);
+
+        for (key_index, key) in row.keys.iter().enumerate() {
+            let background = color(row_index, key_index);
+            let label = format!("{:^width$}", key.label, width = key.width);
+            for ch in label.chars() {
+                label_line.push_with_background(ch, label_color, background);
+                base_line.push_with_background(' ', label_color, background);
+            }
+            label_line.push(' ', label_color);
+            base_line.push(' ', label_color);
+        }
+
+        surface.write_line(&label_line);
+        surface.write_break();
+        surface.write_line(&base_line);
+        surface.write_break();
+    }
+}
+
+/// Key speed view of the statistics screen: the keyboard colored by how long each
+/// key takes after the one before it, and the slowest and most missed bigrams and
+/// words in `app.stats_range`
+pub struct KeySpeedBlock;
+
+impl KeySpeedBlock {
+    fn write_list(surface: &mut dyn TextSurface, title: &str, rows: &[String], color: [f32; 4]) {
+        if rows.is_empty() {
+            return;
+        }
+        let mut line = ColoredLine::new();
+        line.push_str(title, [1.0, 0.5, 0.0, 1.0]);
+        surface.write_line(&line);
+        surface.write_break();
+        for row in rows {
+            let mut line = ColoredLine::new();
+            line.push_str(&format!("  {}", row), color);
+            surface.write_line(&line);
+            surface.write_break();
+        }
+        surface.write_break();
+    }
+
+    fn bigram_name(bigram: &str) -> String {
+        bigram
+            .chars()
+            .map(keyboard::key_name)
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+
+    /// The `count` entries of `stats` with enough samples that `key` ranks highest
+    fn top<'a, K>(
+        stats: impl Iterator<Item = (K, &'a SequenceStat)>,
+        key: impl Fn(&SequenceStat) -> f64,
+        count: usize,
+    ) -> Vec<(K, &'a SequenceStat)> {
+        let mut ranked: Vec<_> = stats
+            .filter(|(_, stat)| stat.count >= MIN_SEQUENCE_SAMPLES && key(stat) > 0.0)
+            .collect();
+        ranked.sort_by(|a, b| key(b.1).total_cmp(&key(a.1)));
+        ranked.truncate(count);
+        ranked
+    }
+}
+
+impl UiBlock for KeySpeedBlock {
+    fn render(&self, app: &mut CargoTapApp, surface: &mut dyn TextSurface) {
+        let text_color = app.config.colors.text_default;
+        let bigrams = app.session_history.get_bigram_stats(app.stats_range);
+        let words = app.session_history.get_word_stats(app.stats_range);
+        let latencies = session_state::key_latencies(&bigrams);
+
+        let layout = keyboard::us_layout();
+        let totals = keyboard::key_totals(&layout, &latencies, SequenceStat::add);
+        let slowest = totals
+            .iter()
+            .flatten()
+            .filter(|stat| stat.count >= MIN_SEQUENCE_SAMPLES)
+            .map(SequenceStat::mean_secs)
+            .fold(0.0, f64::max);
+        write_keyboard(surface, &layout, |row, key| {
+            let stat = totals[row][key];
+            if stat.count < MIN_SEQUENCE_SAMPLES {
+                keyboard::latency_color(SequenceStat::default(), slowest)
+            } else {
+                keyboard::latency_color(stat, slowest)
+            }
+        });
+
+        let mut legend = ColoredLine::new();
+        legend.push_str(
+            "Greener: reached quickly | Redder: slow after the key before it",
+            [0.7, 0.7, 0.7, 1.0],
+        );
+        surface.write_break();
+        surface.write_line(&legend);
+        surface.write_break();
+        surface.write_break();
+
+        if bigrams.is_empty() {
+            let mut line = ColoredLine::new();
+            line.push_str("No timed keys in this range yet.", text_color);
+            surface.write_line(&line);
+            surface.write_break();
+            surface.write_break();
+            return;
+        }
+
+        let ms = |stat: &SequenceStat| format!("{:>4.0} ms", stat.mean_secs() * 1000.0);
+        let slowest_keys: Vec<String> = Self::top(latencies.iter(), SequenceStat::mean_secs, 5)
+            .into_iter()
+            .map(|(ch, stat)| {
+                format!(
+                    "{:<7} {} ({} times)",
+                    keyboard::key_name(*ch),
+                    ms(stat),
+                    stat.count
+                )
+            })
+            .collect();
+        Self::write_list(surface, "SLOWEST KEYS", &slowest_keys, text_color);
+
+        let slowest_bigrams: Vec<String> = Self::top(bigrams.iter(), SequenceStat::mean_secs, 5)
+            .into_iter()
+            .map(|(bigram, stat)| {
+                format!(
+                    "{:<17} {} ({} times)",
+                    Self::bigram_name(bigram),
+                    ms(stat),
+                    stat.count
+                )
+            })
+            .collect();
+        Self::write_list(surface, "SLOWEST BIGRAMS", &slowest_bigrams, text_color);
+
+        let missed_bigrams: Vec<String> = Self::top(bigrams.iter(), SequenceStat::error_rate, 5)
+            .into_iter()
+            .map(|(bigram, stat)| {
+                format!(
+                    "{:<17} {:.1}% missed ({} of {})",
+                    Self::bigram_name(bigram),
+                    stat.error_rate() * 100.0,
+                    stat.misses,
+                    stat.count + stat.misses
+                )
+            })
+            .collect();
+        Self::write_list(surface, "MOST MISSED BIGRAMS", &missed_bigrams, text_color);
+
+        // Words are ranked per character, or long words would always top the list
+        let per_char =
+            |word: &str, stat: &SequenceStat| stat.mean_secs() / word.chars().count().max(1) as f64;
+        let mut slowest_words: Vec<_> = words
+            .iter()
+            .filter(|(_, stat)| stat.count >= MIN_SEQUENCE_SAMPLES)
+            .collect();
+        slowest_words.sort_by(|a, b| per_char(b.0, b.1).total_cmp(&per_char(a.0, a.1)));
+        let slowest_words: Vec<String> = slowest_words
+            .into_iter()
+            .take(5)
+            .map(|(word, stat)| {
+                format!(
+                    "{:<16} {:>4.0} ms/char, {} missed ({} times)",
+                    word,
+                    per_char(word, stat) * 1000.0,
+                    stat.misses,
+                    stat.count
+                )
+            })
+            .collect();
+        Self::write_list(surface, "SLOWEST WORDS", &slowest_words, text_color);
+    }
+}
+
 pub struct FooterBlock;
 
 impl UiBlock for FooterBlock {
@@ -275,7 +1814,142 @@ impl UiBlock for FooterBlock {
             "Press Cmd+P to change file | Press Ctrl+T / Cmd+T to view statistics",
             [0.5, 0.5, 0.5, 1.0],
         );
-        surface.write_line(&line);
+        surface.write_line_wordwrap(&line);
         surface.write_break();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(line: &ColoredLine) -> String {
+        line.chars.iter().map(|ch| ch.ch).collect()
+    }
+
+    #[test]
+    fn test_speed_chart_overlays_curves() {
+        let curves = SpeedCurves {
+            average: vec![20.0, 30.0, 30.0],
+            best: vec![40.0],
+            best_wpm: 40.0,
+            sessions: 3,
+        };
+        let current = [20.0, 40.0];
+        let lines: Vec<String> = SpeedChart {
+            current: &current,
+            curves: &curves,
+        }
+        .lines()
+        .iter()
+        .map(plain)
+        .collect();
+
+        assert!(lines[0].contains("average of 3 earlier"));
+        assert!(lines[0].contains("best (40 WPM)"));
+        // The current session is drawn over the average where they meet; the one-minute
+        // best curve is a single point, and the average stops at the session's end
+        assert_eq!(
+            &lines[1..=SpeedChart::ROWS],
+            [
+                "   40│○     ●",
+                "     │    ●● ",
+                "     │   ● ··",
+                "     │ ●●··  ",
+                "   20│●·     ",
+            ]
+        );
+        assert_eq!(lines[SpeedChart::ROWS + 1], "      1     2 min");
+    }
+
+    #[test]
+    fn test_upcoming_lines_start_at_the_caret() {
+        let text = ColoredText::from_str_with_color("let a = 1;\nlet é = 2;\nend", [1.0; 4]);
+
+        let lines = upcoming_lines(&text, 4, 2);
+        assert_eq!(
+            lines.iter().map(plain).collect::<Vec<_>>(),
+            ["a = 1;", "let é = 2;"]
+        );
+
+        // Past the multi-byte 'é' on the second line
+        let second_line = "let a = 1;\n".len();
+        let lines = upcoming_lines(&text, second_line + "let é".len(), 2);
+        assert_eq!(
+            lines.iter().map(plain).collect::<Vec<_>>(),
+            [" = 2;", "end"]
+        );
+
+        // At a line end only the line break is left
+        let lines = upcoming_lines(&text, "let a = 1;".len(), 1);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].chars.is_empty());
+
+        let lines = upcoming_lines(&text, "let a = 1;\nlet é = 2;\ne".len(), 2);
+        assert_eq!(lines.iter().map(plain).collect::<Vec<_>>(), ["nd"]);
+    }
+
+    #[test]
+    fn test_typed_function_bodies_fold() {
+        let code = "fn a() {\n    one();\n}\nfn b() {\n    two();\n}\n";
+        let text = ColoredText::from_str_with_color(code, [1.0; 4]);
+        let lines: Vec<&str> = code.lines().collect();
+        let bodies = crate::folding::function_bodies(&lines, &crate::language::RUST);
+        // The caret on the second line of b()
+        let cursor = "fn a() {\n    one();\n}\nfn b() {\n".len();
+
+        let rows: Vec<(String, String)> = CodeRows::new(&text, cursor, 0, 0, true, true, [1.0; 4])
+            .with_folds(&bodies, Some(4))
+            .map(|(gutter, line)| (gutter.as_ref().map(plain).unwrap_or_default(), plain(&line)))
+            .collect();
+        assert_eq!(
+            rows[0],
+            (
+                "  1▸│ ".to_string(),
+                "fn a() {  ⋯ 3 lines typed".to_string()
+            )
+        );
+        assert_eq!(rows[1].0, "  4▾│ ");
+        assert_eq!(rows[2].0, "  5 │ ");
+        // The caret is still found after the folded lines
+        assert!(rows[2].1.starts_with("    two"));
+
+        let unfolded = CodeRows::new(&text, cursor, 0, 0, true, true, [1.0; 4])
+            .with_folds(&bodies, None)
+            .count();
+        assert_eq!(unfolded, 7);
+    }
+
+    #[test]
+    fn test_whitespace_marks() {
+        let white = [1.0; 4];
+        let marks = WhitespaceMarks {
+            space: [0.5; 4],
+            newline: [0.25; 4],
+        };
+        let text = ColoredText::from_str_with_color("a b\n\tc d\nend", white);
+        // The caret between "c" and " d"
+        let cursor = "a b\n\tc".len();
+        let rows: Vec<ColoredLine> = CodeRows::new(&text, cursor, 0, 0, false, true, white)
+            .with_whitespace(Some(marks))
+            .map(|(_, line)| line.into_owned())
+            .collect();
+        let texts: Vec<String> = rows.iter().map(plain).collect();
+        assert_eq!(texts, ["a·b¶", "→c·d¶", "end"]);
+
+        // Typed whitespace keeps its color, the rest gets the marks' colors
+        assert_eq!(rows[0].chars[1].color, white);
+        assert_eq!(rows[1].chars[0].color, white);
+        assert_eq!(rows[1].chars[2].color, marks.space);
+        assert!(rows[1].chars[2].caret);
+        assert_eq!(rows[1].chars.iter().filter(|c| c.caret).count(), 1);
+        assert_eq!(rows[0].chars[3].color, marks.newline);
+
+        // With the caret on the line break, its mark stands in for the ¶
+        let rows: Vec<String> = CodeRows::new(&text, "a b".len(), 0, 0, false, true, white)
+            .with_whitespace(Some(marks))
+            .map(|(_, line)| plain(&line))
+            .collect();
+        assert_eq!(rows[0], "a·b↩");
+    }
+}
@@ -0,0 +1,121 @@
+//! Webhook notifications for completed sessions
+//!
+//! Completed session statistics are POSTed as JSON to a user-configured URL.
+//! The webhook subscribes to the app's event bus and listens on its own thread.
+//! Delivery happens on a background thread with exponential backoff so a slow
+//! or unreachable endpoint never blocks the render loop.
+
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::Duration;
+
+use crate::events::AppEvent;
+use crate::session_state::SessionStats;
+
+/// Delay before the first retry; doubled for every following attempt
+const INITIAL_BACKOFF_MS: u64 = 500;
+
+/// Builds the JSON body sent to the webhook
+///
+/// `content` and `text` carry a one-line summary so the payload can be fed
+/// straight into Discord or Slack incoming webhooks; `stats` has the raw data.
+pub fn build_payload(stats: &SessionStats) -> serde_json::Value {
+    let summary = format!(
+        "CargoTap session finished: {:.0} WPM, {:.1}% accuracy, {} chars in {:.0}s ({})",
+        stats.words_per_minute,
+        stats.accuracy,
+        stats.chars_typed,
+        stats.time_elapsed_secs,
+        stats.file_path
+    );
+
+    serde_json::json!({
+        "event": "session_completed",
+        "content": summary,
+        "text": summary,
+        "stats": stats,
+    })
+}
+
+/// Returns how long to wait before retry number `attempt` (starting at 1)
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(10);
+    Duration::from_millis(INITIAL_BACKOFF_MS * factor)
+}
+
+/// Posts every finished session received from the event bus to `url`
+pub fn subscribe(events: Receiver<AppEvent>, url: String, max_retries: u32) {
+    thread::spawn(move || {
+        for event in events {
+            if let AppEvent::SessionFinished(stats) = event {
+                send_session_stats(&url, &stats, max_retries);
+            }
+        }
+    });
+}
+
+/// Sends the session statistics to `url` in the background
+fn send_session_stats(url: &str, stats: &SessionStats, max_retries: u32) {
+    let url = url.to_string();
+    let body = build_payload(stats).to_string();
+
+    thread::spawn(move || {
+        let mut attempt = 0;
+        loop {
+            let result = ureq::post(&url)
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+
+            let retryable = match result {
+                Ok(_) => {
+                    log::info!("📨 Session posted to webhook");
+                    return;
+                }
+                Err(ureq::Error::Status(code, _)) => {
+                    log::warn!("Webhook responded with HTTP {}", code);
+                    code == 429 || code >= 500
+                }
+                Err(e) => {
+                    log::warn!("Webhook request failed: {}", e);
+                    true
+                }
+            };
+
+            attempt += 1;
+            if !retryable || attempt > max_retries {
+                log::error!("Giving up on webhook delivery after {} attempt(s)", attempt);
+                return;
+            }
+
+            thread::sleep(backoff_delay(attempt));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles() {
+        assert_eq!(backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(3), Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        assert_eq!(backoff_delay(50), backoff_delay(11));
+    }
+
+    #[test]
+    fn test_payload_contains_stats() {
+        let stats = SessionStats::new(300, 60.0, 0, 300, 3, "main.rs".to_string());
+        let payload = build_payload(&stats);
+
+        assert_eq!(payload["event"], "session_completed");
+        assert_eq!(payload["stats"]["chars_typed"], 300);
+        assert_eq!(payload["stats"]["file_path"], "main.rs");
+        assert!(payload["content"].as_str().unwrap().contains("60 WPM"));
+    }
+}